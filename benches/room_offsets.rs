@@ -0,0 +1,42 @@
+//! Benchmarks filled `room_offsets` against the original `Vec::contains` deduplication.
+//!
+//! Run with `cargo bench --bench room_offsets`. Shape "x" has four base offsets spread
+//! over a 5x5 area, so large sizes produce heavy overlap between the expanded squares.
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use chia_dungeon::utils::shapes::{room_offsets, ShapeStyle};
+
+// The pre-rewrite implementation: a linear `contains` scan per generated tile
+fn room_offsets_naive(size: u32, base_offsets: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let size = size as i32;
+    let mut offsets = Vec::new();
+    for &(base_x, base_y) in base_offsets {
+        for x in (base_x - (size - 1))..=(base_x + (size - 1)) {
+            for y in (base_y - (size - 1))..=(base_y + (size - 1)) {
+                if !offsets.contains(&(x, y)) {
+                    offsets.push((x, y));
+                }
+            }
+        }
+    }
+    offsets
+}
+
+fn bench_room_offsets(c: &mut Criterion) {
+    // Base offsets of shape "x"
+    let base_offsets = [(-2, 2), (2, -2), (-2, -2), (2, 2)];
+    let mut group = c.benchmark_group("room_offsets");
+    for size in [5u32, 10, 20, 40] {
+        group.bench_with_input(BenchmarkId::new("naive", size), &size, |b, &size| {
+            b.iter(|| room_offsets_naive(black_box(size), &base_offsets))
+        });
+        group.bench_with_input(BenchmarkId::new("room_offsets", size), &size, |b, &size| {
+            b.iter(|| room_offsets(black_box(size), "x", ShapeStyle::Filled))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_room_offsets);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Writes the C header for the `ffi` bindings to `$OUT_DIR/chia_dungeon.h` when the
+//! `ffi` feature is enabled (see `utils::ffi`), leaving the source tree untouched.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    write_ffi_header();
+}
+
+#[cfg(feature = "ffi")]
+fn write_ffi_header() {
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=utils/ffi.rs");
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("Cargo sets CARGO_MANIFEST_DIR");
+    let out_dir = std::env::var("OUT_DIR").expect("Cargo sets OUT_DIR");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).expect("Unable to read cbindgen.toml");
+    // Only the bindings module, so the header leaves out the constants of the rest of the crate
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/utils/ffi.rs", crate_dir))
+        .generate()
+        .expect("Unable to generate the C header")
+        .write_to_file(format!("{}/chia_dungeon.h", out_dir));
+}
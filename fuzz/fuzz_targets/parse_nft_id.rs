@@ -0,0 +1,13 @@
+//! Fuzzes NFT ID parsing: any input either decodes or returns a `DungeonError`, never
+//! panicking. Run with `cargo fuzz run parse_nft_id` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use chia_dungeon::parse_nft_id;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(nft_id) = std::str::from_utf8(data) {
+        let _ = parse_nft_id(nft_id);
+    }
+});
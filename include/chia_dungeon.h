@@ -0,0 +1,150 @@
+/* C bindings for chia_dungeon (see utils/ffi.rs). Generated by cbindgen; do not edit. */
+
+#ifndef CHIA_DUNGEON_H
+#define CHIA_DUNGEON_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Width of the images written by `chia_dungeon_render_rgba`, in pixels
+#define CHIA_DUNGEON_IMAGE_WIDTH 640
+
+// Height of the images written by `chia_dungeon_render_rgba`, in pixels
+#define CHIA_DUNGEON_IMAGE_HEIGHT 480
+
+// Kind of a grid cell written by `chia_dungeon_render_tiles` (see `Tile`).
+enum ChiaDungeonTile
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  CHIA_DUNGEON_TILE_WALL = 0,
+  CHIA_DUNGEON_TILE_FLOOR = 1,
+  CHIA_DUNGEON_TILE_TUNNEL = 2,
+  CHIA_DUNGEON_TILE_DOOR = 3,
+  CHIA_DUNGEON_TILE_ROOM_CENTER = 4,
+  CHIA_DUNGEON_TILE_STAIRS_UP = 5,
+  CHIA_DUNGEON_TILE_STAIRS_DOWN = 6,
+  CHIA_DUNGEON_TILE_WATER = 7,
+  CHIA_DUNGEON_TILE_LAVA = 8,
+  CHIA_DUNGEON_TILE_SAND = 9,
+  CHIA_DUNGEON_TILE_SHALLOW_WATER = 10,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum ChiaDungeonTile ChiaDungeonTile;
+#else
+typedef uint8_t ChiaDungeonTile;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+// A parsed dungeon, opaque to C.
+typedef struct ChiaDungeon ChiaDungeon;
+
+// Placement and size of a floor's tile grid.
+typedef struct ChiaDungeonGrid {
+  // X coordinate of the first column.
+  int32_t min_x;
+  // Y coordinate of the first row.
+  int32_t min_y;
+  // Number of columns.
+  uint32_t width;
+  // Number of rows.
+  uint32_t height;
+} ChiaDungeonGrid;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Decode a NUL-terminated NFT ID into a dungeon; `NULL` on failure
+//
+// # Safety
+//
+// `nft_id` must be `NULL` or point to a NUL-terminated string.
+struct ChiaDungeon *chia_dungeon_parse(const char *nft_id);
+
+// Generate the synthetic NFT ID for `seed` and decode its dungeon; `NULL` on failure
+struct ChiaDungeon *chia_dungeon_generate(uint64_t seed);
+
+// Release a dungeon returned by `chia_dungeon_parse` or `chia_dungeon_generate`
+//
+// # Safety
+//
+// `dungeon` must be `NULL` or a handle not yet released.
+void chia_dungeon_free(struct ChiaDungeon *dungeon);
+
+// Number of floors of the dungeon, including the top floor; 0 for `NULL`
+//
+// # Safety
+//
+// `dungeon` must be `NULL` or a live handle.
+uint32_t chia_dungeon_floor_count(const struct ChiaDungeon *dungeon);
+
+// The dungeon as JSON (see `Dungeon::to_json`); `NULL` on failure
+//
+// # Safety
+//
+// `dungeon` must be `NULL` or a live handle.
+char *chia_dungeon_to_json(const struct ChiaDungeon *dungeon);
+
+// Render a floor as ASCII text (see `ascii`); `NULL` on failure
+//
+// # Safety
+//
+// `dungeon` must be `NULL` or a live handle.
+char *chia_dungeon_render_ascii(const struct ChiaDungeon *dungeon, uint32_t floor);
+
+// Write the placement and size of a floor's grid to `grid`; 0 on success, -1 on failure
+//
+// # Safety
+//
+// `dungeon` must be `NULL` or a live handle, and `grid` `NULL` or writable.
+int32_t chia_dungeon_grid(const struct ChiaDungeon *dungeon,
+                          uint32_t floor,
+                          struct ChiaDungeonGrid *grid);
+
+// Write one `ChiaDungeonTile` per cell of a floor's grid to `buffer`, row by row from the
+// lowest y; 0 on success, -1 on failure or if `len` is below the cell count
+//
+// # Safety
+//
+// `dungeon` must be `NULL` or a live handle, and `buffer` `NULL` or writable for `len`
+// bytes.
+int32_t chia_dungeon_render_tiles(const struct ChiaDungeon *dungeon,
+                                  uint32_t floor,
+                                  uint8_t *buffer,
+                                  size_t len);
+
+// Render a floor as RGBA pixels, row by row from the top, in the theme of its dungeon
+// type; 0 on success, -1 on failure or if `len` is below
+// `CHIA_DUNGEON_IMAGE_WIDTH * CHIA_DUNGEON_IMAGE_HEIGHT * 4`
+//
+// # Safety
+//
+// `dungeon` must be `NULL` or a live handle, and `buffer` `NULL` or writable for `len`
+// bytes.
+int32_t chia_dungeon_render_rgba(const struct ChiaDungeon *dungeon,
+                                 uint32_t floor,
+                                 uint8_t *buffer,
+                                 size_t len);
+
+// Message of the last failure on the calling thread, empty if none; valid until the
+// next failure on the same thread
+const char *chia_dungeon_last_error(void);
+
+// Release a string returned by `chia_dungeon_to_json` or `chia_dungeon_render_ascii`
+//
+// # Safety
+//
+// `string` must be `NULL` or a string returned by this library and not yet released.
+void chia_dungeon_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHIA_DUNGEON_H */
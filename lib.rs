@@ -0,0 +1,149 @@
+//! # Chia Dungeon
+//!
+//! Generates 2D dungeons from Chia NFT IDs.
+//!
+//! The crate decodes an `nft_id` into rooms, tunnels, and derived attributes
+//! (type, level, area), then excavates and plots the resulting map. The
+//! `chia_dungeon` binary is a thin consumer of this API.
+//!
+//! ## Modules:
+//! - `utils::excavator`: NFT decoding and dungeon excavation.
+//! - `utils::analysis`: Character statistics and decoded traits of NFT IDs (`analyze_nft`).
+//! - `utils::animation`: Animated GIFs of the dungeon being dug, for NFT reveals.
+//! - `utils::arena`: Player-versus-player arenas of two dungeons mirrored across a center line (`Arena`).
+//! - `utils::balance`: Per-level spawn curves for monsters, gold, and traps, loaded from TOML.
+//! - `utils::batch`: Parallel generation of many dungeons with a summary CSV.
+//! - `utils::biome`: Dungeon types with sub-biome modifiers from the top characters of an NFT ID (`DungeonType`).
+//! - `utils::boss`: A unique boss per dungeon with an element, stats, and special ability (`Dungeon::boss`).
+//! - `utils::cache`: In-memory LRU and on-disk cache of excavated dungeons (`DungeonCache`).
+//! - `utils::campaign`: Chaining the dungeons of several NFT IDs into a campaign with a manifest and overview map.
+//! - `utils::chia`: Bech32m decoding of real Chia NFT IDs.
+//! - `utils::combat`: Turn-based combat between the player and monsters in explore mode.
+//! - `utils::config`: `GenerationConfig` for tuning generation parameters.
+//! - `utils::danger`: Danger of each tile from its depth, nearby monsters, and traps, for route-planning heatmaps.
+//! - `utils::decorate`: Rubble heaped against rooms and strewn along tunnels, and decoration objects on room floors, by dungeon type.
+//! - `utils::dungeon`: The `Dungeon`, `Room`, and `Bounds` types.
+//! - `utils::elevation`: Height maps with cliffs and ramps, shading plotted maps.
+//! - `utils::epoch`: Living dungeons whose monsters and decorations move with the Chia block height (`parse_nft_id_at_epoch`).
+//! - `utils::error`: The `DungeonError` enum returned by parsing.
+//! - `utils::grid`: The `Grid<Tile>` tile map populated during excavation.
+//! - `utils::hooks`: Observer hooks on generation steps (`parse_nft_id_with_observer`).
+//! - `utils::i18n`: Catalogs translating dungeon type names, room kind labels, and lore (`Catalog`).
+//! - `utils::gallery`: Contact sheets previewing every room shape character at a few sizes.
+//! - `utils::gdext`: The `ChiaDungeon` Godot class returning dungeons as dictionaries and tile map arrays (`godot` feature).
+//! - `utils::generator`: Synthetic NFT ID generation, optionally meeting constraints.
+//! - `utils::godot`: Dungeon maps as Godot `TileMapLayer` cells on the sprite atlas.
+//! - `utils::plot`: Rendering the excavated map to an image.
+//! - `utils::ascii`: Rendering the excavated map as ASCII text or Unicode box-drawing art.
+//! - `utils::audio`: Ambience, music, and sound effect cues per dungeon type and room kind, from an overridable table (`AudioManifest`).
+//! - `utils::explore`: Interactive terminal exploration.
+//! - `utils::export`: Exporting dungeons to external formats (Tiled maps, RexPaint images, room graphs, NFT metadata).
+//! - `utils::ffi`: C bindings for native game engine plugins, with a cbindgen header (`ffi` feature).
+//! - `utils::fingerprint`: Stable layout fingerprints for spotting duplicate dungeons (`Dungeon::fingerprint`).
+//! - `utils::floors`: Multi-floor dungeons linked by stairs.
+//! - `utils::fog`: Fog of war and line of sight for exploration.
+//! - `utils::fusion`: Fusing two dungeons into one (`Dungeon::merge`).
+//! - `utils::inventory`: Loot, locked rooms, and the player inventory for exploration.
+//! - `utils::isometric`: Isometric pseudo-3D rendering (`RenderFormat::Isometric`).
+//! - `utils::liquid`: Water pooling in the low ground of wet dungeons as shallow and deep water.
+//! - `utils::lore`: Deterministic flavor text describing a dungeon, for NFT descriptions and the CLI.
+//! - `utils::maze`: Rooms embedded in a perfect maze carved from the NFT ID (`GenerationAlgorithm::Maze`).
+//! - `utils::naming`: Deterministic flavor names for rooms (`Room::name`), optionally labeled on plotted maps.
+//! - `utils::noise`: Open cave layouts dug from a noise field tuned by the NFT ID (`GenerationAlgorithm::Noise`).
+//! - `utils::output`: Structured JSON, YAML, or TOML output of parsed dungeons (`OutputFormat`).
+//! - `utils::ownership`: Gating dungeons to the wallet or DID holding their NFT (`verify_ownership`).
+//! - `utils::quests`: NPCs and a quest graph derived from the NFT.
+//! - `utils::rarity`: Rarity scores from dungeon attributes.
+//! - `utils::regions`: Connected-component labeling of excavated tiles (`Dungeon::components`).
+//! - `utils::replay`: Recording explore-mode runs and replaying them in the terminal or as GIFs.
+//! - `utils::rpc`: Chia wallet RPC client for on-chain provenance (`chia-rpc` feature).
+//! - `utils::pathfinding`: Shortest paths over walkable tiles (`Dungeon::path`).
+//! - `utils::pipeline`: Generation split into memoized structure, decoration, and render stages (`Pipeline`).
+//! - `utils::progress`: Per-stage generation timings for progress bars and verbose output (`StageTimer`).
+//! - `utils::progression`: Locked gates and their keys along the critical path (`Dungeon::progression`).
+//! - `utils::save`: Saving explore-mode games keyed to their NFT ID and continuing them later.
+//! - `utils::seed`: Deterministic RNG seeding from NFT IDs.
+//! - `utils::server`: HTTP API serving dungeon renders (`server` feature).
+//! - `utils::shapes`: Room shape offsets (`ShapeStyle::Filled`, `Scaled`, or `Outlined`) and runtime custom shapes (`ShapeRegistry`).
+//! - `utils::smoothing`: Cellular-automaton cave smoothing for natural dungeon types.
+//! - `utils::spatial`: Bucketed spatial index of walkable tiles for range and nearest-tile queries (`SpatialIndex`).
+//! - `utils::spawn`: Monster placement.
+//! - `utils::sprites`: Sprite tileset rendering of game-looking PNG maps.
+//! - `utils::stats`: Layout metrics for balancing and rarity analysis.
+//! - `utils::terrain`: Water, lava, and sand features carved per dungeon type.
+//! - `utils::theme`: Per-dungeon-type color palettes for the renderers.
+//! - `utils::topology`: Room adjacency through tunnels and shared walls, with neighbor queries (`Dungeon::room_graph`).
+//! - `utils::transform`: Translating, rotating, mirroring, and scaling dungeons (`Dungeon::scale`).
+//! - `utils::traps`: Trap placement on tunnels and room thresholds.
+//! - `utils::tunnels`: Tunnel styles between rooms: Manhattan, Bresenham lines, drunkard's walks, or winding curves.
+//! - `utils::validate`: Structural lint warnings for decoded dungeons (`Dungeon::validate`).
+//! - `utils::viewer`: Terminal map viewer with pan, zoom, and room inspection (`view` command).
+//! - `utils::wasm`: JavaScript bindings for browsers (`wasm` feature).
+//! - `utils::watermark`: Branded overlays on rendered maps: NFT ID, logo, badges, and QR code (`RenderOptions`).
+//!
+//! ## Logging:
+//!
+//! Parsing and rendering run in `tracing` spans (`parse`, `excavate` per floor, and
+//! `render`) at the debug level, and the HTTP server logs each request. The crate never
+//! installs a subscriber; services embedding it install their own, as the CLI does
+//! (filtered by `RUST_LOG`, as text or `--log-format json`).
+//!
+//! ## Example Usage:
+//!
+//! ```rust
+//! use chia_dungeon::{generate_nft_id, parse_nft_id};
+//!
+//! let nft_id = generate_nft_id();
+//! let dungeon = parse_nft_id(&nft_id).expect("Failed to parse NFT ID");
+//!
+//! println!("{} (level {}) with {} rooms", dungeon.dungeon_type, dungeon.level, dungeon.rooms.len());
+//! ```
+
+pub mod utils;
+
+pub use utils::analysis::{analyze_nft, NftAnalysis, NftTraits};
+pub use utils::animation::{render_build_gif, AnimationOptions};
+pub use utils::arena::Arena;
+pub use utils::ascii::{render_ascii, render_ascii_box, render_ascii_themed};
+pub use utils::audio::{AudioError, AudioManifest, AudioTable, CueSet};
+pub use utils::balance::{Balance, BalanceError, Curve};
+pub use utils::biome::DungeonType;
+pub use utils::boss::{Boss, Element};
+pub use utils::cache::{CacheMiss, DungeonCache};
+pub use utils::campaign::{Campaign, CampaignManifest, CampaignStage};
+pub use utils::combat::{Combatant, CombatOutcome, CombatReport};
+pub use utils::config::{GenerationAlgorithm, GenerationConfig, RoomSizing};
+pub use utils::decorate::{Decoration, DecorationKind};
+pub use utils::dungeon::{Bounds, Dungeon, Provenance, Room, RoomKind};
+pub use utils::epoch::parse_nft_id_at_epoch;
+pub use utils::error::DungeonError;
+pub use utils::excavator::{parse_chia_nft_id, parse_nft_id, parse_nft_id_with_config, parse_nft_id_with_observer, parse_nft_id_with_shapes};
+pub use utils::fingerprint::Fingerprint;
+pub use utils::floors::Floor;
+pub use utils::fusion::MergeStrategy;
+pub use utils::generator::{generate_nft_id, generate_nft_id_from_seed, generate_nft_id_with, GenerateOptions};
+pub use utils::grid::{Grid, Tile};
+pub use utils::hooks::{GenerationObserver, HookAction, NoObserver, Stage};
+pub use utils::i18n::{Catalog, CatalogError};
+pub use utils::inventory::{Inventory, Item, Lock, Loot, Player};
+pub use utils::output::{OutputError, OutputFormat};
+pub use utils::ownership::{verify_ownership, Owner, OwnershipError, OwnershipProof};
+pub use utils::pipeline::{Pipeline, PipelineStats, Structure};
+pub use utils::plot::{plot_dungeon_map, render_arena_map, render_dungeon_map, render_floor_map, render_fogged_map, render_labeled_map, render_options_map, render_png_bytes, render_region_map, render_rgba_image, render_room, render_svg_string, render_themed_map, render_viewport_map, RenderFormat, RoomCrop, RoomView, Viewport};
+pub use utils::progress::{StageTimer, StageTimings};
+pub use utils::progression::Progression;
+pub use utils::quests::{Npc, NpcRole, Objective, Quest, QuestLog};
+pub use utils::rarity::{Rarity, RarityTier, RarityWeights};
+pub use utils::regions::Region;
+pub use utils::shapes::{ShapeRegistry, ShapeStyle};
+pub use utils::smoothing::Smoothing;
+pub use utils::spatial::SpatialIndex;
+pub use utils::spawn::MonsterSpawn;
+pub use utils::sprites::{render_sprite_map, save_sprite_map, Sprite, Tileset};
+pub use utils::stats::DungeonStats;
+pub use utils::theme::{Theme, TileStyle};
+pub use utils::topology::{RoomGraph, SharedWall, TunnelLink};
+pub use utils::traps::Trap;
+pub use utils::tunnels::TunnelStyle;
+pub use utils::validate::LintWarning;
+pub use utils::watermark::{render_branded_png, RenderOptions};
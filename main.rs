@@ -1,61 +1,626 @@
-mod utils;
-use rand::Rng;
-use std::collections::HashSet;
-use plotters::prelude::*;
-
-
-fn generate_nft_id() -> String {
-    let mut rng = rand::thread_rng();
-    let mut nft_id = String::from("nft1");
-
-    // Generate the remaining 58 random characters
-    for _ in 0..58 {
-        let char_index = rng.gen_range(0..62); // 0-61 for base62
-        let random_char = match char_index {
-            0..=9 => (b'0' + char_index as u8) as char,      // Numbers '0'-'9'
-            10..=35 => (b'a' + (char_index - 10) as u8) as char, // Lowercase letters 'a'-'z'
-            36..=61 => (b'A' + (char_index - 36) as u8) as char, // Uppercase letters 'A'-'Z'
-            _ => unreachable!(), // Should never reach here
-        };
-        nft_id.push(random_char);
-    }
-    nft_id
-}
-
-/// Generate and plot the dungeon map
-fn plot_dungeon_map(
-    excavated_coordinates: Vec<(i32, i32)>,
-    x_range: (i32, i32),
-    y_range: (i32, i32),
-) -> Result<(), Box<dyn std::error::Error>> {
-    let room_coords: HashSet<(i32, i32)> = excavated_coordinates.into_iter().collect();
-
-    // Create the plot using plotters
-    let root = BitMapBackend::new("dungeon_map.png", (640, 480)).into_drawing_area();
-    root.fill(&WHITE)?;
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Dungeon Map", ("sans-serif", 30))
-        .margin(10)
-        .x_label_area_size(40)
-        .y_label_area_size(40)
-        .build_cartesian_2d(x_range.0..x_range.1, y_range.0..y_range.1)?;
-
-    chart.configure_mesh().draw()?;
-
-    // Draw the dungeon map
-    chart.draw_series(room_coords.iter().map(|&(x, y)| {
-        Circle::new((x, y), 3, &RED) // Room excavated
-    }))?;
-
-    // Save the plot
-    root.present()?;
-    println!("Dungeon map saved to 'dungeon_map.png'");
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::Duration;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+use chia_dungeon::utils::animation::DEFAULT_FRAME_DELAY;
+use chia_dungeon::utils::batch::{find_dungeon_files, read_nft_ids, rerender_with_progress, run_batch_with_progress, write_summary_csv, BatchEntry, BatchOptions, SUMMARY_FILE};
+use chia_dungeon::utils::campaign::{Campaign, MANIFEST_FILE};
+use chia_dungeon::utils::epoch::epoch_for_height;
+use chia_dungeon::utils::explore;
+use chia_dungeon::utils::export::graph::{export_graph, GraphFormat};
+use chia_dungeon::utils::export::metadata::export_metadata;
+use chia_dungeon::utils::export::rexpaint::export_rexpaint;
+use chia_dungeon::utils::export::tiled::{export_tiled, TiledFormat};
+use chia_dungeon::utils::gallery::{render_shape_gallery, GalleryOptions};
+use chia_dungeon::utils::i18n::BUILTIN_LANGS;
+use chia_dungeon::utils::plot::{DEFAULT_DPI, DEFAULT_STUB_LENGTH};
+use chia_dungeon::utils::replay::{play, render_replay_gif, Recording};
+use chia_dungeon::utils::save::{default_save_path, GameState};
+use chia_dungeon::utils::sprites::DEFAULT_SPRITE_SIZE;
+use chia_dungeon::utils::viewer;
+use chia_dungeon::utils::watermark::{nft_url, watermark_file};
+use chia_dungeon::{analyze_nft, generate_nft_id_with, parse_nft_id_with_config, parse_nft_id_with_observer, render_arena_map, render_ascii, render_ascii_box, render_build_gif, render_dungeon_map, render_options_map, render_region_map, render_room, save_sprite_map, AnimationOptions, Arena, AudioTable, Balance, Catalog, Dungeon, DungeonStats, GenerateOptions, GenerationAlgorithm, GenerationConfig, GenerationObserver, MergeStrategy, NftAnalysis, OutputFormat, RarityWeights, RenderFormat, RenderOptions, RoomView, ShapeRegistry, ShapeStyle, Stage, StageTimer, StageTimings, Theme, Tileset, TunnelStyle, Viewport};
+use chia_dungeon::{verify_ownership, Owner, OwnershipProof};
 
-    Ok(())
+/// Generate and explore dungeons derived from Chia NFT IDs.
+#[derive(Parser)]
+#[command(name = "chia_dungeon", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// TOML balance file with per-level curves for monsters, gold, and traps
+    #[arg(long, global = true)]
+    balance: Option<PathBuf>,
+    /// How tunnels between rooms are dug
+    #[arg(long, global = true, value_enum, default_value = "manhattan")]
+    tunnels: TunnelStyleArg,
+    /// How the dungeon layout is excavated: rooms joined by tunnels, open noise caves, or rooms in a maze
+    #[arg(long, global = true, value_enum, default_value = "rooms")]
+    algorithm: AlgorithmArg,
+    /// Collection salt mixed into every derivation, so the same IDs dig different dungeons
+    #[arg(long, global = true)]
+    collection_salt: Option<String>,
+    /// Epoch mixed into where monsters and decorations are placed, so the dungeon changes over time
+    #[arg(long, global = true, conflicts_with = "height")]
+    epoch: Option<u64>,
+    /// Chia block height whose epoch places the monsters and decorations
+    #[arg(long, global = true)]
+    height: Option<u64>,
+    /// Print the time spent in each generation stage, and log at the info level unless RUST_LOG is set
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    /// Format of the log lines written to stderr; RUST_LOG sets what is logged
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    log_format: LogFormatArg,
+    /// Language of dungeon type names, room kinds, and lore: one of the built-in catalogs
+    #[arg(long, global = true, default_value = "en")]
+    lang: String,
+    /// TOML catalog overriding entries of the `--lang` catalog
+    #[arg(long, global = true)]
+    catalog: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a random synthetic NFT ID, optionally meeting constraints on its dungeon
+    Generate {
+        /// Dungeon type, such as "Cave" or "Hellforge"
+        #[arg(long = "type")]
+        dungeon_type: Option<String>,
+        /// Lowest dungeon level
+        #[arg(long)]
+        min_level: Option<u64>,
+        /// Fewest rooms
+        #[arg(long)]
+        min_rooms: Option<usize>,
+        /// Most rooms
+        #[arg(long)]
+        max_rooms: Option<usize>,
+        /// Seed for a reproducible ID
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Parse an NFT ID and print the decoded dungeon
+    Parse {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output format; "text" prints a summary, the others the whole dungeon
+        #[arg(long, value_enum, default_value = "text")]
+        format: OutputFormatArg,
+    },
+    /// Parse an NFT ID and print layout statistics of the dungeon
+    Stats {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Floor to measure; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+        /// Print the statistics as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Report the character statistics of an NFT ID and the traits it decodes to
+    Analyze {
+        /// NFT ID to analyze
+        nft_id: String,
+        /// Print the analysis as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Parse an NFT ID and report structural problems of every floor
+    Lint {
+        /// NFT ID to decode
+        nft_id: String,
+    },
+    /// Print the layout fingerprint of each NFT ID and the near-duplicate layouts among them
+    Fingerprint {
+        /// NFT IDs to fingerprint
+        #[arg(required = true)]
+        nft_ids: Vec<String>,
+        /// Lowest similarity, from 0 to 1, reported as a near-duplicate
+        #[arg(long, default_value_t = 0.9)]
+        threshold: f64,
+    },
+    /// Parse an NFT ID and plot the dungeon map to an image
+    Plot {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output image path
+        #[arg(short, long, default_value = "dungeon_map.png")]
+        out: PathBuf,
+        /// Image format; inferred from the output extension when omitted
+        #[arg(short, long, value_enum)]
+        format: Option<FormatArg>,
+        /// Floor to plot; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+        /// Color theme: "Classic" or a dungeon type; defaults to the dungeon's own type
+        #[arg(long)]
+        theme: Option<String>,
+        /// Label every room with its name
+        #[arg(long)]
+        labels: bool,
+        /// Color each connected region of excavated tiles distinctly
+        #[arg(long)]
+        regions: bool,
+        /// Map coordinate to center the view on, as "X,Y"
+        #[arg(long, value_parser = parse_center, conflicts_with = "room", allow_hyphen_values = true)]
+        center: Option<(i32, i32)>,
+        /// Plot a close-up of this room and its tunnel stubs, numbered from 1 as in `parse`
+        #[arg(long, conflicts_with_all = ["regions", "minimap"])]
+        room: Option<usize>,
+        /// Tiles of each tunnel shown past the edge of the room in close-ups
+        #[arg(long, default_value_t = DEFAULT_STUB_LENGTH, requires = "room")]
+        stubs: u32,
+        /// Zoom factor; 1 shows the whole dungeon
+        #[arg(long, default_value_t = 1.0)]
+        zoom: f64,
+        /// Image resolution in pixels, as "WIDTHxHEIGHT"
+        #[arg(long, value_parser = parse_size, default_value = "640x480")]
+        size: (u32, u32),
+        /// Overlay a minimap of the whole dungeon
+        #[arg(long)]
+        minimap: bool,
+        /// Resolution in dots per inch; text and markers grow with it
+        #[arg(long, default_value_t = DEFAULT_DPI)]
+        dpi: u32,
+        /// Pixels left around the chart
+        #[arg(long, default_value_t = 10)]
+        margin: u32,
+        /// Shrink the image to the aspect ratio of the plotted area
+        #[arg(long)]
+        fit: bool,
+        #[command(flatten)]
+        overlays: OverlayArgs,
+        #[command(flatten)]
+        rpc: RpcArgs,
+    },
+    /// Fuse the dungeons of two NFT IDs and plot the combined map to an image
+    Fuse {
+        /// NFT ID of the first dungeon
+        first: String,
+        /// NFT ID of the dungeon fused into the first
+        second: String,
+        /// Output image path
+        #[arg(short, long, default_value = "fused_map.png")]
+        out: PathBuf,
+        /// Image format; inferred from the output extension when omitted
+        #[arg(short, long, value_enum)]
+        format: Option<FormatArg>,
+        /// Where the second dungeon is placed relative to the first
+        #[arg(long, value_enum, default_value = "side-by-side")]
+        strategy: StrategyArg,
+    },
+    /// Face the dungeons of two NFT IDs across a center line for two players, plotting the arena
+    Arena {
+        /// NFT ID of player one's dungeon, kept on the left
+        first: String,
+        /// NFT ID of player two's dungeon, mirrored onto the right
+        second: String,
+        /// Output image path
+        #[arg(short, long, default_value = "arena_map.png")]
+        out: PathBuf,
+        /// Image format; inferred from the output extension when omitted
+        #[arg(short, long, value_enum)]
+        format: Option<FormatArg>,
+        /// Label every room with its name
+        #[arg(long)]
+        labels: bool,
+    },
+    /// Chain the dungeons of several NFT IDs into a campaign, writing its manifest and overview map
+    Campaign {
+        /// NFT IDs in campaign order
+        #[arg(required = true)]
+        nft_ids: Vec<String>,
+        /// Output directory
+        #[arg(short, long, default_value = "campaign")]
+        out_dir: PathBuf,
+        /// Overview map image format
+        #[arg(short, long, value_enum, default_value = "png")]
+        format: FormatArg,
+    },
+    /// Parse an NFT ID and render the dungeon being dug as an animated GIF
+    Animate {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output GIF path
+        #[arg(short, long, default_value = "dungeon_build.gif")]
+        out: PathBuf,
+        /// Floor to animate; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+        /// Delay between frames, in milliseconds
+        #[arg(long, default_value_t = DEFAULT_FRAME_DELAY)]
+        delay: u32,
+    },
+    /// Parse an NFT ID and render the dungeon map from a sprite tileset as a PNG
+    Sprites {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output image path
+        #[arg(short, long, default_value = "dungeon_sprites.png")]
+        out: PathBuf,
+        /// Floor to render; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+        /// Custom tileset atlas image; the embedded tileset is used when omitted
+        #[arg(long)]
+        tileset: Option<PathBuf>,
+        /// Width and height of a sprite in the custom tileset, in pixels
+        #[arg(long, default_value_t = DEFAULT_SPRITE_SIZE, requires = "tileset")]
+        sprite_size: u32,
+    },
+    /// Serve dungeon renders over HTTP
+    #[cfg(feature = "server")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = chia_dungeon::utils::server::DEFAULT_ADDR)]
+        addr: String,
+    },
+    /// Generate the dungeons for a file of NFT IDs, writing maps, JSON, and a summary CSV
+    Batch {
+        /// Text file with one NFT ID per line
+        input: PathBuf,
+        /// Output directory
+        #[arg(short, long, default_value = "dungeons")]
+        out_dir: PathBuf,
+        /// Map image format
+        #[arg(short, long, value_enum, default_value = "png")]
+        format: FormatArg,
+        /// Skip writing map images
+        #[arg(long)]
+        no_images: bool,
+        /// Skip writing dungeon JSON
+        #[arg(long)]
+        no_json: bool,
+    },
+    /// Plot every dungeon JSON under a batch output or cache directory again, next to its file
+    Rerender {
+        /// Directory searched for dungeon JSON files, including its subdirectories
+        #[arg(default_value = "dungeons")]
+        dir: PathBuf,
+        /// Map image format
+        #[arg(short, long, value_enum, default_value = "png")]
+        format: FormatArg,
+        /// Color theme: "Classic" or a dungeon type; defaults to each dungeon's own type
+        #[arg(long)]
+        theme: Option<String>,
+    },
+    /// Parse an NFT ID and export the dungeon as a Tiled map
+    Export {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output map path
+        #[arg(short, long, default_value = "dungeon.tmx")]
+        out: PathBuf,
+        /// Map format; inferred from the output extension when omitted
+        #[arg(short, long, value_enum)]
+        format: Option<TiledFormatArg>,
+        /// Floor to export; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+    },
+    /// Parse an NFT ID and export its room connectivity graph as DOT or GraphML
+    Graph {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output graph path
+        #[arg(short, long, default_value = "dungeon.dot")]
+        out: PathBuf,
+        /// Graph format; inferred from the output extension when omitted
+        #[arg(short, long, value_enum)]
+        format: Option<GraphFormatArg>,
+        /// Floor to export; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+    },
+    /// Parse an NFT ID and export the dungeon as a layered RexPaint .xp image
+    Rexpaint {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output image path
+        #[arg(short, long, default_value = "dungeon.xp")]
+        out: PathBuf,
+        /// Floor to export; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+    },
+    /// Parse an NFT ID and print the dungeon as text, or save it to a file
+    Ascii {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Save the map to this path instead of printing it
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Floor to render; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+        /// Glyphs of the map: "@" and "O" tiles, or walls of Unicode box-drawing characters
+        #[arg(long, value_enum, default_value = "plain")]
+        style: AsciiStyleArg,
+    },
+    /// Parse an NFT ID and export its traits as CHIP-0007 NFT metadata
+    Metadata {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output metadata path
+        #[arg(short, long, default_value = "metadata.json")]
+        out: PathBuf,
+    },
+    /// Parse an NFT ID and export the audio cues of the dungeon and its rooms as JSON
+    Audio {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Output manifest path
+        #[arg(short, long, default_value = "audio.json")]
+        out: PathBuf,
+        /// TOML audio table overriding entries of the default table
+        #[arg(long)]
+        audio_table: Option<PathBuf>,
+    },
+    /// Explore the dungeon interactively in the terminal
+    Explore {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Sight radius in tiles for the fog of war
+        #[arg(long, default_value_t = explore::DEFAULT_SIGHT_RADIUS)]
+        sight: u32,
+        /// Save a recording of the run to this path, for `replay`
+        #[arg(long)]
+        record: Option<PathBuf>,
+        /// Save the game to this path on quitting (`<NFT_ID>.sav` with `--continue`)
+        #[arg(long)]
+        save: Option<PathBuf>,
+        /// Continue the saved game of this NFT ID, with its sight radius, and save it again on quitting
+        #[arg(long = "continue", conflicts_with = "record")]
+        resume: bool,
+        /// Only explore if this wallet address, DID, or puzzle hash owns the NFT, as the wallet RPC reports
+        #[arg(long)]
+        owner: Option<String>,
+        #[command(flatten)]
+        rpc: RpcArgs,
+    },
+    /// Parse an NFT ID and browse its map in the terminal, with pan, zoom, and room details
+    View {
+        /// NFT ID to decode
+        nft_id: String,
+        /// Floor to view; 0 is the top floor
+        #[arg(long, default_value_t = 0)]
+        floor: usize,
+    },
+    /// Preview the room shapes of the NFT characters
+    Shapes {
+        #[command(subcommand)]
+        command: ShapesCommand,
+    },
+    /// Play back a run recorded by `explore --record` in the terminal, or export it as a GIF
+    Replay {
+        /// Recording to play back
+        file: PathBuf,
+        /// Save the replay as an animated GIF at this path instead of playing it
+        #[arg(long)]
+        gif: Option<PathBuf>,
+        /// Delay between actions in milliseconds
+        #[arg(long, default_value_t = DEFAULT_FRAME_DELAY)]
+        delay: u32,
+    },
+}
+
+/// Danger heatmap and branded overlays drawn over maps
+#[derive(Args)]
+struct OverlayArgs {
+    /// Tint walkable tiles by their danger, from green to red
+    #[arg(long, conflicts_with = "regions")]
+    heatmap: bool,
+    /// Write the NFT ID in the bottom-left corner
+    #[arg(long)]
+    nft_label: bool,
+    /// Collection logo image drawn in the top-right corner
+    #[arg(long)]
+    logo: Option<PathBuf>,
+    /// Show level and dungeon type badges in the top-left corner
+    #[arg(long)]
+    badges: bool,
+    /// Add a QR code linking to the NFT in the bottom-right corner
+    #[arg(long)]
+    qr: bool,
+    /// Link encoded in the QR code; the NFT's marketplace page by default
+    #[arg(long, requires = "qr")]
+    qr_url: Option<String>,
+}
+
+impl OverlayArgs {
+    /// Overlays of these options for `dungeon`
+    fn options(self, dungeon: &Dungeon) -> RenderOptions {
+        let qr_url = self.qr.then(|| self.qr_url.unwrap_or_else(|| nft_url(&dungeon.nft_id)));
+        RenderOptions { nft_id: self.nft_label, logo: self.logo, badges: self.badges, qr_url, heatmap: self.heatmap }
+    }
+}
+
+/// Chia wallet RPC options for on-chain provenance and ownership checks
+#[derive(Args)]
+struct RpcArgs {
+    /// Wallet RPC URL to fetch the NFT's owner and minting height from
+    #[cfg(feature = "chia-rpc")]
+    #[arg(long)]
+    rpc_url: Option<String>,
+    /// Private wallet certificate for the RPC connection
+    #[cfg(feature = "chia-rpc")]
+    #[arg(long, requires_all = ["rpc_key", "rpc_ca"])]
+    rpc_cert: Option<PathBuf>,
+    /// Private wallet key for the RPC connection
+    #[cfg(feature = "chia-rpc")]
+    #[arg(long, requires = "rpc_cert")]
+    rpc_key: Option<PathBuf>,
+    /// Private CA certificate (`ssl/ca/private_ca.crt`) the wallet's certificate is signed by
+    #[cfg(feature = "chia-rpc")]
+    #[arg(long, requires = "rpc_cert")]
+    rpc_ca: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Png,
+    Svg,
+    Isometric,
+}
+
+#[derive(Subcommand)]
+enum ShapesCommand {
+    /// Render every shape character at a few sizes into one contact-sheet PNG
+    Preview {
+        /// Output image path
+        #[arg(short, long, default_value = "shapes.png")]
+        out: PathBuf,
+        /// Room sizes drawn for every shape, comma-separated
+        #[arg(long, value_delimiter = ',', default_value = "1,2,3")]
+        sizes: Vec<u32>,
+        /// How shapes are expanded by the room size
+        #[arg(long, value_enum, default_value = "filled")]
+        style: ShapeStyleArg,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ShapeStyleArg {
+    Filled,
+    Scaled,
+    Outlined,
+}
+
+impl From<ShapeStyleArg> for ShapeStyle {
+    fn from(style: ShapeStyleArg) -> ShapeStyle {
+        match style {
+            ShapeStyleArg::Filled => ShapeStyle::Filled,
+            ShapeStyleArg::Scaled => ShapeStyle::Scaled,
+            ShapeStyleArg::Outlined => ShapeStyle::Outlined,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormatArg {
+    Text,
+    Json,
 }
 
-fn main() {
+#[derive(Clone, Copy, ValueEnum)]
+enum AlgorithmArg {
+    Rooms,
+    Noise,
+    Maze,
+}
+
+impl From<AlgorithmArg> for GenerationAlgorithm {
+    fn from(algorithm: AlgorithmArg) -> GenerationAlgorithm {
+        match algorithm {
+            AlgorithmArg::Rooms => GenerationAlgorithm::Rooms,
+            AlgorithmArg::Noise => GenerationAlgorithm::Noise,
+            AlgorithmArg::Maze => GenerationAlgorithm::Maze,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TunnelStyleArg {
+    Manhattan,
+    Bresenham,
+    Drunkard,
+    Winding,
+    ByType,
+}
+
+impl From<TunnelStyleArg> for TunnelStyle {
+    fn from(style: TunnelStyleArg) -> TunnelStyle {
+        match style {
+            TunnelStyleArg::Manhattan => TunnelStyle::Manhattan,
+            TunnelStyleArg::Bresenham => TunnelStyle::Bresenham,
+            TunnelStyleArg::Drunkard => TunnelStyle::DrunkardsWalk,
+            TunnelStyleArg::Winding => TunnelStyle::Winding,
+            TunnelStyleArg::ByType => TunnelStyle::ByType,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormatArg {
+    Text,
+    Json,
+    Yaml,
+    Toml,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AsciiStyleArg {
+    Plain,
+    Box,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StrategyArg {
+    SideBySide,
+    Stacked,
+    Overlay,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum GraphFormatArg {
+    Dot,
+    #[value(name = "graphml")]
+    GraphMl,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TiledFormatArg {
+    Tmx,
+    Json,
+}
+
+impl From<TiledFormatArg> for TiledFormat {
+    fn from(format: TiledFormatArg) -> TiledFormat {
+        match format {
+            TiledFormatArg::Tmx => TiledFormat::Tmx,
+            TiledFormatArg::Json => TiledFormat::Json,
+        }
+    }
+}
+
+impl From<GraphFormatArg> for GraphFormat {
+    fn from(format: GraphFormatArg) -> GraphFormat {
+        match format {
+            GraphFormatArg::Dot => GraphFormat::Dot,
+            GraphFormatArg::GraphMl => GraphFormat::GraphMl,
+        }
+    }
+}
+
+impl From<FormatArg> for RenderFormat {
+    fn from(format: FormatArg) -> RenderFormat {
+        match format {
+            FormatArg::Png => RenderFormat::Png,
+            FormatArg::Svg => RenderFormat::Svg,
+            FormatArg::Isometric => RenderFormat::Isometric,
+        }
+    }
+}
+
+impl From<StrategyArg> for MergeStrategy {
+    fn from(strategy: StrategyArg) -> MergeStrategy {
+        match strategy {
+            StrategyArg::SideBySide => MergeStrategy::SideBySide,
+            StrategyArg::Stacked => MergeStrategy::Stacked,
+            StrategyArg::Overlay => MergeStrategy::Overlay,
+        }
+    }
+}
+
+fn main() -> ExitCode {
     //! This program generates a dungeon and simulates player movement based on an NFT code.
     //! Description of the original implementation:
     //! 1. Decodes the `nft_id` to determine:
@@ -67,23 +632,761 @@ fn main() {
     //! 3. Simulates a simple game loop where the player can explore the dungeon.
     //! 4. Includes random room type generation and validation for room existence.
 
-    // Generate a random NFT ID
-    let nft_code = generate_nft_id();
-    println!("Generated NFT ID: {}", nft_code);
+    let cli = Cli::parse();
+    init_logging(cli.log_format, cli.verbose);
+    let epoch = cli.epoch.or(cli.height.map(epoch_for_height));
+    let config = match generation_config(cli.balance.as_deref(), cli.algorithm.into(), cli.tunnels.into(), cli.collection_salt, epoch) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let catalog = match catalog(&cli.lang, cli.catalog.as_deref()) {
+        Ok(catalog) => catalog,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
 
-    // Parse the NFT ID
-    match utils::excavator::parse_nft_id(&nft_code) {
-        Ok((_num_rooms, _coordinates, _sizes, _shapes, x_range, y_range, _area_size, _char_frequency, _most_frequent_char, dungeon_type, dungeon_level, excavated_coordinates)) => {
-            println!("Parsed NFT ID:");
-            println!("Type: {:?}", dungeon_type);
-            println!("Level: {:?}", dungeon_level);
-            println!("Excavated rooms: {:?}", excavated_coordinates);
+    let result = match cli.command {
+        Command::Generate { dungeon_type, min_level, min_rooms, max_rooms, seed } => {
+            let options = GenerateOptions { dungeon_type, min_level, min_rooms, max_rooms, seed };
+            generate_nft_id_with(&options)
+                .map(|nft_id| println!("{}", nft_id))
+                .ok_or_else(|| "No NFT ID meets the given constraints".to_string())
+        }
+        Command::Parse { nft_id, format } => parse(&nft_id, &config).and_then(|dungeon| {
+            let format = match format {
+                OutputFormatArg::Text => {
+                    print_dungeon(&dungeon, &catalog);
+                    return Ok(());
+                }
+                OutputFormatArg::Json => OutputFormat::Json,
+                OutputFormatArg::Yaml => OutputFormat::Yaml,
+                OutputFormatArg::Toml => OutputFormat::Toml,
+            };
+            let output = format.serialize(&dungeon).map_err(|err| format!("Error serializing dungeon: {}", err))?;
+            println!("{}", output.trim_end());
+            Ok(())
+        }),
+        Command::Stats { nft_id, floor, json } => parse(&nft_id, &config).and_then(|dungeon| {
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            let stats = floor.stats();
+            if json {
+                let json = serde_json::to_string_pretty(&stats).map_err(|err| format!("Error serializing statistics: {}", err))?;
+                println!("{}", json);
+            } else {
+                print_stats(&stats);
+            }
+            Ok(())
+        }),
+        Command::Analyze { nft_id, json } => analyze_nft(&nft_id, &config).map_err(|err| err.to_string()).and_then(|analysis| {
+            if json {
+                let json = serde_json::to_string_pretty(&analysis).map_err(|err| format!("Error serializing analysis: {}", err))?;
+                println!("{}", json);
+            } else {
+                print_analysis(&analysis);
+            }
+            Ok(())
+        }),
+        Command::Lint { nft_id } => parse(&nft_id, &config).and_then(|dungeon| lint(&dungeon)),
+        Command::Fingerprint { nft_ids, threshold } => run_fingerprint_command(&nft_ids, threshold, &config),
+        Command::Plot { nft_id, out, format, floor, theme, labels, regions, center, room, stubs, zoom, size, minimap, dpi, margin, fit, overlays, rpc } => parse_with_progress(&nft_id, &config).and_then(|(mut dungeon, mut progress)| {
+            add_provenance(&mut dungeon, &rpc)?;
+            let format = format.map(RenderFormat::from).unwrap_or_else(|| RenderFormat::from_path(&out));
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            let theme = match theme {
+                Some(name) => Theme::by_name(&name).ok_or_else(|| format!("Unknown theme '{}'", name))?,
+                None => Theme::for_dungeon(&floor),
+            };
+            let viewport = Viewport { center, scale: zoom, size, minimap, dpi, margin, fit };
+            let options = overlays.options(&floor);
+            if options.has_watermarks() && format == RenderFormat::Svg {
+                return Err("Watermarks need a raster image format".to_string());
+            }
+            progress.on_stage_started(0, Stage::Render);
+            if let Some(index) = room {
+                let index = index.checked_sub(1).filter(|&index| index < floor.rooms.len()).ok_or_else(|| format!("Dungeon has no room {}", index))?;
+                let view = RoomView { stub_length: stubs, viewport, labels };
+                render_room(&floor, index, &out, format, &theme, &view, &options).map_err(|err| format!("Error plotting room: {}", err))?;
+            } else if regions {
+                render_region_map(&floor, &out, format, &theme, &viewport, labels).map_err(|err| format!("Error plotting dungeon: {}", err))?;
+                if options.has_watermarks() {
+                    watermark_file(&out, &floor, &options).map_err(|err| format!("Error watermarking map: {}", err))?;
+                }
+            } else {
+                render_options_map(&floor, &out, format, &theme, &viewport, labels, &options).map_err(|err| format!("Error plotting dungeon: {}", err))?;
+            }
+            let timings = progress.finish();
+            println!("Dungeon map saved to '{}'", out.display());
+            if cli.verbose {
+                eprintln!("{}", timings);
+            }
+            Ok(())
+        }),
+        Command::Fuse { first, second, out, format, strategy } => parse(&first, &config).and_then(|first| {
+            let fused = first.merge(&parse(&second, &config)?, strategy.into());
+            let format = format.map(RenderFormat::from).unwrap_or_else(|| RenderFormat::from_path(&out));
+            render_dungeon_map(&fused, &out, format).map_err(|err| format!("Error plotting dungeon: {}", err))?;
+            println!("{} (level {}) with {} rooms", fused.dungeon_type, fused.level, fused.rooms.len());
+            println!("Fused map saved to '{}'", out.display());
+            Ok(())
+        }),
+        Command::Arena { first, second, out, format, labels } => {
+            let format = format.map(RenderFormat::from).unwrap_or_else(|| RenderFormat::from_path(&out));
+            run_arena_command(&first, &second, &config, &out, format, labels)
+        }
+        Command::Campaign { nft_ids, out_dir, format } => run_campaign_command(&nft_ids, &config, &out_dir, format.into()),
+        Command::Animate { nft_id, out, floor, delay } => parse(&nft_id, &config).and_then(|dungeon| {
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            let options = AnimationOptions { frame_delay: delay, ..AnimationOptions::default() };
+            render_build_gif(&floor, &out, &options).map_err(|err| format!("Error rendering animation: {}", err))?;
+            println!("Build animation saved to '{}'", out.display());
+            Ok(())
+        }),
+        Command::Sprites { nft_id, out, floor, tileset, sprite_size } => parse(&nft_id, &config).and_then(|dungeon| {
+            let tileset = match tileset {
+                Some(path) => Tileset::load(&path, sprite_size).map_err(|err| format!("Error loading tileset: {}", err))?,
+                None => Tileset::default(),
+            };
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            save_sprite_map(&floor, &tileset, &out).map_err(|err| format!("Error rendering dungeon: {}", err))?;
+            println!("Sprite map saved to '{}'", out.display());
+            Ok(())
+        }),
+        #[cfg(feature = "server")]
+        Command::Serve { addr } => tokio::runtime::Runtime::new()
+            .map_err(|err| format!("Error starting async runtime: {}", err))
+            .and_then(|runtime| {
+                println!("Serving dungeons on http://{}", addr);
+                runtime
+                    .block_on(chia_dungeon::utils::server::serve(&addr, chia_dungeon::DungeonCache::default().with_config(config.clone())))
+                    .map_err(|err| format!("Error serving dungeons: {}", err))
+            }),
+        Command::Batch { input, out_dir, format, no_images, no_json } => {
+            run_batch_command(&input, &out_dir, &BatchOptions {
+                image_format: (!no_images).then(|| format.into()),
+                json: !no_json,
+                config: &config,
+            }, cli.verbose)
+        }
+        Command::Rerender { dir, format, theme } => run_rerender_command(&dir, format.into(), theme.as_deref()),
+        Command::Export { nft_id, out, format, floor } => parse(&nft_id, &config).and_then(|dungeon| {
+            let format = format.map(TiledFormat::from).unwrap_or_else(|| TiledFormat::from_path(&out));
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            export_tiled(&floor, &out, format).map_err(|err| format!("Error exporting dungeon: {}", err))?;
+            println!("Tiled map saved to '{}'", out.display());
+            Ok(())
+        }),
+        Command::Graph { nft_id, out, format, floor } => parse(&nft_id, &config).and_then(|dungeon| {
+            let format = format.map(GraphFormat::from).unwrap_or_else(|| GraphFormat::from_path(&out));
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            export_graph(&floor, &out, format).map_err(|err| format!("Error exporting graph: {}", err))?;
+            println!("Room graph saved to '{}'", out.display());
+            Ok(())
+        }),
+        Command::Rexpaint { nft_id, out, floor } => parse(&nft_id, &config).and_then(|dungeon| {
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            export_rexpaint(&floor, &out).map_err(|err| format!("Error exporting RexPaint image: {}", err))?;
+            println!("RexPaint image saved to '{}'", out.display());
+            Ok(())
+        }),
+        Command::Ascii { nft_id, out, floor, style } => parse(&nft_id, &config).and_then(|dungeon| {
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            let text = match style {
+                AsciiStyleArg::Plain => render_ascii(&floor),
+                AsciiStyleArg::Box => render_ascii_box(&floor),
+            };
+            match out {
+                Some(out) => {
+                    fs::write(&out, text + "\n").map_err(|err| format!("Error saving map: {}", err))?;
+                    println!("Text map saved to '{}'", out.display());
+                }
+                None => println!("{}", text),
+            }
+            Ok(())
+        }),
+        Command::Metadata { nft_id, out } => parse(&nft_id, &config).and_then(|dungeon| {
+            export_metadata(&dungeon, &out).map_err(|err| format!("Error exporting metadata: {}", err))?;
+            println!("NFT metadata saved to '{}'", out.display());
+            Ok(())
+        }),
+        Command::Audio { nft_id, out, audio_table } => parse(&nft_id, &config).and_then(|dungeon| {
+            let table = match audio_table {
+                Some(path) => AudioTable::load(&path).map_err(|err| format!("Error loading audio table: {}", err))?,
+                None => AudioTable::default(),
+            };
+            let json = dungeon.audio_manifest(&table).to_json().map_err(|err| format!("Error serializing audio manifest: {}", err))?;
+            fs::write(&out, json).map_err(|err| format!("Error saving audio manifest: {}", err))?;
+            println!("Audio manifest saved to '{}'", out.display());
+            Ok(())
+        }),
+        Command::Explore { nft_id, sight, record, save, resume, owner, rpc } => parse(&nft_id, &config).and_then(|dungeon| {
+            if let Some(owner) = owner {
+                check_ownership(&dungeon, &owner, &rpc)?;
+            }
+            run_explore_command(&dungeon, &config, sight, record, save, resume)
+        }),
+        Command::View { nft_id, floor } => parse(&nft_id, &config).and_then(|dungeon| {
+            let floor = dungeon.floor_view(floor).ok_or_else(|| format!("Dungeon has no floor {}", floor))?;
+            viewer::run_viewer(&floor).map_err(|err| format!("Error running viewer: {}", err))
+        }),
+        Command::Shapes { command: ShapesCommand::Preview { out, sizes, style } } => {
+            let options = GalleryOptions { sizes, style: style.into() };
+            render_shape_gallery(&ShapeRegistry::new(), &out, &options)
+                .map(|()| println!("Shape gallery saved to '{}'", out.display()))
+                .map_err(|err| format!("Error rendering shape gallery: {}", err))
+        }
+        Command::Replay { file, gif, delay } => Recording::load(&file).map_err(|err| format!("Error loading recording: {}", err)).and_then(|recording| {
+            let dungeon = parse(&recording.nft_id, &config)?;
+            match gif {
+                Some(out) => {
+                    let options = AnimationOptions { frame_delay: delay, ..AnimationOptions::default() };
+                    render_replay_gif(&dungeon, &recording, &out, &options).map_err(|err| format!("Error rendering replay: {}", err))?;
+                    println!("Replay animation saved to '{}'", out.display());
+                    Ok(())
+                }
+                None => play(&dungeon, &recording, delay).map_err(|err| format!("Error during replay: {}", err)),
+            }
+        }),
+    };
 
-            // Print the dungeon map
-            println!("Dungeon Map:");
-            let _ = plot_dungeon_map(excavated_coordinates, x_range, y_range);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
         }
-        Err(err) => println!("Error parsing NFT ID: {}", err),
     }
 }
 
+// Log to stderr in `format`, filtered by RUST_LOG or else by the verbosity
+fn init_logging(format: LogFormatArg, verbose: bool) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(if verbose { "info" } else { "warn" }));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr).with_span_events(FmtSpan::CLOSE);
+    match format {
+        LogFormatArg::Text => subscriber.init(),
+        LogFormatArg::Json => subscriber.json().init(),
+    }
+}
+
+// Generation config with the balance file, if any, the algorithm, the tunnel style, the
+// collection salt, and the epoch
+fn generation_config(
+    balance: Option<&Path>,
+    algorithm: GenerationAlgorithm,
+    tunnel_style: TunnelStyle,
+    collection_salt: Option<String>,
+    epoch: Option<u64>,
+) -> Result<GenerationConfig, String> {
+    let balance = match balance {
+        Some(path) => Balance::load(path).map_err(|err| format!("Error loading balance: {}", err))?,
+        None => Balance::default(),
+    };
+    Ok(GenerationConfig { algorithm, balance, tunnel_style, collection_salt, epoch, ..GenerationConfig::default() })
+}
+
+// Built-in catalog of `lang` with the entries of the catalog file, if any, over it
+fn catalog(lang: &str, path: Option<&Path>) -> Result<Catalog, String> {
+    let builtin = Catalog::builtin(lang).ok_or_else(|| format!("Unknown language '{}'; built-in languages: {}", lang, BUILTIN_LANGS.join(", ")))?;
+    match path {
+        Some(path) => Ok(builtin.with_overrides(Catalog::load(path).map_err(|err| format!("Error loading catalog: {}", err))?)),
+        None => Ok(builtin),
+    }
+}
+
+// Parse the NFT ID, formatting errors for the terminal
+fn parse(nft_id: &str, config: &GenerationConfig) -> Result<Dungeon, String> {
+    parse_nft_id_with_config(nft_id, config).map_err(|err| format!("Error parsing NFT ID: {}", err))
+}
+
+// Parse the NFT ID like `parse`, showing the running stage on a spinner
+fn parse_with_progress(nft_id: &str, config: &GenerationConfig) -> Result<(Dungeon, StageProgress), String> {
+    let mut progress = StageProgress::new();
+    let dungeon = parse_nft_id_with_observer(nft_id, config, &ShapeRegistry::new(), &mut progress);
+    dungeon.map(|dungeon| (dungeon, progress)).map_err(|err| format!("Error parsing NFT ID: {}", err))
+}
+
+// Spinner naming the running generation stage, timing every stage; cleared when dropped
+struct StageProgress {
+    spinner: ProgressBar,
+    timer: StageTimer,
+}
+
+impl StageProgress {
+    fn new() -> StageProgress {
+        let spinner = ProgressBar::new_spinner().with_finish(ProgressFinish::AndClear);
+        spinner.enable_steady_tick(Duration::from_millis(100));
+        StageProgress { spinner, timer: StageTimer::new() }
+    }
+
+    // Clear the spinner and return the time spent in each stage
+    fn finish(&mut self) -> StageTimings {
+        self.spinner.finish_and_clear();
+        self.timer.stop();
+        self.timer.timings()
+    }
+}
+
+impl GenerationObserver for StageProgress {
+    fn on_stage_started(&mut self, depth: usize, stage: Stage) {
+        self.spinner.set_message(format!("floor {}: {}", depth, stage));
+        self.timer.start(stage);
+    }
+}
+
+// Print the fingerprint of every NFT ID, then every pair of near-duplicate layouts
+fn run_fingerprint_command(nft_ids: &[String], threshold: f64, config: &GenerationConfig) -> Result<(), String> {
+    let mut fingerprints = Vec::new();
+    for nft_id in nft_ids {
+        let fingerprint = parse(nft_id, config)?.fingerprint();
+        println!("{}  {}", fingerprint, nft_id);
+        fingerprints.push((nft_id, fingerprint));
+    }
+    for (index, (first, fingerprint)) in fingerprints.iter().enumerate() {
+        for (second, other) in &fingerprints[index + 1..] {
+            let similarity = fingerprint.similarity(other);
+            if similarity >= threshold {
+                println!("Near-duplicate: {} and {} ({:.0}% similar)", first, second, similarity * 100.0);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Print the lint warnings of every floor, failing when there are any
+fn lint(dungeon: &Dungeon) -> Result<(), String> {
+    let mut count = 0;
+    for depth in 0..dungeon.floor_count() {
+        let floor = dungeon.floor_view(depth).ok_or_else(|| format!("Dungeon has no floor {}", depth))?;
+        for warning in floor.validate() {
+            println!("Floor {}: {}", depth, warning);
+            count += 1;
+        }
+    }
+    match count {
+        0 => {
+            println!("No problems found in '{}'", dungeon.nft_id);
+            Ok(())
+        }
+        count => Err(format!("{} problem(s) found in '{}'", count, dungeon.nft_id)),
+    }
+}
+
+// Parse a map coordinate given as "X,Y"
+fn parse_center(value: &str) -> Result<(i32, i32), String> {
+    let (x, y) = value.split_once(',').ok_or("expected X,Y")?;
+    Ok((x.trim().parse().map_err(|_| "X is not an integer")?, y.trim().parse().map_err(|_| "Y is not an integer")?))
+}
+
+// Parse an image resolution given as "WIDTHxHEIGHT"
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value.split_once('x').ok_or("expected WIDTHxHEIGHT")?;
+    let parse = |side: &str| side.trim().parse::<u32>().ok().filter(|&side| side > 0).ok_or("sides must be positive integers");
+    Ok((parse(width)?, parse(height)?))
+}
+
+// Explore the dungeon in the terminal, resuming and saving the game and recording the
+// run when asked
+fn run_explore_command(dungeon: &Dungeon, config: &GenerationConfig, sight: u32, record: Option<PathBuf>, save: Option<PathBuf>, resume: bool) -> Result<(), String> {
+    let save = save.or_else(|| resume.then(|| default_save_path(&dungeon.nft_id)));
+    let mut explorer = match &save {
+        Some(path) if resume => GameState::load(path)
+            .and_then(|state| state.resume(dungeon, config))
+            .map_err(|err| format!("Error loading saved game: {}", err))?,
+        _ => explore::Explorer::with_sight_radius(dungeon, sight),
+    };
+    let recording = explore::run_explorer(dungeon, &mut explorer).map_err(|err| format!("Error during exploration: {}", err))?;
+    if let Some(path) = save {
+        GameState::capture(dungeon, config, &explorer).save(&path).map_err(|err| format!("Error saving game: {}", err))?;
+        println!("Game saved to '{}'", path.display());
+    }
+    if let Some(path) = record {
+        recording.save(&path).map_err(|err| format!("Error saving recording: {}", err))?;
+        println!("Recording saved to '{}'", path.display());
+    }
+    Ok(())
+}
+
+// Generate every dungeon listed in `input` and write the summary CSV
+fn run_batch_command(input: &Path, out_dir: &Path, options: &BatchOptions, verbose: bool) -> Result<(), String> {
+    let nft_ids = read_nft_ids(input).map_err(|err| format!("Error reading NFT IDs: {}", err))?;
+    let bar = ProgressBar::new(nft_ids.len() as u64).with_style(
+        ProgressStyle::with_template("{bar:40} {pos}/{len} dungeons [{elapsed_precise}, {eta} left] {msg}").unwrap_or_else(|_| ProgressStyle::default_bar()),
+    );
+    let total = Mutex::new(StageTimings::default());
+    let entries = run_batch_with_progress(&nft_ids, out_dir, options, |entry, timings| {
+        *total.lock().unwrap() += *timings;
+        bar.set_message(entry.nft_id.clone());
+        bar.inc(1);
+    })
+    .map_err(|err| format!("Error creating output directory: {}", err))?;
+    bar.finish_and_clear();
+    let summary = out_dir.join(SUMMARY_FILE);
+    write_summary_csv(&entries, &summary).map_err(|err| format!("Error writing summary: {}", err))?;
+
+    let failed: Vec<&BatchEntry> = entries.iter().filter(|entry| entry.error.is_some()).collect();
+    for entry in &failed {
+        tracing::warn!(nft_id = %entry.nft_id, error = entry.error.as_deref().unwrap_or_default(), "dungeon generation failed");
+    }
+    println!(
+        "Generated {} of {} dungeons into '{}'; summary saved to '{}'",
+        entries.len() - failed.len(),
+        entries.len(),
+        out_dir.display(),
+        summary.display()
+    );
+    if verbose {
+        eprintln!("Time per stage, summed over {} dungeons:", entries.len());
+        eprintln!("{}", total.into_inner().unwrap());
+    }
+    Ok(())
+}
+
+// Plot every dungeon JSON under `dir` again in `format`, and in the named theme if any
+fn run_rerender_command(dir: &Path, format: RenderFormat, theme: Option<&str>) -> Result<(), String> {
+    let theme = theme.map(|name| Theme::by_name(name).ok_or_else(|| format!("Unknown theme '{}'", name))).transpose()?;
+    let files = find_dungeon_files(dir).map_err(|err| format!("Error reading '{}': {}", dir.display(), err))?;
+    let bar = ProgressBar::new(files.len() as u64).with_style(
+        ProgressStyle::with_template("{bar:40} {pos}/{len} maps [{elapsed_precise}, {eta} left] {msg}").unwrap_or_else(|_| ProgressStyle::default_bar()),
+    );
+    let entries = rerender_with_progress(&files, format, theme.as_ref(), |entry| {
+        bar.set_message(entry.json.display().to_string());
+        bar.inc(1);
+    });
+    bar.finish_and_clear();
+
+    let failed = entries.iter().filter(|entry| entry.error.is_some()).count();
+    for entry in entries.iter().filter(|entry| entry.error.is_some()) {
+        tracing::warn!(json = %entry.json.display(), error = entry.error.as_deref().unwrap_or_default(), "dungeon rerender failed");
+    }
+    println!("Rerendered {} of {} dungeon files under '{}'", entries.len() - failed, entries.len(), dir.display());
+    Ok(())
+}
+
+// Face the dungeons of `first` and `second` in an arena, plotting it to `out`
+fn run_arena_command(first: &str, second: &str, config: &GenerationConfig, out: &Path, format: RenderFormat, labels: bool) -> Result<(), String> {
+    let arena = Arena::from_nft_ids(first, second, config).map_err(|err| format!("Error parsing NFT ID: {}", err))?;
+    render_arena_map(&arena, out, format, &Theme::for_dungeon(&arena.dungeon), labels).map_err(|err| format!("Error plotting arena: {}", err))?;
+    println!(
+        "{} (level {}) with {} rooms, center line at x = {}",
+        arena.dungeon.dungeon_type,
+        arena.dungeon.level,
+        arena.dungeon.rooms.len(),
+        arena.center_line
+    );
+    for (player, spawn) in arena.spawns.iter().enumerate() {
+        println!("Player {} spawns at {:?}", player + 1, spawn);
+    }
+    println!("Arena map saved to '{}'", out.display());
+    Ok(())
+}
+
+// Chain the dungeons of `nft_ids` under `config`, writing the manifest and overview map
+// into `out_dir`
+fn run_campaign_command(nft_ids: &[String], config: &GenerationConfig, out_dir: &Path, format: RenderFormat) -> Result<(), String> {
+    let campaign = Campaign::from_nft_ids(nft_ids, config).map_err(|err| err.to_string())?;
+    let overview = campaign.overview.as_ref().ok_or("No NFT IDs given")?;
+    fs::create_dir_all(out_dir).map_err(|err| format!("Error creating output directory: {}", err))?;
+
+    let manifest_path = out_dir.join(MANIFEST_FILE);
+    let json = campaign.manifest().to_json().map_err(|err| format!("Error serializing manifest: {}", err))?;
+    fs::write(&manifest_path, json).map_err(|err| format!("Error writing manifest: {}", err))?;
+    let extension = if format == RenderFormat::Svg { "svg" } else { "png" };
+    let map_path = out_dir.join(format!("overview.{}", extension));
+    render_dungeon_map(overview, &map_path, format).map_err(|err| format!("Error plotting overview: {}", err))?;
+
+    println!("Campaign of {} dungeons with {} rooms", campaign.stages.len(), overview.rooms.len());
+    println!("Manifest saved to '{}'; overview map saved to '{}'", manifest_path.display(), map_path.display());
+    Ok(())
+}
+
+// Connect to the wallet RPC when an RPC URL was given
+#[cfg(feature = "chia-rpc")]
+fn rpc_client(rpc: &RpcArgs) -> Result<Option<chia_dungeon::utils::rpc::ChiaRpcClient>, String> {
+    use chia_dungeon::utils::rpc::ChiaRpcClient;
+
+    let Some(url) = &rpc.rpc_url else {
+        return Ok(None);
+    };
+    let client = match (&rpc.rpc_cert, &rpc.rpc_key, &rpc.rpc_ca) {
+        (Some(cert), Some(key), Some(ca)) => ChiaRpcClient::with_certificate(url, cert, key, ca),
+        _ => ChiaRpcClient::new(url),
+    };
+    client.map(Some).map_err(|err| format!("Error connecting to Chia RPC: {}", err))
+}
+
+// Block on a request to the wallet RPC
+#[cfg(feature = "chia-rpc")]
+fn block_on<T>(request: impl std::future::Future<Output = T>) -> Result<T, String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|err| format!("Error starting async runtime: {}", err))?;
+    Ok(runtime.block_on(request))
+}
+
+// Fetch on-chain provenance when an RPC URL was given
+#[cfg(feature = "chia-rpc")]
+fn add_provenance(dungeon: &mut Dungeon, rpc: &RpcArgs) -> Result<(), String> {
+    use chia_dungeon::utils::rpc::enrich_dungeon;
+
+    let Some(client) = rpc_client(rpc)? else {
+        return Ok(());
+    };
+    block_on(enrich_dungeon(dungeon, &client))?.map_err(|err| format!("Error fetching NFT provenance: {}", err))
+}
+
+#[cfg(not(feature = "chia-rpc"))]
+fn add_provenance(_dungeon: &mut Dungeon, _rpc: &RpcArgs) -> Result<(), String> {
+    Ok(())
+}
+
+// Ask the wallet who owns the NFT of `dungeon`, when an RPC URL was given
+#[cfg(feature = "chia-rpc")]
+fn fetch_ownership_proof(dungeon: &Dungeon, rpc: &RpcArgs) -> Result<Option<OwnershipProof>, String> {
+    use chia_dungeon::OwnershipError;
+
+    let Some(client) = rpc_client(rpc)? else {
+        return Ok(None);
+    };
+    let launcher_id = dungeon.launcher_id.as_deref().ok_or_else(|| OwnershipError::MissingLauncherId.to_string())?;
+    block_on(client.ownership_proof(launcher_id))?
+        .map(Some)
+        .map_err(|err| format!("Error fetching NFT owner: {}", err))
+}
+
+#[cfg(not(feature = "chia-rpc"))]
+fn fetch_ownership_proof(_dungeon: &Dungeon, _rpc: &RpcArgs) -> Result<Option<OwnershipProof>, String> {
+    Ok(None)
+}
+
+// Refuse to open the dungeon unless the wallet reports `owner` holding its NFT
+fn check_ownership(dungeon: &Dungeon, owner: &str, rpc: &RpcArgs) -> Result<(), String> {
+    let owner = Owner::parse(owner).map_err(|err| err.to_string())?;
+    let proof = fetch_ownership_proof(dungeon, rpc)?.ok_or("Checking ownership needs a wallet --rpc-url (`chia-rpc` feature)")?;
+    verify_ownership(dungeon, &owner, &proof).map_err(|err| format!("Access denied: {}", err))
+}
+
+fn print_stats(stats: &DungeonStats) {
+    let rows = [
+        ("Rooms", stats.rooms.to_string()),
+        ("Floor area", stats.floor_area.to_string()),
+        ("Room area", stats.room_area.to_string()),
+        ("Corridor area", stats.corridor_area.to_string()),
+        ("Corridor/room ratio", format!("{:.2}", stats.corridor_to_room_ratio)),
+        ("Average room size", format!("{:.1}", stats.average_room_size)),
+        ("Average degree", format!("{:.2}", stats.average_degree)),
+        ("Max degree", stats.max_degree.to_string()),
+        ("Bounding box area", stats.bounding_box_area.to_string()),
+        ("Density", format!("{:.3}", stats.density)),
+        ("Longest path", stats.longest_path.to_string()),
+    ];
+    for (metric, value) in rows {
+        println!("{:<20} {:>10}", metric, value);
+    }
+}
+
+fn print_analysis(analysis: &NftAnalysis) {
+    let frequencies = |counts: &BTreeMap<char, usize>| {
+        counts.iter().map(|(c, count)| format!("{}:{}", c, count)).collect::<Vec<_>>().join(" ")
+    };
+    let traits = &analysis.traits;
+    let rows = [
+        ("Type", traits.dungeon_type.clone()),
+        ("Biome", traits.biome.to_string()),
+        ("Most frequent", analysis.most_frequent_char.clone()),
+        ("Rooms", traits.rooms.to_string()),
+        ("Area", traits.area.to_string()),
+        ("Level", traits.level.to_string()),
+        ("Chia NFT ID", traits.chia_id.to_string()),
+        ("Entropy", format!("{:.3}", analysis.entropy)),
+        ("Digit ratio", format!("{:.3}", analysis.digit_ratio)),
+        ("Letter ratio", format!("{:.3}", analysis.letter_ratio)),
+        ("Upper-case ratio", format!("{:.3}", analysis.uppercase_ratio)),
+    ];
+    for (metric, value) in rows {
+        println!("{:<20} {:>10}", metric, value);
+    }
+    println!("Letters: {}", frequencies(&analysis.letter_frequency));
+    println!("Digits: {}", frequencies(&analysis.digit_frequency));
+}
+
+fn print_dungeon(dungeon: &Dungeon, catalog: &Catalog) {
+    println!("Parsed NFT ID: {}", dungeon.nft_id);
+    if let Some(launcher_id) = &dungeon.launcher_id {
+        println!("Launcher ID: {}", launcher_id);
+    }
+    println!("Type: {}", catalog.dungeon_type(&dungeon.dungeon_type));
+    println!("Biome: {}", catalog.biome(&dungeon.biome));
+    println!("Level: {}", dungeon.level);
+    println!("Lore: {}", dungeon.lore_in(catalog));
+    println!("Rooms: {}", dungeon.rooms.len());
+    for (i, room) in dungeon.rooms.iter().enumerate() {
+        println!("  {:>2}. {} ({}) at {:?}, size {}, shape '{}'", i + 1, room.name, catalog.room_kind(room.kind), room.center, room.size, room.shape);
+    }
+    println!("Fully connected: {}", dungeon.is_fully_connected());
+    if let (Some(entrance), Some(exit)) = (dungeon.entrance(), dungeon.exit()) {
+        println!("Entrance: {:?}, Exit: {:?}", entrance, exit);
+    }
+    println!("Solvable: {}", dungeon.is_solvable());
+    let rarity = dungeon.rarity(&RarityWeights::default());
+    println!("Rarity: {:.1} ({})", rarity.score, rarity.tier);
+    println!("Area: {}", dungeon.area_size);
+    println!("Bounds: x {:?}, y {:?}", dungeon.bounds.x_range, dungeon.bounds.y_range);
+    println!("Excavated tiles: {}", dungeon.excavated.len());
+    println!("Monsters: {}", dungeon.monsters.len());
+    println!("Traps: {}", dungeon.traps().len());
+    println!("Decorations: {}", dungeon.decorations.len());
+    if let Some(boss) = dungeon.boss() {
+        println!(
+            "Boss: {} ({} level {}, {} HP, attack {}, defense {}, {}) in room {} at {:?}",
+            boss.name,
+            boss.element,
+            boss.level,
+            boss.hp,
+            boss.attack,
+            boss.defense,
+            boss.ability,
+            boss.room + 1,
+            boss.position
+        );
+    }
+    println!("Floors: {}", dungeon.floor_count());
+    for floor in &dungeon.floors {
+        println!(
+            "  {:>2}. level {}, {} rooms, {} monsters, stairs up at {:?}",
+            floor.depth,
+            floor.level,
+            floor.rooms.len(),
+            floor.monsters.len(),
+            floor.stairs_up
+        );
+    }
+    let quests = dungeon.quest_log();
+    println!("NPCs: {}", quests.npcs.len());
+    for npc in &quests.npcs {
+        println!("  {} ({:?}) in room {} at {:?}", npc.name, npc.role, npc.room + 1, npc.position);
+    }
+    println!("Quests: {}", quests.quests.len());
+    for (i, quest) in quests.quests.iter().enumerate() {
+        let after: Vec<String> = quest.requires.iter().map(|q| (q + 1).to_string()).collect();
+        println!(
+            "  {:>2}. {}{}{}",
+            i + 1,
+            quest.title,
+            if quest.main { "" } else { " (side quest)" },
+            if after.is_empty() { String::new() } else { format!(", after {}", after.join(", ")) }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use clap::CommandFactory;
+    use super::*;
+
+    #[test]
+    fn the_cli_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn subcommands_parse_their_arguments() {
+        let cli = Cli::parse_from(["chia_dungeon", "generate"]);
+        assert!(matches!(cli.command, Command::Generate { dungeon_type: None, seed: None, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "generate", "--type", "Cave", "--min-level", "3"]);
+        assert!(matches!(cli.command, Command::Generate { dungeon_type: Some(t), min_level: Some(3), .. } if t == "Cave"));
+        let cli = Cli::parse_from(["chia_dungeon", "parse", "nft1abc"]);
+        assert!(matches!(cli.command, Command::Parse { format: OutputFormatArg::Text, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "parse", "nft1abc", "--format", "yaml"]);
+        assert!(matches!(cli.command, Command::Parse { format: OutputFormatArg::Yaml, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "fuse", "nft1abc", "nft1def", "--strategy", "overlay"]);
+        assert!(matches!(cli.command, Command::Fuse { strategy: StrategyArg::Overlay, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "arena", "nft1abc", "nft1def", "--labels"]);
+        assert!(matches!(cli.command, Command::Arena { labels: true, out, .. } if out == Path::new("arena_map.png")));
+        let cli = Cli::parse_from(["chia_dungeon", "campaign", "nft1abc", "nft1def", "--out-dir", "run"]);
+        assert!(matches!(cli.command, Command::Campaign { nft_ids, out_dir, .. } if nft_ids.len() == 2 && out_dir == Path::new("run")));
+        assert!(Cli::try_parse_from(["chia_dungeon", "campaign"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "analyze", "nft1abc", "--json"]);
+        assert!(matches!(cli.command, Command::Analyze { json: true, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "graph", "nft1abc", "--format", "graphml"]);
+        assert!(matches!(cli.command, Command::Graph { format: Some(GraphFormatArg::GraphMl), .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "rexpaint", "nft1abc", "--floor", "1"]);
+        assert!(matches!(cli.command, Command::Rexpaint { floor: 1, out, .. } if out == Path::new("dungeon.xp")));
+        let cli = Cli::parse_from(["chia_dungeon", "ascii", "nft1abc", "--style", "box", "--out", "map.txt"]);
+        assert!(matches!(cli.command, Command::Ascii { style: AsciiStyleArg::Box, out: Some(out), floor: 0, .. } if out == Path::new("map.txt")));
+        assert!(matches!(Cli::parse_from(["chia_dungeon", "ascii", "nft1abc"]).command, Command::Ascii { style: AsciiStyleArg::Plain, out: None, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "stats", "nft1abc", "--balance", "deep.toml"]);
+        assert!(matches!(cli.command, Command::Stats { .. }) && cli.balance.as_deref() == Some(Path::new("deep.toml")));
+        let cli = Cli::parse_from(["chia_dungeon", "fingerprint", "nft1abc", "nft1def", "--threshold", "0.8"]);
+        assert!(matches!(cli.command, Command::Fingerprint { nft_ids, threshold } if nft_ids.len() == 2 && threshold == 0.8));
+        assert!(Cli::try_parse_from(["chia_dungeon", "fingerprint"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "explore", "nft1abc", "--record", "run.json"]);
+        assert!(matches!(cli.command, Command::Explore { record: Some(path), .. } if path == Path::new("run.json")));
+        let cli = Cli::parse_from(["chia_dungeon", "replay", "run.json", "--gif", "run.gif"]);
+        assert!(matches!(cli.command, Command::Replay { gif: Some(out), delay: DEFAULT_FRAME_DELAY, .. } if out == Path::new("run.gif")));
+        let cli = Cli::parse_from(["chia_dungeon", "shapes", "preview", "--sizes", "2,5", "--style", "scaled"]);
+        assert!(matches!(cli.command, Command::Shapes { command: ShapesCommand::Preview { sizes, style: ShapeStyleArg::Scaled, .. } } if sizes == [2, 5]));
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--tunnels", "drunkard"]);
+        assert!(matches!(cli.tunnels, TunnelStyleArg::Drunkard));
+        assert!(matches!(cli.algorithm, AlgorithmArg::Rooms));
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--algorithm", "noise"]);
+        assert!(matches!(cli.algorithm, AlgorithmArg::Noise));
+        let cli = Cli::parse_from(["chia_dungeon", "parse", "nft1abc", "--algorithm", "maze"]);
+        assert!(matches!(cli.algorithm, AlgorithmArg::Maze));
+        let cli = Cli::parse_from(["chia_dungeon", "parse", "nft1abc", "--collection-salt", "my-collection"]);
+        assert_eq!(cli.collection_salt.as_deref(), Some("my-collection"));
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--height", "9216"]);
+        assert_eq!((cli.epoch, cli.height.map(epoch_for_height)), (None, Some(2)));
+        assert!(Cli::try_parse_from(["chia_dungeon", "parse", "nft1abc", "--epoch", "3", "--height", "9216"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "batch", "ids.txt", "-v"]);
+        assert!(matches!(cli.command, Command::Batch { .. }) && cli.verbose);
+        let cli = Cli::parse_from(["chia_dungeon", "rerender", "cache", "--format", "svg"]);
+        assert!(matches!(cli.command, Command::Rerender { dir, format: FormatArg::Svg, theme: None } if dir == Path::new("cache")));
+        let cli = Cli::parse_from(["chia_dungeon", "explore", "nft1abc", "--continue"]);
+        assert!(matches!(cli.command, Command::Explore { save: None, resume: true, .. }));
+        assert!(Cli::try_parse_from(["chia_dungeon", "explore", "nft1abc", "--continue", "--record", "run.json"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--regions"]);
+        assert!(matches!(cli.command, Command::Plot { regions: true, labels: false, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--badges", "--qr", "--qr-url", "https://example.com/nft"]);
+        assert!(matches!(cli.command, Command::Plot { overlays: OverlayArgs { badges: true, nft_label: false, qr: true, qr_url: Some(url), heatmap: false, .. }, .. } if url == "https://example.com/nft"));
+        assert!(Cli::try_parse_from(["chia_dungeon", "plot", "nft1abc", "--qr-url", "https://example.com/nft"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--heatmap", "--format", "svg"]);
+        assert!(matches!(cli.command, Command::Plot { overlays: OverlayArgs { heatmap: true, .. }, regions: false, .. }));
+        assert!(Cli::try_parse_from(["chia_dungeon", "plot", "nft1abc", "--heatmap", "--regions"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "explore", "nft1abc", "--owner", "xch1abc"]);
+        assert!(matches!(cli.command, Command::Explore { owner: Some(owner), .. } if owner == "xch1abc"));
+        assert!(Cli::try_parse_from(["chia_dungeon", "explore", "nft1abc", "--proof", "proof.json"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "parse", "nft1abc", "--log-format", "json"]);
+        assert!(matches!(cli.log_format, LogFormatArg::Json));
+        let cli = Cli::parse_from(["chia_dungeon", "view", "nft1abc", "--floor", "1"]);
+        assert!(matches!(cli.command, Command::View { floor: 1, .. }));
+        let cli = Cli::parse_from(["chia_dungeon", "lint", "nft1abc"]);
+        assert!(matches!(cli.command, Command::Lint { nft_id } if nft_id == "nft1abc"));
+        let cli = Cli::parse_from(["chia_dungeon", "parse", "nft1abc", "--lang", "es", "--catalog", "words.toml"]);
+        assert!(cli.lang == "es" && cli.catalog.as_deref() == Some(Path::new("words.toml")));
+        assert!(catalog("xx", None).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "audio", "nft1abc", "--audio-table", "sounds.toml"]);
+        assert!(matches!(cli.command, Command::Audio { out, audio_table: Some(table), .. } if out == Path::new("audio.json") && table == Path::new("sounds.toml")));
+        let cli = Cli::parse_from(["chia_dungeon", "parse", "nft1abc"]);
+        assert!(matches!(cli.command, Command::Parse { nft_id, .. } if nft_id == "nft1abc"));
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--out", "map.svg"]);
+        assert!(matches!(cli.command, Command::Plot { out, .. } if out == Path::new("map.svg")));
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--center", "-3,7", "--zoom", "2.5", "--size", "800x600"]);
+        assert!(matches!(cli.command, Command::Plot { center: Some((-3, 7)), size: (800, 600), zoom, .. } if zoom == 2.5));
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--size", "2048x2048", "--dpi", "300", "--fit"]);
+        assert!(matches!(cli.command, Command::Plot { size: (2048, 2048), dpi: 300, margin: 10, fit: true, .. }));
+        assert!(Cli::try_parse_from(["chia_dungeon", "plot", "nft1abc", "--size", "800"]).is_err());
+        let cli = Cli::parse_from(["chia_dungeon", "plot", "nft1abc", "--room", "3"]);
+        assert!(matches!(cli.command, Command::Plot { room: Some(3), stubs: DEFAULT_STUB_LENGTH, .. }));
+        assert!(Cli::try_parse_from(["chia_dungeon", "plot", "nft1abc", "--stubs", "2"]).is_err());
+        assert!(Cli::try_parse_from(["chia_dungeon", "plot", "nft1abc", "--room", "3", "--regions"]).is_err());
+    }
+
+    #[test]
+    fn a_subcommand_is_required() {
+        assert!(Cli::try_parse_from(["chia_dungeon"]).is_err());
+        assert!(Cli::try_parse_from(["chia_dungeon", "parse"]).is_err());
+    }
+}
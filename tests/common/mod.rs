@@ -0,0 +1,23 @@
+//! Checks shared by the integration tests.
+
+// Every test crate compiles this module but uses only some of its checks
+#![allow(dead_code)]
+
+use chia_dungeon::{Dungeon, GenerationConfig};
+
+// Room count stays within the configured range, and room centers and excavated tiles
+// within the bounds
+pub fn assert_invariants(dungeon: &Dungeon, config: &GenerationConfig) {
+    assert!((config.min_rooms..=config.max_rooms).contains(&dungeon.rooms.len()));
+    let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+    for room in &dungeon.rooms {
+        let (x, y) = room.center;
+        assert!(x > x_range.0 && x < x_range.1, "room center {:?} outside {:?}", room.center, x_range);
+        assert!(y > y_range.0 && y < y_range.1, "room center {:?} outside {:?}", room.center, y_range);
+    }
+    for &(x, y) in &dungeon.excavated {
+        assert!(dungeon.grid.contains((x, y)), "excavated {:?} outside the grid", (x, y));
+        assert!(x > x_range.0 && x < x_range.1 && y > y_range.0 && y < y_range.1, "excavated {:?} outside the bounds", (x, y));
+    }
+    assert!(dungeon.is_fully_connected());
+}
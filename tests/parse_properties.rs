@@ -0,0 +1,87 @@
+//! Property tests for NFT ID parsing: arbitrary input never panics, and every parsed
+//! dungeon satisfies the layout invariants.
+//!
+//! Run with `cargo test --test parse_properties`. Set `PROPTEST_CASES` for a longer run of
+//! the malformed-input properties, or fuzz the parser with `cargo fuzz run parse_nft_id`
+//! (see `fuzz/`).
+
+mod common;
+
+use proptest::prelude::*;
+use chia_dungeon::{parse_nft_id, parse_nft_id_with_config, DungeonError, GenerationAlgorithm, GenerationConfig};
+use common::assert_invariants;
+
+// Malformed IDs are rejected before excavation, so these run the default case count
+proptest! {
+    #[test]
+    fn arbitrary_strings_never_panic(nft_id in "\\PC{0,120}") {
+        let _ = parse_nft_id(&nft_id);
+    }
+
+    #[test]
+    fn prefixed_strings_never_panic(rest in "\\PC{0,80}") {
+        if let Err(err) = parse_nft_id(&format!("nft1{}", rest)) {
+            prop_assert!(matches!(err, DungeonError::InvalidChar { .. } | DungeonError::InvalidRoomChar(_) | DungeonError::TooShort { .. }), "{}", err);
+        }
+    }
+}
+
+// Every valid ID is excavated in full, so these run fewer cases
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn base62_ids_satisfy_invariants(rest in "[0-9a-zA-Z]{2,80}") {
+        let nft_id = format!("nft1{}", rest);
+        let config = GenerationConfig::default();
+        // Every ID of at least `MIN_NFT_ID_LEN` characters decodes, wrapping around short ones
+        let dungeon = parse_nft_id(&nft_id).map_err(|err| TestCaseError::fail(format!("{}: {}", nft_id, err)))?;
+        assert_invariants(&dungeon, &config);
+        prop_assert_eq!(parse_nft_id(&nft_id).ok(), Some(dungeon));
+    }
+
+    #[test]
+    fn entrances_lead_to_the_exit(rest in "[0-9a-zA-Z]{2,80}") {
+        if let Ok(dungeon) = parse_nft_id(&format!("nft1{}", rest)) {
+            prop_assert!(dungeon.is_solvable(), "no path from {:?} to {:?}", dungeon.entrance(), dungeon.exit());
+        }
+    }
+
+    #[test]
+    fn tuned_configs_never_panic(
+        rest in "[0-9a-zA-Z]{2,40}",
+        size_base in -50i32..50,
+        size_multiplier in 0.0f64..20.0,
+        min_rooms in 2usize..8,
+        extra_rooms in 0usize..40,
+        smoothing_passes in 0u32..8,
+    ) {
+        let config = GenerationConfig {
+            size_base,
+            size_multiplier,
+            min_rooms,
+            max_rooms: min_rooms + extra_rooms,
+            smoothing_passes,
+            ..GenerationConfig::default()
+        };
+        if let Ok(dungeon) = parse_nft_id_with_config(&format!("nft1{}", rest), &config) {
+            assert_invariants(&dungeon, &config);
+        }
+    }
+
+    #[test]
+    fn noise_caves_satisfy_invariants(rest in "[0-9a-zA-Z]{2,80}") {
+        let config = GenerationConfig { algorithm: GenerationAlgorithm::Noise, ..GenerationConfig::default() };
+        let dungeon = parse_nft_id_with_config(&format!("nft1{}", rest), &config).unwrap();
+        assert_invariants(&dungeon, &config);
+        prop_assert!(dungeon.is_solvable());
+    }
+
+    #[test]
+    fn maze_dungeons_satisfy_invariants(rest in "[0-9a-zA-Z]{2,80}") {
+        let config = GenerationConfig { algorithm: GenerationAlgorithm::Maze, ..GenerationConfig::default() };
+        let dungeon = parse_nft_id_with_config(&format!("nft1{}", rest), &config).unwrap();
+        assert_invariants(&dungeon, &config);
+        prop_assert!(dungeon.is_fully_connected() && dungeon.is_solvable());
+    }
+}
@@ -0,0 +1,196 @@
+//! # NFT ID Analysis
+//!
+//! Character statistics of an `nft_id` and the dungeon traits they decode to, without
+//! excavating the dungeon (see the `analyze` CLI command).
+//!
+//! - **Frequencies**: How often each letter and each digit occurs. Letters are counted
+//!   over the whole ID, "nft1" prefix included, exactly as the dungeon type is chosen;
+//!   the most frequent letter, ties going to the earliest, names the type.
+//! - **Entropy**: Shannon entropy in bits per character of the characters after "nft1",
+//!   at most `MAX_ENTROPY` for IDs spreading evenly over all 62 base62 digits.
+//! - **Ratios**: Shares of digits, letters, and upper-case letters after "nft1".
+//!   Bech32m Chia IDs never contain upper-case letters.
+//! - **Traits**: The dungeon type, room count, room area, and level decoded with a
+//!   `GenerationConfig`, and whether the ID is a checksummed Chia NFT ID. With a
+//!   collection salt, the statistics and traits are those of the salted copy the dungeon
+//!   decodes (see `seed::salt_nft_id`).
+//!
+//! `shannon_entropy` is shared with the rarity scorer's shape rating (see `rarity`).
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use super::biome::{classify, DungeonType};
+use super::chia;
+use super::config::GenerationConfig;
+use super::error::DungeonError;
+use super::excavator::{decode_room_count, decode_room_sizes, get_dungeon_level, get_dungeon_type, nft_id_chars};
+use super::seed::salt_nft_id;
+
+/// Highest entropy in bits per character, of IDs using all 62 base62 digits evenly
+pub const MAX_ENTROPY: f64 = 5.954_196_310_386_876;
+
+/// Character statistics of an NFT ID and the traits it decodes to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NftAnalysis {
+    /// The analyzed ID.
+    pub nft_id: String,
+    /// Count of each letter a-z and A-Z in the whole ID.
+    pub letter_frequency: BTreeMap<char, usize>,
+    /// Count of each digit after "nft1".
+    pub digit_frequency: BTreeMap<char, usize>,
+    /// The letter naming the dungeon type, or "None" for IDs without letters.
+    pub most_frequent_char: String,
+    /// Shannon entropy of the characters after "nft1", in bits per character.
+    pub entropy: f64,
+    /// Share of digits among the characters after "nft1", 0 to 1.
+    pub digit_ratio: f64,
+    /// Share of letters among the characters after "nft1", 0 to 1.
+    pub letter_ratio: f64,
+    /// Share of upper-case letters among the characters after "nft1", 0 to 1.
+    pub uppercase_ratio: f64,
+    /// Dungeon traits decoded from the ID.
+    pub traits: NftTraits,
+}
+
+/// Dungeon traits decoded from an NFT ID with a `GenerationConfig`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftTraits {
+    /// Dungeon type named by the most frequent letter.
+    pub dungeon_type: String,
+    /// Dungeon type with its sub-biome modifier.
+    pub biome: DungeonType,
+    /// Number of rooms on the top floor.
+    pub rooms: usize,
+    /// Total room area.
+    pub area: u64,
+    /// Dungeon level.
+    pub level: u64,
+    /// Whether the ID is a checksummed bech32m Chia NFT ID.
+    pub chia_id: bool,
+}
+
+/// Analyze the characters of `nft_id` as decoded with `config`, failing like
+/// `parse_nft_id_with_config` on malformed IDs
+pub fn analyze_nft(nft_id: &str, config: &GenerationConfig) -> Result<NftAnalysis, DungeonError> {
+    let seed_id = salt_nft_id(nft_id, config.collection_salt.as_deref());
+    let chars = nft_id_chars(&seed_id)?;
+    let body = &chars[4..];
+
+    let (letters, most_frequent_char) = letter_frequency(&seed_id);
+    let mut digit_frequency = BTreeMap::new();
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for &c in body {
+        *counts.entry(c).or_insert(0) += 1;
+        if c.is_ascii_digit() {
+            *digit_frequency.entry(c).or_insert(0) += 1;
+        }
+    }
+    let share = |matches: fn(&char) -> bool| body.iter().filter(|c| matches(c)).count() as f64 / body.len() as f64;
+
+    let rooms = decode_room_count(chars[4], config);
+    let (_, area) = decode_room_sizes(&chars, rooms, config);
+
+    Ok(NftAnalysis {
+        nft_id: nft_id.to_string(),
+        letter_frequency: letters.into_iter().collect(),
+        digit_frequency,
+        entropy: shannon_entropy(counts.into_values()),
+        digit_ratio: share(char::is_ascii_digit),
+        letter_ratio: share(char::is_ascii_alphabetic),
+        uppercase_ratio: share(char::is_ascii_uppercase),
+        traits: NftTraits {
+            dungeon_type: get_dungeon_type(&most_frequent_char),
+            biome: classify(&seed_id),
+            rooms,
+            area,
+            level: get_dungeon_level(area, config.level_band),
+            chia_id: chia::decode_nft_id(nft_id).is_ok(),
+        },
+        most_frequent_char,
+    })
+}
+
+/// Frequency of each letter a-z and A-Z in the `nft_id`, and the most frequent one
+pub fn letter_frequency(nft_id: &str) -> (HashMap<char, usize>, String) {
+    // Calculate frequency of each letter a-z and A-Z
+    let mut char_frequency: HashMap<char, usize> = HashMap::new();
+    for c in nft_id.chars() {
+        if c.is_ascii_alphabetic() {
+            *char_frequency.entry(c).or_insert(0) += 1;
+        }
+    }
+
+    // Find the first character with the highest frequency, breaking ties by
+    // position in the ID so the result does not depend on HashMap ordering
+    let mut most_frequent: Option<(char, usize)> = None;
+    for c in nft_id.chars().filter(|c| c.is_ascii_alphabetic()) {
+        let count = char_frequency[&c];
+        if most_frequent.is_none_or(|(_, best)| count > best) {
+            most_frequent = Some((c, count));
+        }
+    }
+    let most_frequent_char = most_frequent
+        .map(|(c, _)| c.to_string())
+        .unwrap_or("None".to_string());
+
+    (char_frequency, most_frequent_char)
+}
+
+/// Shannon entropy in bits of a distribution given by its counts; 0 when empty
+pub fn shannon_entropy(counts: impl IntoIterator<Item = usize>) -> f64 {
+    let counts: Vec<usize> = counts.into_iter().filter(|&count| count > 0).collect();
+    let total: usize = counts.iter().sum();
+    counts
+        .iter()
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::{parse_nft_id, parse_nft_id_with_config};
+    use super::super::fixtures::NFT_ID;
+    use super::*;
+
+    #[test]
+    fn traits_match_the_excavated_dungeon() {
+        let analysis = analyze_nft(NFT_ID, &GenerationConfig::default()).unwrap();
+        let dungeon = parse_nft_id(NFT_ID).unwrap();
+        assert_eq!(analysis.traits.dungeon_type, dungeon.dungeon_type);
+        assert_eq!(analysis.traits.rooms, dungeon.rooms.len());
+        assert_eq!((analysis.traits.area, analysis.traits.level), (dungeon.area_size, dungeon.level));
+        assert_eq!(analysis.most_frequent_char, dungeon.most_frequent_char);
+        assert_eq!(analysis.letter_frequency, dungeon.char_frequency.into_iter().collect());
+        assert!(!analysis.traits.chia_id && analysis.uppercase_ratio > 0.0);
+        assert!((analysis.digit_ratio + analysis.letter_ratio - 1.0).abs() < 1e-9);
+        assert!(analysis.entropy > 0.0 && analysis.entropy <= MAX_ENTROPY);
+
+        let chia = analyze_nft("nft1qgqarlcwfjj7ct7kvh0zt067am2mgewp4y7a2nzfx8d9x8mudmes4u8mnv", &GenerationConfig::default()).unwrap();
+        assert!(chia.traits.chia_id);
+        assert_eq!(chia.uppercase_ratio, 0.0);
+
+        // Salted and banded configs decode other traits, matching their dungeons
+        let config = GenerationConfig { collection_salt: Some("other".to_string()), level_band: 500, ..GenerationConfig::default() };
+        let analysis = analyze_nft(NFT_ID, &config).unwrap();
+        let dungeon = parse_nft_id_with_config(NFT_ID, &config).unwrap();
+        assert_eq!((analysis.nft_id.as_str(), analysis.traits.dungeon_type.as_str()), (NFT_ID, dungeon.dungeon_type.as_str()));
+        assert_eq!((analysis.traits.rooms, analysis.traits.area, analysis.traits.level), (dungeon.rooms.len(), dungeon.area_size, dungeon.level));
+        assert_eq!(analysis.traits.biome, dungeon.biome);
+    }
+
+    #[test]
+    fn entropy_measures_how_evenly_characters_spread() {
+        assert_eq!(shannon_entropy([4]), 0.0);
+        assert_eq!(shannon_entropy([1, 1, 1, 1]), 2.0);
+        assert_eq!(shannon_entropy([]), 0.0);
+        assert!((shannon_entropy([1; 62]) - MAX_ENTROPY).abs() < 1e-12);
+
+        let repetitive = analyze_nft("nft1aaaaaaaa", &GenerationConfig::default()).unwrap();
+        assert_eq!((repetitive.entropy, repetitive.most_frequent_char.as_str()), (0.0, "a"));
+        assert_eq!(repetitive.digit_frequency, BTreeMap::new());
+        assert_eq!(analyze_nft("xch1abcdef", &GenerationConfig::default()).err(), Some(DungeonError::InvalidPrefix));
+    }
+}
@@ -0,0 +1,124 @@
+//! # Build Animation
+//!
+//! Renders the dungeon being dug step by step into an animated GIF, for NFT reveals.
+//!
+//! `build_steps` splits the excavated tiles into the order they are dug:
+//! - **Rooms**: One step per room, in the order the rooms were decoded.
+//! - **Tunnels**: One step per tunnel, with the full corridor width.
+//! - **Rubble**: A last step with the remaining excavated tiles (scattered points, stairs,
+//!   and terrain features).
+//!
+//! `render_build_gif` draws one frame per step with `plot::draw_map_frame`, each showing
+//! every tile dug so far, then holds the finished map for a few more frames.
+
+use std::collections::HashSet;
+use std::path::Path;
+use plotters::prelude::*;
+use super::dungeon::Dungeon;
+use super::excavator::corridor_brush;
+use super::fog::Visibility;
+use super::grid::Tile;
+use super::plot::draw_map_frame;
+use super::theme::Theme;
+
+/// Delay between frames when none is given, in milliseconds
+pub const DEFAULT_FRAME_DELAY: u32 = 150;
+
+/// Timing of the build animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationOptions {
+    /// Delay between frames, in milliseconds.
+    pub frame_delay: u32,
+    /// Extra frames showing the finished map before the animation loops.
+    pub hold_frames: usize,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> AnimationOptions {
+        AnimationOptions {
+            frame_delay: DEFAULT_FRAME_DELAY,
+            hold_frames: 10,
+        }
+    }
+}
+
+/// Excavated tiles newly dug at each build step: rooms, then tunnels, then the rest
+pub fn build_steps(dungeon: &Dungeon) -> Vec<Vec<(i32, i32)>> {
+    let mut dug = HashSet::new();
+    let mut steps = Vec::new();
+    let is_excavated = |pos: (i32, i32)| dungeon.grid.get(pos).is_some_and(|&tile| tile != Tile::Wall);
+    let mut dig = |tiles: &mut dyn Iterator<Item = (i32, i32)>| {
+        let step: Vec<(i32, i32)> = tiles.filter(|&pos| is_excavated(pos) && dug.insert(pos)).collect();
+        if !step.is_empty() {
+            steps.push(step);
+        }
+    };
+
+    for room in &dungeon.rooms {
+        dig(&mut room.tiles.iter().copied());
+    }
+    for tunnel in &dungeon.tunnels {
+        dig(&mut tunnel.iter().flat_map(|&pos| corridor_brush(pos, dungeon.corridor_width)));
+    }
+    dig(&mut dungeon.tiles().map(|(pos, _)| pos));
+
+    steps
+}
+
+/// Render the dungeon being dug as an animated GIF, saving it to `out_path`
+pub fn render_build_gif(dungeon: &Dungeon, out_path: &Path, options: &AnimationOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let root = BitMapBackend::gif(out_path, (640, 480), options.frame_delay)?.into_drawing_area();
+    let theme = Theme::for_dungeon(dungeon);
+
+    let mut dug = HashSet::new();
+    for step in build_steps(dungeon) {
+        dug.extend(step);
+        draw_map_frame(&root, dungeon, &theme, &|pos| visibility(&dug, pos))?;
+    }
+    for _ in 0..options.hold_frames {
+        draw_map_frame(&root, dungeon, &theme, &|pos| visibility(&dug, pos))?;
+    }
+
+    Ok(())
+}
+
+// Tiles dug so far are visible; the rest are not drawn yet
+fn visibility(dug: &HashSet<(i32, i32)>, pos: (i32, i32)) -> Visibility {
+    if dug.contains(&pos) {
+        Visibility::Visible
+    } else {
+        Visibility::Unexplored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn steps_dig_every_excavated_tile_once() {
+        let dungeon = dungeon();
+        let steps = build_steps(&dungeon);
+        let dug: Vec<(i32, i32)> = steps.iter().flatten().copied().collect();
+        let unique: HashSet<(i32, i32)> = dug.iter().copied().collect();
+        assert_eq!(unique.len(), dug.len());
+        assert_eq!(unique.len(), dungeon.tiles().filter(|&(_, tile)| tile != Tile::Wall).count());
+        // Rooms come first, starting with the entrance
+        assert!(steps[0].contains(&dungeon.rooms[0].center));
+        assert!(steps.len() <= dungeon.rooms.len() + dungeon.tunnels.len() + 1);
+    }
+
+    #[test]
+    fn builds_render_as_animated_gifs() {
+        let path = env::temp_dir().join(format!("chia_dungeon_build_{}.gif", std::process::id()));
+        let options = AnimationOptions { frame_delay: 50, hold_frames: 1 };
+        render_build_gif(&dungeon(), &path, &options).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+        // Logical screen of 640x480, little-endian
+        assert_eq!(&bytes[6..10], &[128, 2, 224, 1]);
+    }
+}
@@ -0,0 +1,212 @@
+//! # Arena Mode
+//!
+//! Builds a player-versus-player arena from the dungeons of two NFT IDs.
+//!
+//! - **Layout**: Both dungeons share one coordinate space, split by a vertical center
+//!   line. The first dungeon keeps its coordinates on the left; the second is mirrored
+//!   onto the right, bottom edges aligned, so each player looks across at the other's
+//!   layout flipped. At least `ARENA_GAP` tiles of rock separate each half from the line.
+//! - **Contested corridor**: A tunnel of the wider corridor width joins the exit rooms
+//!   of both halves across the center line, the only way from one half to the other.
+//! - **Spawns**: Player one starts on the entrance tile of the first dungeon, player two
+//!   on the mirrored entrance tile of the second (see `Dungeon::entrance`).
+//! - **Attributes**: The arena's `nft_id` joins both IDs with "-vs-"; rooms keep their kinds,
+//!   so the arena holds two entrances and two exits. The letter frequencies and dungeon
+//!   type are recomputed from both IDs, the level is the higher of the two, and the
+//!   elevation is raised anew over the arena grid (see `elevation`).
+//!
+//! Only the top floors take part: lower floors are dropped and stairs down become floor.
+
+use serde::{Deserialize, Serialize};
+use super::analysis::letter_frequency;
+use super::biome::classify;
+use super::config::GenerationConfig;
+use super::decorate::Decoration;
+use super::dungeon::{Bounds, Dungeon, Room, RoomKind};
+use super::elevation::raise_terrain;
+use super::error::DungeonError;
+use super::excavator::{corridor_brush, create_tunnel, get_dungeon_type, parse_nft_id_with_config};
+use super::grid::{Grid, Tile};
+use super::naming::unique_names;
+use super::seed::{rng_for_stream, salt_nft_id};
+use super::spawn::MonsterSpawn;
+
+/// Tiles of rock left at least between each half of an arena and its center line
+pub const ARENA_GAP: i32 = 3;
+
+/// Two dungeons facing each other across a center line (see `arena`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arena {
+    /// Combined map of both halves and the contested corridor.
+    pub dungeon: Dungeon,
+    /// X coordinate of the line the second dungeon is mirrored across.
+    pub center_line: i32,
+    /// Path of the contested corridor, from the first exit room to the second.
+    pub corridor: Vec<(i32, i32)>,
+    /// Starting tiles of player one and player two.
+    pub spawns: [(i32, i32); 2],
+}
+
+impl Arena {
+    /// Parses both NFT IDs with `config` and builds their arena.
+    pub fn from_nft_ids(first: &str, second: &str, config: &GenerationConfig) -> Result<Arena, DungeonError> {
+        Ok(Arena::new(&parse_nft_id_with_config(first, config)?, &parse_nft_id_with_config(second, config)?))
+    }
+
+    /// Builds the arena of `first`, on the left, and `second`, mirrored onto the right.
+    pub fn new(first: &Dungeon, second: &Dungeon) -> Arena {
+        let (first_x, first_y) = (first.grid.x_range(), first.grid.y_range());
+        let (second_x, second_y) = (second.grid.x_range(), second.grid.y_range());
+        let (width, height) = (second_x.1 - second_x.0, second_y.1 - second_y.0);
+
+        // Far enough right that neither half comes closer than the gap to the line
+        let center_line = first_x.1.max(first_x.0 + width) + ARENA_GAP;
+        let mirror = |(x, y): (i32, i32)| (2 * center_line - (x - second_x.0 + first_x.0), y - second_y.0 + first_y.0);
+
+        let mut rooms = first.rooms.clone();
+        rooms.extend(second.rooms.iter().map(|room| Room {
+            center: mirror(room.center),
+            tiles: room.tiles.iter().map(|&pos| mirror(pos)).collect(),
+            ..room.clone()
+        }));
+        let names = unique_names(rooms.iter().map(|room| room.name.clone()));
+        for (room, name) in rooms.iter_mut().zip(names) {
+            room.name = name;
+        }
+
+        // Contested corridor between the exit rooms, falling back to the last rooms
+        let corridor_width = first.corridor_width.max(second.corridor_width);
+        let exit_room = |dungeon: &Dungeon| dungeon.rooms.iter().find(|room| room.kind == RoomKind::Exit).or(dungeon.rooms.last()).map(|room| room.center);
+        let corridor = match (exit_room(first), exit_room(second)) {
+            (Some(left), Some(right)) => create_tunnel(left, mirror(right)),
+            _ => Vec::new(),
+        };
+
+        // Grid covering both halves, stairs down becoming floor
+        let x_range = (first_x.0, 2 * center_line - first_x.0);
+        let y_range = (first_y.0, first_y.1.max(first_y.0 + height));
+        let mut grid = Grid::new(x_range, y_range, Tile::Wall);
+        let unstair = |tile: Tile| if tile == Tile::StairsDown { Tile::Floor } else { tile };
+        for (pos, &tile) in first.grid.iter().filter(|&(_, &tile)| tile != Tile::Wall) {
+            grid.set(pos, unstair(tile));
+        }
+        for (pos, &tile) in second.grid.iter().filter(|&(_, &tile)| tile != Tile::Wall) {
+            grid.set(mirror(pos), unstair(tile));
+        }
+        let corridor_tiles: Vec<(i32, i32)> = corridor.iter().flat_map(|&pos| corridor_brush(pos, corridor_width)).collect();
+        for &pos in &corridor_tiles {
+            if grid.get(pos).is_some_and(|current| !current.is_walkable()) {
+                grid.set(pos, Tile::Tunnel);
+            }
+        }
+        for room in &rooms {
+            grid.set(room.center, Tile::RoomCenter);
+        }
+
+        let mut tunnels = first.tunnels.clone();
+        tunnels.extend(second.tunnels.iter().map(|tunnel| tunnel.iter().map(|&pos| mirror(pos)).collect()));
+        tunnels.push(corridor.clone());
+
+        let mut excavated = first.excavated.clone();
+        excavated.extend(second.excavated.iter().map(|&pos| mirror(pos)));
+        excavated.extend(corridor_tiles.iter().copied().filter(|pos| grid.contains(*pos)));
+
+        let mut monsters = first.monsters.clone();
+        monsters.extend(second.monsters.iter().map(|monster| MonsterSpawn { position: mirror(monster.position), ..monster.clone() }));
+
+        let mut decorations = first.decorations.clone();
+        decorations.extend(second.decorations.iter().map(|decoration| Decoration { position: mirror(decoration.position), ..*decoration }));
+
+        let spawn = |dungeon: &Dungeon| dungeon.entrance().or(dungeon.rooms.first().map(|room| room.center));
+        let spawns = [
+            spawn(first).unwrap_or((first_x.0, first_y.0)),
+            spawn(second).map_or((x_range.1, first_y.0), mirror),
+        ];
+
+        let nft_id = format!("{}-vs-{}", first.nft_id, second.nft_id);
+        let seed_id = salt_nft_id(&nft_id, first.collection_salt.as_deref());
+        let (char_frequency, most_frequent_char) = letter_frequency(&seed_id);
+        let dungeon_type = get_dungeon_type(&most_frequent_char);
+        let elevation = raise_terrain(&grid, &dungeon_type, &mut rng_for_stream(&seed_id, "elevation"));
+
+        let mut dungeon = Dungeon {
+            launcher_id: None,
+            rooms,
+            tunnels,
+            corridor_width,
+            level: first.level.max(second.level),
+            dungeon_type,
+            biome: classify(&seed_id),
+            bounds: Bounds { x_range, y_range },
+            area_size: first.area_size.saturating_add(second.area_size),
+            char_frequency,
+            most_frequent_char,
+            excavated,
+            grid,
+            elevation,
+            monsters,
+            decorations,
+            stairs_down: None,
+            floors: Vec::new(),
+            provenance: None,
+            balance: first.balance.clone(),
+            collection_salt: first.collection_salt.clone(),
+            epoch: first.epoch,
+            nft_id,
+        };
+        dungeon.recompute_bounds();
+
+        Arena { dungeon, center_line, corridor, spawns }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::parse_nft_id;
+    use super::super::fixtures::dungeon;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    // A rival dungeon for the fixture
+    fn rival() -> Dungeon {
+        parse_nft_id(&generate_nft_id_with(&GenerateOptions { seed: Some(5), ..GenerateOptions::default() }).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn halves_mirror_each_other_across_the_center_line() {
+        let (first, second) = (dungeon(), rival());
+        let arena = Arena::new(&first, &second);
+        let (left, right) = arena.dungeon.rooms.split_at(first.rooms.len());
+        assert!(left.iter().flat_map(|room| &room.tiles).all(|&(x, _)| x <= arena.center_line - ARENA_GAP));
+        assert!(right.iter().flat_map(|room| &room.tiles).all(|&(x, _)| x >= arena.center_line + ARENA_GAP));
+
+        // Rooms further left in the second dungeon lie further right in the arena
+        let (a, b) = (&second.rooms[0], &second.rooms[1]);
+        assert_eq!(a.center.0 < b.center.0, right[0].center.0 > right[1].center.0);
+        assert_eq!(right[0].center.1 - right[1].center.1, a.center.1 - b.center.1);
+        assert_eq!(right.iter().map(|room| room.tiles.len()).collect::<Vec<_>>(), second.rooms.iter().map(|room| room.tiles.len()).collect::<Vec<_>>());
+        assert_eq!(arena.dungeon.monsters.len(), first.monsters.len() + second.monsters.len());
+        assert_eq!(arena.dungeon.nft_id, format!("{}-vs-{}", first.nft_id, second.nft_id));
+        assert_eq!(Arena::new(&first, &second), arena);
+    }
+
+    #[test]
+    fn the_contested_corridor_joins_both_spawns() {
+        let (first, second) = (dungeon(), rival());
+        let arena = Arena::new(&first, &second);
+        let [one, two] = arena.spawns;
+        assert_eq!(one, first.entrance().unwrap());
+        assert!(arena.dungeon.rooms[first.rooms.len()].tiles.contains(&two));
+        assert!(arena.dungeon.grid.is_walkable(one) && arena.dungeon.grid.is_walkable(two));
+        assert!(arena.dungeon.path(one, two).is_some());
+
+        // The corridor is the only way across the center line
+        let (start, end) = (arena.corridor[0], arena.corridor[arena.corridor.len() - 1]);
+        assert!(start.0 < arena.center_line && end.0 > arena.center_line);
+        let crossing: Vec<i32> = arena.dungeon.excavated_tiles().filter(|&((x, _), _)| x == arena.center_line).map(|((_, y), _)| y).collect();
+        assert!(!crossing.is_empty());
+        assert!(crossing.iter().all(|&y| arena.corridor.iter().any(|&(_, cy)| (cy - y).abs() <= arena.dungeon.corridor_width as i32)));
+        assert!(arena.dungeon.excavated.iter().all(|&pos| arena.dungeon.grid.contains(pos)));
+        assert!(Arena::from_nft_ids("nft1", &second.nft_id, &GenerationConfig::default()).is_err());
+    }
+}
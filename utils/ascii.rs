@@ -0,0 +1,348 @@
+//! # ASCII Map Rendering
+//!
+//! Renders excavated tiles as a text grid, one character per tile.
+//!
+//! Symbols:
+//! - `@`: Empty space.
+//! - `O`: Excavated room or tunnel point.
+//! - `E`, `X`, `T`, `B`, `S`, `R`: Center of an entrance, exit, treasure, boss, shrine,
+//!   or normal room.
+//! - `+`: Door where a tunnel enters a room.
+//! - `M`: Monster.
+//! - `!`: Trap.
+//! - `<`, `>`: Stairs up and down between floors.
+//! - `~`, `=`, `:`: Water, lava, and sand terrain features.
+//! - `"`: Shallow water flooding low ground (see `liquid`).
+//! - `-`, `;`, `'`, `` ` ``, `,`: Bones, mushrooms, crystals, cobwebs, and embers (see
+//!   `decorate`).
+//!
+//! With fog of war applied (`apply_fog`), unexplored tiles are blank and tiles
+//! seen earlier but out of view show excavated ground as `o`, hiding monsters.
+//!
+//! Rows are emitted from the highest y to the lowest, matching the orientation
+//! of the plotted map.
+//!
+//! `render_ascii_themed` swaps the empty and excavated glyphs for those of a `Theme`
+//! and colors every glyph with ANSI true-color escapes for terminal display.
+//!
+//! `render_ascii_box` draws the same map in Unicode for pretty terminal and text-file
+//! exports: excavated ground is `·`, rock away from it is blank and trimmed from line
+//! ends, and the rock bordering it becomes walls of box-drawing characters
+//! (`─│┌┐└┘├┤┬┴┼`). Each wall joins the walls beside it when the edge between them
+//! faces excavated ground, so rooms and tunnels are outlined without solid rock turning
+//! into a lattice. Markers keep their glyphs.
+
+use super::decorate::{decoration_color, DecorationKind};
+use super::dungeon::{Dungeon, RoomKind};
+use super::fog::{FogOfWar, Visibility};
+use super::grid::{Grid, Tile};
+use super::inventory::Item;
+use super::terrain::{LAVA_COLOR, SAND_COLOR, SHALLOW_WATER_COLOR, WATER_COLOR};
+use super::theme::{Rgb, Theme};
+
+/// Glyph for empty space
+pub const EMPTY_GLYPH: char = '@';
+/// Glyph for an excavated room or tunnel point
+pub const EXCAVATED_GLYPH: char = 'O';
+/// Glyph for a door where a tunnel enters a room
+pub const DOOR_GLYPH: char = '+';
+/// Glyph for a monster
+pub const MONSTER_GLYPH: char = 'M';
+/// Glyph for the dungeon's boss
+pub const BOSS_GLYPH: char = '&';
+/// Glyph for a trap
+pub const TRAP_GLYPH: char = '!';
+/// Glyph for stairs up to the floor above
+pub const STAIRS_UP_GLYPH: char = '<';
+/// Glyph for stairs down to the floor below
+pub const STAIRS_DOWN_GLYPH: char = '>';
+/// Glyph for water
+pub const WATER_GLYPH: char = '~';
+/// Glyph for lava
+pub const LAVA_GLYPH: char = '=';
+/// Glyph for sand
+pub const SAND_GLYPH: char = ':';
+/// Glyph for shallow water
+pub const SHALLOW_WATER_GLYPH: char = '"';
+/// Glyph for an unexplored tile under fog of war
+pub const FOG_GLYPH: char = ' ';
+/// Glyph for excavated ground seen earlier but not currently visible
+pub const SEEN_GLYPH: char = 'o';
+/// Glyph for a key
+pub const KEY_GLYPH: char = 'k';
+/// Glyph for a potion
+pub const POTION_GLYPH: char = '%';
+/// Glyph for a pile of gold
+pub const GOLD_GLYPH: char = '$';
+/// Glyph for bones
+pub const BONES_GLYPH: char = '-';
+/// Glyph for mushrooms
+pub const MUSHROOMS_GLYPH: char = ';';
+/// Glyph for crystals
+pub const CRYSTALS_GLYPH: char = '\'';
+/// Glyph for cobwebs
+pub const COBWEBS_GLYPH: char = '`';
+/// Glyph for embers
+pub const EMBERS_GLYPH: char = ',';
+/// Glyph for excavated ground in box-drawing maps
+pub const BOX_FLOOR_GLYPH: char = '·';
+/// Glyph for rock away from any excavated tile in box-drawing maps
+pub const BOX_ROCK_GLYPH: char = ' ';
+/// Glyph for a wall joining no other wall in box-drawing maps
+pub const BOX_PILLAR_GLYPH: char = '□';
+
+/// Glyph marking the center of a room of the given kind
+pub fn room_glyph(kind: RoomKind) -> char {
+    match kind {
+        RoomKind::Entrance => 'E',
+        RoomKind::Exit => 'X',
+        RoomKind::Treasure => 'T',
+        RoomKind::Boss => 'B',
+        RoomKind::Shrine => 'S',
+        RoomKind::Normal => 'R',
+    }
+}
+
+/// Glyph for an item lying in the dungeon
+pub fn item_glyph(item: Item) -> char {
+    match item {
+        Item::Key => KEY_GLYPH,
+        Item::Potion => POTION_GLYPH,
+        Item::Gold(_) => GOLD_GLYPH,
+    }
+}
+
+/// Glyph for a decoration object lying on the floor
+pub fn decoration_glyph(kind: DecorationKind) -> char {
+    match kind {
+        DecorationKind::Bones => BONES_GLYPH,
+        DecorationKind::Mushrooms => MUSHROOMS_GLYPH,
+        DecorationKind::Crystals => CRYSTALS_GLYPH,
+        DecorationKind::Cobwebs => COBWEBS_GLYPH,
+        DecorationKind::Embers => EMBERS_GLYPH,
+    }
+}
+
+/// Render the whole dungeon within its bounds
+pub fn render_ascii(dungeon: &Dungeon) -> String {
+    render_rows(dungeon).join("\n")
+}
+
+/// Render the whole dungeon in the glyphs and colors of `theme`, for ANSI terminals
+pub fn render_ascii_themed(dungeon: &Dungeon, theme: &Theme) -> String {
+    let background = format!("\x1b[48;2;{};{};{}m", theme.background.0, theme.background.1, theme.background.2);
+    let foreground = |(r, g, b): Rgb| format!("\x1b[38;2;{};{};{}m", r, g, b);
+    render_rows(dungeon)
+        .iter()
+        .map(|row| {
+            let mut line = background.clone();
+            for glyph in row.chars() {
+                let (color, glyph) = match glyph {
+                    EMPTY_GLYPH => (theme.wall, theme.wall_glyph),
+                    EXCAVATED_GLYPH => (theme.floor, theme.floor_glyph),
+                    MONSTER_GLYPH | BOSS_GLYPH | TRAP_GLYPH => (theme.monster, glyph),
+                    WATER_GLYPH => (WATER_COLOR, glyph),
+                    SHALLOW_WATER_GLYPH => (SHALLOW_WATER_COLOR, glyph),
+                    LAVA_GLYPH => (LAVA_COLOR, glyph),
+                    SAND_GLYPH => (SAND_COLOR, glyph),
+                    BONES_GLYPH => (decoration_color(DecorationKind::Bones), glyph),
+                    MUSHROOMS_GLYPH => (decoration_color(DecorationKind::Mushrooms), glyph),
+                    CRYSTALS_GLYPH => (decoration_color(DecorationKind::Crystals), glyph),
+                    COBWEBS_GLYPH => (decoration_color(DecorationKind::Cobwebs), glyph),
+                    EMBERS_GLYPH => (decoration_color(DecorationKind::Embers), glyph),
+                    _ => (theme.wall, glyph),
+                };
+                line.push_str(&foreground(color));
+                line.push(glyph);
+            }
+            line.push_str("\x1b[0m");
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render the whole dungeon within its bounds with box-drawing walls (see `ascii`)
+pub fn render_ascii_box(dungeon: &Dungeon) -> String {
+    let cells: Vec<Vec<char>> = render_rows(dungeon).iter().map(|row| row.chars().collect()).collect();
+    // Cells past the edges are rock
+    let rock = |row: isize, col: isize| {
+        let glyph = usize::try_from(row).ok().and_then(|row| cells.get(row)).zip(usize::try_from(col).ok()).and_then(|(cells, col)| cells.get(col));
+        glyph.is_none_or(|&glyph| glyph == EMPTY_GLYPH)
+    };
+    let wall = |row: isize, col: isize| rock(row, col) && (-1..=1).any(|dr| (-1..=1).any(|dc| !rock(row + dr, col + dc)));
+    // Walls join across an edge when the ground lies along either side of it
+    let joins = |row: isize, col: isize, (dr, dc): (isize, isize)| {
+        let (across_row, across_col) = (dc, dr);
+        wall(row + dr, col + dc)
+            && [-1, 1].iter().any(|&side| {
+                !rock(row + across_row * side, col + across_col * side) || !rock(row + dr + across_row * side, col + dc + across_col * side)
+            })
+    };
+    cells
+        .iter()
+        .enumerate()
+        .map(|(row, glyphs)| {
+            let row = row as isize;
+            glyphs
+                .iter()
+                .enumerate()
+                .map(|(col, &glyph)| match glyph {
+                    EXCAVATED_GLYPH => BOX_FLOOR_GLYPH,
+                    EMPTY_GLYPH if wall(row, col as isize) => {
+                        let col = col as isize;
+                        box_glyph(joins(row, col, (-1, 0)), joins(row, col, (1, 0)), joins(row, col, (0, -1)), joins(row, col, (0, 1)))
+                    }
+                    EMPTY_GLYPH => BOX_ROCK_GLYPH,
+                    _ => glyph,
+                })
+                .collect::<String>()
+                .trim_end_matches(BOX_ROCK_GLYPH)
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Box-drawing character of a wall joining the walls above, below, left, and right of it
+fn box_glyph(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, false, false) => BOX_PILLAR_GLYPH,
+        (false, false, _, _) => '─',
+        (_, _, false, false) => '│',
+        (false, true, false, true) => '┌',
+        (false, true, true, false) => '┐',
+        (true, false, false, true) => '└',
+        (true, false, true, false) => '┘',
+        (true, true, false, true) => '├',
+        (true, true, true, false) => '┤',
+        (false, true, true, true) => '┬',
+        (true, false, true, true) => '┴',
+        (true, true, true, true) => '┼',
+    }
+}
+
+/// Render the whole dungeon within its bounds as seen through `fog`
+pub fn render_ascii_fogged(dungeon: &Dungeon, fog: &FogOfWar) -> String {
+    let mut rows = render_rows(dungeon);
+    apply_fog(&mut rows, dungeon.bounds.x_range, dungeon.bounds.y_range, fog);
+    rows.join("\n")
+}
+
+// Rows for the dungeon bounds with room, decoration, trap, monster, and boss markers
+pub(crate) fn render_rows(dungeon: &Dungeon) -> Vec<String> {
+    let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+    let mut rows = render_window(&dungeon.grid, x_range, y_range);
+    for room in &dungeon.rooms {
+        put_glyph(&mut rows, x_range, y_range, room.center, room_glyph(room.kind));
+    }
+    for decoration in &dungeon.decorations {
+        put_glyph(&mut rows, x_range, y_range, decoration.position, decoration_glyph(decoration.kind));
+    }
+    for trap in dungeon.traps() {
+        put_glyph(&mut rows, x_range, y_range, trap.position, TRAP_GLYPH);
+    }
+    for monster in &dungeon.monsters {
+        put_glyph(&mut rows, x_range, y_range, monster.position, MONSTER_GLYPH);
+    }
+    if let Some(boss) = dungeon.boss() {
+        put_glyph(&mut rows, x_range, y_range, boss.position, BOSS_GLYPH);
+    }
+    rows
+}
+
+/// Render the inclusive `x_range` x `y_range` window, one string per row
+pub fn render_window(
+    grid: &Grid<Tile>,
+    x_range: (i32, i32),
+    y_range: (i32, i32),
+) -> Vec<String> {
+    (y_range.0..=y_range.1)
+        .rev()
+        .map(|y| {
+            (x_range.0..=x_range.1)
+                .map(|x| match grid.get((x, y)) {
+                    Some(Tile::Door) => DOOR_GLYPH,
+                    Some(Tile::StairsUp) => STAIRS_UP_GLYPH,
+                    Some(Tile::StairsDown) => STAIRS_DOWN_GLYPH,
+                    Some(Tile::Water) => WATER_GLYPH,
+                    Some(Tile::Lava) => LAVA_GLYPH,
+                    Some(Tile::Sand) => SAND_GLYPH,
+                    Some(Tile::ShallowWater) => SHALLOW_WATER_GLYPH,
+                    Some(&tile) if tile.is_walkable() => EXCAVATED_GLYPH,
+                    _ => EMPTY_GLYPH,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Overwrite the glyph at `pos` in rows produced by `render_window`
+/// Positions outside the window are ignored.
+pub fn put_glyph(rows: &mut [String], x_range: (i32, i32), y_range: (i32, i32), pos: (i32, i32), glyph: char) {
+    if pos.0 < x_range.0 || pos.0 > x_range.1 || pos.1 < y_range.0 || pos.1 > y_range.1 {
+        return;
+    }
+    // Rows run from the highest y down
+    let row = (y_range.1 - pos.1) as usize;
+    let col = (pos.0 - x_range.0) as usize;
+    if let Some(line) = rows.get_mut(row) {
+        let mut chars: Vec<char> = line.chars().collect();
+        if col < chars.len() {
+            chars[col] = glyph;
+            *line = chars.into_iter().collect();
+        }
+    }
+}
+
+/// Hide unexplored tiles and dim seen ones in rows produced by `render_window`
+pub fn apply_fog(rows: &mut [String], x_range: (i32, i32), y_range: (i32, i32), fog: &FogOfWar) {
+    for (i, line) in rows.iter_mut().enumerate() {
+        let y = y_range.1 - i as i32;
+        *line = line
+            .chars()
+            .zip(x_range.0..)
+            .map(|(glyph, x)| match fog.visibility((x, y)) {
+                Visibility::Unexplored => FOG_GLYPH,
+                Visibility::Seen if glyph == EXCAVATED_GLYPH || glyph == MONSTER_GLYPH => SEEN_GLYPH,
+                _ => glyph,
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn box_maps_outline_the_plain_map() {
+        let dungeon = dungeon();
+        let plain: Vec<Vec<char>> = render_ascii(&dungeon).lines().map(|line| line.chars().collect()).collect();
+        let boxed = render_ascii_box(&dungeon);
+        assert_eq!(boxed.lines().count(), plain.len());
+        for (plain, boxed) in plain.iter().zip(boxed.lines()) {
+            assert!(!boxed.ends_with(BOX_ROCK_GLYPH));
+            for (&plain, boxed) in plain.iter().zip(boxed.chars()) {
+                match plain {
+                    EXCAVATED_GLYPH => assert_eq!(boxed, BOX_FLOOR_GLYPH),
+                    EMPTY_GLYPH => assert!(boxed == BOX_ROCK_GLYPH || boxed == BOX_PILLAR_GLYPH || ('\u{2500}'..='\u{257f}').contains(&boxed)),
+                    _ => assert_eq!(boxed, plain),
+                }
+            }
+        }
+        for corner in ['─', '│', '┌', '┐', '└', '┘'] {
+            assert!(boxed.contains(corner), "{}", corner);
+        }
+    }
+
+    #[test]
+    fn walls_join_the_walls_beside_them() {
+        assert_eq!(box_glyph(false, true, false, true), '┌');
+        assert_eq!(box_glyph(true, false, true, false), '┘');
+        assert_eq!(box_glyph(false, false, true, false), '─');
+        assert_eq!(box_glyph(true, true, true, true), '┼');
+        assert_eq!(box_glyph(false, false, false, false), BOX_PILLAR_GLYPH);
+    }
+}
@@ -0,0 +1,282 @@
+//! # Audio Manifest
+//!
+//! Ambience, music, and sound effect cues for a dungeon, so game clients can wire audio
+//! without hardcoding it per dungeon type. Cues are names such as "music/boss_theme"
+//! that clients map to their own sound files.
+//!
+//! An `AudioTable` holds the cues of:
+//! - **Dungeon types**: Cues playing throughout the dungeon, by type; variant types
+//!   (see `excavator::DUNGEON_VARIANTS`) fall back to their base type, and types without
+//!   an entry to the table's `default`. Each cue list, or the music, a type leaves out
+//!   also falls back to the `default`.
+//! - **Room kinds**: Cues played within rooms of a kind, such as boss themes.
+//! - **Terrain**: Cues added to every room holding water, lava, or sand (see `terrain`).
+//!
+//! `AudioTable::default()` is the table embedded from `assets/audio.toml`
+//! (`DEFAULT_AUDIO_TABLE`). `AudioTable::from_toml` and `AudioTable::load` read the same
+//! format, replacing the default entries they name and keeping the rest, so users
+//! override single cues without copying the whole table.
+//!
+//! `audio_manifest` writes the cues of a dungeon and each room of its top floor as an
+//! `AudioManifest`, serialized to JSON for clients (`AudioManifest::to_json`).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use super::dungeon::{Dungeon, RoomKind};
+use super::excavator::DUNGEON_VARIANTS;
+use super::grid::Tile;
+
+/// The default audio table, in TOML
+pub const DEFAULT_AUDIO_TABLE: &str = include_str!("../assets/audio.toml");
+
+/// Terrain tiles with cues, by their name in an `AudioTable`
+const TERRAIN_TILES: [(Tile, &str); 3] = [(Tile::Water, "Water"), (Tile::Lava, "Lava"), (Tile::Sand, "Sand")];
+
+/// Cues played together.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CueSet {
+    /// Looping background sounds.
+    pub ambience: Vec<String>,
+    /// Music track, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub music: Option<String>,
+    /// One-shot sound effects.
+    pub sfx: Vec<String>,
+}
+
+impl CueSet {
+    /// Whether the set holds no cue.
+    pub fn is_empty(&self) -> bool {
+        self.ambience.is_empty() && self.music.is_none() && self.sfx.is_empty()
+    }
+
+    /// These cues, with every list or music they leave out taken from `base`
+    pub fn or(&self, base: &CueSet) -> CueSet {
+        let pick = |own: &Vec<String>, base: &Vec<String>| if own.is_empty() { base.clone() } else { own.clone() };
+        CueSet {
+            ambience: pick(&self.ambience, &base.ambience),
+            music: self.music.clone().or_else(|| base.music.clone()),
+            sfx: pick(&self.sfx, &base.sfx),
+        }
+    }
+
+    // Add the ambience and effects of `other` missing from these cues
+    fn add(&mut self, other: &CueSet) {
+        for (own, extra) in [(&mut self.ambience, &other.ambience), (&mut self.sfx, &other.sfx)] {
+            for cue in extra {
+                if !own.contains(cue) {
+                    own.push(cue.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Cues by dungeon type, room kind, and terrain (see `audio`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioTable {
+    /// Cues of dungeon types without an entry, and of the parts an entry leaves out.
+    #[serde(default)]
+    pub default: CueSet,
+    /// Cues by dungeon type, such as "Cave".
+    #[serde(default)]
+    pub dungeon_types: BTreeMap<String, CueSet>,
+    /// Cues by room kind, such as "Boss".
+    #[serde(default)]
+    pub rooms: BTreeMap<String, CueSet>,
+    /// Cues by terrain tile: "Water", "Lava", or "Sand".
+    #[serde(default)]
+    pub terrain: BTreeMap<String, CueSet>,
+}
+
+/// Errors returned while loading an audio table.
+#[derive(Debug)]
+pub enum AudioError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not valid audio table TOML.
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AudioError::Io(err) => write!(f, "Failed to read audio table: {}", err),
+            AudioError::Toml(err) => write!(f, "Invalid audio table: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+impl From<io::Error> for AudioError {
+    fn from(err: io::Error) -> AudioError {
+        AudioError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for AudioError {
+    fn from(err: toml::de::Error) -> AudioError {
+        AudioError::Toml(err)
+    }
+}
+
+impl Default for AudioTable {
+    fn default() -> AudioTable {
+        toml::from_str(DEFAULT_AUDIO_TABLE).expect("the default audio table is valid")
+    }
+}
+
+impl AudioTable {
+    /// The default table, with the entries of `toml` replacing those of the same name.
+    pub fn from_toml(toml: &str) -> Result<AudioTable, AudioError> {
+        Ok(AudioTable::default().with_overrides(toml::from_str(toml)?))
+    }
+
+    /// Reads an audio table TOML file from `path` over the default table.
+    pub fn load(path: &Path) -> Result<AudioTable, AudioError> {
+        AudioTable::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// This table with the entries of `overrides` replacing those of the same name; the
+    /// default cues are replaced list by list
+    pub fn with_overrides(mut self, overrides: AudioTable) -> AudioTable {
+        self.default = overrides.default.or(&self.default);
+        self.dungeon_types.extend(overrides.dungeon_types);
+        self.rooms.extend(overrides.rooms);
+        self.terrain.extend(overrides.terrain);
+        self
+    }
+
+    /// Cues playing throughout a dungeon of `dungeon_type`
+    pub fn dungeon_cues(&self, dungeon_type: &str) -> CueSet {
+        let base_type = DUNGEON_VARIANTS.iter().find(|&&(variant, _)| variant == dungeon_type).map(|&(_, base)| base);
+        self.dungeon_types
+            .get(dungeon_type)
+            .or_else(|| base_type.and_then(|base| self.dungeon_types.get(base)))
+            .map_or_else(|| self.default.clone(), |cues| cues.or(&self.default))
+    }
+
+    /// Cues played within a room of `kind`
+    pub fn room_cues(&self, kind: RoomKind) -> CueSet {
+        self.rooms.get(&format!("{:?}", kind)).cloned().unwrap_or_default()
+    }
+}
+
+/// Cues of one room of a dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomCues {
+    /// Index of the room in `Dungeon::rooms`.
+    pub index: usize,
+    /// Name of the room.
+    pub name: String,
+    /// Kind of the room.
+    pub kind: RoomKind,
+    /// Center of the room, where positional cues are emitted.
+    pub center: (i32, i32),
+    /// Cues of the room's kind and of the terrain within it, played over the dungeon's.
+    pub cues: CueSet,
+}
+
+/// Audio cues of a dungeon, for game clients (see `audio`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioManifest {
+    /// NFT ID of the dungeon.
+    pub nft_id: String,
+    /// Dungeon type the cues were picked for.
+    pub dungeon_type: String,
+    /// Cues playing throughout the dungeon.
+    pub cues: CueSet,
+    /// Cues of every room of the top floor, in room order.
+    pub rooms: Vec<RoomCues>,
+}
+
+impl AudioManifest {
+    /// Serialize the manifest as pretty-printed JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Deserialize a manifest previously produced by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<AudioManifest> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Audio cues of `dungeon` from `table` (see `audio`)
+pub fn audio_manifest(dungeon: &Dungeon, table: &AudioTable) -> AudioManifest {
+    let rooms = dungeon
+        .rooms
+        .iter()
+        .enumerate()
+        .map(|(index, room)| {
+            let mut cues = table.room_cues(room.kind);
+            for (terrain, name) in TERRAIN_TILES {
+                if let Some(terrain_cues) = table.terrain.get(name) {
+                    if room.tiles.iter().any(|&pos| dungeon.grid.get(pos) == Some(&terrain)) {
+                        cues.add(terrain_cues);
+                    }
+                }
+            }
+            RoomCues { index, name: room.name.clone(), kind: room.kind, center: room.center, cues }
+        })
+        .collect();
+    AudioManifest {
+        nft_id: dungeon.nft_id.clone(),
+        dungeon_type: dungeon.dungeon_type.clone(),
+        cues: table.dungeon_cues(&dungeon.dungeon_type),
+        rooms,
+    }
+}
+
+impl Dungeon {
+    /// Audio cues of the dungeon from `table` (see `audio`).
+    pub fn audio_manifest(&self, table: &AudioTable) -> AudioManifest {
+        audio_manifest(self, table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn manifests_cue_the_dungeon_type_and_room_kinds() {
+        let table = AudioTable::default();
+        let cave = table.dungeon_cues("Cave");
+        assert!(cave.ambience.contains(&"ambience/water_drips".to_string()));
+        assert_eq!(table.dungeon_cues("Crystal Grotto"), cave);
+        assert_eq!(table.dungeon_cues("Barrens"), table.default);
+        assert_eq!(table.dungeon_cues("Hell").sfx, table.default.sfx);
+
+        let dungeon = dungeon();
+        let manifest = dungeon.audio_manifest(&table);
+        assert_eq!(manifest.cues, table.dungeon_cues(&dungeon.dungeon_type));
+        assert_eq!(manifest.rooms.len(), dungeon.rooms.len());
+        let boss = manifest.rooms.iter().find(|room| room.kind == RoomKind::Boss).unwrap();
+        assert_eq!(boss.cues.music.as_deref(), Some("music/boss_theme"));
+        assert!(manifest.rooms.iter().filter(|room| room.kind == RoomKind::Normal).all(|room| room.cues.music.is_none()));
+        assert_eq!(AudioManifest::from_json(&manifest.to_json().unwrap()).unwrap(), manifest);
+    }
+
+    #[test]
+    fn overrides_replace_only_the_entries_they_name() {
+        let table = AudioTable::from_toml("[default]\nmusic = \"music/custom\"\n\n[rooms.Boss]\nsfx = [\"sfx/gong\"]\n\n[dungeon_types.Barrens]\nambience = [\"ambience/dust\"]\n").unwrap();
+        let default = AudioTable::default();
+        assert_eq!(table.default.music.as_deref(), Some("music/custom"));
+        assert_eq!(table.default.ambience, default.default.ambience);
+        assert_eq!(table.room_cues(RoomKind::Boss), CueSet { sfx: vec!["sfx/gong".to_string()], ..CueSet::default() });
+        assert_eq!(table.room_cues(RoomKind::Treasure), default.room_cues(RoomKind::Treasure));
+        assert_eq!(table.dungeon_cues("Barrens").ambience, ["ambience/dust"]);
+        assert_eq!(table.dungeon_cues("Barrens").music.as_deref(), Some("music/custom"));
+        assert_eq!(table.dungeon_types.len(), default.dungeon_types.len() + 1);
+        assert!(matches!(AudioTable::from_toml("rooms = 3"), Err(AudioError::Toml(_))));
+        assert!(matches!(AudioTable::load(Path::new("missing_audio.toml")), Err(AudioError::Io(_))));
+    }
+}
@@ -0,0 +1,232 @@
+//! # Spawn Balancing
+//!
+//! Per-level curves scaling how many monsters a dungeon holds, how much its gold is worth,
+//! and how many traps it arms, so deep dungeons are more crowded, richer, and more
+//! dangerous than shallow ones.
+//!
+//! Each `Curve` maps the dungeon level to `base + per_level * level^exponent`:
+//! - **Monsters**: `monsters_per_room` for every room plus `monsters` at the level,
+//!   rounded to the nearest whole monster (see `spawn`).
+//! - **Gold**: Each treasure pile is worth 10-19 coins times `gold` at the level (see
+//!   `inventory`).
+//! - **Traps**: `traps` at the level is the share of the NFT ID's character triples that
+//!   arm a trap, clamped to 0-1, with the armed triples spread evenly over the ID (see
+//!   `traps`).
+//!
+//! `Balance::default()` reproduces the original rules: one monster per room plus one per
+//! level, piles worth 10-19 coins per level, and a trap for every triple.
+//!
+//! Balances are read from TOML at runtime (`Balance::load`, or the CLI's `--balance`
+//! option). Every key is optional: an omitted curve keeps its default, an omitted key of
+//! a given curve is 0 (or 1 for `exponent`).
+//!
+//! ```toml
+//! monsters_per_room = 0.5
+//!
+//! [monsters]
+//! per_level = 0.25
+//! exponent = 1.5
+//!
+//! [traps]
+//! base = 0.3
+//! per_level = 0.02
+//! ```
+//!
+//! The balance is part of the `GenerationConfig` and is kept with every dungeon
+//! (`Dungeon::balance`), so loot and traps derived later follow the same curves.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use super::error::DungeonError;
+
+/// A value that changes with the dungeon level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Curve {
+    /// Value at level 0.
+    pub base: f64,
+    /// Growth per level, raised to `exponent`.
+    pub per_level: f64,
+    /// Exponent on the level; 1 grows linearly, above 1 faster at high levels.
+    pub exponent: f64,
+}
+
+impl Default for Curve {
+    fn default() -> Curve {
+        Curve { base: 0.0, per_level: 0.0, exponent: 1.0 }
+    }
+}
+
+impl Curve {
+    /// A curve growing by `per_level` each level from `base`.
+    pub fn linear(base: f64, per_level: f64) -> Curve {
+        Curve { base, per_level, exponent: 1.0 }
+    }
+
+    /// Value at `level`.
+    pub fn at(&self, level: u64) -> f64 {
+        self.base + self.per_level * (level as f64).powf(self.exponent)
+    }
+
+    fn is_finite(&self) -> bool {
+        self.base.is_finite() && self.per_level.is_finite() && self.exponent.is_finite()
+    }
+}
+
+/// Per-level curves for monsters, gold, and traps.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Balance {
+    /// Monsters per room, before the per-level extra.
+    pub monsters_per_room: f64,
+    /// Extra monsters at each level.
+    pub monsters: Curve,
+    /// Multiplier on the 10-19 coins of a treasure pile at each level.
+    pub gold: Curve,
+    /// Share of character triples that arm a trap at each level.
+    pub traps: Curve,
+}
+
+impl Default for Balance {
+    fn default() -> Balance {
+        Balance {
+            monsters_per_room: 1.0,
+            monsters: Curve::linear(0.0, 1.0),
+            gold: Curve::linear(0.0, 1.0),
+            traps: Curve::linear(1.0, 0.0),
+        }
+    }
+}
+
+/// Errors returned while loading a balance file.
+#[derive(Debug)]
+pub enum BalanceError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not valid balance TOML.
+    Toml(toml::de::Error),
+    /// The balance has a non-finite or negative parameter.
+    Invalid(DungeonError),
+}
+
+impl fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BalanceError::Io(err) => write!(f, "Failed to read balance file: {}", err),
+            BalanceError::Toml(err) => write!(f, "Invalid balance file: {}", err),
+            BalanceError::Invalid(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+impl From<io::Error> for BalanceError {
+    fn from(err: io::Error) -> BalanceError {
+        BalanceError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for BalanceError {
+    fn from(err: toml::de::Error) -> BalanceError {
+        BalanceError::Toml(err)
+    }
+}
+
+impl Balance {
+    /// Parses and validates a balance from TOML.
+    pub fn from_toml(toml: &str) -> Result<Balance, BalanceError> {
+        let balance: Balance = toml::from_str(toml)?;
+        balance.validate().map_err(BalanceError::Invalid)?;
+        Ok(balance)
+    }
+
+    /// Reads a balance TOML file from `path`.
+    pub fn load(path: &Path) -> Result<Balance, BalanceError> {
+        Balance::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Checks that every parameter is finite and the monster rate is non-negative.
+    pub fn validate(&self) -> Result<(), DungeonError> {
+        if !(self.monsters_per_room.is_finite() && self.monsters_per_room >= 0.0) {
+            return Err(DungeonError::InvalidConfig("balance monsters_per_room must be finite and non-negative"));
+        }
+        if ![self.monsters, self.gold, self.traps].iter().all(Curve::is_finite) {
+            return Err(DungeonError::InvalidConfig("balance curves must be finite"));
+        }
+        Ok(())
+    }
+
+    /// Number of monsters in a dungeon of `rooms` rooms at `level`.
+    pub fn monster_count(&self, rooms: usize, level: u64) -> usize {
+        (rooms as f64 * self.monsters_per_room + self.monsters.at(level)).round().max(0.0) as usize
+    }
+
+    /// Coins in a treasure pile at `level`, for a `roll` of 10-19.
+    pub fn gold_value(&self, roll: u64, level: u64) -> u64 {
+        (roll as f64 * self.gold.at(level)).round().max(0.0) as u64
+    }
+
+    /// Share of character triples that arm a trap at `level`, from 0 to 1.
+    pub fn trap_frequency(&self, level: u64) -> f64 {
+        self.traps.at(level).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::GenerationConfig;
+    use super::super::dungeon::Dungeon;
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::super::inventory::Item;
+    use super::*;
+
+    #[test]
+    fn the_default_balance_keeps_the_original_rules() {
+        let balance = Balance::default();
+        assert_eq!(balance.monster_count(9, 3), 12);
+        assert_eq!(balance.gold_value(15, 4), 60);
+        assert_eq!((balance.trap_frequency(1), balance.trap_frequency(40)), (1.0, 1.0));
+        let dungeon = dungeon();
+        assert_eq!(dungeon.balance, balance);
+        assert_eq!(dungeon.monsters.len(), dungeon.rooms.len() + dungeon.level as usize);
+    }
+
+    #[test]
+    fn curves_separate_shallow_and_deep_dungeons() {
+        let balance = Balance::from_toml("monsters_per_room = 0.5\n[monsters]\nper_level = 0.25\nexponent = 1.5\n[traps]\nbase = 0.3\nper_level = 0.02\n").unwrap();
+        assert_eq!(balance.gold, Balance::default().gold);
+        assert_eq!(balance.monster_count(10, 1), 5);
+        assert_eq!(balance.monster_count(10, 40), 68);
+        assert!((balance.trap_frequency(1) - 0.32).abs() < 1e-9);
+        assert_eq!(balance.trap_frequency(40), 1.0);
+    }
+
+    #[test]
+    fn dungeons_follow_their_balance() {
+        let balance = Balance { monsters_per_room: 0.0, monsters: Curve::linear(2.0, 0.0), gold: Curve::linear(5.0, 0.0), traps: Curve::default() };
+        let config = GenerationConfig { balance: balance.clone(), ..GenerationConfig::default() };
+        let dungeon = parse_nft_id_with_config(NFT_ID, &config).unwrap();
+        assert_eq!(dungeon.monsters.len(), 2);
+        assert!(dungeon.traps().is_empty());
+        for loot in dungeon.loot() {
+            if let Item::Gold(amount) = loot.item {
+                assert!((50..100).contains(&amount));
+            }
+        }
+        assert!(Dungeon::from_json(&dungeon.to_json().unwrap()).unwrap().traps().is_empty());
+    }
+
+    #[test]
+    fn invalid_balances_are_rejected() {
+        assert!(matches!(Balance::from_toml("monsters_per_room = -1.0"), Err(BalanceError::Invalid(_))));
+        assert!(matches!(Balance::from_toml("[gold]\nbase = nan"), Err(BalanceError::Invalid(_))));
+        assert!(matches!(Balance::from_toml("traps = 3"), Err(BalanceError::Toml(_))));
+        assert!(matches!(Balance::load(Path::new("no/such/balance.toml")), Err(BalanceError::Io(_))));
+        assert_eq!(Balance::from_toml("").unwrap(), Balance::default());
+    }
+}
@@ -0,0 +1,332 @@
+//! # Batch Generation
+//!
+//! Generates many dungeons at once so collection owners can preview a whole collection.
+//!
+//! `run_batch` decodes every NFT ID in parallel (`rayon`) with the config of its
+//! `BatchOptions` and writes into the output directory, per ID:
+//! - `{nft_id}.png` or `{nft_id}.svg`: The plotted map of the top floor.
+//! - `{nft_id}.json`: The dungeon as JSON (see `Dungeon::to_json`).
+//!
+//! It returns one `BatchEntry` per ID, in input order, which `write_summary_csv` writes
+//! as a CSV table with the level, type, room count, area, and rarity score of each
+//! dungeon. IDs that
+//! fail to decode or render keep their row with the error message instead.
+//!
+//! `run_batch_with_progress` also reports every finished dungeon with the time spent in
+//! each generation stage (see `progress`), as the dungeons finish.
+//!
+//! `rerender_with_progress` plots stored dungeons again, such as the JSON files of a batch
+//! or of a disk cache (see `cache`) found by `find_dungeon_files`, writing each image next
+//! to its JSON. Previews pick up renderer and theme changes without regenerating anything.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use rayon::prelude::*;
+use serde::Serialize;
+use super::config::GenerationConfig;
+use super::dungeon::Dungeon;
+use super::excavator::parse_nft_id_with_observer;
+use super::hooks::Stage;
+use super::plot::{render_dungeon_map, render_themed_map, RenderFormat};
+use super::progress::{StageTimer, StageTimings};
+use super::shapes::ShapeRegistry;
+use super::theme::Theme;
+
+/// Default file name of the summary CSV, written alongside the dungeon files
+pub const SUMMARY_FILE: &str = "summary.csv";
+
+/// How `run_batch` generates each dungeon and what it writes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchOptions<'a> {
+    /// Format of the map image, or `None` to skip images.
+    pub image_format: Option<RenderFormat>,
+    /// Whether to write the dungeon JSON.
+    pub json: bool,
+    /// Config every dungeon is generated with.
+    pub config: &'a GenerationConfig,
+}
+
+impl Default for BatchOptions<'static> {
+    fn default() -> BatchOptions<'static> {
+        static DEFAULT_CONFIG: OnceLock<GenerationConfig> = OnceLock::new();
+        BatchOptions {
+            image_format: Some(RenderFormat::Png),
+            json: true,
+            config: DEFAULT_CONFIG.get_or_init(GenerationConfig::default),
+        }
+    }
+}
+
+/// Summary of one generated dungeon, a row of the summary CSV.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct BatchEntry {
+    /// The NFT ID as given.
+    pub nft_id: String,
+    /// Dungeon level.
+    pub level: Option<u64>,
+    /// Dungeon type.
+    #[serde(rename = "type")]
+    pub dungeon_type: Option<String>,
+    /// Number of rooms on the top floor.
+    pub rooms: Option<usize>,
+    /// Total room area.
+    pub area: Option<u64>,
+    /// Rarity score from 0 to 100 (see `rarity`).
+    pub rarity: Option<f64>,
+    /// Why the dungeon could not be generated or written.
+    pub error: Option<String>,
+}
+
+/// Outcome of plotting one stored dungeon again.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RerenderEntry {
+    /// The dungeon JSON file.
+    pub json: PathBuf,
+    /// The image written next to it, unless plotting failed.
+    pub image: Option<PathBuf>,
+    /// Why the file could not be read or plotted.
+    pub error: Option<String>,
+}
+
+/// Read NFT IDs from a text file, one per line
+/// Blank lines and lines starting with `#` are skipped.
+pub fn read_nft_ids(path: &Path) -> io::Result<Vec<String>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Generate every dungeon in parallel, writing its files to `out_dir`
+pub fn run_batch(nft_ids: &[String], out_dir: &Path, options: &BatchOptions) -> io::Result<Vec<BatchEntry>> {
+    run_batch_with_progress(nft_ids, out_dir, options, |_, _| {})
+}
+
+/// Generate every dungeon like `run_batch`, calling `on_done` from the worker threads
+/// with each dungeon's entry and stage timings as it finishes
+pub fn run_batch_with_progress(
+    nft_ids: &[String],
+    out_dir: &Path,
+    options: &BatchOptions,
+    on_done: impl Fn(&BatchEntry, &StageTimings) + Sync,
+) -> io::Result<Vec<BatchEntry>> {
+    fs::create_dir_all(out_dir)?;
+    Ok(nft_ids
+        .par_iter()
+        .map(|nft_id| {
+            let mut timer = StageTimer::new();
+            let entry = generate_entry(nft_id, out_dir, options, &mut timer);
+            timer.stop();
+            on_done(&entry, &timer.timings());
+            entry
+        })
+        .collect())
+}
+
+/// Every `.json` file under `dir` and its subdirectories, sorted by path
+pub fn find_dungeon_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|extension| extension == "json") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Plot the dungeon of every JSON file in parallel, next to the file with the extension
+/// of `format`, in `theme` or else each dungeon's own theme, calling `on_done` from the
+/// worker threads with each file's entry as it finishes
+/// Files that do not hold a dungeon, such as campaign manifests, fail with an error.
+pub fn rerender_with_progress(
+    files: &[PathBuf],
+    format: RenderFormat,
+    theme: Option<&Theme>,
+    on_done: impl Fn(&RerenderEntry) + Sync,
+) -> Vec<RerenderEntry> {
+    files
+        .par_iter()
+        .map(|json| {
+            let entry = match rerender_file(json, format, theme) {
+                Ok(image) => RerenderEntry { json: json.clone(), image: Some(image), error: None },
+                Err(error) => RerenderEntry { json: json.clone(), image: None, error: Some(error) },
+            };
+            on_done(&entry);
+            entry
+        })
+        .collect()
+}
+
+// Reload and plot a single stored dungeon, returning the image path
+fn rerender_file(json: &Path, format: RenderFormat, theme: Option<&Theme>) -> Result<PathBuf, String> {
+    let text = fs::read_to_string(json).map_err(|err| format!("Error reading dungeon JSON: {}", err))?;
+    let dungeon = Dungeon::from_json(&text).map_err(|err| format!("Error reading dungeon JSON: {}", err))?;
+    let image = json.with_extension(image_extension(format));
+    let theme = theme.cloned().unwrap_or_else(|| Theme::for_dungeon(&dungeon));
+    render_themed_map(&dungeon, &image, format, &theme).map_err(|err| format!("Error plotting dungeon: {}", err))?;
+    Ok(image)
+}
+
+// File extension of map images in `format`
+fn image_extension(format: RenderFormat) -> &'static str {
+    match format {
+        RenderFormat::Png | RenderFormat::Isometric => "png",
+        RenderFormat::Svg => "svg",
+    }
+}
+
+/// Write the batch summary as CSV to `path`
+pub fn write_summary_csv(entries: &[BatchEntry], path: &Path) -> Result<(), csv::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for entry in entries {
+        writer.serialize(entry)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Decode, render, and save a single dungeon, timing each stage
+fn generate_entry(nft_id: &str, out_dir: &Path, options: &BatchOptions, timer: &mut StageTimer) -> BatchEntry {
+    let failed = |error: String| BatchEntry {
+        nft_id: nft_id.to_string(),
+        error: Some(error),
+        ..BatchEntry::default()
+    };
+    let dungeon = match parse_nft_id_with_observer(nft_id, options.config, &ShapeRegistry::new(), timer) {
+        Ok(dungeon) => dungeon,
+        Err(err) => return failed(err.to_string()),
+    };
+
+    timer.start(Stage::Render);
+
+    if let Some(format) = options.image_format {
+        let image_path = out_dir.join(format!("{}.{}", nft_id, image_extension(format)));
+        if let Err(err) = render_dungeon_map(&dungeon, &image_path, format) {
+            return failed(format!("Error plotting dungeon: {}", err));
+        }
+    }
+    if options.json {
+        let written = dungeon
+            .to_json()
+            .map_err(|err| err.to_string())
+            .and_then(|json| fs::write(out_dir.join(format!("{}.json", nft_id)), json).map_err(|err| err.to_string()));
+        if let Err(err) = written {
+            return failed(format!("Error writing dungeon JSON: {}", err));
+        }
+    }
+
+    BatchEntry {
+        nft_id: nft_id.to_string(),
+        level: Some(dungeon.level),
+        dungeon_type: Some(dungeon.dungeon_type.clone()),
+        rooms: Some(dungeon.rooms.len()),
+        area: Some(dungeon.area_size),
+        rarity: Some((dungeon.rarity_score() * 10.0).round() / 10.0),
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::PathBuf;
+    use super::super::dungeon::Dungeon;
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    // An empty directory for one test under the system temp directory
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("chia_dungeon_batch_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn id_lists_skip_blank_lines_and_comments() {
+        let dir = scratch_dir("ids");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ids.txt");
+        fs::write(&path, format!("# collection\n\n  {}  \nnft1abc\n", NFT_ID)).unwrap();
+        assert_eq!(read_nft_ids(&path).unwrap(), vec![NFT_ID.to_string(), "nft1abc".to_string()]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn batches_write_files_and_keep_failed_rows() {
+        let dir = scratch_dir("run");
+        let ids = vec![NFT_ID.to_string(), "bogus".to_string()];
+        let options = BatchOptions { image_format: Some(RenderFormat::Svg), ..BatchOptions::default() };
+        let finished = std::sync::Mutex::new(Vec::new());
+        let entries = run_batch_with_progress(&ids, &dir, &options, |entry, timings| {
+            finished.lock().unwrap().push((entry.nft_id.clone(), timings.total()));
+        })
+        .unwrap();
+        let mut finished = finished.into_inner().unwrap();
+        finished.sort();
+        assert_eq!(finished.iter().map(|(nft_id, _)| nft_id.as_str()).collect::<Vec<_>>(), ["bogus", NFT_ID]);
+        assert!(finished[1].1 > std::time::Duration::ZERO);
+
+        let dungeon = dungeon();
+        assert_eq!(entries[0].nft_id, NFT_ID);
+        assert_eq!((entries[0].level, entries[0].rooms, entries[0].error.as_deref()), (Some(dungeon.level), Some(dungeon.rooms.len()), None));
+        assert!(dir.join(format!("{}.svg", NFT_ID)).exists());
+        let json = fs::read_to_string(dir.join(format!("{}.json", NFT_ID))).unwrap();
+        assert_eq!(Dungeon::from_json(&json).unwrap(), dungeon);
+        assert_eq!(entries[1].nft_id, "bogus");
+        assert!(entries[1].error.is_some() && entries[1].level.is_none());
+
+        let summary = dir.join(SUMMARY_FILE);
+        write_summary_csv(&entries, &summary).unwrap();
+        let csv = fs::read_to_string(&summary).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.starts_with("nft_id,level,type,rooms,area,rarity,error\n"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn batches_generate_with_the_given_config() {
+        let dir = scratch_dir("config");
+        let salted = GenerationConfig { collection_salt: Some("other".to_string()), ..GenerationConfig::default() };
+        let options = BatchOptions { image_format: None, json: true, config: &salted };
+        let entries = run_batch(&[NFT_ID.to_string()], &dir, &options).unwrap();
+        let expected = parse_nft_id_with_config(NFT_ID, &salted).unwrap();
+        assert_eq!((entries[0].dungeon_type.as_deref(), entries[0].area), (Some(expected.dungeon_type.as_str()), Some(expected.area_size)));
+        let json = fs::read_to_string(dir.join(format!("{}.json", NFT_ID))).unwrap();
+        assert_eq!(Dungeon::from_json(&json).unwrap(), expected);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stored_dungeons_are_rerendered_in_place() {
+        let dir = scratch_dir("rerender");
+        fs::create_dir_all(dir.join("cache")).unwrap();
+        let stored = dir.join("cache").join("dungeon.json");
+        fs::write(&stored, dungeon().to_json().unwrap()).unwrap();
+        fs::write(dir.join("manifest.json"), "{}").unwrap();
+        fs::write(dir.join("notes.txt"), "not json").unwrap();
+
+        let files = find_dungeon_files(&dir).unwrap();
+        assert_eq!(files, [stored.clone(), dir.join("manifest.json")]);
+        let count = std::sync::atomic::AtomicUsize::new(0);
+        let entries = rerender_with_progress(&files, RenderFormat::Svg, Some(&Theme::classic()), |_| {
+            count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+        assert_eq!(count.into_inner(), 2);
+        assert_eq!(entries[0], RerenderEntry { json: stored, image: Some(dir.join("cache").join("dungeon.svg")), error: None });
+        assert!(dir.join("cache").join("dungeon.svg").exists());
+        assert!(entries[1].image.is_none() && entries[1].error.is_some());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
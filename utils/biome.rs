@@ -0,0 +1,129 @@
+//! # Biomes
+//!
+//! Structured dungeon types combining the top three characters of an NFT ID into a
+//! primary type and a sub-biome modifier (e.g., "Frozen Necropolis"), so dungeons sharing
+//! a most frequent letter still tell apart.
+//!
+//! - **Primary**: The dungeon type of the most frequent letter, the same as
+//!   `Dungeon::dungeon_type`, so themes and bestiaries keep following it.
+//! - **Modifier**: The adjective of the most frequent other character, letters and digits
+//!   alike (see `modifier_for`). Upper- and lower-case letters share an adjective, and
+//!   case variants of the primary letter never count as another character.
+//! - **Pure biomes**: When the primary letter occurs at least as often as the next two
+//!   characters together, it dominates and the type has no modifier.
+//!
+//! Characters are counted over the whole ID, like `analysis::letter_frequency`, with ties
+//! going to the character seen first.
+
+use std::collections::HashMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::analysis::letter_frequency;
+use super::excavator::get_dungeon_type;
+
+/// Sub-biome adjectives of the letters 'a'-'z', each echoing the type of its letter
+pub const LETTER_MODIFIERS: [&str; 26] = [
+    "Ancient", "Barren", "Cavernous", "Arid", "Enchanted", "Overgrown", "Verdant", "Infernal", "Frozen",
+    "Tangled", "Royal", "Molten", "Craggy", "Haunted", "Sunken", "Poisoned", "Mired", "Dripping", "Boggy",
+    "Sacred", "Subterranean", "Smoldering", "Flooded", "Alien", "Blighted", "Windswept",
+];
+
+/// Sub-biome adjectives of the digits '0'-'9'
+pub const DIGIT_MODIFIERS: [&str; 10] = [
+    "Forsaken", "Lonely", "Twin", "Shattered", "Hollow", "Silent", "Cursed", "Gilded", "Endless", "Abyssal",
+];
+
+/// A dungeon type with an optional sub-biome modifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DungeonType {
+    /// Dungeon type of the most frequent letter.
+    pub primary: String,
+    /// Adjective of the most frequent other character, unless the primary letter dominates.
+    pub modifier: Option<String>,
+}
+
+impl Default for DungeonType {
+    fn default() -> DungeonType {
+        DungeonType { primary: "Unknown".to_string(), modifier: None }
+    }
+}
+
+impl fmt::Display for DungeonType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.modifier {
+            Some(modifier) => write!(f, "{} {}", modifier, self.primary),
+            None => write!(f, "{}", self.primary),
+        }
+    }
+}
+
+/// Sub-biome adjective of a base62 character
+pub fn modifier_for(c: char) -> Option<&'static str> {
+    match c.to_ascii_lowercase() {
+        letter @ 'a'..='z' => Some(LETTER_MODIFIERS[(letter as u8 - b'a') as usize]),
+        digit @ '0'..='9' => Some(DIGIT_MODIFIERS[(digit as u8 - b'0') as usize]),
+        _ => None,
+    }
+}
+
+/// Classifies the `nft_id` into a primary dungeon type and sub-biome modifier
+pub fn classify(nft_id: &str) -> DungeonType {
+    let (_, most_frequent_char) = letter_frequency(nft_id);
+    let primary = get_dungeon_type(&most_frequent_char);
+    // IDs without letters have no most frequent letter to modify
+    let Ok(primary_char) = most_frequent_char.parse::<char>() else {
+        return DungeonType { primary, modifier: None };
+    };
+
+    // Every base62 character ranked by count, ties going to the first seen
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    let mut order = Vec::new();
+    for c in nft_id.chars().filter(char::is_ascii_alphanumeric) {
+        let count = counts.entry(c).or_insert(0);
+        if *count == 0 {
+            order.push(c);
+        }
+        *count += 1;
+    }
+    let mut others: Vec<char> = order.into_iter().filter(|c| !c.eq_ignore_ascii_case(&primary_char)).collect();
+    others.sort_by_key(|c| std::cmp::Reverse(counts[c]));
+
+    let runner_ups: usize = others.iter().take(2).map(|c| counts[c]).sum();
+    let modifier = others
+        .first()
+        .filter(|_| counts[&primary_char] < runner_ups)
+        .and_then(|&c| modifier_for(c))
+        .map(str::to_string);
+    DungeonType { primary, modifier }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    #[test]
+    fn runner_up_characters_pick_the_modifier() {
+        // 'n' leads with 5 (counting "nft1"), then 'i' with 4 and '7' with 3
+        let classified = classify("nft1nnnniiii777xyz");
+        assert_eq!(classified, DungeonType { primary: "Necropolis".to_string(), modifier: Some("Frozen".to_string()) });
+        assert_eq!(classified.to_string(), "Frozen Necropolis");
+        // Digits modify too, and upper-case letters keep their variant type
+        assert_eq!(classify("nft1TTTT77777zzz").to_string(), "Gilded Twilight Temple");
+        // The case variant of the primary letter is not another character
+        assert_eq!(classify("nft1ttttTTTTccc000").to_string(), "Cavernous Temple");
+    }
+
+    #[test]
+    fn dominant_letters_make_pure_biomes() {
+        assert_eq!(classify("nft1cccccccab"), DungeonType { primary: "Cave".to_string(), modifier: None });
+        assert_eq!(classify("nft1cccccccab").to_string(), "Cave");
+        assert_eq!(classify("0123").modifier, None);
+        assert_eq!(modifier_for('Z'), Some("Windswept"));
+        assert_eq!(modifier_for('-'), None);
+
+        let dungeon = dungeon();
+        assert_eq!(dungeon.biome, classify(NFT_ID));
+        assert_eq!(dungeon.biome.primary, dungeon.dungeon_type);
+    }
+}
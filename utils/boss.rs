@@ -0,0 +1,233 @@
+//! # Bosses
+//!
+//! Derives the unique boss guarding each dungeon from its NFT ID.
+//!
+//! - **Name**: Two syllables picked by the last two characters of the ID, followed by
+//!   an epithet of the boss's element (e.g., "Vorgath the Flame-Crowned").
+//! - **Element**: Matches the dungeon type (see `element_for_type`): fire in Hell and the
+//!   Lava Pits, frost in the Ice Cavern, and so on. Variant types share the element of
+//!   their base type.
+//! - **Strength**: The boss fights three levels above the dungeon; its hit points,
+//!   attack, and defense grow with that level (see `Boss::stats`).
+//! - **Ability**: A special ability picked by the third-to-last character of the ID,
+//!   from `ABILITIES`.
+//! - **Lair**: The boss waits in the largest room other than the entrance, on the floor
+//!   tile closest to the room center, so the room's marker stays visible.
+//!
+//! Bosses are derived on demand by `Dungeon::boss`, like traps, so saved dungeons need no
+//! extra fields. Plotted maps mark the boss with a diamond and ASCII maps with `&`.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::combat::Combatant;
+use super::dungeon::{Dungeon, RoomKind};
+use super::excavator::{base_dungeon_type, char_to_num};
+
+/// Levels the boss fights above its dungeon
+pub const BOSS_LEVEL_BONUS: u64 = 3;
+
+/// Special abilities a boss can wield
+pub const ABILITIES: [&str; 8] = [
+    "Summon Minions",
+    "Regeneration",
+    "Enrage",
+    "Life Drain",
+    "Shockwave",
+    "Petrifying Gaze",
+    "Blink",
+    "Elemental Breath",
+];
+
+/// First syllables of boss names
+const NAME_HEADS: [&str; 8] = ["Vor", "Kha", "Mal", "Zul", "Ith", "Gor", "Sae", "Bra"];
+/// Last syllables of boss names
+const NAME_TAILS: [&str; 8] = ["gath", "rix", "thas", "mora", "zeth", "dun", "lith", "kor"];
+
+/// Element a boss draws its power from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Element {
+    /// Hell, lava, and volcanoes.
+    Fire,
+    /// Ice caverns.
+    Frost,
+    /// Stone ruins, caves, mountains, and deserts.
+    Earth,
+    /// Oceans, waters, and swamps.
+    Water,
+    /// Toxic bogs, hives, and wastelands.
+    Poison,
+    /// Crypts and fallen kingdoms.
+    Shadow,
+    /// Temples.
+    Radiance,
+    /// Highlands and open grass.
+    Storm,
+    /// Forests and jungles.
+    Nature,
+    /// Everything else.
+    Arcane,
+}
+
+impl Element {
+    /// Epithet of bosses of this element
+    pub fn epithet(self) -> &'static str {
+        match self {
+            Element::Fire => "Flame-Crowned",
+            Element::Frost => "Winter's Heart",
+            Element::Earth => "Mountain-Breaker",
+            Element::Water => "Tide-Caller",
+            Element::Poison => "Blight-Bringer",
+            Element::Shadow => "Grave-Born",
+            Element::Radiance => "Sun-Touched",
+            Element::Storm => "Storm-Lord",
+            Element::Nature => "Root-Bound",
+            Element::Arcane => "Star-Eater",
+        }
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// The boss guarding a dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Boss {
+    /// Name with its element's epithet.
+    pub name: String,
+    /// Element matching the dungeon type.
+    pub element: Element,
+    /// Level the boss fights at.
+    pub level: u64,
+    /// Hit points.
+    pub hp: u64,
+    /// Damage dealt before the defender's defense.
+    pub attack: u64,
+    /// Damage absorbed from every hit taken.
+    pub defense: u64,
+    /// Special ability from `ABILITIES`.
+    pub ability: String,
+    /// Index of the room the boss guards.
+    pub room: usize,
+    /// Tile the boss stands on.
+    pub position: (i32, i32),
+}
+
+impl Boss {
+    /// Combat statistics of the boss
+    pub fn stats(&self) -> Combatant {
+        Combatant { name: self.name.clone(), hp: self.hp, attack: self.attack, defense: self.defense }
+    }
+}
+
+impl Dungeon {
+    /// The boss guarding this dungeon (see `boss`), or `None` without rooms.
+    pub fn boss(&self) -> Option<Boss> {
+        generate_boss(self)
+    }
+}
+
+/// Element of bosses in a dungeon of the given type
+pub fn element_for_type(dungeon_type: &str) -> Element {
+    match base_dungeon_type(dungeon_type) {
+        "Hell" | "Lava Pits" | "Volcanic Crater" => Element::Fire,
+        "Ice Cavern" => Element::Frost,
+        "Ancient Ruins" | "Cave" | "Mountain" | "Underground Tunnels" | "Desert" => Element::Earth,
+        "Ocean Depths" | "Water" | "Swamp" => Element::Water,
+        "Poison Swamp" | "Quagmire" | "Xeno Hive" | "Yellow Wasteland" => Element::Poison,
+        "Necropolis" | "Kingdom Ruins" | "Barrens" => Element::Shadow,
+        "Temple" => Element::Radiance,
+        "Zephyr Highlands" | "Grassland" => Element::Storm,
+        "Forest" | "Jungle" | "Rainforest" => Element::Nature,
+        _ => Element::Arcane,
+    }
+}
+
+/// Derive the boss of the dungeon from its NFT ID
+pub fn generate_boss(dungeon: &Dungeon) -> Option<Boss> {
+    // The largest room, preferring any other room to the entrance; ties go to the first
+    let largest = |entrance: bool| {
+        dungeon
+            .rooms
+            .iter()
+            .enumerate()
+            .filter(|(_, room)| entrance || room.kind != RoomKind::Entrance)
+            .rev()
+            .max_by_key(|(_, room)| room.tiles.len())
+    };
+    let (index, room) = largest(false).or_else(|| largest(true))?;
+
+    // Stand beside the center, on the closest walkable room tile
+    let distance = |(x, y): (i32, i32)| (x - room.center.0).pow(2) + (y - room.center.1).pow(2);
+    let position = room
+        .tiles
+        .iter()
+        .copied()
+        .filter(|&pos| pos != room.center && dungeon.grid.is_walkable(pos))
+        .min_by_key(|&pos| (distance(pos), pos))
+        .unwrap_or(room.center);
+
+    // Name and ability come from the last characters of the ID
+    let chars: Vec<usize> = dungeon.seed_id().chars().skip(4).map(|c| char_to_num(c) as usize).collect();
+    let from_end = |offset: usize| chars.len().checked_sub(offset).map_or(0, |i| chars[i]);
+    let element = element_for_type(&dungeon.dungeon_type);
+    let name = format!(
+        "{}{} the {}",
+        NAME_HEADS[from_end(2) % NAME_HEADS.len()],
+        NAME_TAILS[from_end(1) % NAME_TAILS.len()],
+        element.epithet()
+    );
+
+    let level = dungeon.level + BOSS_LEVEL_BONUS;
+    Some(Boss {
+        name,
+        element,
+        level,
+        hp: 20 + 10 * level,
+        attack: 4 + 2 * level,
+        defense: 1 + level,
+        ability: ABILITIES[from_end(3) % ABILITIES.len()].to_string(),
+        room: index,
+        position,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn bosses_guard_the_largest_room() {
+        let dungeon = dungeon();
+        let boss = dungeon.boss().unwrap();
+        let lair = &dungeon.rooms[boss.room];
+        assert_ne!(lair.kind, RoomKind::Entrance);
+        assert!(dungeon.rooms.iter().filter(|room| room.kind != RoomKind::Entrance).all(|room| room.tiles.len() <= lair.tiles.len()));
+        assert!(lair.tiles.contains(&boss.position) && boss.position != lair.center);
+        assert!(dungeon.grid.is_walkable(boss.position));
+        assert_eq!(dungeon.boss(), Some(boss));
+    }
+
+    #[test]
+    fn bosses_follow_the_dungeon_type_and_level() {
+        let dungeon = dungeon();
+        let boss = dungeon.boss().unwrap();
+        // A Temple guarded from level 3
+        assert_eq!(boss.element, Element::Radiance);
+        assert!(boss.name.ends_with(" the Sun-Touched"));
+        assert_eq!(boss.level, dungeon.level + BOSS_LEVEL_BONUS);
+        assert_eq!(boss.stats(), Combatant { name: boss.name.clone(), hp: 20 + 10 * boss.level, attack: 4 + 2 * boss.level, defense: 1 + boss.level });
+        assert!(ABILITIES.contains(&boss.ability.as_str()));
+
+        assert_eq!(element_for_type("Twilight Temple"), Element::Radiance);
+        assert_eq!(element_for_type("Lava Pits"), Element::Fire);
+        assert_eq!(element_for_type("Unknown"), Element::Arcane);
+
+        let mut empty = dungeon;
+        empty.rooms.clear();
+        assert_eq!(empty.boss(), None);
+    }
+}
@@ -0,0 +1,305 @@
+//! # Dungeon Cache
+//!
+//! Keeps excavated dungeons around so parsing the same NFT ID again (as the HTTP server
+//! does for every render of a popular NFT) costs a lookup instead of a full excavation.
+//!
+//! - **Memory**: The `capacity` most recently used dungeons are held in an LRU, the
+//!   same one the stages of a `Pipeline` keep their results in (see `pipeline`).
+//! - **Disk**: With `DungeonCache::with_dir`, dungeons are also written as JSON (see
+//!   `Dungeon::to_json`), one file per ID named after the FNV-1a hash of the `nft_id`
+//!   and of the `GenerationConfig`, so they survive restarts.
+//!
+//! Callers sharing one cache behind a lock look a dungeon up with `DungeonCache::get`,
+//! and on a miss excavate it with the `CacheMiss` of `DungeonCache::miss` outside the
+//! lock, then store it with `DungeonCache::insert`, so no request waits for another's
+//! excavation. `DungeonCache::get_or_generate` does all three in one call.
+//!
+//! Changing the config with `DungeonCache::set_config` empties the memory cache, and
+//! files written under another config are never read, since their names differ. The
+//! disk cache is best effort: unreadable files are regenerated and failed writes are
+//! ignored. Parse errors are never cached.
+
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::hash::Hash;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use super::config::GenerationConfig;
+use super::dungeon::Dungeon;
+use super::error::DungeonError;
+use super::excavator::parse_nft_id_with_config;
+use super::seed::seed_from_nft_id;
+
+/// Dungeons held in memory by `DungeonCache::default`
+pub const DEFAULT_CACHE_CAPACITY: usize = 64;
+
+/// LRU cache of excavated dungeons, optionally backed by a directory of JSON files.
+#[derive(Debug, Clone)]
+pub struct DungeonCache {
+    config: GenerationConfig,
+    /// Hash of `config`, part of every file name.
+    config_hash: u64,
+    entries: Lru<String, Arc<Dungeon>>,
+    dir: Option<PathBuf>,
+}
+
+impl Default for DungeonCache {
+    fn default() -> Self {
+        DungeonCache::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl DungeonCache {
+    /// An in-memory cache of up to `capacity` dungeons generated with the default config
+    pub fn new(capacity: usize) -> DungeonCache {
+        let config = GenerationConfig::default();
+        DungeonCache {
+            config_hash: config_hash(&config),
+            config,
+            entries: Lru::new(capacity),
+            dir: None,
+        }
+    }
+
+    /// Also keep every generated dungeon as a JSON file in `dir`, creating it if needed
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> io::Result<DungeonCache> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        self.dir = Some(dir);
+        Ok(self)
+    }
+
+    /// Generate dungeons with `config` instead of the default
+    pub fn with_config(mut self, config: GenerationConfig) -> DungeonCache {
+        self.set_config(config);
+        self
+    }
+
+    /// Config the cached dungeons were generated with
+    pub fn config(&self) -> &GenerationConfig {
+        &self.config
+    }
+
+    /// Switch to `config`, dropping the dungeons in memory when it differs from the current one
+    pub fn set_config(&mut self, config: GenerationConfig) {
+        if config != self.config {
+            self.config_hash = config_hash(&config);
+            self.config = config;
+            self.clear();
+        }
+    }
+
+    /// Number of dungeons held in memory
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no dungeon is held in memory
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop every dungeon held in memory; files on disk are kept
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The dungeon of `nft_id`, from memory, then disk, then a fresh excavation
+    pub fn get_or_generate(&mut self, nft_id: &str) -> Result<Arc<Dungeon>, DungeonError> {
+        if let Some(dungeon) = self.get(nft_id) {
+            return Ok(dungeon);
+        }
+        let miss = self.miss(nft_id);
+        let dungeon = miss.load_or_generate()?;
+        Ok(self.insert(&miss, dungeon))
+    }
+
+    /// The dungeon of `nft_id` if it is held in memory, marking it as the most recently used
+    pub fn get(&mut self, nft_id: &str) -> Option<Arc<Dungeon>> {
+        self.entries.get(nft_id).cloned()
+    }
+
+    /// What loading or excavating the dungeon of `nft_id` needs, usable without the cache
+    pub fn miss(&self, nft_id: &str) -> CacheMiss {
+        CacheMiss { nft_id: nft_id.to_string(), config: self.config.clone(), path: self.path_for(nft_id) }
+    }
+
+    /// Hold the `dungeon` loaded for `miss` as the most recently used, and return it
+    ///
+    /// A dungeon another caller stored meanwhile is kept and returned instead, and one
+    /// generated under a config the cache has since left is returned but not held.
+    pub fn insert(&mut self, miss: &CacheMiss, dungeon: Dungeon) -> Arc<Dungeon> {
+        if miss.config != self.config {
+            return Arc::new(dungeon);
+        }
+        if let Some(held) = self.get(&miss.nft_id) {
+            return held;
+        }
+        let dungeon = Arc::new(dungeon);
+        self.entries.insert(miss.nft_id.clone(), Arc::clone(&dungeon));
+        dungeon
+    }
+
+    // File holding the dungeon of `nft_id`, when the cache has a directory
+    fn path_for(&self, nft_id: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        Some(dir.join(format!("{:016x}-{:016x}.json", seed_from_nft_id(nft_id), self.config_hash)))
+    }
+}
+
+/// A dungeon missing from a `DungeonCache`, to load or excavate without holding the cache.
+#[derive(Debug, Clone)]
+pub struct CacheMiss {
+    nft_id: String,
+    config: GenerationConfig,
+    /// File of the dungeon, when the cache has a directory.
+    path: Option<PathBuf>,
+}
+
+impl CacheMiss {
+    /// The dungeon from its file, or freshly excavated and written to the file
+    pub fn load_or_generate(&self) -> Result<Dungeon, DungeonError> {
+        if let Some(dungeon) = self.path.as_deref().and_then(|path| load(path, &self.nft_id)) {
+            return Ok(dungeon);
+        }
+        let dungeon = parse_nft_id_with_config(&self.nft_id, &self.config)?;
+        if let (Some(path), Ok(json)) = (&self.path, dungeon.to_json()) {
+            let _ = fs::write(path, json);
+        }
+        Ok(dungeon)
+    }
+}
+
+/// Values by key, holding the `capacity` most recently used and evicting the rest.
+#[derive(Debug, Clone)]
+pub(crate) struct Lru<K, V> {
+    capacity: usize,
+    entries: HashMap<K, V>,
+    /// Keys of `entries` from the least to the most recently used.
+    order: VecDeque<K>,
+}
+
+impl<K: Clone + Eq + Hash, V> Lru<K, V> {
+    /// An empty LRU holding up to `capacity` values
+    pub(crate) fn new(capacity: usize) -> Lru<K, V> {
+        Lru { capacity, entries: HashMap::new(), order: VecDeque::new() }
+    }
+
+    /// The value of `key`, marking it as the most recently used
+    pub(crate) fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if let Some(index) = self.order.iter().position(|held| held.borrow() == key) {
+            let held = self.order.remove(index)?;
+            self.order.push_back(held);
+        }
+        self.entries.get(key)
+    }
+
+    /// Hold `value` as the most recently used, evicting the least recently used beyond
+    /// capacity
+    pub(crate) fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.order.retain(|held| *held != key);
+        self.order.push_back(key.clone());
+        self.entries.insert(key, value);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Number of values held
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no value is held
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop every value
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+// FNV-1a hash of the config's JSON, naming the files generated with it
+pub(crate) fn config_hash(config: &GenerationConfig) -> u64 {
+    seed_from_nft_id(&serde_json::to_string(config).unwrap_or_default())
+}
+
+// A dungeon file, unless it is missing, unreadable, or holds another ID with the same hash
+fn load(path: &Path, nft_id: &str) -> Option<Dungeon> {
+    let dungeon = Dungeon::from_json(&fs::read_to_string(path).ok()?).ok()?;
+    (dungeon.nft_id == nft_id).then_some(dungeon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::NFT_ID;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    #[test]
+    fn repeated_ids_are_served_from_memory() {
+        let mut cache = DungeonCache::new(2);
+        let first = cache.get_or_generate(NFT_ID).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.get_or_generate(NFT_ID).unwrap()));
+        assert_eq!(*first, parse_nft_id_with_config(NFT_ID, &GenerationConfig::default()).unwrap());
+        assert!(cache.get_or_generate("xch1abcdef").is_err());
+        assert_eq!(cache.len(), 1);
+
+        // Misses excavated outside the cache keep the first dungeon stored, and skip
+        // storing one generated under a config the cache has since left
+        let miss = cache.miss(NFT_ID);
+        assert!(Arc::ptr_eq(&first, &cache.insert(&miss, miss.load_or_generate().unwrap())));
+        let other = generate_nft_id_with(&GenerateOptions { seed: Some(4), ..GenerateOptions::default() }).unwrap();
+        let stale = cache.miss(&other);
+        cache.set_config(GenerationConfig { level_band: 500, ..GenerationConfig::default() });
+        cache.insert(&stale, stale.load_or_generate().unwrap());
+        assert!(cache.get(&other).is_none());
+    }
+
+    #[test]
+    fn the_least_recently_used_dungeon_is_evicted() {
+        let mut cache = DungeonCache::new(2);
+        let id = |seed| generate_nft_id_with(&GenerateOptions { seed: Some(seed), ..GenerateOptions::default() }).unwrap();
+        let (a, b, c) = (id(1), id(2), id(3));
+        let first = cache.get_or_generate(&a).unwrap();
+        cache.get_or_generate(&b).unwrap();
+        cache.get_or_generate(&a).unwrap();
+        cache.get_or_generate(&c).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&a).is_some() && cache.get(&b).is_none());
+        assert!(Arc::ptr_eq(&first, &cache.get_or_generate(&a).unwrap()));
+    }
+
+    #[test]
+    fn config_changes_invalidate_memory_and_disk() {
+        let dir = std::env::temp_dir().join(format!("chia_dungeon_cache_{}", std::process::id()));
+        let mut cache = DungeonCache::new(4).with_dir(&dir).unwrap();
+        let dungeon = cache.get_or_generate(NFT_ID).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // A fresh cache over the same directory loads the file instead of excavating
+        let mut reloaded = DungeonCache::new(4).with_dir(&dir).unwrap();
+        assert_eq!(*reloaded.get_or_generate(NFT_ID).unwrap(), *dungeon);
+
+        let config = GenerationConfig { level_band: 500, ..GenerationConfig::default() };
+        reloaded.set_config(config.clone());
+        assert!(reloaded.is_empty());
+        assert_eq!(*reloaded.get_or_generate(NFT_ID).unwrap(), parse_nft_id_with_config(NFT_ID, &config).unwrap());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+//! # Campaigns
+//!
+//! Chains the dungeons of an ordered list of NFT IDs into one campaign: the exit of each
+//! dungeon leads on to the entrance of the next.
+//!
+//! - **Overview**: `Campaign::overview` fuses the stages side by side, left to right, with
+//!   `Dungeon::merge`, so every link is a tunnel from one stage's exit room to the next
+//!   stage's entrance room.
+//! - **Manifest**: `Campaign::manifest` lists every stage with its attributes, where its
+//!   rooms start in the overview, and where its entrance and exit lie in overview
+//!   coordinates; `CampaignManifest::to_json` writes it for game clients.
+
+use serde::{Deserialize, Serialize};
+use super::dungeon::Dungeon;
+use super::config::GenerationConfig;
+use super::error::DungeonError;
+use super::excavator::parse_nft_id_with_config;
+use super::fusion::MergeStrategy;
+
+/// Default file name of the campaign manifest, written alongside the overview map
+pub const MANIFEST_FILE: &str = "campaign.json";
+
+/// An ordered chain of dungeons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Campaign {
+    /// The dungeons in campaign order.
+    pub stages: Vec<Dungeon>,
+    /// Every stage fused into one map; `None` for a campaign without stages.
+    pub overview: Option<Dungeon>,
+}
+
+/// One dungeon of a campaign, as listed in the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CampaignStage {
+    /// Position in the campaign, counting from 1.
+    pub stage: usize,
+    /// NFT ID of the dungeon.
+    pub nft_id: String,
+    /// Dungeon type.
+    pub dungeon_type: String,
+    /// Dungeon level.
+    pub level: u64,
+    /// Number of rooms on the top floor.
+    pub rooms: usize,
+    /// Total room area.
+    pub area: u64,
+    /// Index in the overview's rooms of the stage's first room.
+    pub first_room: usize,
+    /// Center of the entrance room in overview coordinates.
+    pub entrance: Option<(i32, i32)>,
+    /// Center of the exit room in overview coordinates.
+    pub exit: Option<(i32, i32)>,
+    /// NFT ID of the stage the exit leads to; `None` for the last stage.
+    pub next: Option<String>,
+}
+
+/// Summary of a campaign for game clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CampaignManifest {
+    /// The stages in campaign order.
+    pub stages: Vec<CampaignStage>,
+    /// Rooms across every stage.
+    pub rooms: usize,
+    /// Room area across every stage.
+    pub area: u64,
+}
+
+impl CampaignManifest {
+    /// Serializes the manifest as pretty-printed JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Reloads a manifest previously written by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<CampaignManifest> {
+        serde_json::from_str(json)
+    }
+}
+
+impl Campaign {
+    /// Chain `stages` in order, fusing them into the overview
+    pub fn new(stages: Vec<Dungeon>) -> Campaign {
+        let overview = stages.split_first().map(|(first, rest)| {
+            rest.iter().fold(first.clone(), |overview, stage| overview.merge(stage, MergeStrategy::SideBySide))
+        });
+        Campaign { stages, overview }
+    }
+
+    /// Decode every NFT ID with `config` and chain the dungeons, failing on the first
+    /// invalid ID
+    pub fn from_nft_ids<S: AsRef<str>>(nft_ids: &[S], config: &GenerationConfig) -> Result<Campaign, DungeonError> {
+        let stages = nft_ids.iter().map(|nft_id| parse_nft_id_with_config(nft_id.as_ref(), config)).collect::<Result<Vec<_>, _>>()?;
+        Ok(Campaign::new(stages))
+    }
+
+    /// Manifest listing every stage and its place in the overview
+    pub fn manifest(&self) -> CampaignManifest {
+        let overview_rooms = self.overview.as_ref().map(|overview| overview.rooms.as_slice()).unwrap_or_default();
+        let mut first_room = 0;
+        let stages = self
+            .stages
+            .iter()
+            .enumerate()
+            .map(|(index, dungeon)| {
+                let rooms = overview_rooms.get(first_room..first_room + dungeon.rooms.len()).unwrap_or_default();
+                let stage = CampaignStage {
+                    stage: index + 1,
+                    nft_id: dungeon.nft_id.clone(),
+                    dungeon_type: dungeon.dungeon_type.clone(),
+                    level: dungeon.level,
+                    rooms: dungeon.rooms.len(),
+                    area: dungeon.area_size,
+                    first_room,
+                    entrance: rooms.first().map(|room| room.center),
+                    exit: rooms.last().map(|room| room.center),
+                    next: self.stages.get(index + 1).map(|next| next.nft_id.clone()),
+                };
+                first_room += dungeon.rooms.len();
+                stage
+            })
+            .collect();
+        CampaignManifest {
+            stages,
+            rooms: self.stages.iter().map(|dungeon| dungeon.rooms.len()).sum(),
+            area: self.stages.iter().fold(0, |area, dungeon| area.saturating_add(dungeon.area_size)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    #[test]
+    fn stages_link_exit_to_entrance_in_order() {
+        let nft_ids: Vec<String> =
+            (1..=3).map(|seed| generate_nft_id_with(&GenerateOptions { seed: Some(seed), ..GenerateOptions::default() }).unwrap()).collect();
+        let campaign = Campaign::from_nft_ids(&nft_ids, &GenerationConfig::default()).unwrap();
+        let overview = campaign.overview.as_ref().unwrap();
+        assert!(overview.is_fully_connected() && overview.is_solvable());
+
+        let manifest = campaign.manifest();
+        assert_eq!(manifest.stages.len(), 3);
+        assert_eq!(manifest.rooms, overview.rooms.len());
+        for (stage, nft_id) in manifest.stages.iter().zip(&nft_ids) {
+            assert_eq!(&stage.nft_id, nft_id);
+            assert_eq!(stage.entrance, Some(overview.rooms[stage.first_room].center));
+        }
+        // Each exit has a tunnel to the next stage's entrance
+        for pair in manifest.stages.windows(2) {
+            assert_eq!(pair[0].next.as_ref(), Some(&pair[1].nft_id));
+            let link = [pair[0].exit.unwrap(), pair[1].entrance.unwrap()];
+            assert!(overview.tunnels.iter().any(|tunnel| [tunnel.first(), tunnel.last()] == [Some(&link[0]), Some(&link[1])]));
+        }
+        assert_eq!(manifest.stages[2].next, None);
+        assert_eq!(CampaignManifest::from_json(&manifest.to_json().unwrap()).unwrap(), manifest);
+
+        // Every stage is decoded with the given config
+        let salted = GenerationConfig { collection_salt: Some("other".to_string()), ..GenerationConfig::default() };
+        let campaign = Campaign::from_nft_ids(&nft_ids, &salted).unwrap();
+        for (stage, nft_id) in campaign.stages.iter().zip(&nft_ids) {
+            assert_eq!(*stage, parse_nft_id_with_config(nft_id, &salted).unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_ids_and_empty_campaigns() {
+        assert_eq!(Campaign::from_nft_ids(&["xch1abcdef"], &GenerationConfig::default()).err(), Some(DungeonError::InvalidPrefix));
+        let empty = Campaign::new(Vec::new());
+        assert_eq!(empty.overview, None);
+        assert_eq!(empty.manifest(), CampaignManifest { stages: Vec::new(), rooms: 0, area: 0 });
+    }
+}
@@ -0,0 +1,269 @@
+//! # Chia NFT ID Decoding
+//!
+//! Real Chia NFT IDs are bech32m addresses: the "nft" human-readable part, the
+//! "1" separator, then the 32-byte launcher ID encoded as 5-bit groups followed
+//! by a 6-character checksum.
+//!
+//! ## Functions:
+//! - `decode_nft_id`: Validates the checksum and returns the 32-byte launcher ID.
+//! - `encode_launcher_id`: Encodes a launcher ID back into an "nft1..." ID.
+//! - `detect_format`: Tells real bech32m IDs apart from synthetic base62 IDs, so
+//!   `parse_nft_id` can fall back to the synthetic format.
+//! - `decode_bech32m` / `encode_bech32m`: The same for any 32-byte value under another
+//!   prefix, such as "xch" wallet addresses and "did:chia:" DIDs (see `ownership`).
+
+use std::fmt;
+
+/// Human-readable part of Chia NFT IDs.
+pub const NFT_HRP: &str = "nft";
+/// Human-readable part of Chia wallet addresses, which encode a puzzle hash.
+pub const ADDRESS_HRP: &str = "xch";
+/// Human-readable part of Chia DIDs, which encode the DID's launcher ID.
+pub const DID_HRP: &str = "did:chia:";
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+const CHECKSUM_LEN: usize = 6;
+
+/// Errors returned when decoding a bech32m NFT ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bech32Error {
+    /// The ID mixes upper and lower case characters.
+    MixedCase,
+    /// The ID has no "1" separator.
+    MissingSeparator,
+    /// The human-readable part is not the expected one ("nft" for NFT IDs).
+    InvalidHrp(String),
+    /// A data character is outside the bech32 alphabet.
+    InvalidChar(char),
+    /// The checksum does not match the bech32m constant.
+    InvalidChecksum,
+    /// The payload does not decode to a 32-byte launcher ID.
+    InvalidLength(usize),
+    /// The leftover padding bits are not zero.
+    InvalidPadding,
+}
+
+impl fmt::Display for Bech32Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bech32Error::MixedCase => write!(f, "NFT ID mixes upper and lower case characters."),
+            Bech32Error::MissingSeparator => write!(f, "NFT ID has no '1' separator."),
+            Bech32Error::InvalidHrp(hrp) => write!(f, "Unexpected '{}' prefix.", hrp),
+            Bech32Error::InvalidChar(c) => write!(f, "Character '{}' is not valid bech32.", c),
+            Bech32Error::InvalidChecksum => write!(f, "Invalid bech32m checksum."),
+            Bech32Error::InvalidLength(len) => write!(f, "Expected a 32-byte launcher ID, found {} bytes.", len),
+            Bech32Error::InvalidPadding => write!(f, "Invalid bech32 padding."),
+        }
+    }
+}
+
+impl std::error::Error for Bech32Error {}
+
+/// Encoding of an NFT ID accepted by `parse_nft_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NftIdFormat {
+    /// A checksummed bech32m Chia NFT ID.
+    Bech32m,
+    /// A synthetic "nft1" + base62 ID, such as those from `generate_nft_id`.
+    Synthetic,
+}
+
+/// Detects whether `nft_id` is a valid bech32m Chia NFT ID or falls back to the synthetic format.
+pub fn detect_format(nft_id: &str) -> NftIdFormat {
+    match decode_nft_id(nft_id) {
+        Ok(_) => NftIdFormat::Bech32m,
+        Err(_) => NftIdFormat::Synthetic,
+    }
+}
+
+/// Decodes a bech32m NFT ID into its 32-byte launcher ID.
+pub fn decode_nft_id(nft_id: &str) -> Result<[u8; 32], Bech32Error> {
+    decode_bech32m(nft_id, NFT_HRP)
+}
+
+/// Decodes a bech32m string with the human-readable part `expected_hrp` into its 32 bytes.
+pub fn decode_bech32m(encoded: &str, expected_hrp: &str) -> Result<[u8; 32], Bech32Error> {
+    let has_lower = encoded.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = encoded.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(Bech32Error::MixedCase);
+    }
+    let encoded = encoded.to_ascii_lowercase();
+
+    let separator = encoded.rfind('1').ok_or(Bech32Error::MissingSeparator)?;
+    let (hrp, data) = (&encoded[..separator], &encoded[separator + 1..]);
+    if hrp != expected_hrp {
+        return Err(Bech32Error::InvalidHrp(hrp.to_string()));
+    }
+
+    // Map each data character to its 5-bit value
+    let mut values = Vec::with_capacity(data.len());
+    for c in data.chars() {
+        let value = CHARSET
+            .iter()
+            .position(|&b| b as char == c)
+            .ok_or(Bech32Error::InvalidChar(c))?;
+        values.push(value as u8);
+    }
+
+    if values.len() < CHECKSUM_LEN {
+        return Err(Bech32Error::InvalidChecksum);
+    }
+    let mut checked = hrp_expand(hrp);
+    checked.extend_from_slice(&values);
+    if polymod(&checked) != BECH32M_CONST {
+        return Err(Bech32Error::InvalidChecksum);
+    }
+
+    let payload = convert_bits(&values[..values.len() - CHECKSUM_LEN], 5, 8, false)?;
+    payload
+        .as_slice()
+        .try_into()
+        .map_err(|_| Bech32Error::InvalidLength(payload.len()))
+}
+
+/// Encodes a 32-byte launcher ID as a bech32m "nft1..." ID.
+pub fn encode_launcher_id(launcher_id: &[u8; 32]) -> String {
+    encode_bech32m(launcher_id, NFT_HRP)
+}
+
+/// Encodes 32 bytes as a bech32m string with the human-readable part `hrp`.
+pub fn encode_bech32m(bytes: &[u8; 32], hrp: &str) -> String {
+    // Regrouping whole bytes into 5-bit groups with padding cannot fail
+    let mut values = convert_bits(bytes, 8, 5, true).unwrap_or_default();
+
+    let mut checked = hrp_expand(hrp);
+    checked.extend_from_slice(&values);
+    checked.extend_from_slice(&[0; CHECKSUM_LEN]);
+    let checksum = polymod(&checked) ^ BECH32M_CONST;
+    for i in 0..CHECKSUM_LEN {
+        values.push(((checksum >> (5 * (5 - i))) & 31) as u8);
+    }
+
+    let mut encoded = format!("{}1", hrp);
+    encoded.extend(values.iter().map(|&v| CHARSET[v as usize] as char));
+    encoded
+}
+
+/// Formats a launcher ID as lowercase hex.
+pub fn launcher_id_hex(launcher_id: &[u8; 32]) -> String {
+    launcher_id.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// BCH checksum over GF(32) as defined by BIP-173
+fn polymod(values: &[u8]) -> u32 {
+    const GENERATORS: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+    let mut chk: u32 = 1;
+    for &value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+// Expand the human-readable part into values for checksum computation
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|b| b & 31));
+    expanded
+}
+
+// Regroup a sequence of `from`-bit values into `to`-bit values
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, Bech32Error> {
+    let mut acc: u32 = 0;
+    let mut bits: u32 = 0;
+    let max_value = (1 << to) - 1;
+    let mut result = Vec::new();
+
+    for &value in data {
+        acc = (acc << from) | value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            result.push(((acc >> bits) & max_value) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            result.push(((acc << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || ((acc << (to - bits)) & max_value) != 0 {
+        return Err(Bech32Error::InvalidPadding);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::{parse_chia_nft_id, parse_nft_id};
+    use super::super::fixtures;
+    use super::*;
+
+    // Launcher ID 0x00, 0x01, ..., 0x1f, encoded with the BIP-350 reference implementation
+    const NFT_ID: &str = "nft1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0s9s222c";
+
+    fn ascending() -> [u8; 32] {
+        std::array::from_fn(|i| i as u8)
+    }
+
+    #[test]
+    fn known_good_ids_decode_to_their_launcher_ids() {
+        assert_eq!(decode_nft_id(NFT_ID), Ok(ascending()));
+        assert_eq!(encode_launcher_id(&ascending()), NFT_ID);
+        assert_eq!(launcher_id_hex(&ascending()), "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f");
+        assert_eq!(detect_format(NFT_ID), NftIdFormat::Bech32m);
+    }
+
+    #[test]
+    fn bip350_vectors_have_valid_checksums() {
+        for vector in ["a1lqfn3a", "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx"] {
+            let (hrp, data) = vector.rsplit_once('1').unwrap();
+            let mut values = hrp_expand(hrp);
+            values.extend(data.bytes().map(|c| CHARSET.iter().position(|&b| b == c).unwrap() as u8));
+            assert_eq!(polymod(&values), BECH32M_CONST, "{}", vector);
+        }
+    }
+
+    #[test]
+    fn corrupted_checksums_are_rejected() {
+        let mut corrupted = NFT_ID.to_string();
+        corrupted.replace_range(10..11, "p");
+        assert_eq!(decode_nft_id(&corrupted), Err(Bech32Error::InvalidChecksum));
+        let truncated = &NFT_ID[..NFT_ID.len() - 1];
+        assert_eq!(decode_nft_id(truncated), Err(Bech32Error::InvalidChecksum));
+        assert_eq!(detect_format(&corrupted), NftIdFormat::Synthetic);
+        assert_eq!(decode_nft_id("nft1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0s9s222b"), Err(Bech32Error::InvalidChar('b')));
+    }
+
+    #[test]
+    fn the_human_readable_part_is_checked_case_insensitively() {
+        assert_eq!(decode_nft_id(&NFT_ID.to_ascii_uppercase()), Ok(ascending()));
+        assert_eq!(decode_nft_id(&NFT_ID.replacen('n', "N", 1)), Err(Bech32Error::MixedCase));
+        assert_eq!(decode_nft_id("qqqsyqcyq5rqwzqfpg"), Err(Bech32Error::MissingSeparator));
+        let address = "xch14w46h2at4w46h2at4w46h2at4w46h2at4w46h2at4w46h2at4w4ss4hlxt";
+        assert_eq!(decode_nft_id(address), Err(Bech32Error::InvalidHrp("xch".to_string())));
+        // Wallet addresses and DIDs share the encoding under their own prefix
+        let puzzle_hash = decode_bech32m(address, ADDRESS_HRP).unwrap();
+        assert_eq!(encode_bech32m(&puzzle_hash, ADDRESS_HRP), address);
+        let did = encode_bech32m(&ascending(), DID_HRP);
+        assert!(did.starts_with("did:chia:1"));
+        assert_eq!(decode_bech32m(&did, DID_HRP), Ok(ascending()));
+    }
+
+    #[test]
+    fn real_ids_record_their_launcher_id_and_synthetic_ids_do_not() {
+        let dungeon = parse_chia_nft_id(NFT_ID).unwrap();
+        assert_eq!(dungeon.launcher_id.as_deref(), Some(launcher_id_hex(&ascending()).as_str()));
+        assert_eq!(parse_nft_id(fixtures::NFT_ID).unwrap().launcher_id, None);
+        assert!(parse_chia_nft_id(fixtures::NFT_ID).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! # Turn-Based Combat
+//!
+//! Resolves a fight when the player walks into a monster in explore mode.
+//!
+//! - **Monsters**: `5 + 3 * level` hit points, `2 + level` attack, and `level / 2` defense.
+//! - **Player**: `3 + dungeon level` attack and `1 + dungeon level / 2` defense, each raised
+//!   by one per `GOLD_PER_GEAR` gold carried (better gear). Hit points come from `Player`.
+//! - **Turns**: The player strikes first, then the two sides alternate. Every hit deals the
+//!   attacker's attack minus the defender's defense, but at least 1.
+//! - **Potions**: When the monster's next hit would be fatal, the player drinks a potion
+//!   instead of attacking, if they carry one.
+//!
+//! Fights are fully deterministic, and every turn is recorded in the `CombatReport` log.
+//! A defeated player is respawned by the explorer (see `explore`).
+
+use serde::{Deserialize, Serialize};
+use super::inventory::Player;
+use super::spawn::MonsterSpawn;
+
+/// Gold carried per point of extra player attack and defense
+pub const GOLD_PER_GEAR: u64 = 100;
+
+/// Fighting statistics of one side of a fight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Combatant {
+    /// Name shown in the combat log.
+    pub name: String,
+    /// Remaining hit points.
+    pub hp: u64,
+    /// Damage dealt before the defender's defense.
+    pub attack: u64,
+    /// Damage absorbed from every hit taken.
+    pub defense: u64,
+}
+
+/// How a fight ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombatOutcome {
+    /// The monster was slain.
+    Victory,
+    /// The player was slain.
+    Defeat,
+}
+
+/// Outcome and turn-by-turn log of a fight.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CombatReport {
+    /// How the fight ended.
+    pub outcome: CombatOutcome,
+    /// One line per turn, in order.
+    pub log: Vec<String>,
+}
+
+/// Statistics of a spawned monster
+pub fn monster_stats(monster: &MonsterSpawn) -> Combatant {
+    Combatant {
+        name: monster.species.clone(),
+        hp: 5 + 3 * monster.level,
+        attack: 2 + monster.level,
+        defense: monster.level / 2,
+    }
+}
+
+/// Statistics of the player in a dungeon of the given level
+pub fn player_stats(player: &Player, dungeon_level: u64) -> Combatant {
+    let gear = player.inventory.gold / GOLD_PER_GEAR;
+    Combatant {
+        name: "You".to_string(),
+        hp: player.hp,
+        attack: 3 + dungeon_level + gear,
+        defense: 1 + dungeon_level / 2 + gear,
+    }
+}
+
+/// Damage one hit from `attacker` deals to `defender`
+pub fn hit_damage(attacker: &Combatant, defender: &Combatant) -> u64 {
+    attacker.attack.saturating_sub(defender.defense).max(1)
+}
+
+/// Fight `monster` to the death, updating the player's hit points and potions
+pub fn fight(player: &mut Player, dungeon_level: u64, monster: &MonsterSpawn) -> CombatReport {
+    let mut hero = player_stats(player, dungeon_level);
+    let mut foe = monster_stats(monster);
+    let mut log = vec![format!("A level {} {} attacks!", monster.level, foe.name)];
+
+    let outcome = loop {
+        // Player turn: drink a potion if the next hit would kill, otherwise attack
+        let incoming = hit_damage(&foe, &hero);
+        if incoming >= hero.hp && player.drink_potion() {
+            hero.hp = player.hp;
+            log.push(format!("You drink a potion ({} HP).", hero.hp));
+        } else {
+            let damage = hit_damage(&hero, &foe);
+            foe.hp = foe.hp.saturating_sub(damage);
+            log.push(format!("You hit the {} for {} damage ({} HP left).", foe.name, damage, foe.hp));
+            if foe.hp == 0 {
+                log.push(format!("The {} is slain.", foe.name));
+                break CombatOutcome::Victory;
+            }
+        }
+
+        // Monster turn
+        hero.hp = hero.hp.saturating_sub(incoming);
+        player.hp = hero.hp;
+        log.push(format!("The {} hits you for {} damage ({} HP left).", foe.name, incoming, hero.hp));
+        if hero.hp == 0 {
+            log.push("You are slain.".to_string());
+            break CombatOutcome::Defeat;
+        }
+    };
+
+    CombatReport { outcome, log }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::inventory::{Inventory, STARTING_HP};
+    use super::*;
+
+    fn monster(level: u64) -> MonsterSpawn {
+        MonsterSpawn { species: "Slime".to_string(), position: (0, 0), level }
+    }
+
+    #[test]
+    fn stats_grow_with_levels_and_gear() {
+        assert_eq!(monster_stats(&monster(4)), Combatant { name: "Slime".to_string(), hp: 17, attack: 6, defense: 2 });
+        let mut player = Player::default();
+        assert_eq!(player_stats(&player, 4), Combatant { name: "You".to_string(), hp: STARTING_HP, attack: 7, defense: 3 });
+        player.inventory.gold = 2 * GOLD_PER_GEAR + 50;
+        let geared = player_stats(&player, 4);
+        assert_eq!((geared.attack, geared.defense), (9, 5));
+    }
+
+    #[test]
+    fn hits_deal_at_least_one_damage() {
+        let (weak, strong) = (monster_stats(&monster(0)), monster_stats(&monster(30)));
+        assert_eq!(hit_damage(&weak, &strong), 1);
+        assert_eq!(hit_damage(&strong, &weak), strong.attack);
+    }
+
+    #[test]
+    fn the_player_strikes_first_and_keeps_the_damage_taken() {
+        let mut player = Player::default();
+        let report = fight(&mut player, 1, &monster(1));
+        assert_eq!(report.outcome, CombatOutcome::Victory);
+        assert_eq!(report.log.len(), 5);
+        assert_eq!(report.log[0], "A level 1 Slime attacks!");
+        assert_eq!(report.log[4], "The Slime is slain.");
+        assert_eq!(player.hp, STARTING_HP - 2);
+    }
+
+    #[test]
+    fn potions_are_drunk_before_a_fatal_hit() {
+        let mut player = Player::default();
+        assert_eq!(fight(&mut player, 1, &monster(20)).outcome, CombatOutcome::Defeat);
+        assert_eq!(player.hp, 0);
+
+        let mut player = Player { inventory: Inventory { potions: 1, ..Inventory::default() }, ..Player::default() };
+        let report = fight(&mut player, 1, &monster(20));
+        assert_eq!(report.outcome, CombatOutcome::Defeat);
+        assert_eq!(report.log[1], format!("You drink a potion ({} HP).", STARTING_HP));
+        assert_eq!(player.inventory.potions, 0);
+        assert_eq!(report.log.last().map(String::as_str), Some("You are slain."));
+    }
+}
@@ -0,0 +1,271 @@
+//! # Generation Config
+//!
+//! Tunable parameters for dungeon generation, accepted by `parse_nft_id_with_config`.
+//!
+//! `GenerationConfig::default()` reproduces `parse_nft_id` exactly, so existing NFT IDs
+//! keep their dungeons; changing any field yields a different (but still deterministic)
+//! dungeon for the same ID.
+//!
+//! - **Algorithm**: Rooms joined by tunnels, open caves dug from a noise field (see
+//!   `noise`), or rooms embedded in a maze (see `maze`) (`algorithm`). The room
+//!   parameters below shape the rooms only, and mazes ignore the tunnel style.
+//! - **Room count**: `min_rooms + value % (max_rooms - min_rooms + 1)`, where `value` is the
+//!   base62 room-count character.
+//! - **Room size**: `size_base + round(sqrt(value) * size_multiplier) - round(sqrt(rooms) / crowding_divisor)`,
+//!   clamped to `min_room_size..=max_room_size`, so rooms of crowded dungeons bottom out
+//!   instead of shrinking below zero. `room_sizing` picks whether the sizes stay
+//!   independent of each other or shrink to fit an area budget decoded from the ID.
+//! - **Level**: One level per `level_band` units of room area.
+//! - **Room shapes**: How shapes grow with the room size (`shape_style`, see `shapes`);
+//!   `ShapeStyle::Outlined` keeps large rooms from turning into squares.
+//! - **Rubble**: One rubble point per `area_per_random_point` units of room area, scaled by the
+//!   dungeon type's density (see `decorate`).
+//! - **Smoothing**: When the cave smoothing pass runs (`smoothing`) and how many passes it makes.
+//! - **Tunnels**: How tunnels between rooms are dug (`tunnel_style`, see `tunnels`).
+//! - **Terrain**: Whether water, lava, and sand features are carved and wet dungeons
+//!   flooded (`terrain`).
+//! - **Balance**: Per-level curves for monsters, gold, and traps (`balance`).
+//! - **Collection salt**: A project-specific string mixed into every derivation
+//!   (`collection_salt`, see `seed::salt_nft_id`), so collections sharing this crate get
+//!   distinct dungeons from the same IDs.
+//! - **Epoch**: A period of the Chia blockchain mixed into monster and decoration
+//!   placement (`epoch`, see `epoch`), so dungeons change over time around a stable layout.
+//!
+//! Rubble, smoothing, terrain, balance, and the epoch only shape a dungeon once its rooms
+//! and tunnels are laid out; `structure_config` drops them, so configs differing only there
+//! share their layouts (see `pipeline`).
+
+use serde::{Deserialize, Serialize};
+use super::balance::Balance;
+use super::error::DungeonError;
+use super::excavator::{MAX_CORRIDOR_WIDTH, MAX_ROOMS, MAX_ROOM_SIZE};
+use super::shapes::ShapeStyle;
+use super::smoothing::{Smoothing, DEFAULT_SMOOTHING_PASSES, MAX_SMOOTHING_PASSES};
+use super::tunnels::TunnelStyle;
+
+/// How the layout of a dungeon is excavated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum GenerationAlgorithm {
+    /// Shaped rooms decoded from the `nft_id`, joined by tunnels.
+    #[default]
+    Rooms,
+    /// Open caves dug from a noise field tuned by the `nft_id` (see `noise`).
+    Noise,
+    /// Shaped rooms decoded from the `nft_id`, embedded in a perfect maze (see `maze`).
+    Maze,
+}
+
+/// Whether room sizes are bounded by the total area of the dungeon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RoomSizing {
+    /// Every room keeps its decoded size, however large the total area.
+    #[default]
+    Independent,
+    /// Rooms shrink until the total area fits a budget decoded from the `nft_id`, which
+    /// caps the level of the top floor at `BUDGET_LEVELS` (see `excavator::area_budget`).
+    Budgeted,
+}
+
+/// Parameters controlling how an NFT ID is turned into a dungeon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationConfig {
+    /// How the layout is excavated.
+    pub algorithm: GenerationAlgorithm,
+    /// Fewest rooms a dungeon can have, at least 2 so it has both an entrance and an exit.
+    pub min_rooms: usize,
+    /// Most rooms a dungeon can have, at most `MAX_ROOMS`.
+    pub max_rooms: usize,
+    /// Base room size before the size character is applied.
+    pub size_base: i32,
+    /// Multiplier on the square root of the size character's value.
+    pub size_multiplier: f64,
+    /// Divisor on the square root of the room count; smaller values shrink rooms in crowded dungeons.
+    pub crowding_divisor: f64,
+    /// Smallest room size; smaller decoded sizes are raised to it.
+    pub min_room_size: u32,
+    /// Largest room size, at most `MAX_ROOM_SIZE`; larger decoded sizes are lowered to it.
+    pub max_room_size: u32,
+    /// Whether room sizes shrink to fit an area budget.
+    pub room_sizing: RoomSizing,
+    /// Room area covered by each dungeon level.
+    pub level_band: u64,
+    /// Room area per rubble point, before the dungeon type's density is applied.
+    pub area_per_random_point: u64,
+    /// Widest corridor dug at high levels.
+    pub max_corridor_width: u32,
+    /// How room shapes are expanded by the room size.
+    pub shape_style: ShapeStyle,
+    /// When the cellular-automaton cave smoothing runs.
+    pub smoothing: Smoothing,
+    /// Number of smoothing passes after the walls are eroded.
+    pub smoothing_passes: u32,
+    /// How tunnels between room centers are dug.
+    pub tunnel_style: TunnelStyle,
+    /// Whether terrain features are carved and low ground flooded for the dungeon types
+    /// that have them (see `terrain` and `liquid`).
+    pub terrain: bool,
+    /// Per-level spawn-rate curves, kept with the dungeon.
+    pub balance: Balance,
+    /// Salt of the collection, mixed into every derivation and kept with the dungeon.
+    pub collection_salt: Option<String>,
+    /// Epoch mixed into monster and decoration placement, kept with the dungeon; left out
+    /// of the serialized config when unset, so cache hashes of timeless configs hold.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<u64>,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> GenerationConfig {
+        GenerationConfig {
+            algorithm: GenerationAlgorithm::Rooms,
+            min_rooms: 2,
+            max_rooms: 37,
+            size_base: 2,
+            size_multiplier: 1.5,
+            crowding_divisor: 4.0,
+            min_room_size: 0,
+            max_room_size: MAX_ROOM_SIZE,
+            room_sizing: RoomSizing::Independent,
+            level_band: 1000,
+            area_per_random_point: 50,
+            max_corridor_width: MAX_CORRIDOR_WIDTH,
+            shape_style: ShapeStyle::Filled,
+            smoothing: Smoothing::Auto,
+            smoothing_passes: DEFAULT_SMOOTHING_PASSES,
+            tunnel_style: TunnelStyle::Manhattan,
+            terrain: true,
+            balance: Balance::default(),
+            collection_salt: None,
+            epoch: None,
+        }
+    }
+}
+
+impl GenerationConfig {
+    /// This config with the parameters applied after the rooms and tunnels are laid out
+    /// (rubble, smoothing, terrain, balance, and the epoch) reset to their defaults
+    pub fn structure_config(&self) -> GenerationConfig {
+        let default = GenerationConfig::default();
+        GenerationConfig {
+            area_per_random_point: default.area_per_random_point,
+            smoothing: default.smoothing,
+            smoothing_passes: default.smoothing_passes,
+            terrain: default.terrain,
+            balance: default.balance,
+            epoch: default.epoch,
+            ..self.clone()
+        }
+    }
+
+    /// Checks that the parameters can produce a dungeon.
+    pub fn validate(&self) -> Result<(), DungeonError> {
+        // One room for the entrance and another for the exit
+        if self.min_rooms < 2 {
+            return Err(DungeonError::InvalidConfig("min_rooms must be at least 2"));
+        }
+        if self.min_rooms > self.max_rooms {
+            return Err(DungeonError::InvalidConfig("min_rooms must not exceed max_rooms"));
+        }
+        if self.max_rooms > MAX_ROOMS {
+            return Err(DungeonError::InvalidConfig("max_rooms must not exceed MAX_ROOMS"));
+        }
+        if !(self.size_multiplier.is_finite() && self.size_multiplier >= 0.0) {
+            return Err(DungeonError::InvalidConfig("size_multiplier must be finite and non-negative"));
+        }
+        if !(self.crowding_divisor.is_finite() && self.crowding_divisor > 0.0) {
+            return Err(DungeonError::InvalidConfig("crowding_divisor must be finite and positive"));
+        }
+        if self.min_room_size > self.max_room_size {
+            return Err(DungeonError::InvalidConfig("min_room_size must not exceed max_room_size"));
+        }
+        if self.max_room_size > MAX_ROOM_SIZE {
+            return Err(DungeonError::InvalidConfig("max_room_size must not exceed MAX_ROOM_SIZE"));
+        }
+        if self.level_band == 0 {
+            return Err(DungeonError::InvalidConfig("level_band must be positive"));
+        }
+        if self.area_per_random_point == 0 {
+            return Err(DungeonError::InvalidConfig("area_per_random_point must be positive"));
+        }
+        if self.max_corridor_width == 0 {
+            return Err(DungeonError::InvalidConfig("max_corridor_width must be at least 1"));
+        }
+        if self.smoothing_passes > MAX_SMOOTHING_PASSES {
+            return Err(DungeonError::InvalidConfig("smoothing_passes must not exceed MAX_SMOOTHING_PASSES"));
+        }
+        self.balance.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::{area_budget, decode_room_size, parse_nft_id_with_config, BUDGET_LEVELS};
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    #[test]
+    fn the_default_config_reproduces_parse_nft_id() {
+        let config = GenerationConfig::default();
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(parse_nft_id_with_config(NFT_ID, &config).unwrap(), dungeon());
+    }
+
+    #[test]
+    fn invalid_parameters_are_rejected() {
+        let invalid = |config: GenerationConfig| config.validate().unwrap_err();
+        let default = GenerationConfig::default;
+        assert_eq!(invalid(GenerationConfig { min_rooms: 1, ..default() }), DungeonError::InvalidConfig("min_rooms must be at least 2"));
+        assert_eq!(invalid(GenerationConfig { min_rooms: 9, max_rooms: 8, ..default() }), DungeonError::InvalidConfig("min_rooms must not exceed max_rooms"));
+        assert_eq!(invalid(GenerationConfig { size_multiplier: f64::NAN, ..default() }), DungeonError::InvalidConfig("size_multiplier must be finite and non-negative"));
+        assert_eq!(invalid(GenerationConfig { crowding_divisor: 0.0, ..default() }), DungeonError::InvalidConfig("crowding_divisor must be finite and positive"));
+        assert_eq!(invalid(GenerationConfig { min_room_size: 5, max_room_size: 4, ..default() }), DungeonError::InvalidConfig("min_room_size must not exceed max_room_size"));
+        assert_eq!(invalid(GenerationConfig { max_room_size: MAX_ROOM_SIZE + 1, ..default() }), DungeonError::InvalidConfig("max_room_size must not exceed MAX_ROOM_SIZE"));
+        assert_eq!(invalid(GenerationConfig { max_rooms: MAX_ROOMS + 1, ..default() }), DungeonError::InvalidConfig("max_rooms must not exceed MAX_ROOMS"));
+        assert_eq!(invalid(GenerationConfig { level_band: 0, ..default() }), DungeonError::InvalidConfig("level_band must be positive"));
+        assert_eq!(invalid(GenerationConfig { max_corridor_width: 0, ..default() }), DungeonError::InvalidConfig("max_corridor_width must be at least 1"));
+        assert_eq!(invalid(GenerationConfig { area_per_random_point: 0, ..default() }), DungeonError::InvalidConfig("area_per_random_point must be positive"));
+        assert!(parse_nft_id_with_config(NFT_ID, &GenerationConfig { min_rooms: 0, ..default() }).is_err());
+    }
+
+    #[test]
+    fn changed_parameters_change_the_dungeon() {
+        let config = GenerationConfig { min_rooms: 20, max_rooms: 20, ..GenerationConfig::default() };
+        assert_eq!(parse_nft_id_with_config(NFT_ID, &config).unwrap().rooms.len(), 20);
+    }
+
+    #[test]
+    fn crowded_dungeons_clamp_room_sizes() {
+        // 50 rooms crowd every decoded size below zero, which used to wrap around to huge rooms
+        let nft_id = generate_nft_id_with(&GenerateOptions { seed: Some(7), ..GenerateOptions::default() }).unwrap();
+        let config = GenerationConfig { min_rooms: 50, max_rooms: 50, crowding_divisor: 0.5, min_room_size: 1, ..GenerationConfig::default() };
+        let dungeon = parse_nft_id_with_config(&nft_id, &config).unwrap();
+        assert_eq!(dungeon.rooms.len(), 50);
+        assert!(dungeon.rooms.iter().all(|room| room.size == 1));
+        assert_eq!(dungeon.area_size, 50 * 9);
+
+        // Extreme parameters saturate instead of overflowing
+        let huge = GenerationConfig { size_base: i32::MAX, size_multiplier: 1e300, max_room_size: 40, ..GenerationConfig::default() };
+        assert_eq!(decode_room_size('z', 2, &huge), 40);
+        let tiny = GenerationConfig { size_base: i32::MIN, crowding_divisor: 1e-300, ..GenerationConfig::default() };
+        assert_eq!(decode_room_size('0', usize::MAX, &tiny), 0);
+    }
+
+    #[test]
+    fn budgeted_room_sizes_fit_the_area_budget() {
+        let config = GenerationConfig { room_sizing: RoomSizing::Budgeted, ..GenerationConfig::default() };
+        let budgeted = parse_nft_id_with_config(NFT_ID, &config).unwrap();
+        assert!(budgeted.area_size <= area_budget(&NFT_ID.chars().collect::<Vec<_>>(), &config));
+        assert!(budgeted.level <= BUDGET_LEVELS);
+        assert!(budgeted.rooms.iter().zip(&dungeon().rooms).all(|(budgeted, free)| budgeted.size <= free.size));
+
+        // 27 rooms of size 13 cover 19683 units, but their characters leave a budget of 999
+        let nft_id = format!("nft1{}", "Z".repeat(40));
+        let free = parse_nft_id_with_config(&nft_id, &GenerationConfig::default()).unwrap();
+        let budgeted = parse_nft_id_with_config(&nft_id, &config).unwrap();
+        assert_eq!((free.area_size, free.level), (19683, 20));
+        assert_eq!(area_budget(&nft_id.chars().collect::<Vec<_>>(), &config), 999);
+        assert!(budgeted.area_size <= 999 && budgeted.level == 1);
+        assert_eq!(budgeted.rooms.len(), free.rooms.len());
+    }
+}
@@ -0,0 +1,161 @@
+//! # Danger Map
+//!
+//! How dangerous each walkable tile of a dungeon is, from 0 (safe) to 1 (deadly), for
+//! heatmaps that help players plan their routes (see `plot::render_options_map`).
+//!
+//! A tile's danger blends three measures, each scaled to [0, 1]:
+//! - **Depth**: Steps from the entrance along walkable tiles, relative to the furthest
+//!   tile reached; tiles the entrance cannot reach count as deepest. Weighted by
+//!   `DEPTH_WEIGHT`.
+//! - **Monsters**: Monsters within `MONSTER_RADIUS` tiles, each counting less the further
+//!   it stands, relative to the most crowded tile. Weighted by `MONSTER_WEIGHT`.
+//! - **Traps**: Closeness to the nearest trap within `TRAP_RADIUS` tiles. Weighted by
+//!   `TRAP_WEIGHT`.
+//!
+//! The blend is then scaled so the most dangerous tile is 1, spreading every dungeon
+//! over the whole of the green-yellow-red scale of `danger_color`.
+
+use std::collections::VecDeque;
+use super::dungeon::Dungeon;
+use super::grid::Grid;
+use super::pathfinding::neighbors;
+use super::theme::Rgb;
+
+/// Share of a tile's danger from its depth below the entrance
+pub const DEPTH_WEIGHT: f64 = 0.4;
+/// Share of a tile's danger from the monsters around it
+pub const MONSTER_WEIGHT: f64 = 0.4;
+/// Share of a tile's danger from the nearest trap
+pub const TRAP_WEIGHT: f64 = 0.2;
+/// Furthest a monster adds to the danger of a tile, in tiles along either axis
+pub const MONSTER_RADIUS: i32 = 5;
+/// Furthest a trap adds to the danger of a tile, in tiles along either axis
+pub const TRAP_RADIUS: i32 = 3;
+
+/// Danger of every tile of the dungeon's grid in [0, 1]; walls are 0 (see `danger`)
+pub fn danger_map(dungeon: &Dungeon) -> Grid<f64> {
+    let grid = &dungeon.grid;
+    let (x_range, y_range) = (grid.x_range(), grid.y_range());
+    let depth = depth_map(dungeon);
+
+    // Each monster or trap raises the tiles around it, less with every tile of distance
+    let falloff = |(x, y): (i32, i32), (cx, cy): (i32, i32), radius: i32| {
+        let distance = (x - cx).abs().max((y - cy).abs());
+        (distance <= radius).then(|| 1.0 - distance as f64 / (radius + 1) as f64)
+    };
+    let around = |(cx, cy): (i32, i32), radius: i32| {
+        (cy - radius..=cy + radius).flat_map(move |y| (cx - radius..=cx + radius).map(move |x| (x, y)))
+    };
+    let mut crowding = Grid::new(x_range, y_range, 0.0);
+    for monster in &dungeon.monsters {
+        for pos in around(monster.position, MONSTER_RADIUS) {
+            if let (Some(weight), Some(value)) = (falloff(pos, monster.position, MONSTER_RADIUS), crowding.get_mut(pos)) {
+                *value += weight;
+            }
+        }
+    }
+    let most_crowded = crowding.iter().map(|(_, &value)| value).fold(0.0, f64::max);
+    let mut traps = Grid::new(x_range, y_range, 0.0f64);
+    for trap in dungeon.traps() {
+        for pos in around(trap.position, TRAP_RADIUS) {
+            if let (Some(weight), Some(value)) = (falloff(pos, trap.position, TRAP_RADIUS), traps.get_mut(pos)) {
+                *value = value.max(weight);
+            }
+        }
+    }
+
+    let mut danger = Grid::new(x_range, y_range, 0.0);
+    for (pos, tile) in grid.iter() {
+        if !tile.is_walkable() {
+            continue;
+        }
+        let crowding = crowding.get(pos).map_or(0.0, |&value| if most_crowded > 0.0 { value / most_crowded } else { 0.0 });
+        let value = DEPTH_WEIGHT * depth.get(pos).copied().unwrap_or(1.0)
+            + MONSTER_WEIGHT * crowding
+            + TRAP_WEIGHT * traps.get(pos).copied().unwrap_or(0.0);
+        danger.set(pos, value);
+    }
+    let most_dangerous = danger.iter().map(|(_, &value)| value).fold(0.0, f64::max);
+    if most_dangerous > 0.0 {
+        for (pos, _) in grid.iter() {
+            if let Some(value) = danger.get_mut(pos) {
+                *value /= most_dangerous;
+            }
+        }
+    }
+    danger
+}
+
+// Steps from the entrance to every walkable tile relative to the furthest one reached,
+// 1 for tiles it cannot reach
+fn depth_map(dungeon: &Dungeon) -> Grid<f64> {
+    let grid = &dungeon.grid;
+    let mut steps: Grid<Option<u32>> = Grid::new(grid.x_range(), grid.y_range(), None);
+    let mut queue = VecDeque::new();
+    if let Some(entrance) = dungeon.entrance() {
+        steps.set(entrance, Some(0));
+        queue.push_back((entrance, 0));
+    }
+    let mut furthest = 0;
+    while let Some((pos, distance)) = queue.pop_front() {
+        furthest = distance;
+        for next in neighbors(pos) {
+            if grid.is_walkable(next) && steps.get(next) == Some(&None) {
+                steps.set(next, Some(distance + 1));
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    let mut depth = Grid::new(grid.x_range(), grid.y_range(), 1.0);
+    for (pos, distance) in steps.iter() {
+        if let Some(distance) = distance {
+            depth.set(pos, *distance as f64 / furthest.max(1) as f64);
+        }
+    }
+    depth
+}
+
+/// Heatmap color of a danger level: green when safe, yellow halfway, red when deadly
+pub fn danger_color(danger: f64) -> Rgb {
+    let danger = danger.clamp(0.0, 1.0);
+    let (red, green) = if danger < 0.5 { (danger * 2.0, 1.0) } else { (1.0, (1.0 - danger) * 2.0) };
+    ((red * 230.0).round() as u8, (green * 200.0).round() as u8, 40)
+}
+
+impl Dungeon {
+    /// Danger of every tile of the dungeon (see `danger`).
+    pub fn danger_map(&self) -> Grid<f64> {
+        danger_map(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::super::grid::Tile;
+    use super::*;
+
+    #[test]
+    fn danger_grows_away_from_the_entrance_and_near_monsters() {
+        let dungeon = dungeon();
+        let danger = dungeon.danger_map();
+        assert!(danger.iter().all(|(_, &value)| (0.0..=1.0).contains(&value)));
+        assert!(danger.iter().any(|(_, &value)| value == 1.0));
+        assert!(dungeon.grid.iter().filter(|(_, &tile)| tile == Tile::Wall).all(|(pos, _)| danger.get(pos) == Some(&0.0)));
+
+        let entrance = dungeon.entrance().unwrap();
+        let monster = dungeon.monsters[0].position;
+        assert!(danger.get(monster).unwrap() > danger.get(entrance).unwrap(), "{:?} vs {:?}", danger.get(monster), danger.get(entrance));
+        let depth = depth_map(&dungeon);
+        assert_eq!(depth.get(entrance), Some(&0.0));
+        assert!(depth.iter().any(|(_, &value)| value == 1.0));
+    }
+
+    #[test]
+    fn colors_run_from_green_to_red() {
+        assert_eq!(danger_color(0.0), (0, 200, 40));
+        assert_eq!(danger_color(0.5), (230, 200, 40));
+        assert_eq!(danger_color(1.0), (230, 0, 40));
+        assert_eq!(danger_color(7.0), danger_color(1.0));
+    }
+}
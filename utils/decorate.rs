@@ -0,0 +1,307 @@
+//! # Decoration
+//!
+//! Scatters rubble through the excavated map where a real dungeon would collect it,
+//! instead of as noise spread evenly over the whole bounding box, and lays decoration
+//! objects on the room floors.
+//!
+//! - **Clusters**: Heaps of `CLUSTER_SIZE` points piled against the outside of a room,
+//!   grown by a short random walk from a tile just past the room's edge.
+//! - **Debris**: Loose stones strewn one or two tiles beside a tunnel's corridor.
+//! - **Density**: The decoration of the dungeon type (`decoration_for_type`) scales the
+//!   number of points and sets the share of them that lies along tunnels: ruins pile
+//!   heaps of masonry by their rooms, caves and mines litter their passages, and open
+//!   wastelands stay sparse.
+//!
+//! Rubble never overlaps rooms or corridors and stays within the dungeon bounds. It is
+//! drawn from the "decorate" RNG stream of the `nft_id`.
+//!
+//! ## Decoration objects
+//!
+//! Once monsters are placed, `place_decorations` lays bones, mushrooms, crystals,
+//! cobwebs, and embers on the plain floor tiles of the rooms (`Dungeon::decorations`).
+//!
+//! - **Count**: One object per `TILES_PER_OBJECT` free room floor tiles, scaled by the
+//!   density of the dungeon type.
+//! - **Kinds**: Drawn from the weight table of the dungeon type (`decoration_weights`):
+//!   Ice Caverns glitter with crystals, Necropolises are strewn with bones, forests and
+//!   swamps sprout mushrooms, and fiery pits smoulder with embers.
+//! - **Placement**: Objects never share a tile with each other or a monster, and never
+//!   lie on a room center, stairs, door, or terrain feature. Tiles and kinds come from the
+//!   "decorations" RNG stream of the `nft_id`, so existing layouts are unchanged; living
+//!   dungeons rename it per epoch (see `epoch`).
+
+use std::collections::HashSet;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::dungeon::{Dungeon, Room};
+use super::epoch::epoch_stream;
+use super::excavator::{base_dungeon_type, corridor_brush};
+use super::grid::Tile;
+use super::seed::rng_for_stream;
+use super::theme::Rgb;
+
+/// Rubble points per heap piled against a room
+pub const CLUSTER_SIZE: usize = 6;
+/// Farthest a heap's random walk strays from where it started
+const CLUSTER_RADIUS: i32 = 3;
+/// Attempts allowed per requested point before the pass gives up on crowded maps
+const ATTEMPTS_PER_POINT: usize = 8;
+/// Free room floor tiles per decoration object, at a density of 1
+pub const TILES_PER_OBJECT: usize = 12;
+
+/// How densely a dungeon type is decorated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecorationDensity {
+    /// Multiplier on the number of rubble points.
+    pub density: f64,
+    /// Share of the points strewn along tunnels rather than heaped by rooms, from 0 to 1.
+    pub debris: f64,
+}
+
+/// Decoration of dungeons of the given type
+/// Variant types of upper-case letters are decorated like their base type.
+pub fn decoration_for_type(dungeon_type: &str) -> DecorationDensity {
+    let (density, debris) = match base_dungeon_type(dungeon_type) {
+        "Ancient Ruins" | "Kingdom Ruins" | "Temple" | "Necropolis" => (1.5, 0.3),
+        "Cave" | "Ice Cavern" | "Mountain" | "Underground Tunnels" | "Volcanic Crater" => (1.25, 0.6),
+        "Hell" | "Lava Pits" | "Xeno Hive" => (1.25, 0.4),
+        "Forest" | "Enchanted Forest" | "Jungle" | "Rainforest" => (1.0, 0.3),
+        "Water" | "Ocean Depths" | "Swamp" | "Poison Swamp" | "Quagmire" => (0.75, 0.5),
+        "Barrens" | "Desert" | "Grassland" | "Yellow Wasteland" | "Zephyr Highlands" => (0.5, 0.5),
+        _ => (1.0, 0.5),
+    };
+    DecorationDensity { density, debris }
+}
+
+/// Kind of a decoration object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DecorationKind {
+    /// Scattered bones and skulls.
+    Bones,
+    /// A patch of mushrooms.
+    Mushrooms,
+    /// A cluster of crystals.
+    Crystals,
+    /// Cobwebs strung between the walls.
+    Cobwebs,
+    /// Smouldering embers.
+    Embers,
+}
+
+impl DecorationKind {
+    /// Every kind, in the order of the weight tables (see `decoration_weights`).
+    pub const ALL: [DecorationKind; 5] =
+        [DecorationKind::Bones, DecorationKind::Mushrooms, DecorationKind::Crystals, DecorationKind::Cobwebs, DecorationKind::Embers];
+}
+
+/// A decoration object lying on a room floor tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decoration {
+    /// What the object is.
+    pub kind: DecorationKind,
+    /// Floor tile the object lies on.
+    pub position: (i32, i32),
+}
+
+/// Weight of each decoration kind, in the order of `DecorationKind::ALL`, in dungeons of
+/// the given type
+pub fn decoration_weights(dungeon_type: &str) -> [u32; 5] {
+    match base_dungeon_type(dungeon_type) {
+        "Necropolis" => [8, 1, 0, 3, 0],
+        "Ancient Ruins" | "Kingdom Ruins" | "Temple" => [3, 1, 1, 4, 1],
+        "Ice Cavern" => [1, 0, 8, 1, 0],
+        "Cave" | "Mountain" | "Underground Tunnels" => [2, 3, 3, 2, 0],
+        "Hell" | "Lava Pits" | "Volcanic Crater" => [3, 0, 1, 0, 6],
+        "Forest" | "Enchanted Forest" | "Jungle" | "Rainforest" => [1, 6, 0, 3, 0],
+        "Swamp" | "Poison Swamp" | "Quagmire" => [2, 6, 0, 1, 0],
+        "Water" | "Ocean Depths" => [3, 2, 3, 0, 0],
+        "Xeno Hive" => [4, 2, 2, 3, 0],
+        "Barrens" | "Desert" | "Grassland" | "Yellow Wasteland" | "Zephyr Highlands" => [6, 1, 1, 0, 1],
+        _ => [2, 2, 2, 2, 1],
+    }
+}
+
+/// Color of a decoration object on rendered maps
+pub fn decoration_color(kind: DecorationKind) -> Rgb {
+    match kind {
+        DecorationKind::Bones => (235, 225, 200),
+        DecorationKind::Mushrooms => (170, 90, 200),
+        DecorationKind::Crystals => (120, 220, 255),
+        DecorationKind::Cobwebs => (190, 190, 190),
+        DecorationKind::Embers => (255, 110, 30),
+    }
+}
+
+/// Decoration objects for the dungeon's rooms, one per `TILES_PER_OBJECT` free floor
+/// tiles scaled by the type's density, their kinds drawn from `decoration_weights`
+pub fn place_decorations(dungeon: &Dungeon) -> Vec<Decoration> {
+    let mut rng = rng_for_stream(&dungeon.seed_id(), &epoch_stream("decorations", dungeon.epoch));
+    let weights = decoration_weights(&dungeon.dungeon_type);
+    let total: u32 = weights.iter().sum();
+
+    // Candidate tiles: plain floor tiles of the rooms without a monster
+    let mut seen: HashSet<(i32, i32)> = dungeon.monsters.iter().map(|monster| monster.position).collect();
+    let mut candidates: Vec<(i32, i32)> = dungeon
+        .rooms
+        .iter()
+        .flat_map(|room| room.tiles.iter().copied())
+        .filter(|&pos| dungeon.grid.get(pos) == Some(&Tile::Floor) && seen.insert(pos))
+        .collect();
+
+    let density = decoration_for_type(&dungeon.dungeon_type).density;
+    let count = ((candidates.len() as f64 * density / TILES_PER_OBJECT as f64).round() as usize).min(candidates.len());
+    let mut decorations = Vec::with_capacity(count);
+    for _ in 0..count {
+        let position = candidates.swap_remove(rng.gen_range(0..candidates.len()));
+        // Walk the table until the roll falls within a kind's weight
+        let mut roll = rng.gen_range(0..total);
+        let mut kind = DecorationKind::ALL[0];
+        for (&candidate, &weight) in DecorationKind::ALL.iter().zip(&weights) {
+            if roll < weight {
+                kind = candidate;
+                break;
+            }
+            roll -= weight;
+        }
+        decorations.push(Decoration { kind, position });
+    }
+    decorations
+}
+
+/// Rubble for a dungeon of `dungeon_type` within `bounds`, about `base_count` points
+/// scaled by the type's density, none of them on a room or corridor tile
+pub fn decorate(
+    rooms: &[Room],
+    tunnels: &[Vec<(i32, i32)>],
+    corridor_width: u32,
+    (x_range, y_range): ((i32, i32), (i32, i32)),
+    base_count: usize,
+    dungeon_type: &str,
+    rng: &mut impl Rng,
+) -> Vec<(i32, i32)> {
+    let decoration = decoration_for_type(dungeon_type);
+    let count = (base_count as f64 * decoration.density).round() as usize;
+    let debris_count = (count as f64 * decoration.debris).round() as usize;
+
+    let room_tiles: HashSet<(i32, i32)> = rooms.iter().flat_map(|room| room.tiles.iter().copied()).collect();
+    let corridors: Vec<(i32, i32)> = tunnels.iter().flatten().copied().collect();
+    let mut taken: HashSet<(i32, i32)> =
+        room_tiles.iter().copied().chain(corridors.iter().flat_map(|&pos| corridor_brush(pos, corridor_width))).collect();
+    let in_bounds = |(x, y): (i32, i32)| (x_range.0..=x_range.1).contains(&x) && (y_range.0..=y_range.1).contains(&y);
+    let mut points = Vec::new();
+
+    // Heaps against the outside of the rooms
+    let walled: Vec<&Room> = rooms.iter().filter(|room| !room.tiles.is_empty()).collect();
+    let mut attempts = (count - debris_count) * ATTEMPTS_PER_POINT;
+    while points.len() < count - debris_count && !walled.is_empty() && attempts > 0 {
+        attempts -= 1;
+        let room = walled[rng.gen_range(0..walled.len())];
+        let (dx, dy) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0..4)];
+        let mut anchor = room.tiles[rng.gen_range(0..room.tiles.len())];
+        while room_tiles.contains(&anchor) {
+            anchor = (anchor.0 + dx, anchor.1 + dy);
+        }
+        let mut pos = anchor;
+        for _ in 0..CLUSTER_SIZE.min(count - debris_count - points.len()) {
+            if in_bounds(pos) && taken.insert(pos) {
+                points.push(pos);
+            }
+            let (sx, sy) = [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0..4)];
+            let next = (pos.0 + sx, pos.1 + sy);
+            if (next.0 - anchor.0).abs() <= CLUSTER_RADIUS && (next.1 - anchor.1).abs() <= CLUSTER_RADIUS {
+                pos = next;
+            }
+        }
+    }
+
+    // Debris beside the corridors
+    let (low, high) = ((corridor_width as i32 - 1) / 2, corridor_width as i32 / 2);
+    let target = points.len() + debris_count;
+    let mut attempts = debris_count * ATTEMPTS_PER_POINT;
+    while points.len() < target && !corridors.is_empty() && attempts > 0 {
+        attempts -= 1;
+        let (x, y) = corridors[rng.gen_range(0..corridors.len())];
+        let away = rng.gen_range(1..=2);
+        let across = rng.gen_range(-1..=1);
+        let pos = match rng.gen_range(0..4) {
+            0 => (x + high + away, y + across),
+            1 => (x - low - away, y + across),
+            2 => (x + across, y + high + away),
+            _ => (x + across, y - low - away),
+        };
+        if in_bounds(pos) && taken.insert(pos) {
+            points.push(pos);
+        }
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::super::seed::rng_for_stream;
+    use super::*;
+
+    #[test]
+    fn rubble_lies_beside_rooms_and_corridors() {
+        let dungeon = dungeon();
+        let bounds = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+        let draw = || {
+            let mut rng = rng_for_stream(&dungeon.nft_id, "decorate");
+            decorate(&dungeon.rooms, &dungeon.tunnels, dungeon.corridor_width, bounds, 120, &dungeon.dungeon_type, &mut rng)
+        };
+        let rubble = draw();
+        assert_eq!(rubble, draw());
+        assert!(!rubble.is_empty());
+
+        let room_tiles: HashSet<(i32, i32)> = dungeon.rooms.iter().flat_map(|room| room.tiles.iter().copied()).collect();
+        let unique: HashSet<(i32, i32)> = rubble.iter().copied().collect();
+        assert_eq!(unique.len(), rubble.len());
+        for &(x, y) in &rubble {
+            assert!(!room_tiles.contains(&(x, y)));
+            assert!((bounds.0 .0..=bounds.0 .1).contains(&x) && (bounds.1 .0..=bounds.1 .1).contains(&y));
+            // Every point lies within a heap's reach of a room or beside a tunnel
+            let near = |pos: &(i32, i32)| (pos.0 - x).abs() <= 2 * CLUSTER_RADIUS && (pos.1 - y).abs() <= 2 * CLUSTER_RADIUS;
+            assert!(room_tiles.iter().any(near) || dungeon.tunnels.iter().flatten().any(near), "stray rubble at {:?}", (x, y));
+        }
+    }
+
+    #[test]
+    fn density_follows_the_dungeon_type() {
+        assert!(decoration_for_type("Ancient Ruins").density > decoration_for_type("Barrens").density);
+        assert_eq!(decoration_for_type("Ancient Ruins"), decoration_for_type(&super::super::excavator::get_dungeon_type("A")));
+        assert!(decoration_for_type("Cave").debris > decoration_for_type("Temple").debris);
+    }
+
+    #[test]
+    fn objects_lie_on_free_room_floors() {
+        let dungeon = dungeon();
+        let decorations = place_decorations(&dungeon);
+        assert_eq!(decorations, dungeon.decorations);
+        assert!(!decorations.is_empty());
+
+        let monsters: HashSet<(i32, i32)> = dungeon.monsters.iter().map(|monster| monster.position).collect();
+        let unique: HashSet<(i32, i32)> = decorations.iter().map(|decoration| decoration.position).collect();
+        assert_eq!(unique.len(), decorations.len());
+        for decoration in &decorations {
+            assert_eq!(dungeon.grid.get(decoration.position), Some(&Tile::Floor));
+            assert!(!monsters.contains(&decoration.position));
+            assert!(dungeon.rooms.iter().any(|room| room.tiles.contains(&decoration.position)));
+            assert!(decoration_weights(&dungeon.dungeon_type)[decoration.kind as usize] > 0);
+        }
+    }
+
+    #[test]
+    fn weights_follow_the_biome() {
+        let heaviest = |dungeon_type: &str| {
+            let weights = decoration_weights(dungeon_type);
+            DecorationKind::ALL[(0..weights.len()).max_by_key(|&i| weights[i]).unwrap()]
+        };
+        assert_eq!(heaviest("Ice Cavern"), DecorationKind::Crystals);
+        assert_eq!(heaviest("Necropolis"), DecorationKind::Bones);
+        assert_eq!(heaviest("Jungle"), DecorationKind::Mushrooms);
+        assert_eq!(heaviest("Lava Pits"), DecorationKind::Embers);
+        assert_eq!(decoration_weights("Ice Cavern"), decoration_weights(&super::super::excavator::get_dungeon_type("I")));
+    }
+}
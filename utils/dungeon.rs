@@ -0,0 +1,376 @@
+//! # Dungeon Types
+//!
+//! Structured representation of a dungeon decoded from an NFT ID.
+//!
+//! `parse_nft_id` returns a `Dungeon`, which owns one `Room` per decoded room
+//! alongside the tunnels, bounds, and derived attributes (type, level, area).
+//! All types implement `serde::{Serialize, Deserialize}`, and `Dungeon::to_json` /
+//! `Dungeon::from_json` persist a parsed dungeon without re-parsing the NFT.
+//!
+//! `Dungeon::tiles` streams the tile grid row by row without collecting it, so renderers
+//! of very large dungeons never hold more than the grid itself.
+//!
+//! `Dungeon::bounds` covers every excavated tile and room center with one tile of
+//! margin, so maps drawn from it never clip tunnels or rubble; after changing the
+//! excavation, `Dungeon::recompute_bounds` brings them up to date.
+//!
+//! `Dungeon::entrance` and `Dungeon::exit` designate the tiles where a run starts and
+//! ends, and `Dungeon::is_solvable` checks that a path joins them.
+//!
+//! The `Dungeon` itself describes the top floor; deeper floors are listed in
+//! `Dungeon::floors` (see `floors`).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Serialize};
+use super::balance::Balance;
+use super::biome::DungeonType;
+use super::decorate::Decoration;
+use super::excavator::char_to_num;
+use super::floors::Floor;
+use super::grid::{Grid, Tile};
+use super::pathfinding::{neighbors, reachable_tiles};
+use super::seed::salt_nft_id;
+use super::spawn::MonsterSpawn;
+
+/// Inclusive x and y ranges covered by the dungeon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub x_range: (i32, i32),
+    pub y_range: (i32, i32),
+}
+
+impl Bounds {
+    /// Smallest bounds holding every point, padded by one tile; `None` without points.
+    pub fn covering(points: impl IntoIterator<Item = (i32, i32)>) -> Option<Bounds> {
+        points.into_iter().fold(None, |bounds: Option<Bounds>, (x, y)| {
+            Some(match bounds {
+                None => Bounds { x_range: (x - 1, x + 1), y_range: (y - 1, y + 1) },
+                Some(Bounds { x_range, y_range }) => Bounds {
+                    x_range: (x_range.0.min(x - 1), x_range.1.max(x + 1)),
+                    y_range: (y_range.0.min(y - 1), y_range.1.max(y + 1)),
+                },
+            })
+        })
+    }
+}
+
+/// Role of a room within the dungeon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RoomKind {
+    /// Where the player enters; always the first room.
+    Entrance,
+    /// Where the player leaves; always the last room.
+    Exit,
+    /// Holds loot.
+    Treasure,
+    /// Guarded by a boss.
+    Boss,
+    /// A place of rest or blessing.
+    Shrine,
+    /// An ordinary room.
+    Normal,
+}
+
+/// A single room decoded from the NFT ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Room {
+    /// Room center coordinate.
+    pub center: (i32, i32),
+    /// Room size used to expand the shape offsets.
+    pub size: u32,
+    /// Shape character the room offsets were derived from.
+    pub shape: String,
+    /// Role of the room, derived from its coordinate characters.
+    pub kind: RoomKind,
+    /// Flavor name of the room, unique within the dungeon (see `naming`).
+    #[serde(default)]
+    pub name: String,
+    /// Excavated tiles belonging to this room.
+    pub tiles: Vec<(i32, i32)>,
+}
+
+/// On-chain provenance of the NFT a dungeon was decoded from (see `rpc`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// Hex puzzle hash of the current owner.
+    pub owner_puzzle_hash: String,
+    /// DID of the current owner, if assigned.
+    pub owner_did: Option<String>,
+    /// Block height the NFT was minted at.
+    pub minting_height: u64,
+    /// Metadata URIs recorded on chain.
+    pub metadata_uris: Vec<String>,
+}
+
+/// A fully parsed and excavated dungeon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dungeon {
+    /// NFT ID the dungeon was decoded from.
+    pub nft_id: String,
+    /// Hex launcher ID, present when `nft_id` is a valid bech32m Chia NFT ID.
+    pub launcher_id: Option<String>,
+    /// Rooms in the order they were decoded.
+    pub rooms: Vec<Room>,
+    /// Tunnel paths connecting room centers.
+    pub tunnels: Vec<Vec<(i32, i32)>>,
+    /// Width in tiles of the corridors dug around each tunnel path.
+    pub corridor_width: u32,
+    /// Dungeon level, derived from the total room area.
+    pub level: u64,
+    /// Environment name, derived from the most frequent character.
+    pub dungeon_type: String,
+    /// Dungeon type with its sub-biome modifier (see `biome`); the default for dungeons
+    /// saved before biomes existed.
+    #[serde(default)]
+    pub biome: DungeonType,
+    /// Bounding box of the excavated tiles and room centers, padded by one tile.
+    pub bounds: Bounds,
+    /// Total area of all rooms.
+    pub area_size: u64,
+    /// Frequency of each letter in the NFT ID.
+    pub char_frequency: HashMap<char, usize>,
+    /// Most frequent letter in the NFT ID.
+    pub most_frequent_char: String,
+    /// Every excavated coordinate (rooms, tunnels, and random points).
+    pub excavated: Vec<(i32, i32)>,
+    /// Tile grid covering every excavated coordinate.
+    pub grid: Grid<Tile>,
+    /// Elevation level of every tile of the grid (see `elevation`); empty for dungeons
+    /// saved before elevation existed.
+    #[serde(default)]
+    pub elevation: Grid<u8>,
+    /// Monsters placed in the rooms.
+    pub monsters: Vec<MonsterSpawn>,
+    /// Bones, mushrooms, and other objects lying on the room floors (see `decorate`); empty
+    /// for dungeons saved before decoration objects existed.
+    #[serde(default)]
+    pub decorations: Vec<Decoration>,
+    /// Stairs down to the first lower floor, if the dungeon has one.
+    pub stairs_down: Option<(i32, i32)>,
+    /// Lower floors, from the shallowest to the deepest.
+    pub floors: Vec<Floor>,
+    /// On-chain provenance, filled in by `rpc::enrich_dungeon` with the `chia-rpc` feature.
+    pub provenance: Option<Provenance>,
+    /// Spawn-rate curves the monsters were placed with and loot and traps follow (see `balance`).
+    #[serde(default)]
+    pub balance: Balance,
+    /// Salt of the collection the dungeon was generated for (see `seed::salt_nft_id`).
+    #[serde(default)]
+    pub collection_salt: Option<String>,
+    /// Epoch the monsters and decorations were placed at (see `epoch`); `None` for the
+    /// timeless placement.
+    #[serde(default)]
+    pub epoch: Option<u64>,
+}
+
+impl Dungeon {
+    /// Serializes the dungeon to pretty-printed JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// ID every derivation reads: the NFT ID salted by the collection, or the NFT ID itself
+    /// when the dungeon has no collection salt (see `seed::salt_nft_id`).
+    pub fn seed_id(&self) -> String {
+        salt_nft_id(&self.nft_id, self.collection_salt.as_deref())
+    }
+
+    /// Reloads a dungeon previously written by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<Dungeon> {
+        serde_json::from_str(json)
+    }
+
+    /// Fits `bounds` to the excavated tiles and room centers, padded by one tile, after
+    /// the excavation changed; dungeons without either keep their bounds.
+    pub fn recompute_bounds(&mut self) {
+        let centers = self.rooms.iter().map(|room| room.center);
+        if let Some(bounds) = Bounds::covering(self.excavated.iter().copied().chain(centers)) {
+            self.bounds = bounds;
+        }
+    }
+
+    /// Streams every tile of the grid as `(position, tile)`, row by row from the highest y,
+    /// matching the orientation of the rendered maps.
+    pub fn tiles(&self) -> impl Iterator<Item = ((i32, i32), Tile)> + '_ {
+        let (x_range, y_range) = (self.grid.x_range(), self.grid.y_range());
+        (y_range.0..=y_range.1).rev().flat_map(move |y| {
+            (x_range.0..=x_range.1).filter_map(move |x| self.grid.get((x, y)).map(|&tile| ((x, y), tile)))
+        })
+    }
+
+    /// Streams the walkable tiles of `tiles`.
+    pub fn excavated_tiles(&self) -> impl Iterator<Item = ((i32, i32), Tile)> + '_ {
+        self.tiles().filter(|&(_, tile)| tile.is_walkable())
+    }
+
+    /// Checks that every room center is reachable from the first room over excavated tiles.
+    ///
+    /// Movement is 4-directional, matching the Manhattan-style tunnels.
+    pub fn is_fully_connected(&self) -> bool {
+        let Some(first) = self.rooms.first() else {
+            return true;
+        };
+        // Breadth-first flood fill from the first room center
+        let mut visited = HashSet::from([first.center]);
+        let mut queue = VecDeque::from([first.center]);
+        while let Some(pos) = queue.pop_front() {
+            for next in neighbors(pos) {
+                if self.grid.is_walkable(next) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        self.rooms.iter().all(|room| visited.contains(&room.center))
+    }
+
+    /// Tile where the player enters: a floor tile of the entrance room picked by the first
+    /// character after "nft1" of the seed ID.
+    pub fn entrance(&self) -> Option<(i32, i32)> {
+        let first = self.seed_id().chars().nth(4)?;
+        self.designated_tile(RoomKind::Entrance, first)
+    }
+
+    /// Tile where the player leaves: a floor tile of the exit room picked by the last
+    /// character of the seed ID.
+    pub fn exit(&self) -> Option<(i32, i32)> {
+        let last = self.seed_id().chars().last()?;
+        self.designated_tile(RoomKind::Exit, last)
+    }
+
+    /// Whether a walkable path leads from the entrance to the exit.
+    pub fn is_solvable(&self) -> bool {
+        match (self.entrance(), self.exit()) {
+            (Some(entrance), Some(exit)) => self.path(entrance, exit).is_some(),
+            _ => false,
+        }
+    }
+
+    // Floor tile of the first room of `kind`, indexed by the value of `c`. Only tiles
+    // reachable from the room center are candidates, so terrain or water cutting off part
+    // of the room never strands the player.
+    fn designated_tile(&self, kind: RoomKind, c: char) -> Option<(i32, i32)> {
+        let room = self.rooms.iter().find(|room| room.kind == kind)?;
+        let reachable = reachable_tiles(&self.grid, room.center);
+        let candidates: Vec<(i32, i32)> = room
+            .tiles
+            .iter()
+            .copied()
+            .filter(|&pos| matches!(self.grid.get(pos), Some(Tile::Floor | Tile::RoomCenter)) && reachable.contains(&pos))
+            .collect();
+        if candidates.is_empty() {
+            return Some(room.center);
+        }
+        Some(candidates[char_to_num(c).unsigned_abs() as usize % candidates.len()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    #[test]
+    fn rooms_are_named_fields_of_the_dungeon() {
+        let dungeon = dungeon();
+        assert_eq!(dungeon.nft_id, NFT_ID);
+        assert_eq!(dungeon.rooms.len(), 9);
+        assert_eq!(dungeon.dungeon_type, "Temple");
+        assert_eq!(dungeon.level, 3);
+        assert_eq!(dungeon.area_size, 2721);
+    }
+
+    #[test]
+    fn rooms_are_excavated_within_the_bounds() {
+        let dungeon = dungeon();
+        let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+        for room in &dungeon.rooms {
+            let (x, y) = room.center;
+            assert!(x > x_range.0 && x < x_range.1 && y > y_range.0 && y < y_range.1);
+            assert!(room.tiles.iter().all(|pos| dungeon.excavated.contains(pos)), "room at {:?} is not excavated", room.center);
+        }
+    }
+
+    #[test]
+    fn bounds_cover_every_excavated_tile() {
+        let mut dungeon = dungeon();
+        let contains = |bounds: Bounds, (x, y): (i32, i32)| {
+            x > bounds.x_range.0 && x < bounds.x_range.1 && y > bounds.y_range.0 && y < bounds.y_range.1
+        };
+        assert!(dungeon.excavated.iter().all(|&pos| contains(dungeon.bounds, pos)));
+        assert!(dungeon.tunnels.iter().flatten().all(|&pos| contains(dungeon.bounds, pos)));
+        // The padding is exactly one tile on every side
+        assert_eq!(Bounds::covering(dungeon.excavated.iter().copied()), Some(dungeon.bounds));
+
+        let far = (dungeon.bounds.x_range.1 + 10, dungeon.bounds.y_range.0 - 3);
+        dungeon.excavated.push(far);
+        dungeon.recompute_bounds();
+        assert!(contains(dungeon.bounds, far));
+        assert_eq!((dungeon.bounds.x_range.1, dungeon.bounds.y_range.0), (far.0 + 1, far.1 - 1));
+        assert_eq!(Bounds::covering([]), None);
+    }
+
+    #[test]
+    fn json_round_trips_the_whole_dungeon() {
+        let dungeon = dungeon();
+        let json = dungeon.to_json().unwrap();
+        assert_eq!(Dungeon::from_json(&json).unwrap(), dungeon);
+        assert!(Dungeon::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn entrance_and_exit_sit_in_their_rooms() {
+        let dungeon = dungeon();
+        let (entrance, exit) = (dungeon.entrance().unwrap(), dungeon.exit().unwrap());
+        assert!(dungeon.rooms[0].tiles.contains(&entrance));
+        assert!(dungeon.rooms.last().unwrap().tiles.contains(&exit));
+        assert!(dungeon.grid.is_walkable(entrance) && dungeon.grid.is_walkable(exit));
+        assert!(dungeon.is_solvable());
+    }
+
+    #[test]
+    fn a_walled_off_exit_is_unsolvable() {
+        let mut dungeon = dungeon();
+        let room = dungeon.rooms.last().unwrap().clone();
+        for &pos in room.tiles.iter().filter(|&&pos| pos != room.center) {
+            dungeon.grid.set(pos, Tile::Wall);
+        }
+        assert_eq!(dungeon.exit(), Some(room.center));
+        assert!(!dungeon.is_solvable());
+    }
+
+    #[test]
+    fn designated_tiles_stay_reachable_from_the_room_center() {
+        let mut dungeon = dungeon();
+        // Cut off the rows of the entrance room above its center with a wall across the map
+        let room = dungeon.rooms[0].clone();
+        assert!(room.tiles.iter().any(|&(_, y)| y > room.center.1 + 1));
+        let (min_x, max_x) = dungeon.grid.x_range();
+        for x in min_x..=max_x {
+            dungeon.grid.set((x, room.center.1 + 1), Tile::Wall);
+        }
+        for c in ('0'..='9').chain('a'..='z').chain('A'..='Z') {
+            let tile = dungeon.designated_tile(RoomKind::Entrance, c).unwrap();
+            assert!(tile.1 <= room.center.1, "{} picks the cut-off tile {:?}", c, tile);
+        }
+    }
+
+    #[test]
+    fn tiles_stream_row_by_row_from_the_top() {
+        let dungeon = dungeon();
+        let tiles: Vec<((i32, i32), Tile)> = dungeon.tiles().collect();
+        assert_eq!(tiles.len(), dungeon.grid.width() * dungeon.grid.height());
+        let (x_range, y_range) = (dungeon.grid.x_range(), dungeon.grid.y_range());
+        assert_eq!(tiles.first().unwrap().0, (x_range.0, y_range.1));
+        assert_eq!(tiles.last().unwrap().0, (x_range.1, y_range.0));
+        assert!(tiles.iter().all(|&(pos, tile)| dungeon.grid.get(pos) == Some(&tile)));
+    }
+
+    #[test]
+    fn excavated_tiles_are_the_walkable_ones() {
+        let dungeon = dungeon();
+        let walkable = dungeon.grid.iter().filter(|(_, tile)| tile.is_walkable()).count();
+        assert_eq!(dungeon.excavated_tiles().count(), walkable);
+        assert!(dungeon.excavated_tiles().all(|(pos, _)| dungeon.grid.is_walkable(pos)));
+    }
+}
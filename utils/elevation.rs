@@ -0,0 +1,209 @@
+//! # Elevation
+//!
+//! Raises a height map over the tile grid, so dungeons have hills, hollows, cliffs, and
+//! ramps instead of lying flat.
+//!
+//! - **Noise**: Every tile gets a value from smooth value noise (random values on a
+//!   coarse lattice, interpolated between lattice points and layered in finer octaves),
+//!   stretched over the map and rounded down to one of the dungeon type's levels.
+//! - **Relief**: Mountain and Zephyr Highlands dungeons rise (`HIGHLAND_RELIEF`)
+//!   steeply over many levels, every other type gently over a few (see `relief_for_type`).
+//! - **Ramps**: Walkable ground is lowered until neighbouring walkable tiles differ by
+//!   at most one level, so every step between them is a walkable ramp.
+//! - **Cliffs**: Tiles at the foot of a neighbour `CLIFF_HEIGHT` or more levels higher.
+//!   Since walkable ground only changes by ramps, cliffs rise from rock and terrain.
+//!
+//! Elevation never changes what can be walked on. It is drawn from the "elevation" RNG
+//! stream of the `nft_id` and stored in `Dungeon::elevation`; plotted maps shade
+//! excavated tiles by it (`Dungeon::shade`).
+
+use rand::Rng;
+use super::dungeon::Dungeon;
+use super::excavator::base_dungeon_type;
+use super::grid::{Grid, Tile};
+use super::pathfinding::neighbors;
+use super::theme::Rgb;
+
+/// Relief of most dungeon types: gentle swells over a few levels
+pub const LOWLAND_RELIEF: Relief = Relief { levels: 3, spacing: 16 };
+/// Relief of the mountainous dungeon types: steep, rugged ground over many levels
+pub const HIGHLAND_RELIEF: Relief = Relief { levels: 8, spacing: 6 };
+/// Fewest levels a neighbour must rise above a tile to form a cliff
+pub const CLIFF_HEIGHT: u8 = 2;
+/// Noise octaves, each with half the lattice spacing and weight of the one before
+const NOISE_OCTAVES: u32 = 3;
+/// Brightness of the lowest and the highest level, relative to the unshaded color
+const SHADE_RANGE: (f64, f64) = (0.75, 1.2);
+/// Brightness of the foot of a cliff, on top of its level's shading
+const CLIFF_SHADOW: f64 = 0.7;
+
+/// How high and how steeply the ground of a dungeon type rises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Relief {
+    /// Number of elevation levels, from 0 to one less than this.
+    pub levels: u8,
+    /// Tiles between lattice points of the coarsest noise octave; smaller is steeper.
+    pub spacing: usize,
+}
+
+/// Relief of dungeons of the given type
+/// Variant types of upper-case letters rise like their base type.
+pub fn relief_for_type(dungeon_type: &str) -> Relief {
+    match base_dungeon_type(dungeon_type) {
+        "Mountain" | "Zephyr Highlands" => HIGHLAND_RELIEF,
+        _ => LOWLAND_RELIEF,
+    }
+}
+
+/// Height map of `grid` for a dungeon of `dungeon_type`, with the levels of its relief
+/// and ramps between walkable tiles
+pub fn raise_terrain(grid: &Grid<Tile>, dungeon_type: &str, rng: &mut impl Rng) -> Grid<u8> {
+    let Relief { levels, spacing } = relief_for_type(dungeon_type);
+    let (x_range, y_range) = (grid.x_range(), grid.y_range());
+    let noise = value_noise(grid.width(), grid.height(), spacing, NOISE_OCTAVES, rng);
+    let (low, high) = noise.iter().fold((f64::MAX, f64::MIN), |(low, high), &value| (low.min(value), high.max(value)));
+    let spread = (high - low).max(f64::EPSILON);
+
+    let mut elevation = Grid::new(x_range, y_range, 0);
+    for (pos, _) in grid.iter() {
+        let value = noise[(pos.1 - y_range.0) as usize * grid.width() + (pos.0 - x_range.0) as usize];
+        let level = ((value - low) / spread * levels as f64) as u8;
+        elevation.set(pos, level.min(levels - 1));
+    }
+
+    // Lower walkable ground until every step between walkable tiles is a ramp
+    let walkable: Vec<(i32, i32)> = grid.iter().filter(|(_, tile)| tile.is_walkable()).map(|(pos, _)| pos).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &pos in &walkable {
+            let lowest = neighbors(pos).into_iter().filter(|&next| grid.is_walkable(next)).filter_map(|next| elevation.get(next).copied()).min();
+            if let (Some(lowest), Some(&level)) = (lowest, elevation.get(pos)) {
+                if level > lowest + 1 {
+                    elevation.set(pos, lowest + 1);
+                    changed = true;
+                }
+            }
+        }
+    }
+    elevation
+}
+
+// Value noise in [0, 1] for a `width` x `height` map with lattice points `spacing` tiles
+// apart in the first of `octaves` octaves, row-major from the lowest row
+pub(crate) fn value_noise(width: usize, height: usize, spacing: usize, octaves: u32, rng: &mut impl Rng) -> Vec<f64> {
+    let mut noise = vec![0.0; width * height];
+    let mut total = 0.0;
+    for octave in 0..octaves {
+        let cell = (spacing >> octave).max(1);
+        let weight = 0.5f64.powi(octave as i32);
+        let columns = width / cell + 2;
+        let lattice: Vec<f64> = (0..columns * (height / cell + 2)).map(|_| rng.gen()).collect();
+        let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+        for y in 0..height {
+            for x in 0..width {
+                let (cx, cy) = (x / cell, y / cell);
+                let (tx, ty) = (smooth((x % cell) as f64 / cell as f64), smooth((y % cell) as f64 / cell as f64));
+                let at = |dx: usize, dy: usize| lattice[(cy + dy) * columns + cx + dx];
+                let bottom = at(0, 0) + (at(1, 0) - at(0, 0)) * tx;
+                let top = at(0, 1) + (at(1, 1) - at(0, 1)) * tx;
+                noise[y * width + x] += (bottom + (top - bottom) * ty) * weight;
+            }
+        }
+        total += weight;
+    }
+    noise.iter_mut().for_each(|value| *value /= total);
+    noise
+}
+
+impl Dungeon {
+    /// Elevation level of the tile at `pos`; `None` outside the height map.
+    pub fn elevation_at(&self, pos: (i32, i32)) -> Option<u8> {
+        self.elevation.get(pos).copied()
+    }
+
+    /// Whether the tile at `pos` lies at the foot of a cliff (see `elevation`).
+    pub fn is_cliff(&self, pos: (i32, i32)) -> bool {
+        let Some(level) = self.elevation_at(pos) else {
+            return false;
+        };
+        neighbors(pos).into_iter().filter_map(|next| self.elevation_at(next)).any(|next| next >= level + CLIFF_HEIGHT)
+    }
+
+    /// Whether the walkable tile at `pos` leads up a ramp to a walkable neighbour one level higher.
+    pub fn is_ramp(&self, pos: (i32, i32)) -> bool {
+        let Some(level) = self.elevation_at(pos).filter(|_| self.grid.is_walkable(pos)) else {
+            return false;
+        };
+        neighbors(pos).into_iter().any(|next| self.grid.is_walkable(next) && self.elevation_at(next) == Some(level + 1))
+    }
+
+    /// `color` shaded for the elevation of the tile at `pos`: darker when low, brighter when
+    /// high, and darker still at the foot of a cliff; unchanged outside the height map.
+    pub fn shade(&self, pos: (i32, i32), color: Rgb) -> Rgb {
+        let Some(level) = self.elevation_at(pos) else {
+            return color;
+        };
+        let top = (relief_for_type(&self.dungeon_type).levels - 1).max(1);
+        let mut factor = SHADE_RANGE.0 + (SHADE_RANGE.1 - SHADE_RANGE.0) * level.min(top) as f64 / top as f64;
+        if self.is_cliff(pos) {
+            factor *= CLIFF_SHADOW;
+        }
+        let channel = |value: u8| (value as f64 * factor).round().min(255.0) as u8;
+        (channel(color.0), channel(color.1), channel(color.2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::super::excavator::parse_nft_id;
+    use super::super::fixtures::dungeon;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    #[test]
+    fn walkable_ground_only_changes_by_ramps() {
+        let dungeon = dungeon();
+        assert_eq!((dungeon.elevation.x_range(), dungeon.elevation.y_range()), (dungeon.grid.x_range(), dungeon.grid.y_range()));
+        assert!(dungeon.elevation.iter().all(|(_, &level)| level < LOWLAND_RELIEF.levels));
+        for (pos, _) in dungeon.grid.iter().filter(|(_, tile)| tile.is_walkable()) {
+            let level = dungeon.elevation_at(pos).unwrap();
+            for next in neighbors(pos).into_iter().filter(|&next| dungeon.grid.is_walkable(next)) {
+                assert!(dungeon.elevation_at(next).unwrap().abs_diff(level) <= 1, "{:?} to {:?}", pos, next);
+            }
+        }
+        assert!(dungeon.grid.iter().any(|(pos, _)| dungeon.is_ramp(pos)));
+        assert_eq!(parse_nft_id(&dungeon.nft_id).unwrap().elevation, dungeon.elevation);
+    }
+
+    #[test]
+    fn mountains_rise_higher_with_cliffs() {
+        let options = GenerateOptions { dungeon_type: Some("Mountain".to_string()), seed: Some(3), ..GenerateOptions::default() };
+        let mountain = parse_nft_id(&generate_nft_id_with(&options).unwrap()).unwrap();
+        let highest = mountain.elevation.iter().map(|(_, &level)| level).max().unwrap();
+        assert!((LOWLAND_RELIEF.levels..HIGHLAND_RELIEF.levels).contains(&highest));
+        assert!(mountain.grid.iter().any(|(pos, &tile)| tile != Tile::Wall && mountain.is_cliff(pos)));
+        assert_eq!(relief_for_type("Zephyr Highlands"), HIGHLAND_RELIEF);
+        assert_eq!(relief_for_type("Temple"), LOWLAND_RELIEF);
+    }
+
+    #[test]
+    fn shading_brightens_with_height() {
+        let mut dungeon = dungeon();
+        dungeon.elevation = Grid::new(dungeon.grid.x_range(), dungeon.grid.y_range(), 0);
+        assert!(!dungeon.grid.iter().any(|(pos, _)| dungeon.is_cliff(pos) || dungeon.is_ramp(pos)));
+        let pos = dungeon.rooms[0].center;
+        let low = dungeon.shade(pos, (100, 100, 100));
+        dungeon.elevation.set(pos, LOWLAND_RELIEF.levels - 1);
+        let high = dungeon.shade(pos, (100, 100, 100));
+        assert!(high.0 > 100 && low.0 < 100);
+        assert!(dungeon.is_cliff((pos.0 + 1, pos.1)));
+        assert_eq!(dungeon.shade((i32::MAX, 0), (100, 100, 100)), (100, 100, 100));
+
+        // The noise stays within [0, 1]
+        let noise = value_noise(40, 30, 8, NOISE_OCTAVES, &mut StdRng::seed_from_u64(1));
+        assert!(noise.iter().all(|value| (0.0..=1.0).contains(value)));
+    }
+}
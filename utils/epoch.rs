@@ -0,0 +1,91 @@
+//! # Living Dungeons
+//!
+//! Dungeons that change over time: an epoch, counted in Chia blocks, is mixed into where
+//! monsters stand and decoration objects lie, while the rooms, tunnels, rubble, terrain,
+//! and monster species of the dungeon stay as its NFT ID decodes them.
+//!
+//! - **Epochs**: `GenerationConfig::epoch`, kept in `Dungeon::epoch`. A block height
+//!   maps to the epoch of `BLOCKS_PER_EPOCH` blocks holding it (`epoch_for_height`), so
+//!   dungeons read at the current height change about once a day. The current height
+//!   comes from a wallet with the `chia-rpc` feature (`rpc::ChiaRpcClient::height`).
+//! - **Streams**: The "spawn" and "decorations" RNG streams are renamed per epoch
+//!   (`epoch_stream`), so every epoch places them anew and reading an epoch again places
+//!   them the same way. Dungeons without an epoch keep the timeless streams.
+//!
+//! Epochs are dropped by `GenerationConfig::structure_config`, so a `Pipeline` lays a
+//! dungeon out once and only decorates it again for every new epoch.
+
+use super::config::GenerationConfig;
+use super::dungeon::Dungeon;
+use super::error::DungeonError;
+use super::excavator::parse_nft_id_with_config;
+
+/// Blocks of one epoch, about a day of Chia blocks
+pub const BLOCKS_PER_EPOCH: u64 = 4608;
+
+/// Epoch holding the block at `height`
+pub fn epoch_for_height(height: u64) -> u64 {
+    height / BLOCKS_PER_EPOCH
+}
+
+/// Name of the RNG `stream` at `epoch`, such as "spawn@12"; the stream itself without one
+pub fn epoch_stream(stream: &str, epoch: Option<u64>) -> String {
+    match epoch {
+        Some(epoch) => format!("{}@{}", stream, epoch),
+        None => stream.to_string(),
+    }
+}
+
+/// Decodes the `nft_id` like `parse_nft_id`, with the monsters and decorations of `epoch`
+/// (see `epoch`)
+pub fn parse_nft_id_at_epoch(nft_id: &str, epoch: u64) -> Result<Dungeon, DungeonError> {
+    parse_nft_id_with_config(nft_id, &GenerationConfig { epoch: Some(epoch), ..GenerationConfig::default() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::super::pipeline::{Pipeline, PipelineStats};
+    use super::*;
+
+    #[test]
+    fn epochs_move_monsters_around_a_stable_layout() {
+        let timeless = dungeon();
+        let first = parse_nft_id_at_epoch(NFT_ID, 1).unwrap();
+        let second = parse_nft_id_at_epoch(NFT_ID, 2).unwrap();
+        assert_eq!(first, parse_nft_id_at_epoch(NFT_ID, 1).unwrap());
+        assert_eq!(first.epoch, Some(1));
+        assert_eq!(timeless.epoch, None);
+        for dungeon in [&first, &second] {
+            assert_eq!((&dungeon.rooms, &dungeon.tunnels, &dungeon.grid), (&timeless.rooms, &timeless.tunnels, &timeless.grid));
+            assert_eq!(dungeon.floor_count(), timeless.floor_count());
+            assert_eq!(dungeon.monsters.len(), timeless.monsters.len());
+            let species = |dungeon: &Dungeon| dungeon.monsters.iter().map(|monster| monster.species.clone()).collect::<Vec<_>>();
+            assert_eq!(species(dungeon), species(&timeless));
+        }
+        let positions = |dungeon: &Dungeon| dungeon.monsters.iter().map(|monster| monster.position).collect::<Vec<_>>();
+        assert_ne!(positions(&first), positions(&second));
+        assert_ne!(positions(&first), positions(&timeless));
+        assert_ne!(first.decorations, second.decorations);
+
+        // Lower floors move with the epoch too
+        let deep_id = format!("{}qE2uR8wT4oP6iA0sD3fG7hJ9kL1zX5cV", NFT_ID);
+        let (first, second) = (parse_nft_id_at_epoch(&deep_id, 1).unwrap(), parse_nft_id_at_epoch(&deep_id, 2).unwrap());
+        assert_eq!(first.floors[0].rooms, second.floors[0].rooms);
+        assert_ne!(first.floors[0].monsters, second.floors[0].monsters);
+    }
+
+    #[test]
+    fn heights_map_to_epochs_sharing_one_structure() {
+        assert_eq!((epoch_for_height(0), epoch_for_height(BLOCKS_PER_EPOCH - 1), epoch_for_height(BLOCKS_PER_EPOCH)), (0, 0, 1));
+        assert_eq!(epoch_stream("spawn", Some(12)), "spawn@12");
+        assert_eq!(epoch_stream("spawn", None), "spawn");
+
+        let mut pipeline = Pipeline::new();
+        for epoch in [3, 4] {
+            let config = GenerationConfig { epoch: Some(epoch), ..GenerationConfig::default() };
+            assert_eq!(*pipeline.dungeon(NFT_ID, &config).unwrap(), parse_nft_id_at_epoch(NFT_ID, epoch).unwrap());
+        }
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 1, decorations: 2, renders: 0 });
+    }
+}
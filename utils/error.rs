@@ -0,0 +1,93 @@
+//! # Dungeon Errors
+//!
+//! `DungeonError` is returned by every parsing path instead of panicking or
+//! reporting plain strings.
+
+use std::fmt;
+use super::chia::Bech32Error;
+
+/// Minimum NFT ID length: the "nft1" prefix, the room-count character, and at
+/// least one payload character; every longer ID decodes, wrapping around its payload
+/// (see `excavator`).
+pub const MIN_NFT_ID_LEN: usize = 6;
+
+/// Errors returned while decoding an NFT ID into a dungeon.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DungeonError {
+    /// The ID does not start with "nft1".
+    InvalidPrefix,
+    /// The ID has fewer characters than decoding requires.
+    TooShort { len: usize, min: usize },
+    /// A character after the prefix is not ASCII alphanumeric.
+    InvalidChar { index: usize, found: char },
+    /// The room-count character, the first after "nft1", is not ASCII alphanumeric.
+    InvalidRoomChar(char),
+    /// A derived value fell outside its valid range.
+    OutOfRange { field: &'static str, value: i64 },
+    /// The ID is not a valid bech32m Chia NFT ID.
+    InvalidChiaId(Bech32Error),
+    /// A `GenerationConfig` parameter cannot produce a dungeon.
+    InvalidConfig(&'static str),
+    /// A floor was left with no rooms to excavate, such as when an observer vetoes every
+    /// room (see `hooks`).
+    NothingExcavated(&'static str),
+}
+
+impl fmt::Display for DungeonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DungeonError::InvalidPrefix => write!(f, "Invalid NFT ID format. It must start with 'nft1'."),
+            DungeonError::TooShort { len, min } => {
+                write!(f, "NFT ID is too short: {} characters, at least {} required.", len, min)
+            }
+            DungeonError::InvalidChar { index, found } => {
+                write!(f, "Invalid character '{}' at position {}; only ASCII letters and digits are allowed.", found, index)
+            }
+            DungeonError::InvalidRoomChar(c) => write!(f, "Invalid character for room count: '{}'.", c),
+            DungeonError::OutOfRange { field, value } => write!(f, "Derived {} is out of range: {}.", field, value),
+            DungeonError::InvalidChiaId(err) => write!(f, "Invalid Chia NFT ID: {}", err),
+            DungeonError::InvalidConfig(reason) => write!(f, "Invalid generation config: {}.", reason),
+            DungeonError::NothingExcavated(reason) => write!(f, "Nothing to excavate: {}.", reason),
+        }
+    }
+}
+
+impl std::error::Error for DungeonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DungeonError::InvalidChiaId(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Bech32Error> for DungeonError {
+    fn from(err: Bech32Error) -> DungeonError {
+        DungeonError::InvalidChiaId(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use super::super::excavator::parse_nft_id;
+    use super::*;
+
+    #[test]
+    fn malformed_ids_return_errors_instead_of_panicking() {
+        assert_eq!(parse_nft_id("xch1abcdef").err(), Some(DungeonError::InvalidPrefix));
+        assert_eq!(parse_nft_id("nft1a").err(), Some(DungeonError::TooShort { len: 5, min: MIN_NFT_ID_LEN }));
+        assert_eq!(parse_nft_id("nft1ab-cd").err(), Some(DungeonError::InvalidChar { index: 6, found: '-' }));
+        assert_eq!(parse_nft_id("nft1-abcd").err(), Some(DungeonError::InvalidRoomChar('-')));
+    }
+
+    #[test]
+    fn errors_describe_themselves_and_their_source() {
+        assert_eq!(DungeonError::InvalidPrefix.to_string(), "Invalid NFT ID format. It must start with 'nft1'.");
+        assert_eq!(DungeonError::InvalidChar { index: 6, found: '-' }.to_string(), "Invalid character '-' at position 6; only ASCII letters and digits are allowed.");
+        let err = DungeonError::from(Bech32Error::InvalidChecksum);
+        assert_eq!(err.to_string(), "Invalid Chia NFT ID: Invalid bech32m checksum.");
+        assert!(err.source().is_some());
+        assert!(DungeonError::InvalidPrefix.source().is_none());
+    }
+}
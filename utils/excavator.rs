@@ -8,23 +8,41 @@
 //! 1. **NFT Decoding**:
 //!    - Decodes the `nft_id` (e.g., "nft1qgqarlcwfjj7ct7kvh0zt067am2mgewp4y7a2nzfx8d9x8mudmes4u8mnv") 
 //!      to extract dungeon properties.
+//!    - Real bech32m Chia NFT IDs are checksum-verified and their launcher ID recorded;
+//!      synthetic base62 IDs fall back to plain character decoding.
+//!    - Characters are read as base62 digits: '0'-'9' are 0-9, 'a'-'z' are 10-35, and
+//!      'A'-'Z' are 36-61. Bech32m IDs are always lower-case, so they never reach past 35.
+//!    - With a `GenerationConfig::collection_salt`, every character below is read from a
+//!      salted copy of the ID instead (see `seed::salt_nft_id`), so each collection gets
+//!      its own dungeons; the launcher ID still comes from the ID itself.
 //!
 //! 2. **Dungeon Attributes**:
 //!    - **Number of Rooms**: Determined by the first character after "nft1".
 //!      For example:
 //!        - '1' corresponds to (2 + 1) rooms.
-//!        - 'z' corresponds to (2 + 36) rooms.
+//!        - 'z' corresponds to (2 + 35) rooms.
+//!        - 'Z' wraps around to (2 + 25) rooms.
 //!    - **Room Center Coordinates**: Starting from the character immediately after the room count,
 //!      every two characters represent an (x, y) coordinate. If the number of rooms exceeds the 
 //!      available characters for encoding, the process wraps to reuse characters.
 //!    - **Room Sizes**: The size of each room is calculated based on the square area formula:
-//!      `size = (1 + character value)^2`.
+//!      `size = (1 + character value)^2`, one room per character at the end of the ID. With
+//!      `RoomSizing::Budgeted`, the sizes shrink until the total area fits a budget decoded
+//!      from the whole `nft_id` (`area_budget`).
 //!    - **Room Shapes**: Shapes are derived from the character following the room coordinates.
+//!    - **Any Length**: Coordinates, shapes, and sizes read the payload after the room count
+//!      (from `PAYLOAD_START`) as a cycle, forwards from its start or backwards from its end,
+//!      so every ID of at least `MIN_NFT_ID_LEN` characters decodes, however many rooms it
+//!      has; shorter IDs fail with `DungeonError::TooShort`.
 //!      Each shape is represented as a unique pattern of offsets relative to the room center.
+//!    - **Room Kinds**: The first room is the entrance and the last the exit. Every other room
+//!      is a boss, treasure, shrine, or normal room, picked from its coordinate characters.
 //!
 //! 3. **Additional Properties**:
-//!    - **Dungeon Type**: The most frequent character in the `nft_id` determines the environment 
-//!      (e.g., "Forest", "Hell").
+//!    - **Dungeon Type**: The most frequent letter in the `nft_id` determines the environment 
+//!      (e.g., "Forest", "Hell"). Upper-case letters pick a variant of the type of the same
+//!      letter (e.g., "Fungal Forest", "Hellforge"), see `DUNGEON_VARIANTS`. The next most
+//!      frequent characters add a sub-biome modifier (e.g., "Frozen Necropolis"), see `biome`.
 //!    - **Dungeon Level**: Computed based on the total area of the rooms, categorized every 1000 units.
 //!      For example:
 //!        - Area 0-999 → Level 1
@@ -32,10 +50,30 @@
 //!
 //! 4. **Excavation and Connections**:
 //!    - Excavates rooms based on their sizes and shapes.
-//!    - Randomly adds extra excavated points within the dungeon's x and y ranges to simulate scattered elements.
-//!    - Generates tunnels connecting room centers using Manhattan-style paths, ensuring connectivity.
+//!    - Decorates the map with rubble heaped against rooms and strewn along tunnels, denser
+//!      in some dungeon types than others (see `decorate`). The randomness is seeded from a
+//!      hash of the `nft_id`, so the same NFT always yields the same dungeon.
+//!    - Generates tunnels connecting room centers along a minimum spanning tree of the rooms,
+//!      ensuring every room is reachable (`Dungeon::is_fully_connected`). Tunnels are
+//!      Manhattan-style paths unless the config picks another style (see `tunnels`).
+//!    - Corridors widen with the dungeon level (`corridor_width`), and door tiles mark where
+//!      a tunnel enters a room.
+//!
+//!    - Natural dungeon types (e.g., "Cave", "Ocean Depths") are eroded into organic caverns
+//!      by a cellular-automaton pass (see `smoothing`).
+//!
+//!    - Water, Lava Pits, and Desert dungeons get rivers, lava channels, and sand drifts
+//!      carved into their floor (see `terrain`).
+//!    - Spawns monsters in the rooms according to the dungeon type and level (see `spawn`).
+//!    - Digs lower floors from later segments of the `nft_id`, linked by stairs (see `floors`).
+//!
+//!    - `GenerationAlgorithm::Noise` digs open caves from a noise field instead of rooms
+//!      and tunnels (see `noise`), and `GenerationAlgorithm::Maze` carves a maze around
+//!      the rooms instead of tunnels (see `maze`).
 //!
 //! 5. **Generated Map**:
+//!    - Populates a `Grid<Tile>` (Wall, Floor, Tunnel, Door, RoomCenter, stairs) covering every excavated
+//!      coordinate for O(1) tile queries.
 //!    - Outputs a 2D grid of dungeon tiles using ASCII characters or can be plotted graphically.
 //!    - Symbols:
 //!        - `@`: Empty space.
@@ -44,34 +82,113 @@
 //! ## Functions:
 //!
 //! ### Core Functions:
-//! - `parse_nft_id`: Decodes the `nft_id` and returns detailed dungeon attributes, including the
-//!   number of rooms, coordinates, sizes, shapes, and dungeon map.
+//! - `parse_nft_id`: Decodes the `nft_id` and returns a `Dungeon` with its rooms, tunnels,
+//!   bounds, type, level, and excavated map.
+//!
+//! - `parse_chia_nft_id`: Like `parse_nft_id`, but only accepts checksummed bech32m Chia NFT IDs.
+//!
+//! - `parse_nft_id_with_config`: Like `parse_nft_id`, with tunable parameters (`GenerationConfig`).
 //!
-//! - `get_room_offsets`: Generates offset coordinates for a room based on its shape and size.
+//! - `parse_nft_id_with_shapes`: Like `parse_nft_id_with_config`, with custom room shapes (`ShapeRegistry`).
 //!
-//! - `add_random_excavated_points`: Adds randomly scattered excavated points within a given range.
+//! - `parse_nft_id_with_observer`: Like `parse_nft_id_with_shapes`, with generation hooks (`GenerationObserver`).
+//!
+//! - `shapes::room_offsets`: Generates offset coordinates for a room based on its shape and size.
+//!
+//! - `decorate::decorate`: Places rubble around rooms and tunnels, by dungeon type.
 //!
 //! - `generate_tunnels`: Creates tunnels connecting room centers to ensure the dungeon is fully connected.
 //!
+//! - `corridor_width`: Corridor width in tiles for a dungeon level.
+//!
 //! ### Helper Functions:
-//! - `char_to_num`: Converts a character into a numeric value, handling both alphanumeric characters.
+//! - `char_to_num`: Converts a character into its base62 value.
+//!
+//! - `base_dungeon_type`: The lower-case dungeon type an upper-case variant is derived from.
 //!
 //! ## Example Usage:
 //!
 //! ```rust
+//! # use chia_dungeon::utils::excavator::parse_nft_id;
 //! let nft_id = "nft1qgqarlcwfjj7ct7kvh0zt067am2mgewp4y7a2nzfx8d9x8mudmes4u8mnv";
-//! let dungeon_data = parse_nft_id(nft_id).expect("Failed to parse NFT ID");
+//! let dungeon = parse_nft_id(nft_id).expect("Failed to parse NFT ID");
 //!
-//! println!("Dungeon Level: {}", dungeon_data.10);
-//! println!("Dungeon Type: {}", dungeon_data.9);
-//! println!("Dungeon Map: {:?}", dungeon_data.11);
+//! println!("Dungeon Level: {}", dungeon.level);
+//! println!("Dungeon Type: {}", dungeon.dungeon_type);
+//! println!("Dungeon Map: {:?}", dungeon.excavated);
 //! ```
 
-use std::collections::HashMap;
-use rand::Rng;
 use std::collections::HashSet;
+use rand::Rng;
+use super::analysis::letter_frequency;
+use super::biome::classify;
+use super::chia;
+use super::config::{GenerationAlgorithm, GenerationConfig, RoomSizing};
+use super::decorate::{decorate, place_decorations};
+use super::dungeon::{Bounds, Dungeon, Room, RoomKind};
+use super::elevation::raise_terrain;
+use super::error::{DungeonError, MIN_NFT_ID_LEN};
+use super::floors::dig_floors;
+use super::grid::{Grid, Tile};
+use super::hooks::{GenerationObserver, HookAction, NoObserver, Stage};
+use super::liquid::flood_lowlands;
+use super::maze::carve_maze;
+use super::naming::{room_name, unique_names};
+use super::noise::excavate_caves;
+use super::pathfinding::neighbors;
+use super::seed::{rng_for_stream, salt_nft_id};
+use super::shapes::ShapeRegistry;
+use super::smoothing::smooth_excavation;
+use super::spawn::spawn_monsters;
+use super::terrain::carve_terrain;
+use super::tunnels::TunnelStyle;
+
+/// Variant dungeon types of the upper-case letters 'A'-'Z', each paired with the type of
+/// the same lower-case letter it is derived from
+pub const DUNGEON_VARIANTS: [(&str, &str); 26] = [
+    ("Astral Ruins", "Ancient Ruins"),
+    ("Bone Wastes", "Barrens"),
+    ("Crystal Grotto", "Cave"),
+    ("Dune Sea", "Desert"),
+    ("Elven Glade", "Enchanted Forest"),
+    ("Fungal Forest", "Forest"),
+    ("Goblin Steppe", "Grassland"),
+    ("Hellforge", "Hell"),
+    ("Ice Palace", "Ice Cavern"),
+    ("Jade Jungle", "Jungle"),
+    ("Kingsfall Keep", "Kingdom Ruins"),
+    ("Lava Tubes", "Lava Pits"),
+    ("Mithril Mines", "Mountain"),
+    ("Nightmare Crypt", "Necropolis"),
+    ("Oceanic Trench", "Ocean Depths"),
+    ("Plague Marsh", "Poison Swamp"),
+    ("Quicksilver Bog", "Quagmire"),
+    ("Rotwood Canopy", "Rainforest"),
+    ("Sunken Swamp", "Swamp"),
+    ("Twilight Temple", "Temple"),
+    ("Undercity", "Underground Tunnels"),
+    ("Void Caldera", "Volcanic Crater"),
+    ("Whirlpool Grotto", "Water"),
+    ("Xeno Nexus", "Xeno Hive"),
+    ("Yellowed Dunes", "Yellow Wasteland"),
+    ("Zenith Peaks", "Zephyr Highlands"),
+];
+
+/// The lower-case dungeon type a variant is derived from; other types map to themselves
+/// Themes, bestiaries, and trap flavors are looked up by the base type.
+pub fn base_dungeon_type(dungeon_type: &str) -> &str {
+    DUNGEON_VARIANTS
+        .iter()
+        .find(|&&(variant, _)| variant == dungeon_type)
+        .map_or(dungeon_type, |&(_, base)| base)
+}
 
-fn get_dungeon_type(most_frequent_char: &str) -> String {
+pub(crate) fn get_dungeon_type(most_frequent_char: &str) -> String {
+    if let Some(c) = most_frequent_char.chars().next().filter(char::is_ascii_uppercase) {
+        if let Some(&(variant, _)) = DUNGEON_VARIANTS.get(usize::from(c as u8 - b'A')) {
+            return variant.to_string();
+        }
+    }
     match most_frequent_char {
         "a" => "Ancient Ruins".to_string(),
         "b" => "Barrens".to_string(),
@@ -103,116 +220,202 @@ fn get_dungeon_type(most_frequent_char: &str) -> String {
     }
 }
 
-fn get_dungeon_level(area_size: u64) -> u64 {
-    (area_size / 1000) + 1
-}
-
-fn get_room_offsets(size: u32, shape: String) -> Vec<(i32, i32)> {
-    let size = size as i32; // Convert size to i32 for calculations
-    let shape_char = shape.to_ascii_lowercase(); // Normalize shape to lowercase for consistent matching
-
-    // Define base offsets based on shape character
-    let base_offsets = match shape_char.as_str() {
-        // 0-9 (unique patterns)
-        "0" => vec![(0, 0)], // Single point
-        "1" => vec![(0, 1), (0, -1)], // Vertical line
-        "2" => vec![(1, 0), (-1, 0)], // Horizontal line
-        "3" => vec![(1, 1), (-1, -1)], // Diagonal line
-        "4" => vec![(-1, 0), (1, 0), (0, 1)], // L-shape
-        "5" => vec![(0, -1), (1, 0), (-1, 1)], // Reverse L-shape
-        "6" => vec![(-1, -1), (1, 1), (1, -1), (-1, 1)], // Diagonal cross
-        "7" => vec![(0, 1), (1, 0), (0, -1), (-1, 0)], // Full cross
-        "8" => vec![(-2, 0), (2, 0), (0, -2), (0, 2)], // Large cross
-        "9" => vec![(-3, 0), (3, 0), (0, -3), (0, 3)], // Very large cross
-
-        // a-z (unique patterns with distinct offsets)
-        "a" => vec![(0, 1), (-1, 0), (1, 0), (0, -1)], // Cross
-        "b" => vec![(-1, 1), (1, -1)], // Diagonal corners
-        "c" => vec![(-1, 1), (1, 1), (1, -1), (-1, -1)], // Full diamond
-        "d" => vec![(-2, 2), (2, 2), (-2, -2), (2, -2)], // Large diamond
-        "e" => vec![(-2, 0), (2, 0), (0, -2), (0, 2)], // Expanded cross
-        "f" => vec![(1, 1), (2, 2)], // Expanding diagonal
-        "g" => vec![(-1, 0), (-2, 0), (-3, 0)], // Horizontal line left
-        "h" => vec![(0, 1), (0, 2), (0, 3)], // Vertical line up
-        "i" => vec![(0, 0)], // Single point
-        "j" => vec![(-1, 1), (0, 1), (1, 0)], // Corner
-        "k" => vec![(0, 2), (-1, 1), (1, -1)], // Triangle
-        "l" => vec![(-2, 0), (1, -1), (2, -2)], // Reverse diagonal
-        "m" => vec![(-1, -1), (0, 1), (1, 0), (-1, 1)], // M-shape
-        "n" => vec![(-1, 1), (1, -1), (0, 0)], // Zigzag
-        "o" => vec![(-2, 2), (2, -2), (0, 0)], // Circle-like
-        "p" => vec![(-1, 1), (1, 1), (1, -1)], // Partial diamond
-        "q" => vec![(-1, 1), (-1, -1)], // Partial diamond reversed
-        "r" => vec![(-2, 2), (0, 2), (2, 2)], // Semi-circle
-        "s" => vec![(-2, -2), (0, -2), (2, -2)], // Semi-circle reversed
-        "t" => vec![(-1, 0), (0, 0), (1, 0)], // T-shape
-        "u" => vec![(-1, -1), (1, -1)], // U-shape
-        "v" => vec![(0, 2), (-1, 1), (1, 1)], // V-shape
-        "w" => vec![(-1, 1), (0, 0), (1, -1)], // W-shape
-        "x" => vec![(-2, 2), (2, -2), (-2, -2), (2, 2)], // X-shape
-        "y" => vec![(0, 2), (-1, 1), (1, -1)], // Y-shape
-        "z" => vec![(-1, 0), (0, 1), (1, 0)], // Z-shape
-        _ => vec![], // Default to no offsets if shape is not recognized
-    };
+/// The letter whose dominance in an `nft_id` yields `dungeon_type`, ignoring case
+pub(crate) fn dungeon_type_letter(dungeon_type: &str) -> Option<char> {
+    ('a'..='z')
+        .chain('A'..='Z')
+        .find(|c| get_dungeon_type(&c.to_string()).eq_ignore_ascii_case(dungeon_type))
+}
 
+/// Dungeon type named by the most frequent letter of the `nft_id`
+pub(crate) fn decode_dungeon_type(nft_id: &str) -> String {
+    get_dungeon_type(&letter_frequency(nft_id).1)
+}
 
-    // Generate all points within the extended range based on size
-    let mut offsets = Vec::new();
+/// Number of rooms encoded by the room-count character
+pub(crate) fn decode_room_count(room_char: char, config: &GenerationConfig) -> usize {
+    let choices = config.max_rooms.saturating_sub(config.min_rooms).saturating_add(1);
+    config.min_rooms.saturating_add(char_to_num(room_char).unsigned_abs() as usize % choices)
+}
 
-    for &(base_x, base_y) in &base_offsets {
-        for x in (base_x - (size - 1))..=(base_x + (size - 1)) {
-            for y in (base_y - (size - 1))..=(base_y + (size - 1)) {
-                if !offsets.contains(&(x, y)) { // Avoid duplicates
-                    offsets.push((x, y));
-                }
-            }
-        }
+/// Room sizes encoded by the last `num_rooms` characters, and their total area, which
+/// saturates at `u64::MAX` rather than wrapping
+pub(crate) fn decode_room_sizes(chars: &[char], num_rooms: usize, config: &GenerationConfig) -> (Vec<u32>, u64) {
+    let mut sizes = Vec::new();
+    let mut area_size: u64 = 0;
+    for size_char in trailing_chars(chars, num_rooms) {
+        let size = decode_room_size(size_char, num_rooms, config);
+        sizes.push(size);
+        area_size = area_size.saturating_add(room_area(size)); // Calculate area and add it to `area_size`
+    }
+    if config.room_sizing == RoomSizing::Budgeted {
+        area_size = fit_to_budget(&mut sizes, area_budget(chars, config), config.min_room_size);
     }
+    (sizes, area_size)
+}
 
-    offsets
+// Area of a room of the given size, as counted towards the dungeon level, saturating at
+// `u64::MAX` for sizes far beyond `MAX_ROOM_SIZE`
+fn room_area(size: u32) -> u64 {
+    (u64::from(size) * 2 + 1).saturating_pow(2)
 }
 
-/// Add random excavated points to the map
-fn add_random_excavated_points(
-    existing_points: Vec<(i32, i32)>,
-    x_range: (i32, i32),
-    y_range: (i32, i32),
-    num_points: usize,
-) -> Vec<(i32, i32)> {
-    let mut rng = rand::thread_rng();
-    let mut point_set: HashSet<(i32, i32)> = existing_points.iter().copied().collect();
+/// Room area a `RoomSizing::Budgeted` dungeon may reach: `level_band * (1 + sum % BUDGET_LEVELS) - 1`,
+/// where `sum` adds up the base62 values of the characters after "nft1"
+/// The top floor of a budgeted dungeon therefore never rises past level `BUDGET_LEVELS`.
+pub fn area_budget(chars: &[char], config: &GenerationConfig) -> u64 {
+    let sum: u64 = chars.iter().skip(4).map(|&c| char_to_num(c) as u64).sum();
+    config.level_band.saturating_mul(1 + sum % BUDGET_LEVELS).saturating_sub(1)
+}
 
-    while point_set.len() < existing_points.len() + num_points {
-        let random_x = rng.gen_range(x_range.0..=x_range.1);
-        let random_y = rng.gen_range(y_range.0..=y_range.1);
-        point_set.insert((random_x, random_y));
+// Shrinks `sizes` until their total area fits `budget`, and returns the area
+// Every room is scaled by the same factor first, then the largest rooms lose a step at a
+// time; rooms never shrink below `min_size`, so many rooms may still overrun a tiny budget.
+fn fit_to_budget(sizes: &mut [u32], budget: u64, min_size: u32) -> u64 {
+    let total_area = |sizes: &[u32]| sizes.iter().fold(0u64, |area, &size| area.saturating_add(room_area(size)));
+    let mut area = total_area(sizes);
+    if area <= budget {
+        return area;
+    }
+    let scale = (budget as f64 / area as f64).sqrt();
+    for size in sizes.iter_mut() {
+        let side = (*size as f64 * 2.0 + 1.0) * scale;
+        *size = (((side - 1.0) / 2.0).max(0.0) as u32).max(min_size);
     }
+    area = total_area(sizes);
+    while area > budget {
+        let Some(largest) = sizes.iter_mut().filter(|size| **size > min_size).max_by_key(|size| **size) else {
+            break;
+        };
+        area = area.saturating_sub(room_area(*largest) - room_area(*largest - 1));
+        *largest -= 1;
+    }
+    area
+}
 
-    point_set.into_iter().collect()
+/// Size of a room encoded by `size_char` in a dungeon of `num_rooms` rooms, clamped to
+/// `min_room_size..=max_room_size` so crowded dungeons never wrap around to huge rooms
+pub(crate) fn decode_room_size(size_char: char, num_rooms: usize, config: &GenerationConfig) -> u32 {
+    let growth = ((char_to_num(size_char) as f64).sqrt() * config.size_multiplier).round() as i64;
+    let crowding = ((num_rooms as f64).sqrt() / config.crowding_divisor).round() as i64;
+    let size = (config.size_base as i64).saturating_add(growth).saturating_sub(crowding);
+    size.clamp(config.min_room_size as i64, config.max_room_size as i64) as u32
+}
+
+/// Derives a room's role from its coordinate characters
+/// The first room is always the entrance and the last always the exit; the rest are
+/// picked from the sum of their coordinate character values.
+pub(crate) fn get_room_kind(index: usize, num_rooms: usize, x_char: char, y_char: char) -> RoomKind {
+    if index == 0 {
+        return RoomKind::Entrance;
+    }
+    if index + 1 == num_rooms {
+        return RoomKind::Exit;
+    }
+    match (char_to_num(x_char) + char_to_num(y_char)).rem_euclid(10) {
+        0 => RoomKind::Boss,
+        1 | 2 => RoomKind::Treasure,
+        3 => RoomKind::Shrine,
+        _ => RoomKind::Normal,
+    }
+}
+
+/// Level of a dungeon whose rooms cover `area_size` tiles: one level per `level_band`,
+/// starting at 1; a zero band counts as 1 and the level saturates at `u64::MAX`
+pub(crate) fn get_dungeon_level(area_size: u64, level_band: u64) -> u64 {
+    (area_size / level_band.max(1)).saturating_add(1)
 }
 
 /// Generates tunnels connecting room centers
-/// Connects the first room to the second, the third to the fourth, and so on.
-fn generate_tunnels(room_centers: &Vec<(i32, i32)>) -> Vec<Vec<(i32, i32)>> {
+/// Builds a minimum spanning tree over the room centers (Manhattan distance, Prim's algorithm)
+/// and digs one tunnel per tree edge in the given style, so every room is reachable from
+/// every other room.
+pub(crate) fn generate_tunnels(room_centers: &[(i32, i32)], style: TunnelStyle, rng: &mut impl Rng) -> Vec<Vec<(i32, i32)>> {
     let mut tunnels = Vec::new();
+    if room_centers.is_empty() {
+        return tunnels;
+    }
 
-    // Iterate through pairs of room centers
-    for i in (0..room_centers.len()).step_by(2) {
-        if i + 1 < room_centers.len() {
-            let start = room_centers[i];
-            let end = room_centers[i + 1];
-
-            // Generate a tunnel path
-            let tunnel = create_tunnel(start, end);
-            tunnels.push(tunnel);
+    let distance = |a: (i32, i32), b: (i32, i32)| u64::from(a.0.abs_diff(b.0)) + u64::from(a.1.abs_diff(b.1));
+
+    // For each room: whether it is in the tree, and its closest distance to the tree and
+    // closest tree room
+    let first = room_centers[0];
+    let mut closest: Vec<(bool, u64, usize)> = room_centers.iter().map(|&center| (false, distance(center, first), 0)).collect();
+    closest[0].0 = true;
+
+    for _ in 1..room_centers.len() {
+        // Pick the nearest room outside the tree; ties go to the lowest index
+        let Some((next, &(_, _, parent))) = closest.iter().enumerate().filter(|(_, entry)| !entry.0).min_by_key(|&(i, entry)| (entry.1, i)) else {
+            break;
+        };
+        closest[next].0 = true;
+
+        let start = room_centers[parent];
+        let end = room_centers[next];
+        tunnels.push(style.dig(start, end, rng));
+
+        // Update distances now that `next` is part of the tree
+        for (entry, &center) in closest.iter_mut().zip(room_centers) {
+            let d = distance(center, end);
+            if !entry.0 && d < entry.1 {
+                *entry = (false, d, next);
+            }
         }
     }
 
     tunnels
 }
 
+/// Highest level the top floor of a `RoomSizing::Budgeted` dungeon can reach (see `area_budget`)
+pub const BUDGET_LEVELS: u64 = 4;
+
+/// Largest room size a config may allow (`GenerationConfig::max_room_size`)
+pub const MAX_ROOM_SIZE: u32 = 1000;
+
+/// Most rooms a config may allow (`GenerationConfig::max_rooms`), keeping room coordinates
+/// and areas far from the limits of their integer types
+pub const MAX_ROOMS: usize = 1000;
+
+/// Default widest corridor dug by `corridor_width`
+pub const MAX_CORRIDOR_WIDTH: u32 = 3;
+
+/// Index of the first ID character after "nft1" and the room-count character, where the
+/// payload of coordinates, shapes, and sizes starts
+pub const PAYLOAD_START: usize = 5;
+
+/// Corridor width in tiles for a dungeon of the given level
+/// Corridors gain one tile every 4 levels, up to `max_width`.
+pub fn corridor_width(level: u64, max_width: u32) -> u32 {
+    (1 + level.saturating_sub(1) / 4).min(max_width as u64) as u32
+}
+
+// Tiles covered by a corridor of the given width centered on a tunnel tile
+pub(crate) fn corridor_brush((x, y): (i32, i32), width: u32) -> impl Iterator<Item = (i32, i32)> {
+    let (low, high) = (-((width as i32 - 1) / 2), width as i32 / 2);
+    (low..=high).flat_map(move |dx| (low..=high).map(move |dy| (x + dx, y + dy)))
+}
+
+// Corridor tiles just outside a room wherever a tunnel enters or leaves it
+fn find_doors(tunnels: &[Vec<(i32, i32)>], room_tiles: &HashSet<(i32, i32)>, width: u32) -> Vec<(i32, i32)> {
+    let mut doors = Vec::new();
+    for tunnel in tunnels {
+        for step in tunnel.windows(2) {
+            let outside = match (room_tiles.contains(&step[0]), room_tiles.contains(&step[1])) {
+                (false, true) => step[0],
+                (true, false) => step[1],
+                _ => continue,
+            };
+            doors.extend(corridor_brush(outside, width).filter(|pos| {
+                !room_tiles.contains(pos) && neighbors(*pos).iter().any(|next| room_tiles.contains(next))
+            }));
+        }
+    }
+    doors
+}
+
 /// Creates a tunnel (a series of points) connecting two room centers
-fn create_tunnel(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
+pub(crate) fn create_tunnel(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
     let mut tunnel = Vec::new();
 
     // Use a simple Manhattan-style path creation
@@ -238,137 +441,578 @@ fn create_tunnel(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
         }
     }
 
+    // Include the end point so the tunnel always reaches the target room center
+    tunnel.push(end);
+
     tunnel
 }
 
-pub fn parse_nft_id(
+/// Decodes a real bech32m Chia NFT ID into a `Dungeon`, rejecting synthetic IDs.
+pub fn parse_chia_nft_id(nft_id: &str) -> Result<Dungeon, DungeonError> {
+    chia::decode_nft_id(nft_id)?;
+    parse_nft_id(nft_id)
+}
+
+/// Decodes the `nft_id` into a fully excavated `Dungeon`.
+///
+/// Accepts both real bech32m Chia NFT IDs and synthetic base62 IDs; only real IDs
+/// populate `Dungeon::launcher_id`. Malformed IDs return a `DungeonError` and never panic.
+pub fn parse_nft_id(nft_id: &str) -> Result<Dungeon, DungeonError> {
+    parse_nft_id_with_config(nft_id, &GenerationConfig::default())
+}
+
+/// Decodes the `nft_id` into a fully excavated `Dungeon` using tuned generation parameters.
+///
+/// With `GenerationConfig::default()` this is identical to `parse_nft_id`. An invalid
+/// config returns `DungeonError::InvalidConfig`.
+pub fn parse_nft_id_with_config(nft_id: &str, config: &GenerationConfig) -> Result<Dungeon, DungeonError> {
+    parse_nft_id_with_shapes(nft_id, config, &ShapeRegistry::default())
+}
+
+/// Decodes the `nft_id` like `parse_nft_id_with_config`, excavating rooms with the shapes
+/// of `shapes` (see `ShapeRegistry`).
+///
+/// With an empty `ShapeRegistry` this is identical to `parse_nft_id_with_config`.
+pub fn parse_nft_id_with_shapes(nft_id: &str, config: &GenerationConfig, shapes: &ShapeRegistry) -> Result<Dungeon, DungeonError> {
+    parse_nft_id_with_observer(nft_id, config, shapes, &mut NoObserver)
+}
+
+/// Decodes the `nft_id` like `parse_nft_id_with_shapes`, reporting every room, tunnel,
+/// and decoration step to `observer`, which may change or veto it (see `hooks`).
+///
+/// With `NoObserver` this is identical to `parse_nft_id_with_shapes`.
+pub fn parse_nft_id_with_observer(
     nft_id: &str,
-) -> Result<(usize, Vec<(i32, i32)>, Vec<u32>, Vec<String>, (i32, i32), (i32, i32), u64, HashMap<char, usize>, String, String, u64, Vec<(i32, i32)>), String> {
-    // Ensure the NFT ID starts with "nft1" and has sufficient length
-    if !nft_id.starts_with("nft1") || nft_id.len() < 4 {
-        return Err("Invalid NFT ID format. It must start with 'nft1' and be long enough.".to_string());
+    config: &GenerationConfig,
+    shapes: &ShapeRegistry,
+    observer: &mut dyn GenerationObserver,
+) -> Result<Dungeon, DungeonError> {
+    let _span = tracing::debug_span!("parse", nft_id).entered();
+    config.validate()?;
+    let mut dungeon = excavate(nft_id, config, shapes, observer, 0)?;
+
+    // Dig the lower floors and link them with stairs
+    dig_floors(&mut dungeon, config, shapes, observer)?;
+    populate(&mut dungeon);
+
+    tracing::debug!(dungeon_type = %dungeon.dungeon_type, level = dungeon.level, rooms = dungeon.rooms.len(), "parsed dungeon");
+    Ok(dungeon)
+}
+
+/// Spawns the monsters of the top floor of an excavated dungeon and lays its decoration
+/// objects, which avoid the monsters
+pub(crate) fn populate(dungeon: &mut Dungeon) {
+    dungeon.monsters = spawn_monsters(dungeon);
+    dungeon.decorations = place_decorations(dungeon);
+}
+
+/// Characters of the `nft_id`, once it is known to start with "nft1", use only ASCII
+/// alphanumerics after it, and be long enough to decode
+pub(crate) fn nft_id_chars(nft_id: &str) -> Result<Vec<char>, DungeonError> {
+    if !nft_id.starts_with("nft1") {
+        return Err(DungeonError::InvalidPrefix);
     }
+    let chars: Vec<char> = nft_id.chars().collect();
+    if let Some((index, &found)) = chars.iter().enumerate().skip(4).find(|(_, c)| !c.is_ascii_alphanumeric()) {
+        // The first character after "nft1" is the room count
+        if index == 4 {
+            return Err(DungeonError::InvalidRoomChar(found));
+        }
+        return Err(DungeonError::InvalidChar { index, found });
+    }
+    if chars.len() < MIN_NFT_ID_LEN {
+        return Err(DungeonError::TooShort { len: chars.len(), min: MIN_NFT_ID_LEN });
+    }
+    Ok(chars)
+}
 
-    // Extract the number of rooms
-    let room_char = nft_id.chars().nth(4).unwrap(); // First character after "nft1"
-    let num_rooms = match room_char.to_digit(36) {
-        Some(val) => 2 + val as usize,
-        None => return Err("Invalid character for room count.".to_string()),
-    };
+/// Character `index` of an ID of at least `MIN_NFT_ID_LEN` `chars`, reading the payload
+/// after the room-count character as a cycle once past the end of the ID; shorter IDs
+/// read '0' past their end
+pub(crate) fn char_at(chars: &[char], index: usize) -> char {
+    let payload = chars.get(PAYLOAD_START..).unwrap_or_default();
+    chars
+        .get(index)
+        .or_else(|| payload.get(index.checked_sub(PAYLOAD_START)?.checked_rem(payload.len())?))
+        .copied()
+        .unwrap_or('0')
+}
 
-    // Extract coordinates
+/// The last `count` characters of the payload of an ID of at least `MIN_NFT_ID_LEN`
+/// `chars`, wrapping back around the payload when it holds fewer; shorter IDs read '0'
+pub(crate) fn trailing_chars(chars: &[char], count: usize) -> Vec<char> {
+    let payload = chars.get(PAYLOAD_START..).unwrap_or_default();
+    let len = payload.len();
+    (0..count)
+        .map(|i| (count - i).checked_rem(len).and_then(|back| payload.get((len - back) % len)).copied().unwrap_or('0'))
+        .collect()
+}
+
+/// Decodes and excavates a single floor, without lower floors or monsters, reporting each
+/// step to `observer` as the floor at `depth`
+pub(crate) fn excavate(
+    nft_id: &str,
+    config: &GenerationConfig,
+    registry: &ShapeRegistry,
+    observer: &mut dyn GenerationObserver,
+    depth: usize,
+) -> Result<Dungeon, DungeonError> {
+    let layout = excavate_layout(nft_id, config, registry, observer, depth)?;
+    Ok(finish_layout(layout, config, observer, depth))
+}
+
+/// The rooms and tunnels of a single floor, before rubble, smoothing, terrain, and
+/// elevation, which only depend on the later fields of a `GenerationConfig` (see
+/// `GenerationConfig::structure_config`)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Layout {
+    /// The floor so far. Rooms dug from the ID have neither a grid nor elevation yet,
+    /// and their excavated tiles are the rooms and corridors alone.
+    pub(crate) dungeon: Dungeon,
+    /// The ID the floor is decoded from, salted for collections.
+    pub(crate) seed_id: String,
+    /// Bounding box of the room centers that rubble is scattered over; caves (see
+    /// `noise`) get no rubble.
+    pub(crate) rubble_area: Option<((i32, i32), (i32, i32))>,
+}
+
+/// Decodes and lays out the rooms and tunnels of a single floor, reporting the parse,
+/// excavation, and tunnel stages to `observer` as the floor at `depth`
+pub(crate) fn excavate_layout(
+    nft_id: &str,
+    config: &GenerationConfig,
+    registry: &ShapeRegistry,
+    observer: &mut dyn GenerationObserver,
+    depth: usize,
+) -> Result<Layout, DungeonError> {
+    let _span = tracing::debug_span!("excavate", depth).entered();
+    observer.on_stage_started(depth, Stage::Parse);
+    // Collections decode a salted copy of the ID; salting keeps the prefix, the length,
+    // and every invalid character, so errors still describe the ID as given
+    let seed_id = salt_nft_id(nft_id, config.collection_salt.as_deref());
+    let chars = nft_id_chars(&seed_id)?;
+
+    // Real Chia IDs carry a launcher ID; synthetic IDs fall back to character decoding only
+    let launcher_id = chia::decode_nft_id(nft_id).ok().map(|id| chia::launcher_id_hex(&id));
+    if config.algorithm == GenerationAlgorithm::Noise {
+        return excavate_caves(nft_id, &seed_id, &chars, launcher_id, config, observer, depth);
+    }
+
+    // Extract the number of rooms from the first character after "nft1"
+    let num_rooms = decode_room_count(char_at(&chars, 4), config);
+
+    // Extract coordinates and the room kinds derived from them
     let mut coordinates = Vec::new();
-    let coord_start = 5; // Start reading coordinates after "nft1" + room count character
-    let mut coord_index = coord_start;
+    let mut kinds = Vec::new();
+    let mut coord_chars = Vec::new();
+    let mut coord_index = PAYLOAD_START;
 
     for _ in 0..num_rooms {
-        let x_char = nft_id.chars().nth(coord_index).unwrap_or_else(|| {
-            nft_id.chars().nth((coord_index - coord_start) % (nft_id.len() - coord_start)).unwrap()
-        });
-        let y_char = nft_id.chars().nth(coord_index + 1).unwrap_or_else(|| {
-            nft_id.chars().nth((coord_index - coord_start + 1) % (nft_id.len() - coord_start)).unwrap()
-        });
+        let x_char = char_at(&chars, coord_index);
+        let y_char = char_at(&chars, coord_index + 1);
 
         let x = (char_to_num(x_char) as f64 * (num_rooms as f64).sqrt()).round() as i32;
         let y = (char_to_num(y_char) as f64 * (num_rooms as f64).sqrt()).round() as i32;
         coordinates.push((x, y));
+        kinds.push(get_room_kind(coordinates.len() - 1, num_rooms, x_char, y_char));
+        coord_chars.push((x_char, y_char));
 
         coord_index += 2;
     }
 
     // Extract room sizes
-    let mut sizes = Vec::new();
-    let mut area_size = 0;
-    let size_start = nft_id.len() - num_rooms;
-    for i in 0..num_rooms {
-        let size_char = nft_id.chars().nth(size_start + i).unwrap();
-        let size = (2 + ((char_to_num(size_char) as f64).sqrt() * 1.5).round() as i32
-        - ((num_rooms as f64).sqrt() / 4.0).round() as i32) as u32;
-        sizes.push(size);
-        area_size += ((size * 2 + 1).pow(2)) as u64; // Calculate area and add it to `area_size`
-    }
+    let (sizes, area_size) = decode_room_sizes(&chars, num_rooms, config);
 
     // Determine dungeon level based on area size
-    let dungeon_level = get_dungeon_level(area_size);
+    let dungeon_level = get_dungeon_level(area_size, config.level_band);
 
     // Extract room shapes
     let mut shapes = Vec::new();
-    let shape_start = coord_start + (2 * num_rooms);
-    let mut shape_index = shape_start;
+    let shape_start = PAYLOAD_START + (2 * num_rooms);
 
-    for _ in 0..num_rooms {
-        let shape_char = nft_id.chars().nth(shape_index).unwrap_or_else(|| {
-            nft_id.chars().nth((shape_index - coord_start) % (nft_id.len() - coord_start)).unwrap()
-        });
-        shapes.push(shape_char.to_string());
-        shape_index += 1;
+    for shape_index in shape_start..shape_start + num_rooms {
+        shapes.push(char_at(&chars, shape_index).to_string());
+    }
+
+    // Determine dungeon type from the most frequent letter
+    let (char_frequency, most_frequent_char) = letter_frequency(&seed_id);
+    let dungeon_type = get_dungeon_type(&most_frequent_char);
+
+    // Name the rooms from their coordinate and shape characters
+    let names = unique_names(coord_chars.iter().zip(&kinds).enumerate().map(|(i, (&(x_char, y_char), &kind))| {
+        room_name(&dungeon_type, kind, [x_char, y_char, char_at(&chars, shape_start + i)])
+    }));
+
+    // Generate excavated room coordinates, letting the observer change or veto each room
+    observer.on_stage_started(depth, Stage::Excavate);
+    let mut rooms = Vec::new();
+    let decoded = coordinates.into_iter().zip(sizes).zip(shapes).zip(kinds).zip(names);
+    for (i, ((((room_center, size), shape), kind), name)) in decoded.enumerate() {
+        let room_coords: Vec<(i32, i32)> = registry.room_offsets_iter(size, &shape, config.shape_style)
+            .map(|(ox, oy)| (room_center.0.saturating_add(ox), room_center.1.saturating_add(oy)))
+            .collect();
+
+        let mut room = Room {
+            center: room_center,
+            size,
+            shape,
+            kind,
+            name,
+            tiles: room_coords,
+        };
+        if observer.on_room_excavated(depth, i, &mut room) == HookAction::Keep {
+            rooms.push(room);
+        }
+    }
+    if rooms.is_empty() {
+        return Err(DungeonError::NothingExcavated("every room was vetoed"));
     }
+    let coordinates: Vec<(i32, i32)> = rooms.iter().map(|room| room.center).collect();
 
-    // Determine dungeon width and height
+    // Scatter rubble over the room centers' bounding box
     let min_x = coordinates.iter().map(|&(x, _)| x).min().unwrap_or(0) - 1;
     let max_x = coordinates.iter().map(|&(x, _)| x).max().unwrap_or(0) + 1;
     let min_y = coordinates.iter().map(|&(_, y)| y).min().unwrap_or(0) - 1;
     let max_y = coordinates.iter().map(|&(_, y)| y).max().unwrap_or(0) + 1;
 
-    // Calculate frequency of each character a-z
-    let mut char_frequency: HashMap<char, usize> = HashMap::new();
-    for c in nft_id.chars() {
-        if c.is_ascii_lowercase() {
-            *char_frequency.entry(c).or_insert(0) += 1;
-        }
+    let mut all_excavated_coords: Vec<(i32, i32)> = rooms.iter().flat_map(|room| room.tiles.iter().copied()).collect();
+
+    // Generate tunnels between room centers, letting the observer change or veto each tunnel
+    observer.on_stage_started(depth, Stage::Tunnels);
+    // Mazes find their tunnels once carved
+    let maze = config.algorithm == GenerationAlgorithm::Maze;
+    let tunnel_style = config.tunnel_style.resolve(&dungeon_type);
+    let planned = if maze { Vec::new() } else { generate_tunnels(&coordinates, tunnel_style, &mut rng_for_stream(&seed_id, "tunnels")) };
+    let tunnels: Vec<Vec<(i32, i32)>> = planned
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, mut tunnel)| (observer.on_tunnel_created(depth, index, &mut tunnel) == HookAction::Keep).then_some(tunnel))
+        .collect();
+
+    // Flatten and append tunnels, widened to the corridor width, to excavated_coordinates
+    let corridor_width = corridor_width(dungeon_level, config.max_corridor_width);
+    for tunnel in &tunnels {
+        all_excavated_coords.extend(tunnel.iter().flat_map(|&pos| corridor_brush(pos, corridor_width)));
     }
 
-    // Find the first character with the highest frequency
-    let most_frequent_char = char_frequency
+    let dungeon = Dungeon {
+        nft_id: nft_id.to_string(),
+        launcher_id,
+        rooms,
+        tunnels,
+        corridor_width,
+        level: dungeon_level,
+        dungeon_type,
+        biome: classify(&seed_id),
+        bounds: Bounds {
+            x_range: (min_x, max_x),
+            y_range: (min_y, max_y),
+        },
+        area_size,
+        char_frequency,
+        most_frequent_char,
+        excavated: all_excavated_coords,
+        grid: Grid::default(),
+        elevation: Grid::default(),
+        monsters: Vec::new(),
+        decorations: Vec::new(),
+        stairs_down: None,
+        floors: Vec::new(),
+        provenance: None,
+        balance: config.balance.clone(),
+        collection_salt: config.collection_salt.clone(),
+        epoch: config.epoch,
+    };
+    if maze {
+        return Ok(carve_maze(dungeon, seed_id, observer, depth));
+    }
+    Ok(Layout { dungeon, seed_id, rubble_area: Some(((min_x, max_x), (min_y, max_y))) })
+}
+
+/// Finishes the floor of `layout` with rubble, smoothing, terrain, elevation, and flooding,
+/// reporting the decoration stage to `observer` as the floor at `depth`
+pub(crate) fn finish_layout(layout: Layout, config: &GenerationConfig, observer: &mut dyn GenerationObserver, depth: usize) -> Dungeon {
+    let Layout { mut dungeon, seed_id, rubble_area } = layout;
+    let corridor_width = dungeon.corridor_width;
+
+    // Corridors and room centers keep every room reachable, so later passes never fill them
+    let protected: HashSet<(i32, i32)> = dungeon
+        .tunnels
         .iter()
-        .max_by_key(|&(_, &count)| count)
-        .map(|(&c, _)| c.to_string())
-        .unwrap_or("None".to_string());
-    
-    // Determine dungeon type
-    let dungeon_type = get_dungeon_type(&most_frequent_char);
+        .flatten()
+        .flat_map(|&pos| corridor_brush(pos, corridor_width))
+        .chain(dungeon.rooms.iter().map(|room| room.center))
+        .collect();
+
+    if let Some(area) = rubble_area {
+        // Decorate the dungeon with rubble, seeded from the NFT ID so the result is reproducible
+        observer.on_stage_started(depth, Stage::Decorate);
+        let mut rubble = decorate(
+            &dungeon.rooms,
+            &dungeon.tunnels,
+            corridor_width,
+            area,
+            (dungeon.area_size / config.area_per_random_point) as usize,
+            &dungeon.dungeon_type,
+            &mut rng_for_stream(&seed_id, "decorate"),
+        );
+        observer.on_decoration_added(depth, &mut rubble);
+        let mut seen = HashSet::new();
+        let excavated: Vec<(i32, i32)> = std::mem::take(&mut dungeon.excavated).into_iter().chain(rubble).filter(|&pos| seen.insert(pos)).collect();
+
+        // Erode natural dungeon types into organic caverns, keeping the corridors and room centers
+        dungeon.excavated = if config.smoothing.applies_to(&dungeon.dungeon_type) {
+            let mut smoothing_rng = rng_for_stream(&seed_id, "smoothing");
+            let smoothed = smooth_excavation(&excavated, &protected, config.smoothing_passes, &mut smoothing_rng);
+            let dug: HashSet<(i32, i32)> = smoothed.iter().copied().collect();
+            for room in &mut dungeon.rooms {
+                room.tiles.retain(|pos| dug.contains(pos));
+            }
+            smoothed
+        } else {
+            excavated
+        };
 
-    // Generate excavated room coordinates
-    let mut excavated_coordinates = Vec::new();
-    for i in 0..num_rooms {
-        let room_center = coordinates[i];
-        let room_offsets = get_room_offsets(sizes[i], shapes[i].clone());
-        let room_coords: Vec<(i32, i32)> = room_offsets
-            .iter()
-            .map(|&(ox, oy)| (room_center.0 + ox, room_center.1 + oy))
-            .collect();
+        // Build the tile grid covering every excavated coordinate
+        dungeon.grid = build_grid(&dungeon.rooms, &dungeon.tunnels, corridor_width, &dungeon.excavated);
+    }
 
-        // Skip adding if the room coordinates are empty
-        if !room_coords.is_empty() {
-            excavated_coordinates.push(room_coords);
-        }
+    // Carve rivers, lava channels, and sand drifts into the floor
+    if config.terrain {
+        carve_terrain(&mut dungeon.grid, &dungeon.dungeon_type, &protected, &mut rng_for_stream(&seed_id, "terrain"));
     }
 
-    let mut all_excavated_coords: Vec<(i32, i32)> = excavated_coordinates.iter().flatten().copied().collect();
-    let _min_x = all_excavated_coords.iter().map(|&(x, _)| x).min().unwrap_or(0);
-    let _max_x = all_excavated_coords.iter().map(|&(x, _)| x).max().unwrap_or(0);
-    let _min_y = all_excavated_coords.iter().map(|&(_, y)| y).min().unwrap_or(0);
-    let _max_y = all_excavated_coords.iter().map(|&(_, y)| y).max().unwrap_or(0);
+    // Raise hills and hollows over the finished grid
+    dungeon.elevation = raise_terrain(&dungeon.grid, &dungeon.dungeon_type, &mut rng_for_stream(&seed_id, "elevation"));
 
-    // Generate tunnels between room centers
-    let tunnels = generate_tunnels(&coordinates);
+    // Flood the hollows of wet dungeons
+    if config.terrain {
+        flood_lowlands(&mut dungeon.grid, &dungeon.elevation, &dungeon.dungeon_type, &protected, &mut rng_for_stream(&seed_id, "liquid"));
+    }
+    dungeon.balance = config.balance.clone();
+    dungeon.epoch = config.epoch;
 
-    // Flatten and append tunnels to excavated_coordinates
-    for tunnel in tunnels {
-        all_excavated_coords.extend(tunnel);
+    // Widen the bounds to the tunnels and rubble dug outside the room centers
+    if rubble_area.is_some() {
+        dungeon.recompute_bounds();
     }
+    dungeon
+}
 
-    // Add random points to the dungeon
-    let final_excavated_coords = add_random_excavated_points(all_excavated_coords, (min_x, max_x), (min_y, max_y), area_size as usize / 50);
+/// Builds the tile grid for the excavated map
+/// Excavated points start as floor, corridor tiles outside rooms become tunnels, corridor
+/// tiles where a tunnel enters a room become doors, and room centers are marked last.
+pub(crate) fn build_grid(rooms: &[Room], tunnels: &[Vec<(i32, i32)>], corridor_width: u32, excavated: &[(i32, i32)]) -> Grid<Tile> {
+    let min_x = excavated.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let max_x = excavated.iter().map(|&(x, _)| x).max().unwrap_or(0);
+    let min_y = excavated.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    let max_y = excavated.iter().map(|&(_, y)| y).max().unwrap_or(0);
+
+    let mut grid = Grid::new((min_x, max_x), (min_y, max_y), Tile::Wall);
+    for &pos in excavated {
+        grid.set(pos, Tile::Floor);
+    }
 
-    Ok((num_rooms, coordinates, sizes, shapes, (min_x, max_x), (min_y, max_y), area_size, char_frequency, most_frequent_char, dungeon_type, dungeon_level, final_excavated_coords,))
+    let room_tiles: HashSet<(i32, i32)> = rooms.iter().flat_map(|room| room.tiles.iter().copied()).collect();
+    for pos in tunnels.iter().flatten().flat_map(|&pos| corridor_brush(pos, corridor_width)) {
+        if !room_tiles.contains(&pos) {
+            grid.set(pos, Tile::Tunnel);
+        }
+    }
+    for pos in find_doors(tunnels, &room_tiles, corridor_width) {
+        grid.set(pos, Tile::Door);
+    }
+
+    for room in rooms {
+        grid.set(room.center, Tile::RoomCenter);
+    }
+
+    grid
 }
 
-// Helper function to map a character to a number
-fn char_to_num(c: char) -> i32 {
-    if c.is_digit(10) {
-        c.to_digit(10).unwrap() as i32
+// Helper function to map a character to its base62 value: 0-9, a-z, then A-Z
+pub(crate) fn char_to_num(c: char) -> i32 {
+    if let Some(digit) = c.to_digit(10) {
+        digit as i32
+    } else if c.is_ascii_uppercase() {
+        c as i32 - 'A' as i32 + 36
     } else {
         c.to_ascii_lowercase() as i32 - 'a' as i32 + 10
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    #[test]
+    fn tunnels_run_from_center_to_center() {
+        let tunnel = create_tunnel((0, 0), (3, -2));
+        assert_eq!(tunnel, vec![(0, 0), (1, 0), (2, 0), (3, 0), (3, -1), (3, -2)]);
+        assert_eq!(create_tunnel((5, 5), (5, 5)), vec![(5, 5)]);
+    }
+
+    #[test]
+    fn the_spanning_tree_digs_one_tunnel_per_extra_room() {
+        let centers = [(0, 0), (10, 0), (10, 10), (-20, 5)];
+        let tunnels = generate_tunnels(&centers, TunnelStyle::Manhattan, &mut rng_for_stream(NFT_ID, "tunnels"));
+        assert_eq!(tunnels.len(), centers.len() - 1);
+        // Each room joins its nearest neighbour already in the tree
+        assert_eq!(tunnels[0].first(), Some(&(0, 0)));
+        assert_eq!(tunnels[0].last(), Some(&(10, 0)));
+        assert!(generate_tunnels(&[], TunnelStyle::Winding, &mut rng_for_stream(NFT_ID, "tunnels")).is_empty());
+    }
+
+    #[test]
+    fn every_room_is_reachable() {
+        let dungeon = dungeon();
+        assert!(dungeon.is_fully_connected());
+        assert_eq!(dungeon.tunnels.len(), dungeon.rooms.len() - 1);
+    }
+
+    #[test]
+    fn room_kinds_follow_the_coordinate_characters() {
+        assert_eq!(get_room_kind(0, 5, '0', '0'), RoomKind::Entrance);
+        assert_eq!(get_room_kind(4, 5, '0', '0'), RoomKind::Exit);
+        assert_eq!(get_room_kind(1, 5, '0', 'a'), RoomKind::Boss);
+        assert_eq!(get_room_kind(1, 5, '0', '1'), RoomKind::Treasure);
+        assert_eq!(get_room_kind(1, 5, '1', '1'), RoomKind::Treasure);
+        assert_eq!(get_room_kind(1, 5, '1', '2'), RoomKind::Shrine);
+        assert_eq!(get_room_kind(1, 5, '2', '2'), RoomKind::Normal);
+    }
+
+    #[test]
+    fn dungeons_have_one_entrance_first_and_one_exit_last() {
+        let kinds: Vec<RoomKind> = dungeon().rooms.iter().map(|room| room.kind).collect();
+        assert_eq!(kinds.first(), Some(&RoomKind::Entrance));
+        assert_eq!(kinds.last(), Some(&RoomKind::Exit));
+        assert_eq!(kinds.iter().filter(|&&kind| kind == RoomKind::Entrance).count(), 1);
+        assert_eq!(kinds.iter().filter(|&&kind| kind == RoomKind::Exit).count(), 1);
+    }
+
+    #[test]
+    fn registered_shapes_are_excavated() {
+        let shape = dungeon().rooms[0].shape.chars().next().unwrap();
+        let mut shapes = ShapeRegistry::new();
+        shapes.register_fn(shape, |_| vec![(0, 0)]);
+        let custom = parse_nft_id_with_shapes(NFT_ID, &GenerationConfig::default(), &shapes).unwrap();
+        assert!(custom.rooms.iter().filter(|room| room.shape.starts_with(shape)).all(|room| room.tiles == [room.center]));
+        assert_eq!(parse_nft_id_with_shapes(NFT_ID, &GenerationConfig::default(), &ShapeRegistry::new()).unwrap(), dungeon());
+    }
+
+    #[test]
+    fn corridors_widen_every_four_levels() {
+        assert_eq!(corridor_width(0, MAX_CORRIDOR_WIDTH), 1);
+        assert_eq!(corridor_width(4, MAX_CORRIDOR_WIDTH), 1);
+        assert_eq!(corridor_width(5, MAX_CORRIDOR_WIDTH), 2);
+        assert_eq!(corridor_width(9, MAX_CORRIDOR_WIDTH), 3);
+        assert_eq!(corridor_width(u64::MAX, MAX_CORRIDOR_WIDTH), MAX_CORRIDOR_WIDTH);
+        assert_eq!(corridor_width(u64::MAX, 5), 5);
+        assert_eq!(corridor_brush((0, 0), 1).count(), 1);
+        assert_eq!(corridor_brush((0, 0), 2).collect::<Vec<_>>(), vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+        assert_eq!(corridor_brush((0, 0), 3).count(), 9);
+    }
+
+    #[test]
+    fn doors_sit_where_tunnels_enter_rooms() {
+        let room_tiles = HashSet::from([(2, 0), (3, 0), (2, 1), (3, 1)]);
+        let tunnels = vec![vec![(0, 0), (1, 0), (2, 0)]];
+        assert_eq!(find_doors(&tunnels, &room_tiles, 1), vec![(1, 0)]);
+        assert_eq!(find_doors(&tunnels, &room_tiles, 2), vec![(1, 0), (1, 1)]);
+
+        let dungeon = dungeon();
+        assert_eq!(dungeon.corridor_width, corridor_width(dungeon.level, MAX_CORRIDOR_WIDTH));
+        assert!(dungeon.grid.iter().any(|(_, &tile)| tile == Tile::Door));
+    }
+
+    #[test]
+    fn payload_reads_wrap_around_short_ids() {
+        let short: Vec<char> = "nft1zab".chars().collect();
+        assert_eq!([5, 6, 7, 8, 11].map(|index| char_at(&short, index)), ['a', 'b', 'a', 'b', 'a']);
+        assert_eq!(trailing_chars(&short, 3), ['b', 'a', 'b']);
+        let chars: Vec<char> = NFT_ID.chars().collect();
+        assert_eq!(trailing_chars(&chars, 4), chars[chars.len() - 4..]);
+        assert_eq!(char_at(&chars, chars.len()), chars[PAYLOAD_START]);
+    }
+
+    #[test]
+    fn ids_of_any_length_decode() {
+        // 'z' asks for 37 rooms, more than short IDs hold characters
+        let payload: String = NFT_ID.chars().skip(5).cycle().take(400).collect();
+        for len in [MIN_NFT_ID_LEN, 7, 12, 41, 62, 200] {
+            let nft_id = format!("nft1z{}", &payload[..len - 5]);
+            let dungeon = parse_nft_id(&nft_id).unwrap_or_else(|err| panic!("{} characters: {}", len, err));
+            assert_eq!(dungeon.rooms.len(), 37, "{} characters", len);
+            assert!(dungeon.is_fully_connected() && dungeon.is_solvable(), "{} characters", len);
+            assert_eq!(parse_nft_id(&nft_id).unwrap(), dungeon);
+        }
+        assert_eq!(parse_nft_id("nft1z").err(), Some(DungeonError::TooShort { len: 5, min: MIN_NFT_ID_LEN }));
+    }
+
+    #[test]
+    fn areas_and_levels_saturate_at_the_extremes() {
+        assert_eq!(room_area(0), 1);
+        assert_eq!(room_area(MAX_ROOM_SIZE), 2001 * 2001);
+        assert_eq!(room_area(u32::MAX), u64::MAX);
+        assert_eq!(get_dungeon_level(0, 1000), 1);
+        assert_eq!((get_dungeon_level(999, 1000), get_dungeon_level(1000, 1000)), (1, 2));
+        assert_eq!(get_dungeon_level(u64::MAX, 1), u64::MAX);
+        assert_eq!(get_dungeon_level(7, 0), 8);
+
+        let config = GenerationConfig::default();
+        assert_eq!(area_budget(&['0'; 5], &GenerationConfig { level_band: u64::MAX, ..config.clone() }), u64::MAX - 1);
+        assert_eq!(area_budget(&['0'; 5], &GenerationConfig { level_band: 0, ..config.clone() }), 0);
+        assert_eq!(fit_to_budget(&mut [u32::MAX, u32::MAX], 1000, 0), 529 + 441);
+    }
+
+    #[test]
+    fn the_lowest_and_highest_characters_decode_within_bounds() {
+        // '0' is the smallest base62 value and 'Z' the largest
+        let lowest: Vec<char> = "nft1".chars().chain(['0'; 60]).collect();
+        let highest: Vec<char> = "nft1".chars().chain(['Z'; 60]).collect();
+        let widest = GenerationConfig { min_rooms: 2, max_rooms: MAX_ROOMS, ..GenerationConfig::default() };
+        assert_eq!(decode_room_count('0', &widest), 2);
+        assert_eq!(decode_room_count('Z', &widest), 63);
+        let (sizes, area) = decode_room_sizes(&lowest, MAX_ROOMS, &GenerationConfig { size_base: i32::MIN, ..widest.clone() });
+        assert!(sizes.iter().all(|&size| size == 0));
+        assert_eq!(area, MAX_ROOMS as u64);
+
+        let huge = GenerationConfig { size_base: i32::MAX, size_multiplier: f64::MAX, ..widest };
+        let (sizes, area) = decode_room_sizes(&highest, MAX_ROOMS, &huge);
+        assert!(sizes.iter().all(|&size| size == MAX_ROOM_SIZE));
+        assert_eq!(area, MAX_ROOMS as u64 * room_area(MAX_ROOM_SIZE));
+        assert_eq!(get_dungeon_level(area, huge.level_band), 4_004_002);
+        let (_, budgeted) = decode_room_sizes(&highest, MAX_ROOMS, &GenerationConfig { room_sizing: RoomSizing::Budgeted, ..huge.clone() });
+        assert!(budgeted <= area_budget(&highest, &huge).max(MAX_ROOMS as u64));
+
+        for chars in [&lowest, &highest] {
+            let dungeon = parse_nft_id(&chars.iter().collect::<String>()).unwrap();
+            assert_eq!(dungeon.level, get_dungeon_level(dungeon.area_size, 1000));
+            assert!(dungeon.is_fully_connected());
+        }
+    }
+
+    #[test]
+    fn characters_map_to_their_base62_values() {
+        assert_eq!(char_to_num('0'), 0);
+        assert_eq!(char_to_num('9'), 9);
+        assert_eq!(char_to_num('a'), 10);
+        assert_eq!(char_to_num('z'), 35);
+        assert_eq!(char_to_num('A'), 36);
+        assert_eq!(char_to_num('Z'), 61);
+    }
+
+    #[test]
+    fn upper_case_letters_pick_variant_types() {
+        assert_eq!(get_dungeon_type("t"), "Temple");
+        assert_eq!(get_dungeon_type("T"), "Twilight Temple");
+        assert_eq!(get_dungeon_type("7"), "Unknown");
+        assert_eq!(base_dungeon_type("Twilight Temple"), "Temple");
+        assert_eq!(base_dungeon_type("Temple"), "Temple");
+        for (i, c) in ('A'..='Z').enumerate() {
+            assert_eq!(get_dungeon_type(&c.to_string()), DUNGEON_VARIANTS[i].0);
+            assert_eq!(get_dungeon_type(&c.to_ascii_lowercase().to_string()), DUNGEON_VARIANTS[i].1);
+        }
+    }
+}
@@ -0,0 +1,522 @@
+//! # Dungeon Exploration
+//!
+//! A simple terminal game loop for walking through a parsed dungeon.
+//!
+//! - The player starts at the first room center, shown as `P`.
+//! - Room centers are marked with their room-kind glyph (see `ascii::room_glyph`),
+//!   decoration objects with theirs (see `ascii::decoration_glyph`), and monsters with `M`.
+//! - `W`/`A`/`S`/`D` move up, left, down, and right; moves onto empty space and deep water
+//!   are blocked, and wading into shallow water takes two moves (see `liquid`).
+//! - Walking onto an item picks it up (see `inventory`): keys `k`, potions `%`, and gold `$`.
+//!   Locked doors (`#`) open with a key and stay open.
+//! - `P` drinks a potion.
+//! - Walking into a monster fights it to the death (see `combat`). A slain monster is gone;
+//!   a slain player respawns at the first room center with full hit points, dropping half
+//!   their gold.
+//! - `Q` or `Esc` quits.
+//! - Fog of war hides everything outside the player's sight radius that has not been
+//!   seen yet (see `fog`).
+//!
+//! `Explorer` holds the game state and is independent of the terminal, so other
+//! frontends can drive it; `run` wires it to a crossterm-based terminal UI. `Explorer::save`
+//! captures a run as a serializable `SavedRun`, and `Explorer::resume` continues it
+//! (`run_explorer` plays on from a resumed explorer; see `save` for saved games).
+//! `run` also records every action into a `Recording` that can be replayed (see `replay`).
+
+use std::io::{self, Write};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use serde::{Deserialize, Serialize};
+use super::ascii::{apply_fog, decoration_glyph, item_glyph, put_glyph, render_window, room_glyph, MONSTER_GLYPH};
+use super::combat::{fight, CombatOutcome, CombatReport};
+use super::decorate::Decoration;
+use super::dungeon::{Dungeon, RoomKind};
+use super::fog::FogOfWar;
+use super::grid::{Grid, Tile};
+use super::inventory::{Item, Lock, Loot, Player};
+use super::replay::Recording;
+use super::spawn::MonsterSpawn;
+
+/// Glyph for the player
+pub const PLAYER_GLYPH: char = 'P';
+/// Glyph for a locked door
+pub const LOCKED_DOOR_GLYPH: char = '#';
+/// Default sight radius, in tiles
+pub const DEFAULT_SIGHT_RADIUS: u32 = 8;
+/// Combat log lines shown below the status bar
+pub const COMBAT_LOG_LINES: usize = 3;
+
+/// A movement direction, with up meaning increasing y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Coordinate delta for one step in this direction.
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
+
+/// What happened when the player tried to step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MoveOutcome {
+    /// The target tile is not excavated.
+    Blocked,
+    /// The target is a locked door and the player has no key.
+    Locked,
+    /// A key opened the locked door and the player stepped through.
+    Unlocked,
+    /// The player stepped onto the tile and picked up an item.
+    PickedUp(Item),
+    /// The player stepped onto the tile.
+    Moved,
+    /// The player waded into shallow water, taking longer than a step.
+    Waded,
+    /// The player fought the monster on the tile and stayed put (see `Explorer::combat_log`).
+    Fought(CombatOutcome),
+}
+
+/// A run in progress, serializable so it can be saved and resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedRun {
+    /// Player position.
+    pub position: (i32, i32),
+    /// Number of moves so far; wading through shallow water counts extra.
+    pub moves: usize,
+    /// Hit points and inventory.
+    pub player: Player,
+    /// Items not picked up yet.
+    pub loot: Vec<Loot>,
+    /// Rooms still locked.
+    pub locks: Vec<Lock>,
+    /// Monsters not slain yet.
+    pub monsters: Vec<MonsterSpawn>,
+    /// Number of times the player was slain.
+    pub deaths: usize,
+    /// Tiles seen so far, restored as seen when the run resumes.
+    #[serde(default)]
+    pub explored: Vec<(i32, i32)>,
+}
+
+/// Exploration state: walkable tiles, the player, and what is left to pick up.
+#[derive(Debug, Clone)]
+pub struct Explorer {
+    grid: Grid<Tile>,
+    rooms: Vec<((i32, i32), RoomKind)>,
+    decorations: Vec<Decoration>,
+    monsters: Vec<MonsterSpawn>,
+    fog: FogOfWar,
+    level: u64,
+    start: (i32, i32),
+    player: (i32, i32),
+    moves: usize,
+    state: Player,
+    loot: Vec<Loot>,
+    locks: Vec<Lock>,
+    deaths: usize,
+    combat_log: Vec<String>,
+}
+
+impl Explorer {
+    /// Starts exploring `dungeon` from its first room center.
+    pub fn new(dungeon: &Dungeon) -> Explorer {
+        Explorer::with_sight_radius(dungeon, DEFAULT_SIGHT_RADIUS)
+    }
+
+    /// Starts exploring `dungeon` with a custom sight radius.
+    pub fn with_sight_radius(dungeon: &Dungeon, sight_radius: u32) -> Explorer {
+        let player = dungeon.rooms.first().map(|room| room.center).unwrap_or((0, 0));
+        let rooms = dungeon.rooms.iter().map(|room| (room.center, room.kind)).collect();
+        let monsters = dungeon.monsters.clone();
+        let mut fog = FogOfWar::new(&dungeon.grid, sight_radius);
+        fog.update(&dungeon.grid, player);
+        let locks = dungeon.locks();
+        let loot = dungeon.loot();
+        Explorer {
+            grid: dungeon.grid.clone(),
+            rooms,
+            decorations: dungeon.decorations.clone(),
+            monsters,
+            fog,
+            level: dungeon.level,
+            start: player,
+            player,
+            moves: 0,
+            state: Player::default(),
+            loot,
+            locks,
+            deaths: 0,
+            combat_log: Vec::new(),
+        }
+    }
+
+    /// Continues a saved run through `dungeon`.
+    pub fn resume(dungeon: &Dungeon, sight_radius: u32, saved: &SavedRun) -> Explorer {
+        let mut explorer = Explorer::with_sight_radius(dungeon, sight_radius);
+        explorer.player = saved.position;
+        explorer.moves = saved.moves;
+        explorer.state = saved.player;
+        explorer.loot = saved.loot.clone();
+        explorer.locks = saved.locks.clone();
+        explorer.monsters = saved.monsters.clone();
+        explorer.deaths = saved.deaths;
+        explorer.fog.reveal(&saved.explored);
+        explorer.fog.update(&explorer.grid, explorer.player);
+        explorer
+    }
+
+    /// Captures the run so it can be saved and resumed later.
+    pub fn save(&self) -> SavedRun {
+        SavedRun {
+            position: self.player,
+            moves: self.moves,
+            player: self.state,
+            loot: self.loot.clone(),
+            locks: self.locks.clone(),
+            monsters: self.monsters.clone(),
+            deaths: self.deaths,
+            explored: self.fog.explored(),
+        }
+    }
+
+    /// Fog of war from the player's point of view.
+    pub fn fog(&self) -> &FogOfWar {
+        &self.fog
+    }
+
+    /// Current player position.
+    pub fn player(&self) -> (i32, i32) {
+        self.player
+    }
+
+    /// Number of moves so far; wading through shallow water counts extra.
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Hit points and inventory of the player.
+    pub fn player_state(&self) -> &Player {
+        &self.state
+    }
+
+    /// Items not picked up yet.
+    pub fn loot(&self) -> &[Loot] {
+        &self.loot
+    }
+
+    /// Monsters not slain yet.
+    pub fn monsters(&self) -> &[MonsterSpawn] {
+        &self.monsters
+    }
+
+    /// Number of times the player was slain.
+    pub fn deaths(&self) -> usize {
+        self.deaths
+    }
+
+    /// Log of the most recent fight.
+    pub fn combat_log(&self) -> &[String] {
+        &self.combat_log
+    }
+
+    /// Whether `pos` is a door that is still locked.
+    pub fn is_locked(&self, pos: (i32, i32)) -> bool {
+        self.locks.iter().any(|lock| lock.doors.contains(&pos))
+    }
+
+    /// Drinks a potion, returning false if the player has none.
+    pub fn drink_potion(&mut self) -> bool {
+        self.state.drink_potion()
+    }
+
+    /// Whether the tile at `pos` can be walked on.
+    pub fn is_walkable(&self, pos: (i32, i32)) -> bool {
+        self.grid.is_walkable(pos)
+    }
+
+    /// Moves the player one step, returning false if the target tile is not excavated,
+    /// is a door the player cannot unlock, or holds a monster to fight.
+    pub fn try_move(&mut self, direction: Direction) -> bool {
+        !matches!(self.step(direction), MoveOutcome::Blocked | MoveOutcome::Locked | MoveOutcome::Fought(_))
+    }
+
+    /// Moves the player one step, fighting monsters, unlocking doors, and picking up
+    /// items on the way.
+    pub fn step(&mut self, direction: Direction) -> MoveOutcome {
+        let (dx, dy) = direction.delta();
+        let target = (self.player.0 + dx, self.player.1 + dy);
+        if !self.is_walkable(target) {
+            return MoveOutcome::Blocked;
+        }
+        if let Some(index) = self.monsters.iter().position(|monster| monster.position == target) {
+            let CombatReport { outcome, log } = fight(&mut self.state, self.level, &self.monsters[index]);
+            self.combat_log = log;
+            match outcome {
+                CombatOutcome::Victory => {
+                    self.monsters.remove(index);
+                }
+                CombatOutcome::Defeat => self.respawn(),
+            }
+            return MoveOutcome::Fought(outcome);
+        }
+
+        // A key opens every door of the locked room
+        let mut outcome = MoveOutcome::Moved;
+        if let Some(index) = self.locks.iter().position(|lock| lock.doors.contains(&target)) {
+            if !self.state.use_key() {
+                return MoveOutcome::Locked;
+            }
+            self.locks.remove(index);
+            outcome = MoveOutcome::Unlocked;
+        }
+
+        self.player = target;
+        let cost = self.grid.get(target).map_or(1, |tile| tile.movement_cost());
+        self.moves += cost;
+        if cost > 1 && outcome == MoveOutcome::Moved {
+            outcome = MoveOutcome::Waded;
+        }
+        self.fog.update(&self.grid, self.player);
+
+        if let Some(index) = self.loot.iter().position(|loot| loot.position == target) {
+            let item = self.loot.remove(index).item;
+            self.state.inventory.add(item);
+            outcome = MoveOutcome::PickedUp(item);
+        }
+        outcome
+    }
+
+    // Bring a slain player back at the start with full hit points and half their gold
+    fn respawn(&mut self) {
+        self.deaths += 1;
+        self.state.hp = self.state.max_hp;
+        self.state.inventory.gold /= 2;
+        self.player = self.start;
+        self.fog.update(&self.grid, self.player);
+        self.combat_log.push("You awaken at the entrance, lighter of purse.".to_string());
+    }
+
+    /// Renders a `width` x `height` window centered on the player.
+    pub fn render_viewport(&self, width: u16, height: u16) -> Vec<String> {
+        let (width, height) = (width.max(1) as i32, height.max(1) as i32);
+        let x_range = (self.player.0 - width / 2, self.player.0 - width / 2 + width - 1);
+        let y_range = (self.player.1 - height / 2, self.player.1 - height / 2 + height - 1);
+
+        let mut rows = render_window(&self.grid, x_range, y_range);
+        for &(center, kind) in &self.rooms {
+            put_glyph(&mut rows, x_range, y_range, center, room_glyph(kind));
+        }
+        for decoration in &self.decorations {
+            put_glyph(&mut rows, x_range, y_range, decoration.position, decoration_glyph(decoration.kind));
+        }
+        for pos in self.locks.iter().flat_map(|lock| lock.doors.iter()) {
+            put_glyph(&mut rows, x_range, y_range, *pos, LOCKED_DOOR_GLYPH);
+        }
+        for loot in &self.loot {
+            put_glyph(&mut rows, x_range, y_range, loot.position, item_glyph(loot.item));
+        }
+        for monster in &self.monsters {
+            put_glyph(&mut rows, x_range, y_range, monster.position, MONSTER_GLYPH);
+        }
+        apply_fog(&mut rows, x_range, y_range, &self.fog);
+        put_glyph(&mut rows, x_range, y_range, self.player, PLAYER_GLYPH);
+
+        rows
+    }
+}
+
+/// Run the interactive exploration loop in the current terminal, returning the
+/// recording of the run
+pub fn run(dungeon: &Dungeon, sight_radius: u32) -> io::Result<Recording> {
+    run_explorer(dungeon, &mut Explorer::with_sight_radius(dungeon, sight_radius))
+}
+
+/// Run the interactive exploration loop from the state of `explorer`, such as a resumed
+/// run, leaving it where the player quit
+/// The recording holds the actions of this session only, so it replays only when the
+/// explorer started fresh.
+pub fn run_explorer(dungeon: &Dungeon, explorer: &mut Explorer) -> io::Result<Recording> {
+    with_terminal(|stdout| game_loop(dungeon, explorer, stdout))
+}
+
+// Run `body` in raw mode on the alternate screen, restoring the terminal afterwards
+pub(crate) fn with_terminal<T>(body: impl FnOnce(&mut io::Stdout) -> io::Result<T>) -> io::Result<T> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+
+    let result = body(&mut stdout);
+
+    // Always restore the terminal, even if the loop failed
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+// Draw the view around the player, the status bar, and the `status` line, followed by the
+// end of the combat log when `show_combat_log` is set
+pub(crate) fn draw_screen(stdout: &mut io::Stdout, dungeon: &Dungeon, explorer: &Explorer, status: &str, show_combat_log: bool) -> io::Result<()> {
+    // Reserve two lines at the bottom for the status bar, and more for the combat log
+    let (width, height) = terminal::size()?;
+    let rows = explorer.render_viewport(width, height.saturating_sub(2 + COMBAT_LOG_LINES as u16));
+
+    queue!(stdout, Clear(ClearType::All))?;
+    for (i, row) in rows.iter().enumerate() {
+        queue!(stdout, MoveTo(0, i as u16), Print(row))?;
+    }
+    let (x, y) = explorer.player();
+    let state = explorer.player_state();
+    queue!(
+        stdout,
+        MoveTo(0, rows.len() as u16),
+        Print(format!(
+            "{} | Level {} | ({}, {}) | Moves: {} | HP {}/{} | Keys: {} | Potions: {} | Gold: {}",
+            dungeon.dungeon_type,
+            dungeon.level,
+            x,
+            y,
+            explorer.moves(),
+            state.hp,
+            state.max_hp,
+            state.inventory.keys,
+            state.inventory.potions,
+            state.inventory.gold,
+        )),
+        MoveTo(0, rows.len() as u16 + 1),
+        Print(status),
+    )?;
+    if show_combat_log {
+        let log = explorer.combat_log();
+        for (i, line) in log[log.len().saturating_sub(COMBAT_LOG_LINES)..].iter().enumerate() {
+            queue!(stdout, MoveTo(0, rows.len() as u16 + 2 + i as u16), Print(line))?;
+        }
+    }
+    stdout.flush()
+}
+
+// Status line describing a step's `outcome`; `None` for a plain move
+pub(crate) fn outcome_message(outcome: MoveOutcome, deaths: usize) -> Option<String> {
+    Some(match outcome {
+        MoveOutcome::Blocked => String::from("The way is blocked."),
+        MoveOutcome::Locked => String::from("The door is locked. Find a key."),
+        MoveOutcome::Unlocked => String::from("You unlock the door."),
+        MoveOutcome::PickedUp(Item::Key) => String::from("You pick up a key."),
+        MoveOutcome::PickedUp(Item::Potion) => String::from("You pick up a potion."),
+        MoveOutcome::PickedUp(Item::Gold(amount)) => format!("You pick up {} gold.", amount),
+        MoveOutcome::Moved => return None,
+        MoveOutcome::Waded => String::from("You wade through shallow water."),
+        MoveOutcome::Fought(CombatOutcome::Victory) => String::from("Victory!"),
+        MoveOutcome::Fought(CombatOutcome::Defeat) => format!("You were slain. Deaths: {}", deaths),
+    })
+}
+
+fn game_loop(dungeon: &Dungeon, explorer: &mut Explorer, stdout: &mut io::Stdout) -> io::Result<Recording> {
+    let mut recording = Recording::new(&dungeon.nft_id, explorer.fog().sight_radius());
+    let help = "WASD to move, P to drink a potion, Q to quit";
+    let mut status = String::from(help);
+    let mut show_combat_log = false;
+
+    loop {
+        draw_screen(stdout, dungeon, explorer, &status, show_combat_log)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let direction = match key.code {
+            KeyCode::Char('w') | KeyCode::Char('W') => Direction::Up,
+            KeyCode::Char('a') | KeyCode::Char('A') => Direction::Left,
+            KeyCode::Char('s') | KeyCode::Char('S') => Direction::Down,
+            KeyCode::Char('d') | KeyCode::Char('D') => Direction::Right,
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                show_combat_log = false;
+                status = if explorer.drink_potion() {
+                    recording.record_potion();
+                    String::from("You drink a potion and feel refreshed.")
+                } else {
+                    String::from("You have no potions.")
+                };
+                continue;
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Ok(recording),
+            _ => continue,
+        };
+        let outcome = explorer.step(direction);
+        recording.record_step(direction, outcome);
+        show_combat_log = matches!(outcome, MoveOutcome::Fought(_));
+        status = outcome_message(outcome, explorer.deaths()).unwrap_or_else(|| String::from(help));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn the_player_starts_in_the_first_room() {
+        let dungeon = dungeon();
+        let explorer = Explorer::new(&dungeon);
+        assert_eq!(explorer.player(), dungeon.rooms[0].center);
+        assert_eq!(explorer.moves(), 0);
+    }
+
+    #[test]
+    fn walls_block_the_player_in_place() {
+        let dungeon = dungeon();
+        let mut explorer = Explorer::new(&dungeon);
+        // Walk right until the wall of the room stops the player
+        while explorer.try_move(Direction::Right) {}
+        let (player, moves) = (explorer.player(), explorer.moves());
+        assert!(moves > 0);
+        assert!(!explorer.try_move(Direction::Right));
+        assert_eq!((explorer.player(), explorer.moves()), (player, moves));
+        assert!(!explorer.is_walkable((player.0 + 1, player.1)));
+    }
+
+    #[test]
+    fn shallow_water_slows_the_player_and_deep_water_blocks() {
+        let mut dungeon = dungeon();
+        let center = dungeon.rooms[0].center;
+        dungeon.grid.set((center.0 + 1, center.1), Tile::ShallowWater);
+        dungeon.grid.set((center.0 - 1, center.1), Tile::Water);
+        let mut explorer = Explorer::new(&dungeon);
+        assert_eq!(explorer.step(Direction::Left), MoveOutcome::Blocked);
+        assert_eq!(explorer.step(Direction::Right), MoveOutcome::Waded);
+        assert_eq!((explorer.player(), explorer.moves()), ((center.0 + 1, center.1), 2));
+        assert_eq!(outcome_message(MoveOutcome::Waded, 0).as_deref(), Some("You wade through shallow water."));
+    }
+
+    #[test]
+    fn the_viewport_is_centered_on_the_player() {
+        let explorer = Explorer::new(&dungeon());
+        let rows = explorer.render_viewport(21, 11);
+        assert_eq!(rows.len(), 11);
+        assert!(rows.iter().all(|row| row.chars().count() == 21));
+        assert_eq!(rows[5].chars().nth(10), Some(PLAYER_GLYPH));
+    }
+
+    #[test]
+    fn directions_step_one_tile_with_up_increasing_y() {
+        assert_eq!(Direction::Up.delta(), (0, 1));
+        assert_eq!(Direction::Down.delta(), (0, -1));
+        assert_eq!(Direction::Left.delta(), (-1, 0));
+        assert_eq!(Direction::Right.delta(), (1, 0));
+    }
+}
@@ -0,0 +1,176 @@
+//! # Graph Export
+//!
+//! Exports the topology of a dungeon as a room connectivity graph, for network analysis
+//! in tools such as Gephi or abstract maps drawn with Graphviz.
+//!
+//! - **Nodes**: One per room, with its name, kind, size, and center coordinates. In DOT
+//!   the center is also the pinned `pos` attribute, so `neato -n` lays the graph out like
+//!   the map.
+//! - **Edges**: One per tunnel joining two room centers, undirected, with the tunnel's
+//!   length in steps.
+//!
+//! `GraphFormat::Dot` writes a Graphviz `.dot` file and `GraphFormat::GraphMl` a
+//! `.graphml` file with typed attribute keys.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::utils::dungeon::Dungeon;
+use super::tiled::{escape_xml, room_type};
+
+/// File format of an exported graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphFormat {
+    /// Graphviz `.dot` graph.
+    #[default]
+    Dot,
+    /// XML `.graphml` graph.
+    GraphMl,
+}
+
+impl GraphFormat {
+    /// Infers the format from a file extension, defaulting to DOT.
+    pub fn from_path(path: &Path) -> GraphFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("graphml") => GraphFormat::GraphMl,
+            _ => GraphFormat::Dot,
+        }
+    }
+}
+
+/// A tunnel between two rooms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphEdge {
+    /// Index in `Dungeon::rooms` of the room the tunnel starts from.
+    pub from: usize,
+    /// Index in `Dungeon::rooms` of the room the tunnel leads to.
+    pub to: usize,
+    /// Length of the tunnel in steps.
+    pub length: usize,
+}
+
+/// Tunnels of the dungeon as edges between the rooms centered on their two ends
+pub fn room_graph(dungeon: &Dungeon) -> Vec<GraphEdge> {
+    let room_at = |pos: Option<&(i32, i32)>| dungeon.rooms.iter().position(|room| Some(&room.center) == pos);
+    dungeon
+        .tunnels
+        .iter()
+        .filter_map(|tunnel| {
+            let (from, to) = (room_at(tunnel.first())?, room_at(tunnel.last())?);
+            Some(GraphEdge { from, to, length: tunnel.len() - 1 })
+        })
+        .collect()
+}
+
+/// Write the room graph of the dungeon in the given `format` to `out_path`
+pub fn export_graph(dungeon: &Dungeon, out_path: &Path, format: GraphFormat) -> io::Result<()> {
+    let contents = match format {
+        GraphFormat::Dot => to_dot(dungeon),
+        GraphFormat::GraphMl => to_graphml(dungeon),
+    };
+    fs::write(out_path, contents)
+}
+
+/// Render the room graph as a Graphviz DOT graph
+pub fn to_dot(dungeon: &Dungeon) -> String {
+    let mut dot = format!("graph \"{}\" {{\n", escape_dot(&dungeon.nft_id));
+    dot.push_str("  node [shape=ellipse];\n");
+
+    // `write!` to a String cannot fail
+    for (index, room) in dungeon.rooms.iter().enumerate() {
+        let _ = writeln!(
+            dot,
+            "  r{} [label=\"{}\", kind=\"{}\", size={}, x={}, y={}, pos=\"{},{}!\"];",
+            index,
+            escape_dot(&room.name),
+            room_type(room.kind),
+            room.size,
+            room.center.0,
+            room.center.1,
+            room.center.0,
+            room.center.1
+        );
+    }
+    for edge in room_graph(dungeon) {
+        let _ = writeln!(dot, "  r{} -- r{} [length={}, label=\"{}\"];", edge.from, edge.to, edge.length, edge.length);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Render the room graph as a GraphML document
+pub fn to_graphml(dungeon: &Dungeon) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (id, target, name, kind) in [
+        ("label", "node", "label", "string"),
+        ("kind", "node", "kind", "string"),
+        ("size", "node", "size", "int"),
+        ("x", "node", "x", "int"),
+        ("y", "node", "y", "int"),
+        ("length", "edge", "length", "int"),
+    ] {
+        let _ = writeln!(xml, " <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>", id, target, name, kind);
+    }
+    let _ = writeln!(xml, " <graph id=\"{}\" edgedefault=\"undirected\">", escape_xml(&dungeon.nft_id));
+
+    for (index, room) in dungeon.rooms.iter().enumerate() {
+        let _ = writeln!(xml, "  <node id=\"r{}\">", index);
+        for (key, value) in [
+            ("label", escape_xml(&room.name)),
+            ("kind", room_type(room.kind).to_string()),
+            ("size", room.size.to_string()),
+            ("x", room.center.0.to_string()),
+            ("y", room.center.1.to_string()),
+        ] {
+            let _ = writeln!(xml, "   <data key=\"{}\">{}</data>", key, value);
+        }
+        xml.push_str("  </node>\n");
+    }
+    for (index, edge) in room_graph(dungeon).iter().enumerate() {
+        let _ = writeln!(xml, "  <edge id=\"e{}\" source=\"r{}\" target=\"r{}\">", index, edge.from, edge.to);
+        let _ = writeln!(xml, "   <data key=\"length\">{}</data>", edge.length);
+        xml.push_str("  </edge>\n");
+    }
+    xml.push_str(" </graph>\n</graphml>\n");
+    xml
+}
+
+// Escape a value for use inside a double-quoted DOT string
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn formats_follow_the_file_extension() {
+        assert_eq!(GraphFormat::from_path(Path::new("rooms.GraphML")), GraphFormat::GraphMl);
+        assert_eq!(GraphFormat::from_path(Path::new("rooms.dot")), GraphFormat::Dot);
+        assert_eq!(GraphFormat::from_path(Path::new("rooms")), GraphFormat::Dot);
+    }
+
+    #[test]
+    fn every_tunnel_becomes_an_edge_between_rooms() {
+        let dungeon = dungeon();
+        let edges = room_graph(&dungeon);
+        // The tunnels form a spanning tree over the rooms
+        assert_eq!(edges.len(), dungeon.rooms.len() - 1);
+        for edge in &edges {
+            let (from, to) = (dungeon.rooms[edge.from].center, dungeon.rooms[edge.to].center);
+            assert_eq!(edge.length, ((from.0 - to.0).abs() + (from.1 - to.1).abs()) as usize);
+        }
+
+        let dot = to_dot(&dungeon);
+        assert!(dot.starts_with("graph ") && dot.trim_end().ends_with('}'));
+        assert_eq!(dot.matches(" -- ").count(), edges.len());
+        let graphml = to_graphml(&dungeon);
+        assert_eq!(graphml.matches("<node ").count(), dungeon.rooms.len());
+        assert_eq!(graphml.matches("<edge ").count(), edges.len());
+        assert!(graphml.trim_end().ends_with("</graphml>"));
+    }
+}
@@ -0,0 +1,99 @@
+//! # NFT Metadata Export
+//!
+//! Exports a dungeon's traits as Chia NFT off-chain metadata in the CHIP-0007 format
+//! (https://github.com/Chia-Network/chips/blob/main/CHIPs/chip-0007.md), so minters can
+//! attach the generated traits directly to their NFT metadata files.
+//!
+//! The document names the dungeon after its type and level, describes it with its lore
+//! (see `lore`), and carries one attribute per trait:
+//! - **Type**: The dungeon type (e.g., "Cave", "Hellforge").
+//! - **Biome**: The dungeon type with its sub-biome modifier (e.g., "Frozen Necropolis").
+//! - **Level**: The dungeon level, with a `min_value` of 1.
+//! - **Rooms**: The room count, bounded by the default `GenerationConfig` room range.
+//! - **Area**: The total room area.
+//! - **Rarity**: The rarity tier with the default weights (see `rarity`).
+//!
+//! Numeric traits are written as JSON numbers so marketplaces can filter on ranges.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde_json::{json, Value};
+use crate::utils::config::GenerationConfig;
+use crate::utils::dungeon::Dungeon;
+use crate::utils::rarity::RarityWeights;
+
+/// Metadata format written to exported documents
+pub const METADATA_FORMAT: &str = "CHIP-0007";
+/// Minting tool named in exported documents
+pub const MINTING_TOOL: &str = "chia_dungeon";
+
+/// Build the CHIP-0007 metadata document of the dungeon
+pub fn to_chip0007(dungeon: &Dungeon) -> Value {
+    let config = GenerationConfig::default();
+    let rarity = dungeon.rarity(&RarityWeights::default());
+    json!({
+        "format": METADATA_FORMAT,
+        "name": format!("{} Dungeon (Level {})", dungeon.dungeon_type, dungeon.level),
+        "description": format!("{} This dungeon was excavated from {}.", dungeon.lore(), dungeon.nft_id),
+        "minting_tool": MINTING_TOOL,
+        "sensitive_content": false,
+        "attributes": [
+            { "trait_type": "Type", "value": dungeon.dungeon_type },
+            { "trait_type": "Biome", "value": dungeon.biome.to_string() },
+            { "trait_type": "Level", "value": dungeon.level, "min_value": 1 },
+            { "trait_type": "Rooms", "value": dungeon.rooms.len(), "min_value": config.min_rooms, "max_value": config.max_rooms },
+            { "trait_type": "Area", "value": dungeon.area_size },
+            { "trait_type": "Rarity", "value": rarity.tier.to_string() },
+        ],
+    })
+}
+
+/// Render the dungeon's CHIP-0007 metadata as pretty-printed JSON
+pub fn to_metadata_json(dungeon: &Dungeon) -> String {
+    format!("{:#}", to_chip0007(dungeon))
+}
+
+/// Write the dungeon's CHIP-0007 metadata to `out_path`
+pub fn export_metadata(dungeon: &Dungeon, out_path: &Path) -> io::Result<()> {
+    fs::write(out_path, to_metadata_json(dungeon))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use crate::utils::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    #[test]
+    fn documents_carry_one_attribute_per_trait() {
+        let dungeon = dungeon();
+        let metadata = to_chip0007(&dungeon);
+        assert_eq!(metadata["format"], METADATA_FORMAT);
+        assert_eq!(metadata["minting_tool"], MINTING_TOOL);
+        assert_eq!(metadata["name"], format!("{} Dungeon (Level {})", dungeon.dungeon_type, dungeon.level));
+        assert!(metadata["description"].as_str().unwrap().starts_with(&dungeon.lore()));
+        assert!(metadata["description"].as_str().unwrap().ends_with(&format!("excavated from {}.", NFT_ID)));
+
+        let attributes = metadata["attributes"].as_array().unwrap();
+        let traits: Vec<&str> = attributes.iter().map(|attribute| attribute["trait_type"].as_str().unwrap()).collect();
+        assert_eq!(traits, ["Type", "Biome", "Level", "Rooms", "Area", "Rarity"]);
+        assert_eq!(attributes[1]["value"], dungeon.biome.to_string());
+        // Numeric traits stay numbers so marketplaces can filter on ranges
+        assert_eq!(attributes[2]["value"], dungeon.level);
+        assert_eq!(attributes[3]["value"], dungeon.rooms.len());
+        assert_eq!(attributes[3]["max_value"], GenerationConfig::default().max_rooms);
+        assert_eq!(attributes[4]["value"], dungeon.area_size);
+    }
+
+    #[test]
+    fn exported_files_hold_the_pretty_printed_document() {
+        let dungeon = dungeon();
+        let path = env::temp_dir().join(format!("chia_dungeon_metadata_{}.json", std::process::id()));
+        export_metadata(&dungeon, &path).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, to_metadata_json(&dungeon));
+        assert_eq!(serde_json::from_str::<Value>(&written).unwrap(), to_chip0007(&dungeon));
+    }
+}
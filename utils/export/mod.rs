@@ -0,0 +1,14 @@
+//! # Dungeon Export
+//!
+//! Writes excavated dungeons to formats understood by external tools.
+//!
+//! ## Modules:
+//! - `graph`: Room connectivity graphs as Graphviz DOT or GraphML.
+//! - `metadata`: CHIP-0007 NFT metadata with the dungeon's traits.
+//! - `rexpaint`: RexPaint `.xp` images with terrain and objects layers.
+//! - `tiled`: Tiled map editor `.tmx` and `.json` maps.
+
+pub mod graph;
+pub mod metadata;
+pub mod rexpaint;
+pub mod tiled;
@@ -0,0 +1,191 @@
+//! # RexPaint Export
+//!
+//! Exports a dungeon as a RexPaint (https://www.gridsagegames.com/rexpaint) `.xp` image,
+//! which most roguelike libraries load directly, so NFT dungeons can be touched up in
+//! RexPaint and imported into a game.
+//!
+//! An `.xp` file is gzip-compressed: the format version and layer count, then for each
+//! layer its width, height, and cells in column-major order, each a CP437 glyph code and
+//! the foreground and background RGB colors. Integers are 32-bit little-endian.
+//!
+//! Two layers are written:
+//! - **terrain**: Every tile in the glyphs and colors of the dungeon type's `Theme`: rock,
+//!   floor, doors, stairs, and the water, lava, and sand terrain features.
+//! - **objects**: Room centers, loot, traps, and monsters in their ASCII glyphs (see
+//!   `ascii`) over the theme background. Every other cell is transparent.
+//!
+//! Rows run from the highest y down, matching the plotted and ASCII maps.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use crate::utils::ascii::{
+    item_glyph, room_glyph, DOOR_GLYPH, LAVA_GLYPH, MONSTER_GLYPH, SAND_GLYPH, SHALLOW_WATER_GLYPH, STAIRS_DOWN_GLYPH,
+    STAIRS_UP_GLYPH, TRAP_GLYPH, WATER_GLYPH,
+};
+use crate::utils::dungeon::Dungeon;
+use crate::utils::grid::Tile;
+use crate::utils::plot::room_kind_color;
+use crate::utils::terrain::{LAVA_COLOR, SAND_COLOR, SHALLOW_WATER_COLOR, WATER_COLOR};
+use crate::utils::theme::{Rgb, Theme};
+
+/// Format version written to `.xp` files
+pub const XP_VERSION: i32 = -1;
+/// Background color RexPaint draws as transparent
+pub const TRANSPARENT: Rgb = (255, 0, 255);
+
+/// One cell of a RexPaint layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XpCell {
+    /// CP437 code of the glyph.
+    pub glyph: u32,
+    /// Glyph color.
+    pub fg: Rgb,
+    /// Background color; `TRANSPARENT` shows the layers below.
+    pub bg: Rgb,
+}
+
+impl XpCell {
+    /// A transparent cell without a glyph.
+    pub const EMPTY: XpCell = XpCell { glyph: 0, fg: (0, 0, 0), bg: TRANSPARENT };
+}
+
+/// A layered RexPaint image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XpImage {
+    /// Width in cells.
+    pub width: usize,
+    /// Height in cells.
+    pub height: usize,
+    /// Cells of each layer, bottom layer first, row-major from the top row.
+    pub layers: Vec<Vec<XpCell>>,
+}
+
+impl XpImage {
+    /// Draw the dungeon's terrain and objects layers (see `rexpaint`).
+    pub fn from_dungeon(dungeon: &Dungeon) -> XpImage {
+        let grid = &dungeon.grid;
+        let (x_range, y_range) = (grid.x_range(), grid.y_range());
+        let (width, height) = (grid.width(), grid.height());
+        let theme = Theme::for_dungeon(dungeon);
+        let cell = |glyph: char, fg: Rgb| XpCell { glyph: cp437(glyph), fg, bg: theme.background };
+
+        let mut terrain = vec![cell(theme.wall_glyph, theme.wall); width * height];
+        for (pos, &tile) in grid.iter() {
+            let index = (y_range.1 - pos.1) as usize * width + (pos.0 - x_range.0) as usize;
+            terrain[index] = match tile {
+                Tile::Wall => cell(theme.wall_glyph, theme.wall),
+                Tile::Door => cell(DOOR_GLYPH, theme.floor),
+                Tile::StairsUp => cell(STAIRS_UP_GLYPH, theme.floor),
+                Tile::StairsDown => cell(STAIRS_DOWN_GLYPH, theme.floor),
+                Tile::Water => cell(WATER_GLYPH, WATER_COLOR),
+                Tile::Lava => cell(LAVA_GLYPH, LAVA_COLOR),
+                Tile::Sand => cell(SAND_GLYPH, SAND_COLOR),
+                Tile::ShallowWater => cell(SHALLOW_WATER_GLYPH, SHALLOW_WATER_COLOR),
+                _ => cell(theme.floor_glyph, theme.floor),
+            };
+        }
+
+        // Later objects cover earlier ones on the same tile
+        let mut objects = vec![XpCell::EMPTY; width * height];
+        let mut put = |(x, y): (i32, i32), glyph: char, fg: Rgb| {
+            if (x_range.0..=x_range.1).contains(&x) && (y_range.0..=y_range.1).contains(&y) {
+                objects[(y_range.1 - y) as usize * width + (x - x_range.0) as usize] = cell(glyph, fg);
+            }
+        };
+        for room in &dungeon.rooms {
+            let color = room_kind_color(room.kind);
+            put(room.center, room_glyph(room.kind), (color.0, color.1, color.2));
+        }
+        for loot in dungeon.loot() {
+            put(loot.position, item_glyph(loot.item), theme.floor);
+        }
+        for trap in dungeon.traps() {
+            put(trap.position, TRAP_GLYPH, theme.monster);
+        }
+        for monster in &dungeon.monsters {
+            put(monster.position, MONSTER_GLYPH, theme.monster);
+        }
+
+        XpImage { width, height, layers: vec![terrain, objects] }
+    }
+
+    /// The uncompressed `.xp` data, to be gzipped by the caller
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.layers.len() * (8 + self.width * self.height * 10));
+        bytes.extend_from_slice(&XP_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.layers.len() as i32).to_le_bytes());
+        for layer in &self.layers {
+            bytes.extend_from_slice(&(self.width as i32).to_le_bytes());
+            bytes.extend_from_slice(&(self.height as i32).to_le_bytes());
+            // RexPaint stores cells column by column
+            for x in 0..self.width {
+                for y in 0..self.height {
+                    let cell = layer[y * self.width + x];
+                    bytes.extend_from_slice(&cell.glyph.to_le_bytes());
+                    bytes.extend_from_slice(&[cell.fg.0, cell.fg.1, cell.fg.2, cell.bg.0, cell.bg.1, cell.bg.2]);
+                }
+            }
+        }
+        bytes
+    }
+}
+
+/// Write the dungeon as a RexPaint `.xp` image to `out_path`
+pub fn export_rexpaint(dungeon: &Dungeon, out_path: &Path) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(out_path)?, Compression::default());
+    encoder.write_all(&XpImage::from_dungeon(dungeon).to_bytes())?;
+    encoder.finish()?;
+    Ok(())
+}
+
+// CP437 code of a glyph; every glyph the renderers use is printable ASCII, which CP437 keeps
+fn cp437(glyph: char) -> u32 {
+    if glyph.is_ascii() { glyph as u32 } else { b'?' as u32 }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use flate2::read::GzDecoder;
+    use crate::utils::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn images_cover_the_grid_in_two_layers() {
+        let dungeon = dungeon();
+        let image = XpImage::from_dungeon(&dungeon);
+        let cells = dungeon.grid.width() * dungeon.grid.height();
+        assert_eq!((image.width, image.height), (dungeon.grid.width(), dungeon.grid.height()));
+        assert!(image.layers.iter().all(|layer| layer.len() == cells));
+
+        // The entrance marker sits on the objects layer, over floor on the terrain layer
+        let entrance = &dungeon.rooms[0];
+        let (x_range, y_range) = (dungeon.grid.x_range(), dungeon.grid.y_range());
+        let index = (y_range.1 - entrance.center.1) as usize * image.width + (entrance.center.0 - x_range.0) as usize;
+        assert_eq!(image.layers[1][index].glyph, 'E' as u32);
+        assert_eq!(image.layers[0][index].glyph, cp437(Theme::for_dungeon(&dungeon).floor_glyph));
+        assert!(image.layers[1].contains(&XpCell::EMPTY));
+        assert!(!image.layers[0].iter().any(|cell| cell.bg == TRANSPARENT));
+    }
+
+    #[test]
+    fn exported_files_are_gzipped_column_major_layers() {
+        let dungeon = dungeon();
+        let path = std::env::temp_dir().join(format!("chia_dungeon_rexpaint_{}.xp", std::process::id()));
+        export_rexpaint(&dungeon, &path).unwrap();
+        let mut bytes = Vec::new();
+        GzDecoder::new(File::open(&path).unwrap()).read_to_end(&mut bytes).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let image = XpImage::from_dungeon(&dungeon);
+        assert_eq!(bytes, image.to_bytes());
+        let int = |offset: usize| i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        assert_eq!((int(0), int(4), int(8), int(12)), (XP_VERSION, 2, image.width as i32, image.height as i32));
+        assert_eq!(bytes.len(), 8 + 2 * (8 + image.width * image.height * 10));
+        // The second cell stored is the first column's second row
+        assert_eq!(int(16 + 10) as u32, image.layers[0][image.width].glyph);
+    }
+}
@@ -0,0 +1,416 @@
+//! # Tiled Map Export
+//!
+//! Exports a dungeon as a map for the Tiled editor (https://www.mapeditor.org), which
+//! most 2D game engines can import.
+//!
+//! The map is orthogonal with `TILE_SIZE` pixel tiles and three layers:
+//! - **floor**: Excavated tiles (floor, tunnel, door, room center, stairs, the water,
+//!   lava, and sand terrain features, and shallow water), one tile id each.
+//! - **walls**: Solid rock.
+//! - **objects**: One rectangle per room (type = room kind) and a point per spawn: the
+//!   player at the entrance room center, every monster (type "monster"), and every trap
+//!   (type "trap").
+//!
+//! Tiles reference a single embedded tileset whose image, `TILESET_IMAGE`, is the
+//! sprite atlas shipped with the crate (see `sprites`): its first tiles are one per
+//! `Tile` variant in the order of `tile_id`, except for the terrain features, whose
+//! sprites follow the overlays. Rows run from the highest y down, matching
+//! the plotted and ASCII maps.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde_json::{json, Value};
+use crate::utils::dungeon::{Dungeon, RoomKind};
+use crate::utils::grid::Tile;
+use crate::utils::sprites::SPRITE_COUNT;
+
+/// Width and height of a tile in pixels
+pub const TILE_SIZE: u32 = 16;
+/// Tileset image referenced by exported maps
+pub const TILESET_IMAGE: &str = "chia_dungeon_tiles.png";
+/// Tiled map format version written to exported maps
+const TILED_VERSION: &str = "1.10";
+/// Number of tiles in the tileset, the whole sprite atlas
+const TILE_COUNT: u32 = SPRITE_COUNT;
+
+/// File format of an exported Tiled map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TiledFormat {
+    /// XML `.tmx` map.
+    #[default]
+    Tmx,
+    /// JSON `.json` map.
+    Json,
+}
+
+impl TiledFormat {
+    /// Infers the format from a file extension, defaulting to TMX.
+    pub fn from_path(path: &Path) -> TiledFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => TiledFormat::Json,
+            _ => TiledFormat::Tmx,
+        }
+    }
+}
+
+/// Tileset index of a tile; global tile ids are this plus one
+pub fn tile_id(tile: Tile) -> u32 {
+    match tile {
+        Tile::Wall => 0,
+        Tile::Floor => 1,
+        Tile::Tunnel => 2,
+        Tile::Door => 3,
+        Tile::RoomCenter => 4,
+        Tile::StairsUp => 5,
+        Tile::StairsDown => 6,
+        Tile::Water => 14,
+        Tile::Lava => 15,
+        Tile::Sand => 16,
+        Tile::ShallowWater => 17,
+    }
+}
+
+// Object type written for a room of the given kind
+pub(crate) fn room_type(kind: RoomKind) -> &'static str {
+    match kind {
+        RoomKind::Entrance => "entrance",
+        RoomKind::Exit => "exit",
+        RoomKind::Treasure => "treasure",
+        RoomKind::Boss => "boss",
+        RoomKind::Shrine => "shrine",
+        RoomKind::Normal => "normal",
+    }
+}
+
+/// Write the dungeon as a Tiled map in the given `format` to `out_path`
+pub fn export_tiled(dungeon: &Dungeon, out_path: &Path, format: TiledFormat) -> io::Result<()> {
+    let contents = match format {
+        TiledFormat::Tmx => to_tmx(dungeon),
+        TiledFormat::Json => to_json(dungeon),
+    };
+    fs::write(out_path, contents)
+}
+
+/// Render the dungeon as a Tiled `.tmx` XML map
+pub fn to_tmx(dungeon: &Dungeon) -> String {
+    let map = TiledMap::new(dungeon);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    // `write!` to a String cannot fail
+    let _ = writeln!(
+        xml,
+        "<map version=\"{}\" orientation=\"orthogonal\" renderorder=\"right-down\" width=\"{}\" height=\"{}\" tilewidth=\"{}\" tileheight=\"{}\" infinite=\"0\" nextlayerid=\"4\" nextobjectid=\"{}\">",
+        TILED_VERSION, map.width, map.height, TILE_SIZE, TILE_SIZE, map.objects.len() + 1
+    );
+    xml.push_str(" <properties>\n");
+    for (name, kind, value) in &map.properties {
+        let _ = writeln!(xml, "  <property name=\"{}\" type=\"{}\" value=\"{}\"/>", name, kind, escape_xml(value));
+    }
+    xml.push_str(" </properties>\n");
+    let _ = writeln!(
+        xml,
+        " <tileset firstgid=\"1\" name=\"chia_dungeon\" tilewidth=\"{0}\" tileheight=\"{0}\" tilecount=\"{1}\" columns=\"{1}\">\n  <image source=\"{2}\" width=\"{3}\" height=\"{0}\"/>\n </tileset>",
+        TILE_SIZE, TILE_COUNT, TILESET_IMAGE, TILE_SIZE * TILE_COUNT
+    );
+
+    for (id, name, data) in [(1, "floor", &map.floor), (2, "walls", &map.walls)] {
+        let _ = writeln!(xml, " <layer id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\">", id, name, map.width, map.height);
+        xml.push_str("  <data encoding=\"csv\">\n");
+        let rows: Vec<String> = data
+            .chunks(map.width)
+            .map(|row| row.iter().map(|gid| gid.to_string()).collect::<Vec<_>>().join(","))
+            .collect();
+        xml.push_str(&rows.join(",\n"));
+        xml.push_str("\n  </data>\n </layer>\n");
+    }
+
+    xml.push_str(" <objectgroup id=\"3\" name=\"objects\">\n");
+    for object in &map.objects {
+        let _ = write!(
+            xml,
+            "  <object id=\"{}\" name=\"{}\" type=\"{}\" x=\"{}\" y=\"{}\"",
+            object.id,
+            escape_xml(&object.name),
+            object.kind,
+            object.x,
+            object.y
+        );
+        if !object.point {
+            let _ = write!(xml, " width=\"{}\" height=\"{}\"", object.width, object.height);
+        }
+        xml.push_str(">\n   <properties>\n");
+        for (name, kind, value) in &object.properties {
+            let _ = writeln!(xml, "    <property name=\"{}\" type=\"{}\" value=\"{}\"/>", name, kind, escape_xml(value));
+        }
+        xml.push_str("   </properties>\n");
+        if object.point {
+            xml.push_str("   <point/>\n");
+        }
+        xml.push_str("  </object>\n");
+    }
+    xml.push_str(" </objectgroup>\n</map>\n");
+
+    xml
+}
+
+/// Render the dungeon as a Tiled `.json` map
+pub fn to_json(dungeon: &Dungeon) -> String {
+    let map = TiledMap::new(dungeon);
+    let properties = |props: &[(String, &str, String)]| -> Vec<Value> {
+        props
+            .iter()
+            .map(|(name, kind, value)| match *kind {
+                "int" => json!({ "name": name, "type": kind, "value": value.parse::<i64>().unwrap_or(0) }),
+                _ => json!({ "name": name, "type": kind, "value": value }),
+            })
+            .collect()
+    };
+    let tile_layer = |id: u32, name: &str, data: &[u32]| {
+        json!({
+            "id": id,
+            "name": name,
+            "type": "tilelayer",
+            "width": map.width,
+            "height": map.height,
+            "x": 0,
+            "y": 0,
+            "opacity": 1,
+            "visible": true,
+            "data": data,
+        })
+    };
+    let objects: Vec<Value> = map
+        .objects
+        .iter()
+        .map(|object| {
+            json!({
+                "id": object.id,
+                "name": object.name,
+                "type": object.kind,
+                "x": object.x,
+                "y": object.y,
+                "width": object.width,
+                "height": object.height,
+                "rotation": 0,
+                "visible": true,
+                "point": object.point,
+                "properties": properties(&object.properties),
+            })
+        })
+        .collect();
+
+    let value = json!({
+        "type": "map",
+        "version": TILED_VERSION,
+        "orientation": "orthogonal",
+        "renderorder": "right-down",
+        "width": map.width,
+        "height": map.height,
+        "tilewidth": TILE_SIZE,
+        "tileheight": TILE_SIZE,
+        "infinite": false,
+        "nextlayerid": 4,
+        "nextobjectid": map.objects.len() + 1,
+        "properties": properties(&map.properties),
+        "tilesets": [{
+            "firstgid": 1,
+            "name": "chia_dungeon",
+            "image": TILESET_IMAGE,
+            "imagewidth": TILE_SIZE * TILE_COUNT,
+            "imageheight": TILE_SIZE,
+            "tilewidth": TILE_SIZE,
+            "tileheight": TILE_SIZE,
+            "tilecount": TILE_COUNT,
+            "columns": TILE_COUNT,
+            "margin": 0,
+            "spacing": 0,
+        }],
+        "layers": [
+            tile_layer(1, "floor", &map.floor),
+            tile_layer(2, "walls", &map.walls),
+            {
+                "id": 3,
+                "name": "objects",
+                "type": "objectgroup",
+                "draworder": "topdown",
+                "x": 0,
+                "y": 0,
+                "opacity": 1,
+                "visible": true,
+                "objects": objects,
+            },
+        ],
+    });
+
+    format!("{:#}", value)
+}
+
+// Format-independent contents of an exported map
+struct TiledMap {
+    width: usize,
+    height: usize,
+    // Global tile ids per layer, row-major from the top row; 0 is an empty cell
+    floor: Vec<u32>,
+    walls: Vec<u32>,
+    objects: Vec<MapObject>,
+    properties: Vec<(String, &'static str, String)>,
+}
+
+// A room rectangle or spawn point, in pixels from the top-left corner of the map
+struct MapObject {
+    id: usize,
+    name: String,
+    kind: &'static str,
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+    point: bool,
+    properties: Vec<(String, &'static str, String)>,
+}
+
+impl TiledMap {
+    fn new(dungeon: &Dungeon) -> TiledMap {
+        let grid = &dungeon.grid;
+        let (x_range, y_range) = (grid.x_range(), grid.y_range());
+        let (width, height) = (grid.width(), grid.height());
+        let tile = TILE_SIZE as i64;
+        // Pixel offset of the top-left corner of a tile
+        let pixel = |(x, y): (i32, i32)| ((x - x_range.0) as i64 * tile, (y_range.1 - y) as i64 * tile);
+
+        let mut floor = vec![0; width * height];
+        let mut walls = vec![0; width * height];
+        for (pos, &cell) in grid.iter() {
+            let index = (y_range.1 - pos.1) as usize * width + (pos.0 - x_range.0) as usize;
+            let gid = tile_id(cell) + 1;
+            if cell != Tile::Wall {
+                floor[index] = gid;
+            } else {
+                walls[index] = gid;
+            }
+        }
+
+        let mut objects = Vec::new();
+        for room in &dungeon.rooms {
+            let min_x = room.tiles.iter().map(|&(x, _)| x).min().unwrap_or(room.center.0);
+            let max_x = room.tiles.iter().map(|&(x, _)| x).max().unwrap_or(room.center.0);
+            let min_y = room.tiles.iter().map(|&(_, y)| y).min().unwrap_or(room.center.1);
+            let max_y = room.tiles.iter().map(|&(_, y)| y).max().unwrap_or(room.center.1);
+            let (x, y) = pixel((min_x, max_y));
+            objects.push(MapObject {
+                id: objects.len() + 1,
+                name: room.name.clone(),
+                kind: room_type(room.kind),
+                x,
+                y,
+                width: (max_x - min_x + 1) as i64 * tile,
+                height: (max_y - min_y + 1) as i64 * tile,
+                point: false,
+                properties: vec![
+                    ("size".to_string(), "int", room.size.to_string()),
+                    ("shape".to_string(), "string", room.shape.clone()),
+                ],
+            });
+        }
+
+        // Spawn points sit in the middle of their tile
+        let mut spawn = |name: String, kind: &'static str, pos: (i32, i32), properties| {
+            let (x, y) = pixel(pos);
+            objects.push(MapObject {
+                id: objects.len() + 1,
+                name,
+                kind,
+                x: x + tile / 2,
+                y: y + tile / 2,
+                width: 0,
+                height: 0,
+                point: true,
+                properties,
+            });
+        };
+        if let Some(entrance) = dungeon.rooms.iter().find(|room| room.kind == RoomKind::Entrance) {
+            spawn("Player".to_string(), "player", entrance.center, Vec::new());
+        }
+        for monster in &dungeon.monsters {
+            let properties = vec![("level".to_string(), "int", monster.level.to_string())];
+            spawn(monster.species.clone(), "monster", monster.position, properties);
+        }
+        for trap in dungeon.traps() {
+            let properties = vec![("damage".to_string(), "int", trap.damage.to_string())];
+            spawn(trap.kind, "trap", trap.position, properties);
+        }
+
+        let properties = vec![
+            ("nft_id".to_string(), "string", dungeon.nft_id.clone()),
+            ("dungeon_type".to_string(), "string", dungeon.dungeon_type.clone()),
+            ("level".to_string(), "int", dungeon.level.to_string()),
+        ];
+
+        TiledMap { width, height, floor, walls, objects, properties }
+    }
+}
+
+// Escape a value for use inside a double-quoted XML attribute
+pub(crate) fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::fixtures::dungeon;
+    use crate::utils::sprites::Sprite;
+    use super::*;
+
+    #[test]
+    fn formats_follow_the_file_extension() {
+        assert_eq!(TiledFormat::from_path(Path::new("map.JSON")), TiledFormat::Json);
+        assert_eq!(TiledFormat::from_path(Path::new("map.tmx")), TiledFormat::Tmx);
+        assert_eq!(TiledFormat::from_path(Path::new("map")), TiledFormat::Tmx);
+    }
+
+    #[test]
+    fn tile_ids_index_the_sprite_atlas() {
+        for tile in [Tile::Wall, Tile::Door, Tile::StairsDown, Tile::Water, Tile::Lava, Tile::Sand, Tile::ShallowWater] {
+            assert_eq!(tile_id(tile), Sprite::for_tile(tile).index());
+        }
+        assert_ne!(tile_id(Tile::ShallowWater), tile_id(Tile::Water));
+    }
+
+    #[test]
+    fn json_maps_cover_the_grid_and_every_spawn() {
+        let dungeon = dungeon();
+        let map: Value = serde_json::from_str(&to_json(&dungeon)).unwrap();
+        let cells = dungeon.grid.width() * dungeon.grid.height();
+        assert_eq!(map["width"], dungeon.grid.width());
+        assert_eq!(map["layers"][0]["data"].as_array().unwrap().len(), cells);
+        assert_eq!(map["layers"][1]["data"].as_array().unwrap().len(), cells);
+
+        let objects = map["layers"][2]["objects"].as_array().unwrap();
+        assert_eq!(objects.len(), dungeon.rooms.len() + 1 + dungeon.monsters.len() + dungeon.traps().len());
+        assert_eq!(objects[0]["type"], "entrance");
+        assert_eq!(objects[dungeon.rooms.len()]["type"], "player");
+    }
+
+    #[test]
+    fn tmx_maps_declare_the_layers_and_tileset() {
+        let dungeon = dungeon();
+        let tmx = to_tmx(&dungeon);
+        assert!(tmx.starts_with("<?xml"));
+        assert!(tmx.contains(&format!("width=\"{}\" height=\"{}\"", dungeon.grid.width(), dungeon.grid.height())));
+        assert!(tmx.contains(&format!("<image source=\"{}\"", TILESET_IMAGE)));
+        assert!(tmx.contains("name=\"walls\""));
+        assert!(tmx.trim_end().ends_with("</map>"));
+    }
+
+    #[test]
+    fn attribute_values_are_escaped() {
+        assert_eq!(escape_xml("Tom's <\"R&D\"> lab"), "Tom&apos;s &lt;&quot;R&amp;D&quot;&gt; lab");
+    }
+}
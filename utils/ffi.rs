@@ -0,0 +1,380 @@
+//! # C Bindings
+//!
+//! A C ABI for native game engine plugins (Unity, Unreal, Godot) that call the generator
+//! directly, behind the `ffi` feature. Build the library with
+//! `cargo rustc --release --lib --features ffi --crate-type cdylib` (or `staticlib`);
+//! the matching header is checked in as `include/chia_dungeon.h`. The build regenerates
+//! it in `OUT_DIR` with `cbindgen`, and a test fails until the checked-in copy matches.
+//!
+//! - **Handles**: `chia_dungeon_parse` and `chia_dungeon_generate` return an opaque
+//!   `ChiaDungeon`, released with `chia_dungeon_free`. Handles are never modified, so
+//!   several threads may read one at once.
+//! - **Strings**: The JSON and ASCII renders are NUL-terminated UTF-8 owned by the
+//!   caller, released with `chia_dungeon_string_free`.
+//! - **Buffers**: `chia_dungeon_render_rgba` fills a caller-allocated buffer with
+//!   `CHIA_DUNGEON_IMAGE_WIDTH` x `CHIA_DUNGEON_IMAGE_HEIGHT` RGBA pixels, and
+//!   `chia_dungeon_render_tiles` with one `ChiaDungeonTile` per cell of the grid sized by
+//!   `chia_dungeon_grid`, row by row from the lowest y. Every render takes a floor, 0
+//!   being the top floor.
+//! - **Errors**: Failing functions return `NULL` or `-1`, and `chia_dungeon_last_error`
+//!   describes the failure until the next one on the same thread. Panics never unwind
+//!   into the caller; they fail the call instead.
+//!
+//! ```c
+//! ChiaDungeon *dungeon = chia_dungeon_parse("nft1...");
+//! if (dungeon == NULL) {
+//!     fprintf(stderr, "%s\n", chia_dungeon_last_error());
+//!     return;
+//! }
+//! size_t len = CHIA_DUNGEON_IMAGE_WIDTH * CHIA_DUNGEON_IMAGE_HEIGHT * 4;
+//! uint8_t *pixels = malloc(len);
+//! chia_dungeon_render_rgba(dungeon, 0, pixels, len);
+//! chia_dungeon_free(dungeon);
+//! ```
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use super::ascii::render_ascii;
+use super::dungeon::Dungeon;
+use super::excavator::parse_nft_id;
+use super::generator::generate_nft_id_from_seed;
+use super::grid::Tile;
+use super::plot::render_rgba_image;
+use super::theme::Theme;
+
+/// Width of the images written by `chia_dungeon_render_rgba`, in pixels
+pub const CHIA_DUNGEON_IMAGE_WIDTH: u32 = 640;
+/// Height of the images written by `chia_dungeon_render_rgba`, in pixels
+pub const CHIA_DUNGEON_IMAGE_HEIGHT: u32 = 480;
+
+thread_local! {
+    // Message of the last failure on this thread
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// A parsed dungeon, opaque to C.
+pub struct ChiaDungeon {
+    dungeon: Dungeon,
+}
+
+/// Kind of a grid cell written by `chia_dungeon_render_tiles` (see `Tile`).
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChiaDungeonTile {
+    Wall = 0,
+    Floor = 1,
+    Tunnel = 2,
+    Door = 3,
+    RoomCenter = 4,
+    StairsUp = 5,
+    StairsDown = 6,
+    Water = 7,
+    Lava = 8,
+    Sand = 9,
+    ShallowWater = 10,
+}
+
+impl From<Tile> for ChiaDungeonTile {
+    fn from(tile: Tile) -> ChiaDungeonTile {
+        match tile {
+            Tile::Wall => ChiaDungeonTile::Wall,
+            Tile::Floor => ChiaDungeonTile::Floor,
+            Tile::Tunnel => ChiaDungeonTile::Tunnel,
+            Tile::Door => ChiaDungeonTile::Door,
+            Tile::RoomCenter => ChiaDungeonTile::RoomCenter,
+            Tile::StairsUp => ChiaDungeonTile::StairsUp,
+            Tile::StairsDown => ChiaDungeonTile::StairsDown,
+            Tile::Water => ChiaDungeonTile::Water,
+            Tile::Lava => ChiaDungeonTile::Lava,
+            Tile::Sand => ChiaDungeonTile::Sand,
+            Tile::ShallowWater => ChiaDungeonTile::ShallowWater,
+        }
+    }
+}
+
+/// Placement and size of a floor's tile grid.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChiaDungeonGrid {
+    /// X coordinate of the first column.
+    pub min_x: i32,
+    /// Y coordinate of the first row.
+    pub min_y: i32,
+    /// Number of columns.
+    pub width: u32,
+    /// Number of rows.
+    pub height: u32,
+}
+
+/// Decode a NUL-terminated NFT ID into a dungeon; `NULL` on failure
+///
+/// # Safety
+///
+/// `nft_id` must be `NULL` or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_parse(nft_id: *const c_char) -> *mut ChiaDungeon {
+    into_handle(guard(|| parse(unsafe { read_str(nft_id) }?)))
+}
+
+/// Generate the synthetic NFT ID for `seed` and decode its dungeon; `NULL` on failure
+#[no_mangle]
+pub extern "C" fn chia_dungeon_generate(seed: u64) -> *mut ChiaDungeon {
+    into_handle(guard(|| parse(&generate_nft_id_from_seed(seed))))
+}
+
+/// Release a dungeon returned by `chia_dungeon_parse` or `chia_dungeon_generate`
+///
+/// # Safety
+///
+/// `dungeon` must be `NULL` or a handle not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_free(dungeon: *mut ChiaDungeon) {
+    if !dungeon.is_null() {
+        drop(unsafe { Box::from_raw(dungeon) });
+    }
+}
+
+/// Number of floors of the dungeon, including the top floor; 0 for `NULL`
+///
+/// # Safety
+///
+/// `dungeon` must be `NULL` or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_floor_count(dungeon: *const ChiaDungeon) -> u32 {
+    unsafe { dungeon.as_ref() }.map_or(0, |handle| handle.dungeon.floor_count() as u32)
+}
+
+/// The dungeon as JSON (see `Dungeon::to_json`); `NULL` on failure
+///
+/// # Safety
+///
+/// `dungeon` must be `NULL` or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_to_json(dungeon: *const ChiaDungeon) -> *mut c_char {
+    into_string(guard(|| unsafe { borrow(dungeon) }?.to_json().map_err(|err| err.to_string())))
+}
+
+/// Render a floor as ASCII text (see `ascii`); `NULL` on failure
+///
+/// # Safety
+///
+/// `dungeon` must be `NULL` or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_render_ascii(dungeon: *const ChiaDungeon, floor: u32) -> *mut c_char {
+    into_string(guard(|| Ok(render_ascii(&floor_view(unsafe { borrow(dungeon) }?, floor)?))))
+}
+
+/// Write the placement and size of a floor's grid to `grid`; 0 on success, -1 on failure
+///
+/// # Safety
+///
+/// `dungeon` must be `NULL` or a live handle, and `grid` `NULL` or writable.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_grid(dungeon: *const ChiaDungeon, floor: u32, grid: *mut ChiaDungeonGrid) -> i32 {
+    status(guard(|| {
+        let floor = floor_view(unsafe { borrow(dungeon) }?, floor)?;
+        let out = unsafe { grid.as_mut() }.ok_or("grid is NULL")?;
+        *out = grid_of(&floor);
+        Ok(())
+    }))
+}
+
+/// Write one `ChiaDungeonTile` per cell of a floor's grid to `buffer`, row by row from the
+/// lowest y; 0 on success, -1 on failure or if `len` is below the cell count
+///
+/// # Safety
+///
+/// `dungeon` must be `NULL` or a live handle, and `buffer` `NULL` or writable for `len`
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_render_tiles(dungeon: *const ChiaDungeon, floor: u32, buffer: *mut u8, len: usize) -> i32 {
+    status(guard(|| {
+        let floor = floor_view(unsafe { borrow(dungeon) }?, floor)?;
+        let out = unsafe { writable(buffer, len, floor.grid.width() * floor.grid.height()) }?;
+        for (cell, (_, &tile)) in out.iter_mut().zip(floor.grid.iter()) {
+            *cell = ChiaDungeonTile::from(tile) as u8;
+        }
+        Ok(())
+    }))
+}
+
+/// Render a floor as RGBA pixels, row by row from the top, in the theme of its dungeon
+/// type; 0 on success, -1 on failure or if `len` is below
+/// `CHIA_DUNGEON_IMAGE_WIDTH * CHIA_DUNGEON_IMAGE_HEIGHT * 4`
+///
+/// # Safety
+///
+/// `dungeon` must be `NULL` or a live handle, and `buffer` `NULL` or writable for `len`
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_render_rgba(dungeon: *const ChiaDungeon, floor: u32, buffer: *mut u8, len: usize) -> i32 {
+    status(guard(|| {
+        let floor = floor_view(unsafe { borrow(dungeon) }?, floor)?;
+        let needed = (CHIA_DUNGEON_IMAGE_WIDTH * CHIA_DUNGEON_IMAGE_HEIGHT * 4) as usize;
+        let out = unsafe { writable(buffer, len, needed) }?;
+        let image = render_rgba_image(&floor, &Theme::for_dungeon(&floor), None).map_err(|err| err.to_string())?;
+        out.copy_from_slice(image.as_raw());
+        Ok(())
+    }))
+}
+
+/// Message of the last failure on the calling thread, empty if none; valid until the
+/// next failure on the same thread
+#[no_mangle]
+pub extern "C" fn chia_dungeon_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Release a string returned by `chia_dungeon_to_json` or `chia_dungeon_render_ascii`
+///
+/// # Safety
+///
+/// `string` must be `NULL` or a string returned by this library and not yet released.
+#[no_mangle]
+pub unsafe extern "C" fn chia_dungeon_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+fn parse(nft_id: &str) -> Result<Dungeon, String> {
+    parse_nft_id(nft_id).map_err(|err| err.to_string())
+}
+
+fn floor_view(dungeon: &Dungeon, floor: u32) -> Result<Dungeon, String> {
+    dungeon.floor_view(floor as usize).ok_or_else(|| format!("Dungeon has no floor {}", floor))
+}
+
+fn grid_of(dungeon: &Dungeon) -> ChiaDungeonGrid {
+    ChiaDungeonGrid {
+        min_x: dungeon.grid.x_range().0,
+        min_y: dungeon.grid.y_range().0,
+        width: dungeon.grid.width() as u32,
+        height: dungeon.grid.height() as u32,
+    }
+}
+
+// Run `call`, recording its error or panic as the thread's last error
+fn guard<T>(call: impl FnOnce() -> Result<T, String>) -> Option<T> {
+    let result = panic::catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|_| Err("chia_dungeon panicked".to_string()));
+    result
+        .map_err(|message| {
+            let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+            LAST_ERROR.with(|last| *last.borrow_mut() = message);
+        })
+        .ok()
+}
+
+fn into_handle(dungeon: Option<Dungeon>) -> *mut ChiaDungeon {
+    dungeon.map_or(ptr::null_mut(), |dungeon| Box::into_raw(Box::new(ChiaDungeon { dungeon })))
+}
+
+fn into_string(string: Option<String>) -> *mut c_char {
+    string
+        .and_then(|string| guard(|| CString::new(string).map_err(|err| err.to_string())))
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+fn status(result: Option<()>) -> i32 {
+    if result.is_some() { 0 } else { -1 }
+}
+
+unsafe fn read_str<'a>(string: *const c_char) -> Result<&'a str, String> {
+    if string.is_null() {
+        return Err("NFT ID is NULL".to_string());
+    }
+    unsafe { CStr::from_ptr(string) }.to_str().map_err(|err| format!("NFT ID is not UTF-8: {}", err))
+}
+
+unsafe fn borrow<'a>(dungeon: *const ChiaDungeon) -> Result<&'a Dungeon, String> {
+    unsafe { dungeon.as_ref() }.map(|handle| &handle.dungeon).ok_or_else(|| "dungeon is NULL".to_string())
+}
+
+// The first `needed` bytes of `buffer`, if it holds that many
+unsafe fn writable<'a>(buffer: *mut u8, len: usize, needed: usize) -> Result<&'a mut [u8], String> {
+    if buffer.is_null() {
+        return Err("buffer is NULL".to_string());
+    }
+    if len < needed {
+        return Err(format!("buffer holds {} bytes, {} needed", len, needed));
+    }
+    Ok(unsafe { slice::from_raw_parts_mut(buffer, needed) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ascii;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    fn take_string(string: *mut c_char) -> String {
+        assert!(!string.is_null());
+        let text = unsafe { CStr::from_ptr(string) }.to_str().unwrap().to_string();
+        unsafe { chia_dungeon_string_free(string) };
+        text
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(chia_dungeon_last_error()) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn handles_render_into_caller_buffers() {
+        let nft_id = CString::new(NFT_ID).unwrap();
+        let handle = unsafe { chia_dungeon_parse(nft_id.as_ptr()) };
+        assert!(!handle.is_null());
+        let expected = dungeon();
+        assert_eq!(unsafe { chia_dungeon_floor_count(handle) } as usize, expected.floor_count());
+        assert_eq!(Dungeon::from_json(&take_string(unsafe { chia_dungeon_to_json(handle) })).unwrap(), expected);
+        assert_eq!(take_string(unsafe { chia_dungeon_render_ascii(handle, 0) }), ascii::render_ascii(&expected));
+
+        let mut grid = ChiaDungeonGrid::default();
+        assert_eq!(unsafe { chia_dungeon_grid(handle, 0, &mut grid) }, 0);
+        assert_eq!((grid.width as usize, grid.height as usize), (expected.grid.width(), expected.grid.height()));
+        let mut tiles = vec![u8::MAX; expected.grid.width() * expected.grid.height()];
+        assert_eq!(unsafe { chia_dungeon_render_tiles(handle, 0, tiles.as_mut_ptr(), tiles.len()) }, 0);
+        let center = expected.rooms[0].center;
+        let index = (center.1 - grid.min_y) as usize * grid.width as usize + (center.0 - grid.min_x) as usize;
+        assert_eq!(tiles[index], ChiaDungeonTile::RoomCenter as u8);
+        assert!(tiles.iter().all(|&tile| tile <= ChiaDungeonTile::ShallowWater as u8));
+
+        let mut pixels = vec![0; (CHIA_DUNGEON_IMAGE_WIDTH * CHIA_DUNGEON_IMAGE_HEIGHT * 4) as usize];
+        assert_eq!(unsafe { chia_dungeon_render_rgba(handle, 0, pixels.as_mut_ptr(), pixels.len()) }, 0);
+        assert!(pixels.iter().any(|&byte| byte != 0));
+        unsafe { chia_dungeon_free(handle) };
+
+        let generated = chia_dungeon_generate(7);
+        assert!(!generated.is_null());
+        unsafe { chia_dungeon_free(generated) };
+    }
+
+    #[test]
+    fn failures_report_their_reason() {
+        let invalid = CString::new("nft1").unwrap();
+        assert!(unsafe { chia_dungeon_parse(invalid.as_ptr()) }.is_null());
+        assert!(!last_error().is_empty());
+        assert!(unsafe { chia_dungeon_parse(ptr::null()) }.is_null());
+        assert_eq!(last_error(), "NFT ID is NULL");
+
+        let nft_id = CString::new(NFT_ID).unwrap();
+        let handle = unsafe { chia_dungeon_parse(nft_id.as_ptr()) };
+        let mut small = vec![0; 16];
+        assert_eq!(unsafe { chia_dungeon_render_rgba(handle, 0, small.as_mut_ptr(), small.len()) }, -1);
+        assert!(last_error().starts_with("buffer holds 16 bytes"));
+        assert!(unsafe { chia_dungeon_render_ascii(handle, 99) }.is_null());
+        assert_eq!(last_error(), "Dungeon has no floor 99");
+        assert_eq!(unsafe { chia_dungeon_grid(ptr::null(), 0, ptr::null_mut()) }, -1);
+        unsafe { chia_dungeon_free(handle) };
+        unsafe { chia_dungeon_free(ptr::null_mut()) };
+    }
+
+    #[test]
+    fn checked_in_header_is_up_to_date() {
+        // Refresh it by copying the header the build generated
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/chia_dungeon.h"));
+        assert_eq!(include_str!("../include/chia_dungeon.h"), generated, "include/chia_dungeon.h is stale");
+    }
+}
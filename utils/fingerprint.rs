@@ -0,0 +1,153 @@
+//! # Layout Fingerprints
+//!
+//! Stable content hashes of a dungeon's structural layout, so marketplaces can spot
+//! duplicate and near-duplicate dungeons across a collection.
+//!
+//! - **Layout**: The rooms (kind and excavated tiles) and tunnel paths of every floor.
+//!   Rubble, smoothing, terrain, elevation, monsters, loot, and room names are left out,
+//!   so only the decoded structure counts.
+//! - **Translation**: Coordinates are taken relative to the lowest room center
+//!   coordinates of their floor, so a layout shifted across the map keeps its fingerprint.
+//! - **Hash**: 64-bit FNV-1a over the layout (`Fingerprint::hash`), stable across
+//!   platforms and Rust releases like the RNG seeds (see `seed`). Equal hashes mean
+//!   duplicate layouts.
+//! - **Sketch**: A MinHash sketch of the tiles covered by rooms and tunnels.
+//!   `Fingerprint::similarity` estimates the share of those tiles two layouts have in
+//!   common, so near-duplicates score close to 1.
+//!
+//! The CLI's `fingerprint` command prints the hash of every NFT ID it is given and the
+//! pairs of near-duplicate layouts among them.
+
+use std::collections::HashSet;
+use std::fmt;
+use super::dungeon::Dungeon;
+use super::seed::{fnv1a, FNV_OFFSET_BASIS};
+
+/// Number of minimum hashes in a layout sketch
+pub const SKETCH_SIZE: usize = 64;
+
+/// Structural fingerprint of a dungeon (see `fingerprint`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Hash of the whole layout; equal for duplicate layouts.
+    pub hash: u64,
+    /// Smallest hash of the covered tiles under each of `SKETCH_SIZE` hash functions.
+    pub sketch: Vec<u64>,
+}
+
+impl Fingerprint {
+    /// Estimated share of covered tiles two layouts have in common, from 0 to 1.
+    pub fn similarity(&self, other: &Fingerprint) -> f64 {
+        let shared = self.sketch.iter().zip(&other.sketch).filter(|(a, b)| a == b).count();
+        shared as f64 / SKETCH_SIZE as f64
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.hash)
+    }
+}
+
+impl Dungeon {
+    /// Structural fingerprint of the dungeon's layout on every floor (see `fingerprint`).
+    pub fn fingerprint(&self) -> Fingerprint {
+        let layouts = std::iter::once((&self.rooms, &self.tunnels)).chain(self.floors.iter().map(|floor| (&floor.rooms, &floor.tunnels)));
+        let mut hash = FNV_OFFSET_BASIS;
+        let mut covered = HashSet::new();
+        for (depth, (rooms, tunnels)) in layouts.enumerate() {
+            let origin = (
+                rooms.iter().map(|room| room.center.0).min().unwrap_or(0),
+                rooms.iter().map(|room| room.center.1).min().unwrap_or(0),
+            );
+            let relative = |pos: (i32, i32)| (pos.0 - origin.0, pos.1 - origin.1);
+            let mut write = |values: &[i32]| values.iter().for_each(|value| hash = fnv1a(hash, &value.to_le_bytes()));
+
+            write(&[depth as i32, rooms.len() as i32]);
+            for room in rooms {
+                let center = relative(room.center);
+                write(&[room.kind as i32, center.0, center.1, room.tiles.len() as i32]);
+                for &tile in &room.tiles {
+                    let tile = relative(tile);
+                    write(&[tile.0, tile.1]);
+                    covered.insert(tile_hash(depth, tile));
+                }
+            }
+            write(&[tunnels.len() as i32]);
+            for tunnel in tunnels {
+                write(&[tunnel.len() as i32]);
+                for &step in tunnel {
+                    let step = relative(step);
+                    write(&[step.0, step.1]);
+                    covered.insert(tile_hash(depth, step));
+                }
+            }
+        }
+
+        let sketch = (0..SKETCH_SIZE as u64)
+            .map(|index| covered.iter().map(|&tile| mix(tile ^ index.wrapping_mul(0x9e37_79b9_7f4a_7c15))).min().unwrap_or(u64::MAX))
+            .collect();
+        Fingerprint { hash, sketch }
+    }
+}
+
+// Hash of a covered tile on the floor at `depth`
+fn tile_hash(depth: usize, tile: (i32, i32)) -> u64 {
+    [depth as i32, tile.0, tile.1].iter().fold(FNV_OFFSET_BASIS, |hash, value| fnv1a(hash, &value.to_le_bytes()))
+}
+
+// SplitMix64 finalizer, turning a tile hash into one of the sketch's hash functions
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::parse_nft_id;
+    use super::super::fixtures::dungeon;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::super::grid::Tile;
+
+    #[test]
+    fn fingerprints_ignore_decoration_and_position() {
+        let dungeon = dungeon();
+        let fingerprint = dungeon.fingerprint();
+        assert_eq!(fingerprint.to_string(), format!("{:016x}", fingerprint.hash));
+        assert_eq!(fingerprint.similarity(&fingerprint), 1.0);
+
+        // Rubble, monsters, and names are not part of the layout
+        let mut decorated = dungeon.clone();
+        decorated.grid.set(dungeon.excavated[0], Tile::Wall);
+        decorated.excavated.push((-40, -40));
+        decorated.monsters.clear();
+        decorated.rooms.iter_mut().for_each(|room| room.name.clear());
+        assert_eq!(decorated.fingerprint(), fingerprint);
+
+        // Shifting the whole layout keeps its fingerprint
+        let mut shifted = dungeon.clone();
+        let shift = |pos: &mut (i32, i32)| *pos = (pos.0 + 7, pos.1 - 3);
+        for room in &mut shifted.rooms {
+            shift(&mut room.center);
+            room.tiles.iter_mut().for_each(shift);
+        }
+        shifted.tunnels.iter_mut().flatten().for_each(shift);
+        assert_eq!(shifted.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn near_duplicates_score_close_to_one() {
+        let dungeon = dungeon();
+        let fingerprint = dungeon.fingerprint();
+        let mut edited = dungeon.clone();
+        edited.rooms[0].tiles.pop();
+        let near = edited.fingerprint();
+        assert_ne!(near.hash, fingerprint.hash);
+        assert!(near.similarity(&fingerprint) > 0.9);
+
+        let other = parse_nft_id(&generate_nft_id_with(&GenerateOptions { seed: Some(5), ..GenerateOptions::default() }).unwrap()).unwrap();
+        assert_ne!(other.fingerprint().hash, fingerprint.hash);
+        assert!(other.fingerprint().similarity(&fingerprint) < 0.5);
+    }
+}
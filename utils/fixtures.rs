@@ -0,0 +1,14 @@
+//! # Test Fixtures
+//!
+//! Dungeons shared by the unit tests of the `utils` modules.
+
+use super::dungeon::Dungeon;
+use super::excavator::parse_nft_id;
+
+/// A Temple of nine rooms
+pub(crate) const NFT_ID: &str = "nft1Hk3r9Vx2mQ7pL0aZ8cW4tN6yB1sF5dJ";
+
+/// The dungeon of `NFT_ID`
+pub(crate) fn dungeon() -> Dungeon {
+    parse_nft_id(NFT_ID).unwrap()
+}
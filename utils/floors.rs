@@ -0,0 +1,237 @@
+//! # Multi-Floor Dungeons
+//!
+//! Splits the NFT ID into segments and digs one floor per segment.
+//!
+//! - **Segments**: The characters after "nft1" are split into `FLOOR_SEGMENT_LEN`-character
+//!   segments. Each full segment yields one floor; the first is the top floor that the
+//!   `Dungeon` itself describes, so a 62-character ID has three floors. Long IDs stop at
+//!   `MAX_FLOORS`, so generation time stays bounded however long the ID grows.
+//! - **Layout**: Floor `n` is excavated from the ID rotated to start at segment `n`, so each
+//!   floor decodes its room count, coordinates, and shapes from different characters.
+//! - **Stairs**: Every floor but the deepest has stairs down in its exit room; every lower
+//!   floor has stairs up in its entrance room.
+//! - **Level**: Each floor is one level deeper than the floor above, and its monsters are
+//!   spawned from its own ID segment.
+
+use serde::{Deserialize, Serialize};
+use super::config::GenerationConfig;
+use super::decorate::{place_decorations, Decoration};
+use super::dungeon::{Bounds, Dungeon, Room, RoomKind};
+use super::error::DungeonError;
+use super::excavator::excavate;
+use super::grid::{Grid, Tile};
+use super::hooks::GenerationObserver;
+use super::pathfinding::neighbors;
+use super::shapes::ShapeRegistry;
+use super::spawn::{spawn_monsters, MonsterSpawn};
+
+/// Number of ID characters after "nft1" that make up one floor segment
+pub const FLOOR_SEGMENT_LEN: usize = 16;
+/// Most floors a dungeon has, including the top floor
+pub const MAX_FLOORS: usize = 8;
+
+/// A floor below the top floor of a dungeon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Floor {
+    /// Depth below the top floor, starting at 1.
+    pub depth: usize,
+    /// Floor level: the dungeon level plus the depth.
+    pub level: u64,
+    /// Rooms in the order they were decoded.
+    pub rooms: Vec<Room>,
+    /// Tunnel paths connecting room centers.
+    pub tunnels: Vec<Vec<(i32, i32)>>,
+    /// Width in tiles of the corridors dug around each tunnel path.
+    pub corridor_width: u32,
+    /// Bounding box of the excavated tiles and room centers, padded by one tile.
+    pub bounds: Bounds,
+    /// Total area of all rooms.
+    pub area_size: u64,
+    /// Every excavated coordinate (rooms, tunnels, and random points).
+    pub excavated: Vec<(i32, i32)>,
+    /// Tile grid covering every excavated coordinate, including the stairs.
+    pub grid: Grid<Tile>,
+    /// Elevation level of every tile of the grid (see `elevation`).
+    #[serde(default)]
+    pub elevation: Grid<u8>,
+    /// Monsters placed in the rooms.
+    pub monsters: Vec<MonsterSpawn>,
+    /// Objects lying on the room floors (see `decorate`).
+    #[serde(default)]
+    pub decorations: Vec<Decoration>,
+    /// Stairs up to the floor above.
+    pub stairs_up: (i32, i32),
+    /// Stairs down to the floor below; `None` on the deepest floor.
+    pub stairs_down: Option<(i32, i32)>,
+}
+
+/// Number of floors, including the top floor, encoded by `nft_id`
+pub fn floor_count(nft_id: &str) -> usize {
+    (nft_id.chars().count().saturating_sub(4) / FLOOR_SEGMENT_LEN).clamp(1, MAX_FLOORS)
+}
+
+/// The ID a floor is excavated from: `nft_id` rotated to start at the floor's segment
+pub fn floor_nft_id(nft_id: &str, depth: usize) -> String {
+    let chars: Vec<char> = nft_id.chars().skip(4).collect();
+    let start = (depth * FLOOR_SEGMENT_LEN) % chars.len().max(1);
+    let rotated: String = chars[start..].iter().chain(&chars[..start]).collect();
+    format!("nft1{}", rotated)
+}
+
+/// Excavate the lower floors of `dungeon` and link every floor with stairs
+pub(crate) fn dig_floors(
+    dungeon: &mut Dungeon,
+    config: &GenerationConfig,
+    shapes: &ShapeRegistry,
+    observer: &mut dyn GenerationObserver,
+) -> Result<(), DungeonError> {
+    let lower = (1..floor_count(&dungeon.nft_id))
+        .map(|depth| excavate(&floor_nft_id(&dungeon.nft_id, depth), config, shapes, observer, depth))
+        .collect::<Result<Vec<_>, _>>()?;
+    stack_floors(dungeon, lower);
+    Ok(())
+}
+
+/// Link the excavated `lower` floors, from depth 1 down, below `dungeon` with stairs,
+/// and populate them
+pub(crate) fn stack_floors(dungeon: &mut Dungeon, lower: Vec<Dungeon>) {
+    if lower.is_empty() {
+        return;
+    }
+    dungeon.stairs_down = place_stairs(&mut dungeon.grid, &dungeon.rooms, RoomKind::Exit, Tile::StairsDown);
+
+    let count = 1 + lower.len();
+    for (depth, mut layout) in (1..count).zip(lower) {
+        layout.dungeon_type = dungeon.dungeon_type.clone();
+        layout.level = dungeon.level + depth as u64;
+        layout.monsters = spawn_monsters(&layout);
+
+        let stairs_up = place_stairs(&mut layout.grid, &layout.rooms, RoomKind::Entrance, Tile::StairsUp)
+            .unwrap_or(layout.rooms[0].center);
+        let stairs_down = if depth + 1 < count {
+            place_stairs(&mut layout.grid, &layout.rooms, RoomKind::Exit, Tile::StairsDown)
+        } else {
+            None
+        };
+        let decorations = place_decorations(&layout);
+
+        dungeon.floors.push(Floor {
+            depth,
+            level: layout.level,
+            rooms: layout.rooms,
+            tunnels: layout.tunnels,
+            corridor_width: layout.corridor_width,
+            bounds: layout.bounds,
+            area_size: layout.area_size,
+            excavated: layout.excavated,
+            grid: layout.grid,
+            elevation: layout.elevation,
+            monsters: layout.monsters,
+            decorations,
+            stairs_up,
+            stairs_down,
+        });
+    }
+}
+
+// Put stairs in the room of the given kind, on a walkable room tile next to its center
+// where possible so the center marker stays visible
+fn place_stairs(grid: &mut Grid<Tile>, rooms: &[Room], kind: RoomKind, stairs: Tile) -> Option<(i32, i32)> {
+    let room = rooms.iter().find(|room| room.kind == kind)?;
+    let pos = neighbors(room.center)
+        .into_iter()
+        .find(|pos| room.tiles.contains(pos) && grid.get(*pos) == Some(&Tile::Floor))
+        .unwrap_or(room.center);
+    grid.set(pos, stairs);
+    Some(pos)
+}
+
+impl Dungeon {
+    /// Number of floors, including the top floor.
+    pub fn floor_count(&self) -> usize {
+        1 + self.floors.len()
+    }
+
+    /// The floor at `depth` as a standalone `Dungeon`, for rendering one floor at a time.
+    ///
+    /// Depth 0 is the top floor. Returns `None` if the dungeon has no such floor.
+    pub fn floor_view(&self, depth: usize) -> Option<Dungeon> {
+        if depth == 0 {
+            return Some(Dungeon { floors: Vec::new(), ..self.clone() });
+        }
+        let floor = self.floors.get(depth - 1)?;
+        Some(Dungeon {
+            nft_id: self.nft_id.clone(),
+            launcher_id: self.launcher_id.clone(),
+            rooms: floor.rooms.clone(),
+            tunnels: floor.tunnels.clone(),
+            corridor_width: floor.corridor_width,
+            level: floor.level,
+            dungeon_type: self.dungeon_type.clone(),
+            biome: self.biome.clone(),
+            bounds: floor.bounds,
+            area_size: floor.area_size,
+            char_frequency: self.char_frequency.clone(),
+            most_frequent_char: self.most_frequent_char.clone(),
+            excavated: floor.excavated.clone(),
+            grid: floor.grid.clone(),
+            elevation: floor.elevation.clone(),
+            monsters: floor.monsters.clone(),
+            decorations: floor.decorations.clone(),
+            stairs_down: floor.stairs_down,
+            floors: Vec::new(),
+            provenance: self.provenance.clone(),
+            balance: self.balance.clone(),
+            collection_salt: self.collection_salt.clone(),
+            epoch: self.epoch,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::parse_nft_id;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    const DEEP_ID: &str = "nft1Hk3r9Vx2mQ7pL0aZ8cW4tN6yB1sF5dJqE2uR8wT4oP6iA0sD3fG7hJ9kL1zX5cV";
+
+    #[test]
+    fn every_full_segment_is_a_floor() {
+        assert_eq!(floor_count("nft1"), 1);
+        assert_eq!(floor_count(NFT_ID), 1);
+        assert_eq!(floor_count(DEEP_ID), 3);
+        assert_eq!(floor_count(&format!("nft1{}", "a".repeat(1000))), MAX_FLOORS);
+        assert_eq!(dungeon().floor_count(), 1);
+    }
+
+    #[test]
+    fn floor_ids_rotate_to_their_segment() {
+        assert_eq!(floor_nft_id(NFT_ID, 0), NFT_ID);
+        assert_eq!(floor_nft_id(NFT_ID, 1), "nft18cW4tN6yB1sF5dJHk3r9Vx2mQ7pL0aZ");
+        assert_eq!(floor_nft_id("nft1", 1), "nft1");
+    }
+
+    #[test]
+    fn stairs_link_consecutive_floors() {
+        let dungeon = parse_nft_id(DEEP_ID).unwrap();
+        assert_eq!(dungeon.floor_count(), 3);
+        let stairs_down = dungeon.stairs_down.unwrap();
+        assert_eq!(dungeon.grid.get(stairs_down), Some(&Tile::StairsDown));
+        for floor in &dungeon.floors {
+            assert_eq!(floor.level, (dungeon.level + floor.depth as u64));
+            assert_eq!(floor.grid.get(floor.stairs_up), Some(&Tile::StairsUp));
+            assert_eq!(floor.stairs_down.is_none(), floor.depth == 2);
+        }
+    }
+
+    #[test]
+    fn floor_views_stand_alone() {
+        let dungeon = parse_nft_id(DEEP_ID).unwrap();
+        assert_eq!(dungeon.floor_view(0).unwrap().floors, Vec::new());
+        let view = dungeon.floor_view(2).unwrap();
+        assert_eq!(view.rooms, dungeon.floors[1].rooms);
+        assert_eq!(view.level, dungeon.floors[1].level);
+        assert!(dungeon.floor_view(3).is_none());
+    }
+}
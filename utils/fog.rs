@@ -0,0 +1,159 @@
+//! # Fog of War
+//!
+//! Tracks which tiles a viewer has seen during exploration.
+//!
+//! - **Unexplored**: Never seen; renderers hide the tile entirely.
+//! - **Seen**: Seen before but not currently in view; renderers show it dimmed.
+//! - **Visible**: Within the sight radius and in line of sight right now.
+//!
+//! Line of sight follows a Bresenham line from the viewer; walls block sight but
+//! are themselves visible, so room outlines show up as they are approached. Water,
+//! lava, and sand block movement but can be seen across.
+
+use serde::{Deserialize, Serialize};
+use super::grid::{Grid, Tile};
+
+/// Visibility state of a single tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Visibility {
+    Unexplored,
+    Seen,
+    Visible,
+}
+
+/// Per-tile visibility for one viewer, covering the same area as the dungeon grid.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FogOfWar {
+    visibility: Grid<Visibility>,
+    sight_radius: u32,
+}
+
+impl FogOfWar {
+    /// Creates fully unexplored fog over `tiles` with the given sight radius.
+    pub fn new(tiles: &Grid<Tile>, sight_radius: u32) -> FogOfWar {
+        FogOfWar {
+            visibility: Grid::new(tiles.x_range(), tiles.y_range(), Visibility::Unexplored),
+            sight_radius,
+        }
+    }
+
+    /// Sight radius in tiles.
+    pub fn sight_radius(&self) -> u32 {
+        self.sight_radius
+    }
+
+    /// Visibility of `pos`; tiles outside the dungeon are always unexplored.
+    pub fn visibility(&self, pos: (i32, i32)) -> Visibility {
+        self.visibility.get(pos).copied().unwrap_or(Visibility::Unexplored)
+    }
+
+    /// Recomputes what a viewer at `viewer` can see.
+    /// Previously visible tiles are demoted to seen before the new view is marked.
+    pub fn update(&mut self, tiles: &Grid<Tile>, viewer: (i32, i32)) {
+        let (x_range, y_range) = (self.visibility.x_range(), self.visibility.y_range());
+        for x in x_range.0..=x_range.1 {
+            for y in y_range.0..=y_range.1 {
+                if self.visibility((x, y)) == Visibility::Visible {
+                    self.visibility.set((x, y), Visibility::Seen);
+                }
+            }
+        }
+
+        let radius = self.sight_radius as i32;
+        for x in (viewer.0 - radius)..=(viewer.0 + radius) {
+            for y in (viewer.1 - radius)..=(viewer.1 + radius) {
+                let (dx, dy) = (x - viewer.0, y - viewer.1);
+                if dx * dx + dy * dy <= radius * radius && has_line_of_sight(tiles, viewer, (x, y)) {
+                    self.visibility.set((x, y), Visibility::Visible);
+                }
+            }
+        }
+    }
+
+    /// Tiles seen so far, the visible ones included, row by row.
+    pub fn explored(&self) -> Vec<(i32, i32)> {
+        self.visibility.iter().filter(|&(_, &visibility)| visibility != Visibility::Unexplored).map(|(pos, _)| pos).collect()
+    }
+
+    /// Marks unexplored `tiles` as seen, restoring the fog of a saved run; tiles outside
+    /// the dungeon are ignored.
+    pub fn reveal(&mut self, tiles: &[(i32, i32)]) {
+        for &pos in tiles {
+            if self.visibility(pos) == Visibility::Unexplored {
+                self.visibility.set(pos, Visibility::Seen);
+            }
+        }
+    }
+}
+
+/// Whether `to` can be seen from `from`: no tile strictly between them may block sight
+pub fn has_line_of_sight(tiles: &Grid<Tile>, from: (i32, i32), to: (i32, i32)) -> bool {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut err = dx + dy;
+
+    // Bresenham line walk, stopping before the target tile
+    loop {
+        if (x, y) == to {
+            return true;
+        }
+        if (x, y) != from && tiles.get((x, y)).is_none_or(|tile| tile.blocks_sight()) {
+            return false;
+        }
+        let doubled = 2 * err;
+        if doubled >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 7x3 floor room split by a wall at x = 3, except on the bottom row
+    fn room() -> Grid<Tile> {
+        let mut grid = Grid::new((0, 6), (0, 2), Tile::Floor);
+        grid.set((3, 0), Tile::Wall);
+        grid.set((3, 1), Tile::Wall);
+        grid
+    }
+
+    #[test]
+    fn walls_block_sight_but_are_visible() {
+        let grid = room();
+        assert!(has_line_of_sight(&grid, (0, 0), (2, 0)));
+        assert!(has_line_of_sight(&grid, (0, 0), (3, 0)));
+        assert!(!has_line_of_sight(&grid, (0, 0), (5, 0)));
+        assert!(has_line_of_sight(&grid, (0, 2), (6, 2)));
+    }
+
+    #[test]
+    fn fog_starts_unexplored() {
+        let fog = FogOfWar::new(&room(), 4);
+        assert_eq!(fog.sight_radius(), 4);
+        assert_eq!(fog.visibility((0, 0)), Visibility::Unexplored);
+        assert_eq!(fog.visibility((-1, 0)), Visibility::Unexplored);
+    }
+
+    #[test]
+    fn tiles_out_of_view_fade_to_seen() {
+        let grid = room();
+        let mut fog = FogOfWar::new(&grid, 2);
+        fog.update(&grid, (0, 1));
+        assert_eq!(fog.visibility((0, 1)), Visibility::Visible);
+        assert_eq!(fog.visibility((2, 1)), Visibility::Visible);
+        assert_eq!(fog.visibility((3, 2)), Visibility::Unexplored);
+
+        fog.update(&grid, (5, 2));
+        assert_eq!(fog.visibility((0, 1)), Visibility::Seen);
+        assert_eq!(fog.visibility((3, 2)), Visibility::Visible);
+        assert_eq!(fog.visibility((5, 0)), Visibility::Visible);
+    }
+}
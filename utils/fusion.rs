@@ -0,0 +1,204 @@
+//! # Dungeon Fusion
+//!
+//! `Dungeon::merge` combines two parsed dungeons into one, for NFT fusion mechanics.
+//!
+//! - **Placement**: The other dungeon is shifted next to this one according to a
+//!   `MergeStrategy`: to the right, above, or overlaid on the same coordinates.
+//! - **Connection**: A tunnel of the wider corridor width runs from this dungeon's exit
+//!   room to the other's entrance room, which both become ordinary rooms, so the fused
+//!   dungeon keeps a single entrance first and a single exit last.
+//! - **Attributes**: The fused `nft_id` joins both IDs with a '+'; the letter frequencies,
+//!   dungeon type, area, and level (with the default `GenerationConfig::level_band`) are
+//!   recomputed from both halves, and repeated room names are numbered. The elevation
+//!   is raised anew over the fused grid (see `elevation`).
+//!
+//! Only the top floors are fused: this dungeon's lower floors and stairs down are kept,
+//! and the other's stairs down become floor.
+
+use serde::{Deserialize, Serialize};
+use super::analysis::letter_frequency;
+use super::biome::classify;
+use super::config::GenerationConfig;
+use super::decorate::Decoration;
+use super::dungeon::{Bounds, Dungeon, Room, RoomKind};
+use super::elevation::raise_terrain;
+use super::excavator::{corridor_brush, create_tunnel, get_dungeon_level, get_dungeon_type};
+use super::grid::{Grid, Tile};
+use super::naming::unique_names;
+use super::seed::{rng_for_stream, salt_nft_id};
+use super::spawn::MonsterSpawn;
+
+/// Tiles of rock left between dungeons placed side by side or stacked
+const FUSION_GAP: i32 = 4;
+
+/// Where `Dungeon::merge` places the other dungeon relative to this one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum MergeStrategy {
+    /// To the right, bottom edges aligned.
+    #[default]
+    SideBySide,
+    /// Above, left edges aligned.
+    Stacked,
+    /// On the same coordinates; where the dungeons overlap, the walkable tile wins.
+    Overlay,
+}
+
+impl MergeStrategy {
+    /// Offset shifting a dungeon covered by `other` into place next to `this`
+    pub fn offset(self, this: &Grid<Tile>, other: &Grid<Tile>) -> (i32, i32) {
+        match self {
+            MergeStrategy::SideBySide => (this.x_range().1 - other.x_range().0 + 1 + FUSION_GAP, this.y_range().0 - other.y_range().0),
+            MergeStrategy::Stacked => (this.x_range().0 - other.x_range().0, this.y_range().1 - other.y_range().0 + 1 + FUSION_GAP),
+            MergeStrategy::Overlay => (0, 0),
+        }
+    }
+}
+
+impl Dungeon {
+    /// Fuses `other` into a copy of this dungeon, placed according to `strategy`.
+    pub fn merge(&self, other: &Dungeon, strategy: MergeStrategy) -> Dungeon {
+        let (dx, dy) = strategy.offset(&self.grid, &other.grid);
+        let shift = |(x, y): (i32, i32)| (x + dx, y + dy);
+
+        // This dungeon's exit and the other's entrance become ordinary rooms
+        let mut rooms = self.rooms.clone();
+        rooms.extend(other.rooms.iter().map(|room| Room {
+            center: shift(room.center),
+            tiles: room.tiles.iter().map(|&pos| shift(pos)).collect(),
+            ..room.clone()
+        }));
+        for room in rooms.iter_mut().skip(self.rooms.len().saturating_sub(1)).take(2) {
+            room.kind = RoomKind::Normal;
+        }
+        let names = unique_names(rooms.iter().map(|room| room.name.clone()));
+        for (room, name) in rooms.iter_mut().zip(names) {
+            room.name = name;
+        }
+
+        // Tunnel from this dungeon's exit room to the other's entrance room
+        let corridor_width = self.corridor_width.max(other.corridor_width);
+        let link = match (self.rooms.last(), other.rooms.first()) {
+            (Some(exit), Some(entrance)) => create_tunnel(exit.center, shift(entrance.center)),
+            _ => Vec::new(),
+        };
+
+        // Grid covering both dungeons, the other's tiles winning only over rock and terrain
+        let (other_x, other_y) = (other.grid.x_range(), other.grid.y_range());
+        let x_range = (self.grid.x_range().0.min(other_x.0 + dx), self.grid.x_range().1.max(other_x.1 + dx));
+        let y_range = (self.grid.y_range().0.min(other_y.0 + dy), self.grid.y_range().1.max(other_y.1 + dy));
+        let mut grid = Grid::new(x_range, y_range, Tile::Wall);
+        for (pos, &tile) in self.grid.iter().filter(|&(_, &tile)| tile != Tile::Wall) {
+            grid.set(pos, tile);
+        }
+        for (pos, &tile) in other.grid.iter().filter(|&(_, &tile)| tile != Tile::Wall) {
+            let pos = shift(pos);
+            if grid.get(pos).is_some_and(|current| !current.is_walkable()) {
+                grid.set(pos, if tile == Tile::StairsDown { Tile::Floor } else { tile });
+            }
+        }
+        let link_tiles: Vec<(i32, i32)> = link.iter().flat_map(|&pos| corridor_brush(pos, corridor_width)).collect();
+        for &pos in &link_tiles {
+            if grid.get(pos).is_some_and(|current| !current.is_walkable()) {
+                grid.set(pos, Tile::Tunnel);
+            }
+        }
+        for room in &rooms {
+            grid.set(room.center, Tile::RoomCenter);
+        }
+
+        let mut tunnels = self.tunnels.clone();
+        tunnels.extend(other.tunnels.iter().map(|tunnel| tunnel.iter().map(|&pos| shift(pos)).collect()));
+        tunnels.push(link);
+
+        let mut excavated = self.excavated.clone();
+        excavated.extend(other.excavated.iter().map(|&pos| shift(pos)));
+        excavated.extend(link_tiles.iter().copied().filter(|pos| grid.contains(*pos)));
+
+        let mut monsters = self.monsters.clone();
+        monsters.extend(other.monsters.iter().map(|monster| MonsterSpawn { position: shift(monster.position), ..monster.clone() }));
+
+        let mut decorations = self.decorations.clone();
+        decorations.extend(other.decorations.iter().map(|decoration| Decoration { position: shift(decoration.position), ..*decoration }));
+
+        let nft_id = format!("{}+{}", self.nft_id, other.nft_id);
+        let seed_id = salt_nft_id(&nft_id, self.collection_salt.as_deref());
+        let (char_frequency, most_frequent_char) = letter_frequency(&seed_id);
+        let area_size = self.area_size.saturating_add(other.area_size);
+        let (bounds_x, bounds_y) = (other.bounds.x_range, other.bounds.y_range);
+        let dungeon_type = get_dungeon_type(&most_frequent_char);
+        let elevation = raise_terrain(&grid, &dungeon_type, &mut rng_for_stream(&seed_id, "elevation"));
+
+        Dungeon {
+            launcher_id: None,
+            rooms,
+            tunnels,
+            corridor_width,
+            level: get_dungeon_level(area_size, GenerationConfig::default().level_band),
+            dungeon_type,
+            biome: classify(&seed_id),
+            bounds: Bounds {
+                x_range: (self.bounds.x_range.0.min(bounds_x.0 + dx), self.bounds.x_range.1.max(bounds_x.1 + dx)),
+                y_range: (self.bounds.y_range.0.min(bounds_y.0 + dy), self.bounds.y_range.1.max(bounds_y.1 + dy)),
+            },
+            area_size,
+            char_frequency,
+            most_frequent_char,
+            excavated,
+            grid,
+            elevation,
+            monsters,
+            decorations,
+            stairs_down: self.stairs_down,
+            floors: self.floors.clone(),
+            provenance: None,
+            balance: self.balance.clone(),
+            collection_salt: self.collection_salt.clone(),
+            epoch: self.epoch,
+            nft_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::parse_nft_id;
+    use super::super::fixtures::dungeon;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::super::validate::LintWarning;
+    use super::*;
+
+    // A second dungeon to fuse with the fixture
+    fn other() -> Dungeon {
+        parse_nft_id(&generate_nft_id_with(&GenerateOptions { seed: Some(5), ..GenerateOptions::default() }).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn fused_dungeons_keep_one_entrance_and_one_exit() {
+        let (this, other) = (dungeon(), other());
+        for strategy in [MergeStrategy::SideBySide, MergeStrategy::Stacked, MergeStrategy::Overlay] {
+            let fused = this.merge(&other, strategy);
+            assert_eq!(fused.rooms.len(), this.rooms.len() + other.rooms.len());
+            let kinds: Vec<RoomKind> = fused.rooms.iter().map(|room| room.kind).collect();
+            assert_eq!(kinds.iter().filter(|&&kind| kind == RoomKind::Entrance).count(), 1);
+            assert_eq!(kinds.iter().filter(|&&kind| kind == RoomKind::Exit).count(), 1);
+            assert_eq!((kinds[0], kinds[kinds.len() - 1]), (RoomKind::Entrance, RoomKind::Exit));
+            assert!(fused.is_fully_connected(), "{:?}", strategy);
+            assert!(fused.is_solvable(), "{:?}", strategy);
+            assert!(!fused.validate().iter().any(|warning| matches!(warning, LintWarning::DisconnectedRoom { .. } | LintWarning::RoomOutOfBounds { .. })));
+        }
+    }
+
+    #[test]
+    fn side_by_side_dungeons_do_not_overlap() {
+        let (this, other) = (dungeon(), other());
+        let fused = this.merge(&other, MergeStrategy::SideBySide);
+        let right_of_this = this.grid.x_range().1 + FUSION_GAP;
+        assert!(fused.rooms[this.rooms.len()..].iter().all(|room| room.tiles.iter().all(|&(x, _)| x > right_of_this)));
+        assert_eq!(fused.nft_id, format!("{}+{}", this.nft_id, other.nft_id));
+        assert_eq!(fused.area_size, this.area_size + other.area_size);
+        assert!(fused.level >= this.level.max(other.level));
+        assert_eq!(fused.monsters.len(), this.monsters.len() + other.monsters.len());
+        let names: std::collections::HashSet<&str> = fused.rooms.iter().map(|room| room.name.as_str()).collect();
+        assert_eq!(names.len(), fused.rooms.len());
+    }
+}
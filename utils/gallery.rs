@@ -0,0 +1,134 @@
+//! # Shape Gallery
+//!
+//! Renders every shape character into one contact-sheet PNG, so collection designers can
+//! see what each NFT character contributes to layouts.
+//!
+//! - **Cells**: One per shape character, in base62 order (0-9, a-z, A-Z),
+//!   `GALLERY_COLUMNS` to a row, captioned with the character.
+//! - **Sizes**: Each cell shows the room at every size of `GalleryOptions::sizes` side by
+//!   side. Every room is drawn on the same scale, so sizes and shapes compare at a glance.
+//! - **Style**: Rooms expand by `GalleryOptions::style`; filled, as `parse_nft_id` digs
+//!   them, by default.
+//!
+//! Shapes come from a `ShapeRegistry`, so registered shapes preview like the built-in
+//! ones. The room center is marked in the entrance color of the map renderers.
+
+use std::path::Path;
+use plotters::prelude::*;
+use super::dungeon::RoomKind;
+use super::plot::{rgb, room_kind_color, text_color};
+use super::shapes::{ShapeRegistry, ShapeStyle};
+use super::theme::Theme;
+
+/// Shape cells per row of the gallery
+pub const GALLERY_COLUMNS: usize = 8;
+/// Width and height in pixels of the room drawn at each size
+const PANEL_SIZE: u32 = 64;
+/// Height in pixels of a cell's caption
+const CAPTION_HEIGHT: u32 = 18;
+/// Height in pixels of the gallery title
+const TITLE_HEIGHT: u32 = 36;
+
+/// What the shape gallery shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GalleryOptions {
+    /// Room sizes drawn for every shape, left to right.
+    pub sizes: Vec<u32>,
+    /// How shapes are expanded by the size.
+    pub style: ShapeStyle,
+}
+
+impl Default for GalleryOptions {
+    fn default() -> GalleryOptions {
+        GalleryOptions { sizes: vec![1, 2, 3], style: ShapeStyle::Filled }
+    }
+}
+
+/// Every shape character, in base62 order
+pub fn shape_chars() -> impl Iterator<Item = char> {
+    ('0'..='9').chain('a'..='z').chain('A'..='Z')
+}
+
+/// Width and height in pixels of a gallery of `sizes` sizes
+pub fn gallery_size(sizes: usize) -> (u32, u32) {
+    let rows = shape_chars().count().div_ceil(GALLERY_COLUMNS) as u32;
+    (GALLERY_COLUMNS as u32 * sizes as u32 * PANEL_SIZE, TITLE_HEIGHT + rows * (CAPTION_HEIGHT + PANEL_SIZE))
+}
+
+/// Render every shape of `registry` at the sizes of `options` into a PNG contact sheet
+/// saved to `out_path`
+pub fn render_shape_gallery(registry: &ShapeRegistry, out_path: &Path, options: &GalleryOptions) -> Result<(), Box<dyn std::error::Error>> {
+    if options.sizes.is_empty() {
+        return Err("the shape gallery needs at least one room size".into());
+    }
+    let rooms: Vec<_> = shape_chars()
+        .map(|shape| (shape, options.sizes.iter().map(|&size| registry.room_offsets(size, &shape.to_string(), options.style)).collect::<Vec<_>>()))
+        .collect();
+    // Tiles from the center to the furthest offset of any room, shared by every panel
+    let reach = rooms.iter().flat_map(|(_, sizes)| sizes.iter().flatten()).map(|&(x, y)| x.abs().max(y.abs())).max().unwrap_or(0);
+    let tile = (PANEL_SIZE - 4) as f64 / (2 * reach + 1) as f64;
+
+    let theme = Theme::classic();
+    let text = text_color(theme.background);
+    let root = BitMapBackend::new(out_path, gallery_size(options.sizes.len())).into_drawing_area();
+    root.fill(&rgb(theme.background))?;
+    let title = format!("Room shapes at size {}", options.sizes.iter().map(u32::to_string).collect::<Vec<_>>().join(", "));
+    let cells = root.titled(&title, ("sans-serif", 22).into_font().color(&text))?;
+    let rows = rooms.len().div_ceil(GALLERY_COLUMNS);
+
+    for ((shape, sizes), cell) in rooms.iter().zip(cells.split_evenly((rows, GALLERY_COLUMNS))) {
+        let panels = cell.titled(&shape.to_string(), ("monospace", 14).into_font().color(&text))?;
+        for (offsets, panel) in sizes.iter().zip(panels.split_evenly((1, sizes.len()))) {
+            let (width, height) = panel.dim_in_pixel();
+            let center = (width as f64 / 2.0, height as f64 / 2.0);
+            // Up is increasing y, as on the maps
+            let corner = |(x, y): (i32, i32)| ((center.0 + (x as f64 - 0.5) * tile).round() as i32, (center.1 - (y as f64 + 0.5) * tile).round() as i32);
+            let square = |offset: (i32, i32), color: RGBColor| {
+                let (left, top) = corner(offset);
+                let (right, bottom) = corner((offset.0 + 1, offset.1 - 1));
+                Rectangle::new([(left, top), ((right - 1).max(left), (bottom - 1).max(top))], color.filled())
+            };
+            panel.draw(&Rectangle::new([(1, 1), (width as i32 - 2, height as i32 - 2)], text.mix(0.15)))?;
+            for &offset in offsets {
+                panel.draw(&square(offset, rgb(theme.floor)))?;
+            }
+            panel.draw(&square((0, 0), room_kind_color(RoomKind::Entrance)))?;
+        }
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use super::*;
+
+    #[test]
+    fn every_shape_character_has_a_cell() {
+        assert_eq!(shape_chars().count(), 62);
+        assert_eq!(shape_chars().nth(10), Some('a'));
+        assert_eq!(shape_chars().last(), Some('Z'));
+        // 62 shapes fill eight rows of eight cells
+        assert_eq!(gallery_size(3), (8 * 3 * PANEL_SIZE, TITLE_HEIGHT + 8 * (CAPTION_HEIGHT + PANEL_SIZE)));
+    }
+
+    #[test]
+    fn galleries_render_as_png_contact_sheets() {
+        let path = env::temp_dir().join(format!("chia_dungeon_gallery_{}.png", std::process::id()));
+        let mut registry = ShapeRegistry::new();
+        registry.register_fn('0', |size| (0..size as i32 * 4).map(|x| (x, 0)).collect());
+        let options = GalleryOptions { sizes: vec![1, 4], style: ShapeStyle::Scaled };
+        render_shape_gallery(&registry, &path, &options).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(bytes.starts_with(b"\x89PNG"));
+        // The IHDR chunk holds the big-endian width and height
+        let (width, height) = gallery_size(2);
+        assert_eq!(&bytes[16..24], [width.to_be_bytes(), height.to_be_bytes()].concat().as_slice());
+
+        let empty = GalleryOptions { sizes: Vec::new(), ..GalleryOptions::default() };
+        assert!(render_shape_gallery(&registry, &path, &empty).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! # Godot Extension
+//!
+//! A GDExtension class for Godot 4 games that generate NFT dungeons natively, registered
+//! with `gdext` behind the `godot` feature. Build the library with
+//! `cargo rustc --release --lib --features godot --crate-type cdylib`, and point a
+//! `.gdextension` file's `entry_symbol` at `gdext_rust_init`.
+//!
+//! - **Parsing**: `ChiaDungeon.parse(nft_id)` decodes an NFT ID, and
+//!   `ChiaDungeon.generate(seed)` a synthetic one; both return `null` and push an error
+//!   to the editor when the ID is invalid.
+//! - **Dictionaries**: `to_dictionary` returns the dungeon as nested `Dictionary` and
+//!   `Array` values, the same document as `Dungeon::to_json`.
+//! - **Tile maps**: `terrain_cells` and `overlay_cells` return the `PackedInt32Array`
+//!   layouts of `godot` (`pack_cells`), for `TileMapLayer.set_cell` with the atlas
+//!   source `ChiaDungeon.ATLAS_SOURCE`.
+//!
+//! ```gdscript
+//! var dungeon := ChiaDungeon.parse(nft_id)
+//! var cells := dungeon.terrain_cells()
+//! for i in range(0, cells.size(), 4):
+//!     $Terrain.set_cell(Vector2i(cells[i], cells[i + 1]), ChiaDungeon.ATLAS_SOURCE, Vector2i(cells[i + 2], cells[i + 3]))
+//! ```
+
+use godot::prelude::*;
+use serde_json::{Map, Value};
+use super::dungeon::Dungeon;
+use super::excavator;
+use super::generator::generate_nft_id_from_seed;
+use super::godot::{overlay_cells, pack_cells, terrain_cells, GODOT_ATLAS_SOURCE};
+
+// Entry point of the extension library
+struct ChiaDungeonExtension;
+
+#[gdextension]
+unsafe impl ExtensionLibrary for ChiaDungeonExtension {}
+
+/// A parsed dungeon, as a Godot object.
+#[derive(GodotClass)]
+#[class(no_init, base = RefCounted)]
+pub struct ChiaDungeon {
+    dungeon: Dungeon,
+}
+
+#[godot_api]
+impl ChiaDungeon {
+    /// Source ID of the sprite atlas in the `TileSet` of the layers
+    #[constant]
+    const ATLAS_SOURCE: i32 = GODOT_ATLAS_SOURCE;
+
+    /// Decode the NFT ID, or `null` if it is invalid
+    #[func]
+    fn parse(nft_id: GString) -> Option<Gd<ChiaDungeon>> {
+        match excavator::parse_nft_id(&nft_id.to_string()) {
+            Ok(dungeon) => Some(Gd::from_object(ChiaDungeon { dungeon })),
+            Err(err) => {
+                godot_error!("Error parsing NFT ID: {}", err);
+                None
+            }
+        }
+    }
+
+    /// Decode the synthetic NFT ID generated from `seed`
+    #[func]
+    fn generate(seed: i64) -> Option<Gd<ChiaDungeon>> {
+        ChiaDungeon::parse(generate_nft_id_from_seed(seed as u64).as_str().into())
+    }
+
+    /// NFT ID the dungeon was decoded from
+    #[func]
+    fn nft_id(&self) -> GString {
+        self.dungeon.nft_id.as_str().into()
+    }
+
+    /// The dungeon as nested dictionaries and arrays, like its JSON document
+    #[func]
+    fn to_dictionary(&self) -> VarDictionary {
+        match serde_json::to_value(&self.dungeon) {
+            Ok(Value::Object(fields)) => json_dictionary(&fields),
+            _ => VarDictionary::new(),
+        }
+    }
+
+    /// Terrain cells as `[x, y, atlas_x, atlas_y, ...]`
+    #[func]
+    fn terrain_cells(&self) -> PackedInt32Array {
+        PackedInt32Array::from(pack_cells(&terrain_cells(&self.dungeon)))
+    }
+
+    /// Overlay cells as `[x, y, atlas_x, atlas_y, ...]`
+    #[func]
+    fn overlay_cells(&self) -> PackedInt32Array {
+        PackedInt32Array::from(pack_cells(&overlay_cells(&self.dungeon)))
+    }
+}
+
+// Godot value of a JSON value: objects become dictionaries, and arrays arrays
+fn json_variant(value: &Value) -> Variant {
+    match value {
+        Value::Null => Variant::nil(),
+        Value::Bool(value) => value.to_variant(),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => value.to_variant(),
+            None => number.as_f64().unwrap_or_default().to_variant(),
+        },
+        Value::String(value) => value.to_variant(),
+        Value::Array(values) => values.iter().map(json_variant).collect::<VarArray>().to_variant(),
+        Value::Object(fields) => json_dictionary(fields).to_variant(),
+    }
+}
+
+// Godot dictionary of the fields of a JSON object
+fn json_dictionary(fields: &Map<String, Value>) -> VarDictionary {
+    let mut dict = VarDictionary::new();
+    for (key, value) in fields {
+        dict.set(&key.to_variant(), &json_variant(value));
+    }
+    dict
+}
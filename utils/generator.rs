@@ -0,0 +1,194 @@
+//! # NFT ID Generator
+//!
+//! Produces synthetic NFT IDs in the "nft1" + 58 base62 characters format
+//! understood by `parse_nft_id`.
+//!
+//! IDs are drawn from a seeded `StdRng`, so `generate_nft_id_from_seed` is
+//! reproducible. `generate_nft_id` seeds it from the OS RNG, or from
+//! `Math.random` in WebAssembly builds with the `wasm` feature, where no OS RNG
+//! is available.
+//!
+//! `generate_nft_id_with` builds IDs meeting `GenerateOptions` for curated collections.
+//! Each candidate starts as a random ID and is then shaped to the constraints:
+//! - **Rooms**: The room-count character is set to a count inside the range.
+//! - **Level**: Room-size characters are raised until the room area reaches the level.
+//! - **Type**: Characters between the room count and the room sizes are overwritten with
+//!   the type's letter until it is the most frequent one.
+//!
+//! Candidates are checked by decoding them with `parse_nft_id`; constraints no ID can meet
+//! (an unknown type, a level above what the room range allows) yield `None`.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use super::config::GenerationConfig;
+use super::excavator::{char_to_num, decode_dungeon_type, decode_room_sizes, dungeon_type_letter, get_dungeon_level, parse_nft_id};
+
+/// Candidate IDs tried by `generate_nft_id_with` before giving up
+pub const MAX_CONSTRAINED_ATTEMPTS: usize = 1000;
+
+/// Constraints on the dungeon decoded from an ID built by `generate_nft_id_with`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GenerateOptions {
+    /// Dungeon type, such as "Cave" or "Hellforge", matched ignoring case.
+    pub dungeon_type: Option<String>,
+    /// Lowest dungeon level.
+    pub min_level: Option<u64>,
+    /// Fewest rooms on the top floor.
+    pub min_rooms: Option<usize>,
+    /// Most rooms on the top floor.
+    pub max_rooms: Option<usize>,
+    /// Seed making the result reproducible; drawn like `generate_nft_id` when `None`.
+    pub seed: Option<u64>,
+}
+
+/// Generate a random synthetic NFT ID
+pub fn generate_nft_id() -> String {
+    generate_nft_id_from_seed(entropy_seed())
+}
+
+/// Generate the synthetic NFT ID for `seed`; the same seed always yields the same ID
+pub fn generate_nft_id_from_seed(seed: u64) -> String {
+    random_nft_id(&mut StdRng::seed_from_u64(seed))
+}
+
+/// Generate a synthetic NFT ID whose dungeon meets the `options`
+/// Returns `None` when no ID meeting them is found within `MAX_CONSTRAINED_ATTEMPTS` candidates.
+pub fn generate_nft_id_with(options: &GenerateOptions) -> Option<String> {
+    let config = GenerationConfig::default();
+    let letter = match &options.dungeon_type {
+        Some(dungeon_type) => Some(dungeon_type_letter(dungeon_type)?),
+        None => None,
+    };
+    let rooms = (
+        options.min_rooms.unwrap_or(config.min_rooms).max(config.min_rooms),
+        options.max_rooms.unwrap_or(config.max_rooms).min(config.max_rooms),
+    );
+    if rooms.0 > rooms.1 {
+        return None;
+    }
+
+    let mut rng = StdRng::seed_from_u64(options.seed.unwrap_or_else(entropy_seed));
+    (0..MAX_CONSTRAINED_ATTEMPTS).find_map(|_| {
+        let nft_id = constrained_candidate(&mut rng, &config, options, letter, rooms)?;
+        let dungeon = parse_nft_id(&nft_id).ok()?;
+        let meets = options.dungeon_type.as_ref().is_none_or(|dungeon_type| dungeon.dungeon_type.eq_ignore_ascii_case(dungeon_type))
+            && options.min_level.is_none_or(|min_level| dungeon.level >= min_level)
+            && (rooms.0..=rooms.1).contains(&dungeon.rooms.len());
+        meets.then_some(nft_id)
+    })
+}
+
+// A random ID shaped towards the options; `None` when this candidate cannot meet them
+fn constrained_candidate(
+    rng: &mut StdRng,
+    config: &GenerationConfig,
+    options: &GenerateOptions,
+    letter: Option<char>,
+    (min_rooms, max_rooms): (usize, usize),
+) -> Option<String> {
+    let mut chars: Vec<char> = random_nft_id(rng).chars().collect();
+
+    // The room-count character's value is the number of rooms above `config.min_rooms`
+    let num_rooms = rng.gen_range(min_rooms..=max_rooms);
+    chars[4] = base62_char((num_rooms - config.min_rooms) as u32);
+    let size_start = chars.len() - num_rooms;
+
+    // Raise random room sizes until the area reaches the level
+    if let Some(min_level) = options.min_level {
+        while get_dungeon_level(decode_room_sizes(&chars, num_rooms, config).1, config.level_band) < min_level {
+            let raisable: Vec<usize> = (size_start..chars.len()).filter(|&i| char_to_num(chars[i]) < 61).collect();
+            let &index = raisable.choose(rng)?;
+            chars[index] = base62_char(rng.gen_range(char_to_num(chars[index]) as u32 + 1..62));
+        }
+    }
+
+    // Spread the type's letter over the characters that do not decode room sizes
+    if let (Some(letter), Some(dungeon_type)) = (letter, &options.dungeon_type) {
+        while !decode_dungeon_type(&chars.iter().collect::<String>()).eq_ignore_ascii_case(dungeon_type) {
+            let others: Vec<usize> = (5..size_start).filter(|&i| chars[i] != letter).collect();
+            chars[*others.choose(rng)?] = letter;
+        }
+    }
+
+    Some(chars.into_iter().collect())
+}
+
+// "nft1" followed by 58 random base62 characters
+fn random_nft_id(rng: &mut impl Rng) -> String {
+    let mut nft_id = String::from("nft1");
+
+    // Generate the remaining 58 random characters
+    for _ in 0..58 {
+        nft_id.push(base62_char(rng.gen_range(0..62))); // 0-61 for base62
+    }
+    nft_id
+}
+
+// The character of a base62 digit, the inverse of `char_to_num`
+fn base62_char(value: u32) -> char {
+    match value {
+        0..=9 => (b'0' + value as u8) as char,      // Numbers '0'-'9'
+        10..=35 => (b'a' + (value - 10) as u8) as char, // Lowercase letters 'a'-'z'
+        36..=61 => (b'A' + (value - 36) as u8) as char, // Uppercase letters 'A'-'Z'
+        _ => unreachable!(), // Should never reach here
+    }
+}
+
+// Seed for `generate_nft_id` from the OS RNG
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+fn entropy_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+// Seed for `generate_nft_id` from two `Math.random` draws of 32 bits each
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn entropy_seed() -> u64 {
+    let draw = || (js_sys::Math::random() * 4_294_967_296.0) as u64;
+    (draw() << 32) | draw()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::parse_nft_id;
+    use super::*;
+
+    #[test]
+    fn generated_ids_are_nft1_and_58_base62_characters() {
+        let nft_id = generate_nft_id();
+        assert!(nft_id.starts_with("nft1"));
+        assert_eq!(nft_id.len(), 62);
+        assert!(nft_id[4..].chars().all(|c| c.is_ascii_alphanumeric()));
+        assert!(parse_nft_id(&nft_id).is_ok());
+    }
+
+    #[test]
+    fn constrained_ids_meet_every_constraint() {
+        let options = GenerateOptions {
+            dungeon_type: Some("hellforge".to_string()),
+            min_level: Some(12),
+            min_rooms: Some(30),
+            max_rooms: Some(34),
+            seed: Some(7),
+        };
+        let nft_id = generate_nft_id_with(&options).unwrap();
+        let dungeon = parse_nft_id(&nft_id).unwrap();
+        assert_eq!(dungeon.dungeon_type, "Hellforge");
+        assert!(dungeon.level >= 12);
+        assert!((30..=34).contains(&dungeon.rooms.len()));
+        assert_eq!(nft_id.len(), 62);
+        // The same seed always builds the same ID
+        assert_eq!(generate_nft_id_with(&options).unwrap(), nft_id);
+    }
+
+    #[test]
+    fn unsatisfiable_constraints_yield_none() {
+        let unknown_type = GenerateOptions { dungeon_type: Some("Moon Base".to_string()), ..GenerateOptions::default() };
+        assert_eq!(generate_nft_id_with(&unknown_type), None);
+        let empty_range = GenerateOptions { min_rooms: Some(10), max_rooms: Some(5), ..GenerateOptions::default() };
+        assert_eq!(generate_nft_id_with(&empty_range), None);
+        // Few small rooms can never cover the area of a high level
+        let too_deep = GenerateOptions { min_level: Some(10), max_rooms: Some(3), seed: Some(1), ..GenerateOptions::default() };
+        assert_eq!(generate_nft_id_with(&too_deep), None);
+    }
+}
@@ -0,0 +1,100 @@
+//! # Godot Layouts
+//!
+//! Dungeon maps in Godot's conventions, so Godot games can build their levels from a
+//! parsed dungeon without an intermediate file format.
+//!
+//! - **Coordinates**: Godot's 2D y axis points down, so `godot_coords` negates y and
+//!   keeps x; the map keeps its north up on screen.
+//! - **Terrain layer**: `terrain_cells` lists one `GodotCell` per excavated tile and per
+//!   wall bordering one, like the sprite map (see `sprites`), for
+//!   `TileMapLayer.set_cell(coords, GODOT_ATLAS_SOURCE, atlas_coords)` on a `TileSet`
+//!   whose atlas source is the sprite atlas (`sprites::DEFAULT_TILESET`). A sprite's
+//!   atlas coordinates are `(Sprite::index, 0)`.
+//! - **Overlay layer**: `overlay_cells` places the special room, trap, and monster
+//!   sprites drawn over the terrain (see `sprites::overlays`), for a second
+//!   `TileMapLayer` above the first.
+//! - **Packed arrays**: `pack_cells` flattens cells into `[x, y, atlas_x, atlas_y, ...]`,
+//!   the layout of a `PackedInt32Array` read four values at a time.
+//!
+//! The `ChiaDungeon` class of the `godot` feature hands these layouts to GDScript (see
+//! `gdext`).
+
+use super::dungeon::Dungeon;
+use super::grid::Tile;
+use super::sprites::{borders_excavated, overlays, Sprite};
+
+/// Source ID of the sprite atlas in the `TileSet` of the layers
+pub const GODOT_ATLAS_SOURCE: i32 = 0;
+
+/// A cell of a Godot `TileMapLayer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GodotCell {
+    /// Cell coordinates, y pointing down (see `godot_coords`).
+    pub coords: (i32, i32),
+    /// Coordinates of the cell's sprite in the atlas.
+    pub atlas_coords: (i32, i32),
+}
+
+impl GodotCell {
+    /// The cell showing `sprite` at the dungeon position `pos`.
+    pub fn new(pos: (i32, i32), sprite: Sprite) -> GodotCell {
+        GodotCell { coords: godot_coords(pos), atlas_coords: (sprite.index() as i32, 0) }
+    }
+}
+
+/// Godot cell coordinates of a dungeon position
+pub fn godot_coords((x, y): (i32, i32)) -> (i32, i32) {
+    (x, -y)
+}
+
+/// Terrain cells of the dungeon: every excavated tile, and every wall bordering one
+pub fn terrain_cells(dungeon: &Dungeon) -> Vec<GodotCell> {
+    dungeon
+        .grid
+        .iter()
+        .filter(|&(pos, &tile)| tile != Tile::Wall || borders_excavated(dungeon, pos))
+        .map(|(pos, &tile)| GodotCell::new(pos, Sprite::for_tile(tile)))
+        .collect()
+}
+
+/// Overlay cells of the dungeon: special rooms and traps, then monsters, later cells
+/// replacing earlier ones on the same tile
+pub fn overlay_cells(dungeon: &Dungeon) -> Vec<GodotCell> {
+    overlays(dungeon).into_iter().map(|(pos, sprite)| GodotCell::new(pos, sprite)).collect()
+}
+
+/// Cells flattened into `[x, y, atlas_x, atlas_y, ...]`
+pub fn pack_cells(cells: &[GodotCell]) -> Vec<i32> {
+    cells.iter().flat_map(|cell| [cell.coords.0, cell.coords.1, cell.atlas_coords.0, cell.atlas_coords.1]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn terrain_cells_cover_the_excavation_upside_down() {
+        let dungeon = dungeon();
+        let cells = terrain_cells(&dungeon);
+        let walkable = dungeon.grid.iter().filter(|&(_, &tile)| tile != Tile::Wall).count();
+        let walls = cells.iter().filter(|cell| cell.atlas_coords == (Sprite::Wall.index() as i32, 0)).count();
+        assert_eq!(cells.len(), walkable + walls);
+        assert!(walls > 0);
+
+        let (x, y) = dungeon.rooms[0].center;
+        assert!(cells.contains(&GodotCell { coords: (x, -y), atlas_coords: (Sprite::RoomCenter.index() as i32, 0) }));
+        let packed = pack_cells(&cells);
+        assert_eq!(packed.len(), 4 * cells.len());
+        assert_eq!(packed[..4], [cells[0].coords.0, cells[0].coords.1, cells[0].atlas_coords.0, cells[0].atlas_coords.1]);
+    }
+
+    #[test]
+    fn overlay_cells_mark_monsters() {
+        let dungeon = dungeon();
+        let cells = overlay_cells(&dungeon);
+        let monster = dungeon.monsters[0].position;
+        assert!(cells.contains(&GodotCell::new(monster, Sprite::Monster)));
+        assert_eq!(cells.len(), overlays(&dungeon).len());
+    }
+}
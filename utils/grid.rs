@@ -0,0 +1,201 @@
+//! # Tile Grid
+//!
+//! A dense 2D array of tiles with O(1) coordinate lookups.
+//!
+//! `parse_nft_id` populates a `Grid<Tile>` covering every excavated coordinate, so
+//! renderers, pathfinding, and exploration can query tiles without scanning the
+//! excavated coordinate list.
+
+use serde::{Deserialize, Serialize};
+
+/// Kind of a single dungeon tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tile {
+    /// Solid rock; not walkable.
+    Wall,
+    /// Excavated room floor or scattered excavated point.
+    Floor,
+    /// Tunnel tile outside any room.
+    Tunnel,
+    /// Corridor tile where a tunnel enters a room.
+    Door,
+    /// The center tile of a room.
+    RoomCenter,
+    /// Stairs leading up to the floor above.
+    StairsUp,
+    /// Stairs leading down to the floor below.
+    StairsDown,
+    /// Excavated ground flooded by deep water; not walkable.
+    Water,
+    /// Excavated ground filled with lava; not walkable.
+    Lava,
+    /// Excavated ground buried under a sand drift; not walkable.
+    Sand,
+    /// Excavated ground under shallow water; walkable but slow (see `liquid`).
+    ShallowWater,
+}
+
+impl Tile {
+    /// Whether the player can stand on this tile.
+    pub fn is_walkable(self) -> bool {
+        !matches!(self, Tile::Wall | Tile::Water | Tile::Lava | Tile::Sand)
+    }
+
+    /// Whether this is a terrain feature carved into excavated ground (see `terrain`).
+    pub fn is_terrain(self) -> bool {
+        matches!(self, Tile::Water | Tile::Lava | Tile::Sand | Tile::ShallowWater)
+    }
+
+    /// Moves a step onto this tile takes; wading through shallow water takes two.
+    pub fn movement_cost(self) -> usize {
+        if self == Tile::ShallowWater { 2 } else { 1 }
+    }
+
+    /// Whether this tile blocks line of sight; terrain features can be seen across.
+    pub fn blocks_sight(self) -> bool {
+        self == Tile::Wall
+    }
+}
+
+/// Dense 2D grid addressed by dungeon coordinates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Grid<T> {
+    origin: (i32, i32),
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Creates a grid covering the inclusive `x_range` x `y_range`, filled with `fill`.
+    pub fn new(x_range: (i32, i32), y_range: (i32, i32), fill: T) -> Grid<T> {
+        let width = (x_range.1 - x_range.0 + 1).max(0) as usize;
+        let height = (y_range.1 - y_range.0 + 1).max(0) as usize;
+        Grid {
+            origin: (x_range.0, y_range.0),
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+}
+
+/// An empty grid covering no coordinates.
+impl<T> Default for Grid<T> {
+    fn default() -> Grid<T> {
+        Grid { origin: (0, 0), width: 0, height: 0, cells: Vec::new() }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Inclusive x range covered by the grid.
+    pub fn x_range(&self) -> (i32, i32) {
+        (self.origin.0, self.origin.0 + self.width as i32 - 1)
+    }
+
+    /// Inclusive y range covered by the grid.
+    pub fn y_range(&self) -> (i32, i32) {
+        (self.origin.1, self.origin.1 + self.height as i32 - 1)
+    }
+
+    /// Number of columns.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether `pos` lies inside the grid.
+    pub fn contains(&self, pos: (i32, i32)) -> bool {
+        self.index(pos).is_some()
+    }
+
+    /// Tile at `pos`, or `None` outside the grid.
+    pub fn get(&self, pos: (i32, i32)) -> Option<&T> {
+        self.index(pos).map(|i| &self.cells[i])
+    }
+
+    /// Mutable tile at `pos`, or `None` outside the grid.
+    pub fn get_mut(&mut self, pos: (i32, i32)) -> Option<&mut T> {
+        self.index(pos).map(|i| &mut self.cells[i])
+    }
+
+    /// Sets the tile at `pos`, returning false if `pos` is outside the grid.
+    pub fn set(&mut self, pos: (i32, i32), value: T) -> bool {
+        match self.index(pos) {
+            Some(i) => {
+                self.cells[i] = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Iterates over every cell as `(position, value)`, row by row from the lowest y.
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), &T)> {
+        self.cells.iter().enumerate().map(move |(i, value)| {
+            let x = self.origin.0 + (i % self.width) as i32;
+            let y = self.origin.1 + (i / self.width) as i32;
+            ((x, y), value)
+        })
+    }
+
+    // Row-major index of `pos`
+    fn index(&self, (x, y): (i32, i32)) -> Option<usize> {
+        let dx = x.checked_sub(self.origin.0)?;
+        let dy = y.checked_sub(self.origin.1)?;
+        if dx < 0 || dy < 0 || dx as usize >= self.width || dy as usize >= self.height {
+            return None;
+        }
+        Some(dy as usize * self.width + dx as usize)
+    }
+}
+
+impl Grid<Tile> {
+    /// Whether `pos` is inside the grid and walkable.
+    pub fn is_walkable(&self, pos: (i32, i32)) -> bool {
+        self.get(pos).is_some_and(|tile| tile.is_walkable())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn grids_cover_inclusive_ranges_with_negative_origins() {
+        let mut grid = Grid::new((-2, 1), (3, 5), Tile::Wall);
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        assert_eq!((grid.x_range(), grid.y_range()), ((-2, 1), (3, 5)));
+        assert!(grid.set((-2, 5), Tile::Floor));
+        assert_eq!(grid.get((-2, 5)), Some(&Tile::Floor));
+        assert!(!grid.set((2, 5), Tile::Floor));
+        assert_eq!(grid.get((-3, 4)), None);
+        assert_eq!(grid.iter().count(), 12);
+        assert_eq!(grid.iter().next(), Some(((-2, 3), &Tile::Wall)));
+    }
+
+    #[test]
+    fn walls_and_the_outside_are_not_walkable() {
+        let mut grid = Grid::new((0, 1), (0, 0), Tile::Wall);
+        grid.set((1, 0), Tile::Tunnel);
+        assert!(!grid.is_walkable((0, 0)));
+        assert!(grid.is_walkable((1, 0)));
+        assert!(!grid.is_walkable((2, 0)));
+    }
+
+    #[test]
+    fn parsed_grids_mark_every_excavated_tile() {
+        let dungeon = dungeon();
+        for &pos in &dungeon.excavated {
+            assert_ne!(dungeon.grid.get(pos), Some(&Tile::Wall), "{:?} is excavated", pos);
+        }
+        for room in &dungeon.rooms {
+            assert_eq!(dungeon.grid.get(room.center), Some(&Tile::RoomCenter));
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! # Generation Hooks
+//!
+//! Observers called at each step of excavation, so embedders can log generation, stream
+//! it to clients as it happens, or veto and modify steps (see
+//! `parse_nft_id_with_observer`).
+//!
+//! - `on_room_excavated`: Once per decoded room, with its tiles dug. The room can be
+//!   changed in place; moving its center also moves the tunnels, which are laid out
+//!   afterwards. Vetoing removes the room from the dungeon.
+//! - `on_tunnel_created`: Once per tunnel of the spanning tree, before it is dug. The
+//!   path can be changed in place; vetoing leaves the tunnel undug, which may disconnect
+//!   rooms (see `Dungeon::validate`).
+//! - `on_decoration_added`: Once per floor with its rubble points (see `decorate`),
+//!   which can be changed in place.
+//! - `on_stage_started`: Whenever a floor enters the next `Stage` of the pipeline, for
+//!   progress reporting and timing (see `progress`). A stage lasts until the next one
+//!   starts, so the top floor's monsters, placed once every floor is dug, count towards
+//!   the last floor's decoration.
+//!
+//! Every hook receives the depth of the floor being excavated, 0 for the top floor.
+//! Indices count the rooms and tunnels in decode order, vetoed ones included. Vetoing
+//! every room of a floor fails generation with `DungeonError::NothingExcavated`.
+
+use std::fmt;
+use super::dungeon::Room;
+
+/// Whether a generation step goes ahead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HookAction {
+    /// Keep the step, with any changes made by the observer.
+    #[default]
+    Keep,
+    /// Drop the step.
+    Veto,
+}
+
+/// Stage of the generation pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    /// Decoding the room count, coordinates, sizes, shapes, and type from the ID.
+    Parse,
+    /// Digging the rooms.
+    Excavate,
+    /// Laying out and digging the tunnels.
+    Tunnels,
+    /// Rubble, cave smoothing, terrain, and elevation.
+    Decorate,
+    /// Rendering the finished dungeon; reported by callers that render, never by generation.
+    Render,
+}
+
+impl Stage {
+    /// Every stage, in pipeline order.
+    pub const ALL: [Stage; 5] = [Stage::Parse, Stage::Excavate, Stage::Tunnels, Stage::Decorate, Stage::Render];
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Stage::Parse => "parse",
+            Stage::Excavate => "excavate",
+            Stage::Tunnels => "tunnels",
+            Stage::Decorate => "decorate",
+            Stage::Render => "render",
+        };
+        f.pad(name)
+    }
+}
+
+/// Observer of dungeon generation; every hook does nothing by default.
+pub trait GenerationObserver {
+    /// Called when room `index` of the floor at `depth` has been excavated.
+    fn on_room_excavated(&mut self, _depth: usize, _index: usize, _room: &mut Room) -> HookAction {
+        HookAction::Keep
+    }
+
+    /// Called when tunnel `index` of the floor at `depth` has been laid out, before it is dug.
+    fn on_tunnel_created(&mut self, _depth: usize, _index: usize, _tunnel: &mut Vec<(i32, i32)>) -> HookAction {
+        HookAction::Keep
+    }
+
+    /// Called when the rubble of the floor at `depth` has been placed, before it is dug.
+    fn on_decoration_added(&mut self, _depth: usize, _rubble: &mut Vec<(i32, i32)>) {}
+
+    /// Called when the floor at `depth` enters `stage`.
+    fn on_stage_started(&mut self, _depth: usize, _stage: Stage) {}
+}
+
+/// Observer that lets every step go ahead unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NoObserver;
+
+impl GenerationObserver for NoObserver {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::super::config::GenerationConfig;
+    use super::super::excavator::{corridor_brush, parse_nft_id, parse_nft_id_with_observer};
+    use super::super::fixtures::NFT_ID;
+    use super::super::shapes::ShapeRegistry;
+    use super::*;
+
+    // Records every step, vetoing the second room and the first tunnel and clearing the rubble
+    #[derive(Default)]
+    struct Recorder {
+        rooms: Vec<(usize, usize)>,
+        tunnels: usize,
+        decorations: usize,
+        stages: Vec<(usize, Stage)>,
+    }
+
+    impl GenerationObserver for Recorder {
+        fn on_room_excavated(&mut self, depth: usize, index: usize, _room: &mut Room) -> HookAction {
+            self.rooms.push((depth, index));
+            if depth == 0 && index == 1 { HookAction::Veto } else { HookAction::Keep }
+        }
+
+        fn on_tunnel_created(&mut self, depth: usize, index: usize, _tunnel: &mut Vec<(i32, i32)>) -> HookAction {
+            self.tunnels += 1;
+            if depth == 0 && index == 0 { HookAction::Veto } else { HookAction::Keep }
+        }
+
+        fn on_decoration_added(&mut self, depth: usize, rubble: &mut Vec<(i32, i32)>) {
+            self.decorations += 1;
+            if depth == 0 {
+                rubble.clear();
+            }
+        }
+
+        fn on_stage_started(&mut self, depth: usize, stage: Stage) {
+            self.stages.push((depth, stage));
+        }
+    }
+
+    #[test]
+    fn no_observer_changes_nothing() {
+        let config = GenerationConfig::default();
+        let observed = parse_nft_id_with_observer(NFT_ID, &config, &ShapeRegistry::default(), &mut NoObserver).unwrap();
+        assert_eq!(observed, parse_nft_id(NFT_ID).unwrap());
+    }
+
+    #[test]
+    fn observers_see_every_step_and_can_veto() {
+        let plain = parse_nft_id(NFT_ID).unwrap();
+        let mut recorder = Recorder::default();
+        let config = GenerationConfig { smoothing: super::super::smoothing::Smoothing::Never, ..GenerationConfig::default() };
+        let dungeon = parse_nft_id_with_observer(NFT_ID, &config, &ShapeRegistry::default(), &mut recorder).unwrap();
+
+        let floors = 1 + plain.floors.len();
+        assert_eq!(recorder.decorations, floors);
+        // Every floor runs through the generation stages in order
+        assert_eq!(recorder.stages.len(), floors * 4);
+        assert_eq!(recorder.stages[..4], [(0, Stage::Parse), (0, Stage::Excavate), (0, Stage::Tunnels), (0, Stage::Decorate)]);
+        assert_eq!(recorder.rooms.iter().filter(|&&(depth, _)| depth == 0).count(), plain.rooms.len());
+        assert!(recorder.tunnels > dungeon.tunnels.len());
+        assert_eq!(dungeon.rooms.len(), plain.rooms.len() - 1);
+        assert!(!dungeon.rooms.iter().any(|room| room.center == plain.rooms[1].center && room.name == plain.rooms[1].name));
+        assert_eq!(dungeon.tunnels.len(), dungeon.rooms.len() - 2);
+
+        // Without rubble, every excavated tile belongs to a room or a kept corridor
+        let dug: HashSet<(i32, i32)> = dungeon
+            .rooms
+            .iter()
+            .flat_map(|room| room.tiles.iter().copied())
+            .chain(dungeon.tunnels.iter().flatten().flat_map(|&pos| corridor_brush(pos, dungeon.corridor_width)))
+            .collect();
+        assert!(dungeon.excavated.iter().all(|pos| dug.contains(pos)));
+    }
+}
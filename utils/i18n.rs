@@ -0,0 +1,249 @@
+//! # Localization
+//!
+//! Catalogs translating what players read about a dungeon: dungeon type names, sub-biome
+//! modifiers, room kind labels, and the lore text (see `lore`), so non-English
+//! communities can display dungeon attributes natively.
+//!
+//! A `Catalog` is a key-value TOML file:
+//! - `[dungeon_types]`, `[modifiers]`, `[room_kinds]`: Translated names by their English
+//!   name, such as `Cave = "Cueva"`.
+//! - `[plurals]`: Counted nouns of the lore, as `{ one = "...", other = "..." }`.
+//! - `[lore]`: Sentence templates, each a phrase or a list of alternatives that the lore
+//!   RNG picks from. Placeholders in braces, such as `{level}`, are filled in.
+//!
+//! Every entry a catalog leaves out falls back to the English catalog, and names without
+//! an English entry display as they are, so custom catalogs only list what they change
+//! and can be layered over a built-in one (`Catalog::with_overrides`). The English and
+//! Spanish catalogs are built in (`BUILTIN_LANGS`), embedded from `assets/i18n`. Room and
+//! boss names are proper names and stay as generated (see `naming`).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+use serde::{Deserialize, Deserializer, Serialize};
+use super::biome::DungeonType;
+use super::dungeon::RoomKind;
+
+/// Languages of the built-in catalogs
+pub const BUILTIN_LANGS: [&str; 2] = ["en", "es"];
+
+/// The English catalog, in TOML
+pub const ENGLISH_CATALOG: &str = include_str!("../assets/i18n/en.toml");
+
+/// The Spanish catalog, in TOML
+pub const SPANISH_CATALOG: &str = include_str!("../assets/i18n/es.toml");
+
+/// Singular and plural form of a counted noun, each with an `{n}` placeholder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plural {
+    /// Form for a count of 1.
+    pub one: String,
+    /// Form for any other count.
+    pub other: String,
+}
+
+/// Translations of dungeon names and lore (see `i18n`).
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Catalog {
+    /// Language code, such as "es".
+    #[serde(default)]
+    pub lang: String,
+    /// Dungeon type names by English name.
+    #[serde(default)]
+    pub dungeon_types: BTreeMap<String, String>,
+    /// Sub-biome modifiers by English adjective.
+    #[serde(default)]
+    pub modifiers: BTreeMap<String, String>,
+    /// Room kind labels by kind, such as "Boss".
+    #[serde(default)]
+    pub room_kinds: BTreeMap<String, String>,
+    /// Counted nouns of the lore by key.
+    #[serde(default)]
+    pub plurals: BTreeMap<String, Plural>,
+    /// Lore templates by key, each with its alternatives.
+    #[serde(default, deserialize_with = "phrases")]
+    pub lore: BTreeMap<String, Vec<String>>,
+}
+
+// Lore entries are a single phrase or a list of alternatives
+fn phrases<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, Vec<String>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Phrases {
+        One(String),
+        Many(Vec<String>),
+    }
+    let entries = BTreeMap::<String, Phrases>::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .map(|(key, phrases)| match phrases {
+            Phrases::One(phrase) => (key, vec![phrase]),
+            Phrases::Many(phrases) => (key, phrases),
+        })
+        .collect())
+}
+
+/// Errors returned while loading a catalog.
+#[derive(Debug)]
+pub enum CatalogError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not valid catalog TOML.
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Io(err) => write!(f, "Failed to read catalog: {}", err),
+            CatalogError::Toml(err) => write!(f, "Invalid catalog: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl From<io::Error> for CatalogError {
+    fn from(err: io::Error) -> CatalogError {
+        CatalogError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for CatalogError {
+    fn from(err: toml::de::Error) -> CatalogError {
+        CatalogError::Toml(err)
+    }
+}
+
+// The English catalog every lookup falls back to
+fn english() -> &'static Catalog {
+    static ENGLISH: OnceLock<Catalog> = OnceLock::new();
+    ENGLISH.get_or_init(|| Catalog::from_toml(ENGLISH_CATALOG).expect("the English catalog is valid"))
+}
+
+impl Catalog {
+    /// The built-in catalog of `lang`, one of `BUILTIN_LANGS`
+    pub fn builtin(lang: &str) -> Option<Catalog> {
+        match lang {
+            "en" => Some(english().clone()),
+            "es" => Some(Catalog::from_toml(SPANISH_CATALOG).expect("the Spanish catalog is valid")),
+            _ => None,
+        }
+    }
+
+    /// Parses the entries of a catalog from TOML; the rest fall back to English.
+    pub fn from_toml(toml: &str) -> Result<Catalog, CatalogError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Reads a catalog TOML file from `path`.
+    pub fn load(path: &Path) -> Result<Catalog, CatalogError> {
+        Catalog::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// This catalog with the entries of `overrides` replacing those of the same key, and
+    /// its language unless `overrides` names none
+    pub fn with_overrides(mut self, overrides: Catalog) -> Catalog {
+        if !overrides.lang.is_empty() {
+            self.lang = overrides.lang;
+        }
+        self.dungeon_types.extend(overrides.dungeon_types);
+        self.modifiers.extend(overrides.modifiers);
+        self.room_kinds.extend(overrides.room_kinds);
+        self.plurals.extend(overrides.plurals);
+        self.lore.extend(overrides.lore);
+        self
+    }
+
+    /// Name of the dungeon type `name` in this language
+    pub fn dungeon_type(&self, name: &str) -> String {
+        self.dungeon_types.get(name).cloned().unwrap_or_else(|| name.to_string())
+    }
+
+    /// Dungeon type and sub-biome modifier of `biome` in this language, ordered by the
+    /// "biome" lore template
+    pub fn biome(&self, biome: &DungeonType) -> String {
+        let primary = self.dungeon_type(&biome.primary);
+        match &biome.modifier {
+            Some(modifier) => {
+                let modifier = self.modifiers.get(modifier).unwrap_or(modifier);
+                fill(&self.phrases("biome")[0], &[("modifier", modifier), ("type", &primary)])
+            }
+            None => primary,
+        }
+    }
+
+    /// Label of the room kind `kind` in this language
+    pub fn room_kind(&self, kind: RoomKind) -> String {
+        let key = format!("{:?}", kind);
+        self.room_kinds.get(&key).or_else(|| english().room_kinds.get(&key)).cloned().unwrap_or(key)
+    }
+
+    /// `n` of the counted noun `key`, such as "3 chambers"
+    pub fn plural(&self, key: &str, n: usize) -> String {
+        let plural = self.plurals.get(key).or_else(|| english().plurals.get(key));
+        let form = plural.map_or("{n}", |plural| if n == 1 { &plural.one } else { &plural.other });
+        fill(form, &[("n", &n.to_string())])
+    }
+
+    /// Alternatives of the lore template `key`, never empty
+    pub fn phrases(&self, key: &str) -> &[String] {
+        static EMPTY: [String; 1] = [String::new()];
+        [self.lore.get(key), english().lore.get(key)]
+            .into_iter()
+            .flatten()
+            .find(|phrases| !phrases.is_empty())
+            .map_or(&EMPTY, |phrases| phrases)
+    }
+}
+
+/// `template` with each `{name}` placeholder replaced by its value in `values`
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    values.iter().fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_catalogs_translate_names() {
+        let spanish = Catalog::builtin("es").unwrap();
+        assert_eq!(spanish.lang, "es");
+        assert_eq!(spanish.dungeon_type("Cave"), "Cueva");
+        assert_eq!(spanish.dungeon_type("Unmapped"), "Unmapped");
+        assert_eq!(spanish.room_kind(RoomKind::Boss), "Jefe");
+        let frozen = DungeonType { primary: "Necropolis".to_string(), modifier: Some("Frozen".to_string()) };
+        assert_eq!(spanish.biome(&frozen), "Necrópolis de hielo");
+        assert_eq!(spanish.plural("chambers", 1), "1 cámara");
+
+        let english = Catalog::default();
+        assert_eq!(english.biome(&frozen), "Frozen Necropolis");
+        assert_eq!((english.plural("vaults", 1), english.plural("vaults", 2)), ("1 vault".to_string(), "2 vaults".to_string()));
+        assert_eq!(english.room_kind(RoomKind::Shrine), "Shrine");
+        assert!(Catalog::builtin("xx").is_none());
+        for lang in BUILTIN_LANGS {
+            assert_eq!(Catalog::builtin(lang).unwrap().lang, lang);
+        }
+    }
+
+    #[test]
+    fn custom_catalogs_override_and_fall_back() {
+        let custom = Catalog::from_toml("[dungeon_types]\nCave = \"Höhle\"\n\n[lore]\nboss_room = \"das Dunkel\"\n").unwrap();
+        assert_eq!(custom.lang, "");
+        assert_eq!(custom.phrases("boss_room"), ["das Dunkel"]);
+        assert_eq!(custom.phrases("boss").len(), 3);
+        assert_eq!(custom.plural("floors", 2), "2 deeper floors");
+
+        let layered = Catalog::builtin("es").unwrap().with_overrides(custom);
+        assert_eq!(layered.lang, "es");
+        assert_eq!(layered.dungeon_type("Cave"), "Höhle");
+        assert_eq!(layered.dungeon_type("Temple"), "Templo");
+        assert_eq!(fill("{a} and {b}, {a}", &[("a", "x"), ("b", "y")]), "x and y, x");
+        assert!(matches!(Catalog::from_toml("lore = 3"), Err(CatalogError::Toml(_))));
+        assert!(matches!(Catalog::load(Path::new("missing_catalog.toml")), Err(CatalogError::Io(_))));
+    }
+}
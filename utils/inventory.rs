@@ -0,0 +1,297 @@
+//! # Items and Inventory
+//!
+//! Loot scattered through a dungeon and the player state that collects it in explore mode.
+//!
+//! - **Gold**: Every treasure room holds a pile of gold worth 10-19 coins per dungeon level,
+//!   or as set by the dungeon's `Balance`.
+//! - **Potions**: Every other room but the entrance holds a potion with one-in-three odds.
+//!   Drinking one restores `POTION_HEAL` hit points, up to the player's maximum.
+//! - **Locks**: The doors of every treasure room are locked, and so are those of the
+//!   progression gates on the way to the exit (see `progression`). A key opens every door
+//!   of one locked room and is used up.
+//! - **Keys**: Each gate's key lies where `progression::place_gates` put it. The other keys,
+//!   one per locked treasure room, lie where the player can reach them from the entrance
+//!   without passing a locked door.
+//!
+//! Loot lies on room floor tiles, never on a room center, stairs, or another item. Positions
+//! and odds come from the "loot" RNG stream of the `nft_id`.
+//!
+//! `Player` holds hit points and the `Inventory`; both are serializable so runs can be saved.
+
+use std::collections::{HashSet, VecDeque};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::dungeon::{Dungeon, RoomKind};
+use super::grid::Tile;
+use super::pathfinding::neighbors;
+use super::progression::place_gates;
+use super::seed::rng_for_stream;
+
+/// Hit points a new player starts with
+pub const STARTING_HP: u64 = 20;
+/// Hit points restored by a potion
+pub const POTION_HEAL: u64 = 10;
+
+/// An item that can be picked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Item {
+    /// Opens one locked room.
+    Key,
+    /// Restores `POTION_HEAL` hit points when drunk.
+    Potion,
+    /// A pile of coins.
+    Gold(u64),
+}
+
+/// An item lying in the dungeon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Loot {
+    /// The item picked up from this tile.
+    pub item: Item,
+    /// Tile the item lies on.
+    pub position: (i32, i32),
+}
+
+/// The locked doors of one room.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lock {
+    /// Index of the locked room in `Dungeon::rooms`.
+    pub room: usize,
+    /// Door tiles that stay shut until the room is unlocked.
+    pub doors: Vec<(i32, i32)>,
+    /// Tile of the key placed for this lock, for progression gates; `None` for locks whose
+    /// key is placed with the loot.
+    #[serde(default)]
+    pub key: Option<(i32, i32)>,
+}
+
+/// Items carried by the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    /// Unused keys.
+    pub keys: u32,
+    /// Undrunk potions.
+    pub potions: u32,
+    /// Coins collected.
+    pub gold: u64,
+}
+
+impl Inventory {
+    /// Adds a picked-up item.
+    pub fn add(&mut self, item: Item) {
+        match item {
+            Item::Key => self.keys += 1,
+            Item::Potion => self.potions += 1,
+            Item::Gold(amount) => self.gold += amount,
+        }
+    }
+}
+
+/// Hit points and items of the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Player {
+    /// Current hit points.
+    pub hp: u64,
+    /// Most hit points the player can have.
+    pub max_hp: u64,
+    /// Items carried.
+    pub inventory: Inventory,
+}
+
+impl Default for Player {
+    fn default() -> Player {
+        Player { hp: STARTING_HP, max_hp: STARTING_HP, inventory: Inventory::default() }
+    }
+}
+
+impl Player {
+    /// Drinks a potion, returning false if the player has none.
+    pub fn drink_potion(&mut self) -> bool {
+        if self.inventory.potions == 0 {
+            return false;
+        }
+        self.inventory.potions -= 1;
+        self.hp = (self.hp + POTION_HEAL).min(self.max_hp);
+        true
+    }
+
+    /// Uses up a key, returning false if the player has none.
+    pub fn use_key(&mut self) -> bool {
+        if self.inventory.keys == 0 {
+            return false;
+        }
+        self.inventory.keys -= 1;
+        true
+    }
+}
+
+impl Dungeon {
+    /// Locked rooms of this dungeon (see `inventory`).
+    pub fn locks(&self) -> Vec<Lock> {
+        place_locks(self)
+    }
+
+    /// Items lying in this dungeon (see `inventory`).
+    pub fn loot(&self) -> Vec<Loot> {
+        place_loot(self, &place_locks(self))
+    }
+}
+
+/// Lock the doors of every treasure room, then those of the progression gates
+pub fn place_locks(dungeon: &Dungeon) -> Vec<Lock> {
+    let mut locks: Vec<Lock> = dungeon
+        .rooms
+        .iter()
+        .enumerate()
+        .filter(|(_, room)| room.kind == RoomKind::Treasure)
+        .filter_map(|(index, _)| {
+            let doors = room_doors(dungeon, index);
+            (!doors.is_empty()).then_some(Lock { room: index, doors, key: None })
+        })
+        .collect();
+    let gates = place_gates(dungeon, &locks);
+    locks.extend(gates);
+    locks
+}
+
+/// Scatter gold, potions, and one key per lock through the dungeon's rooms
+pub fn place_loot(dungeon: &Dungeon, locks: &[Lock]) -> Vec<Loot> {
+    let mut rng = rng_for_stream(&dungeon.seed_id(), "loot");
+    let Some(start) = dungeon.rooms.first().map(|room| room.center) else {
+        return Vec::new();
+    };
+    let gate_keys: Vec<(i32, i32)> = locks.iter().filter_map(|lock| lock.key).collect();
+    let mut taken: HashSet<(i32, i32)> = dungeon.rooms.iter().map(|room| room.center).chain(gate_keys.iter().copied()).collect();
+    let mut loot: Vec<Loot> = gate_keys.iter().map(|&position| Loot { item: Item::Key, position }).collect();
+
+    for room in dungeon.rooms.iter().filter(|room| room.kind != RoomKind::Entrance) {
+        let item = match room.kind {
+            RoomKind::Treasure => Item::Gold(dungeon.balance.gold_value(rng.gen_range(10..20), dungeon.level)),
+            _ if rng.gen_ratio(1, 3) => Item::Potion,
+            _ => continue,
+        };
+        let candidates = free_floor(dungeon, room.tiles.iter().copied(), &taken);
+        if candidates.is_empty() {
+            continue;
+        }
+        let position = candidates[rng.gen_range(0..candidates.len())];
+        taken.insert(position);
+        loot.push(Loot { item, position });
+    }
+
+    // The other keys lie on room tiles reachable from the entrance with every lock still shut
+    let locked: HashSet<(i32, i32)> = locks.iter().flat_map(|lock| lock.doors.iter().copied()).collect();
+    let reachable = reachable_from(dungeon, start, &locked);
+    let room_tiles = dungeon.rooms.iter().flat_map(|room| room.tiles.iter().copied()).filter(|pos| reachable.contains(pos));
+    let mut candidates = free_floor(dungeon, room_tiles, &taken);
+    for _ in locks.iter().filter(|lock| lock.key.is_none()) {
+        if candidates.is_empty() {
+            break;
+        }
+        let position = candidates.swap_remove(rng.gen_range(0..candidates.len()));
+        loot.push(Loot { item: Item::Key, position });
+    }
+
+    loot
+}
+
+// Door tiles next to the tiles of the room at `index`
+pub(crate) fn room_doors(dungeon: &Dungeon, index: usize) -> Vec<(i32, i32)> {
+    let tiles: HashSet<(i32, i32)> = dungeon.rooms[index].tiles.iter().copied().collect();
+    dungeon
+        .grid
+        .iter()
+        .filter(|&(pos, &tile)| tile == Tile::Door && neighbors(pos).iter().any(|next| tiles.contains(next)))
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+// Distinct floor tiles among `tiles` that hold nothing yet
+pub(crate) fn free_floor(dungeon: &Dungeon, tiles: impl Iterator<Item = (i32, i32)>, taken: &HashSet<(i32, i32)>) -> Vec<(i32, i32)> {
+    let mut seen = HashSet::new();
+    tiles
+        .filter(|pos| dungeon.grid.get(*pos) == Some(&Tile::Floor) && !taken.contains(pos) && seen.insert(*pos))
+        .collect()
+}
+
+// Walkable tiles reachable from `start` without stepping on a `blocked` tile
+pub(crate) fn reachable_from(dungeon: &Dungeon, start: (i32, i32), blocked: &HashSet<(i32, i32)>) -> HashSet<(i32, i32)> {
+    let mut reached = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        for next in neighbors(current) {
+            if dungeon.grid.is_walkable(next) && !blocked.contains(&next) && reached.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn potions_heal_up_to_the_maximum_and_keys_are_used_up() {
+        let mut player = Player::default();
+        assert!(!player.drink_potion() && !player.use_key());
+        player.inventory.add(Item::Potion);
+        player.inventory.add(Item::Key);
+        player.inventory.add(Item::Gold(7));
+        player.inventory.add(Item::Gold(5));
+        assert_eq!(player.inventory, Inventory { keys: 1, potions: 1, gold: 12 });
+
+        player.hp = 3;
+        assert!(player.drink_potion());
+        assert_eq!(player.hp, 3 + POTION_HEAL);
+        player.inventory.add(Item::Potion);
+        assert!(player.drink_potion());
+        assert_eq!(player.hp, STARTING_HP);
+        assert!(player.use_key() && !player.use_key());
+    }
+
+    #[test]
+    fn locks_cover_the_doors_of_treasure_rooms() {
+        let dungeon = dungeon();
+        for lock in dungeon.locks().into_iter().filter(|lock| lock.key.is_none()) {
+            let room = &dungeon.rooms[lock.room];
+            assert_eq!(room.kind, RoomKind::Treasure);
+            assert!(!lock.doors.is_empty());
+            for door in &lock.doors {
+                assert_eq!(dungeon.grid.get(*door), Some(&Tile::Door));
+                assert!(neighbors(*door).iter().any(|next| room.tiles.contains(next)));
+            }
+        }
+    }
+
+    #[test]
+    fn loot_lies_on_distinct_free_floor_tiles() {
+        let dungeon = dungeon();
+        let loot = dungeon.loot();
+        let centers: HashSet<(i32, i32)> = dungeon.rooms.iter().map(|room| room.center).collect();
+        let mut positions = HashSet::new();
+        for item in &loot {
+            assert_eq!(dungeon.grid.get(item.position), Some(&Tile::Floor));
+            assert!(!centers.contains(&item.position));
+            assert!(positions.insert(item.position), "two items on {:?}", item.position);
+        }
+        assert_eq!(loot, dungeon.loot());
+    }
+
+    #[test]
+    fn loose_keys_are_reachable_with_every_lock_shut() {
+        let dungeon = dungeon();
+        let locks = dungeon.locks();
+        let loot = place_loot(&dungeon, &locks);
+        let keys: Vec<(i32, i32)> = loot.iter().filter(|loot| loot.item == Item::Key).map(|loot| loot.position).collect();
+        assert_eq!(keys.len(), locks.len());
+        let keys: Vec<(i32, i32)> = keys.into_iter().filter(|key| !locks.iter().any(|lock| lock.key == Some(*key))).collect();
+
+        let locked: HashSet<(i32, i32)> = locks.iter().flat_map(|lock| lock.doors.iter().copied()).collect();
+        let reachable = reachable_from(&dungeon, dungeon.rooms[0].center, &locked);
+        assert!(keys.iter().all(|key| reachable.contains(key)));
+        assert!(locked.iter().all(|door| !reachable.contains(door)));
+    }
+}
@@ -0,0 +1,231 @@
+//! # Isometric Rendering
+//!
+//! Draws a pseudo-3D isometric view of the dungeon, selected with `RenderFormat::Isometric`,
+//! so NFT preview images look like classic roguelike maps.
+//!
+//! Tiles are projected to 2:1 diamonds, half as tall as they are wide, with the highest
+//! y at the back left so the layout matches the plotted maps turned by 45 degrees:
+//! - **Floors**: Excavated tiles are flat diamonds in the theme's floor color; doors,
+//!   stairs, and terrain features keep the colors of the plotted map.
+//! - **Walls**: Rock next to an excavated tile is raised into a block one tile tall, with
+//!   a top in the theme's wall color and two shaded side faces as height cues.
+//! - **Markers**: Room centers are colored by room kind and monsters drawn in the theme's
+//!   monster color, on top of their floor tile.
+//!
+//! Tiles are painted from back to front, so walls hide the floor behind them. Room names
+//! are written next to the room markers when labels are requested.
+
+use std::collections::HashSet;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use super::dungeon::Dungeon;
+use super::fog::Visibility;
+use super::grid::Tile;
+use super::pathfinding::neighbors;
+use super::plot::{caption, label_style, rgb, room_kind_color, text_color, DOOR_COLOR, STAIRS_DOWN_COLOR, STAIRS_UP_COLOR};
+use super::terrain::terrain_color;
+use super::theme::{Rgb, Theme};
+
+/// Margin around the projected map in pixels
+const MARGIN: i32 = 10;
+/// Brightness of the wall faces turned to the left and to the right
+const FACE_SHADES: (f64, f64) = (0.55, 0.75);
+
+/// Draw and present the isometric map onto `root`, omitting unexplored tiles
+/// Seen tiles use the theme's seen color; monsters only appear on visible tiles, and
+/// explored rooms are labeled by name when `labels` is set.
+pub fn draw_isometric_map<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    dungeon: &Dungeon,
+    theme: &Theme,
+    visibility: &dyn Fn((i32, i32)) -> Visibility,
+    labels: bool,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let _span = tracing::debug_span!("render", nft_id = %dungeon.nft_id, isometric = true).entered();
+    root.fill(&rgb(theme.background))?;
+    let area = root.titled(&caption(dungeon), ("sans-serif", 30).into_font().color(&text_color(theme.background)))?;
+
+    // One tile of rock around the grid, so walls border every excavated tile
+    let (x_range, y_range) = (dungeon.grid.x_range(), dungeon.grid.y_range());
+    let (x_range, y_range) = ((x_range.0 - 1, x_range.1 + 1), (y_range.0 - 1, y_range.1 + 1));
+    let projection = Projection::fit(area.dim_in_pixel(), x_range, y_range);
+
+    let seen = |pos: (i32, i32)| dungeon.grid.get(pos).is_some_and(|&tile| tile != Tile::Wall) && visibility(pos) != Visibility::Unexplored;
+    let monsters: HashSet<(i32, i32)> = dungeon.monsters.iter().map(|monster| monster.position).collect();
+
+    // Paint back to front: a tile only ever hides tiles with a smaller column + row
+    let (columns, rows) = (x_range.1 - x_range.0 + 1, y_range.1 - y_range.0 + 1);
+    for depth in 0..columns + rows - 1 {
+        for column in (depth - rows + 1).max(0)..=depth.min(columns - 1) {
+            let pos = (x_range.0 + column, y_range.1 - (depth - column));
+            let diamond = projection.diamond(pos);
+            if seen(pos) {
+                let tile = dungeon.grid.get(pos).copied().unwrap_or(Tile::Wall);
+                let color = match (tile, visibility(pos)) {
+                    (_, Visibility::Seen) => rgb(theme.seen),
+                    (Tile::Door, _) => DOOR_COLOR,
+                    (Tile::StairsUp, _) => STAIRS_UP_COLOR,
+                    (Tile::StairsDown, _) => STAIRS_DOWN_COLOR,
+                    (tile, _) => rgb(terrain_color(tile).unwrap_or(theme.floor)),
+                };
+                area.draw(&Polygon::new(diamond.to_vec(), color.filled()))?;
+            } else if neighbors(pos).iter().chain(&diagonals(pos)).any(|&next| seen(next)) {
+                draw_wall(&area, &projection, diamond, theme.wall)?;
+            }
+        }
+    }
+
+    // Markers stand on their tile; drawn last so nearby walls do not bury them
+    let radius = (projection.half_height as i32).max(2);
+    for room in dungeon.rooms.iter().filter(|room| visibility(room.center) != Visibility::Unexplored) {
+        area.draw(&Circle::new(projection.center(room.center), radius, room_kind_color(room.kind).filled()))?;
+    }
+    for &position in monsters.iter().filter(|&&position| visibility(position) == Visibility::Visible) {
+        area.draw(&Cross::new(projection.center(position), radius, rgb(theme.monster).stroke_width(2)))?;
+    }
+    if labels {
+        let style = label_style(theme.background, 1.0);
+        for room in dungeon.rooms.iter().filter(|room| visibility(room.center) != Visibility::Unexplored) {
+            let (x, y) = projection.center(room.center);
+            area.draw(&Text::new(room.name.clone(), (x + radius + 2, y - 6), style.clone()))?;
+        }
+    }
+
+    root.present()?;
+
+    Ok(())
+}
+
+// The diagonal neighbors of `pos`, which also border walls at room corners
+fn diagonals((x, y): (i32, i32)) -> [(i32, i32); 4] {
+    [(x - 1, y - 1), (x + 1, y - 1), (x - 1, y + 1), (x + 1, y + 1)]
+}
+
+// A wall block rising one tile above its ground diamond: two shaded faces, then the top
+fn draw_wall<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    projection: &Projection,
+    [top, right, bottom, left]: [(i32, i32); 4],
+    color: Rgb,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let lift = |(x, y): (i32, i32)| (x, y - projection.wall_height());
+    area.draw(&Polygon::new(vec![left, bottom, lift(bottom), lift(left)], shade(color, FACE_SHADES.0).filled()))?;
+    area.draw(&Polygon::new(vec![bottom, right, lift(right), lift(bottom)], shade(color, FACE_SHADES.1).filled()))?;
+    area.draw(&Polygon::new(vec![lift(top), lift(right), lift(bottom), lift(left)], rgb(color).filled()))?;
+    Ok(())
+}
+
+// Darken a theme color by `factor`
+fn shade((r, g, b): Rgb, factor: f64) -> RGBColor {
+    let darken = |channel: u8| (channel as f64 * factor).round() as u8;
+    RGBColor(darken(r), darken(g), darken(b))
+}
+
+/// Maps dungeon coordinates to the pixels of 2:1 diamonds fitted to the drawing area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Projection {
+    /// Pixel position of the top corner of the back (leftmost column, highest y) tile.
+    origin: (f64, f64),
+    /// Half the width of a diamond in pixels.
+    half_width: f64,
+    /// Half the height of a diamond in pixels, always half of `half_width`.
+    half_height: f64,
+    /// Lowest x and highest y covered, the back tile.
+    back: (i32, i32),
+}
+
+impl Projection {
+    /// The largest diamonds whose map, walls included, fits inside `size` pixels
+    fn fit(size: (u32, u32), x_range: (i32, i32), y_range: (i32, i32)) -> Projection {
+        let (columns, rows) = ((x_range.1 - x_range.0 + 1) as f64, (y_range.1 - y_range.0 + 1) as f64);
+        let width = (size.0 as i32 - 2 * MARGIN).max(1) as f64;
+        let height = (size.1 as i32 - 2 * MARGIN).max(1) as f64;
+        // The map spans (columns + rows) half widths across, and as many half heights
+        // down plus one wall height (two half heights) above the back tile
+        let half_height = (width / (2.0 * (columns + rows))).min(height / (columns + rows + 2.0));
+        let half_width = 2.0 * half_height;
+        let used = ((columns + rows) * half_width, (columns + rows + 2.0) * half_height);
+        Projection {
+            origin: (
+                MARGIN as f64 + (width - used.0) / 2.0 + rows * half_width,
+                MARGIN as f64 + (height - used.1) / 2.0 + 2.0 * half_height,
+            ),
+            half_width,
+            half_height,
+            back: (x_range.0, y_range.1),
+        }
+    }
+
+    /// Height of a wall block in pixels, one diamond tall
+    fn wall_height(&self) -> i32 {
+        (2.0 * self.half_height).round() as i32
+    }
+
+    /// Top, right, bottom, and left corners of the ground diamond of `pos`
+    fn diamond(&self, pos: (i32, i32)) -> [(i32, i32); 4] {
+        let (column, row) = ((pos.0 - self.back.0) as f64, (self.back.1 - pos.1) as f64);
+        let x = self.origin.0 + (column - row) * self.half_width;
+        let y = self.origin.1 + (column + row) * self.half_height;
+        let pixel = |dx: f64, dy: f64| ((x + dx).round() as i32, (y + dy).round() as i32);
+        [
+            pixel(0.0, 0.0),
+            pixel(self.half_width, self.half_height),
+            pixel(0.0, 2.0 * self.half_height),
+            pixel(-self.half_width, self.half_height),
+        ]
+    }
+
+    /// Pixel at the middle of the ground diamond of `pos`
+    fn center(&self, pos: (i32, i32)) -> (i32, i32) {
+        let [top, _, bottom, _] = self.diamond(pos);
+        (top.0, (top.1 + bottom.1) / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn projected_maps_fit_the_image() {
+        let dungeon = dungeon();
+        let (x_range, y_range) = (dungeon.grid.x_range(), dungeon.grid.y_range());
+        let projection = Projection::fit((640, 480), x_range, y_range);
+        assert_eq!(projection.half_width, 2.0 * projection.half_height);
+        for x in [x_range.0, x_range.1] {
+            for y in [y_range.0, y_range.1] {
+                let [top, right, bottom, left] = projection.diamond((x, y));
+                assert!(left.0 >= MARGIN - 1 && right.0 <= 640 - MARGIN + 1);
+                assert!(top.1 - projection.wall_height() >= MARGIN - 1 && bottom.1 <= 480 - MARGIN + 1);
+            }
+        }
+        // The back tile sits above and to the left of its right-hand neighbor
+        let back = projection.diamond((x_range.0, y_range.1));
+        let next = projection.diamond((x_range.0 + 1, y_range.1));
+        assert!(next[0].0 > back[0].0 && next[0].1 > back[0].1);
+    }
+
+    #[test]
+    fn walls_are_raised_with_shaded_faces() {
+        let dungeon = dungeon();
+        let theme = Theme::for_dungeon(&dungeon);
+        let (width, height) = (640, 480);
+        let mut pixels = vec![0; (width * height * 3) as usize];
+        let root = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+        draw_isometric_map(&root, &dungeon, &theme, &|_| Visibility::Visible, false).unwrap();
+        drop(root);
+
+        let has = |color: RGBColor| pixels.chunks(3).any(|pixel| pixel == [color.0, color.1, color.2]);
+        assert!(has(rgb(theme.floor)));
+        assert!(has(rgb(theme.wall)));
+        assert!(has(shade(theme.wall, FACE_SHADES.0)));
+        assert!(has(shade(theme.wall, FACE_SHADES.1)));
+    }
+}
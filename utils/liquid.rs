@@ -0,0 +1,157 @@
+//! # Liquid Flow
+//!
+//! Floods the low ground of wet dungeons once their elevation is raised (see `elevation`),
+//! so water pools in the hollows of the map as well as running in rivers (see `terrain`).
+//!
+//! - **Springs**: Water wells up from floor tiles of the lowest elevation level, one
+//!   spring per `TILES_PER_SPRING` floor tiles, drawn from the "liquid" RNG stream.
+//! - **Flow**: From each spring, water spreads breadth-first over neighbouring floor no
+//!   higher than the dungeon type's water level, until the spring's volume is spent
+//!   (see `flood_for_type`).
+//! - **Depth**: Flooded tiles are `Tile::ShallowWater`, walkable but slow to wade through
+//!   (`Tile::movement_cost`). Tiles with water on all eight sides sink into deep
+//!   `Tile::Water`, which blocks movement, except in Swamp dungeons, whose bogs stay
+//!   shallow.
+//!
+//! Water dungeons ("Water", "Ocean Depths") and Swamp dungeons flood; variant types of
+//! upper-case letters flood like their base type. Corridors and room centers are
+//! protected like they are from terrain features: they may be flooded but never deep,
+//! so every room stays reachable, and floor cut off from the rooms by deep water sinks
+//! into it. `GenerationConfig::terrain` turns flooding off with the terrain features.
+
+use std::collections::{HashSet, VecDeque};
+use rand::Rng;
+use super::excavator::base_dungeon_type;
+use super::grid::{Grid, Tile};
+use super::pathfinding::{neighbors, reachable_tiles};
+
+/// Floor tiles per spring
+const TILES_PER_SPRING: usize = 300;
+
+/// How far the water of a dungeon type rises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flood {
+    /// Highest elevation level the water spreads over.
+    pub level: u8,
+    /// Tiles flooded by each spring.
+    pub volume: usize,
+    /// Whether enclosed water sinks into deep water.
+    pub deep: bool,
+}
+
+/// Flooding of dungeons of the given type, if they flood
+pub fn flood_for_type(dungeon_type: &str) -> Option<Flood> {
+    match base_dungeon_type(dungeon_type) {
+        "Ocean Depths" => Some(Flood { level: 1, volume: 160, deep: true }),
+        "Water" => Some(Flood { level: 0, volume: 100, deep: true }),
+        "Swamp" => Some(Flood { level: 0, volume: 120, deep: false }),
+        _ => None,
+    }
+}
+
+/// Flood the low floor of `grid` for a dungeon of `dungeon_type`, never sinking a
+/// `protected` tile into deep water; returns the number of tiles flooded
+pub fn flood_lowlands(grid: &mut Grid<Tile>, elevation: &Grid<u8>, dungeon_type: &str, protected: &HashSet<(i32, i32)>, rng: &mut impl Rng) -> usize {
+    let Some(flood) = flood_for_type(dungeon_type) else {
+        return 0;
+    };
+    let Some(&start) = protected.iter().min() else {
+        return 0;
+    };
+    let level = |pos: (i32, i32)| elevation.get(pos).copied().unwrap_or(u8::MAX);
+    let floor: Vec<(i32, i32)> = grid.iter().filter(|&(_, &tile)| tile == Tile::Floor).map(|(pos, _)| pos).collect();
+    let Some(lowest) = floor.iter().map(|&pos| level(pos)).min().filter(|&lowest| lowest <= flood.level) else {
+        return 0;
+    };
+    let sources: Vec<(i32, i32)> = floor.iter().copied().filter(|&pos| level(pos) == lowest).collect();
+    let reachable = reachable_tiles(grid, start);
+
+    // Let each spring run over the low floor around it
+    let mut flooded = HashSet::new();
+    for _ in 0..(floor.len() / TILES_PER_SPRING).max(1) {
+        let spring = sources[rng.gen_range(0..sources.len())];
+        let mut queue = VecDeque::from([spring]);
+        let mut volume = flood.volume;
+        while let Some(pos) = queue.pop_front() {
+            if volume == 0 {
+                break;
+            }
+            if grid.get(pos) != Some(&Tile::Floor) || level(pos) > flood.level || !flooded.insert(pos) {
+                continue;
+            }
+            volume -= 1;
+            queue.extend(neighbors(pos));
+        }
+    }
+    for &pos in &flooded {
+        grid.set(pos, Tile::ShallowWater);
+    }
+
+    // Sink the water enclosed by water on every side
+    if flood.deep {
+        let wet = |pos: (i32, i32)| matches!(grid.get(pos), Some(Tile::ShallowWater | Tile::Water));
+        let enclosed: Vec<(i32, i32)> = flooded
+            .iter()
+            .copied()
+            .filter(|pos| !protected.contains(pos) && (-1..=1).all(|dx| (-1..=1).all(|dy| wet((pos.0 + dx, pos.1 + dy)))))
+            .collect();
+        for pos in enclosed {
+            grid.set(pos, Tile::Water);
+        }
+
+        // Sink the floor the deep water cuts off from the rooms
+        let still_reachable = reachable_tiles(grid, start);
+        for pos in reachable.difference(&still_reachable) {
+            if matches!(grid.get(*pos), Some(Tile::Floor | Tile::ShallowWater)) {
+                flooded.insert(*pos);
+                grid.set(*pos, Tile::Water);
+            }
+        }
+    }
+    flooded.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::super::excavator::parse_nft_id;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    #[test]
+    fn wet_dungeons_flood_their_hollows() {
+        assert_eq!(flood_for_type("Sunken Swamp").map(|flood| flood.deep), Some(false));
+        assert_eq!(flood_for_type("Temple"), None);
+        for dungeon_type in ["Water", "Ocean Depths", "Swamp"] {
+            let options = GenerateOptions { dungeon_type: Some(dungeon_type.to_string()), seed: Some(3), ..GenerateOptions::default() };
+            let dungeon = parse_nft_id(&generate_nft_id_with(&options).unwrap()).unwrap();
+            let shallows: Vec<(i32, i32)> = dungeon.grid.iter().filter(|&(_, &tile)| tile == Tile::ShallowWater).map(|(pos, _)| pos).collect();
+            assert!(!shallows.is_empty(), "{} has no shallow water", dungeon_type);
+            let level = flood_for_type(dungeon_type).unwrap().level;
+            assert!(shallows.iter().all(|&pos| dungeon.elevation_at(pos).is_some_and(|height| height <= level)));
+            assert!(dungeon.is_fully_connected());
+            assert!(dungeon.monsters.iter().all(|monster| dungeon.grid.is_walkable(monster.position)));
+        }
+    }
+
+    #[test]
+    fn enclosed_water_sinks_but_protected_tiles_stay_shallow() {
+        let corridor: HashSet<(i32, i32)> = (0..20).map(|x| (x, 10)).collect();
+        let elevation = Grid::new((0, 19), (0, 19), 0);
+        let mut grid = Grid::new((0, 19), (0, 19), Tile::Floor);
+        let flooded = flood_lowlands(&mut grid, &elevation, "Water", &corridor, &mut StdRng::seed_from_u64(1));
+        assert!(flooded >= 100);
+        assert!(grid.iter().any(|(_, &tile)| tile == Tile::Water));
+        assert!(corridor.iter().all(|&pos| grid.is_walkable(pos)));
+        // Nothing walkable is stranded
+        let reachable = reachable_tiles(&grid, (0, 10));
+        assert!(grid.iter().filter(|(_, tile)| tile.is_walkable()).all(|(pos, _)| reachable.contains(&pos)));
+
+        let mut swamp = Grid::new((0, 19), (0, 19), Tile::Floor);
+        assert!(flood_lowlands(&mut swamp, &elevation, "Swamp", &corridor, &mut StdRng::seed_from_u64(1)) > 0);
+        assert!(swamp.iter().all(|(_, &tile)| tile.is_walkable()));
+        let mut high = Grid::new((0, 19), (0, 19), Tile::Floor);
+        assert_eq!(flood_lowlands(&mut high, &Grid::new((0, 19), (0, 19), 2), "Water", &corridor, &mut StdRng::seed_from_u64(1)), 0);
+    }
+}
@@ -0,0 +1,179 @@
+//! # Lore
+//!
+//! Deterministic flavor text describing a dungeon, such as "A level 12 Frozen Necropolis
+//! of 14 chambers, its eastern halls collapsed.", used as the description of exported
+//! NFT metadata (see `export::metadata`) and printed by the CLI.
+//!
+//! `describe` joins one sentence per trait the dungeon has:
+//! - **Opening**: The level, biome, and room count, and the side of the map with the
+//!   fewest excavated tiles, "collapsed" when it holds less than `COLLAPSED_SHARE` of
+//!   the tiles of its axis.
+//! - **Ground**: Rivers, lava, or sand carved into the floor (see `terrain`), and cliffs
+//!   when at least `RUGGED_CLIFFS` tiles lie at the foot of one (see `elevation`).
+//! - **Guardian**: The boss and the room it guards (see `boss`).
+//! - **Riches**: The treasure rooms and shrines.
+//! - **Depths**: The floors below the top floor (see `floors`).
+//! - **Rumor**: A closing line on a flavor word of the dungeon type (see `naming`).
+//!
+//! Phrasings are picked from the "lore" RNG stream of the `nft_id`, so a dungeon always
+//! reads the same. `describe_in` writes the sentences from the templates of a `Catalog`
+//! (see `i18n`); `describe` uses the English one.
+
+use std::cmp::Ordering;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use super::dungeon::{Dungeon, RoomKind};
+use super::grid::Tile;
+use super::i18n::{fill, Catalog};
+use super::naming::type_words;
+use super::seed::rng_for_stream;
+
+/// Share of the excavated tiles of an axis below which a side of the map reads as collapsed
+pub const COLLAPSED_SHARE: f64 = 0.4;
+/// Fewest cliff tiles for the ground to read as rugged
+pub const RUGGED_CLIFFS: usize = 10;
+
+/// Flavor text describing the dungeon in English (see `lore`)
+pub fn describe(dungeon: &Dungeon) -> String {
+    describe_in(dungeon, &Catalog::default())
+}
+
+/// Flavor text describing the dungeon in the language of `catalog` (see `lore`)
+pub fn describe_in(dungeon: &Dungeon, catalog: &Catalog) -> String {
+    let mut rng = rng_for_stream(&dungeon.seed_id(), "lore");
+    let mut pick = |key: &str| choose(&mut rng, catalog.phrases(key));
+    let mut sentences = Vec::new();
+
+    let layout = match collapsed_side(dungeon) {
+        Some(side) => fill(&pick("collapsed"), &[("side", &pick(side))]),
+        None => pick("even"),
+    };
+    let rooms = catalog.plural("chambers", dungeon.rooms.len());
+    let level = dungeon.level.to_string();
+    let biome = catalog.biome(&dungeon.biome);
+    sentences.push(fill(&pick("opening"), &[("level", &level), ("biome", &biome), ("rooms", &rooms), ("layout", &layout)]));
+
+    let ground = [(Tile::Water, "water"), (Tile::Lava, "lava"), (Tile::Sand, "sand")]
+        .into_iter()
+        .find(|&(feature, _)| dungeon.grid.iter().any(|(_, &tile)| tile == feature))
+        .map(|(_, key)| pick(key));
+    let rugged = dungeon.grid.iter().filter(|&(pos, _)| dungeon.is_cliff(pos)).count() >= RUGGED_CLIFFS;
+    match (ground, rugged) {
+        (Some(ground), true) => sentences.push(fill(&pick("ground_and_cliffs"), &[("ground", &ground)])),
+        (Some(ground), false) => sentences.push(fill(&pick("ground"), &[("ground", &ground)])),
+        (None, true) => sentences.push(pick("cliffs")),
+        (None, false) => {}
+    }
+
+    if let Some(boss) = dungeon.boss() {
+        let room = dungeon.rooms.get(boss.room).map_or_else(|| pick("boss_room"), |room| room.name.clone());
+        sentences.push(fill(&pick("boss"), &[("boss", &boss.name), ("room", &room)]));
+    }
+
+    let kinds = |kind: RoomKind| dungeon.rooms.iter().filter(|room| room.kind == kind).count();
+    let (vaults, shrines) = (kinds(RoomKind::Treasure), kinds(RoomKind::Shrine));
+    let riches = match (vaults, shrines) {
+        (0, 0) => None,
+        (vaults, 0) => Some(catalog.plural("vaults", vaults)),
+        (0, shrines) => Some(catalog.plural("shrines", shrines)),
+        (vaults, shrines) => Some(fill(&pick("riches"), &[("vaults", &catalog.plural("vaults", vaults)), ("shrines", &catalog.plural("shrines", shrines))])),
+    };
+    if let Some(riches) = riches {
+        // "1 vault" takes a singular verb, anything more a plural one
+        let lead = if vaults + shrines == 1 { pick("riches_one") } else { pick("riches_other") };
+        sentences.push(fill(&lead, &[("riches", &riches)]));
+    }
+
+    if !dungeon.floors.is_empty() {
+        sentences.push(fill(&pick("depths"), &[("floors", &catalog.plural("floors", dungeon.floors.len()))]));
+    }
+
+    // Flavor words are capitalized for room names; "the Dead" and the like keep theirs
+    let flavor = *type_words(&dungeon.dungeon_type).1.choose(&mut rng).unwrap();
+    let flavor = if flavor.starts_with("the ") { flavor.to_string() } else { flavor.to_lowercase() };
+    sentences.push(fill(&choose(&mut rng, catalog.phrases("rumor")), &[("flavor", &flavor)]));
+    sentences.join(" ")
+}
+
+// One of `phrases`, drawn from `rng` only when there are alternatives, so a translation
+// listing fewer of them leaves the picks of the other templates as they are
+fn choose(rng: &mut impl Rng, phrases: &[String]) -> String {
+    if phrases.len() > 1 { phrases.choose(rng).unwrap().clone() } else { phrases[0].clone() }
+}
+
+// Side of the map ("northern", "eastern", ...) with the smallest share of the excavated
+// tiles of its axis, if below `COLLAPSED_SHARE`
+fn collapsed_side(dungeon: &Dungeon) -> Option<&'static str> {
+    let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+    let (cx, cy) = (x_range.0 + x_range.1, y_range.0 + y_range.1);
+    // Coordinates are doubled against the doubled center, so tiles on the middle line fall on neither side
+    let side = |axis: fn((i32, i32)) -> i32, center: i32, above: &'static str, below: &'static str| {
+        let (mut high, mut low) = (0usize, 0usize);
+        for &pos in &dungeon.excavated {
+            match (2 * axis(pos)).cmp(&center) {
+                Ordering::Greater => high += 1,
+                Ordering::Less => low += 1,
+                Ordering::Equal => {}
+            }
+        }
+        let total = (high + low).max(1) as f64;
+        if high <= low { (high as f64 / total, above) } else { (low as f64 / total, below) }
+    };
+    [side(|(x, _)| x, cx, "eastern", "western"), side(|(_, y)| y, cy, "northern", "southern")]
+        .into_iter()
+        .filter(|&(share, _)| share < COLLAPSED_SHARE)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, side)| side)
+}
+
+impl Dungeon {
+    /// Flavor text describing the dungeon (see `lore`).
+    pub fn lore(&self) -> String {
+        describe(self)
+    }
+
+    /// Flavor text describing the dungeon in the language of `catalog` (see `lore`).
+    pub fn lore_in(&self, catalog: &Catalog) -> String {
+        describe_in(self, catalog)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn descriptions_are_deterministic_and_name_the_dungeon() {
+        let dungeon = dungeon();
+        let lore = dungeon.lore();
+        assert_eq!(lore, describe(&dungeon));
+        assert!(lore.starts_with(&format!("A level {} {} of {} chambers, its ", dungeon.level, dungeon.biome, dungeon.rooms.len())), "{}", lore);
+        assert!(lore.contains(&dungeon.boss().unwrap().name));
+        assert!(lore.ends_with('.'));
+        assert!(type_words(&dungeon.dungeon_type).1.iter().any(|flavor| lore.ends_with(&format!(" {}.", flavor.to_lowercase()))));
+    }
+
+    #[test]
+    fn catalogs_translate_descriptions() {
+        let dungeon = dungeon();
+        assert_eq!(dungeon.lore_in(&Catalog::builtin("en").unwrap()), dungeon.lore());
+        let spanish = Catalog::builtin("es").unwrap();
+        let lore = dungeon.lore_in(&spanish);
+        assert_eq!(lore, describe_in(&dungeon, &spanish));
+        assert!(lore.starts_with(&format!("{} de nivel {} con {} cámaras, sus ", spanish.biome(&dungeon.biome), dungeon.level, dungeon.rooms.len())), "{}", lore);
+        assert!(lore.contains(&dungeon.boss().unwrap().name));
+        assert!(!lore.contains("chambers") && !lore.contains("halls"), "{}", lore);
+    }
+
+    #[test]
+    fn lopsided_maps_collapse_on_their_emptier_side() {
+        let mut dungeon = dungeon();
+        let x_range = dungeon.bounds.x_range;
+        let middle = (x_range.0 + x_range.1) / 2;
+        dungeon.excavated.retain(|&(x, _)| x <= middle - 2 || x % 5 == 0);
+        assert_eq!(collapsed_side(&dungeon), Some("eastern"));
+        let english = Catalog::default();
+        assert_eq!((english.plural("vaults", 1), english.plural("vaults", 2)), ("1 vault".to_string(), "2 vaults".to_string()));
+    }
+}
@@ -0,0 +1,158 @@
+//! # Maze Layouts
+//!
+//! An alternative to joining rooms with tunnels (`GenerationAlgorithm::Maze`): the rooms
+//! are decoded and excavated as usual, then a perfect maze is carved around them.
+//!
+//! - **Cells**: The maze covers the bounding box of the room tiles, with a cell on every
+//!   other tile along both axes from its south-west corner; the tiles between two cells
+//!   are their wall, dug when the maze passes through it.
+//! - **Carving**: A recursive backtracker on the "maze" RNG stream of the `nft_id` visits
+//!   every cell from the one nearest the entrance, so the maze alone has exactly one path
+//!   between any two cells (see `maze_passages`).
+//! - **Chambers**: Rooms stay open over the maze, each dug through to its nearest cell,
+//!   so every room is reachable; rooms crossing the maze add loops to it.
+//! - **Tunnels**: Each room is joined to the next by the shortest path through the maze,
+//!   reported to the generation hooks as a tunnel, so topology and progression read maze
+//!   dungeons like any other.
+//!
+//! Passages are a single tile wide at every level and drawn as tunnel tiles. Mazes get no
+//! rubble or smoothing, which would break their walls; terrain and elevation are added as
+//! for rooms and tunnels.
+
+use std::collections::HashSet;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use super::dungeon::{Bounds, Dungeon};
+use super::excavator::{build_grid, Layout};
+use super::grid::{Grid, Tile};
+use super::hooks::{GenerationObserver, HookAction};
+use super::pathfinding::{find_path, neighbors};
+use super::seed::rng_for_stream;
+
+/// Passages of a perfect maze of `columns` by `rows` cells, carved by a recursive
+/// backtracker from the cell `start`, each joining two neighbouring cells
+/// A maze of any size has one passage fewer than it has cells.
+pub fn maze_passages(columns: i32, rows: i32, start: (i32, i32), rng: &mut impl Rng) -> Vec<((i32, i32), (i32, i32))> {
+    let mut passages = Vec::new();
+    if columns <= 0 || rows <= 0 {
+        return passages;
+    }
+    let mut visited = Grid::new((0, columns - 1), (0, rows - 1), false);
+    let start = (start.0.clamp(0, columns - 1), start.1.clamp(0, rows - 1));
+    visited.set(start, true);
+    let mut stack = vec![start];
+    while let Some(&cell) = stack.last() {
+        let unvisited: Vec<(i32, i32)> = neighbors(cell).into_iter().filter(|&next| visited.get(next) == Some(&false)).collect();
+        match unvisited.choose(rng) {
+            Some(&next) => {
+                visited.set(next, true);
+                passages.push((cell, next));
+                stack.push(next);
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+    passages
+}
+
+/// Carves a maze around the excavated rooms of `dungeon`, which has no tunnels yet,
+/// reporting each tunnel to `observer` as the floor at `depth`
+pub(crate) fn carve_maze(mut dungeon: Dungeon, seed_id: String, observer: &mut dyn GenerationObserver, depth: usize) -> Layout {
+    let room_tiles: HashSet<(i32, i32)> = dungeon.rooms.iter().flat_map(|room| room.tiles.iter().copied().chain([room.center])).collect();
+    let bounds = Bounds::covering(room_tiles.iter().copied()).expect("rooms are never empty");
+    let (x_range, y_range) = (bounds.x_range, bounds.y_range);
+    let mut open = Grid::new(x_range, y_range, false);
+    for &pos in &room_tiles {
+        open.set(pos, true);
+    }
+
+    // Cells lie on every other tile from the south-west corner, the last within a tile of the edge
+    let (columns, rows) = ((x_range.1 - x_range.0) / 2 + 1, (y_range.1 - y_range.0) / 2 + 1);
+    let tile = |(column, row): (i32, i32)| (x_range.0 + 2 * column, y_range.0 + 2 * row);
+    let nearest_cell = |(x, y): (i32, i32)| (((x - x_range.0 + 1) / 2).min(columns - 1), ((y - y_range.0 + 1) / 2).min(rows - 1));
+    let passages = maze_passages(columns, rows, nearest_cell(dungeon.rooms[0].center), &mut rng_for_stream(&seed_id, "maze"));
+    open.set(tile(nearest_cell(dungeon.rooms[0].center)), true);
+    for (from, to) in passages {
+        let (from, to) = (tile(from), tile(to));
+        open.set(((from.0 + to.0) / 2, (from.1 + to.1) / 2), true);
+        open.set(to, true);
+    }
+
+    // Dig each room through to its nearest cell, along x and then y
+    for room in &dungeon.rooms {
+        let cell = tile(nearest_cell(room.center));
+        open.set((cell.0, room.center.1), true);
+        open.set(cell, true);
+    }
+
+    // Join each room to the next along the maze, letting the observer change or veto each tunnel
+    let mut walkable = Grid::new(x_range, y_range, Tile::Wall);
+    for (pos, &dug) in open.iter() {
+        if dug {
+            walkable.set(pos, Tile::Floor);
+        }
+    }
+    let centers: Vec<(i32, i32)> = dungeon.rooms.iter().map(|room| room.center).collect();
+    dungeon.tunnels = centers
+        .windows(2)
+        .filter_map(|pair| find_path(&walkable, pair[0], pair[1]))
+        .enumerate()
+        .filter_map(|(index, mut tunnel)| (observer.on_tunnel_created(depth, index, &mut tunnel) == HookAction::Keep).then_some(tunnel))
+        .collect();
+
+    // Rooms are floor and the maze around them tunnels, with doors where tunnels enter rooms
+    dungeon.corridor_width = 1;
+    dungeon.excavated = open.iter().filter(|&(_, &dug)| dug).map(|(pos, _)| pos).collect();
+    dungeon.grid = build_grid(&dungeon.rooms, &dungeon.tunnels, 1, &dungeon.excavated);
+    for &pos in &dungeon.excavated {
+        if !room_tiles.contains(&pos) && dungeon.grid.get(pos) == Some(&Tile::Floor) {
+            dungeon.grid.set(pos, Tile::Tunnel);
+        }
+    }
+    dungeon.recompute_bounds();
+    Layout { dungeon, seed_id, rubble_area: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::{GenerationAlgorithm, GenerationConfig};
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::NFT_ID;
+    use super::*;
+
+    #[test]
+    fn backtrackers_carve_perfect_mazes() {
+        let mut rng = rng_for_stream(NFT_ID, "maze");
+        let passages = maze_passages(7, 5, (3, 9), &mut rng);
+        assert_eq!(passages.len(), 7 * 5 - 1);
+        // One passage fewer than cells, all joined from the start: a spanning tree
+        let mut reached = HashSet::from([(3, 4)]);
+        for &(from, to) in &passages {
+            assert!(reached.contains(&from) && reached.insert(to), "{:?} -> {:?}", from, to);
+            assert_eq!((from.0 - to.0).abs() + (from.1 - to.1).abs(), 1);
+        }
+        assert_eq!(reached.len(), 35);
+        assert_eq!(maze_passages(7, 5, (3, 4), &mut rng_for_stream(NFT_ID, "maze")), passages);
+        assert!(maze_passages(0, 5, (0, 0), &mut rng).is_empty());
+    }
+
+    #[test]
+    fn maze_dungeons_embed_every_room() {
+        let config = GenerationConfig { algorithm: GenerationAlgorithm::Maze, ..GenerationConfig::default() };
+        let dungeon = parse_nft_id_with_config(NFT_ID, &config).unwrap();
+        assert_eq!(dungeon, parse_nft_id_with_config(NFT_ID, &config).unwrap());
+        assert!(dungeon.is_fully_connected());
+        assert_eq!(dungeon.corridor_width, 1);
+        assert_eq!(dungeon.tunnels.len(), dungeon.rooms.len() - 1);
+        for (tunnel, pair) in dungeon.tunnels.iter().zip(dungeon.rooms.windows(2)) {
+            assert_eq!((tunnel[0], tunnel[tunnel.len() - 1]), (pair[0].center, pair[1].center));
+        }
+        // Every room tile stays open, and maze walls fill much of the map around them
+        assert!(dungeon.rooms.iter().flat_map(|room| &room.tiles).all(|&pos| dungeon.grid.get(pos) != Some(&Tile::Wall)));
+        let walls = dungeon.grid.iter().filter(|(_, &tile)| tile == Tile::Wall).count();
+        assert!(walls * 4 > dungeon.grid.width() * dungeon.grid.height(), "{} walls", walls);
+        assert!(dungeon.grid.iter().any(|(_, &tile)| tile == Tile::Tunnel));
+    }
+}
@@ -1,2 +1,85 @@
 // pub mod excavator;
+pub mod analysis;
+pub mod animation;
+pub mod arena;
+pub mod ascii;
+pub mod audio;
+pub mod balance;
+pub mod batch;
+pub mod biome;
+pub mod boss;
+pub mod cache;
+pub mod campaign;
+pub mod chia;
+pub mod combat;
+pub mod config;
+pub mod danger;
+pub mod decorate;
+pub mod dungeon;
+pub mod elevation;
+pub mod epoch;
+pub mod error;
 pub mod excavator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod explore;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fingerprint;
+#[cfg(test)]
+pub(crate) mod fixtures;
+pub mod floors;
+pub mod fog;
+pub mod fusion;
+#[cfg(feature = "godot")]
+pub mod gdext;
+pub mod gallery;
+pub mod generator;
+pub mod godot;
+pub mod grid;
+pub mod hooks;
+pub mod i18n;
+pub mod inventory;
+pub mod isometric;
+pub mod liquid;
+pub mod lore;
+pub mod maze;
+pub mod naming;
+pub mod noise;
+pub mod output;
+pub mod ownership;
+pub mod pathfinding;
+pub mod pipeline;
+pub mod plot;
+pub mod progress;
+pub mod progression;
+pub mod quests;
+pub mod rarity;
+pub mod regions;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod replay;
+#[cfg(feature = "chia-rpc")]
+pub mod rpc;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod save;
+pub mod seed;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shapes;
+pub mod smoothing;
+pub mod spatial;
+pub mod spawn;
+pub mod sprites;
+pub mod stats;
+pub mod terrain;
+pub mod theme;
+pub mod topology;
+pub mod transform;
+pub mod traps;
+pub mod tunnels;
+pub mod validate;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod viewer;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watermark;
@@ -0,0 +1,140 @@
+//! # Room Naming
+//!
+//! Gives every room a deterministic flavor name such as "The Sunken Archive" or
+//! "Hall of Embers", stored in `Room::name`.
+//!
+//! - **Noun**: Picked by the room's x coordinate character from the nouns of its kind
+//!   (a boss room is a "Lair" or "Throne", a treasure room a "Vault" or "Hoard").
+//! - **Adjective** and **flavor**: Picked by the room's y coordinate character and the
+//!   sum of both from the word lists of the dungeon type; variant types of upper-case
+//!   letters use the words of their base type.
+//! - **Form**: The room's shape character picks "The {adjective} {noun}" or
+//!   "{noun} of {flavor}".
+//!
+//! Rooms that would share a name are numbered in decode order ("Hall of Embers II"), so
+//! every name in a dungeon is unique. `plot::render_labeled_map` draws the names on the map.
+
+use std::collections::HashMap;
+use super::dungeon::RoomKind;
+use super::excavator::{base_dungeon_type, char_to_num};
+
+/// Nouns for rooms of the given kind
+pub fn room_nouns(kind: RoomKind) -> [&'static str; 4] {
+    match kind {
+        RoomKind::Entrance => ["Gate", "Threshold", "Antechamber", "Portal"],
+        RoomKind::Exit => ["Descent", "Stair", "Passage", "Gateway"],
+        RoomKind::Treasure => ["Vault", "Hoard", "Treasury", "Coffer"],
+        RoomKind::Boss => ["Lair", "Throne", "Den", "Sanctum"],
+        RoomKind::Shrine => ["Shrine", "Altar", "Chapel", "Reliquary"],
+        RoomKind::Normal => ["Hall", "Chamber", "Archive", "Gallery"],
+    }
+}
+
+/// Adjectives and flavor words of a dungeon type
+pub fn type_words(dungeon_type: &str) -> ([&'static str; 4], [&'static str; 4]) {
+    match base_dungeon_type(dungeon_type) {
+        "Ancient Ruins" => (["Crumbling", "Forgotten", "Weathered", "Fallen"], ["Kings", "Dust", "Ages", "Echoes"]),
+        "Barrens" => (["Barren", "Parched", "Hollow", "Bleached"], ["Bones", "Thirst", "Wind", "Ash"]),
+        "Cave" => (["Dripping", "Hollow", "Echoing", "Dark"], ["Stone", "Bats", "Shadows", "Echoes"]),
+        "Desert" => (["Sunbaked", "Buried", "Shifting", "Golden"], ["Sand", "Mirages", "Scorpions", "Dunes"]),
+        "Enchanted Forest" => (["Glimmering", "Whispering", "Fey", "Moonlit"], ["Dreams", "Pixies", "Starlight", "Wishes"]),
+        "Forest" => (["Mossy", "Tangled", "Green", "Shaded"], ["Oaks", "Wolves", "Leaves", "Roots"]),
+        "Grassland" => (["Windswept", "Open", "Golden", "Quiet"], ["Meadows", "Herds", "Clover", "Skies"]),
+        "Hell" => (["Burning", "Screaming", "Infernal", "Charred"], ["Embers", "Brimstone", "Torment", "Cinders"]),
+        "Ice Cavern" => (["Frozen", "Glacial", "Silent", "Rimed"], ["Frost", "Icicles", "Winter", "Snow"]),
+        "Jungle" => (["Overgrown", "Humid", "Wild", "Verdant"], ["Vines", "Jaguars", "Rain", "Idols"]),
+        "Kingdom Ruins" => (["Royal", "Ruined", "Abandoned", "Gilded"], ["Crowns", "Banners", "Knights", "Oaths"]),
+        "Lava Pits" => (["Molten", "Smoldering", "Scorched", "Blazing"], ["Magma", "Embers", "Flame", "Slag"]),
+        "Mountain" => (["Craggy", "Towering", "Windy", "Granite"], ["Peaks", "Giants", "Eagles", "Stone"]),
+        "Necropolis" => (["Silent", "Haunted", "Ashen", "Grim"], ["Bones", "the Dead", "Graves", "Whispers"]),
+        "Ocean Depths" => (["Sunken", "Drowned", "Abyssal", "Briny"], ["Tides", "Pearls", "Coral", "the Deep"]),
+        "Poison Swamp" => (["Festering", "Toxic", "Rotting", "Fetid"], ["Venom", "Blight", "Spores", "Plague"]),
+        "Quagmire" => (["Sinking", "Muddy", "Murky", "Clinging"], ["Mire", "Leeches", "Silt", "Sludge"]),
+        "Rainforest" => (["Dripping", "Lush", "Misty", "Teeming"], ["Rain", "Orchids", "Canopies", "Frogs"]),
+        "Swamp" => (["Sodden", "Foggy", "Reedy", "Stagnant"], ["Reeds", "Mist", "Lanterns", "Bogs"]),
+        "Temple" => (["Sacred", "Golden", "Hallowed", "Veiled"], ["Prayers", "Incense", "Relics", "the Sun"]),
+        "Underground Tunnels" => (["Winding", "Narrow", "Lightless", "Burrowed"], ["Roots", "Rats", "Lanterns", "Soil"]),
+        "Volcanic Crater" => (["Obsidian", "Ashen", "Sulfurous", "Cracked"], ["Ash", "Smoke", "Obsidian", "Fire"]),
+        "Water" => (["Flooded", "Rippling", "Sunken", "Misty"], ["Tides", "Currents", "Rain", "Reflections"]),
+        "Xeno Hive" => (["Pulsing", "Chitinous", "Alien", "Writhing"], ["the Brood", "Larvae", "Resin", "the Swarm"]),
+        "Yellow Wasteland" => (["Blighted", "Scorched", "Rusted", "Forsaken"], ["Rust", "Wreckage", "Dust", "Raiders"]),
+        "Zephyr Highlands" => (["Windswept", "Lofty", "Cloudy", "Soaring"], ["Winds", "Griffins", "Clouds", "Storms"]),
+        _ => (["Forgotten", "Dark", "Hidden", "Old"], ["Shadows", "Secrets", "Dust", "Stone"]),
+    }
+}
+
+/// Flavor name of a room of `kind` in a dungeon of `dungeon_type`, picked by the room's
+/// x coordinate, y coordinate, and shape characters
+pub fn room_name(dungeon_type: &str, kind: RoomKind, [x_char, y_char, shape_char]: [char; 3]) -> String {
+    let (x, y, shape) = (char_to_num(x_char) as usize, char_to_num(y_char) as usize, char_to_num(shape_char) as usize);
+    let (adjectives, flavors) = type_words(dungeon_type);
+    let noun = room_nouns(kind)[x % 4];
+    if shape % 2 == 0 {
+        format!("The {} {}", adjectives[y % 4], noun)
+    } else {
+        format!("{} of {}", noun, flavors[(x + y) % 4])
+    }
+}
+
+/// Number repeated names in order ("Hall of Embers", "Hall of Embers II", ...)
+pub fn unique_names(names: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    names
+        .into_iter()
+        .map(|name| {
+            let count = seen.entry(name.clone()).or_insert(0);
+            *count += 1;
+            match *count {
+                1 => name,
+                n => format!("{} {}", name, roman(n)),
+            }
+        })
+        .collect()
+}
+
+// Roman numeral of `n`, for numbering repeated names
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut numeral = String::new();
+    for &(value, symbol) in &NUMERALS {
+        while n >= value {
+            numeral.push_str(symbol);
+            n -= value;
+        }
+    }
+    numeral
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn names_follow_the_characters_and_dungeon_type() {
+        assert_eq!(room_name("Hell", RoomKind::Normal, ['0', '0', '1']), "Hall of Embers");
+        assert_eq!(room_name("Hellforge", RoomKind::Normal, ['0', '0', '1']), "Hall of Embers");
+        assert_eq!(room_name("Ocean Depths", RoomKind::Normal, ['2', '0', '0']), "The Sunken Archive");
+        assert_eq!(room_name("Temple", RoomKind::Boss, ['1', '2', '4']), "The Hallowed Throne");
+        assert_eq!(room_name("Temple", RoomKind::Boss, ['1', '2', '4']), room_name("Temple", RoomKind::Boss, ['1', '2', '4']));
+    }
+
+    #[test]
+    fn repeated_names_are_numbered() {
+        let names = unique_names(["Hall", "Den", "Hall", "Hall"].map(String::from));
+        assert_eq!(names, ["Hall", "Den", "Hall II", "Hall III"]);
+        assert_eq!(roman(14), "XIV");
+    }
+
+    #[test]
+    fn every_room_has_a_unique_name() {
+        let dungeon = dungeon();
+        let names: HashSet<&str> = dungeon.rooms.iter().map(|room| room.name.as_str()).collect();
+        assert_eq!(names.len(), dungeon.rooms.len());
+        assert!(dungeon.rooms.iter().all(|room| !room.name.is_empty()));
+    }
+}
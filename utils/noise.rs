@@ -0,0 +1,316 @@
+//! # Noise Caves
+//!
+//! An alternative to excavating rooms and tunnels (`GenerationAlgorithm::Noise`): the
+//! `nft_id` tunes a noise field whose high ground is dug out, giving open, cave-like
+//! maps in the same `Dungeon` type.
+//!
+//! - **Parameters**: The characters after the room-count character pick the field's
+//!   frequency, octaves, and threshold, then the map's width and height (see
+//!   `NoiseParams::decode`). The noise itself is drawn from the "noise" RNG stream.
+//! - **Caves**: Tiles whose noise exceeds the threshold are dug. Pockets smaller than
+//!   `MIN_CAVE_SIZE` are filled back in; if every pocket is that small, the largest stays.
+//! - **Chambers**: The rooms are the highest points of the field, `CHAMBER_SPACING` tiles
+//!   apart, each claiming the cave tiles within `CHAMBER_RADIUS` of its center. The room
+//!   count is decoded as for rooms and tunnels, but cramped caves hold fewer chambers
+//!   (never fewer than `GenerationConfig::min_rooms`). Chambers are ordered west to
+//!   east, so the entrance is the westmost and the exit the eastmost.
+//! - **Passages**: Tunnels join the chambers, and every cave without a chamber, along a
+//!   minimum spanning tree, so every chamber is reachable. Only the stretches dug through
+//!   rock become tunnel tiles.
+//!
+//! The dungeon type, level (from the dug area), room kinds and names, terrain, and
+//! elevation are derived as for rooms and tunnels. Caves get no rubble or smoothing.
+
+use std::collections::HashSet;
+use rand::Rng;
+use super::analysis::letter_frequency;
+use super::biome::classify;
+use super::config::GenerationConfig;
+use super::dungeon::{Bounds, Dungeon, Room};
+use super::elevation::value_noise;
+use super::error::DungeonError;
+use super::excavator::{char_at, char_to_num, corridor_brush, corridor_width, decode_room_count, generate_tunnels, get_dungeon_level, get_dungeon_type, get_room_kind, Layout, PAYLOAD_START};
+use super::grid::{Grid, Tile};
+use super::hooks::{GenerationObserver, HookAction, Stage};
+use super::naming::{room_name, unique_names};
+use super::regions::label_regions;
+use super::seed::rng_for_stream;
+
+/// Fewest tiles a cave keeps; smaller pockets are filled
+pub const MIN_CAVE_SIZE: usize = 12;
+/// Radius of the cave tiles a chamber claims around its center
+pub const CHAMBER_RADIUS: u32 = 2;
+/// Fewest tiles between chamber centers along either axis, unless a cramped map needs
+/// closer chambers to reach `GenerationConfig::min_rooms`
+pub const CHAMBER_SPACING: i32 = 10;
+
+/// Noise field parameters decoded from an `nft_id`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseParams {
+    /// Lattice points per tile in the coarsest octave; higher gives smaller, busier caves.
+    pub frequency: f64,
+    /// Octaves of finer noise, each with twice the frequency and half the weight of the one before.
+    pub octaves: u32,
+    /// Noise level in [0, 1] above which tiles are dug; higher gives fewer, narrower caves.
+    pub threshold: f64,
+    /// Width of the map in tiles.
+    pub width: usize,
+    /// Height of the map in tiles.
+    pub height: usize,
+}
+
+impl NoiseParams {
+    /// Parameters encoded by the five characters after the room-count character, wrapping
+    /// to reuse characters once past the end of short IDs as room coordinates do
+    /// Every character counts modulo 36, so upper-case letters span the same range as
+    /// the lower-case letters of bech32m IDs.
+    pub fn decode(chars: &[char]) -> NoiseParams {
+        let value = |index: usize| (char_to_num(char_at(chars, PAYLOAD_START + index)) % 36) as usize;
+        let fraction = |index: usize| value(index) as f64 / 35.0;
+        NoiseParams {
+            frequency: 0.05 + 0.1 * fraction(0),
+            octaves: 1 + value(1) as u32 % 4,
+            threshold: 0.42 + 0.16 * fraction(2),
+            width: 40 + value(3),
+            height: 30 + value(4),
+        }
+    }
+
+    /// Tiles between lattice points in the coarsest octave
+    pub fn spacing(&self) -> usize {
+        (1.0 / self.frequency).round() as usize
+    }
+}
+
+/// The noise field of `params` over the map, with (0, 0) at the south-west corner
+pub fn noise_field(params: &NoiseParams, rng: &mut impl Rng) -> Grid<f64> {
+    let (width, height) = (params.width, params.height);
+    let values = value_noise(width, height, params.spacing(), params.octaves, rng);
+    let mut field = Grid::new((0, width as i32 - 1), (0, height as i32 - 1), 0.0);
+    for (index, value) in values.into_iter().enumerate() {
+        field.set(((index % width) as i32, (index / width) as i32), value);
+    }
+    field
+}
+
+/// Caves dug from `field` above `threshold`, each a list of its tiles
+/// Pockets smaller than `MIN_CAVE_SIZE` are left out unless no cave is larger.
+pub fn dig_caves(field: &Grid<f64>, threshold: f64) -> Vec<Vec<(i32, i32)>> {
+    let mut open = Grid::new(field.x_range(), field.y_range(), Tile::Wall);
+    for (pos, &value) in field.iter() {
+        if value > threshold {
+            open.set(pos, Tile::Floor);
+        }
+    }
+
+    let (labels, count) = label_regions(&open);
+    let mut caves = vec![Vec::new(); count];
+    for (pos, label) in labels.iter() {
+        if let Some(label) = *label {
+            caves[label].push(pos);
+        }
+    }
+    // Keep the largest pocket, the first of equal ones, when all are small
+    let largest = caves.iter().enumerate().max_by_key(|&(label, cave)| (cave.len(), std::cmp::Reverse(label))).map(|(label, _)| label);
+    caves
+        .into_iter()
+        .enumerate()
+        .filter(|(label, cave)| cave.len() >= MIN_CAVE_SIZE || Some(*label) == largest)
+        .map(|(_, cave)| cave)
+        .collect()
+}
+
+// Centers of up to `count` chambers on the highest cave tiles, ordered west to east
+// The spacing is halved until at least `min_count` chambers fit, or the tiles run out.
+fn pick_chambers(caves: &[Vec<(i32, i32)>], field: &Grid<f64>, count: usize, min_count: usize) -> Vec<(i32, i32)> {
+    let mut candidates: Vec<(f64, (i32, i32))> = caves.iter().flatten().map(|&pos| (field.get(pos).copied().unwrap_or(0.0), pos)).collect();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let mut spacing = CHAMBER_SPACING;
+    let mut chambers: Vec<(i32, i32)> = Vec::new();
+    loop {
+        chambers.clear();
+        for &(_, pos) in &candidates {
+            if chambers.len() == count {
+                break;
+            }
+            if chambers.iter().all(|&other| (other.0 - pos.0).abs().max((other.1 - pos.1).abs()) >= spacing) {
+                chambers.push(pos);
+            }
+        }
+        if chambers.len() >= min_count || spacing <= 1 {
+            break;
+        }
+        spacing /= 2;
+    }
+    chambers.sort();
+    chambers
+}
+
+/// Excavates a single floor of open caves, the counterpart of `excavator::excavate` for
+/// `GenerationAlgorithm::Noise`, decoding `chars` of the salted `seed_id` and reporting
+/// each step to `observer` as the floor at `depth`
+pub(crate) fn excavate_caves(
+    nft_id: &str,
+    seed_id: &str,
+    chars: &[char],
+    launcher_id: Option<String>,
+    config: &GenerationConfig,
+    observer: &mut dyn GenerationObserver,
+    depth: usize,
+) -> Result<Layout, DungeonError> {
+    let params = NoiseParams::decode(chars);
+    tracing::debug!(?params, "noise parameters");
+    let field = noise_field(&params, &mut rng_for_stream(seed_id, "noise"));
+    let caves = dig_caves(&field, params.threshold);
+    if caves.is_empty() {
+        return Err(DungeonError::NothingExcavated("the noise field left no open ground"));
+    }
+    let cave_tiles: HashSet<(i32, i32)> = caves.iter().flatten().copied().collect();
+
+    // Level the dungeon by the dug area, as rooms are by theirs
+    let area_size = cave_tiles.len() as u64;
+    let dungeon_level = get_dungeon_level(area_size, config.level_band);
+    let (char_frequency, most_frequent_char) = letter_frequency(seed_id);
+    let dungeon_type = get_dungeon_type(&most_frequent_char);
+
+    // Name the chambers like rooms, from the characters that would be their coordinates and shapes
+    let centers = pick_chambers(&caves, &field, decode_room_count(char_at(chars, 4), config), config.min_rooms);
+    let num_rooms = centers.len();
+    let shape_start = 5 + 2 * num_rooms;
+    let kinds: Vec<_> = (0..num_rooms).map(|i| get_room_kind(i, num_rooms, char_at(chars, 5 + 2 * i), char_at(chars, 6 + 2 * i))).collect();
+    let names = unique_names(kinds.iter().enumerate().map(|(i, &kind)| {
+        room_name(&dungeon_type, kind, [char_at(chars, 5 + 2 * i), char_at(chars, 6 + 2 * i), char_at(chars, shape_start + i)])
+    }));
+
+    // Let each chamber claim the cave tiles around it, letting the observer change or veto it
+    observer.on_stage_started(depth, Stage::Excavate);
+    let radius = CHAMBER_RADIUS as i32;
+    let mut rooms = Vec::new();
+    for (i, ((&center, kind), name)) in centers.iter().zip(kinds).zip(names).enumerate() {
+        let tiles = (-radius..=radius)
+            .flat_map(|dx| (-radius..=radius).map(move |dy| (dx, dy)))
+            .filter(|&(dx, dy)| dx * dx + dy * dy <= radius * radius)
+            .map(|(dx, dy)| (center.0 + dx, center.1 + dy))
+            .filter(|pos| cave_tiles.contains(pos))
+            .collect();
+        let mut room = Room {
+            center,
+            size: CHAMBER_RADIUS,
+            shape: char_at(chars, shape_start + i).to_string(),
+            kind,
+            name,
+            tiles,
+        };
+        if observer.on_room_excavated(depth, i, &mut room) == HookAction::Keep {
+            rooms.push(room);
+        }
+    }
+    if rooms.is_empty() {
+        return Err(DungeonError::NothingExcavated("every room was vetoed"));
+    }
+
+    // Join the chambers, and the caves holding none, with tunnels
+    observer.on_stage_started(depth, Stage::Tunnels);
+    let mut points: Vec<(i32, i32)> = rooms.iter().map(|room| room.center).collect();
+    for cave in &caves {
+        if !rooms.iter().any(|room| cave.contains(&room.center)) {
+            points.push(cave_anchor(cave));
+        }
+    }
+    let tunnel_style = config.tunnel_style.resolve(&dungeon_type);
+    let tunnels: Vec<Vec<(i32, i32)>> = generate_tunnels(&points, tunnel_style, &mut rng_for_stream(seed_id, "tunnels"))
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, mut tunnel)| (observer.on_tunnel_created(depth, index, &mut tunnel) == HookAction::Keep).then_some(tunnel))
+        .collect();
+    let corridor_width = corridor_width(dungeon_level, config.max_corridor_width);
+    let passages: Vec<(i32, i32)> = tunnels.iter().flatten().flat_map(|&pos| corridor_brush(pos, corridor_width)).collect();
+
+    let mut seen = HashSet::new();
+    let excavated: Vec<(i32, i32)> = caves.iter().flatten().copied().chain(passages.iter().copied()).filter(|&pos| seen.insert(pos)).collect();
+
+    // Caves are floor, passages through rock are tunnels
+    let bounds = Bounds::covering(excavated.iter().copied()).expect("caves are never empty");
+    let (x_range, y_range) = ((bounds.x_range.0 + 1, bounds.x_range.1 - 1), (bounds.y_range.0 + 1, bounds.y_range.1 - 1));
+    let mut grid = Grid::new(x_range, y_range, Tile::Wall);
+    for &pos in &excavated {
+        grid.set(pos, if cave_tiles.contains(&pos) { Tile::Floor } else { Tile::Tunnel });
+    }
+    for room in &rooms {
+        grid.set(room.center, Tile::RoomCenter);
+    }
+
+    // Terrain and elevation are added with the rubble of room dungeons (see `excavator::finish_layout`)
+    let dungeon = Dungeon {
+        nft_id: nft_id.to_string(),
+        launcher_id,
+        rooms,
+        tunnels,
+        corridor_width,
+        level: dungeon_level,
+        dungeon_type,
+        biome: classify(seed_id),
+        bounds,
+        area_size,
+        char_frequency,
+        most_frequent_char,
+        excavated,
+        grid,
+        elevation: Grid::default(),
+        monsters: Vec::new(),
+        decorations: Vec::new(),
+        stairs_down: None,
+        floors: Vec::new(),
+        provenance: None,
+        balance: config.balance.clone(),
+        collection_salt: config.collection_salt.clone(),
+        epoch: config.epoch,
+    };
+    Ok(Layout { dungeon, seed_id: seed_id.to_string(), rubble_area: None })
+}
+
+// Tile of the cave closest to its centroid, where a tunnel joins it
+fn cave_anchor(cave: &[(i32, i32)]) -> (i32, i32) {
+    let (sum_x, sum_y) = cave.iter().fold((0i64, 0i64), |(sx, sy), &(x, y)| (sx + x as i64, sy + y as i64));
+    let (cx, cy) = (sum_x / cave.len() as i64, sum_y / cave.len() as i64);
+    cave.iter().copied().min_by_key(|&(x, y)| (x as i64 - cx).pow(2) + (y as i64 - cy).pow(2)).expect("caves are never empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::GenerationAlgorithm;
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    fn noise_config() -> GenerationConfig {
+        GenerationConfig { algorithm: GenerationAlgorithm::Noise, ..GenerationConfig::default() }
+    }
+
+    #[test]
+    fn parameters_are_decoded_within_their_ranges() {
+        let chars: Vec<char> = NFT_ID.chars().collect();
+        let params = NoiseParams::decode(&chars);
+        assert!((0.05..=0.15).contains(&params.frequency));
+        assert!((1..=4).contains(&params.octaves));
+        assert!((0.42..=0.58).contains(&params.threshold));
+        assert!((40..76).contains(&params.width) && (30..66).contains(&params.height));
+        assert!((7..=20).contains(&params.spacing()));
+    }
+
+    #[test]
+    fn noise_dungeons_are_open_connected_caves() {
+        let config = noise_config();
+        let caves = parse_nft_id_with_config(NFT_ID, &config).unwrap();
+        assert_eq!(parse_nft_id_with_config(NFT_ID, &config).unwrap(), caves);
+        assert_ne!(caves.excavated, dungeon().excavated);
+
+        assert!(caves.rooms.len() >= config.min_rooms);
+        assert!(caves.is_fully_connected());
+        assert!(caves.is_solvable());
+        assert!(caves.rooms.windows(2).all(|pair| pair[0].center < pair[1].center));
+        assert_eq!(caves.area_size as usize, caves.grid.iter().filter(|&(_, &tile)| tile != Tile::Wall && tile != Tile::Tunnel).count());
+        assert!(!caves.monsters.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+//! # Structured Output
+//!
+//! Serializes a parsed dungeon (or any other serializable value) as JSON, YAML, or TOML,
+//! so scripts can consume the CLI's `parse --format` output instead of scraping its text.
+//!
+//! - **JSON**: Pretty-printed, the same as `Dungeon::to_json`.
+//! - **YAML**: Block style, with lists of plain values such as coordinates kept on one
+//!   line in flow style. Strings a YAML reader could take for another type are quoted.
+//! - **TOML**: Fields without a value (`None`) are left out, since TOML has no null.
+//!
+//! YAML and TOML go through `serde_json::Value` first, so map keys are always strings
+//! and maps are written in sorted key order.
+
+use std::fmt;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Format of structured output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON.
+    #[default]
+    Json,
+    /// Block-style YAML.
+    Yaml,
+    /// TOML document.
+    Toml,
+}
+
+/// Errors returned while serializing structured output.
+#[derive(Debug)]
+pub enum OutputError {
+    /// The value could not be converted to JSON.
+    Json(serde_json::Error),
+    /// The value has no TOML representation.
+    Toml(toml::ser::Error),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputError::Json(err) => write!(f, "Failed to serialize as JSON: {}", err),
+            OutputError::Toml(err) => write!(f, "Failed to serialize as TOML: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+impl From<serde_json::Error> for OutputError {
+    fn from(err: serde_json::Error) -> OutputError {
+        OutputError::Json(err)
+    }
+}
+
+impl From<toml::ser::Error> for OutputError {
+    fn from(err: toml::ser::Error) -> OutputError {
+        OutputError::Toml(err)
+    }
+}
+
+impl OutputFormat {
+    /// Serializes `value` in this format.
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String, OutputError> {
+        Ok(match self {
+            OutputFormat::Json => serde_json::to_string_pretty(value)?,
+            OutputFormat::Yaml => to_yaml(&serde_json::to_value(value)?),
+            OutputFormat::Toml => toml::to_string(&without_nulls(serde_json::to_value(value)?))?,
+        })
+    }
+}
+
+// `value` as a YAML document
+fn to_yaml(value: &Value) -> String {
+    let mut out = String::new();
+    write_block(value, 0, &mut out);
+    out
+}
+
+// Write `value` as block lines indented by `indent` spaces
+fn write_block(value: &Value, indent: usize, out: &mut String) {
+    let pad = " ".repeat(indent);
+    match value {
+        Value::Object(map) if !is_flow(value) => {
+            for (key, item) in map {
+                out.push_str(&pad);
+                out.push_str(&yaml_string(key));
+                out.push(':');
+                write_entry(item, indent + 2, out);
+            }
+        }
+        Value::Array(items) if !is_flow(value) => {
+            for item in items {
+                if is_flow(item) {
+                    out.push_str(&pad);
+                    out.push('-');
+                    write_entry(item, indent + 2, out);
+                } else {
+                    // Nest the block under the dash, starting on its line
+                    let mut nested = String::new();
+                    write_block(item, indent + 2, &mut nested);
+                    out.push_str(&pad);
+                    out.push_str("- ");
+                    out.push_str(&nested[indent + 2..]);
+                }
+            }
+        }
+        _ => {
+            out.push_str(&pad);
+            out.push_str(&flow(value));
+            out.push('\n');
+        }
+    }
+}
+
+// Write the value of a mapping key or list item after its `:` or `-`
+fn write_entry(value: &Value, indent: usize, out: &mut String) {
+    if is_flow(value) {
+        out.push(' ');
+        out.push_str(&flow(value));
+        out.push('\n');
+    } else {
+        out.push('\n');
+        write_block(value, indent, out);
+    }
+}
+
+// Whether `value` fits on one line: a scalar, an empty collection, or a list of scalars
+fn is_flow(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.is_empty(),
+        Value::Array(items) => items.iter().all(|item| !item.is_array() && !item.is_object()),
+        _ => true,
+    }
+}
+
+// `value` in flow style
+fn flow(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => yaml_string(text),
+        Value::Array(items) => format!("[{}]", items.iter().map(flow).collect::<Vec<_>>().join(", ")),
+        Value::Object(_) => "{}".to_string(),
+    }
+}
+
+// `text` as a YAML scalar, double-quoted unless it reads back as the same plain string
+fn yaml_string(text: &str) -> String {
+    let plain = text.starts_with(|c: char| c.is_ascii_alphabetic())
+        && !text.ends_with(' ')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || " _-./".contains(c))
+        && !["true", "false", "null", "yes", "no", "on", "off", "y", "n"].contains(&text.to_ascii_lowercase().as_str());
+    if plain {
+        text.to_string()
+    } else {
+        // JSON strings are valid double-quoted YAML scalars
+        Value::String(text.to_string()).to_string()
+    }
+}
+
+// `value` with every null field of its objects removed
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter().filter(|(_, item)| !item.is_null()).map(|(key, item)| (key, without_nulls(item))).collect::<Map<_, _>>()),
+        Value::Array(items) => Value::Array(items.into_iter().map(without_nulls).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::super::dungeon::Dungeon;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn dungeons_round_trip_through_json_and_toml() {
+        let dungeon = dungeon();
+        assert_eq!(OutputFormat::Json.serialize(&dungeon).unwrap(), dungeon.to_json().unwrap());
+        let toml = OutputFormat::Toml.serialize(&dungeon).unwrap();
+        assert!(toml.contains(&format!("nft_id = \"{}\"", dungeon.nft_id)));
+        let reloaded: Dungeon = toml::from_str(&toml).unwrap();
+        assert_eq!(reloaded, dungeon);
+    }
+
+    #[test]
+    fn yaml_nests_blocks_and_quotes_ambiguous_strings() {
+        let value = json!({
+            "name": "Crypt of Ash",
+            "center": [3, -4],
+            "launcher_id": null,
+            "rooms": [{"kind": "Entrance", "tiles": [[0, 1], [2, 3]]}, {"kind": "no", "tiles": []}],
+            "tags": {},
+            "level": "12",
+        });
+        let expected = concat!(
+            "center: [3, -4]\n",
+            "launcher_id: null\n",
+            "level: \"12\"\n",
+            "name: Crypt of Ash\n",
+            "rooms:\n",
+            "  - kind: Entrance\n",
+            "    tiles:\n",
+            "      - [0, 1]\n",
+            "      - [2, 3]\n",
+            "  - kind: \"no\"\n",
+            "    tiles: []\n",
+            "tags: {}\n",
+        );
+        assert_eq!(OutputFormat::Yaml.serialize(&value).unwrap(), expected);
+        assert!(OutputFormat::Yaml.serialize(&dungeon()).unwrap().starts_with("area_size: "));
+    }
+}
@@ -0,0 +1,183 @@
+//! # Ownership Gating
+//!
+//! Checks that a wallet owns an NFT before its dungeon is opened, so game servers can
+//! keep a dungeon to the holder of its NFT (see the CLI's `explore --owner`).
+//!
+//! - **Owners**: An `Owner` is a wallet's puzzle hash or a DID, given as an "xch1..."
+//!   address, a "did:chia:1..." DID, or the hex puzzle hash (`Owner::parse`).
+//! - **Proofs**: An `OwnershipProof` is the `nft_info` object of the wallet's
+//!   `nft_get_info` RPC: the NFT's launcher ID, owner puzzle hash, and owner DID. It is
+//!   always fetched from a live wallet (`rpc::ChiaRpcClient::ownership_proof`, `chia-rpc`
+//!   feature); an unsigned proof saved to a file could claim any NFT, so none is read.
+//! - **Verification**: `verify_ownership` accepts the proof when it names the dungeon's
+//!   launcher ID and the owner matches. Only real Chia NFT IDs can be owned; dungeons of
+//!   synthetic IDs are always refused.
+
+use std::fmt;
+use super::chia::{self, Bech32Error, ADDRESS_HRP, DID_HRP};
+use super::dungeon::Dungeon;
+
+/// Human-readable part of testnet wallet addresses
+const TESTNET_ADDRESS_HRP: &str = "txch";
+
+/// Wallet or DID claiming an NFT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Owner {
+    /// Lowercase hex puzzle hash of a wallet address.
+    PuzzleHash(String),
+    /// Lowercase hex launcher ID of a DID.
+    Did(String),
+}
+
+impl Owner {
+    /// Parses an "xch1..." (or testnet "txch1...") address, a "did:chia:1..." DID, or a
+    /// hex puzzle hash.
+    pub fn parse(owner: &str) -> Result<Owner, OwnershipError> {
+        let owner = owner.trim();
+        let lower = owner.to_ascii_lowercase();
+        if lower.starts_with(DID_HRP) {
+            return Ok(Owner::Did(chia::launcher_id_hex(&chia::decode_bech32m(owner, DID_HRP)?)));
+        }
+        for hrp in [ADDRESS_HRP, TESTNET_ADDRESS_HRP] {
+            if lower.starts_with(&format!("{}1", hrp)) {
+                return Ok(Owner::PuzzleHash(chia::launcher_id_hex(&chia::decode_bech32m(owner, hrp)?)));
+            }
+        }
+        let hex = normalize_hex(owner);
+        if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            Ok(Owner::PuzzleHash(hex))
+        } else {
+            Err(OwnershipError::InvalidOwner(owner.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Owner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Owner::PuzzleHash(hex) => write!(f, "puzzle hash 0x{}", hex),
+            Owner::Did(hex) => write!(f, "DID 0x{}", hex),
+        }
+    }
+}
+
+/// Current owner of an NFT, as reported by the wallet's `nft_get_info` RPC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnershipProof {
+    /// Hex launcher ID of the NFT.
+    pub launcher_id: String,
+    /// Hex puzzle hash of the current owner.
+    pub p2_address: String,
+    /// Hex launcher ID of the owner's DID, if assigned.
+    pub owner_did: Option<String>,
+}
+
+impl OwnershipProof {
+    /// Whether the proof names `owner` as the owner.
+    pub fn is_owned_by(&self, owner: &Owner) -> bool {
+        match owner {
+            Owner::PuzzleHash(hex) => normalize_hex(&self.p2_address) == *hex,
+            Owner::Did(hex) => self.owner_did.as_deref().is_some_and(|did| normalize_hex(did) == *hex),
+        }
+    }
+}
+
+/// Errors returned while checking the ownership of an NFT.
+#[derive(Debug)]
+pub enum OwnershipError {
+    /// The owner's address or DID is malformed.
+    Address(Bech32Error),
+    /// The owner is neither an address, a DID, nor a hex puzzle hash.
+    InvalidOwner(String),
+    /// The dungeon was decoded from a synthetic ID, which no one can own.
+    MissingLauncherId,
+    /// The proof is about the NFT with another launcher ID.
+    WrongNft(String),
+    /// The NFT belongs to someone else.
+    NotOwner(Owner),
+}
+
+impl fmt::Display for OwnershipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OwnershipError::Address(err) => write!(f, "Invalid owner address: {}", err),
+            OwnershipError::InvalidOwner(owner) => write!(f, "'{}' is not a wallet address, DID, or puzzle hash.", owner),
+            OwnershipError::MissingLauncherId => write!(f, "Dungeon has no launcher ID; only real Chia NFT IDs can be owned."),
+            OwnershipError::WrongNft(launcher_id) => write!(f, "Ownership proof is for another NFT: 0x{}", normalize_hex(launcher_id)),
+            OwnershipError::NotOwner(owner) => write!(f, "NFT is not owned by {}.", owner),
+        }
+    }
+}
+
+impl std::error::Error for OwnershipError {}
+
+impl From<Bech32Error> for OwnershipError {
+    fn from(err: Bech32Error) -> OwnershipError {
+        OwnershipError::Address(err)
+    }
+}
+
+/// Checks that `proof` shows `owner` holding the NFT of `dungeon`
+pub fn verify_ownership(dungeon: &Dungeon, owner: &Owner, proof: &OwnershipProof) -> Result<(), OwnershipError> {
+    let launcher_id = dungeon.launcher_id.as_deref().ok_or(OwnershipError::MissingLauncherId)?;
+    if normalize_hex(&proof.launcher_id) != normalize_hex(launcher_id) {
+        return Err(OwnershipError::WrongNft(proof.launcher_id.clone()));
+    }
+    if !proof.is_owned_by(owner) {
+        return Err(OwnershipError::NotOwner(owner.clone()));
+    }
+    Ok(())
+}
+
+// Lowercase hex without the "0x" prefix
+fn normalize_hex(hex: &str) -> String {
+    hex.trim().trim_start_matches("0x").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::chia::encode_bech32m;
+    use super::super::excavator::parse_chia_nft_id;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    // Launcher ID 0x00, 0x01, ..., 0x1f
+    const NFT_ID: &str = "nft1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0s9s222c";
+    const LAUNCHER_ID: &str = "0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn proof() -> OwnershipProof {
+        OwnershipProof {
+            launcher_id: LAUNCHER_ID.to_string(),
+            p2_address: format!("0x{}", "CD".repeat(32)),
+            owner_did: Some(format!("0x{}", "ab".repeat(32))),
+        }
+    }
+
+    #[test]
+    fn owners_parse_from_addresses_dids_and_hex() {
+        let puzzle_hash = [0xcd; 32];
+        let address = encode_bech32m(&puzzle_hash, ADDRESS_HRP);
+        assert_eq!(Owner::parse(&address).unwrap(), Owner::PuzzleHash("cd".repeat(32)));
+        assert_eq!(Owner::parse(&encode_bech32m(&puzzle_hash, "txch")).unwrap(), Owner::PuzzleHash("cd".repeat(32)));
+        assert_eq!(Owner::parse(&format!("0x{}", "CD".repeat(32))).unwrap(), Owner::PuzzleHash("cd".repeat(32)));
+        assert_eq!(Owner::parse(&encode_bech32m(&[0xab; 32], DID_HRP)).unwrap(), Owner::Did("ab".repeat(32)));
+        assert!(matches!(Owner::parse(&address[..address.len() - 1]), Err(OwnershipError::Address(Bech32Error::InvalidChecksum))));
+        assert!(matches!(Owner::parse("alice"), Err(OwnershipError::InvalidOwner(_))));
+    }
+
+    #[test]
+    fn only_the_holder_passes_the_gate() {
+        let owned = parse_chia_nft_id(NFT_ID).unwrap();
+        let proof = proof();
+        assert!(verify_ownership(&owned, &Owner::PuzzleHash("cd".repeat(32)), &proof).is_ok());
+        assert!(verify_ownership(&owned, &Owner::Did("ab".repeat(32)), &proof).is_ok());
+        let stranger = Owner::PuzzleHash("ef".repeat(32));
+        let err = verify_ownership(&owned, &stranger, &proof).unwrap_err();
+        assert!(matches!(&err, OwnershipError::NotOwner(owner) if *owner == stranger));
+        assert_eq!(err.to_string(), format!("NFT is not owned by puzzle hash 0x{}.", "ef".repeat(32)));
+
+        let other = OwnershipProof { launcher_id: "0x01".to_string(), ..proof.clone() };
+        assert!(matches!(verify_ownership(&owned, &stranger, &other), Err(OwnershipError::WrongNft(_))));
+        assert!(matches!(verify_ownership(&dungeon(), &stranger, &proof), Err(OwnershipError::MissingLauncherId)));
+    }
+}
@@ -0,0 +1,126 @@
+//! # Pathfinding
+//!
+//! Shortest routes over walkable tiles of the dungeon grid.
+//!
+//! Movement is 4-directional with unit cost, so a breadth-first search already
+//! yields shortest paths; no heuristic is needed.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use super::dungeon::Dungeon;
+use super::grid::{Grid, Tile};
+
+/// Neighbouring tiles reachable in one step
+pub fn neighbors((x, y): (i32, i32)) -> [(i32, i32); 4] {
+    [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+}
+
+/// Shortest 4-directional path between two walkable tiles of `grid`
+/// The returned path includes both `from` and `to`.
+pub fn find_path(grid: &Grid<Tile>, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+    if !grid.is_walkable(from) || !grid.is_walkable(to) {
+        return None;
+    }
+
+    // Breadth-first search, remembering where each tile was reached from
+    let mut came_from: HashMap<(i32, i32), (i32, i32)> = HashMap::from([(from, from)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            break;
+        }
+        for next in neighbors(current) {
+            if grid.is_walkable(next) && !came_from.contains_key(&next) {
+                came_from.insert(next, current);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    // Walk back from the target to rebuild the path
+    came_from.get(&to)?;
+    let mut path = vec![to];
+    let mut current = to;
+    while current != from {
+        current = came_from[&current];
+        path.push(current);
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Walkable tiles of `grid` reachable from `from`, including `from` itself; empty if
+/// `from` is not walkable.
+pub fn reachable_tiles(grid: &Grid<Tile>, from: (i32, i32)) -> HashSet<(i32, i32)> {
+    if !grid.is_walkable(from) {
+        return HashSet::new();
+    }
+    let mut reached = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(current) = queue.pop_front() {
+        for next in neighbors(current) {
+            if grid.is_walkable(next) && reached.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+impl Dungeon {
+    /// Shortest walkable path from `from` to `to`, or `None` if either tile is not
+    /// walkable or no route exists.
+    pub fn path(&self, from: (i32, i32), to: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        find_path(&self.grid, from, to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    // A 5x3 grid with a floor corridor along the top row and a pillar in the middle row
+    fn corridor() -> Grid<Tile> {
+        let mut grid = Grid::new((0, 4), (0, 2), Tile::Wall);
+        for x in 0..=4 {
+            grid.set((x, 0), Tile::Floor);
+        }
+        grid.set((0, 1), Tile::Floor);
+        grid.set((4, 2), Tile::Floor);
+        grid
+    }
+
+    #[test]
+    fn paths_run_between_both_ends() {
+        let path = find_path(&corridor(), (0, 1), (4, 0)).unwrap();
+        assert_eq!(path, vec![(0, 1), (0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+        assert_eq!(find_path(&corridor(), (2, 0), (2, 0)), Some(vec![(2, 0)]));
+    }
+
+    #[test]
+    fn walls_and_gaps_have_no_path() {
+        let grid = corridor();
+        assert_eq!(find_path(&grid, (0, 0), (2, 1)), None);
+        assert_eq!(find_path(&grid, (0, 0), (4, 2)), None);
+        assert_eq!(find_path(&grid, (9, 9), (0, 0)), None);
+    }
+
+    #[test]
+    fn reachable_tiles_stop_at_walls() {
+        let grid = corridor();
+        assert_eq!(reachable_tiles(&grid, (0, 0)).len(), 6);
+        assert_eq!(reachable_tiles(&grid, (4, 2)), HashSet::from([(4, 2)]));
+        assert!(reachable_tiles(&grid, (2, 1)).is_empty());
+    }
+
+    #[test]
+    fn the_exit_can_be_reached_from_the_entrance() {
+        let dungeon = dungeon();
+        let (first, last) = (dungeon.rooms.first().unwrap(), dungeon.rooms.last().unwrap());
+        let path = dungeon.path(first.center, last.center).unwrap();
+        assert_eq!(path.first(), Some(&first.center));
+        assert_eq!(path.last(), Some(&last.center));
+        assert!(path.iter().all(|&pos| dungeon.grid.is_walkable(pos)));
+        assert!(path.windows(2).all(|step| neighbors(step[0]).contains(&step[1])));
+    }
+}
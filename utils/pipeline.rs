@@ -0,0 +1,270 @@
+//! # Staged Pipeline
+//!
+//! Dungeon generation split into cacheable stages, so tweaking a late parameter only
+//! reruns the stages after it:
+//!
+//! 1. **Structure**: The rooms and tunnels of every floor (`Structure`), which depend on
+//!    the `nft_id`, the `ShapeRegistry`, and the fields kept by
+//!    `GenerationConfig::structure_config`.
+//! 2. **Decoration**: Rubble, smoothing, terrain, elevation, stairs, monsters, and
+//!    decoration objects, finishing a structure into a `Dungeon` under the rest of the
+//!    config.
+//! 3. **Render**: The PNG of a finished dungeon in the palette of a `Theme`.
+//!
+//! A `Pipeline` memoizes every stage by the `nft_id` and the JSON of its other inputs, so
+//! changing the rubble density decorates the kept structure again, and changing the theme
+//! only renders again. A dungeon finished from a structure equals the one
+//! `parse_nft_id_with_shapes` decodes from the same ID and config, minus the generation
+//! hooks. Each stage holds its `capacity` most recently used results in an LRU, like
+//! `DungeonCache`; `PipelineStats` counts how often each stage ran.
+
+use std::io::Cursor;
+use std::sync::Arc;
+use image::ImageFormat;
+use super::cache::Lru;
+use super::config::GenerationConfig;
+use super::dungeon::{Dungeon, Room};
+use super::error::DungeonError;
+use super::excavator::{excavate_layout, finish_layout, populate, Layout};
+use super::floors::{floor_count, floor_nft_id, stack_floors};
+use super::hooks::NoObserver;
+use super::plot::render_rgb_image;
+use super::shapes::ShapeRegistry;
+use super::theme::Theme;
+
+/// Results held by each stage of `Pipeline::default`
+pub const DEFAULT_PIPELINE_CAPACITY: usize = 64;
+
+/// The rooms and tunnels of every floor of a dungeon, before decoration (see `pipeline`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Structure {
+    /// Layouts of the floors, the top floor first.
+    floors: Vec<Layout>,
+}
+
+impl Structure {
+    /// Lay out the floors of `nft_id` with the parameters of `config` and the room shapes
+    /// of `shapes`
+    pub fn new(nft_id: &str, config: &GenerationConfig, shapes: &ShapeRegistry) -> Result<Structure, DungeonError> {
+        config.validate()?;
+        let floors = (0..floor_count(nft_id))
+            .map(|depth| {
+                let floor_id = if depth == 0 { nft_id.to_string() } else { floor_nft_id(nft_id, depth) };
+                excavate_layout(&floor_id, config, shapes, &mut NoObserver, depth)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Structure { floors })
+    }
+
+    /// Number of floors, the top floor included
+    pub fn floor_count(&self) -> usize {
+        self.floors.len()
+    }
+
+    /// Rooms of the top floor, before smoothing erodes them
+    pub fn rooms(&self) -> &[Room] {
+        &self.floors[0].dungeon.rooms
+    }
+
+    /// Tunnels of the top floor
+    pub fn tunnels(&self) -> &[Vec<(i32, i32)>] {
+        &self.floors[0].dungeon.tunnels
+    }
+
+    /// Finish the dungeon with the rubble, smoothing, terrain, and balance of `config`;
+    /// its other fields are those the structure was laid out with
+    pub fn decorate(&self, config: &GenerationConfig) -> Result<Dungeon, DungeonError> {
+        config.validate()?;
+        let mut floors = self.floors.iter().enumerate().map(|(depth, layout)| finish_layout(layout.clone(), config, &mut NoObserver, depth));
+        let mut dungeon = floors.next().expect("structures have a top floor");
+        stack_floors(&mut dungeon, floors.collect());
+        populate(&mut dungeon);
+        Ok(dungeon)
+    }
+}
+
+/// How often each stage of a `Pipeline` has run, memoized results aside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PipelineStats {
+    /// Structures laid out.
+    pub structures: usize,
+    /// Structures decorated into dungeons.
+    pub decorations: usize,
+    /// Dungeons rendered.
+    pub renders: usize,
+}
+
+/// Memoized stages of dungeon generation (see `pipeline`).
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    shapes: ShapeRegistry,
+    /// Structures by ID and JSON of the structure config.
+    structures: Lru<(String, String), Arc<Structure>>,
+    /// Dungeons by ID and JSON of the whole config.
+    dungeons: Lru<(String, String), Arc<Dungeon>>,
+    /// Encoded PNGs by ID, JSON of the whole config, and JSON of the theme.
+    renders: Lru<(String, String, String), Arc<Vec<u8>>>,
+    stats: PipelineStats,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline::with_capacity(DEFAULT_PIPELINE_CAPACITY)
+    }
+}
+
+impl Pipeline {
+    /// An empty pipeline excavating the built-in room shapes
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// An empty pipeline holding up to `capacity` results of each stage
+    pub fn with_capacity(capacity: usize) -> Pipeline {
+        Pipeline {
+            shapes: ShapeRegistry::default(),
+            structures: Lru::new(capacity),
+            dungeons: Lru::new(capacity),
+            renders: Lru::new(capacity),
+            stats: PipelineStats::default(),
+        }
+    }
+
+    /// An empty pipeline excavating rooms with the shapes of `shapes`
+    pub fn with_shapes(shapes: ShapeRegistry) -> Pipeline {
+        Pipeline { shapes, ..Pipeline::default() }
+    }
+
+    /// The structure of `nft_id` under `config`, laid out unless a config with the same
+    /// structure config already did
+    pub fn structure(&mut self, nft_id: &str, config: &GenerationConfig) -> Result<Arc<Structure>, DungeonError> {
+        let key = (nft_id.to_string(), config_json(&config.structure_config()));
+        if let Some(structure) = self.structures.get(&key) {
+            return Ok(Arc::clone(structure));
+        }
+        let structure = Arc::new(Structure::new(nft_id, config, &self.shapes)?);
+        self.stats.structures += 1;
+        self.structures.insert(key, Arc::clone(&structure));
+        Ok(structure)
+    }
+
+    /// The dungeon of `nft_id` under `config`, decorated from its memoized structure
+    /// unless the same config already did
+    pub fn dungeon(&mut self, nft_id: &str, config: &GenerationConfig) -> Result<Arc<Dungeon>, DungeonError> {
+        let key = (nft_id.to_string(), config_json(config));
+        if let Some(dungeon) = self.dungeons.get(&key) {
+            return Ok(Arc::clone(dungeon));
+        }
+        let dungeon = Arc::new(self.structure(nft_id, config)?.decorate(config)?);
+        self.stats.decorations += 1;
+        self.dungeons.insert(key, Arc::clone(&dungeon));
+        Ok(dungeon)
+    }
+
+    /// The map of the dungeon of `nft_id` under `config` in the palette of `theme`, as an
+    /// encoded PNG, rendered from the memoized dungeon unless already rendered
+    pub fn render_png(&mut self, nft_id: &str, config: &GenerationConfig, theme: &Theme) -> Result<Arc<Vec<u8>>, Box<dyn std::error::Error>> {
+        let key = (nft_id.to_string(), serde_json::to_string(config)?, serde_json::to_string(theme)?);
+        if let Some(png) = self.renders.get(&key) {
+            return Ok(Arc::clone(png));
+        }
+        let image = render_rgb_image(&*self.dungeon(nft_id, config)?, theme, None)?;
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        let png = Arc::new(png);
+        self.stats.renders += 1;
+        self.renders.insert(key, Arc::clone(&png));
+        Ok(png)
+    }
+
+    /// How often each stage has run
+    pub fn stats(&self) -> PipelineStats {
+        self.stats
+    }
+
+    /// Drop every memoized structure, dungeon, and render
+    pub fn clear(&mut self) {
+        self.structures.clear();
+        self.dungeons.clear();
+        self.renders.clear();
+    }
+}
+
+// JSON of a config, keying the memoized stages
+fn config_json(config: &GenerationConfig) -> String {
+    serde_json::to_string(config).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::GenerationAlgorithm;
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    #[test]
+    fn decorated_structures_match_parsed_dungeons() {
+        let config = GenerationConfig::default();
+        let structure = Structure::new(NFT_ID, &config, &ShapeRegistry::default()).unwrap();
+        assert_eq!(structure.floor_count(), dungeon().floor_count());
+        assert_eq!(structure.tunnels(), dungeon().tunnels);
+        assert_eq!(structure.decorate(&config).unwrap(), dungeon());
+
+        let caves = GenerationConfig { algorithm: GenerationAlgorithm::Noise, terrain: false, ..GenerationConfig::default() };
+        let structure = Structure::new(NFT_ID, &caves, &ShapeRegistry::default()).unwrap();
+        assert_eq!(structure.decorate(&caves).unwrap(), parse_nft_id_with_config(NFT_ID, &caves).unwrap());
+        assert!(structure.decorate(&GenerationConfig { area_per_random_point: 0, ..caves }).is_err());
+    }
+
+    #[test]
+    fn late_changes_rerun_only_the_later_stages() {
+        let mut pipeline = Pipeline::new();
+        let config = GenerationConfig::default();
+        let first = pipeline.dungeon(NFT_ID, &config).unwrap();
+        assert!(Arc::ptr_eq(&first, &pipeline.dungeon(NFT_ID, &config).unwrap()));
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 1, decorations: 1, renders: 0 });
+
+        // Denser rubble decorates the kept structure again
+        let dense = GenerationConfig { area_per_random_point: 10, ..GenerationConfig::default() };
+        assert_eq!(*pipeline.dungeon(NFT_ID, &dense).unwrap(), parse_nft_id_with_config(NFT_ID, &dense).unwrap());
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 1, decorations: 2, renders: 0 });
+
+        // A new theme renders the kept dungeon again
+        let theme = Theme::for_dungeon(&first);
+        let png = pipeline.render_png(NFT_ID, &config, &theme).unwrap();
+        assert!(Arc::ptr_eq(&png, &pipeline.render_png(NFT_ID, &config, &theme).unwrap()));
+        let other = Theme { background: (0, 0, 0), ..theme };
+        assert_ne!(*pipeline.render_png(NFT_ID, &config, &other).unwrap(), *png);
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 1, decorations: 2, renders: 2 });
+
+        // Structural changes lay the dungeon out again
+        let banded = GenerationConfig { level_band: 500, ..GenerationConfig::default() };
+        pipeline.dungeon(NFT_ID, &banded).unwrap();
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 2, decorations: 3, renders: 2 });
+        pipeline.clear();
+        pipeline.dungeon(NFT_ID, &config).unwrap();
+        assert_eq!(pipeline.stats().structures, 3);
+    }
+
+    #[test]
+    fn least_recently_used_results_are_evicted() {
+        let mut pipeline = Pipeline::with_capacity(1);
+        let config = GenerationConfig::default();
+        let dense = GenerationConfig { area_per_random_point: 10, ..GenerationConfig::default() };
+        let first = pipeline.dungeon(NFT_ID, &config).unwrap();
+        pipeline.dungeon(NFT_ID, &dense).unwrap();
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 1, decorations: 2, renders: 0 });
+
+        // The first dungeon was evicted, and is decorated again
+        let again = pipeline.dungeon(NFT_ID, &config).unwrap();
+        assert!(!Arc::ptr_eq(&first, &again));
+        assert_eq!(*first, *again);
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 1, decorations: 3, renders: 0 });
+
+        // Nothing is held without capacity
+        let mut pipeline = Pipeline::with_capacity(0);
+        pipeline.dungeon(NFT_ID, &config).unwrap();
+        pipeline.dungeon(NFT_ID, &config).unwrap();
+        assert_eq!(pipeline.stats(), PipelineStats { structures: 2, decorations: 2, renders: 0 });
+    }
+}
@@ -0,0 +1,913 @@
+//! # Dungeon Map Plotting
+//!
+//! Renders excavated dungeon coordinates to an image using `plotters`.
+//!
+//! Maps are drawn in the palette of a `Theme`: by default the one for the dungeon's
+//! type, or any theme passed to `render_themed_map`. Excavated tiles are drawn as dots
+//! or blocks in the theme's floor color, or in the color of their terrain feature (see
+//! `terrain`), shaded by their elevation (see `elevation`); room centers are drawn as larger markers
+//! colored by room kind (see `room_kind_color`), doors as orange dots, stairs as
+//! triangles, decoration objects (see `decorate`) as small dots in their colors, traps as
+//! small magenta squares, monsters as crosses, and the boss (see `boss`) as a crimson
+//! diamond. `render_floor_map` draws a single floor of a multi-floor dungeon.
+//!
+//! `render_fogged_map` draws the map as seen through a `FogOfWar`: unexplored tiles
+//! are omitted, seen tiles are drawn in the theme's seen color, and monsters only
+//! appear when visible.
+//!
+//! When the dungeon carries on-chain provenance (see `rpc`), the caption names the
+//! owner puzzle hash and minting height.
+//!
+//! `draw_map_frame` is the frame-by-frame hook: it draws the map onto any plotters drawing
+//! area, showing only the tiles a visibility function marks as explored, so animated
+//! renderers (see `animation`) can present one frame per call.
+//! `draw_explorer_frame` does the same through a `Viewport`, marking the player, for
+//! replays of explored runs (see `replay`).
+//!
+//! The output format is selected with `RenderFormat`: `Png` rasterizes the map
+//! with the bitmap backend, while `Svg` writes a scalable vector image suited to
+//! large dungeons and web display. `Isometric` rasterizes a pseudo-3D view instead
+//! (see `isometric`). `render_svg_string` and `render_png_bytes` return
+//! the SVG document or encoded PNG instead of writing a file.
+//!
+//! `render_labeled_map` also writes each room's name (see `naming`) next to its center
+//! marker, on flat and isometric maps alike.
+//!
+//! `render_viewport_map` plots a close-up through a `Viewport`: a center, a zoom scale, and
+//! an output resolution, with an optional minimap of the whole dungeon in the top-right
+//! corner outlining the plotted part. Markers grow with the zoom so close-ups stay
+//! readable. Isometric maps honor the resolution and always show the whole dungeon.
+//!
+//! For high-resolution previews the `Viewport` also sets the DPI, which scales text,
+//! markers, and axis areas by `dpi / DEFAULT_DPI` so a 2048x2048 map looks like an
+//! enlarged 640x480 one, and the margin around the chart. With `fit` set, the image
+//! shrinks along one side to follow the aspect ratio of the plotted area, so tiles are
+//! drawn square instead of stretched to fill the requested size.
+//!
+//! `render_region_map` colors each connected region of excavated tiles (see `regions`)
+//! distinctly instead of in the theme's floor color, so cut-off areas stand out; the
+//! regions cycle through `REGION_COLORS`. Isometric maps keep the theme's colors.
+//!
+//! `render_arena_map` plots a player-versus-player `Arena` (see `arena`) with its center
+//! line drawn across the map and each player's spawn point ringed in `SPAWN_COLORS`.
+//! Isometric arena maps show the tiles alone.
+//!
+//! `render_rgba_image` rasterizes the map into an in-memory RGBA buffer, for servers and
+//! WASM consumers without filesystem access; it also saves the image when given a path.
+//!
+//! Branded previews carrying the NFT ID, a logo, badges, or a QR code are drawn over
+//! raster maps by `watermark`.
+//!
+//! `render_options_map` plots a map with the overlays of a `RenderOptions`: with
+//! `heatmap` set, walkable tiles are tinted by their danger (see `danger`) from green to
+//! red at `HEATMAP_ALPHA` opacity, over their colors and under every marker, so players
+//! can plan their routes. Isometric maps show no heatmap.
+//!
+//! `render_room` plots a close-up of a single room for detailed trait imagery: the map is
+//! cropped to the room and the first `RoomView::stub_length` tiles of each tunnel leaving
+//! it (`Dungeon::room_crop`), and every tile, marker, and room outside the crop is left out.
+
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::path::Path;
+use image::{DynamicImage, ImageFormat, RgbImage, RgbaImage};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use super::arena::Arena;
+use super::danger::danger_color;
+use super::decorate::decoration_color;
+use super::dungeon::{Bounds, Dungeon, RoomKind};
+use super::error::DungeonError;
+use super::fog::{FogOfWar, Visibility};
+use super::grid::{Grid, Tile};
+use super::isometric::draw_isometric_map;
+use super::pathfinding::neighbors;
+use super::regions::label_regions;
+use super::terrain::terrain_color;
+use super::theme::{Rgb, Theme, TileStyle};
+use super::watermark::{watermark_file, RenderOptions};
+
+/// Color of doors where tunnels enter rooms
+pub(crate) const DOOR_COLOR: RGBColor = RGBColor(255, 140, 0);
+/// Color of stairs up to the floor above
+pub(crate) const STAIRS_UP_COLOR: RGBColor = RGBColor(0, 150, 150);
+/// Color of stairs down to the floor below
+pub(crate) const STAIRS_DOWN_COLOR: RGBColor = RGBColor(150, 80, 0);
+/// Color of traps
+const TRAP_COLOR: RGBColor = RGBColor(200, 0, 120);
+/// Color of the boss marker
+pub(crate) const BOSS_COLOR: RGBColor = RGBColor(190, 0, 40);
+/// Opacity of the danger heatmap over the tiles
+pub const HEATMAP_ALPHA: f64 = 0.55;
+/// Colors of the spawn points of player one and player two in arenas
+pub const SPAWN_COLORS: [RGBColor; 2] = [RGBColor(30, 110, 255), RGBColor(255, 60, 30)];
+/// Width and height of rendered maps in pixels
+const MAP_SIZE: (u32, u32) = (640, 480);
+/// Resolution at which text and markers are drawn at their base size
+pub const DEFAULT_DPI: u32 = 96;
+/// Pixels left around the chart by default, at `DEFAULT_DPI`
+const DEFAULT_MARGIN: u32 = 10;
+/// Height of the caption and the x axis labels, and width of the y axis labels, at `DEFAULT_DPI`
+const CAPTION_SIZE: f64 = 30.0;
+const AXIS_LABEL_AREA: f64 = 40.0;
+/// Fewest tiles a zoomed view spans either side of its center
+const MIN_HALF_SPAN: f64 = 2.0;
+/// Fraction of the image width and height taken by the minimap
+const MINIMAP_FRACTION: u32 = 4;
+/// Tiles of each tunnel shown past the edge of a room in close-ups by default
+pub const DEFAULT_STUB_LENGTH: u32 = 4;
+/// Colors of connected regions on region maps, by label
+pub const REGION_COLORS: [RGBColor; 8] = [
+    RGBColor(70, 130, 180),
+    RGBColor(220, 60, 60),
+    RGBColor(60, 170, 80),
+    RGBColor(240, 170, 30),
+    RGBColor(150, 80, 190),
+    RGBColor(30, 180, 180),
+    RGBColor(230, 100, 170),
+    RGBColor(140, 110, 70),
+];
+
+/// Image format produced by the map renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderFormat {
+    /// Raster PNG via the bitmap backend.
+    #[default]
+    Png,
+    /// Scalable SVG via the SVG backend.
+    Svg,
+    /// Raster PNG of the isometric pseudo-3D view.
+    Isometric,
+}
+
+impl RenderFormat {
+    /// Infers the format from a file extension, defaulting to PNG.
+    pub fn from_path(path: &Path) -> RenderFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("svg") => RenderFormat::Svg,
+            _ => RenderFormat::Png,
+        }
+    }
+}
+
+/// Part of the map to plot and the size of the image, for readable close-ups of large
+/// dungeons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    /// Map coordinate at the middle of the image; the middle of the dungeon when `None`.
+    pub center: Option<(i32, i32)>,
+    /// Zoom factor; 1.0 shows the whole dungeon, 2.0 half its width and height.
+    pub scale: f64,
+    /// Width and height of the image in pixels.
+    pub size: (u32, u32),
+    /// Whether to overlay a minimap of the whole dungeon outlining the plotted part.
+    pub minimap: bool,
+    /// Dots per inch; text and markers are scaled by `dpi / DEFAULT_DPI`.
+    pub dpi: u32,
+    /// Pixels left around the chart.
+    pub margin: u32,
+    /// Whether to shrink `size` to the aspect ratio of the plotted area.
+    pub fit: bool,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Viewport { center: None, scale: 1.0, size: MAP_SIZE, minimap: false, dpi: DEFAULT_DPI, margin: DEFAULT_MARGIN, fit: false }
+    }
+}
+
+impl Viewport {
+    /// Inclusive x and y ranges plotted for a dungeon covering `bounds`
+    /// Scales below 1.0 are treated as 1.0; views may extend past the dungeon's edges.
+    pub fn ranges(&self, bounds: &Bounds) -> ((i32, i32), (i32, i32)) {
+        let scale = self.scale.max(1.0);
+        let span = |(low, high): (i32, i32), center: Option<i32>| {
+            let center = center.map_or((low + high) as f64 / 2.0, f64::from);
+            let half = ((high - low) as f64 / scale / 2.0).max(MIN_HALF_SPAN);
+            ((center - half).round() as i32, (center + half).round() as i32)
+        };
+        (span(bounds.x_range, self.center.map(|(x, _)| x)), span(bounds.y_range, self.center.map(|(_, y)| y)))
+    }
+
+    /// Factor by which text, markers, and axis areas grow at this DPI
+    pub fn text_scale(&self) -> f64 {
+        self.dpi.max(1) as f64 / DEFAULT_DPI as f64
+    }
+
+    /// Width and height of the image plotting `bounds`
+    /// Without `fit` this is `size`; with it, one side shrinks so the chart area, inside
+    /// the margin, caption, and axis labels, has the aspect ratio of the plotted ranges.
+    pub fn image_size(&self, bounds: &Bounds) -> (u32, u32) {
+        if !self.fit {
+            return self.size;
+        }
+        let ((x_low, x_high), (y_low, y_high)) = self.ranges(bounds);
+        let scale = self.text_scale();
+        let (extra_x, extra_y) = (
+            2.0 * self.margin as f64 + AXIS_LABEL_AREA * scale,
+            2.0 * self.margin as f64 + (AXIS_LABEL_AREA + CAPTION_SIZE) * scale,
+        );
+        let (width, height) = ((self.size.0 as f64 - extra_x).max(1.0), (self.size.1 as f64 - extra_y).max(1.0));
+        let (span_x, span_y) = ((x_high - x_low).max(1) as f64, (y_high - y_low).max(1) as f64);
+        let pixels_per_tile = (width / span_x).min(height / span_y);
+        let fitted = |span: f64, extra: f64, limit: u32| ((span * pixels_per_tile + extra).round() as u32).clamp(1, limit);
+        (fitted(span_x, extra_x, self.size.0), fitted(span_y, extra_y, self.size.1))
+    }
+}
+
+/// Close-up of a single room plotted by `render_room`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoomView {
+    /// Tiles of each connecting tunnel shown past the room's edge.
+    pub stub_length: u32,
+    /// Image size, zoom, DPI, margin, and fit; the view is centered on the crop, without
+    /// a minimap.
+    pub viewport: Viewport,
+    /// Whether to write the room's name next to its center.
+    pub labels: bool,
+}
+
+impl Default for RoomView {
+    fn default() -> Self {
+        RoomView { stub_length: DEFAULT_STUB_LENGTH, viewport: Viewport { fit: true, ..Viewport::default() }, labels: false }
+    }
+}
+
+/// Tiles of one room and of the tunnel stubs leaving it (see `Dungeon::room_crop`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomCrop {
+    /// Excavated tiles shown in the close-up.
+    pub tiles: HashSet<(i32, i32)>,
+    /// Bounds of the tiles, padded by one tile.
+    pub bounds: Bounds,
+}
+
+impl Dungeon {
+    /// Excavated tiles of the room at `index` (0-based) and of the tunnels leaving it, up
+    /// to `stub_length` steps from its edge and never into another room; `None` without
+    /// such a room
+    pub fn room_crop(&self, index: usize, stub_length: u32) -> Option<RoomCrop> {
+        let room = self.rooms.get(index)?;
+        let others: HashSet<(i32, i32)> =
+            self.rooms.iter().enumerate().filter(|&(i, _)| i != index).flat_map(|(_, other)| other.tiles.iter().copied()).collect();
+        let open = |pos: (i32, i32)| self.grid.get(pos).is_some_and(|&tile| tile != Tile::Wall);
+        let mut tiles: HashSet<(i32, i32)> = room.tiles.iter().copied().filter(|&pos| open(pos)).collect();
+
+        // Walk out along the tunnels one step at a time
+        let mut frontier: Vec<(i32, i32)> = tiles.iter().copied().collect();
+        for _ in 0..stub_length {
+            frontier = frontier.into_iter().flat_map(neighbors).filter(|&pos| open(pos) && !others.contains(&pos) && tiles.insert(pos)).collect();
+        }
+        let bounds = Bounds::covering(tiles.iter().copied().chain([room.center]))?;
+        Some(RoomCrop { tiles, bounds })
+    }
+}
+
+// What is drawn over the tiles of a map
+#[derive(Default)]
+struct Overlays<'a> {
+    // Room names next to their centers
+    labels: bool,
+    // Position of the player
+    player: Option<(i32, i32)>,
+    // Region labels coloring the tiles
+    regions: Option<&'a Grid<Option<usize>>>,
+    // Arena whose center line and spawn points are marked
+    arena: Option<&'a Arena>,
+    // Danger of each tile, tinting the tiles as a heatmap
+    danger: Option<&'a Grid<f64>>,
+}
+
+/// Color of the connected region with the given label
+pub fn region_color(label: usize) -> RGBColor {
+    REGION_COLORS[label % REGION_COLORS.len()]
+}
+
+/// Marker color for the center of a room of the given kind
+pub fn room_kind_color(kind: RoomKind) -> RGBColor {
+    match kind {
+        RoomKind::Entrance => RGBColor(0, 170, 0),
+        RoomKind::Exit => RGBColor(0, 90, 220),
+        RoomKind::Treasure => RGBColor(230, 180, 0),
+        RoomKind::Boss => RGBColor(0, 0, 0),
+        RoomKind::Shrine => RGBColor(170, 0, 200),
+        RoomKind::Normal => RGBColor(120, 120, 120),
+    }
+}
+
+/// Generate and plot the dungeon map as a PNG, saving it to `out_path`
+pub fn plot_dungeon_map(dungeon: &Dungeon, out_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    render_dungeon_map(dungeon, out_path, RenderFormat::Png)
+}
+
+/// Generate and plot the dungeon map in the given `format`, saving it to `out_path`
+pub fn render_dungeon_map(
+    dungeon: &Dungeon,
+    out_path: &Path,
+    format: RenderFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render_themed_map(dungeon, out_path, format, &Theme::for_dungeon(dungeon))
+}
+
+/// Generate and plot the dungeon map in the palette of `theme`, saving it to `out_path`
+pub fn render_themed_map(
+    dungeon: &Dungeon,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render_map(dungeon, out_path, format, theme, &all_visible, &Viewport::default(), &Overlays::default())
+}
+
+/// Plot the dungeon map in the palette of `theme` with every room labeled by its name,
+/// saving it to `out_path`
+pub fn render_labeled_map(
+    dungeon: &Dungeon,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render_map(dungeon, out_path, format, theme, &all_visible, &Viewport::default(), &Overlays { labels: true, ..Overlays::default() })
+}
+
+/// Plot the part of the dungeon map within `viewport` in the palette of `theme`, saving
+/// it to `out_path`; rooms are labeled by name when `labels` is set
+pub fn render_viewport_map(
+    dungeon: &Dungeon,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+    viewport: &Viewport,
+    labels: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render_map(dungeon, out_path, format, theme, &all_visible, viewport, &Overlays { labels, ..Overlays::default() })
+}
+
+/// Plot the part of the dungeon map within `viewport` with each connected region in its
+/// own color, saving it to `out_path`; rooms are labeled by name when `labels` is set
+pub fn render_region_map(
+    dungeon: &Dungeon,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+    viewport: &Viewport,
+    labels: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (regions, _) = label_regions(&dungeon.grid);
+    render_map(dungeon, out_path, format, theme, &all_visible, viewport, &Overlays { labels, regions: Some(&regions), ..Overlays::default() })
+}
+
+/// Plot the combined map of `arena` with its center line and spawn points, saving it to
+/// `out_path`; rooms are labeled by name when `labels` is set
+pub fn render_arena_map(
+    arena: &Arena,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+    labels: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    render_map(&arena.dungeon, out_path, format, theme, &all_visible, &Viewport::default(), &Overlays { labels, arena: Some(arena), ..Overlays::default() })
+}
+
+/// Plot the part of the dungeon map within `viewport` in the palette of `theme` with the
+/// overlays of `options`, saving it to `out_path`; rooms are labeled by name when
+/// `labels` is set. Watermarks need a raster `format`.
+pub fn render_options_map(
+    dungeon: &Dungeon,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+    viewport: &Viewport,
+    labels: bool,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.has_watermarks() && format == RenderFormat::Svg {
+        return Err("watermarks need a raster image format".into());
+    }
+    let danger = options.heatmap.then(|| dungeon.danger_map());
+    render_map(dungeon, out_path, format, theme, &all_visible, viewport, &Overlays { labels, danger: danger.as_ref(), ..Overlays::default() })?;
+    if options.has_watermarks() {
+        watermark_file(out_path, dungeon, options)?;
+    }
+    Ok(())
+}
+
+/// Plot a close-up of the room at `room_index` (0-based) and the stubs of its tunnels in
+/// the palette of `theme` with the overlays of `options`, saving it to `out_path`.
+/// Watermarks need a raster `format`.
+pub fn render_room(
+    dungeon: &Dungeon,
+    room_index: usize,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+    view: &RoomView,
+    options: &RenderOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if options.has_watermarks() && format == RenderFormat::Svg {
+        return Err("watermarks need a raster image format".into());
+    }
+    let crop = dungeon
+        .room_crop(room_index, view.stub_length)
+        .ok_or(DungeonError::OutOfRange { field: "room", value: room_index as i64 })?;
+    // The whole dungeon stays behind the close-up, so traps and the boss are placed as on the full map
+    let closeup = Dungeon { bounds: crop.bounds, ..dungeon.clone() };
+    let visibility = |pos| if crop.tiles.contains(&pos) { Visibility::Visible } else { Visibility::Unexplored };
+    let viewport = Viewport { center: None, minimap: false, ..view.viewport };
+    let danger = options.heatmap.then(|| dungeon.danger_map());
+    let overlays = Overlays { labels: view.labels, danger: danger.as_ref(), ..Overlays::default() };
+    render_map(&closeup, out_path, format, theme, &visibility, &viewport, &overlays)?;
+    if options.has_watermarks() {
+        watermark_file(out_path, dungeon, options)?;
+    }
+    Ok(())
+}
+
+// Visibility of every tile on maps without fog
+fn all_visible(_: (i32, i32)) -> Visibility {
+    Visibility::Visible
+}
+
+// Plot the map within `viewport` to `out_path` as far as `visibility` shows it, with the
+// given overlays
+fn render_map(
+    dungeon: &Dungeon,
+    out_path: &Path,
+    format: RenderFormat,
+    theme: &Theme,
+    visibility: &dyn Fn((i32, i32)) -> Visibility,
+    viewport: &Viewport,
+    overlays: &Overlays,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let size = viewport.image_size(&dungeon.bounds);
+    match format {
+        RenderFormat::Png => draw_chart(&BitMapBackend::new(out_path, size).into_drawing_area(), dungeon, theme, visibility, viewport, overlays),
+        RenderFormat::Svg => draw_chart(&SVGBackend::new(out_path, size).into_drawing_area(), dungeon, theme, visibility, viewport, overlays),
+        RenderFormat::Isometric => {
+            draw_isometric_map(&BitMapBackend::new(out_path, size).into_drawing_area(), dungeon, theme, visibility, overlays.labels)
+        }
+    }
+}
+
+/// Render the dungeon map as an SVG document in memory
+pub fn render_svg_string(dungeon: &Dungeon) -> Result<String, Box<dyn std::error::Error>> {
+    let mut svg = String::new();
+    let theme = Theme::for_dungeon(dungeon);
+    draw_map(&SVGBackend::with_string(&mut svg, MAP_SIZE).into_drawing_area(), dungeon, None, &theme)?;
+    Ok(svg)
+}
+
+/// Render the dungeon map as an encoded PNG in memory
+pub fn render_png_bytes(dungeon: &Dungeon) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let image = render_rgb_image(dungeon, &Theme::for_dungeon(dungeon), None)?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Render the dungeon map in the palette of `theme` into an in-memory RGBA buffer
+/// When `out_path` is given the image is also saved there, in the format of its extension.
+pub fn render_rgba_image(
+    dungeon: &Dungeon,
+    theme: &Theme,
+    out_path: Option<&Path>,
+) -> Result<RgbaImage, Box<dyn std::error::Error>> {
+    let image = DynamicImage::ImageRgb8(render_rgb_image(dungeon, theme, None)?).into_rgba8();
+    if let Some(out_path) = out_path {
+        image.save(out_path)?;
+    }
+    Ok(image)
+}
+
+/// Plot the floor at `depth` (0 is the top floor), saving it to `out_path`
+pub fn render_floor_map(
+    dungeon: &Dungeon,
+    depth: usize,
+    out_path: &Path,
+    format: RenderFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let floor = dungeon
+        .floor_view(depth)
+        .ok_or(DungeonError::OutOfRange { field: "floor", value: depth as i64 })?;
+    render_dungeon_map(&floor, out_path, format)
+}
+
+/// Plot the dungeon map as seen through `fog`, saving it to `out_path`
+pub fn render_fogged_map(
+    dungeon: &Dungeon,
+    fog: &FogOfWar,
+    out_path: &Path,
+    format: RenderFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let theme = Theme::for_dungeon(dungeon);
+    match format {
+        RenderFormat::Png => draw_map(&BitMapBackend::new(out_path, MAP_SIZE).into_drawing_area(), dungeon, Some(fog), &theme),
+        RenderFormat::Svg => draw_map(&SVGBackend::new(out_path, MAP_SIZE).into_drawing_area(), dungeon, Some(fog), &theme),
+        RenderFormat::Isometric => {
+            draw_isometric_map(&BitMapBackend::new(out_path, MAP_SIZE).into_drawing_area(), dungeon, &theme, &|pos| fog.visibility(pos), false)
+        }
+    }
+}
+
+// Rasterize the map with the bitmap backend into an RGB image, tinted by `danger` if given
+pub(crate) fn render_rgb_image(dungeon: &Dungeon, theme: &Theme, danger: Option<&Grid<f64>>) -> Result<RgbImage, Box<dyn std::error::Error>> {
+    let (width, height) = MAP_SIZE;
+    let mut pixels = vec![0; (width * height * 3) as usize];
+    let root = BitMapBackend::with_buffer(&mut pixels, MAP_SIZE).into_drawing_area();
+    draw_chart(&root, dungeon, theme, &all_visible, &Viewport::default(), &Overlays { danger, ..Overlays::default() })?;
+    drop(root);
+    Ok(RgbImage::from_raw(width, height, pixels).ok_or("bitmap buffer has the wrong size")?)
+}
+
+// Map title, with the owner and minting height when provenance is known
+pub(crate) fn caption(dungeon: &Dungeon) -> String {
+    match &dungeon.provenance {
+        Some(provenance) => {
+            let owner = provenance.owner_puzzle_hash.trim_start_matches("0x");
+            format!("Dungeon Map (owner 0x{}..., minted at {})", &owner[..owner.len().min(8)], provenance.minting_height)
+        }
+        None => "Dungeon Map".to_string(),
+    }
+}
+
+// Convert a theme color for plotters
+pub(crate) fn rgb((r, g, b): Rgb) -> RGBColor {
+    RGBColor(r, g, b)
+}
+
+// Black or white, whichever reads better on `background`
+pub(crate) fn text_color((r, g, b): Rgb) -> RGBColor {
+    let luminance = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+    if luminance > 128.0 { BLACK } else { WHITE }
+}
+
+// Style of room name labels on `background`, with the font grown by `scale`
+pub(crate) fn label_style(background: Rgb, scale: f64) -> TextStyle<'static> {
+    ("sans-serif", 11.0 * scale).into_font().color(&text_color(background))
+}
+
+// Draw the map onto any plotters backend
+fn draw_map<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    dungeon: &Dungeon,
+    fog: Option<&FogOfWar>,
+    theme: &Theme,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    draw_chart(root, dungeon, theme, &|pos| fog.map_or(Visibility::Visible, |fog| fog.visibility(pos)), &Viewport::default(), &Overlays::default())
+}
+
+/// Draw and present one frame of the map onto `root`, omitting unexplored tiles
+/// Seen tiles use the theme's seen color; monsters only appear on visible tiles.
+pub fn draw_map_frame<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    dungeon: &Dungeon,
+    theme: &Theme,
+    visibility: &dyn Fn((i32, i32)) -> Visibility,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    draw_chart(root, dungeon, theme, visibility, &Viewport::default(), &Overlays::default())
+}
+
+/// Draw and present one frame of an exploration onto `root`: the map within `viewport`
+/// as far as `visibility` reveals it, with the player marked at `player`
+pub fn draw_explorer_frame<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    dungeon: &Dungeon,
+    theme: &Theme,
+    visibility: &dyn Fn((i32, i32)) -> Visibility,
+    viewport: &Viewport,
+    player: (i32, i32),
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    draw_chart(root, dungeon, theme, visibility, viewport, &Overlays { player: Some(player), ..Overlays::default() })
+}
+
+// Draw and present the part of the map within `viewport` onto `root`, writing room names
+// next to the explored room centers, marking the player, and coloring regions and
+// danger as the `overlays` ask
+fn draw_chart<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    dungeon: &Dungeon,
+    theme: &Theme,
+    visibility: &dyn Fn((i32, i32)) -> Visibility,
+    viewport: &Viewport,
+    overlays: &Overlays,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let _span = tracing::debug_span!("render", nft_id = %dungeon.nft_id).entered();
+    let (x_range, y_range) = viewport.ranges(&dungeon.bounds);
+    let text = text_color(theme.background);
+
+    // Tiles outside the view are left out like unexplored ones, and markers grow with the zoom
+    let in_view = |(x, y): (i32, i32)| (x_range.0..=x_range.1).contains(&x) && (y_range.0..=y_range.1).contains(&y);
+    let shown = |pos: (i32, i32)| if in_view(pos) { visibility(pos) } else { Visibility::Unexplored };
+    let scale = viewport.text_scale();
+    let zoom = viewport.scale.max(1.0) * scale;
+    let px = |radius: i32| (radius as f64 * zoom).round() as i32;
+    let axis_area = (AXIS_LABEL_AREA * scale).round() as u32;
+
+    root.fill(&rgb(theme.background))?;
+
+    let mut chart = ChartBuilder::on(root)
+        .caption(caption(dungeon), ("sans-serif", CAPTION_SIZE * scale).into_font().color(&text))
+        .margin(viewport.margin)
+        .x_label_area_size(axis_area)
+        .y_label_area_size(axis_area)
+        .build_cartesian_2d(x_range.0..x_range.1, y_range.0..y_range.1)?;
+
+    chart
+        .configure_mesh()
+        .axis_style(text)
+        .label_style(("sans-serif", 12.0 * scale).into_font().color(&text))
+        .light_line_style(text.mix(0.1))
+        .bold_line_style(text.mix(0.2))
+        .draw()?;
+
+    // Draw the dungeon map in the theme's floor color or the terrain's color, shaded by
+    // elevation, or the theme's seen color under fog; region maps color walkable tiles by region
+    let region = |pos: (i32, i32)| overlays.regions.and_then(|regions| regions.get(pos).copied().flatten());
+    let tile_color = |pos: (i32, i32), tile: Tile| match shown(pos) {
+        Visibility::Unexplored => None,
+        Visibility::Seen => Some(rgb(theme.seen)),
+        Visibility::Visible => match region(pos) {
+            Some(label) => Some(region_color(label)),
+            None => Some(rgb(dungeon.shade(pos, terrain_color(tile).unwrap_or(theme.floor)))),
+        },
+    };
+    let excavated = || dungeon.tiles().filter(|&(_, tile)| tile != Tile::Wall);
+    // Half the on-screen width of a tile, so neighbouring blocks touch
+    let (width, _) = chart.plotting_area().dim_in_pixel();
+    let half = (width as i32 / (x_range.1 - x_range.0).max(1) / 2).max(2);
+    let block = move |color: ShapeStyle| Rectangle::new([(-half, -half), (half, half)], color);
+    match theme.tile_style {
+        TileStyle::Dot => {
+            chart.draw_series(excavated().filter_map(|(pos, tile)| tile_color(pos, tile).map(|color| Circle::new(pos, px(3), color))))?;
+        }
+        TileStyle::Block => {
+            chart.draw_series(excavated().filter_map(|(pos, tile)| tile_color(pos, tile).map(|color| EmptyElement::at(pos) + block(color.filled()))))?;
+        }
+    }
+
+    // Tint visible walkable tiles by their danger, as blocks whatever the tile style
+    if let Some(danger) = overlays.danger {
+        chart.draw_series(excavated().filter(|&(pos, tile)| tile.is_walkable() && shown(pos) == Visibility::Visible).filter_map(|(pos, _)| {
+            danger.get(pos).map(|&value| EmptyElement::at(pos) + block(rgb(danger_color(value)).mix(HEATMAP_ALPHA).filled()))
+        }))?;
+    }
+
+    // Mark room centers by kind
+    chart.draw_series(dungeon.rooms.iter().filter(|room| shown(room.center) != Visibility::Unexplored).map(|room| {
+        Circle::new(room.center, px(6), room_kind_color(room.kind).filled())
+    }))?;
+
+    // Mark doors
+    chart.draw_series(dungeon.grid.iter().filter(|&(pos, &tile)| tile == Tile::Door && shown(pos) != Visibility::Unexplored).map(|(pos, _)| {
+        Circle::new(pos, px(3), DOOR_COLOR.filled())
+    }))?;
+
+    // Mark stairs between floors
+    chart.draw_series(dungeon.grid.iter().filter(|&(pos, _)| shown(pos) != Visibility::Unexplored).filter_map(|(pos, tile)| match tile {
+        Tile::StairsUp => Some(TriangleMarker::new(pos, px(7), STAIRS_UP_COLOR.filled())),
+        Tile::StairsDown => Some(TriangleMarker::new(pos, px(7), STAIRS_DOWN_COLOR.filled())),
+        _ => None,
+    }))?;
+
+    // Dot decoration objects in their colors
+    chart.draw_series(dungeon.decorations.iter().filter(|decoration| shown(decoration.position) != Visibility::Unexplored).map(|decoration| {
+        Circle::new(decoration.position, px(2), rgb(decoration_color(decoration.kind)).filled())
+    }))?;
+
+    // Mark traps
+    chart.draw_series(dungeon.traps().into_iter().filter(|trap| shown(trap.position) != Visibility::Unexplored).map(|trap| {
+        EmptyElement::at(trap.position) + Rectangle::new([(-px(3), -px(3)), (px(3), px(3))], TRAP_COLOR.filled())
+    }))?;
+
+    // Mark monsters
+    chart.draw_series(dungeon.monsters.iter().filter(|monster| shown(monster.position) == Visibility::Visible).map(|monster| {
+        Cross::new(monster.position, px(4), rgb(theme.monster).stroke_width(2))
+    }))?;
+
+    // Mark the boss with a diamond
+    chart.draw_series(dungeon.boss().into_iter().filter(|boss| shown(boss.position) == Visibility::Visible).map(|boss| {
+        let r = px(7);
+        EmptyElement::at(boss.position) + Polygon::new(vec![(0, -r), (r, 0), (0, r), (-r, 0)], BOSS_COLOR.filled())
+    }))?;
+
+    // Draw the arena's center line and ring each player's spawn point
+    if let Some(arena) = overlays.arena {
+        let line = [(arena.center_line, y_range.0), (arena.center_line, y_range.1)];
+        chart.draw_series(std::iter::once(PathElement::new(line, text.stroke_width(1))))?;
+        chart.draw_series(arena.spawns.iter().zip(SPAWN_COLORS).map(|(&pos, color)| Circle::new(pos, px(6), color.stroke_width(3))))?;
+    }
+
+    // Mark the player in the color of the text, so it stands out on any theme
+    chart.draw_series(overlays.player.into_iter().map(|pos| Circle::new(pos, px(4), text.filled())))?;
+
+    // Label rooms by name
+    if overlays.labels {
+        let style = label_style(theme.background, scale);
+        chart.draw_series(dungeon.rooms.iter().filter(|room| shown(room.center) != Visibility::Unexplored).map(|room| {
+            EmptyElement::at(room.center) + Text::new(room.name.clone(), (px(8), -px(6)), style.clone())
+        }))?;
+    }
+
+    if viewport.minimap {
+        draw_minimap(root, dungeon, theme, visibility, (x_range, y_range))?;
+    }
+
+    // Save the plot
+    root.present()?;
+
+    Ok(())
+}
+
+// Overlay a minimap of the whole explored dungeon in the top-right corner of `root`,
+// outlining the plotted `view`
+fn draw_minimap<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    dungeon: &Dungeon,
+    theme: &Theme,
+    visibility: &dyn Fn((i32, i32)) -> Visibility,
+    (view_x, view_y): ((i32, i32), (i32, i32)),
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let (width, height) = root.dim_in_pixel();
+    let size = (width / MINIMAP_FRACTION, height / MINIMAP_FRACTION);
+    let area = root.clone().shrink((width.saturating_sub(size.0 + 10), 10), size);
+    let text = text_color(theme.background);
+    area.fill(&rgb(theme.background))?;
+    area.draw(&Rectangle::new([(0, 0), (size.0 as i32 - 1, size.1 as i32 - 1)], text))?;
+
+    let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+    let mut chart = ChartBuilder::on(&area).margin(3).build_cartesian_2d(x_range.0..x_range.1 + 1, y_range.0..y_range.1 + 1)?;
+    chart.draw_series(dungeon.tiles().filter(|&(pos, tile)| tile != Tile::Wall && visibility(pos) != Visibility::Unexplored).map(|(pos, tile)| {
+        Rectangle::new([pos, (pos.0 + 1, pos.1 + 1)], rgb(terrain_color(tile).unwrap_or(theme.floor)).filled())
+    }))?;
+
+    // The view, clamped to the dungeon so the outline stays inside the minimap
+    let clamp = |(low, high): (i32, i32), (min, max): (i32, i32)| (low.clamp(min, max), high.clamp(min, max) + 1);
+    let ((left, right), (bottom, top)) = (clamp(view_x, x_range), clamp(view_y, y_range));
+    chart.draw_series(std::iter::once(Rectangle::new([(left, bottom), (right, top)], text.stroke_width(1))))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn formats_follow_the_file_extension() {
+        assert_eq!(RenderFormat::from_path(Path::new("map.SVG")), RenderFormat::Svg);
+        assert_eq!(RenderFormat::from_path(Path::new("map.png")), RenderFormat::Png);
+        assert_eq!(RenderFormat::from_path(Path::new("map")), RenderFormat::Png);
+    }
+
+    #[test]
+    fn formats_encode_as_svg_text_or_png_bytes() {
+        let dungeon = dungeon();
+        let dir = std::env::temp_dir().join(format!("chia_dungeon_plot_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (svg_path, png_path) = (dir.join("map.svg"), dir.join("map.png"));
+        render_dungeon_map(&dungeon, &svg_path, RenderFormat::Svg).unwrap();
+        render_dungeon_map(&dungeon, &png_path, RenderFormat::Png).unwrap();
+        let isometric_path = dir.join("isometric.png");
+        render_dungeon_map(&dungeon, &isometric_path, RenderFormat::Isometric).unwrap();
+        assert!(fs::read(&isometric_path).unwrap().starts_with(b"\x89PNG"));
+        let svg = fs::read_to_string(&svg_path).unwrap();
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert!(fs::read(&png_path).unwrap().starts_with(b"\x89PNG"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn room_closeups_show_the_room_and_its_tunnel_stubs() {
+        let dungeon = dungeon();
+        let room = &dungeon.rooms[1];
+        let crop = dungeon.room_crop(1, DEFAULT_STUB_LENGTH).unwrap();
+        assert!(room.tiles.iter().filter(|&&pos| dungeon.grid.get(pos) != Some(&Tile::Wall)).all(|pos| crop.tiles.contains(pos)));
+        assert!(crop.tiles.len() > dungeon.room_crop(1, 0).unwrap().tiles.len());
+        for (i, other) in dungeon.rooms.iter().enumerate().filter(|&(i, _)| i != 1) {
+            assert!(other.tiles.iter().all(|pos| !crop.tiles.contains(pos)), "room {} leaks into the crop", i);
+        }
+        let inside = |(x, y): (i32, i32), bounds: &Bounds| (bounds.x_range.0..=bounds.x_range.1).contains(&x) && (bounds.y_range.0..=bounds.y_range.1).contains(&y);
+        assert!(crop.tiles.iter().all(|&pos| inside(pos, &crop.bounds)));
+        assert!(inside(room.center, &crop.bounds) && crop.bounds != dungeon.bounds);
+        assert_eq!(dungeon.room_crop(dungeon.rooms.len(), DEFAULT_STUB_LENGTH), None);
+
+        let path = std::env::temp_dir().join(format!("chia_dungeon_room_{}.svg", std::process::id()));
+        let view = RoomView { labels: true, ..RoomView::default() };
+        render_room(&dungeon, 1, &path, RenderFormat::Svg, &Theme::classic(), &view, &RenderOptions::default()).unwrap();
+        let svg = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(svg.contains(&room.name) && !svg.contains(&dungeon.rooms[0].name));
+        let missing = render_room(&dungeon, 99, &path, RenderFormat::Png, &Theme::classic(), &view, &RenderOptions::default());
+        assert!(missing.unwrap_err().to_string().contains("room"));
+    }
+
+    #[test]
+    fn region_maps_color_each_region() {
+        let mut dungeon = dungeon();
+        let center = dungeon.rooms[0].center;
+        for (x, y) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            dungeon.grid.set((center.0 + x, center.1 + y), Tile::Wall);
+        }
+        let path = std::env::temp_dir().join(format!("chia_dungeon_regions_{}.svg", std::process::id()));
+        render_region_map(&dungeon, &path, RenderFormat::Svg, &Theme::classic(), &Viewport::default(), false).unwrap();
+        let svg = fs::read_to_string(&path).unwrap().to_uppercase();
+        fs::remove_file(&path).unwrap();
+        // Every region is drawn in its own color
+        let regions = dungeon.components().len();
+        assert!(regions > 1);
+        for color in REGION_COLORS.iter().take(regions) {
+            assert!(svg.contains(&format!("#{:02X}{:02X}{:02X}", color.0, color.1, color.2)), "{:?}", color);
+        }
+        assert_eq!(region_color(REGION_COLORS.len() + 1), REGION_COLORS[1]);
+    }
+
+    #[test]
+    fn viewports_zoom_in_on_their_center() {
+        let bounds = Bounds { x_range: (0, 40), y_range: (-10, 10) };
+        assert_eq!(Viewport::default().ranges(&bounds), ((0, 40), (-10, 10)));
+        let zoomed = Viewport { center: Some((30, 5)), scale: 4.0, ..Viewport::default() };
+        assert_eq!(zoomed.ranges(&bounds), ((25, 35), (3, 8)));
+        // Views never shrink below a few tiles, nor zoom out past the whole dungeon
+        let close = Viewport { scale: 100.0, ..Viewport::default() };
+        assert_eq!(close.ranges(&bounds), ((18, 22), (-2, 2)));
+        assert_eq!(Viewport { scale: 0.5, ..Viewport::default() }.ranges(&bounds), ((0, 40), (-10, 10)));
+    }
+
+    #[test]
+    fn fitted_images_follow_the_aspect_ratio_of_the_view() {
+        let bounds = Bounds { x_range: (0, 40), y_range: (-10, 10) };
+        let square = Viewport { size: (2048, 2048), dpi: 2 * DEFAULT_DPI, ..Viewport::default() };
+        assert_eq!(square.image_size(&bounds), (2048, 2048));
+        assert_eq!(square.text_scale(), 2.0);
+        // The chart area of a fitted image is twice as wide as it is high, like the view
+        let fitted = Viewport { fit: true, ..square };
+        let (width, height) = fitted.image_size(&bounds);
+        assert_eq!(width, 2048);
+        let chart = (width as f64 - 20.0 - 80.0, height as f64 - 20.0 - 140.0);
+        assert!((chart.0 / chart.1 - 2.0).abs() < 0.01, "{:?}", chart);
+    }
+
+    #[test]
+    fn viewport_maps_have_the_requested_size_and_minimap() {
+        let dungeon = dungeon();
+        let theme = Theme::for_dungeon(&dungeon);
+        let dir = std::env::temp_dir().join(format!("chia_dungeon_viewport_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let room = &dungeon.rooms[0];
+        let viewport = Viewport { center: Some(room.center), scale: 3.0, size: (800, 600), minimap: true, ..Viewport::default() };
+        let path = dir.join("closeup.png");
+        render_viewport_map(&dungeon, &path, RenderFormat::Png, &theme, &viewport, true).unwrap();
+        let image = image::open(&path).unwrap().into_rgb8();
+        assert_eq!(image.dimensions(), (800, 600));
+        // The minimap's corner shows floor of the whole dungeon
+        let floor = image::Rgb([theme.floor.0, theme.floor.1, theme.floor.2]);
+        assert!(image.enumerate_pixels().any(|(x, y, &pixel)| x >= 600 && y <= 160 && pixel == floor));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn labeled_maps_name_every_room() {
+        let dungeon = dungeon();
+        let theme = Theme::for_dungeon(&dungeon);
+        let dir = std::env::temp_dir().join(format!("chia_dungeon_labels_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (plain_path, labeled_path) = (dir.join("plain.svg"), dir.join("labeled.svg"));
+        render_themed_map(&dungeon, &plain_path, RenderFormat::Svg, &theme).unwrap();
+        render_labeled_map(&dungeon, &labeled_path, RenderFormat::Svg, &theme).unwrap();
+        render_labeled_map(&dungeon, &dir.join("labeled.png"), RenderFormat::Isometric, &theme).unwrap();
+        let (plain, labeled) = (fs::read_to_string(&plain_path).unwrap(), fs::read_to_string(&labeled_path).unwrap());
+        assert!(dungeon.rooms.iter().all(|room| labeled.contains(&room.name) && !plain.contains(&room.name)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn headless_renders_fill_an_opaque_buffer() {
+        let dungeon = dungeon();
+        let theme = Theme::for_dungeon(&dungeon);
+        let image = render_rgba_image(&dungeon, &theme, None).unwrap();
+        assert_eq!(image.dimensions(), MAP_SIZE);
+        assert_eq!(image.as_raw().len(), (MAP_SIZE.0 * MAP_SIZE.1 * 4) as usize);
+        assert!(image.pixels().all(|pixel| pixel.0[3] == 255));
+        // The map is drawn on the theme's background
+        let (r, g, b) = theme.background;
+        assert!(image.pixels().any(|pixel| pixel.0 == [r, g, b, 255]));
+        assert!(image.pixels().any(|pixel| pixel.0 != [r, g, b, 255]));
+        assert_eq!(render_rgba_image(&dungeon, &theme, None).unwrap(), image);
+    }
+}
@@ -0,0 +1,127 @@
+//! # Progress and Timing
+//!
+//! Wall-clock time spent in each generation `Stage`, so the CLI can report progress on
+//! large dungeons and batches and print per-stage timings with `--verbose`.
+//!
+//! - **Timer**: `StageTimer` is a `GenerationObserver` that times the stages reported by
+//!   `on_stage_started`. Each stage runs until the next one starts or the timer stops;
+//!   callers that render mark `Stage::Render` themselves.
+//! - **Timings**: `StageTimings` holds the time per stage, summed over every floor (and,
+//!   added together, over every dungeon of a batch).
+
+use std::fmt;
+use std::ops::AddAssign;
+use std::time::{Duration, Instant};
+use super::hooks::{GenerationObserver, Stage};
+
+/// Time spent in each generation stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StageTimings {
+    elapsed: [Duration; Stage::ALL.len()],
+}
+
+impl StageTimings {
+    /// Time spent in `stage`.
+    pub fn get(&self, stage: Stage) -> Duration {
+        self.elapsed[stage as usize]
+    }
+
+    /// Time spent in every stage together.
+    pub fn total(&self) -> Duration {
+        self.elapsed.iter().sum()
+    }
+
+    /// Every stage with its time, in pipeline order.
+    pub fn iter(&self) -> impl Iterator<Item = (Stage, Duration)> + '_ {
+        Stage::ALL.iter().map(|&stage| (stage, self.get(stage)))
+    }
+}
+
+impl AddAssign for StageTimings {
+    fn add_assign(&mut self, other: StageTimings) {
+        for (elapsed, more) in self.elapsed.iter_mut().zip(other.elapsed) {
+            *elapsed += more;
+        }
+    }
+}
+
+impl fmt::Display for StageTimings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (stage, elapsed) in self.iter() {
+            writeln!(f, "{:<10} {:>10.2?}", stage, elapsed)?;
+        }
+        write!(f, "{:<10} {:>10.2?}", "total", self.total())
+    }
+}
+
+/// Observer timing every generation stage.
+#[derive(Debug, Clone, Default)]
+pub struct StageTimer {
+    current: Option<(Stage, Instant)>,
+    timings: StageTimings,
+}
+
+impl StageTimer {
+    /// A timer with no stage running.
+    pub fn new() -> StageTimer {
+        StageTimer::default()
+    }
+
+    /// Ends the running stage, if any, and starts timing `stage`.
+    pub fn start(&mut self, stage: Stage) {
+        self.stop();
+        self.current = Some((stage, Instant::now()));
+    }
+
+    /// Ends the running stage, if any.
+    pub fn stop(&mut self) {
+        if let Some((stage, started)) = self.current.take() {
+            self.timings.elapsed[stage as usize] += started.elapsed();
+        }
+    }
+
+    /// The running stage, if any.
+    pub fn current(&self) -> Option<Stage> {
+        self.current.map(|(stage, _)| stage)
+    }
+
+    /// Time spent in each stage that has ended.
+    pub fn timings(&self) -> StageTimings {
+        self.timings
+    }
+}
+
+impl GenerationObserver for StageTimer {
+    fn on_stage_started(&mut self, _depth: usize, stage: Stage) {
+        self.start(stage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::GenerationConfig;
+    use super::super::excavator::parse_nft_id_with_observer;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::super::shapes::ShapeRegistry;
+    use super::*;
+
+    #[test]
+    fn timers_time_every_generation_stage() {
+        let mut timer = StageTimer::new();
+        let timed = parse_nft_id_with_observer(NFT_ID, &GenerationConfig::default(), &ShapeRegistry::new(), &mut timer).unwrap();
+        assert_eq!(timed, dungeon());
+        assert_eq!(timer.current(), Some(Stage::Decorate));
+        timer.start(Stage::Render);
+        timer.stop();
+        assert_eq!(timer.current(), None);
+
+        let timings = timer.timings();
+        assert!(timings.get(Stage::Excavate) > Duration::ZERO);
+        assert_eq!(timings.total(), timings.iter().map(|(_, elapsed)| elapsed).sum());
+        let mut doubled = timings;
+        doubled += timings;
+        assert_eq!(doubled.get(Stage::Decorate), timings.get(Stage::Decorate) * 2);
+        assert_eq!(timings.to_string().lines().count(), Stage::ALL.len() + 1);
+        assert!(timings.to_string().starts_with("parse "));
+    }
+}
@@ -0,0 +1,242 @@
+//! # Lock-and-Key Progression
+//!
+//! Gates the way from the entrance to the exit behind locked rooms whose keys lie in the
+//! rooms before them, so a run unlocks the dungeon step by step.
+//!
+//! - **Critical path**: The rooms the tunnels lead through from the entrance to the exit.
+//! - **Gates**: One to `MAX_GATES` rooms on the critical path, other than the entrance and
+//!   the locked treasure rooms, have every door locked (see `inventory::Lock`).
+//! - **Keys**: The key of each gate lies on a room floor tile reachable once the gates
+//!   before it are open, preferably in the area the previous gate opened up. A gate with
+//!   no such tile is dropped.
+//!
+//! Gate rooms and key tiles come from the "progression" RNG stream of the `nft_id`.
+//!
+//! `Dungeon::progression` exposes the resulting dependency graph: for every lock, the
+//! locks that must be opened before its key can be reached. `Progression::unlock_order`
+//! orders the locks so each key is reachable in time, which proves the dungeon solvable.
+//! Since every key opens any lock and every lock has its own key, a player who spends a
+//! key on the wrong door is still never stuck.
+
+use std::collections::{HashSet, VecDeque};
+use rand::seq::index::sample;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::dungeon::Dungeon;
+use super::inventory::{free_floor, reachable_from, room_doors, Item, Lock};
+use super::seed::rng_for_stream;
+
+/// Most progression gates locked on the critical path
+pub const MAX_GATES: usize = 3;
+
+/// Lock-and-key dependency graph of a dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progression {
+    /// Rooms from the entrance to the exit along the tunnels, as indices into `Dungeon::rooms`.
+    pub critical_path: Vec<usize>,
+    /// Every lock of the dungeon, as returned by `Dungeon::locks`.
+    pub locks: Vec<Lock>,
+    /// For each lock, the indices of the locks that must be opened before its key can be
+    /// reached; `None` when no key for it can be reached at all.
+    pub requires: Vec<Option<Vec<usize>>>,
+}
+
+impl Progression {
+    /// Indices of the locks in an order that opens each one after its requirements, or
+    /// `None` when some lock can never be opened
+    pub fn unlock_order(&self) -> Option<Vec<usize>> {
+        let mut opened = vec![false; self.locks.len()];
+        let mut order = Vec::new();
+        while order.len() < self.locks.len() {
+            let next = (0..self.locks.len()).find(|&lock| {
+                !opened[lock] && self.requires[lock].as_ref().is_some_and(|requires| requires.iter().all(|&other| opened[other]))
+            })?;
+            opened[next] = true;
+            order.push(next);
+        }
+        Some(order)
+    }
+
+    /// Whether every lock can be opened
+    pub fn is_solvable(&self) -> bool {
+        self.unlock_order().is_some()
+    }
+}
+
+impl Dungeon {
+    /// Lock-and-key dependency graph of this dungeon (see `progression`).
+    pub fn progression(&self) -> Progression {
+        let locks = self.locks();
+        let loot = self.loot();
+        let start = self.rooms.first().map_or((0, 0), |room| room.center);
+        let gates: Vec<usize> = (0..locks.len()).filter(|&lock| locks[lock].key.is_some()).collect();
+
+        // Each gate's own key, then the loose keys matched to the remaining locks in order
+        let mut loose = loot
+            .iter()
+            .filter(|loot| loot.item == Item::Key && !locks.iter().any(|lock| lock.key == Some(loot.position)))
+            .map(|loot| loot.position);
+        let keys: Vec<Option<(i32, i32)>> = locks.iter().map(|lock| lock.key.or_else(|| loose.next())).collect();
+
+        // A key requires the gates that must open, in order, before it comes within reach
+        let requires = keys
+            .iter()
+            .map(|key| {
+                let key = (*key)?;
+                (0..=gates.len()).find_map(|open| {
+                    let shut: HashSet<(i32, i32)> = (0..locks.len())
+                        .filter(|lock| !gates[..open].contains(lock))
+                        .flat_map(|lock| locks[lock].doors.iter().copied())
+                        .collect();
+                    reachable_from(self, start, &shut).contains(&key).then(|| gates[..open].to_vec())
+                })
+            })
+            .collect();
+
+        Progression { critical_path: critical_path(self), locks, requires }
+    }
+}
+
+/// Rooms the tunnels lead through from the entrance to the exit, as indices into
+/// `Dungeon::rooms`; empty when the tunnels do not join them
+pub fn critical_path(dungeon: &Dungeon) -> Vec<usize> {
+    let rooms = &dungeon.rooms;
+    if rooms.is_empty() {
+        return Vec::new();
+    }
+    // Each tunnel joins the rooms centered on its two ends
+    let at = |pos: Option<(i32, i32)>| rooms.iter().enumerate().filter(move |(_, room)| Some(room.center) == pos).map(|(index, _)| index);
+    let mut adjacent = vec![Vec::new(); rooms.len()];
+    for tunnel in &dungeon.tunnels {
+        for from in at(tunnel.first().copied()) {
+            for to in at(tunnel.last().copied()) {
+                adjacent[from].push(to);
+                adjacent[to].push(from);
+            }
+        }
+    }
+
+    // Breadth-first search from the entrance, then walk back from the exit
+    let exit = rooms.len() - 1;
+    let mut previous = vec![None; rooms.len()];
+    let mut queue = VecDeque::from([0]);
+    let mut visited = vec![false; rooms.len()];
+    visited[0] = true;
+    while let Some(room) = queue.pop_front() {
+        for &next in &adjacent[room] {
+            if !visited[next] {
+                visited[next] = true;
+                previous[next] = Some(room);
+                queue.push_back(next);
+            }
+        }
+    }
+    if !visited[exit] {
+        return Vec::new();
+    }
+    let mut path = vec![exit];
+    while let Some(room) = previous[*path.last().unwrap()] {
+        path.push(room);
+    }
+    path.reverse();
+    path
+}
+
+/// Lock rooms on the critical path as progression gates, each with its key placed where
+/// it can be reached once the gates before it are open; `locked` are the locks already
+/// placed, which stay shut throughout
+pub fn place_gates(dungeon: &Dungeon, locked: &[Lock]) -> Vec<Lock> {
+    let mut rng = rng_for_stream(&dungeon.seed_id(), "progression");
+    let Some(start) = dungeon.rooms.first().map(|room| room.center) else {
+        return Vec::new();
+    };
+    let eligible: Vec<Lock> = critical_path(dungeon)
+        .into_iter()
+        .skip(1)
+        .filter(|room| !locked.iter().any(|lock| lock.room == *room))
+        .filter_map(|room| {
+            let doors = room_doors(dungeon, room);
+            (!doors.is_empty()).then_some(Lock { room, doors, key: None })
+        })
+        .collect();
+    if eligible.is_empty() {
+        return Vec::new();
+    }
+    let count = rng.gen_range(1..=MAX_GATES).min(eligible.len());
+    let mut picked = sample(&mut rng, eligible.len(), count).into_vec();
+    picked.sort_unstable();
+    let mut gates: Vec<Lock> = picked.into_iter().map(|index| eligible[index].clone()).collect();
+
+    let mut taken: HashSet<(i32, i32)> = dungeon.rooms.iter().map(|room| room.center).collect();
+    let room_tiles = || dungeon.rooms.iter().flat_map(|room| room.tiles.iter().copied());
+    let mut behind = HashSet::new();
+    let mut index = 0;
+    while index < gates.len() {
+        // Reachable with the earlier gates open and this and every later gate shut
+        let shut: HashSet<(i32, i32)> = locked.iter().chain(&gates[index..]).flat_map(|lock| lock.doors.iter().copied()).collect();
+        let reachable = reachable_from(dungeon, start, &shut);
+        let candidates = free_floor(dungeon, room_tiles().filter(|pos| reachable.contains(pos)), &taken);
+        let opened: Vec<(i32, i32)> = candidates.iter().copied().filter(|pos| !behind.contains(pos)).collect();
+        let candidates = if opened.is_empty() { candidates } else { opened };
+        if candidates.is_empty() {
+            gates.remove(index);
+            continue;
+        }
+        let key = candidates[rng.gen_range(0..candidates.len())];
+        taken.insert(key);
+        gates[index].key = Some(key);
+        behind = reachable;
+        index += 1;
+    }
+    gates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::excavator::parse_nft_id;
+    use super::super::fixtures::dungeon;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    #[test]
+    fn the_critical_path_joins_the_entrance_to_the_exit() {
+        let dungeon = dungeon();
+        let path = critical_path(&dungeon);
+        assert_eq!(path.first(), Some(&0));
+        assert_eq!(path.last(), Some(&(dungeon.rooms.len() - 1)));
+        let unique: HashSet<usize> = path.iter().copied().collect();
+        assert_eq!(unique.len(), path.len());
+    }
+
+    #[test]
+    fn gates_lock_the_critical_path_and_stay_solvable() {
+        for seed in 0..6 {
+            let nft_id = generate_nft_id_with(&GenerateOptions { seed: Some(seed), ..GenerateOptions::default() }).unwrap();
+            let dungeon = parse_nft_id(&nft_id).unwrap();
+            let progression = dungeon.progression();
+            let path = &progression.critical_path;
+            for lock in progression.locks.iter().filter(|lock| lock.key.is_some()) {
+                assert!(path[1..].contains(&lock.room), "{}: gate off the critical path", nft_id);
+            }
+            assert!(progression.is_solvable(), "{}: {:?}", nft_id, progression.requires);
+            assert_eq!(progression, dungeon.progression());
+        }
+    }
+
+    #[test]
+    fn later_gates_depend_on_earlier_ones() {
+        let progression = Progression {
+            critical_path: vec![0, 1, 2],
+            locks: vec![
+                Lock { room: 1, doors: vec![(1, 0)], key: Some((0, 1)) },
+                Lock { room: 2, doors: vec![(2, 0)], key: Some((1, 1)) },
+            ],
+            requires: vec![Some(vec![]), Some(vec![0])],
+        };
+        assert_eq!(progression.unlock_order(), Some(vec![0, 1]));
+        let cyclic = Progression { requires: vec![Some(vec![1]), Some(vec![0])], ..progression.clone() };
+        assert!(!cyclic.is_solvable());
+        let keyless = Progression { requires: vec![Some(vec![]), None], ..progression };
+        assert!(!keyless.is_solvable());
+    }
+}
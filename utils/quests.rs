@@ -0,0 +1,283 @@
+//! # Quests and NPCs
+//!
+//! Deterministically derives a small quest graph and its NPCs from the NFT, so game
+//! frontends can build content on top of the dungeon.
+//!
+//! - **NPCs**: A guide waits in the entrance room, a captive is held in one of the
+//!   ordinary rooms, and a hermit tends the first shrine. Names come from the NFT
+//!   characters; positions from the "quests" RNG stream of the `nft_id`.
+//! - **Quests**: Rescue the captive; slay the boss guarding the first boss room and
+//!   retrieve the artifact it holds (or, without a boss room, retrieve it from the first
+//!   treasure room); restore the shrine as a side quest; and finally escape through the
+//!   exit once every main quest is done.
+//! - **Graph**: Each quest lists the quests it `requires`, by index into
+//!   `QuestLog::quests`, so the log forms a small dependency graph.
+//!
+//! Rooms are referred to by index into `Dungeon::rooms`; titles number them from 1 like
+//! the CLI does. `QuestLog` implements `serde::{Serialize, Deserialize}`.
+
+use std::collections::HashSet;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::dungeon::{Dungeon, RoomKind};
+use super::excavator::char_to_num;
+use super::seed::rng_for_stream;
+use super::spawn::bestiary;
+
+const NAME_STARTS: [&str; 12] = ["Al", "Bri", "Cor", "Da", "El", "Fen", "Gar", "Hal", "Ira", "Jor", "Ka", "Lys"];
+const NAME_ENDS: [&str; 12] = ["wyn", "ric", "a", "en", "dra", "mir", "os", "eth", "is", "un", "ara", "iel"];
+const ARTIFACT_ADJECTIVES: [&str; 8] = ["Obsidian", "Gilded", "Whispering", "Shattered", "Ancient", "Moonlit", "Cursed", "Radiant"];
+const ARTIFACT_ITEMS: [&str; 8] = ["Crown", "Chalice", "Amulet", "Blade", "Tome", "Orb", "Sigil", "Lantern"];
+
+/// Role of an NPC in the quest log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NpcRole {
+    /// Waits at the entrance and gives the main quests.
+    Guide,
+    /// Held in a room until rescued.
+    Captive,
+    /// Tends a shrine and gives its side quest.
+    Hermit,
+}
+
+/// A non-player character placed in the dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Npc {
+    /// Generated name.
+    pub name: String,
+    /// Role in the quest log.
+    pub role: NpcRole,
+    /// Index of the room the NPC is in.
+    pub room: usize,
+    /// Tile the NPC stands on.
+    pub position: (i32, i32),
+}
+
+/// What a quest asks the player to do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Objective {
+    /// Free the NPC at index `npc` from `room`.
+    Rescue { npc: usize, room: usize },
+    /// Defeat the boss guarding `room`.
+    Slay { target: String, room: usize },
+    /// Bring `item` back from `room`.
+    Retrieve { item: String, room: usize },
+    /// Restore the shrine in `room`.
+    Restore { room: usize },
+    /// Leave through the exit in `room`.
+    Escape { room: usize },
+}
+
+/// A single quest in the graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quest {
+    /// Human-readable summary.
+    pub title: String,
+    /// What must be done.
+    pub objective: Objective,
+    /// Index of the NPC giving the quest, if any.
+    pub giver: Option<usize>,
+    /// Indices of the quests that must be completed first.
+    pub requires: Vec<usize>,
+    /// Whether the quest is needed to finish the dungeon.
+    pub main: bool,
+}
+
+/// NPCs and quests derived from a dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct QuestLog {
+    /// NFT ID the quests were derived from.
+    pub nft_id: String,
+    /// NPCs in the order they were placed.
+    pub npcs: Vec<Npc>,
+    /// Quests; the escape quest is always last.
+    pub quests: Vec<Quest>,
+}
+
+impl QuestLog {
+    /// Serializes the quest log to pretty-printed JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Reloads a quest log previously written by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<QuestLog> {
+        serde_json::from_str(json)
+    }
+}
+
+impl Dungeon {
+    /// NPCs and quests for this dungeon (see `quests`).
+    pub fn quest_log(&self) -> QuestLog {
+        generate_quests(self)
+    }
+}
+
+/// Derive the NPCs and quest graph of a dungeon
+pub fn generate_quests(dungeon: &Dungeon) -> QuestLog {
+    let mut rng = rng_for_stream(&dungeon.seed_id(), "quests");
+    let chars: Vec<char> = dungeon.seed_id().chars().skip(4).collect();
+    let char_value = |i: usize| chars.get(i % chars.len().max(1)).map_or(0, |&c| char_to_num(c)).unsigned_abs() as usize;
+    let room_of = |kind: RoomKind| dungeon.rooms.iter().position(|room| room.kind == kind);
+
+    let mut log = QuestLog { nft_id: dungeon.nft_id.clone(), ..QuestLog::default() };
+    let mut occupied: HashSet<(i32, i32)> = dungeon.monsters.iter().map(|monster| monster.position).collect();
+    let mut place_npc = |log: &mut QuestLog, role: NpcRole, room: usize, rng: &mut _| {
+        let position = free_tile(dungeon, room, &occupied, rng);
+        occupied.insert(position);
+        let index = log.npcs.len();
+        let name = format!(
+            "{}{}",
+            NAME_STARTS[(char_value(3 * index) + index) % NAME_STARTS.len()],
+            NAME_ENDS[(char_value(3 * index + 1) + index) % NAME_ENDS.len()]
+        );
+        log.npcs.push(Npc { name, role, room, position });
+        index
+    };
+
+    let (Some(entrance), Some(exit)) = (room_of(RoomKind::Entrance), room_of(RoomKind::Exit)) else {
+        return log;
+    };
+    let guide = place_npc(&mut log, NpcRole::Guide, entrance, &mut rng);
+    let mut main_quests = Vec::new();
+
+    // Rescue a captive held in one of the ordinary rooms
+    let normal_rooms: Vec<usize> = (0..dungeon.rooms.len()).filter(|&i| dungeon.rooms[i].kind == RoomKind::Normal).collect();
+    if !normal_rooms.is_empty() {
+        let room = normal_rooms[rng.gen_range(0..normal_rooms.len())];
+        let captive = place_npc(&mut log, NpcRole::Captive, room, &mut rng);
+        main_quests.push(log.quests.len());
+        log.quests.push(Quest {
+            title: format!("Rescue {} in room {}", log.npcs[captive].name, room + 1),
+            objective: Objective::Rescue { npc: captive, room },
+            giver: Some(guide),
+            requires: Vec::new(),
+            main: true,
+        });
+    }
+
+    // Retrieve the artifact, from behind the boss if there is one
+    let artifact = format!(
+        "{} {}",
+        ARTIFACT_ADJECTIVES[char_value(chars.len().saturating_sub(1)) % ARTIFACT_ADJECTIVES.len()],
+        ARTIFACT_ITEMS[char_value(chars.len().saturating_sub(2)) % ARTIFACT_ITEMS.len()]
+    );
+    let mut artifact_requires = Vec::new();
+    if let Some(room) = room_of(RoomKind::Boss) {
+        let species = bestiary(&dungeon.dungeon_type);
+        let target = format!("{} Warlord", species[char_value(room) % species.len()]);
+        artifact_requires.push(log.quests.len());
+        main_quests.push(log.quests.len());
+        log.quests.push(Quest {
+            title: format!("Slay the {} in room {}", target, room + 1),
+            objective: Objective::Slay { target, room },
+            giver: Some(guide),
+            requires: Vec::new(),
+            main: true,
+        });
+    }
+    if let Some(room) = room_of(RoomKind::Boss).or_else(|| room_of(RoomKind::Treasure)) {
+        main_quests.push(log.quests.len());
+        log.quests.push(Quest {
+            title: format!("Retrieve the {} from room {}", artifact, room + 1),
+            objective: Objective::Retrieve { item: artifact, room },
+            giver: Some(guide),
+            requires: artifact_requires,
+            main: true,
+        });
+    }
+
+    // Side quest from the hermit of the first shrine
+    if let Some(room) = room_of(RoomKind::Shrine) {
+        let hermit = place_npc(&mut log, NpcRole::Hermit, room, &mut rng);
+        log.quests.push(Quest {
+            title: format!("Restore the shrine in room {}", room + 1),
+            objective: Objective::Restore { room },
+            giver: Some(hermit),
+            requires: Vec::new(),
+            main: false,
+        });
+    }
+
+    log.quests.push(Quest {
+        title: format!("Escape through the exit in room {}", exit + 1),
+        objective: Objective::Escape { room: exit },
+        giver: Some(guide),
+        requires: main_quests,
+        main: true,
+    });
+
+    log
+}
+
+// Walkable tile of the room not taken by a monster or NPC, falling back to the center
+fn free_tile(dungeon: &Dungeon, room: usize, occupied: &HashSet<(i32, i32)>, rng: &mut impl Rng) -> (i32, i32) {
+    let room = &dungeon.rooms[room];
+    let candidates: Vec<(i32, i32)> = room
+        .tiles
+        .iter()
+        .copied()
+        .filter(|pos| dungeon.grid.is_walkable(*pos) && !occupied.contains(pos))
+        .collect();
+    if candidates.is_empty() {
+        return room.center;
+    }
+    candidates[rng.gen_range(0..candidates.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn npcs_stand_on_free_tiles_of_their_rooms() {
+        let dungeon = dungeon();
+        let log = dungeon.quest_log();
+        assert_eq!(log.nft_id, dungeon.nft_id);
+        assert_eq!(log.npcs[0].role, NpcRole::Guide);
+        assert_eq!(dungeon.rooms[log.npcs[0].room].kind, RoomKind::Entrance);
+        let mut taken: HashSet<(i32, i32)> = dungeon.monsters.iter().map(|monster| monster.position).collect();
+        for npc in &log.npcs {
+            assert!(dungeon.rooms[npc.room].tiles.contains(&npc.position));
+            assert!(dungeon.grid.is_walkable(npc.position));
+            assert!(taken.insert(npc.position), "{} stands on an occupied tile", npc.name);
+        }
+    }
+
+    #[test]
+    fn escape_comes_last_and_requires_every_main_quest() {
+        let log = dungeon().quest_log();
+        let escape = log.quests.last().unwrap();
+        assert!(matches!(escape.objective, Objective::Escape { .. }));
+        let main: Vec<usize> = (0..log.quests.len() - 1).filter(|&i| log.quests[i].main).collect();
+        assert!(!main.is_empty());
+        assert_eq!(escape.requires, main);
+        for (index, quest) in log.quests.iter().enumerate() {
+            assert!(quest.requires.iter().all(|&required| required < index));
+        }
+    }
+
+    #[test]
+    fn the_artifact_lies_behind_the_boss() {
+        let log = dungeon().quest_log();
+        let slay = log.quests.iter().position(|quest| matches!(quest.objective, Objective::Slay { .. })).unwrap();
+        let retrieve = log.quests.iter().find(|quest| matches!(quest.objective, Objective::Retrieve { .. })).unwrap();
+        assert_eq!(retrieve.requires, vec![slay]);
+    }
+
+    #[test]
+    fn quest_logs_are_deterministic_and_round_trip() {
+        let log = dungeon().quest_log();
+        assert_eq!(log, generate_quests(&dungeon()));
+        assert_eq!(QuestLog::from_json(&log.to_json().unwrap()).unwrap(), log);
+    }
+
+    #[test]
+    fn dungeons_without_an_exit_have_no_quests() {
+        let mut dungeon = dungeon();
+        dungeon.rooms.retain(|room| room.kind != RoomKind::Exit);
+        let log = dungeon.quest_log();
+        assert!(log.npcs.is_empty() && log.quests.is_empty());
+    }
+}
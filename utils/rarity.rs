@@ -0,0 +1,233 @@
+//! # Rarity Scoring
+//!
+//! Scores how rare a dungeon is from its attributes, for NFT marketplaces.
+//!
+//! Each attribute is rated from 0 (ordinary) to 1 (as rare as it gets):
+//! - **Dungeon type**: Looked up in `RarityWeights::type_rarity`. By default the types
+//!   whose letters never occur in bech32m (Barrens, Ice Cavern, Ocean Depths) are rare,
+//!   since only synthetic IDs produce them, as are the variant types of upper-case
+//!   letters (`excavator::DUNGEON_VARIANTS`). "Unknown" (no letters at all) is rarest.
+//! - **Room count**: Distance from the middle of the possible room counts; both tiny and
+//!   sprawling dungeons are rare.
+//! - **Shape distribution**: How far the room shapes are from evenly spread. Dungeons
+//!   repeating a few shapes are rare.
+//! - **Level**: The dungeon level relative to `RarityWeights::level_cap`.
+//!
+//! The score is the weighted average of the ratings, scaled to 0-100, and falls in one
+//! of the `RarityTier`s.
+
+use std::collections::HashMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use super::analysis::shannon_entropy;
+use super::config::GenerationConfig;
+use super::dungeon::Dungeon;
+use super::excavator::DUNGEON_VARIANTS;
+
+/// Number of distinct shape characters (0-9, a-z, A-Z)
+const SHAPE_KINDS: usize = 62;
+
+/// Weights of each attribute in the rarity score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RarityWeights {
+    /// Weight of the dungeon type rating.
+    pub dungeon_type: f64,
+    /// Weight of the room count rating.
+    pub room_count: f64,
+    /// Weight of the shape distribution rating.
+    pub shape_distribution: f64,
+    /// Weight of the level rating.
+    pub level: f64,
+    /// Level rated as maximally rare; higher levels are capped.
+    pub level_cap: u64,
+    /// Rating of each dungeon type; unlisted types rate 0.
+    pub type_rarity: HashMap<String, f64>,
+}
+
+impl Default for RarityWeights {
+    fn default() -> RarityWeights {
+        let type_rarity = [("Barrens", 0.8), ("Ice Cavern", 0.8), ("Ocean Depths", 0.8), ("Unknown", 1.0)]
+            .into_iter()
+            .chain(DUNGEON_VARIANTS.iter().map(|&(variant, _)| (variant, 0.8)))
+            .map(|(dungeon_type, rating)| (dungeon_type.to_string(), rating))
+            .collect();
+        RarityWeights {
+            dungeon_type: 3.0,
+            room_count: 2.0,
+            shape_distribution: 1.0,
+            level: 2.0,
+            level_cap: 15,
+            type_rarity,
+        }
+    }
+}
+
+/// Named band of rarity scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RarityTier {
+    /// Score below 20.
+    Common,
+    /// Score from 20 to below 40.
+    Uncommon,
+    /// Score from 40 to below 60.
+    Rare,
+    /// Score from 60 to below 80.
+    Epic,
+    /// Score of 80 or more.
+    Legendary,
+}
+
+impl RarityTier {
+    /// Tier of a 0-100 rarity score
+    pub fn from_score(score: f64) -> RarityTier {
+        match score {
+            s if s >= 80.0 => RarityTier::Legendary,
+            s if s >= 60.0 => RarityTier::Epic,
+            s if s >= 40.0 => RarityTier::Rare,
+            s if s >= 20.0 => RarityTier::Uncommon,
+            _ => RarityTier::Common,
+        }
+    }
+}
+
+impl fmt::Display for RarityTier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// A rarity score with the rating of each attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rarity {
+    /// Weighted score from 0 to 100.
+    pub score: f64,
+    /// Tier of the score.
+    pub tier: RarityTier,
+    /// Dungeon type rating, 0 to 1.
+    pub dungeon_type: f64,
+    /// Room count rating, 0 to 1.
+    pub room_count: f64,
+    /// Shape distribution rating, 0 to 1.
+    pub shape_distribution: f64,
+    /// Level rating, 0 to 1.
+    pub level: f64,
+}
+
+impl Dungeon {
+    /// Rarity score from 0 to 100 with the default weights.
+    pub fn rarity_score(&self) -> f64 {
+        self.rarity(&RarityWeights::default()).score
+    }
+
+    /// Rarity score and per-attribute ratings with the given weights.
+    pub fn rarity(&self, weights: &RarityWeights) -> Rarity {
+        let dungeon_type = weights.type_rarity.get(&self.dungeon_type).copied().unwrap_or(0.0).clamp(0.0, 1.0);
+        let room_count = room_count_rating(self.rooms.len());
+        let shape_distribution = shape_rating(self.rooms.iter().map(|room| room.shape.clone()));
+        let level = if weights.level_cap > 1 {
+            (self.level.saturating_sub(1) as f64 / (weights.level_cap - 1) as f64).min(1.0)
+        } else {
+            1.0
+        };
+
+        let ratings = [
+            (weights.dungeon_type, dungeon_type),
+            (weights.room_count, room_count),
+            (weights.shape_distribution, shape_distribution),
+            (weights.level, level),
+        ];
+        let total_weight: f64 = ratings.iter().map(|&(weight, _)| weight.max(0.0)).sum();
+        let score = if total_weight > 0.0 {
+            100.0 * ratings.iter().map(|&(weight, rating)| weight.max(0.0) * rating).sum::<f64>() / total_weight
+        } else {
+            0.0
+        };
+
+        Rarity {
+            score,
+            tier: RarityTier::from_score(score),
+            dungeon_type,
+            room_count,
+            shape_distribution,
+            level,
+        }
+    }
+}
+
+// Distance of the room count from the middle of the default range, 0 to 1
+fn room_count_rating(rooms: usize) -> f64 {
+    let config = GenerationConfig::default();
+    let middle = (config.min_rooms + config.max_rooms) as f64 / 2.0;
+    let half_range = (config.max_rooms - config.min_rooms) as f64 / 2.0;
+    ((rooms as f64 - middle).abs() / half_range).min(1.0)
+}
+
+// One minus the normalized entropy of the shape counts, 0 to 1
+// A dungeon with every room a different shape rates 0; one shape repeated rates 1.
+fn shape_rating(shapes: impl Iterator<Item = String>) -> f64 {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut rooms = 0;
+    for shape in shapes {
+        *counts.entry(shape).or_insert(0) += 1;
+        rooms += 1;
+    }
+    let max_kinds = rooms.min(SHAPE_KINDS);
+    if max_kinds < 2 {
+        return 0.0;
+    }
+
+    (1.0 - shannon_entropy(counts.into_values()) / (max_kinds as f64).log2()).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn scores_fall_in_their_tiers() {
+        assert_eq!(RarityTier::from_score(0.0), RarityTier::Common);
+        assert_eq!(RarityTier::from_score(19.9), RarityTier::Common);
+        assert_eq!(RarityTier::from_score(20.0), RarityTier::Uncommon);
+        assert_eq!(RarityTier::from_score(59.9), RarityTier::Rare);
+        assert_eq!(RarityTier::from_score(60.0), RarityTier::Epic);
+        assert_eq!(RarityTier::from_score(100.0), RarityTier::Legendary);
+        assert_eq!(RarityTier::Epic.to_string(), "Epic");
+    }
+
+    #[test]
+    fn room_counts_rate_by_distance_from_the_middle() {
+        assert_eq!(room_count_rating(2), 1.0);
+        assert_eq!(room_count_rating(37), 1.0);
+        assert_eq!(room_count_rating(1000), 1.0);
+        assert!(room_count_rating(19) < 0.1);
+    }
+
+    #[test]
+    fn repeated_shapes_are_rarer() {
+        let shapes = |list: &[&str]| shape_rating(list.iter().map(|shape| shape.to_string()));
+        assert_eq!(shapes(&["a", "b", "c", "d"]), 0.0);
+        assert_eq!(shapes(&["a", "a", "a", "a"]), 1.0);
+        assert_eq!(shapes(&["a"]), 0.0);
+        assert!(shapes(&["a", "a", "a", "b"]) > shapes(&["a", "a", "b", "b"]));
+    }
+
+    #[test]
+    fn scores_are_weighted_averages_of_the_ratings() {
+        let dungeon = dungeon();
+        let rarity = dungeon.rarity(&RarityWeights::default());
+        assert_eq!(rarity.dungeon_type, 0.0);
+        assert_eq!(rarity.level, (dungeon.level - 1) as f64 / 14.0);
+        let expected = 100.0 * (2.0 * rarity.room_count + rarity.shape_distribution + 2.0 * rarity.level) / 8.0;
+        assert!((rarity.score - expected).abs() < 1e-9);
+        assert_eq!(rarity.tier, RarityTier::from_score(rarity.score));
+        assert_eq!(dungeon.rarity_score(), rarity.score);
+
+        // Only the type counts when the other weights are zero
+        let weights = RarityWeights { room_count: 0.0, shape_distribution: 0.0, level: 0.0, ..RarityWeights::default() };
+        let unknown = Dungeon { dungeon_type: "Unknown".to_string(), ..dungeon.clone() };
+        assert_eq!(unknown.rarity(&weights).score, 100.0);
+        let weightless = RarityWeights { dungeon_type: 0.0, ..weights };
+        assert_eq!(dungeon.rarity(&weightless).score, 0.0);
+    }
+}
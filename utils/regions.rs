@@ -0,0 +1,122 @@
+//! # Regions
+//!
+//! Connected-component labeling of the excavated dungeon: walkable tiles joined by
+//! 4-directional steps form one `Region`, found by flood fill.
+//!
+//! - **Labels**: `label_regions` numbers the regions of a tile grid in the order their first
+//!   tile appears, row by row from the lowest y, so labels are deterministic.
+//! - **Regions**: `Dungeon::components` lists each region's tiles and the rooms centered in
+//!   it. A sound dungeon keeps every room in one region, next to the small regions of
+//!   isolated rubble (see `decorate`); `Dungeon::validate` reports larger regions as
+//!   disconnected areas, and `plot::render_region_map` draws each in its own color.
+
+use std::collections::VecDeque;
+use super::dungeon::Dungeon;
+use super::grid::{Grid, Tile};
+use super::pathfinding::neighbors;
+
+/// Walkable tiles connected to each other, but to no walkable tile outside.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Label of the region (see `label_regions`).
+    pub label: usize,
+    /// Tiles of the region, in flood-fill order from its first tile.
+    pub tiles: Vec<(i32, i32)>,
+    /// Indices of the rooms whose center lies in the region.
+    pub rooms: Vec<usize>,
+}
+
+impl Region {
+    /// Number of tiles in the region.
+    pub fn size(&self) -> usize {
+        self.tiles.len()
+    }
+}
+
+/// Labels every walkable tile of `grid` with its region, numbered from 0, and returns the
+/// labels with the number of regions; unwalkable tiles have no label.
+pub fn label_regions(grid: &Grid<Tile>) -> (Grid<Option<usize>>, usize) {
+    let mut labels = Grid::new(grid.x_range(), grid.y_range(), None);
+    let mut count = 0;
+    for (start, _) in grid.iter().filter(|(_, tile)| tile.is_walkable()) {
+        if labels.get(start).copied().flatten().is_some() {
+            continue;
+        }
+        // Breadth-first flood fill of a region not labeled yet
+        labels.set(start, Some(count));
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            for next in neighbors(current) {
+                if grid.is_walkable(next) && labels.get(next) == Some(&None) {
+                    labels.set(next, Some(count));
+                    queue.push_back(next);
+                }
+            }
+        }
+        count += 1;
+    }
+    (labels, count)
+}
+
+impl Dungeon {
+    /// The connected excavated regions of the dungeon, in label order.
+    pub fn components(&self) -> Vec<Region> {
+        let (labels, count) = label_regions(&self.grid);
+        let mut regions: Vec<Region> = (0..count).map(|label| Region { label, tiles: Vec::new(), rooms: Vec::new() }).collect();
+        for (pos, label) in labels.iter() {
+            if let Some(label) = *label {
+                regions[label].tiles.push(pos);
+            }
+        }
+        for (index, room) in self.rooms.iter().enumerate() {
+            if let Some(&Some(label)) = labels.get(room.center) {
+                regions[label].rooms.push(index);
+            }
+        }
+        regions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::decorate::CLUSTER_SIZE;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn separate_floors_get_separate_labels() {
+        // Two corridors split by a wall column, and a lone tile
+        let mut grid = Grid::new((0, 4), (0, 2), Tile::Wall);
+        for pos in [(0, 0), (1, 0), (1, 1), (3, 0), (3, 1), (4, 1), (0, 2)] {
+            grid.set(pos, Tile::Floor);
+        }
+        grid.set((4, 0), Tile::Water);
+        let (labels, count) = label_regions(&grid);
+        assert_eq!(count, 3);
+        assert_eq!(labels.get((1, 1)), Some(&Some(0)));
+        assert_eq!(labels.get((4, 1)), Some(&Some(1)));
+        assert_eq!(labels.get((0, 2)), Some(&Some(2)));
+        assert_eq!(labels.get((4, 0)), Some(&None));
+    }
+
+    #[test]
+    fn rooms_share_one_region_until_walled_off() {
+        let mut dungeon = dungeon();
+        let regions = dungeon.components();
+        let (main, rubble): (Vec<_>, Vec<_>) = regions.iter().partition(|region| !region.rooms.is_empty());
+        assert_eq!(main.len(), 1);
+        assert_eq!(main[0].rooms, (0..dungeon.rooms.len()).collect::<Vec<_>>());
+        assert!(rubble.iter().all(|region| region.size() <= CLUSTER_SIZE));
+
+        // Wall in the center of the last room
+        let last = dungeon.rooms.len() - 1;
+        let center = dungeon.rooms[last].center;
+        for pos in neighbors(center) {
+            dungeon.grid.set(pos, Tile::Wall);
+        }
+        let regions = dungeon.components();
+        let walled = regions.iter().find(|region| region.tiles == [center]).unwrap();
+        assert_eq!(walled.rooms, [last]);
+        assert_eq!(regions.iter().map(Region::size).sum::<usize>(), dungeon.excavated_tiles().count());
+    }
+}
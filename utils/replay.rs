@@ -0,0 +1,422 @@
+//! # Exploration Replays
+//!
+//! Records a run through explore mode as a compact log, and plays it back frame by frame
+//! in the terminal or as an animated GIF.
+//!
+//! - **Actions**: One character per action that changed the run: `w`, `a`, `s`, `d` for
+//!   steps (including steps into a fight) and `p` for drinking a potion. Blocked steps
+//!   are left out. Since exploration is deterministic (see `combat`), the actions alone
+//!   rebuild the whole run.
+//! - **Events**: Every fight, pickup, and unlocked door, with the index of the action it
+//!   happened at. Replays check them, so a log that no longer matches its dungeon (for
+//!   example one generated with another `--balance`) fails with `ReplayError::Desync`
+//!   instead of playing a different run.
+//! - **Format**: A `Recording` names the NFT ID and sight radius of the run, and is
+//!   stored as JSON (`Recording::save` and `Recording::load`).
+//!
+//! `Replay` steps through a recording with its own `Explorer`. `play` shows it in the
+//! terminal, one action per frame, and `render_replay_gif` draws one GIF frame per action
+//! with the view following the player (see `plot::draw_explorer_frame`).
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use plotters::prelude::*;
+use serde::{Deserialize, Serialize};
+use super::animation::AnimationOptions;
+use super::dungeon::Dungeon;
+use super::explore::{draw_screen, outcome_message, with_terminal, Direction, Explorer, MoveOutcome};
+use super::plot::{draw_explorer_frame, Viewport};
+use super::theme::Theme;
+
+/// Zoom of the view following the player in replay GIFs
+pub const REPLAY_ZOOM: f64 = 4.0;
+
+/// One recorded action of the player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// A step in a direction, possibly into a fight.
+    Step(Direction),
+    /// Drinking a potion.
+    DrinkPotion,
+}
+
+impl Action {
+    /// Character the action is recorded as.
+    pub fn to_char(self) -> char {
+        match self {
+            Action::Step(Direction::Up) => 'w',
+            Action::Step(Direction::Left) => 'a',
+            Action::Step(Direction::Down) => 's',
+            Action::Step(Direction::Right) => 'd',
+            Action::DrinkPotion => 'p',
+        }
+    }
+
+    /// Action recorded as `c`, if any.
+    pub fn from_char(c: char) -> Option<Action> {
+        match c {
+            'w' => Some(Action::Step(Direction::Up)),
+            'a' => Some(Action::Step(Direction::Left)),
+            's' => Some(Action::Step(Direction::Down)),
+            'd' => Some(Action::Step(Direction::Right)),
+            'p' => Some(Action::DrinkPotion),
+            _ => None,
+        }
+    }
+}
+
+/// Log of an explore-mode run (see `replay`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recording {
+    /// NFT ID of the explored dungeon.
+    pub nft_id: String,
+    /// Sight radius the run was played with.
+    pub sight_radius: u32,
+    /// One character per action (see `Action::to_char`).
+    pub actions: String,
+    /// Fights, pickups, and unlocked doors, each with the index of its action.
+    pub events: Vec<(usize, MoveOutcome)>,
+}
+
+/// Errors returned while loading or replaying a recording.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The recording could not be read or written.
+    Io(io::Error),
+    /// The recording is not valid JSON.
+    Json(serde_json::Error),
+    /// The recording is of a dungeon with another NFT ID.
+    WrongDungeon(String),
+    /// The actions hold a character that is not an action.
+    InvalidAction(char),
+    /// The run no longer matches the recording from the action at this index on.
+    Desync(usize),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "Failed to access recording: {}", err),
+            ReplayError::Json(err) => write!(f, "Invalid recording: {}", err),
+            ReplayError::WrongDungeon(nft_id) => write!(f, "Recording is of another dungeon: {}", nft_id),
+            ReplayError::InvalidAction(c) => write!(f, "Invalid action '{}' in recording", c),
+            ReplayError::Desync(index) => write!(f, "Recording no longer matches the dungeon at action {}", index),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> ReplayError {
+        ReplayError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(err: serde_json::Error) -> ReplayError {
+        ReplayError::Json(err)
+    }
+}
+
+impl Recording {
+    /// Starts an empty recording of a run through the dungeon of `nft_id`.
+    pub fn new(nft_id: &str, sight_radius: u32) -> Recording {
+        Recording { nft_id: nft_id.to_string(), sight_radius, actions: String::new(), events: Vec::new() }
+    }
+
+    /// Records a step in `direction` and its `outcome`; blocked steps are left out.
+    pub fn record_step(&mut self, direction: Direction, outcome: MoveOutcome) {
+        if matches!(outcome, MoveOutcome::Blocked | MoveOutcome::Locked) {
+            return;
+        }
+        if is_event(outcome) {
+            self.events.push((self.actions.len(), outcome));
+        }
+        self.actions.push(Action::Step(direction).to_char());
+    }
+
+    /// Records drinking a potion.
+    pub fn record_potion(&mut self) {
+        self.actions.push(Action::DrinkPotion.to_char());
+    }
+
+    /// Recorded actions, in order.
+    pub fn actions(&self) -> Result<Vec<Action>, ReplayError> {
+        self.actions.chars().map(|c| Action::from_char(c).ok_or(ReplayError::InvalidAction(c))).collect()
+    }
+
+    /// Serializes the recording to JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Reloads a recording previously written by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<Recording> {
+        serde_json::from_str(json)
+    }
+
+    /// Writes the recording to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Reads a recording from `path`.
+    pub fn load(path: &Path) -> Result<Recording, ReplayError> {
+        Ok(Recording::from_json(&fs::read_to_string(path)?)?)
+    }
+}
+
+// Whether a step's outcome is recorded as an event
+fn is_event(outcome: MoveOutcome) -> bool {
+    matches!(outcome, MoveOutcome::Fought(_) | MoveOutcome::PickedUp(_) | MoveOutcome::Unlocked)
+}
+
+// Play `action`, returning the outcome of a step or `None` for a potion; blocked steps
+// and missing potions are desyncs at `index`
+fn play_action(explorer: &mut Explorer, action: Action, index: usize) -> Result<Option<MoveOutcome>, ReplayError> {
+    match action {
+        Action::Step(direction) => match explorer.step(direction) {
+            MoveOutcome::Blocked | MoveOutcome::Locked => Err(ReplayError::Desync(index)),
+            outcome => Ok(Some(outcome)),
+        },
+        Action::DrinkPotion if explorer.drink_potion() => Ok(None),
+        Action::DrinkPotion => Err(ReplayError::Desync(index)),
+    }
+}
+
+/// A recording being played back through its dungeon.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    explorer: Explorer,
+    actions: Vec<Action>,
+    played: usize,
+}
+
+impl Replay {
+    /// Starts replaying `recording` through `dungeon`, first checking that every action
+    /// still plays and every recorded event still happens.
+    pub fn new(dungeon: &Dungeon, recording: &Recording) -> Result<Replay, ReplayError> {
+        if recording.nft_id != dungeon.nft_id {
+            return Err(ReplayError::WrongDungeon(recording.nft_id.clone()));
+        }
+        let actions = recording.actions()?;
+        let explorer = Explorer::with_sight_radius(dungeon, recording.sight_radius);
+
+        let mut check = explorer.clone();
+        let mut events = recording.events.iter();
+        for (index, &action) in actions.iter().enumerate() {
+            let outcome = play_action(&mut check, action, index)?.filter(|&outcome| is_event(outcome));
+            let expected = events.as_slice().first().filter(|&&(at, _)| at == index).map(|&(_, outcome)| outcome);
+            if outcome != expected {
+                return Err(ReplayError::Desync(index));
+            }
+            if expected.is_some() {
+                events.next();
+            }
+        }
+        if let Some(&(index, _)) = events.next() {
+            return Err(ReplayError::Desync(index));
+        }
+
+        Ok(Replay { explorer, actions, played: 0 })
+    }
+
+    /// State of the run after the actions played so far.
+    pub fn explorer(&self) -> &Explorer {
+        &self.explorer
+    }
+
+    /// Number of actions played so far.
+    pub fn played(&self) -> usize {
+        self.played
+    }
+
+    /// Number of actions in the recording.
+    pub fn action_count(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Plays the next action, returning it with the outcome of a step (`None` for a
+    /// potion); `None` once every action is played.
+    pub fn advance(&mut self) -> Option<(Action, Option<MoveOutcome>)> {
+        let action = *self.actions.get(self.played)?;
+        // Every action was checked to play in `Replay::new`
+        let outcome = play_action(&mut self.explorer, action, self.played).ok()?;
+        self.played += 1;
+        Some((action, outcome))
+    }
+}
+
+/// Play `recording` through `dungeon` in the current terminal, one action every
+/// `frame_delay` milliseconds; Space pauses and Q or Esc quits
+pub fn play(dungeon: &Dungeon, recording: &Recording, frame_delay: u32) -> Result<(), ReplayError> {
+    let mut replay = Replay::new(dungeon, recording)?;
+    with_terminal(|stdout| {
+        let mut status = String::from("Space to pause, Q to quit");
+        let mut show_combat_log = false;
+        let mut paused = false;
+        loop {
+            let line = format!("Action {}/{} | {}", replay.played(), replay.action_count(), status);
+            draw_screen(stdout, dungeon, replay.explorer(), &line, show_combat_log)?;
+
+            if event::poll(Duration::from_millis(frame_delay as u64))? {
+                if let Event::Key(key) = event::read()? {
+                    match key.code {
+                        _ if key.kind != KeyEventKind::Press => {}
+                        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char(' ') => paused = !paused,
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+            if paused {
+                continue;
+            }
+
+            match replay.advance() {
+                Some((Action::Step(_), Some(outcome))) => {
+                    show_combat_log = matches!(outcome, MoveOutcome::Fought(_));
+                    if let Some(message) = outcome_message(outcome, replay.explorer().deaths()) {
+                        status = message;
+                    }
+                }
+                Some(_) => {
+                    show_combat_log = false;
+                    status = String::from("You drink a potion and feel refreshed.");
+                }
+                None => {
+                    let line = format!("Action {}/{} | Replay finished. Press any key to exit.", replay.played(), replay.action_count());
+                    draw_screen(stdout, dungeon, replay.explorer(), &line, false)?;
+                    loop {
+                        if let Event::Key(key) = event::read()? {
+                            if key.kind == KeyEventKind::Press {
+                                return Ok(());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })?;
+    Ok(())
+}
+
+/// Render `recording` through `dungeon` as an animated GIF saved to `out_path`, one frame
+/// per action with the view following the player, then hold the last frame
+pub fn render_replay_gif(dungeon: &Dungeon, recording: &Recording, out_path: &Path, options: &AnimationOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::new(dungeon, recording)?;
+    let root = BitMapBackend::gif(out_path, (640, 480), options.frame_delay)?.into_drawing_area();
+    let theme = Theme::for_dungeon(dungeon);
+
+    // Monsters slain so far disappear from the frames
+    let mut frame = dungeon.clone();
+    let mut draw = |replay: &Replay| {
+        let explorer = replay.explorer();
+        frame.monsters = explorer.monsters().to_vec();
+        let viewport = Viewport { center: Some(explorer.player()), scale: REPLAY_ZOOM, ..Viewport::default() };
+        draw_explorer_frame(&root, &frame, &theme, &|pos| explorer.fog().visibility(pos), &viewport, explorer.player())
+    };
+
+    draw(&replay)?;
+    while replay.advance().is_some() {
+        draw(&replay)?;
+    }
+    for _ in 0..options.hold_frames {
+        draw(&replay)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use super::super::combat::CombatOutcome;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    // A recorded run from the entrance into a fight with the first monster behind no locked door
+    fn recorded_run(dungeon: &Dungeon) -> (Recording, Explorer) {
+        let mut explorer = Explorer::new(dungeon);
+        let mut recording = Recording::new(&dungeon.nft_id, explorer.fog().sight_radius());
+        let path = dungeon
+            .monsters
+            .iter()
+            .filter_map(|monster| dungeon.path(explorer.player(), monster.position))
+            .find(|path| !path.iter().any(|&pos| explorer.is_locked(pos)))
+            .unwrap();
+        for step in path.windows(2) {
+            let direction = match (step[1].0 - step[0].0, step[1].1 - step[0].1) {
+                (0, 1) => Direction::Up,
+                (0, -1) => Direction::Down,
+                (-1, 0) => Direction::Left,
+                _ => Direction::Right,
+            };
+            let outcome = explorer.step(direction);
+            recording.record_step(direction, outcome);
+            if matches!(outcome, MoveOutcome::Fought(_) | MoveOutcome::Locked) {
+                break;
+            }
+        }
+        if explorer.drink_potion() {
+            recording.record_potion();
+        }
+        (recording, explorer)
+    }
+
+    #[test]
+    fn replays_rebuild_the_recorded_run() {
+        let dungeon = dungeon();
+        let (recording, explorer) = recorded_run(&dungeon);
+        assert!(recording.events.iter().any(|(_, outcome)| matches!(outcome, MoveOutcome::Fought(CombatOutcome::Victory | CombatOutcome::Defeat))));
+        let recording = Recording::from_json(&recording.to_json().unwrap()).unwrap();
+
+        let mut replay = Replay::new(&dungeon, &recording).unwrap();
+        assert_eq!(replay.action_count(), recording.actions.len());
+        while replay.advance().is_some() {}
+        assert_eq!(replay.played(), replay.action_count());
+        let replayed = replay.explorer();
+        assert_eq!((replayed.player(), replayed.moves(), replayed.player_state()), (explorer.player(), explorer.moves(), explorer.player_state()));
+        assert_eq!(replayed.monsters(), explorer.monsters());
+        assert_eq!(replayed.save(), explorer.save());
+    }
+
+    #[test]
+    fn tampered_recordings_are_rejected() {
+        let dungeon = dungeon();
+        let (recording, _) = recorded_run(&dungeon);
+        let fight = recording.events.last().unwrap().0;
+
+        let mut skipped = recording.clone();
+        skipped.actions.remove(0);
+        assert!(matches!(Replay::new(&dungeon, &skipped), Err(ReplayError::Desync(_))));
+        let mut forged = recording.clone();
+        forged.events.push((fight + 1, MoveOutcome::Unlocked));
+        assert!(matches!(Replay::new(&dungeon, &forged), Err(ReplayError::Desync(index)) if index == fight + 1));
+        let mut invalid = recording.clone();
+        invalid.actions.push('x');
+        assert!(matches!(Replay::new(&dungeon, &invalid), Err(ReplayError::InvalidAction('x'))));
+        let other = Recording::new("nft1other", 8);
+        assert!(matches!(Replay::new(&dungeon, &other), Err(ReplayError::WrongDungeon(_))));
+        assert_eq!(Action::from_char(Action::Step(Direction::Left).to_char()), Some(Action::Step(Direction::Left)));
+    }
+
+    #[test]
+    fn replays_render_as_animated_gifs() {
+        let dungeon = dungeon();
+        let mut recording = recorded_run(&dungeon).0;
+        recording.actions.truncate(3);
+        recording.events.retain(|&(index, _)| index < 3);
+        let path = env::temp_dir().join(format!("chia_dungeon_replay_{}.gif", std::process::id()));
+        render_replay_gif(&dungeon, &recording, &path, &AnimationOptions { frame_delay: 50, hold_frames: 1 }).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+    }
+}
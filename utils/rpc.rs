@@ -0,0 +1,263 @@
+//! # Chia RPC Client
+//!
+//! Fetches on-chain NFT metadata from a Chia wallet RPC server. Only available with
+//! the `chia-rpc` feature.
+//!
+//! The wallet's `nft_get_info` endpoint returns the owner, minting height, and
+//! metadata URIs of an NFT given its launcher ID. `enrich_dungeon` stores them in
+//! `Dungeon::provenance`, which the plotted map shows in its caption, and
+//! `ChiaRpcClient::ownership_proof` returns them as proof of who holds the NFT (see
+//! `ownership`). `ChiaRpcClient::height` returns the wallet's current block height, the
+//! epoch of living dungeons (see `epoch`).
+//!
+//! Chia RPC servers require mutual TLS with the private wallet certificate
+//! (`~/.chia/mainnet/config/ssl/wallet/private_wallet.crt` and `.key`) and present a
+//! certificate signed by the node's private CA (`~/.chia/mainnet/config/ssl/ca/private_ca.crt`);
+//! connect with `ChiaRpcClient::with_certificate`, or with `ChiaRpcClient::new` when a
+//! proxy terminates TLS in front of the wallet.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use super::dungeon::{Dungeon, Provenance};
+use super::ownership::OwnershipProof;
+
+/// Default address of a local Chia wallet RPC server
+pub const DEFAULT_WALLET_RPC_URL: &str = "https://localhost:9256";
+
+/// Errors returned while querying the Chia RPC.
+#[derive(Debug)]
+pub enum RpcError {
+    /// The TLS certificate or key could not be read.
+    Io(io::Error),
+    /// The request failed or the response could not be decoded.
+    Http(reqwest::Error),
+    /// The RPC server reported an error.
+    Rpc(String),
+    /// The dungeon was decoded from a synthetic ID and has no launcher ID.
+    MissingLauncherId,
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcError::Io(err) => write!(f, "Failed to read RPC certificate: {}", err),
+            RpcError::Http(err) => write!(f, "RPC request failed: {}", err),
+            RpcError::Rpc(message) => write!(f, "RPC server returned an error: {}", message),
+            RpcError::MissingLauncherId => write!(f, "Dungeon has no launcher ID; only real Chia NFT IDs can be looked up."),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RpcError::Io(err) => Some(err),
+            RpcError::Http(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RpcError {
+    fn from(err: io::Error) -> RpcError {
+        RpcError::Io(err)
+    }
+}
+
+impl From<reqwest::Error> for RpcError {
+    fn from(err: reqwest::Error) -> RpcError {
+        RpcError::Http(err)
+    }
+}
+
+/// On-chain NFT details returned by the wallet's `nft_get_info` endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NftInfo {
+    /// Hex launcher ID.
+    pub launcher_id: String,
+    /// Hex ID of the coin currently holding the NFT.
+    pub nft_coin_id: String,
+    /// DID of the current owner, if assigned.
+    pub owner_did: Option<String>,
+    /// Hex puzzle hash of the current owner.
+    pub p2_address: String,
+    /// Block height the NFT was minted at.
+    pub mint_height: u64,
+    /// Content URIs.
+    #[serde(default)]
+    pub data_uris: Vec<String>,
+    /// Metadata URIs.
+    #[serde(default)]
+    pub metadata_uris: Vec<String>,
+}
+
+impl From<NftInfo> for OwnershipProof {
+    fn from(info: NftInfo) -> OwnershipProof {
+        OwnershipProof { launcher_id: info.launcher_id, p2_address: info.p2_address, owner_did: info.owner_did }
+    }
+}
+
+// Envelope of every Chia RPC response
+#[derive(Deserialize)]
+struct NftInfoResponse {
+    success: bool,
+    #[serde(default)]
+    error: Option<String>,
+    nft_info: Option<NftInfo>,
+}
+
+// Response of the wallet's `get_height_info` endpoint
+#[derive(Deserialize)]
+struct HeightInfoResponse {
+    success: bool,
+    #[serde(default)]
+    error: Option<String>,
+    height: Option<u64>,
+}
+
+/// Async client for a Chia wallet RPC server.
+#[derive(Debug, Clone)]
+pub struct ChiaRpcClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl ChiaRpcClient {
+    /// Creates a client without a client certificate.
+    pub fn new(base_url: &str) -> Result<ChiaRpcClient, RpcError> {
+        Ok(ChiaRpcClient {
+            http: reqwest::Client::builder().build()?,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Creates a client authenticating with the wallet's private certificate and key,
+    /// trusting only servers whose certificate is signed by the private CA at `ca_path`.
+    pub fn with_certificate(base_url: &str, cert_path: &Path, key_path: &Path, ca_path: &Path) -> Result<ChiaRpcClient, RpcError> {
+        let mut pem = fs::read(cert_path)?;
+        pem.extend(fs::read(key_path)?);
+        let ca = reqwest::Certificate::from_pem(&fs::read(ca_path)?)?;
+        let http = reqwest::Client::builder()
+            .identity(reqwest::Identity::from_pem(&pem)?)
+            .add_root_certificate(ca)
+            // Chia nodes issue their certificates for "chia.net", not the host they run on
+            .danger_accept_invalid_hostnames(true)
+            .build()?;
+        Ok(ChiaRpcClient {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Looks up an NFT by its hex launcher ID.
+    pub async fn nft_info(&self, launcher_id: &str) -> Result<NftInfo, RpcError> {
+        let coin_id = format!("0x{}", launcher_id.trim_start_matches("0x"));
+        let response: NftInfoResponse = self.post("nft_get_info", json!({ "coin_id": coin_id })).await?;
+        match response {
+            NftInfoResponse { success: true, nft_info: Some(info), .. } => Ok(info),
+            NftInfoResponse { error, .. } => Err(RpcError::Rpc(error.unwrap_or_else(|| "no NFT info returned".to_string()))),
+        }
+    }
+
+    /// Looks up the provenance of an NFT by its hex launcher ID.
+    pub async fn provenance(&self, launcher_id: &str) -> Result<Provenance, RpcError> {
+        let info = self.nft_info(launcher_id).await?;
+        Ok(Provenance {
+            owner_puzzle_hash: info.p2_address,
+            owner_did: info.owner_did,
+            minting_height: info.mint_height,
+            metadata_uris: info.metadata_uris,
+        })
+    }
+
+    /// Looks up the current owner of an NFT by its hex launcher ID.
+    pub async fn ownership_proof(&self, launcher_id: &str) -> Result<OwnershipProof, RpcError> {
+        Ok(self.nft_info(launcher_id).await?.into())
+    }
+
+    /// Looks up the block height the wallet is synced to.
+    pub async fn height(&self) -> Result<u64, RpcError> {
+        let response: HeightInfoResponse = self.post("get_height_info", json!({})).await?;
+        match response {
+            HeightInfoResponse { success: true, height: Some(height), .. } => Ok(height),
+            HeightInfoResponse { error, .. } => Err(RpcError::Rpc(error.unwrap_or_else(|| "no height returned".to_string()))),
+        }
+    }
+
+    // POST a JSON body to an RPC endpoint and decode the response
+    async fn post<T: DeserializeOwned>(&self, endpoint: &str, body: Value) -> Result<T, RpcError> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+        Ok(self.http.post(url).json(&body).send().await?.error_for_status()?.json().await?)
+    }
+}
+
+/// Fetch the dungeon's on-chain provenance and store it in `Dungeon::provenance`
+pub async fn enrich_dungeon(dungeon: &mut Dungeon, client: &ChiaRpcClient) -> Result<(), RpcError> {
+    let launcher_id = dungeon.launcher_id.as_deref().ok_or(RpcError::MissingLauncherId)?;
+    dungeon.provenance = Some(client.provenance(launcher_id).await?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn nft_info_responses_decode() {
+        let body = json!({
+            "success": true,
+            "nft_info": {
+                "launcher_id": "0xab",
+                "nft_coin_id": "0xcd",
+                "owner_did": null,
+                "p2_address": "0xef",
+                "mint_height": 4608,
+                "metadata_uris": ["https://example.com/meta.json"],
+            },
+        });
+        let response: NftInfoResponse = serde_json::from_value(body).unwrap();
+        let info = response.nft_info.unwrap();
+        assert!(response.success);
+        assert_eq!(info.mint_height, 4608);
+        assert!(info.data_uris.is_empty());
+        assert_eq!(info.metadata_uris, vec!["https://example.com/meta.json".to_string()]);
+        let proof = OwnershipProof::from(info);
+        assert_eq!((proof.launcher_id.as_str(), proof.p2_address.as_str(), proof.owner_did), ("0xab", "0xef", None));
+
+        let failure: NftInfoResponse = serde_json::from_value(json!({ "success": false, "error": "not synced" })).unwrap();
+        assert_eq!((failure.success, failure.error.as_deref(), failure.nft_info.is_none()), (false, Some("not synced"), true));
+
+        let height: HeightInfoResponse = serde_json::from_value(json!({ "success": true, "height": 5_600_000 })).unwrap();
+        assert_eq!((height.success, height.height, height.error), (true, Some(5_600_000), None));
+    }
+
+    #[test]
+    fn clients_trim_the_base_url() {
+        let client = ChiaRpcClient::new("https://localhost:9256/").unwrap();
+        assert_eq!(client.base_url, DEFAULT_WALLET_RPC_URL);
+    }
+
+    #[test]
+    fn missing_certificates_are_io_errors() {
+        let missing = Path::new("/nonexistent/private_wallet.crt");
+        let err = ChiaRpcClient::with_certificate(DEFAULT_WALLET_RPC_URL, missing, missing, missing).unwrap_err();
+        assert!(matches!(err, RpcError::Io(_)));
+        assert!(err.to_string().starts_with("Failed to read RPC certificate"));
+    }
+
+    #[test]
+    fn synthetic_ids_cannot_be_enriched() {
+        let mut dungeon = dungeon();
+        let client = ChiaRpcClient::new(DEFAULT_WALLET_RPC_URL).unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let err = runtime.block_on(enrich_dungeon(&mut dungeon, &client)).unwrap_err();
+        assert!(matches!(err, RpcError::MissingLauncherId));
+        assert_eq!(dungeon.provenance, None);
+    }
+}
@@ -0,0 +1,233 @@
+//! # Saved Games
+//!
+//! Persists explore-mode runs so players can quit and continue later.
+//!
+//! - **State**: A `GameState` names the NFT ID and sight radius of the run and holds its
+//!   `SavedRun`: the player's position, hit points, and inventory, the loot, locks, and
+//!   monsters left, and the tiles seen through the fog of war. Monsters missing from the
+//!   run are the defeated ones (`GameState::defeated`).
+//! - **Keying**: States only resume in the dungeon they were saved in: the same NFT ID,
+//!   seed ID (`Dungeon::seed_id`), and structure config
+//!   (`GenerationConfig::structure_config`), since another collection salt, algorithm, or
+//!   room parameter lays out another grid. Any other dungeon fails with
+//!   `SaveError::WrongDungeon`. By default a game is saved next to the working directory
+//!   under its NFT ID (`default_save_path`).
+//! - **Format**: Files ending in `.json` hold plain JSON; any other path holds the same
+//!   JSON gzip-compressed, the compact binary format of `default_save_path`.
+//!
+//! The CLI's `explore --continue` resumes the saved game of an NFT ID and saves it again
+//! on quitting.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use super::config::GenerationConfig;
+use super::dungeon::Dungeon;
+use super::explore::{Explorer, SavedRun};
+use super::spawn::MonsterSpawn;
+
+/// Extension of compressed save files
+pub const SAVE_EXTENSION: &str = "sav";
+
+/// Saved explore-mode run through the dungeon of an NFT ID (see `save`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameState {
+    /// NFT ID of the explored dungeon.
+    pub nft_id: String,
+    /// Seed ID of the explored dungeon, the NFT ID salted by its collection.
+    pub seed_id: String,
+    /// Structure config the explored dungeon was laid out with.
+    pub config: GenerationConfig,
+    /// Sight radius the run is played with.
+    pub sight_radius: u32,
+    /// Player, loot, locks, monsters, and fog of war.
+    pub run: SavedRun,
+}
+
+/// Errors returned while saving, loading, or resuming a game.
+#[derive(Debug)]
+pub enum SaveError {
+    /// The save file could not be read or written.
+    Io(io::Error),
+    /// The save file is not a valid game state.
+    Json(serde_json::Error),
+    /// The game was saved in the dungeon of another NFT ID, salt, or structure config.
+    WrongDungeon(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "Failed to access save file: {}", err),
+            SaveError::Json(err) => write!(f, "Invalid save file: {}", err),
+            SaveError::WrongDungeon(nft_id) => write!(f, "Game was saved in another dungeon: {}", nft_id),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> SaveError {
+        SaveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> SaveError {
+        SaveError::Json(err)
+    }
+}
+
+/// Default save file of the game in the dungeon of `nft_id`
+pub fn default_save_path(nft_id: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{}", nft_id, SAVE_EXTENSION))
+}
+
+impl GameState {
+    /// Captures the run of `explorer` through `dungeon`, generated with `config`.
+    pub fn capture(dungeon: &Dungeon, config: &GenerationConfig, explorer: &Explorer) -> GameState {
+        GameState {
+            nft_id: dungeon.nft_id.clone(),
+            seed_id: dungeon.seed_id(),
+            config: config.structure_config(),
+            sight_radius: explorer.fog().sight_radius(),
+            run: explorer.save(),
+        }
+    }
+
+    /// Continues the run in `dungeon`, generated with `config`, which must be the dungeon
+    /// it was saved in.
+    pub fn resume(&self, dungeon: &Dungeon, config: &GenerationConfig) -> Result<Explorer, SaveError> {
+        if self.nft_id != dungeon.nft_id || self.seed_id != dungeon.seed_id() || self.config != config.structure_config() {
+            return Err(SaveError::WrongDungeon(self.nft_id.clone()));
+        }
+        Ok(Explorer::resume(dungeon, self.sight_radius, &self.run))
+    }
+
+    /// Monsters of `dungeon` the player has slain.
+    pub fn defeated<'a>(&self, dungeon: &'a Dungeon) -> Vec<&'a MonsterSpawn> {
+        dungeon.monsters.iter().filter(|monster| !self.run.monsters.contains(monster)).collect()
+    }
+
+    /// Serializes the state to JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Reloads a state previously written by `to_json`.
+    pub fn from_json(json: &str) -> serde_json::Result<GameState> {
+        serde_json::from_str(json)
+    }
+
+    /// Writes the state to `path`, as JSON if it ends in `.json` and compressed otherwise.
+    pub fn save(&self, path: &Path) -> Result<(), SaveError> {
+        let json = self.to_json()?;
+        if is_json(path) {
+            fs::write(path, json)?;
+        } else {
+            let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+            encoder.write_all(json.as_bytes())?;
+            encoder.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Reads a state written by `save` from `path`.
+    pub fn load(path: &Path) -> Result<GameState, SaveError> {
+        let json = if is_json(path) {
+            fs::read_to_string(path)?
+        } else {
+            let mut json = String::new();
+            GzDecoder::new(File::open(path)?).read_to_string(&mut json)?;
+            json
+        };
+        Ok(GameState::from_json(&json)?)
+    }
+}
+
+// Whether `path` names a plain JSON save file
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use super::super::config::GenerationAlgorithm;
+    use super::super::excavator::{parse_nft_id, parse_nft_id_with_config};
+    use super::super::explore::Direction;
+    use super::super::fixtures::dungeon;
+    use super::super::fog::Visibility;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    // An explorer that has walked around the first room
+    fn walked(dungeon: &Dungeon) -> Explorer {
+        let mut explorer = Explorer::new(dungeon);
+        for direction in [Direction::Right, Direction::Up, Direction::Left, Direction::Down] {
+            while explorer.try_move(direction) {}
+        }
+        explorer
+    }
+
+    #[test]
+    fn games_resume_where_they_were_saved() {
+        let dungeon = dungeon();
+        let explorer = walked(&dungeon);
+        let config = GenerationConfig::default();
+        let state = GameState::capture(&dungeon, &config, &explorer);
+        assert_eq!(state.nft_id, dungeon.nft_id);
+
+        let resumed = state.resume(&dungeon, &config).unwrap();
+        assert_eq!(resumed.save(), explorer.save());
+        assert_eq!((resumed.player(), resumed.moves()), (explorer.player(), explorer.moves()));
+        // Tiles seen before saving stay explored
+        let explored = explorer.fog().explored();
+        assert_eq!(state.run.explored, explored);
+        assert!(explored.iter().all(|&pos| resumed.fog().visibility(pos) != Visibility::Unexplored));
+        assert!(state.defeated(&dungeon).is_empty());
+
+        let other = parse_nft_id(&generate_nft_id_with(&GenerateOptions { seed: Some(5), ..GenerateOptions::default() }).unwrap()).unwrap();
+        assert!(matches!(state.resume(&other, &config), Err(SaveError::WrongDungeon(nft_id)) if nft_id == dungeon.nft_id));
+
+        // The same NFT ID under another salt or algorithm is another layout
+        let salted = GenerationConfig { collection_salt: Some("other".to_string()), ..GenerationConfig::default() };
+        let resalted = parse_nft_id_with_config(&dungeon.nft_id, &salted).unwrap();
+        assert!(matches!(state.resume(&resalted, &salted), Err(SaveError::WrongDungeon(_))));
+        let caves = GenerationConfig { algorithm: GenerationAlgorithm::Noise, ..GenerationConfig::default() };
+        let cave = parse_nft_id_with_config(&dungeon.nft_id, &caves).unwrap();
+        assert!(matches!(state.resume(&cave, &caves), Err(SaveError::WrongDungeon(_))));
+        // Decoration parameters keep the layout
+        let dense = GenerationConfig { area_per_random_point: 10, ..GenerationConfig::default() };
+        assert!(state.resume(&dungeon, &dense).is_ok());
+    }
+
+    #[test]
+    fn saves_round_trip_as_json_and_compressed_files() {
+        let dungeon = dungeon();
+        let mut state = GameState::capture(&dungeon, &GenerationConfig::default(), &walked(&dungeon));
+        state.run.monsters.remove(0);
+        assert_eq!(state.defeated(&dungeon), [&dungeon.monsters[0]]);
+
+        let dir = env::temp_dir();
+        let json = dir.join(format!("chia_dungeon_save_{}.json", std::process::id()));
+        let compressed = dir.join(format!("chia_dungeon_save_{}.{}", std::process::id(), SAVE_EXTENSION));
+        state.save(&json).unwrap();
+        state.save(&compressed).unwrap();
+        assert_eq!(GameState::from_json(&fs::read_to_string(&json).unwrap()).unwrap(), state);
+        assert!(fs::read(&compressed).unwrap().starts_with(&[0x1f, 0x8b]));
+        assert_eq!(GameState::load(&json).unwrap(), state);
+        assert_eq!(GameState::load(&compressed).unwrap(), state);
+        fs::remove_file(&json).unwrap();
+        fs::remove_file(&compressed).unwrap();
+
+        assert!(matches!(GameState::load(&json), Err(SaveError::Io(_))));
+        assert_eq!(default_save_path(&dungeon.nft_id), PathBuf::from(format!("{}.sav", dungeon.nft_id)));
+    }
+}
@@ -0,0 +1,115 @@
+//! # Deterministic Seeding
+//!
+//! Derives reproducible RNG seeds from NFT IDs so the same NFT always
+//! excavates the same dungeon.
+//!
+//! Subsystems that add content after excavation (such as monster spawning) draw
+//! from their own named stream via `rng_for_stream`, so adding or changing one
+//! subsystem never shifts the random sequence seen by another.
+//!
+//! Collections that set `GenerationConfig::collection_salt` decode a salted copy of each
+//! ID instead (see `salt_nft_id`), so two projects sharing this crate derive distinct
+//! dungeons from the same IDs; `Dungeon::seed_id` is the ID every derivation reads.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashes the `nft_id` with 64-bit FNV-1a.
+///
+/// Unlike `DefaultHasher`, the result is stable across platforms and Rust releases.
+pub fn seed_from_nft_id(nft_id: &str) -> u64 {
+    fnv1a(FNV_OFFSET_BASIS, nft_id.as_bytes())
+}
+
+/// Continues the 64-bit FNV-1a `hash` over `bytes`
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+/// Seeded RNG used for all randomness derived from the `nft_id`
+pub fn rng_for_nft_id(nft_id: &str) -> StdRng {
+    StdRng::seed_from_u64(seed_from_nft_id(nft_id))
+}
+
+/// Seeded RNG for a named subsystem stream, independent of the excavation RNG
+pub fn rng_for_stream(nft_id: &str, stream: &str) -> StdRng {
+    // Continue the FNV-1a hash of the ID over ":<stream>"
+    let seed = fnv1a(fnv1a(seed_from_nft_id(nft_id), b":"), stream.as_bytes());
+    StdRng::seed_from_u64(seed)
+}
+
+/// Copy of `nft_id` salted by a collection's `salt`, decoded in place of the ID itself
+/// Every ASCII alphanumeric after "nft1" is shifted within its class (digits, lowercase,
+/// uppercase) by an offset drawn from the "collection" stream of the salt, so the copy
+/// keeps the length, alphabet, and invalid characters of the ID, and distinct IDs stay
+/// distinct. IDs without the prefix, and every ID without a salt, are returned unchanged.
+pub fn salt_nft_id(nft_id: &str, salt: Option<&str>) -> String {
+    let (Some(salt), Some(rest)) = (salt, nft_id.strip_prefix("nft1")) else {
+        return nft_id.to_string();
+    };
+    let mut rng = rng_for_stream(salt, "collection");
+    let salted: String = rest
+        .chars()
+        .map(|c| {
+            let (first, len) = match c {
+                '0'..='9' => (b'0', 10),
+                'a'..='z' => (b'a', 26),
+                'A'..='Z' => (b'A', 26),
+                _ => return c,
+            };
+            (first + (c as u8 - first + rng.gen_range(0..len)) % len) as char
+        })
+        .collect();
+    format!("nft1{}", salted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::config::GenerationConfig;
+    use super::super::excavator::{parse_nft_id, parse_nft_id_with_config};
+    use super::super::fixtures::NFT_ID;
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_the_reference_vectors() {
+        assert_eq!(seed_from_nft_id(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(seed_from_nft_id("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(seed_from_nft_id("foobar"), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn rngs_are_reproducible_per_id() {
+        let draw = |nft_id: &str| rng_for_nft_id(nft_id).gen::<u64>();
+        assert_eq!(draw(NFT_ID), draw(NFT_ID));
+        assert_ne!(draw(NFT_ID), draw("nft1foobar"));
+    }
+
+    #[test]
+    fn the_same_id_always_excavates_the_same_dungeon() {
+        assert_eq!(parse_nft_id(NFT_ID).unwrap(), parse_nft_id(NFT_ID).unwrap());
+    }
+
+    #[test]
+    fn salts_give_collections_their_own_dungeons() {
+        let salted = salt_nft_id(NFT_ID, Some("collection-a"));
+        assert_eq!(salted, salt_nft_id(NFT_ID, Some("collection-a")));
+        assert_ne!(salted, salt_nft_id(NFT_ID, Some("collection-b")));
+        assert_eq!(salted.len(), NFT_ID.len());
+        assert!(salted.starts_with("nft1") && salted.chars().zip(NFT_ID.chars()).all(|(a, b)| a.is_ascii_digit() == b.is_ascii_digit() && a.is_ascii_lowercase() == b.is_ascii_lowercase()));
+        assert_eq!(salt_nft_id(NFT_ID, None), NFT_ID);
+        assert_eq!(salt_nft_id("nft1ab-cd", Some("collection-a")).chars().nth(6), Some('-'));
+
+        let config = |salt: &str| GenerationConfig { collection_salt: Some(salt.to_string()), ..GenerationConfig::default() };
+        let plain = parse_nft_id(NFT_ID).unwrap();
+        let dungeon = parse_nft_id_with_config(NFT_ID, &config("collection-a")).unwrap();
+        assert_eq!(dungeon.nft_id, NFT_ID);
+        assert_eq!(dungeon.seed_id(), salted);
+        assert_ne!(dungeon.excavated, plain.excavated);
+        assert_ne!(dungeon.monsters, plain.monsters);
+        assert_eq!(dungeon, parse_nft_id_with_config(NFT_ID, &config("collection-a")).unwrap());
+        assert_ne!(dungeon.excavated, parse_nft_id_with_config(NFT_ID, &config("collection-b")).unwrap().excavated);
+    }
+}
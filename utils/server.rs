@@ -0,0 +1,232 @@
+//! # HTTP Server
+//!
+//! Serves dungeon renders over HTTP so web frontends can fetch them on demand. Only
+//! available with the `server` feature.
+//!
+//! ## Endpoints:
+//! - `GET /dungeon/{nft_id}.png`: Plotted map as a PNG image.
+//! - `GET /dungeon/{nft_id}.svg`: Plotted map as an SVG image.
+//! - `GET /dungeon/{nft_id}.json`: The dungeon as JSON (see `Dungeon::to_json`).
+//! - `GET /dungeon/{nft_id}/ascii`: ASCII map as plain text.
+//!
+//! Every endpoint accepts `?floor=N` (0 is the top floor). Without it, images and ASCII
+//! show the top floor and JSON holds the whole dungeon with all its floors.
+//!
+//! Dungeons are kept in a shared in-memory `DungeonCache`, so repeated requests for the
+//! same NFT only excavate it once. The cache's config (`DungeonCache::with_config`)
+//! decides how every served dungeon is generated.
+//!
+//! Malformed NFT IDs and missing floors answer `400 Bad Request` with the error message;
+//! unknown file extensions answer `404 Not Found`.
+//!
+//! Every request runs in a `tracing` span and logs its status when answered, with the
+//! parse and render spans of the library nested inside; install a subscriber (such as
+//! the CLI's `--log-format json`) to collect them.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+use super::ascii::render_ascii;
+use super::cache::DungeonCache;
+use super::dungeon::Dungeon;
+use super::error::DungeonError;
+use super::plot::{render_png_bytes, render_svg_string};
+
+/// Address `serve` listens on when none is given
+pub const DEFAULT_ADDR: &str = "127.0.0.1:3000";
+
+// Error status and plain-text message
+type ApiError = (StatusCode, String);
+
+// Dungeon cache shared by every request
+type SharedCache = Arc<Mutex<DungeonCache>>;
+
+// Query parameters shared by every endpoint
+#[derive(Deserialize)]
+struct FloorQuery {
+    floor: Option<usize>,
+}
+
+/// Routes of the dungeon API, for embedding in a larger axum application
+pub fn router() -> Router {
+    router_with_cache(DungeonCache::default())
+}
+
+/// Routes of the dungeon API, generating dungeons into `cache`
+pub fn router_with_cache(cache: DungeonCache) -> Router {
+    Router::new()
+        .route("/dungeon/{file}", get(dungeon_file))
+        .route("/dungeon/{nft_id}/ascii", get(dungeon_ascii))
+        .with_state(Arc::new(Mutex::new(cache)))
+}
+
+/// Serve the dungeon API on `addr` until the process stops, generating dungeons into
+/// `cache`
+pub async fn serve(addr: &str, cache: DungeonCache) -> io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!(addr, "serving dungeons");
+    axum::serve(listener, router_with_cache(cache)).await
+}
+
+// `/dungeon/{nft_id}.{png,svg,json}`
+#[tracing::instrument(name = "request", skip(cache, query), fields(floor = query.floor))]
+async fn dungeon_file(State(cache): State<SharedCache>, Path(file): Path<String>, Query(query): Query<FloorQuery>) -> Response {
+    let Some((nft_id, extension)) = file.rsplit_once('.') else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if !matches!(extension, "png" | "svg" | "json") {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let (nft_id, extension) = (nft_id.to_string(), extension.to_string());
+
+    // Generation and rendering are CPU-bound; keep them off the async workers
+    run_blocking(move || {
+        let dungeon = cached_dungeon(&cache, &nft_id).map_err(bad_request)?;
+        if extension == "json" && query.floor.is_none() {
+            let json = dungeon.to_json().map_err(internal_error)?;
+            return Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response());
+        }
+        let floor = floor_of(&dungeon, query.floor.unwrap_or(0))?;
+        Ok(match extension.as_str() {
+            "png" => ([(header::CONTENT_TYPE, "image/png")], render_png_bytes(&floor).map_err(internal_error)?).into_response(),
+            "svg" => ([(header::CONTENT_TYPE, "image/svg+xml")], render_svg_string(&floor).map_err(internal_error)?).into_response(),
+            _ => ([(header::CONTENT_TYPE, "application/json")], floor.to_json().map_err(internal_error)?).into_response(),
+        })
+    })
+    .await
+}
+
+// `/dungeon/{nft_id}/ascii`
+#[tracing::instrument(name = "request", skip(cache, query), fields(floor = query.floor))]
+async fn dungeon_ascii(State(cache): State<SharedCache>, Path(nft_id): Path<String>, Query(query): Query<FloorQuery>) -> Response {
+    run_blocking(move || {
+        let dungeon = cached_dungeon(&cache, &nft_id).map_err(bad_request)?;
+        let floor = floor_of(&dungeon, query.floor.unwrap_or(0))?;
+        Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], render_ascii(&floor)).into_response())
+    })
+    .await
+}
+
+// Run `handler` on the blocking thread pool, answering with its response or error
+async fn run_blocking<F>(handler: F) -> Response
+where
+    F: FnOnce() -> Result<Response, ApiError> + Send + 'static,
+{
+    // Carry the request span over to the blocking thread
+    let span = tracing::Span::current();
+    let response = match tokio::task::spawn_blocking(move || span.in_scope(handler)).await {
+        Ok(Ok(response)) => response,
+        Ok(Err((status, message))) => {
+            tracing::warn!(%status, message, "request failed");
+            (status, message).into_response()
+        }
+        Err(err) => internal_error(err).into_response(),
+    };
+    tracing::info!(status = %response.status(), "answered request");
+    response
+}
+
+// The dungeon of `nft_id` from the cache shared by every request
+fn cached_dungeon(cache: &Mutex<DungeonCache>, nft_id: &str) -> Result<Arc<Dungeon>, DungeonError> {
+    // A handler that panicked mid-update leaves at worst a stale LRU order
+    let lock = || cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let miss = {
+        let mut cache = lock();
+        if let Some(dungeon) = cache.get(nft_id) {
+            return Ok(dungeon);
+        }
+        cache.miss(nft_id)
+    };
+    // Excavate without the lock, so other requests are not held up behind this one
+    let dungeon = miss.load_or_generate()?;
+    Ok(lock().insert(&miss, dungeon))
+}
+
+// The floor at `depth`, or a 400 response when the dungeon has no such floor
+fn floor_of(dungeon: &Dungeon, depth: usize) -> Result<Dungeon, ApiError> {
+    dungeon
+        .floor_view(depth)
+        .ok_or_else(|| bad_request(format!("Dungeon has no floor {}", depth)))
+}
+
+// 400 error carrying the error message
+fn bad_request(err: impl ToString) -> ApiError {
+    (StatusCode::BAD_REQUEST, err.to_string())
+}
+
+// 500 error carrying the error message
+fn internal_error(err: impl ToString) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use tower::ServiceExt;
+    use super::super::config::GenerationConfig;
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    // Status, content type, and body of a GET request to `uri`
+    fn get(uri: &str) -> (StatusCode, Option<String>, Vec<u8>) {
+        get_from(router(), uri)
+    }
+
+    // Status, content type, and body of a GET request to `uri` on `router`
+    fn get_from(router: Router, uri: &str) -> (StatusCode, Option<String>, Vec<u8>) {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let response = router.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let content_type = response.headers().get(header::CONTENT_TYPE).map(|value| value.to_str().unwrap().to_string());
+            let status = response.status();
+            (status, content_type, to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec())
+        })
+    }
+
+    #[test]
+    fn maps_are_served_in_every_format() {
+        let (status, content_type, body) = get(&format!("/dungeon/{}.png", NFT_ID));
+        assert_eq!((status, content_type.as_deref()), (StatusCode::OK, Some("image/png")));
+        assert!(body.starts_with(b"\x89PNG"));
+
+        let (status, content_type, body) = get(&format!("/dungeon/{}.svg", NFT_ID));
+        assert_eq!((status, content_type.as_deref()), (StatusCode::OK, Some("image/svg+xml")));
+        assert!(String::from_utf8(body).unwrap().contains("<svg"));
+
+        let (status, _, body) = get(&format!("/dungeon/{}/ascii", NFT_ID));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(String::from_utf8(body).unwrap(), render_ascii(&dungeon()));
+    }
+
+    #[test]
+    fn json_holds_the_whole_dungeon() {
+        let (status, content_type, body) = get(&format!("/dungeon/{}.json", NFT_ID));
+        assert_eq!((status, content_type.as_deref()), (StatusCode::OK, Some("application/json")));
+        assert_eq!(Dungeon::from_json(std::str::from_utf8(&body).unwrap()).unwrap(), dungeon());
+    }
+
+    #[test]
+    fn dungeons_are_generated_with_the_cache_config() {
+        let salted = GenerationConfig { collection_salt: Some("other".to_string()), ..GenerationConfig::default() };
+        let router = router_with_cache(DungeonCache::default().with_config(salted.clone()));
+        let (status, _, body) = get_from(router, &format!("/dungeon/{}.json", NFT_ID));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(Dungeon::from_json(std::str::from_utf8(&body).unwrap()).unwrap(), parse_nft_id_with_config(NFT_ID, &salted).unwrap());
+    }
+
+    #[test]
+    fn bad_ids_floors_and_extensions_are_rejected() {
+        assert_eq!(get("/dungeon/nope.png").0, StatusCode::BAD_REQUEST);
+        let (status, _, body) = get(&format!("/dungeon/{}.svg?floor=5", NFT_ID));
+        assert_eq!((status, String::from_utf8(body).unwrap().as_str()), (StatusCode::BAD_REQUEST, "Dungeon has no floor 5"));
+        assert_eq!(get(&format!("/dungeon/{}.gif", NFT_ID)).0, StatusCode::NOT_FOUND);
+        assert_eq!(get(&format!("/dungeon/{}", NFT_ID)).0, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,393 @@
+//! # Room Shapes
+//!
+//! The one implementation of room offsets, used by every excavation path.
+//!
+//! Each shape character maps to a handful of base offsets around the room center
+//! (`base_offsets`), which `room_offsets` expands by the room size according to a
+//! `ShapeStyle`:
+//! - **Filled**: Every base offset grows into a filled square of side `2 * size - 1`,
+//!   so rooms are solid areas. `parse_nft_id` excavates filled rooms.
+//! - **Scaled**: Every base offset is multiplied by the size, keeping the outline of
+//!   the shape as sparse points spread further apart in larger rooms.
+//! - **Outlined**: The base offsets are multiplied by the size and joined in order, back
+//!   to the first, into an outline three tiles thick; every tile enclosed by the outline
+//!   is flood-filled. Large rooms keep the character of their shape (a cross becomes a
+//!   diamond, a diagonal cross an hourglass) where filled squares would merge into one
+//!   big square. Shapes of a single point grow like filled ones.
+//!
+//! Every style returns offsets without duplicates, in the order they were first generated.
+//! `room_offsets_iter` yields the same offsets lazily, without collecting them, so large
+//! rooms can be excavated tile by tile.
+//!
+//! `ShapeRegistry` extends the room vocabulary at runtime: downstream games register a
+//! shape character with its own base offsets (expanded like the built-in ones) or with a
+//! closure producing every offset of the room from its size. Shape characters come from
+//! the NFT ID, so only ASCII letters and digits are ever looked up; registering one
+//! replaces its built-in shape, and unregistered characters keep theirs. Pass the registry to `parse_nft_id_with_shapes` to
+//! excavate with it.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use super::pathfinding::neighbors;
+use super::tunnels::line;
+
+/// How `room_offsets` expands a shape's base offsets by the room size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ShapeStyle {
+    /// Each base offset becomes a filled square around it.
+    #[default]
+    Filled,
+    /// Each base offset is multiplied by the size.
+    Scaled,
+    /// The base offsets, multiplied by the size, outline the room, which is filled inside.
+    Outlined,
+}
+
+/// Base offsets of a shape character, relative to the room center
+/// Matching is case-sensitive; unrecognized shapes have no offsets.
+pub fn base_offsets(shape: &str) -> Vec<(i32, i32)> {
+    // Define base offsets based on shape character
+    match shape {
+        // 0-9 (unique patterns)
+        "0" => vec![(0, 0)], // Single point
+        "1" => vec![(0, 1), (0, -1)], // Vertical line
+        "2" => vec![(1, 0), (-1, 0)], // Horizontal line
+        "3" => vec![(1, 1), (-1, -1)], // Diagonal line
+        "4" => vec![(-1, 0), (1, 0), (0, 1)], // L-shape
+        "5" => vec![(0, -1), (1, 0), (-1, 1)], // Reverse L-shape
+        "6" => vec![(-1, -1), (1, 1), (1, -1), (-1, 1)], // Diagonal cross
+        "7" => vec![(0, 1), (1, 0), (0, -1), (-1, 0)], // Full cross
+        "8" => vec![(-2, 0), (2, 0), (0, -2), (0, 2)], // Large cross
+        "9" => vec![(-3, 0), (3, 0), (0, -3), (0, 3)], // Very large cross
+
+        // a-z (unique patterns with distinct offsets)
+        "a" => vec![(0, 1), (-1, 0), (1, 0), (0, -1)], // Cross
+        "b" => vec![(-1, 1), (1, -1)], // Diagonal corners
+        "c" => vec![(-1, 1), (1, 1), (1, -1), (-1, -1)], // Full diamond
+        "d" => vec![(-2, 2), (2, 2), (-2, -2), (2, -2)], // Large diamond
+        "e" => vec![(-2, 0), (2, 0), (0, -2), (0, 2)], // Expanded cross
+        "f" => vec![(1, 1), (2, 2)], // Expanding diagonal
+        "g" => vec![(-1, 0), (-2, 0), (-3, 0)], // Horizontal line left
+        "h" => vec![(0, 1), (0, 2), (0, 3)], // Vertical line up
+        "i" => vec![(0, 0)], // Single point
+        "j" => vec![(-1, 1), (0, 1), (1, 0)], // Corner
+        "k" => vec![(0, 2), (-1, 1), (1, -1)], // Triangle
+        "l" => vec![(-2, 0), (1, -1), (2, -2)], // Reverse diagonal
+        "m" => vec![(-1, -1), (0, 1), (1, 0), (-1, 1)], // M-shape
+        "n" => vec![(-1, 1), (1, -1), (0, 0)], // Zigzag
+        "o" => vec![(-2, 2), (2, -2), (0, 0)], // Circle-like
+        "p" => vec![(-1, 1), (1, 1), (1, -1)], // Partial diamond
+        "q" => vec![(-1, 1), (-1, -1)], // Partial diamond reversed
+        "r" => vec![(-2, 2), (0, 2), (2, 2)], // Semi-circle
+        "s" => vec![(-2, -2), (0, -2), (2, -2)], // Semi-circle reversed
+        "t" => vec![(-1, 0), (0, 0), (1, 0)], // T-shape
+        "u" => vec![(-1, -1), (1, -1)], // U-shape
+        "v" => vec![(0, 2), (-1, 1), (1, 1)], // V-shape
+        "w" => vec![(-1, 1), (0, 0), (1, -1)], // W-shape
+        "x" => vec![(-2, 2), (2, -2), (-2, -2), (2, 2)], // X-shape
+        "y" => vec![(0, 2), (-1, 1), (1, -1)], // Y-shape
+        "z" => vec![(-1, 0), (0, 1), (1, 0)], // Z-shape
+
+        // A-Z (larger patterns, distinct from the lower-case ones)
+        "A" => vec![(0, 2), (-1, 0), (1, 0), (-2, -2), (2, -2)], // A-frame
+        "B" => vec![(-1, 2), (-1, 0), (-1, -2), (1, 1), (1, -1)], // Twin bays
+        "C" => vec![(1, 2), (-1, 1), (-1, -1), (1, -2)], // Open arc
+        "D" => vec![(-1, 2), (1, 1), (1, -1), (-1, -2)], // Closed arc
+        "E" => vec![(-1, 2), (-1, 0), (-1, -2), (1, 2), (1, 0), (1, -2)], // Comb
+        "F" => vec![(-1, 2), (1, 2), (-1, 0), (0, 0), (-1, -2)], // Flag
+        "G" => vec![(1, 2), (-1, 1), (-1, -1), (1, -2), (1, 0)], // Hooked arc
+        "H" => vec![(-2, 2), (-2, -2), (2, 2), (2, -2), (0, 0)], // Twin towers
+        "I" => vec![(0, 2), (0, -2), (-2, 2), (2, -2)], // Pillar
+        "J" => vec![(1, 2), (1, 0), (0, -2), (-1, -1)], // Hook
+        "K" => vec![(-1, 2), (-1, -2), (1, 1), (1, -1), (2, 2), (2, -2)], // Fork
+        "L" => vec![(-2, 2), (-2, 0), (-2, -2), (0, -2), (2, -2)], // Large L
+        "M" => vec![(-2, 2), (-2, -2), (2, 2), (2, -2), (0, 1)], // Peaks
+        "N" => vec![(-2, 2), (-2, -2), (2, 2), (2, -2), (-1, 1), (1, -1)], // Slash
+        "O" => vec![(0, 3), (3, 0), (0, -3), (-3, 0), (2, 2), (-2, -2), (2, -2), (-2, 2)], // Ring
+        "P" => vec![(-1, 2), (1, 2), (1, 1), (-1, 0), (-1, -2)], // Banner
+        "Q" => vec![(0, 3), (3, 0), (0, -3), (-3, 0), (3, -3)], // Tailed ring
+        "R" => vec![(-1, 2), (1, 1), (-1, 0), (1, -2), (-1, -2)], // Kinked banner
+        "S" => vec![(1, 2), (-1, 1), (1, -1), (-1, -2)], // Serpent
+        "T" => vec![(-2, 2), (0, 2), (2, 2), (0, -1), (0, -2)], // Large T
+        "U" => vec![(-2, 2), (-2, 0), (-1, -2), (1, -2), (2, 0), (2, 2)], // Large U
+        "V" => vec![(-2, 2), (-1, 0), (0, -2), (1, 0), (2, 2)], // Large V
+        "W" => vec![(-2, 2), (-1, -2), (0, 1), (1, -2), (2, 2)], // Large W
+        "X" => vec![(-3, 3), (3, -3), (-3, -3), (3, 3), (0, 0)], // Large X
+        "Y" => vec![(-2, 2), (2, 2), (0, 0), (0, -2)], // Large Y
+        "Z" => vec![(-2, 2), (2, 2), (0, 0), (-2, -2), (2, -2)], // Large Z
+        _ => vec![], // Default to no offsets if shape is not recognized
+    }
+}
+
+/// Offsets of every tile of a room with the given size and shape, relative to its center
+pub fn room_offsets(size: u32, shape: &str, style: ShapeStyle) -> Vec<(i32, i32)> {
+    room_offsets_iter(size, shape, style).collect()
+}
+
+/// Lazily yields the offsets of `room_offsets`, in the same order
+pub fn room_offsets_iter(size: u32, shape: &str, style: ShapeStyle) -> impl Iterator<Item = (i32, i32)> {
+    expand(base_offsets(shape), size, style)
+}
+
+// Expand `bases` by `size` according to `style`, skipping duplicates
+fn expand(bases: Vec<(i32, i32)>, size: u32, style: ShapeStyle) -> Box<dyn Iterator<Item = (i32, i32)>> {
+    match style {
+        ShapeStyle::Outlined => Box::new(outline(bases, size).into_iter()),
+        _ => Box::new(expand_each(bases, size, style)),
+    }
+}
+
+// Expand every base offset of `bases` on its own, skipping points of earlier ones
+fn expand_each(bases: Vec<(i32, i32)>, size: u32, style: ShapeStyle) -> impl Iterator<Item = (i32, i32)> {
+    let size = size as i32; // Convert size to i32 for calculations
+
+    (0..bases.len()).flat_map(move |i| {
+        let (base_x, base_y) = bases[i];
+        let earlier = bases[..i].to_vec();
+        let points: Box<dyn Iterator<Item = (i32, i32)>> = match style {
+            // Generate all points within the extended range based on size; outlined
+            // shapes only get here as single points, which grow like filled ones
+            ShapeStyle::Filled | ShapeStyle::Outlined => Box::new(((base_x - (size - 1))..=(base_x + (size - 1))).flat_map(move |x| {
+                ((base_y - (size - 1))..=(base_y + (size - 1))).map(move |y| (x, y))
+            })),
+            ShapeStyle::Scaled => Box::new(std::iter::once((base_x * size, base_y * size))),
+        };
+        // Skip points an earlier base offset already produced, keeping first-seen order
+        points.filter(move |&point| !earlier.iter().any(|&base| produces(base, point, size, style)))
+    })
+}
+
+// Whether expanding `base` by `size` yields `point`
+fn produces((base_x, base_y): (i32, i32), (x, y): (i32, i32), size: i32, style: ShapeStyle) -> bool {
+    match style {
+        ShapeStyle::Filled | ShapeStyle::Outlined => (x - base_x).abs() < size && (y - base_y).abs() < size,
+        ShapeStyle::Scaled => (base_x * size, base_y * size) == (x, y),
+    }
+}
+
+// Outline through the scaled `bases`, closed back to the first, followed by the tiles it
+// encloses row by row
+fn outline(bases: Vec<(i32, i32)>, size: u32) -> Vec<(i32, i32)> {
+    let scale = size as i32;
+    let mut corners: Vec<(i32, i32)> = Vec::new();
+    for (x, y) in bases.iter().map(|&(x, y)| (x * scale, y * scale)) {
+        if !corners.contains(&(x, y)) {
+            corners.push((x, y));
+        }
+    }
+    if size == 0 || corners.len() < 2 {
+        return expand_each(bases, size, ShapeStyle::Filled).collect();
+    }
+
+    // Thicken the outline so shapes without an inside still leave walkable halls
+    let closing = if corners.len() > 2 { Some(corners[0]) } else { None };
+    let path: Vec<(i32, i32)> = corners.iter().copied().chain(closing).collect();
+    let radius = if size > 1 { 1 } else { 0 };
+    let mut seen = HashSet::new();
+    let mut offsets = Vec::new();
+    for step in path.windows(2) {
+        for (x, y) in line(step[0], step[1]) {
+            for pos in (-radius..=radius).flat_map(|dy| (-radius..=radius).map(move |dx| (x + dx, y + dy))) {
+                if seen.insert(pos) {
+                    offsets.push(pos);
+                }
+            }
+        }
+    }
+
+    // Flood the bounding box, padded by one tile, from a corner; the tiles it misses lie inside
+    let min_x = offsets.iter().map(|&(x, _)| x).min().unwrap_or(0) - 1;
+    let max_x = offsets.iter().map(|&(x, _)| x).max().unwrap_or(0) + 1;
+    let min_y = offsets.iter().map(|&(_, y)| y).min().unwrap_or(0) - 1;
+    let max_y = offsets.iter().map(|&(_, y)| y).max().unwrap_or(0) + 1;
+    let mut outside = HashSet::from([(min_x, min_y)]);
+    let mut stack = vec![(min_x, min_y)];
+    while let Some(pos) = stack.pop() {
+        for next in neighbors(pos) {
+            let in_box = (min_x..=max_x).contains(&next.0) && (min_y..=max_y).contains(&next.1);
+            if in_box && !seen.contains(&next) && outside.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    for y in min_y..=max_y {
+        offsets.extend((min_x..=max_x).map(|x| (x, y)).filter(|pos| !seen.contains(pos) && !outside.contains(pos)));
+    }
+    offsets
+}
+
+/// Offsets of every tile of a custom room of the given size, relative to its center
+pub type ShapeFn = dyn Fn(u32) -> Vec<(i32, i32)> + Send + Sync;
+
+// A registered shape
+#[derive(Clone)]
+enum CustomShape {
+    // Base offsets, expanded by the room size like the built-in shapes
+    Offsets(Vec<(i32, i32)>),
+    // Every offset of the room, computed from its size
+    Closure(Arc<ShapeFn>),
+}
+
+/// Shape characters registered at runtime, on top of the built-in shapes.
+#[derive(Clone, Default)]
+pub struct ShapeRegistry {
+    shapes: HashMap<char, CustomShape>,
+}
+
+impl fmt::Debug for ShapeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut registered: Vec<char> = self.shapes.keys().copied().collect();
+        registered.sort_unstable();
+        f.debug_struct("ShapeRegistry").field("registered", &registered).finish()
+    }
+}
+
+impl ShapeRegistry {
+    /// A registry holding only the built-in shapes
+    pub fn new() -> ShapeRegistry {
+        ShapeRegistry::default()
+    }
+
+    /// Make `shape` expand `base` offsets by the room size, replacing any earlier shape
+    pub fn register_offsets(&mut self, shape: char, base: Vec<(i32, i32)>) -> &mut ShapeRegistry {
+        self.shapes.insert(shape, CustomShape::Offsets(base));
+        self
+    }
+
+    /// Make `shape` excavate the offsets `offsets` returns for the room size, replacing any
+    /// earlier shape; the `ShapeStyle` does not apply and duplicate offsets are dropped
+    pub fn register_fn<F>(&mut self, shape: char, offsets: F) -> &mut ShapeRegistry
+    where
+        F: Fn(u32) -> Vec<(i32, i32)> + Send + Sync + 'static,
+    {
+        self.shapes.insert(shape, CustomShape::Closure(Arc::new(offsets)));
+        self
+    }
+
+    /// Whether `shape` was registered, rather than built in
+    pub fn is_registered(&self, shape: char) -> bool {
+        self.shapes.contains_key(&shape)
+    }
+
+    /// Offsets of every tile of a room with the given size and shape, like `room_offsets`
+    /// but honoring the registered shapes
+    pub fn room_offsets(&self, size: u32, shape: &str, style: ShapeStyle) -> Vec<(i32, i32)> {
+        self.room_offsets_iter(size, shape, style).collect()
+    }
+
+    /// Lazily yields the offsets of `ShapeRegistry::room_offsets`, in the same order
+    pub fn room_offsets_iter(&self, size: u32, shape: &str, style: ShapeStyle) -> Box<dyn Iterator<Item = (i32, i32)>> {
+        let mut chars = shape.chars();
+        let custom = match (chars.next(), chars.next()) {
+            (Some(c), None) => self.shapes.get(&c),
+            _ => None,
+        };
+        match custom {
+            Some(CustomShape::Offsets(base)) => Box::new(expand(base.clone(), size, style)),
+            Some(CustomShape::Closure(offsets)) => {
+                let mut seen = HashSet::new();
+                Box::new(offsets(size).into_iter().filter(move |&offset| seen.insert(offset)))
+            }
+            None => Box::new(room_offsets_iter(size, shape, style)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::super::config::GenerationConfig;
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    const STYLES: [ShapeStyle; 3] = [ShapeStyle::Filled, ShapeStyle::Scaled, ShapeStyle::Outlined];
+
+    #[test]
+    fn offsets_have_no_duplicates() {
+        let shapes = ('0'..='9').chain('a'..='z').chain('A'..='Z');
+        for shape in shapes.map(String::from) {
+            for style in STYLES {
+                for size in 0..6 {
+                    let offsets = room_offsets(size, &shape, style);
+                    let unique: HashSet<(i32, i32)> = offsets.iter().copied().collect();
+                    assert_eq!(unique.len(), offsets.len(), "shape {} {:?} size {}", shape, style, size);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn offsets_keep_their_first_seen_order() {
+        // The second square only adds the columns left of the first
+        assert_eq!(
+            room_offsets(2, "2", ShapeStyle::Filled),
+            vec![(0, -1), (0, 0), (0, 1), (1, -1), (1, 0), (1, 1), (2, -1), (2, 0), (2, 1), (-2, -1), (-2, 0), (-2, 1), (-1, -1), (-1, 0), (-1, 1)]
+        );
+        assert_eq!(room_offsets_iter(4, "X", ShapeStyle::Filled).collect::<Vec<_>>(), room_offsets(4, "X", ShapeStyle::Filled));
+    }
+
+    #[test]
+    fn styles_expand_the_base_offsets() {
+        assert_eq!(room_offsets(3, "0", ShapeStyle::Filled).len(), 25);
+        assert_eq!(room_offsets(3, "3", ShapeStyle::Scaled), vec![(3, 3), (-3, -3)]);
+        assert!(room_offsets(3, "?", ShapeStyle::Filled).is_empty());
+        assert!(room_offsets(0, "7", ShapeStyle::Filled).is_empty());
+    }
+
+    #[test]
+    fn outlined_rooms_keep_their_shape_when_large() {
+        // A cross grows into a diamond rather than a square
+        let diamond = room_offsets(6, "7", ShapeStyle::Outlined);
+        assert!(diamond.contains(&(0, 0)) && diamond.contains(&(0, 6)) && diamond.contains(&(-6, 0)));
+        assert!(!diamond.contains(&(5, 5)));
+        assert!(room_offsets(6, "7", ShapeStyle::Filled).contains(&(5, 5)));
+        // A diagonal cross becomes an hourglass lying on its side
+        let hourglass = room_offsets(6, "6", ShapeStyle::Outlined);
+        assert!(hourglass.contains(&(4, 0)) && hourglass.contains(&(-4, 0)));
+        assert!(!hourglass.contains(&(0, 4)));
+        // Lines become halls three tiles wide, and single points stay filled squares
+        assert_eq!(room_offsets(4, "2", ShapeStyle::Outlined).len(), 3 * 11);
+        assert_eq!(room_offsets(4, "0", ShapeStyle::Outlined), room_offsets(4, "0", ShapeStyle::Filled));
+        assert!(room_offsets(0, "7", ShapeStyle::Outlined).is_empty());
+
+        let config = GenerationConfig { shape_style: ShapeStyle::Outlined, ..GenerationConfig::default() };
+        let dungeon = parse_nft_id_with_config(NFT_ID, &config).unwrap();
+        for room in &dungeon.rooms {
+            let tiles: HashSet<(i32, i32)> = room_offsets(room.size, &room.shape, ShapeStyle::Outlined)
+                .into_iter()
+                .map(|(dx, dy)| (room.center.0 + dx, room.center.1 + dy))
+                .collect();
+            assert!(room.tiles.iter().all(|pos| tiles.contains(pos)));
+        }
+        assert!(dungeon.is_fully_connected());
+    }
+
+    #[test]
+    fn registered_shapes_replace_the_built_in_ones() {
+        let mut registry = ShapeRegistry::new();
+        registry
+            .register_offsets('7', vec![(0, 0), (4, 0)])
+            .register_fn('q', |size| (0..size as i32).flat_map(|x| [(x, 0), (x, 0), (0, x)]).collect());
+        assert!(registry.is_registered('q') && !registry.is_registered('8'));
+        assert_eq!(registry.room_offsets(1, "7", ShapeStyle::Scaled), vec![(0, 0), (4, 0)]);
+        assert_eq!(registry.room_offsets(2, "q", ShapeStyle::Filled), vec![(0, 0), (1, 0), (0, 1)]);
+        for style in STYLES {
+            assert_eq!(registry.room_offsets(3, "8", style), room_offsets(3, "8", style));
+        }
+        assert_eq!(format!("{:?}", registry), "ShapeRegistry { registered: ['7', 'q'] }");
+    }
+
+    #[test]
+    fn excavated_rooms_use_the_shared_offsets() {
+        for room in dungeon().rooms {
+            let tiles: Vec<(i32, i32)> = room_offsets(room.size, &room.shape, ShapeStyle::Filled)
+                .into_iter()
+                .map(|(dx, dy)| (room.center.0 + dx, room.center.1 + dy))
+                .collect();
+            assert_eq!(room.tiles, tiles);
+        }
+    }
+}
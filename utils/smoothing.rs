@@ -0,0 +1,160 @@
+//! # Cave Smoothing
+//!
+//! Optional cellular-automaton pass that turns the blocky excavation of natural dungeon
+//! types into organic caverns.
+//!
+//! - **Erosion**: Every wall tile touching the excavation is dug with even odds, drawn
+//!   from the "smoothing" RNG stream of the `nft_id`, roughening the room outlines.
+//! - **Smoothing**: Each pass digs the tiles with at least 5 of their 8 neighbours dug
+//!   and fills the tiles with fewer than 4, so the noise settles into rounded walls.
+//! - **Protection**: Tunnel corridors and room centers are never filled, so every room
+//!   stays reachable.
+//!
+//! `GenerationConfig::smoothing` decides when the pass runs: by default
+//! (`Smoothing::Auto`) only for the natural types accepted by `is_natural_type`,
+//! including their upper-case variants.
+
+use std::collections::HashSet;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::excavator::base_dungeon_type;
+use super::grid::Grid;
+
+/// Smoothing passes run when none are configured
+pub const DEFAULT_SMOOTHING_PASSES: u32 = 4;
+
+/// Most smoothing passes a `GenerationConfig` may ask for
+pub const MAX_SMOOTHING_PASSES: u32 = 32;
+
+/// When the cave smoothing pass runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Smoothing {
+    /// Only for natural dungeon types (see `is_natural_type`).
+    #[default]
+    Auto,
+    /// For every dungeon type.
+    Always,
+    /// Never; every dungeon keeps its blocky rooms.
+    Never,
+}
+
+impl Smoothing {
+    /// Whether the pass runs for a dungeon of the given type
+    pub fn applies_to(self, dungeon_type: &str) -> bool {
+        match self {
+            Smoothing::Auto => is_natural_type(dungeon_type),
+            Smoothing::Always => true,
+            Smoothing::Never => false,
+        }
+    }
+}
+
+/// Whether a dungeon type is a natural cavern rather than built or open ground
+pub fn is_natural_type(dungeon_type: &str) -> bool {
+    matches!(
+        base_dungeon_type(dungeon_type),
+        "Cave" | "Ice Cavern" | "Lava Pits" | "Ocean Depths" | "Underground Tunnels" | "Volcanic Crater"
+    )
+}
+
+/// Erode and smooth the excavated coordinates, never filling a `protected` tile
+/// Surviving coordinates keep their order; newly dug ones follow row by row.
+pub fn smooth_excavation(
+    excavated: &[(i32, i32)],
+    protected: &HashSet<(i32, i32)>,
+    passes: u32,
+    rng: &mut impl Rng,
+) -> Vec<(i32, i32)> {
+    if excavated.is_empty() {
+        return Vec::new();
+    }
+
+    // Leave enough margin that the dug area never reaches the edge of the grid
+    let margin = passes as i32 + 2;
+    let min_x = excavated.iter().map(|&(x, _)| x).min().unwrap_or(0) - margin;
+    let max_x = excavated.iter().map(|&(x, _)| x).max().unwrap_or(0) + margin;
+    let min_y = excavated.iter().map(|&(_, y)| y).min().unwrap_or(0) - margin;
+    let max_y = excavated.iter().map(|&(_, y)| y).max().unwrap_or(0) + margin;
+    let mut dug = Grid::new((min_x, max_x), (min_y, max_y), false);
+    for &pos in excavated {
+        dug.set(pos, true);
+    }
+
+    // Erode the walls touching the excavation
+    let edges: Vec<(i32, i32)> = dug
+        .iter()
+        .filter(|&(pos, &is_dug)| !is_dug && dug_neighbors(&dug, pos) > 0)
+        .map(|(pos, _)| pos)
+        .collect();
+    for pos in edges {
+        if rng.gen_bool(0.5) {
+            dug.set(pos, true);
+        }
+    }
+
+    for _ in 0..passes {
+        let mut next = dug.clone();
+        for (pos, &is_dug) in dug.iter() {
+            if !protected.contains(&pos) {
+                let count = dug_neighbors(&dug, pos);
+                next.set(pos, count >= 5 || (is_dug && count >= 4));
+            }
+        }
+        dug = next;
+    }
+
+    let original: HashSet<(i32, i32)> = excavated.iter().copied().collect();
+    let mut smoothed: Vec<(i32, i32)> = excavated.iter().copied().filter(|&pos| dug.get(pos) == Some(&true)).collect();
+    smoothed.extend(dug.iter().filter(|&(pos, &is_dug)| is_dug && !original.contains(&pos)).map(|(pos, _)| pos));
+    smoothed
+}
+
+// Number of dug tiles among the 8 surrounding `pos`
+fn dug_neighbors(dug: &Grid<bool>, (x, y): (i32, i32)) -> usize {
+    (-1..=1)
+        .flat_map(|dx| (-1..=1).map(move |dy| (dx, dy)))
+        .filter(|&(dx, dy)| (dx, dy) != (0, 0) && dug.get((x + dx, y + dy)) == Some(&true))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    #[test]
+    fn natural_types_are_smoothed_by_default() {
+        assert!(Smoothing::Auto.applies_to("Cave"));
+        assert!(Smoothing::Auto.applies_to("Crystal Grotto"));
+        assert!(!Smoothing::Auto.applies_to("Temple"));
+        assert!(Smoothing::Always.applies_to("Temple"));
+        assert!(!Smoothing::Never.applies_to("Cave"));
+    }
+
+    #[test]
+    fn protected_tiles_survive_smoothing() {
+        // A lone corridor would be filled in without protection
+        let corridor: Vec<(i32, i32)> = (0..10).map(|x| (x, 0)).collect();
+        let protected: HashSet<(i32, i32)> = corridor.iter().copied().collect();
+        for seed in 0..8 {
+            let smoothed = smooth_excavation(&corridor, &protected, DEFAULT_SMOOTHING_PASSES, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(smoothed[..corridor.len()], corridor[..]);
+        }
+        let unprotected = smooth_excavation(&corridor, &HashSet::new(), DEFAULT_SMOOTHING_PASSES, &mut StdRng::seed_from_u64(0));
+        assert!(unprotected.len() < corridor.len());
+    }
+
+    #[test]
+    fn smoothing_rounds_a_square_room_deterministically() {
+        let room: Vec<(i32, i32)> = (0..9).flat_map(|x| (0..9).map(move |y| (x, y))).collect();
+        let smooth = |seed| smooth_excavation(&room, &HashSet::new(), DEFAULT_SMOOTHING_PASSES, &mut StdRng::seed_from_u64(seed));
+        let smoothed = smooth(1);
+        assert_eq!(smoothed, smooth(1));
+        let unique: HashSet<(i32, i32)> = smoothed.iter().copied().collect();
+        assert_eq!(unique.len(), smoothed.len());
+        // The middle of the room stays dug
+        assert!(unique.contains(&(4, 4)));
+        assert!(smooth_excavation(&[], &HashSet::new(), 4, &mut StdRng::seed_from_u64(0)).is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! # Spatial Index
+//!
+//! Bucketed index of the walkable tiles of a dungeon, so range and nearest-tile queries
+//! skip solid rock instead of scanning every excavated coordinate.
+//!
+//! - **Buckets**: The map is cut into squares of `BUCKET_SIZE` tiles a side, each listing
+//!   its walkable tiles row by row; buckets of rock hold nothing.
+//! - **Rectangles**: `SpatialIndex::tiles_in_rect` visits only the buckets overlapping
+//!   the rectangle.
+//! - **Nearest tile**: `SpatialIndex::nearest_floor_tile` searches rings of buckets
+//!   outward from a position, and stops once no bucket left can hold a closer tile.
+//!
+//! `Dungeon::spatial_index` builds the index from the tile grid in one pass. Build it
+//! once and keep it for as long as the grid does not change, as an interactive view or
+//! a spawner placing many things does.
+
+use super::dungeon::{Bounds, Dungeon};
+use super::grid::{Grid, Tile};
+
+/// Tiles per side of a bucket
+pub const BUCKET_SIZE: i32 = 16;
+
+/// Walkable tiles of a dungeon, grouped into buckets of `BUCKET_SIZE` tiles a side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpatialIndex {
+    /// Walkable tiles of each bucket, by bucket coordinates.
+    buckets: Grid<Vec<((i32, i32), Tile)>>,
+    /// Number of indexed tiles.
+    len: usize,
+}
+
+impl SpatialIndex {
+    /// Indexes the walkable tiles of `grid`
+    pub fn new(grid: &Grid<Tile>) -> SpatialIndex {
+        let (x_range, y_range) = (grid.x_range(), grid.y_range());
+        let mut buckets = Grid::new(
+            (x_range.0.div_euclid(BUCKET_SIZE), x_range.1.div_euclid(BUCKET_SIZE)),
+            (y_range.0.div_euclid(BUCKET_SIZE), y_range.1.div_euclid(BUCKET_SIZE)),
+            Vec::new(),
+        );
+        let mut len = 0;
+        for (pos, &tile) in grid.iter().filter(|(_, tile)| tile.is_walkable()) {
+            if let Some(bucket) = buckets.get_mut(bucket_of(pos)) {
+                bucket.push((pos, tile));
+                len += 1;
+            }
+        }
+        SpatialIndex { buckets, len }
+    }
+
+    /// Number of indexed tiles.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no tile is walkable.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Walkable tiles inside the inclusive ranges of `rect`, bucket by bucket from the
+    /// lowest y and row by row within a bucket
+    pub fn tiles_in_rect(&self, rect: Bounds) -> Vec<((i32, i32), Tile)> {
+        let ((x0, x1), (y0, y1)) = (rect.x_range, rect.y_range);
+        let (low, high) = (bucket_of((x0, y0)), bucket_of((x1, y1)));
+        let (bx, by) = (self.buckets.x_range(), self.buckets.y_range());
+        let inside = |&&((x, y), _): &&((i32, i32), Tile)| x >= x0 && x <= x1 && y >= y0 && y <= y1;
+        (low.1.max(by.0)..=high.1.min(by.1))
+            .flat_map(|y| (low.0.max(bx.0)..=high.0.min(bx.1)).map(move |x| (x, y)))
+            .filter_map(|bucket| self.buckets.get(bucket))
+            .flat_map(|bucket| bucket.iter().filter(inside).copied())
+            .collect()
+    }
+
+    /// The walkable tile closest to `pos` by straight-line distance, the lowest `(x, y)`
+    /// of equally close ones; `None` when nothing is walkable
+    pub fn nearest_floor_tile(&self, pos: (i32, i32)) -> Option<(i32, i32)> {
+        if self.is_empty() {
+            return None;
+        }
+        let home = bucket_of(pos);
+        let (bx, by) = (self.buckets.x_range(), self.buckets.y_range());
+        // Rings before the first bucket of the index, or past its last, hold nothing
+        let first_ring = [bx.0 - home.0, home.0 - bx.1, by.0 - home.1, home.1 - by.1, 0].into_iter().max().unwrap_or(0);
+        let last_ring = [home.0 - bx.0, bx.1 - home.0, home.1 - by.0, by.1 - home.1].into_iter().max().unwrap_or(0);
+
+        let mut best: Option<(i64, (i32, i32))> = None;
+        for ring in first_ring..=last_ring {
+            // Tiles of this ring or beyond lie at least this far along one of the axes
+            let gap = (ring as i64 - 1) * BUCKET_SIZE as i64 + 1;
+            if best.is_some_and(|(distance, _)| ring > 0 && distance < gap * gap) {
+                break;
+            }
+            for bucket in ring_buckets(home, ring, (bx, by)) {
+                for &(tile, _) in self.buckets.get(bucket).into_iter().flatten() {
+                    let (dx, dy) = (tile.0 as i64 - pos.0 as i64, tile.1 as i64 - pos.1 as i64);
+                    let candidate = (dx * dx + dy * dy, tile);
+                    if best.is_none_or(|best| candidate < best) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+        best.map(|(_, tile)| tile)
+    }
+}
+
+// Coordinates of the bucket holding `pos`
+fn bucket_of((x, y): (i32, i32)) -> (i32, i32) {
+    (x.div_euclid(BUCKET_SIZE), y.div_euclid(BUCKET_SIZE))
+}
+
+// Buckets `ring` buckets away from `home` along either axis, clipped to the inclusive
+// bucket ranges `(bx, by)`
+fn ring_buckets(home: (i32, i32), ring: i32, (bx, by): ((i32, i32), (i32, i32))) -> Vec<(i32, i32)> {
+    let mut buckets = Vec::new();
+    for y in (home.1 - ring).max(by.0)..=(home.1 + ring).min(by.1) {
+        if (y - home.1).abs() == ring {
+            buckets.extend(((home.0 - ring).max(bx.0)..=(home.0 + ring).min(bx.1)).map(|x| (x, y)));
+        } else {
+            buckets.extend([home.0 - ring, home.0 + ring].into_iter().filter(|x| (bx.0..=bx.1).contains(x)).map(|x| (x, y)));
+        }
+    }
+    buckets
+}
+
+impl Dungeon {
+    /// Spatial index of the walkable tiles of the dungeon (see `spatial`).
+    pub fn spatial_index(&self) -> SpatialIndex {
+        SpatialIndex::new(&self.grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn rectangles_hold_the_walkable_tiles_inside() {
+        let dungeon = dungeon();
+        let index = dungeon.spatial_index();
+        let walkable: Vec<((i32, i32), Tile)> = dungeon.grid.iter().filter(|(_, tile)| tile.is_walkable()).map(|(pos, &tile)| (pos, tile)).collect();
+        assert_eq!(index.len(), walkable.len());
+
+        let (x, y) = dungeon.rooms[0].center;
+        for rect in [
+            Bounds { x_range: (x - 20, x + 5), y_range: (y - 3, y + 40) },
+            dungeon.bounds,
+            Bounds { x_range: (-1000, -900), y_range: (0, 10) },
+        ] {
+            let mut found = index.tiles_in_rect(rect);
+            found.sort_by_key(|&((x, y), _)| (y, x));
+            let inside: Vec<_> = walkable.iter().copied().filter(|&((tx, ty), _)| tx >= rect.x_range.0 && tx <= rect.x_range.1 && ty >= rect.y_range.0 && ty <= rect.y_range.1).collect();
+            assert_eq!(found, inside);
+        }
+    }
+
+    #[test]
+    fn nearest_tiles_match_a_full_scan() {
+        let dungeon = dungeon();
+        let index = dungeon.spatial_index();
+        let scan = |pos: (i32, i32)| {
+            dungeon
+                .grid
+                .iter()
+                .filter(|(_, tile)| tile.is_walkable())
+                .map(|(tile, _)| ((tile.0 - pos.0).pow(2) + (tile.1 - pos.1).pow(2), tile))
+                .min()
+                .map(|(_, tile)| tile)
+        };
+        let (x, y) = dungeon.rooms[0].center;
+        for pos in [(x, y), (x + 7, y - 3), (-50, 300), (1000, -1000), (dungeon.bounds.x_range.1, dungeon.bounds.y_range.0)] {
+            assert_eq!(index.nearest_floor_tile(pos), scan(pos), "nearest to {:?}", pos);
+        }
+        assert_eq!(index.nearest_floor_tile((x, y)), Some((x, y)));
+        assert_eq!(SpatialIndex::new(&Grid::new((0, 3), (0, 3), Tile::Wall)).nearest_floor_tile((1, 1)), None);
+    }
+}
@@ -0,0 +1,143 @@
+//! # Monster Spawning
+//!
+//! Deterministically populates an excavated dungeon with monsters.
+//!
+//! - **Species**: Each dungeon type has its own bestiary (e.g., Hell spawns demons,
+//!   Ice Caverns spawn yetis); the NFT characters pick a species for each spawn.
+//! - **Count**: One monster per room plus one per dungeon level, or as set by the
+//!   dungeon's `Balance`.
+//! - **Placement**: Monsters stand on room tiles, never in the entrance room and never
+//!   two on the same tile. Positions come from the "spawn" RNG stream of the `nft_id`,
+//!   renamed per epoch for living dungeons (see `epoch`).
+//! - **Level**: The dungeon level plus 0-2, picked from the NFT characters.
+
+use std::collections::HashSet;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::dungeon::{Dungeon, RoomKind};
+use super::epoch::epoch_stream;
+use super::excavator::{base_dungeon_type, char_to_num};
+use super::seed::rng_for_stream;
+
+/// A monster placed in the dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonsterSpawn {
+    /// Species name from the dungeon type's bestiary.
+    pub species: String,
+    /// Tile the monster stands on.
+    pub position: (i32, i32),
+    /// Monster level.
+    pub level: u64,
+}
+
+/// Species that can appear in a dungeon of the given type
+pub fn bestiary(dungeon_type: &str) -> [&'static str; 3] {
+    match base_dungeon_type(dungeon_type) {
+        "Ancient Ruins" => ["Skeleton", "Stone Golem", "Mummy"],
+        "Barrens" => ["Vulture", "Scorpion", "Dust Wraith"],
+        "Cave" => ["Bat Swarm", "Cave Troll", "Slime"],
+        "Desert" => ["Sand Worm", "Scorpion", "Mummy"],
+        "Enchanted Forest" => ["Pixie", "Treant", "Will-o'-Wisp"],
+        "Forest" => ["Wolf", "Bear", "Goblin"],
+        "Grassland" => ["Boar", "Centaur", "Giant Beetle"],
+        "Hell" => ["Imp", "Demon", "Hellhound"],
+        "Ice Cavern" => ["Yeti", "Ice Elemental", "Frost Wolf"],
+        "Jungle" => ["Jaguar", "Giant Spider", "Serpent"],
+        "Kingdom Ruins" => ["Ghost Knight", "Gargoyle", "Bandit"],
+        "Lava Pits" => ["Fire Elemental", "Salamander", "Magma Golem"],
+        "Mountain" => ["Harpy", "Stone Giant", "Mountain Goat"],
+        "Necropolis" => ["Zombie", "Lich", "Ghoul"],
+        "Ocean Depths" => ["Merfolk", "Kraken Spawn", "Shark"],
+        "Poison Swamp" => ["Toxic Slime", "Bog Hag", "Plague Rat"],
+        "Quagmire" => ["Mud Golem", "Giant Leech", "Bog Troll"],
+        "Rainforest" => ["Poison Frog", "Great Ape", "Vine Horror"],
+        "Swamp" => ["Lizardfolk", "Crocodile", "Will-o'-Wisp"],
+        "Temple" => ["Cultist", "Guardian Statue", "Priest Wraith"],
+        "Underground Tunnels" => ["Kobold", "Rat Swarm", "Mole Beast"],
+        "Volcanic Crater" => ["Ash Drake", "Fire Imp", "Obsidian Golem"],
+        "Water" => ["Water Elemental", "Eel", "Naga"],
+        "Xeno Hive" => ["Drone", "Xeno Warrior", "Hive Brood"],
+        "Yellow Wasteland" => ["Raider", "Sand Hound", "Mutant"],
+        "Zephyr Highlands" => ["Griffin", "Wind Spirit", "Roc"],
+        _ => ["Goblin", "Giant Rat", "Slime"],
+    }
+}
+
+/// Place monsters in the dungeon's rooms
+pub fn spawn_monsters(dungeon: &Dungeon) -> Vec<MonsterSpawn> {
+    let mut rng = rng_for_stream(&dungeon.seed_id(), &epoch_stream("spawn", dungeon.epoch));
+    let chars: Vec<char> = dungeon.seed_id().chars().skip(4).collect();
+    let species = bestiary(&dungeon.dungeon_type);
+
+    // Candidate tiles: walkable room tiles outside the entrance room
+    let entrance_tiles: HashSet<(i32, i32)> = dungeon
+        .rooms
+        .iter()
+        .filter(|room| room.kind == RoomKind::Entrance)
+        .flat_map(|room| room.tiles.iter().copied())
+        .collect();
+    let mut seen = HashSet::new();
+    let mut candidates: Vec<(i32, i32)> = dungeon
+        .rooms
+        .iter()
+        .flat_map(|room| room.tiles.iter().copied())
+        .filter(|pos| !entrance_tiles.contains(pos) && dungeon.grid.is_walkable(*pos) && seen.insert(*pos))
+        .collect();
+
+    let count = dungeon.balance.monster_count(dungeon.rooms.len(), dungeon.level).min(candidates.len());
+    let mut spawns = Vec::with_capacity(count);
+    for i in 0..count {
+        let position = candidates.swap_remove(rng.gen_range(0..candidates.len()));
+        let trait_value = chars.get(i % chars.len().max(1)).map_or(0, |&c| char_to_num(c)) as usize;
+        spawns.push(MonsterSpawn {
+            species: species[trait_value % species.len()].to_string(),
+            position,
+            level: dungeon.level + (trait_value / species.len() % 3) as u64,
+        });
+    }
+
+    spawns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn monsters_stand_on_distinct_walkable_tiles_outside_the_entrance() {
+        let dungeon = dungeon();
+        let monsters = spawn_monsters(&dungeon);
+        assert!(!monsters.is_empty());
+        let entrance = dungeon.rooms.iter().find(|room| room.kind == RoomKind::Entrance).unwrap();
+        let mut positions = HashSet::new();
+        for monster in &monsters {
+            assert!(dungeon.grid.is_walkable(monster.position));
+            assert!(!entrance.tiles.contains(&monster.position));
+            assert!(positions.insert(monster.position), "two monsters on {:?}", monster.position);
+        }
+    }
+
+    #[test]
+    fn species_and_levels_come_from_the_dungeon() {
+        let dungeon = dungeon();
+        let species = bestiary(&dungeon.dungeon_type);
+        for monster in spawn_monsters(&dungeon) {
+            assert!(species.contains(&monster.species.as_str()));
+            assert!((dungeon.level..=dungeon.level + 2).contains(&monster.level));
+        }
+    }
+
+    #[test]
+    fn spawning_is_deterministic() {
+        let dungeon = dungeon();
+        assert_eq!(spawn_monsters(&dungeon), spawn_monsters(&dungeon));
+        assert_eq!(spawn_monsters(&dungeon), dungeon.monsters);
+    }
+
+    #[test]
+    fn variant_types_share_their_base_bestiary() {
+        assert_eq!(bestiary("Twilight Temple"), bestiary("Temple"));
+        assert_eq!(bestiary("Unknown"), ["Goblin", "Giant Rat", "Slime"]);
+    }
+}
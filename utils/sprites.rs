@@ -0,0 +1,324 @@
+//! # Sprite Map Rendering
+//!
+//! Composes a game-looking PNG map from a sprite tileset instead of plotted markers.
+//!
+//! The tileset is an image atlas of square sprites laid out left to right, top to
+//! bottom, in the order of `Sprite`:
+//! - **Terrain** (`Wall` to `StairsDown`): One opaque sprite per `Tile` variant, in the
+//!   same order as the Tiled export's `tile_id`, so the atlas doubles as its tileset.
+//! - **Overlays** (`Chest` to `Trap`): Sprites with transparent backgrounds drawn on
+//!   top of the terrain for monsters, traps, and the centers of special rooms.
+//! - **Features** (`Water` to `ShallowWater`): Opaque sprites for the terrain features
+//!   carved into the floor (see `terrain`) and the walkable shallows flooding low ground
+//!   (see `liquid`), appended after the overlays.
+//!
+//! `Tileset::default()` uses the embedded 16x16 atlas `DEFAULT_TILESET`; custom atlases
+//! of any sprite size are loaded with `Tileset::load` or `Tileset::from_memory`.
+//!
+//! Walls are only drawn next to excavated tiles; solid rock further away is left black.
+//! Rows run from the highest y down, matching the plotted and ASCII maps.
+
+use std::fmt;
+use std::path::Path;
+use image::imageops;
+use image::{DynamicImage, ImageError, Rgba, RgbaImage};
+use super::dungeon::{Dungeon, RoomKind};
+use super::grid::Tile;
+
+/// The embedded default tileset: 16x16 sprites in a single row
+pub const DEFAULT_TILESET: &[u8] = include_bytes!("../assets/chia_dungeon_tiles.png");
+/// Sprite size of the default tileset in pixels
+pub const DEFAULT_SPRITE_SIZE: u32 = 16;
+/// Number of sprites a tileset must hold, one per `Sprite` variant
+pub const SPRITE_COUNT: u32 = 18;
+
+/// Color of solid rock away from any walkable tile
+const ROCK_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// A sprite in the tileset, in atlas order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sprite {
+    /// Solid rock bordering walkable space.
+    Wall,
+    /// Excavated room floor.
+    Floor,
+    /// Corridor floor.
+    Tunnel,
+    /// Doorway where a tunnel enters a room.
+    Door,
+    /// Floor at the center of a room.
+    RoomCenter,
+    /// Stairs up to the floor above.
+    StairsUp,
+    /// Stairs down to the floor below.
+    StairsDown,
+    /// Treasure room chest overlay.
+    Chest,
+    /// Monster overlay.
+    Monster,
+    /// Entrance room overlay.
+    Entrance,
+    /// Exit room overlay.
+    Exit,
+    /// Boss room overlay.
+    Boss,
+    /// Shrine room overlay.
+    Shrine,
+    /// Trap overlay.
+    Trap,
+    /// Water feature.
+    Water,
+    /// Lava feature.
+    Lava,
+    /// Sand feature.
+    Sand,
+    /// Walkable shallow water.
+    ShallowWater,
+}
+
+impl Sprite {
+    /// Position of the sprite in the atlas
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+
+    /// Terrain sprite for a grid tile
+    pub fn for_tile(tile: Tile) -> Sprite {
+        match tile {
+            Tile::Wall => Sprite::Wall,
+            Tile::Floor => Sprite::Floor,
+            Tile::Tunnel => Sprite::Tunnel,
+            Tile::Door => Sprite::Door,
+            Tile::RoomCenter => Sprite::RoomCenter,
+            Tile::StairsUp => Sprite::StairsUp,
+            Tile::StairsDown => Sprite::StairsDown,
+            Tile::Water => Sprite::Water,
+            Tile::ShallowWater => Sprite::ShallowWater,
+            Tile::Lava => Sprite::Lava,
+            Tile::Sand => Sprite::Sand,
+        }
+    }
+
+    /// Overlay drawn at the center of a room of the given kind, if any
+    pub fn for_room(kind: RoomKind) -> Option<Sprite> {
+        match kind {
+            RoomKind::Entrance => Some(Sprite::Entrance),
+            RoomKind::Exit => Some(Sprite::Exit),
+            RoomKind::Treasure => Some(Sprite::Chest),
+            RoomKind::Boss => Some(Sprite::Boss),
+            RoomKind::Shrine => Some(Sprite::Shrine),
+            RoomKind::Normal => None,
+        }
+    }
+}
+
+/// Errors that can occur while loading a tileset.
+#[derive(Debug)]
+pub enum TilesetError {
+    /// The atlas image could not be read or decoded.
+    Image(ImageError),
+    /// The sprite size is zero.
+    ZeroSpriteSize,
+    /// The atlas holds fewer than `SPRITE_COUNT` sprites of the given size.
+    TooSmall { width: u32, height: u32, sprite_size: u32 },
+}
+
+impl fmt::Display for TilesetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TilesetError::Image(err) => write!(f, "Failed to read tileset image: {}", err),
+            TilesetError::ZeroSpriteSize => write!(f, "Tileset sprite size must be positive."),
+            TilesetError::TooSmall { width, height, sprite_size } => write!(
+                f,
+                "Tileset image is {}x{}, too small for {} sprites of {}x{} pixels.",
+                width, height, SPRITE_COUNT, sprite_size, sprite_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TilesetError {}
+
+impl From<ImageError> for TilesetError {
+    fn from(err: ImageError) -> TilesetError {
+        TilesetError::Image(err)
+    }
+}
+
+/// A sprite atlas used by `render_sprite_map`.
+#[derive(Debug, Clone)]
+pub struct Tileset {
+    atlas: RgbaImage,
+    sprite_size: u32,
+}
+
+impl Default for Tileset {
+    fn default() -> Tileset {
+        Tileset::from_memory(DEFAULT_TILESET, DEFAULT_SPRITE_SIZE).expect("embedded tileset is valid")
+    }
+}
+
+impl Tileset {
+    /// Uses `atlas` as a tileset of `sprite_size` x `sprite_size` sprites
+    pub fn from_image(atlas: DynamicImage, sprite_size: u32) -> Result<Tileset, TilesetError> {
+        if sprite_size == 0 {
+            return Err(TilesetError::ZeroSpriteSize);
+        }
+        let atlas = atlas.into_rgba8();
+        let (width, height) = atlas.dimensions();
+        if (width / sprite_size) * (height / sprite_size) < SPRITE_COUNT {
+            return Err(TilesetError::TooSmall { width, height, sprite_size });
+        }
+        Ok(Tileset { atlas, sprite_size })
+    }
+
+    /// Decodes an encoded atlas image (PNG, etc.)
+    pub fn from_memory(bytes: &[u8], sprite_size: u32) -> Result<Tileset, TilesetError> {
+        Tileset::from_image(image::load_from_memory(bytes)?, sprite_size)
+    }
+
+    /// Loads an atlas image from `path`
+    pub fn load(path: &Path, sprite_size: u32) -> Result<Tileset, TilesetError> {
+        Tileset::from_image(image::open(path)?, sprite_size)
+    }
+
+    /// Width and height of a sprite in pixels
+    pub fn sprite_size(&self) -> u32 {
+        self.sprite_size
+    }
+
+    /// Copy of a single sprite from the atlas
+    pub fn sprite(&self, sprite: Sprite) -> RgbaImage {
+        let columns = self.atlas.width() / self.sprite_size;
+        let (column, row) = (sprite.index() % columns, sprite.index() / columns);
+        imageops::crop_imm(
+            &self.atlas,
+            column * self.sprite_size,
+            row * self.sprite_size,
+            self.sprite_size,
+            self.sprite_size,
+        )
+        .to_image()
+    }
+}
+
+/// Compose the dungeon map from the sprites of `tileset`
+pub fn render_sprite_map(dungeon: &Dungeon, tileset: &Tileset) -> RgbaImage {
+    let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+    let size = tileset.sprite_size();
+    let columns = (x_range.1 - x_range.0 + 1).max(0) as u32;
+    let rows = (y_range.1 - y_range.0 + 1).max(0) as u32;
+    let mut map = RgbaImage::from_pixel(columns * size, rows * size, ROCK_COLOR);
+
+    // Cut each sprite out of the atlas once
+    let sprites: Vec<RgbaImage> = ALL_SPRITES.iter().map(|&sprite| tileset.sprite(sprite)).collect();
+    let pixel = |pos: (i32, i32)| ((pos.0 - x_range.0) as i64 * size as i64, (y_range.1 - pos.1) as i64 * size as i64);
+    let in_bounds = |pos: (i32, i32)| pos.0 >= x_range.0 && pos.0 <= x_range.1 && pos.1 >= y_range.0 && pos.1 <= y_range.1;
+
+    // Terrain
+    for (pos, tile) in dungeon.tiles() {
+        if !in_bounds(pos) || (tile == Tile::Wall && !borders_excavated(dungeon, pos)) {
+            continue;
+        }
+        let (x, y) = pixel(pos);
+        imageops::replace(&mut map, &sprites[Sprite::for_tile(tile).index() as usize], x, y);
+    }
+
+    // Special rooms and traps, then monsters on top
+    for (pos, sprite) in overlays(dungeon).into_iter().filter(|&(pos, _)| in_bounds(pos)) {
+        let (x, y) = pixel(pos);
+        imageops::overlay(&mut map, &sprites[sprite.index() as usize], x, y);
+    }
+
+    map
+}
+
+/// Compose the dungeon map from the sprites of `tileset`, saving it as a PNG to `out_path`
+pub fn save_sprite_map(dungeon: &Dungeon, tileset: &Tileset, out_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    render_sprite_map(dungeon, tileset).save_with_format(out_path, image::ImageFormat::Png)?;
+    Ok(())
+}
+
+// Every sprite in atlas order
+const ALL_SPRITES: [Sprite; SPRITE_COUNT as usize] = [
+    Sprite::Wall,
+    Sprite::Floor,
+    Sprite::Tunnel,
+    Sprite::Door,
+    Sprite::RoomCenter,
+    Sprite::StairsUp,
+    Sprite::StairsDown,
+    Sprite::Chest,
+    Sprite::Monster,
+    Sprite::Entrance,
+    Sprite::Exit,
+    Sprite::Boss,
+    Sprite::Shrine,
+    Sprite::Trap,
+    Sprite::Water,
+    Sprite::Lava,
+    Sprite::Sand,
+    Sprite::ShallowWater,
+];
+
+/// Overlay sprites drawn over the terrain, in drawing order: special rooms and traps,
+/// then monsters
+pub fn overlays(dungeon: &Dungeon) -> Vec<((i32, i32), Sprite)> {
+    dungeon
+        .rooms
+        .iter()
+        .filter_map(|room| Sprite::for_room(room.kind).map(|sprite| (room.center, sprite)))
+        .chain(dungeon.traps().into_iter().map(|trap| (trap.position, Sprite::Trap)))
+        .chain(dungeon.monsters.iter().map(|monster| (monster.position, Sprite::Monster)))
+        .collect()
+}
+
+// Whether any of the eight neighbours of `pos` is excavated
+pub(crate) fn borders_excavated(dungeon: &Dungeon, pos: (i32, i32)) -> bool {
+    (-1..=1).any(|dx| {
+        (-1..=1).any(|dy| (dx, dy) != (0, 0) && dungeon.grid.get((pos.0 + dx, pos.1 + dy)).is_some_and(|&tile| tile != Tile::Wall))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn sprites_follow_the_atlas_order() {
+        for (index, sprite) in ALL_SPRITES.iter().enumerate() {
+            assert_eq!(sprite.index(), index as u32);
+        }
+        assert_eq!(Sprite::for_tile(Tile::Door), Sprite::Door);
+        assert_eq!(Sprite::for_tile(Tile::Lava), Sprite::Lava);
+        assert_ne!(Sprite::for_tile(Tile::ShallowWater), Sprite::for_tile(Tile::Water));
+        assert_eq!(Sprite::for_room(RoomKind::Treasure), Some(Sprite::Chest));
+        assert_eq!(Sprite::for_room(RoomKind::Normal), None);
+    }
+
+    #[test]
+    fn tilesets_need_a_sprite_for_every_slot() {
+        let tileset = Tileset::default();
+        assert_eq!(tileset.sprite_size(), DEFAULT_SPRITE_SIZE);
+        assert_eq!(tileset.sprite(Sprite::Trap).dimensions(), (DEFAULT_SPRITE_SIZE, DEFAULT_SPRITE_SIZE));
+
+        let atlas = |width, height| DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+        assert!(matches!(Tileset::from_image(atlas(64, 64), 0), Err(TilesetError::ZeroSpriteSize)));
+        assert!(matches!(Tileset::from_image(atlas(64, 48), 16), Err(TilesetError::TooSmall { width: 64, height: 48, sprite_size: 16 })));
+        assert!(Tileset::from_image(atlas(32, 224), 16).is_ok());
+        assert!(matches!(Tileset::from_memory(b"not an image", 16), Err(TilesetError::Image(_))));
+    }
+
+    #[test]
+    fn maps_have_one_sprite_per_tile_of_the_bounds() {
+        let dungeon = dungeon();
+        let map = render_sprite_map(&dungeon, &Tileset::default());
+        let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+        let columns = (x_range.1 - x_range.0 + 1) as u32;
+        let rows = (y_range.1 - y_range.0 + 1) as u32;
+        assert_eq!(map.dimensions(), (columns * DEFAULT_SPRITE_SIZE, rows * DEFAULT_SPRITE_SIZE));
+        // Rock far from any walkable tile stays black
+        assert!(map.pixels().any(|&pixel| pixel == ROCK_COLOR));
+    }
+}
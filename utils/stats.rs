@@ -0,0 +1,173 @@
+//! # Dungeon Statistics
+//!
+//! Layout metrics of a dungeon for balancing and rarity analysis.
+//!
+//! - **Areas**: Walkable tiles in total, in rooms, and in corridors (tunnel and door tiles).
+//!   Scattered excavated points count toward the floor area only.
+//! - **Corridor ratio**: Corridor tiles per room tile.
+//! - **Connectivity**: How many tunnels meet at each room, on average and at most.
+//! - **Density**: Share of the tile grid's bounding box that is walkable.
+//! - **Longest path**: The longest of the shortest walkable paths between any two room
+//!   centers, in steps; how far apart the two most distant rooms really are.
+//!
+//! `Dungeon::stats` describes a single floor; use `Dungeon::floor_view` for lower ones.
+//! `DungeonStats` implements `serde::{Serialize, Deserialize}`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Serialize};
+use super::dungeon::Dungeon;
+use super::grid::Tile;
+use super::pathfinding::neighbors;
+
+/// Layout metrics of a dungeon floor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DungeonStats {
+    /// Number of rooms.
+    pub rooms: usize,
+    /// Walkable tiles on the floor.
+    pub floor_area: usize,
+    /// Walkable tiles inside rooms.
+    pub room_area: usize,
+    /// Tunnel and door tiles.
+    pub corridor_area: usize,
+    /// Corridor tiles per room tile.
+    pub corridor_to_room_ratio: f64,
+    /// Mean number of walkable tiles per room.
+    pub average_room_size: f64,
+    /// Mean number of tunnels meeting at a room.
+    pub average_degree: f64,
+    /// Most tunnels meeting at a single room.
+    pub max_degree: usize,
+    /// Tiles in the bounding box of the tile grid.
+    pub bounding_box_area: usize,
+    /// Walkable share of the bounding box, 0 to 1.
+    pub density: f64,
+    /// Longest shortest path between two room centers, in steps.
+    pub longest_path: usize,
+}
+
+impl Dungeon {
+    /// Layout metrics of this floor (see `stats`).
+    pub fn stats(&self) -> DungeonStats {
+        dungeon_stats(self)
+    }
+}
+
+/// Compute the layout metrics of a dungeon floor
+pub fn dungeon_stats(dungeon: &Dungeon) -> DungeonStats {
+    let floor_area = dungeon.excavated_tiles().count();
+    let room_tiles: HashSet<(i32, i32)> = dungeon
+        .rooms
+        .iter()
+        .flat_map(|room| room.tiles.iter().copied())
+        .filter(|&pos| dungeon.grid.is_walkable(pos))
+        .collect();
+    let corridor_area = dungeon
+        .excavated_tiles()
+        .filter(|&(_, tile)| matches!(tile, Tile::Tunnel | Tile::Door))
+        .count();
+    let walkable_room_tiles: usize = dungeon
+        .rooms
+        .iter()
+        .map(|room| room.tiles.iter().filter(|&&pos| dungeon.grid.is_walkable(pos)).count())
+        .sum();
+
+    // Tunnels run from one room center to another
+    let room_at: HashMap<(i32, i32), usize> = dungeon
+        .rooms
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, room)| (room.center, i))
+        .collect();
+    let mut degrees = vec![0; dungeon.rooms.len()];
+    for tunnel in &dungeon.tunnels {
+        for end in [tunnel.first(), tunnel.last()].into_iter().flatten() {
+            if let Some(&room) = room_at.get(end) {
+                degrees[room] += 1;
+            }
+        }
+    }
+
+    let bounding_box_area = dungeon.grid.width() * dungeon.grid.height();
+    let ratio = |part: f64, whole: f64| if whole > 0.0 { part / whole } else { 0.0 };
+
+    DungeonStats {
+        rooms: dungeon.rooms.len(),
+        floor_area,
+        room_area: room_tiles.len(),
+        corridor_area,
+        corridor_to_room_ratio: ratio(corridor_area as f64, room_tiles.len() as f64),
+        average_room_size: ratio(walkable_room_tiles as f64, dungeon.rooms.len() as f64),
+        average_degree: ratio(degrees.iter().sum::<usize>() as f64, degrees.len() as f64),
+        max_degree: degrees.iter().copied().max().unwrap_or(0),
+        bounding_box_area,
+        density: ratio(floor_area as f64, bounding_box_area as f64),
+        longest_path: longest_path(dungeon),
+    }
+}
+
+// Longest shortest path between two reachable room centers, by a flood fill from each center
+fn longest_path(dungeon: &Dungeon) -> usize {
+    let centers: HashSet<(i32, i32)> = dungeon
+        .rooms
+        .iter()
+        .map(|room| room.center)
+        .filter(|&center| dungeon.grid.is_walkable(center))
+        .collect();
+
+    let mut longest = 0;
+    for &start in &centers {
+        let mut distances = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(pos) = queue.pop_front() {
+            let distance = distances[&pos];
+            if centers.contains(&pos) {
+                longest = longest.max(distance);
+            }
+            for next in neighbors(pos) {
+                if dungeon.grid.is_walkable(next) && !distances.contains_key(&next) {
+                    distances.insert(next, distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+
+    #[test]
+    fn areas_add_up() {
+        let dungeon = dungeon();
+        let stats = dungeon.stats();
+        assert_eq!(stats.rooms, 9);
+        assert!(stats.room_area + stats.corridor_area <= stats.floor_area);
+        assert_eq!(stats.bounding_box_area, dungeon.grid.width() * dungeon.grid.height());
+        assert_eq!(stats.density, stats.floor_area as f64 / stats.bounding_box_area as f64);
+        assert_eq!(stats.corridor_to_room_ratio, stats.corridor_area as f64 / stats.room_area as f64);
+    }
+
+    #[test]
+    fn tunnels_of_the_spanning_tree_join_the_rooms() {
+        let stats = dungeon().stats();
+        // A tree over n rooms has n - 1 edges, each meeting two rooms
+        assert_eq!(stats.average_degree, 2.0 * 8.0 / 9.0);
+        assert!(stats.max_degree >= 2);
+    }
+
+    #[test]
+    fn the_longest_path_spans_the_farthest_rooms() {
+        let dungeon = dungeon();
+        let (first, last) = (dungeon.rooms[0].center, dungeon.rooms[8].center);
+        let between = dungeon.path(first, last).unwrap().len() - 1;
+        assert!(dungeon.stats().longest_path >= between);
+
+        let mut single = dungeon.clone();
+        single.rooms.truncate(1);
+        assert_eq!(single.stats().longest_path, 0);
+    }
+}
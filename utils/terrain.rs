@@ -0,0 +1,208 @@
+//! # Terrain Features
+//!
+//! Carves water, lava, and sand into the excavated ground of the dungeon types that
+//! have them, as extra `Tile` kinds that block movement but not sight.
+//!
+//! - **Water** ("Water", "Ocean Depths"): A river meandering across the map, plus
+//!   flooded pools.
+//! - **Lava** ("Lava Pits"): Narrow lava channels crossing the map.
+//! - **Sand** ("Desert"): Drifts of sand heaped up in clusters.
+//!
+//! Variant types of upper-case letters get the features of their base type. Features are
+//! drawn from the "terrain" RNG stream of the `nft_id`. Rivers and channels run through
+//! rock and floor alike, pools and drifts only replace floor. Tunnel corridors and room
+//! centers are protected, so every room stays reachable (corridors bridge the rivers),
+//! and floor cut off from the rooms by a feature is swallowed by it, so nothing walkable
+//! is stranded.
+//!
+//! `GenerationConfig::terrain` turns the features off.
+
+use std::collections::HashSet;
+use rand::Rng;
+use super::excavator::base_dungeon_type;
+use super::grid::{Grid, Tile};
+use super::pathfinding::reachable_tiles;
+use super::theme::Rgb;
+
+/// Color of deep water tiles on rendered maps
+pub const WATER_COLOR: Rgb = (150, 230, 255);
+/// Color of shallow water tiles on rendered maps (see `liquid`)
+pub const SHALLOW_WATER_COLOR: Rgb = (200, 245, 255);
+/// Color of lava tiles on rendered maps
+pub const LAVA_COLOR: Rgb = (255, 230, 60);
+/// Color of sand tiles on rendered maps
+pub const SAND_COLOR: Rgb = (160, 120, 60);
+
+/// Floor tiles per flooded pool or sand drift
+const TILES_PER_CLUSTER: usize = 250;
+
+/// Feature tile carved into dungeons of the given type, if any
+pub fn terrain_for_type(dungeon_type: &str) -> Option<Tile> {
+    match base_dungeon_type(dungeon_type) {
+        "Water" | "Ocean Depths" => Some(Tile::Water),
+        "Lava Pits" => Some(Tile::Lava),
+        "Desert" => Some(Tile::Sand),
+        _ => None,
+    }
+}
+
+/// Color of a terrain feature tile on rendered maps; `None` for other tiles
+pub fn terrain_color(tile: Tile) -> Option<Rgb> {
+    match tile {
+        Tile::Water => Some(WATER_COLOR),
+        Tile::Lava => Some(LAVA_COLOR),
+        Tile::Sand => Some(SAND_COLOR),
+        Tile::ShallowWater => Some(SHALLOW_WATER_COLOR),
+        _ => None,
+    }
+}
+
+/// Carve the features of `dungeon_type` into the floor of `grid`, never touching a
+/// `protected` tile; returns the number of tiles carved
+pub fn carve_terrain(grid: &mut Grid<Tile>, dungeon_type: &str, protected: &HashSet<(i32, i32)>, rng: &mut impl Rng) -> usize {
+    let Some(feature) = terrain_for_type(dungeon_type) else {
+        return 0;
+    };
+    let Some(&start) = protected.iter().min() else {
+        return 0;
+    };
+    let floor: Vec<(i32, i32)> = grid.iter().filter(|&(_, &tile)| tile == Tile::Floor).map(|(pos, _)| pos).collect();
+    if floor.is_empty() {
+        return 0;
+    }
+    let reachable = reachable_tiles(grid, start);
+
+    let mut carved = 0;
+    let mut carve = |grid: &mut Grid<Tile>, pos: (i32, i32), through_rock: bool| {
+        let carvable = match grid.get(pos) {
+            Some(Tile::Floor) => true,
+            Some(Tile::Wall) => through_rock,
+            _ => false,
+        };
+        if carvable && !protected.contains(&pos) {
+            grid.set(pos, feature);
+            carved += 1;
+        }
+    };
+    let clusters = (floor.len() / TILES_PER_CLUSTER).max(1);
+    match feature {
+        Tile::Water => {
+            for pos in meander(grid, 2, rng) {
+                carve(grid, pos, true);
+            }
+            for _ in 0..clusters {
+                let center = floor[rng.gen_range(0..floor.len())];
+                for pos in disk(center, rng.gen_range(1..=3)) {
+                    carve(grid, pos, false);
+                }
+            }
+        }
+        Tile::Lava => {
+            for _ in 0..2 {
+                for pos in meander(grid, 1, rng) {
+                    carve(grid, pos, true);
+                }
+            }
+        }
+        _ => {
+            for _ in 0..clusters {
+                let mut pos = floor[rng.gen_range(0..floor.len())];
+                for _ in 0..rng.gen_range(6..=14) {
+                    for drift in disk(pos, 1) {
+                        carve(grid, drift, false);
+                    }
+                    pos = (pos.0 + rng.gen_range(-1..=1), pos.1 + rng.gen_range(-1..=1));
+                }
+            }
+        }
+    }
+
+    // Swallow the floor the features cut off from the rooms
+    let still_reachable = reachable_tiles(grid, start);
+    for pos in reachable.difference(&still_reachable) {
+        if grid.get(*pos) == Some(&Tile::Floor) {
+            grid.set(*pos, feature);
+            carved += 1;
+        }
+    }
+    carved
+}
+
+// A band `width` tiles wide crossing the grid from one edge to the opposite one,
+// drifting sideways at random
+fn meander(grid: &Grid<Tile>, width: i32, rng: &mut impl Rng) -> Vec<(i32, i32)> {
+    let (x_range, y_range) = (grid.x_range(), grid.y_range());
+    let horizontal = rng.gen_bool(0.5);
+    let (along, across) = if horizontal { (x_range, y_range) } else { (y_range, x_range) };
+    let mut offset = rng.gen_range(across.0..=across.1);
+    let mut band = Vec::new();
+    for step in along.0..=along.1 {
+        for side in 0..width {
+            let cross = offset + side;
+            band.push(if horizontal { (step, cross) } else { (cross, step) });
+        }
+        offset = (offset + rng.gen_range(-1..=1)).clamp(across.0, across.1);
+    }
+    band
+}
+
+// Tiles within `radius` of `center`
+fn disk(center: (i32, i32), radius: i32) -> impl Iterator<Item = (i32, i32)> {
+    (-radius..=radius)
+        .flat_map(move |dx| (-radius..=radius).map(move |dy| (dx, dy)))
+        .filter(move |&(dx, dy)| dx * dx + dy * dy <= radius * radius)
+        .map(move |(dx, dy)| (center.0 + dx, center.1 + dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::super::excavator::parse_nft_id;
+    use super::super::generator::{generate_nft_id_with, GenerateOptions};
+    use super::*;
+
+    // A dungeon of the given type
+    fn dungeon_of_type(dungeon_type: &str) -> super::super::dungeon::Dungeon {
+        let options = GenerateOptions { dungeon_type: Some(dungeon_type.to_string()), seed: Some(3), ..GenerateOptions::default() };
+        parse_nft_id(&generate_nft_id_with(&options).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn features_follow_the_dungeon_type() {
+        assert_eq!(terrain_for_type("Ocean Depths"), Some(Tile::Water));
+        assert_eq!(terrain_for_type("Whirlpool Grotto"), Some(Tile::Water));
+        assert_eq!(terrain_for_type("Lava Tubes"), Some(Tile::Lava));
+        assert_eq!(terrain_for_type("Desert"), Some(Tile::Sand));
+        assert_eq!(terrain_for_type("Temple"), None);
+        assert_eq!(terrain_color(Tile::Lava), Some(LAVA_COLOR));
+        assert_eq!(terrain_color(Tile::Floor), None);
+    }
+
+    #[test]
+    fn features_block_movement_but_keep_every_room_reachable() {
+        for (dungeon_type, feature) in [("Water", Tile::Water), ("Lava Pits", Tile::Lava), ("Desert", Tile::Sand)] {
+            let dungeon = dungeon_of_type(dungeon_type);
+            assert!(dungeon.grid.iter().any(|(_, &tile)| tile == feature), "{} has no {:?}", dungeon_type, feature);
+            assert!(!feature.is_walkable());
+            assert!(dungeon.is_fully_connected());
+            assert!(dungeon.monsters.iter().all(|monster| dungeon.grid.is_walkable(monster.position)));
+        }
+        assert!(dungeon_of_type("Temple").grid.iter().all(|(_, &tile)| terrain_color(tile).is_none()));
+    }
+
+    #[test]
+    fn protected_tiles_are_never_carved() {
+        let mut grid = Grid::new((0, 19), (0, 19), Tile::Floor);
+        let corridor: HashSet<(i32, i32)> = (0..20).map(|x| (x, 10)).collect();
+        for seed in 0..8 {
+            let mut carved = grid.clone();
+            assert!(carve_terrain(&mut carved, "Water", &corridor, &mut StdRng::seed_from_u64(seed)) > 0);
+            assert!(corridor.iter().all(|&pos| carved.get(pos) == Some(&Tile::Floor)));
+            // Floor cut off from the corridor is swallowed by the water
+            let reachable = reachable_tiles(&carved, (0, 10));
+            assert!(carved.iter().all(|(pos, &tile)| tile != Tile::Floor || reachable.contains(&pos)));
+        }
+        assert_eq!(carve_terrain(&mut grid, "Temple", &corridor, &mut StdRng::seed_from_u64(0)), 0);
+    }
+}
@@ -0,0 +1,205 @@
+//! # Rendering Themes
+//!
+//! Maps each of the 26 dungeon types to a color palette, tile style, and ASCII glyphs,
+//! so a "Lava Pits" map looks different from an "Ice Cavern" one. Variant types of
+//! upper-case letters (e.g., "Lava Tubes") share the palette of their base type.
+//!
+//! - **Plotted maps** (`plot`): `background` fills the image, excavated tiles use `floor`
+//!   drawn in the theme's `TileStyle`, tiles seen under fog use `seen`, and monsters use
+//!   `monster`. Room markers keep their per-kind colors.
+//! - **ASCII maps** (`ascii::render_ascii_themed`): empty space and excavated ground use
+//!   `wall_glyph` and `floor_glyph`, colored with ANSI true-color escapes.
+//!
+//! `render_dungeon_map` picks the theme for the dungeon's type (`Theme::for_dungeon`);
+//! `render_themed_map` accepts any theme, including `Theme::classic`, the original
+//! red-on-white look.
+
+use serde::{Deserialize, Serialize};
+use super::dungeon::Dungeon;
+use super::excavator::{base_dungeon_type, DUNGEON_VARIANTS};
+
+/// An RGB color.
+pub type Rgb = (u8, u8, u8);
+
+/// Name accepted by `Theme::by_name` for the classic theme
+pub const CLASSIC_THEME: &str = "Classic";
+
+/// How excavated tiles are drawn on plotted maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TileStyle {
+    /// A small dot per tile.
+    Dot,
+    /// A solid square filling the tile.
+    Block,
+}
+
+/// Colors and glyphs used by the renderers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Theme {
+    /// Theme name; the dungeon type it was made for, or "Classic".
+    pub name: String,
+    /// Background color of plotted maps and ASCII empty space.
+    pub background: Rgb,
+    /// Color of ASCII empty space.
+    pub wall: Rgb,
+    /// Color of excavated tiles.
+    pub floor: Rgb,
+    /// Color of excavated tiles seen earlier but not currently visible.
+    pub seen: Rgb,
+    /// Color of monster markers.
+    pub monster: Rgb,
+    /// How excavated tiles are drawn on plotted maps.
+    pub tile_style: TileStyle,
+    /// ASCII glyph for empty space.
+    pub wall_glyph: char,
+    /// ASCII glyph for excavated ground.
+    pub floor_glyph: char,
+}
+
+impl Theme {
+    /// The original look: red dots on white, `@` walls and `O` floors.
+    pub fn classic() -> Theme {
+        Theme {
+            name: CLASSIC_THEME.to_string(),
+            background: (255, 255, 255),
+            wall: (0, 0, 0),
+            floor: (255, 0, 0),
+            seen: (190, 190, 190),
+            monster: (0, 0, 0),
+            tile_style: TileStyle::Dot,
+            wall_glyph: '@',
+            floor_glyph: 'O',
+        }
+    }
+
+    /// Theme for a dungeon type; unknown types get the classic theme.
+    pub fn for_dungeon_type(dungeon_type: &str) -> Theme {
+        use TileStyle::{Block, Dot};
+
+        // (background, wall, floor, seen, monster, tile style, wall glyph, floor glyph)
+        let (background, wall, floor, seen, monster, tile_style, wall_glyph, floor_glyph) = match base_dungeon_type(dungeon_type) {
+            "Ancient Ruins" => ((236, 226, 198), (120, 105, 80), (150, 120, 70), (205, 195, 170), (70, 40, 20), Block, '#', '.'),
+            "Barrens" => ((222, 205, 170), (140, 115, 80), (170, 110, 60), (200, 185, 155), (90, 40, 20), Dot, ':', '.'),
+            "Cave" => ((60, 55, 50), (110, 100, 90), (170, 160, 145), (95, 90, 85), (230, 200, 60), Block, '#', '.'),
+            "Desert" => ((250, 230, 180), (200, 160, 90), (220, 150, 60), (235, 215, 175), (120, 50, 20), Dot, '~', '.'),
+            "Enchanted Forest" => ((30, 50, 60), (60, 120, 90), (170, 120, 230), (70, 90, 100), (250, 230, 120), Dot, '*', '.'),
+            "Forest" => ((225, 240, 215), (40, 100, 40), (110, 80, 40), (195, 210, 185), (200, 30, 30), Dot, 'T', '.'),
+            "Grassland" => ((210, 240, 170), (90, 160, 60), (170, 140, 80), (190, 215, 160), (150, 30, 30), Dot, '"', '.'),
+            "Hell" => ((40, 0, 0), (120, 20, 0), (255, 90, 0), (90, 40, 30), (255, 230, 0), Block, '^', '.'),
+            "Ice Cavern" => ((230, 245, 255), (150, 190, 220), (60, 140, 210), (200, 220, 235), (20, 40, 110), Block, '#', '.'),
+            "Jungle" => ((200, 230, 170), (30, 110, 40), (120, 90, 40), (180, 205, 160), (220, 120, 0), Dot, '&', '.'),
+            "Kingdom Ruins" => ((225, 222, 215), (110, 105, 120), (130, 60, 140), (200, 198, 195), (40, 30, 60), Block, '#', '.'),
+            "Lava Pits" => ((50, 15, 5), (110, 40, 20), (255, 120, 0), (100, 55, 40), (255, 240, 120), Block, '~', '.'),
+            "Mountain" => ((220, 225, 230), (120, 120, 130), (100, 90, 80), (200, 200, 205), (160, 20, 20), Dot, '^', '.'),
+            "Necropolis" => ((25, 25, 35), (70, 70, 90), (170, 200, 170), (70, 75, 80), (150, 255, 150), Block, '+', '.'),
+            "Ocean Depths" => ((5, 30, 70), (20, 70, 120), (40, 180, 200), (40, 70, 100), (255, 140, 90), Dot, '~', '.'),
+            "Poison Swamp" => ((40, 55, 25), (80, 110, 30), (170, 230, 40), (80, 95, 60), (230, 60, 200), Dot, '%', '.'),
+            "Quagmire" => ((95, 80, 55), (70, 60, 40), (140, 120, 80), (120, 110, 90), (230, 220, 180), Block, '%', '.'),
+            "Rainforest" => ((190, 225, 190), (20, 120, 70), (70, 150, 60), (170, 200, 170), (230, 30, 100), Dot, '&', '.'),
+            "Swamp" => ((170, 190, 150), (70, 90, 60), (100, 120, 70), (160, 170, 140), (200, 230, 80), Dot, '%', '.'),
+            "Temple" => ((250, 245, 225), (190, 160, 80), (220, 180, 40), (230, 225, 205), (120, 20, 60), Block, '#', '.'),
+            "Underground Tunnels" => ((45, 40, 35), (90, 75, 60), (150, 130, 100), (85, 80, 75), (240, 180, 80), Block, '#', '.'),
+            "Volcanic Crater" => ((35, 30, 30), (90, 80, 80), (230, 60, 20), (80, 70, 70), (255, 200, 0), Block, '^', '.'),
+            "Water" => ((225, 240, 255), (110, 170, 230), (20, 100, 200), (200, 220, 240), (0, 60, 60), Dot, '~', '.'),
+            "Xeno Hive" => ((20, 30, 20), (60, 90, 40), (140, 255, 60), (60, 80, 55), (255, 60, 220), Block, '0', '.'),
+            "Yellow Wasteland" => ((245, 235, 150), (190, 170, 60), (150, 120, 30), (225, 215, 150), (90, 60, 0), Dot, ':', '.'),
+            "Zephyr Highlands" => ((235, 245, 250), (170, 200, 210), (90, 170, 140), (215, 225, 230), (60, 60, 140), Dot, '^', '.'),
+            _ => return Theme::classic(),
+        };
+
+        Theme {
+            name: dungeon_type.to_string(),
+            background,
+            wall,
+            floor,
+            seen,
+            monster,
+            tile_style,
+            wall_glyph,
+            floor_glyph,
+        }
+    }
+
+    /// Theme for the dungeon's type.
+    pub fn for_dungeon(dungeon: &Dungeon) -> Theme {
+        Theme::for_dungeon_type(&dungeon.dungeon_type)
+    }
+
+    /// Theme by name: "Classic" or a dungeon type, ignoring case.
+    pub fn by_name(name: &str) -> Option<Theme> {
+        if name.eq_ignore_ascii_case(CLASSIC_THEME) {
+            return Some(Theme::classic());
+        }
+        DUNGEON_TYPES
+            .iter()
+            .chain(DUNGEON_VARIANTS.iter().map(|(variant, _)| variant))
+            .find(|dungeon_type| dungeon_type.eq_ignore_ascii_case(name))
+            .map(|dungeon_type| Theme::for_dungeon_type(dungeon_type))
+    }
+}
+
+/// Every dungeon type with its own theme
+pub const DUNGEON_TYPES: [&str; 26] = [
+    "Ancient Ruins",
+    "Barrens",
+    "Cave",
+    "Desert",
+    "Enchanted Forest",
+    "Forest",
+    "Grassland",
+    "Hell",
+    "Ice Cavern",
+    "Jungle",
+    "Kingdom Ruins",
+    "Lava Pits",
+    "Mountain",
+    "Necropolis",
+    "Ocean Depths",
+    "Poison Swamp",
+    "Quagmire",
+    "Rainforest",
+    "Swamp",
+    "Temple",
+    "Underground Tunnels",
+    "Volcanic Crater",
+    "Water",
+    "Xeno Hive",
+    "Yellow Wasteland",
+    "Zephyr Highlands",
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn every_dungeon_type_has_its_own_palette() {
+        let palettes: HashSet<(Rgb, Rgb, Rgb)> = DUNGEON_TYPES
+            .iter()
+            .map(|dungeon_type| Theme::for_dungeon_type(dungeon_type))
+            .inspect(|theme| assert_ne!(theme.name, CLASSIC_THEME))
+            .map(|theme| (theme.background, theme.wall, theme.floor))
+            .collect();
+        assert_eq!(palettes.len(), DUNGEON_TYPES.len());
+        assert_eq!(Theme::for_dungeon_type("Nowhere"), Theme::classic());
+    }
+
+    #[test]
+    fn variants_share_their_base_palette() {
+        let (variant, base) = DUNGEON_VARIANTS[0];
+        let (variant_theme, base_theme) = (Theme::for_dungeon_type(variant), Theme::for_dungeon_type(base));
+        assert_eq!(variant_theme.name, variant);
+        assert_eq!(Theme { name: base.to_string(), ..variant_theme }, base_theme);
+        assert_eq!(Theme::for_dungeon(&dungeon()), Theme::for_dungeon_type("Temple"));
+    }
+
+    #[test]
+    fn themes_are_found_by_name_ignoring_case() {
+        assert_eq!(Theme::by_name("classic"), Some(Theme::classic()));
+        assert_eq!(Theme::by_name("lava pits").unwrap().name, "Lava Pits");
+        assert_eq!(Theme::by_name(DUNGEON_VARIANTS[3].0).unwrap().name, DUNGEON_VARIANTS[3].0);
+        assert_eq!(Theme::by_name("Moon Base"), None);
+    }
+}
@@ -0,0 +1,221 @@
+//! # Room Topology
+//!
+//! Which rooms of a dungeon open into which, so AI, quest generation, and lock-and-key
+//! logic (see `progression`) can reason about the layout without walking tiles.
+//!
+//! - **Tunnels**: Each tunnel directly joins the rooms centered on its two ends, and is
+//!   as long as its steps. Rooms sharing a center are all joined, but never to
+//!   themselves.
+//! - **Shared walls**: Rooms whose tiles overlap, or lie side by side, open into each
+//!   other without a tunnel. `SharedWall` counts the tiles they cover together and the
+//!   pairs of tiles, one of each room, that touch along an edge.
+//! - **Neighbors**: Rooms joined either way are neighbors; `RoomGraph::hops` counts the
+//!   rooms crossed from one room to every other.
+//!
+//! Rooms are numbered by their index in `Dungeon::rooms`, and pairs list the lower
+//! index first.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use super::dungeon::Dungeon;
+use super::pathfinding::neighbors;
+
+/// A tunnel directly joining two rooms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TunnelLink {
+    /// Indices of the two rooms, the lower first.
+    pub rooms: (usize, usize),
+    /// Index of the tunnel in `Dungeon::tunnels`.
+    pub tunnel: usize,
+    /// Length of the tunnel in steps.
+    pub length: usize,
+}
+
+/// Two rooms whose tiles overlap or touch, opening into each other without a tunnel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharedWall {
+    /// Indices of the two rooms, the lower first.
+    pub rooms: (usize, usize),
+    /// Tiles both rooms cover.
+    pub overlap: usize,
+    /// Pairs of edge-adjacent tiles, each covered by one of the rooms but not the other.
+    pub contact: usize,
+}
+
+/// Adjacency of the rooms of a dungeon (see `topology`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomGraph {
+    /// Tunnels joining two distinct rooms, in the order of `Dungeon::tunnels`.
+    pub tunnels: Vec<TunnelLink>,
+    /// Pairs of rooms that overlap or touch, ordered by their indices.
+    pub shared_walls: Vec<SharedWall>,
+    /// Neighbors of every room, sorted and without repeats.
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl RoomGraph {
+    /// The room graph of `dungeon`
+    pub fn new(dungeon: &Dungeon) -> RoomGraph {
+        let rooms = &dungeon.rooms;
+        let mut centers: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (index, room) in rooms.iter().enumerate() {
+            centers.entry(room.center).or_default().push(index);
+        }
+        let at = |pos: Option<&(i32, i32)>| pos.and_then(|pos| centers.get(pos)).map_or(&[][..], Vec::as_slice);
+        let mut tunnels = Vec::new();
+        for (index, tunnel) in dungeon.tunnels.iter().enumerate() {
+            for &from in at(tunnel.first()) {
+                for &to in at(tunnel.last()).iter().filter(|&&to| to != from) {
+                    tunnels.push(TunnelLink { rooms: (from.min(to), from.max(to)), tunnel: index, length: tunnel.len() - 1 });
+                }
+            }
+        }
+
+        // Rooms covering each tile
+        let mut covering: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (index, room) in rooms.iter().enumerate() {
+            for &tile in &room.tiles {
+                let owners = covering.entry(tile).or_default();
+                if !owners.contains(&index) {
+                    owners.push(index);
+                }
+            }
+        }
+        let mut walls: BTreeMap<(usize, usize), SharedWall> = BTreeMap::new();
+        let mut wall = |a: usize, b: usize, overlap: usize, contact: usize| {
+            let rooms = (a.min(b), a.max(b));
+            let wall = walls.entry(rooms).or_insert(SharedWall { rooms, overlap: 0, contact: 0 });
+            wall.overlap += overlap;
+            wall.contact += contact;
+        };
+        for (&tile, owners) in &covering {
+            for (i, &a) in owners.iter().enumerate() {
+                for &b in &owners[i + 1..] {
+                    wall(a, b, 1, 0);
+                }
+                // Each touching pair is counted from the tile of its lower room
+                for next in neighbors(tile) {
+                    let touching = covering.get(&next).map_or(&[][..], Vec::as_slice);
+                    if touching.contains(&a) {
+                        continue;
+                    }
+                    for &b in touching.iter().filter(|&&b| b > a && !owners.contains(&b)) {
+                        wall(a, b, 0, 1);
+                    }
+                }
+            }
+        }
+
+        let mut adjacency = vec![Vec::new(); rooms.len()];
+        for (a, b) in tunnels.iter().map(|link| link.rooms).chain(walls.keys().copied()) {
+            adjacency[a].push(b);
+            adjacency[b].push(a);
+        }
+        for neighbors in &mut adjacency {
+            neighbors.sort_unstable();
+            neighbors.dedup();
+        }
+        RoomGraph { tunnels, shared_walls: walls.into_values().collect(), adjacency }
+    }
+
+    /// Rooms joined to `room` by a tunnel or a shared wall, in index order; none for
+    /// rooms the dungeon does not have
+    pub fn neighbors(&self, room: usize) -> &[usize] {
+        self.adjacency.get(room).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether a tunnel or a shared wall joins rooms `a` and `b`.
+    pub fn are_adjacent(&self, a: usize, b: usize) -> bool {
+        self.neighbors(a).binary_search(&b).is_ok()
+    }
+
+    /// Length in steps of the shortest tunnel directly joining rooms `a` and `b`
+    pub fn tunnel_length(&self, a: usize, b: usize) -> Option<usize> {
+        let rooms = (a.min(b), a.max(b));
+        self.tunnels.iter().filter(|link| link.rooms == rooms).map(|link| link.length).min()
+    }
+
+    /// The shared wall of rooms `a` and `b`, if they overlap or touch
+    pub fn shared_wall(&self, a: usize, b: usize) -> Option<&SharedWall> {
+        let rooms = (a.min(b), a.max(b));
+        self.shared_walls.iter().find(|wall| wall.rooms == rooms)
+    }
+
+    /// Fewest moves between neighboring rooms from room `from` to every room, by index;
+    /// `None` for rooms it cannot reach
+    pub fn hops(&self, from: usize) -> Vec<Option<usize>> {
+        let mut hops = vec![None; self.adjacency.len()];
+        if from >= hops.len() {
+            return hops;
+        }
+        hops[from] = Some(0);
+        let mut queue = VecDeque::from([from]);
+        while let Some(room) = queue.pop_front() {
+            let next_hops = hops[room].map(|hops| hops + 1);
+            for &next in &self.adjacency[room] {
+                if hops[next].is_none() {
+                    hops[next] = next_hops;
+                    queue.push_back(next);
+                }
+            }
+        }
+        hops
+    }
+}
+
+impl Dungeon {
+    /// Adjacency of the rooms of the dungeon (see `topology`).
+    pub fn room_graph(&self) -> RoomGraph {
+        RoomGraph::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn tunnels_join_their_end_rooms() {
+        let dungeon = dungeon();
+        let graph = dungeon.room_graph();
+        assert_eq!(graph.tunnels.len(), dungeon.tunnels.len());
+        for link in &graph.tunnels {
+            let tunnel = &dungeon.tunnels[link.tunnel];
+            assert_eq!(link.length, tunnel.len() - 1);
+            let ends = [tunnel[0], tunnel[tunnel.len() - 1]];
+            assert!(ends.contains(&dungeon.rooms[link.rooms.0].center) && ends.contains(&dungeon.rooms[link.rooms.1].center));
+            assert!(graph.are_adjacent(link.rooms.1, link.rooms.0));
+            assert!(graph.tunnel_length(link.rooms.1, link.rooms.0).is_some_and(|length| length <= link.length));
+        }
+        // The tunnels span every room, so every room is a finite number of hops away
+        let hops = graph.hops(0);
+        assert!(hops.iter().all(Option::is_some));
+        assert_eq!(hops[0], Some(0));
+        assert!(graph.neighbors(0).iter().all(|&room| hops[room] == Some(1)));
+        assert!(graph.neighbors(dungeon.rooms.len()).is_empty());
+    }
+
+    #[test]
+    fn overlapping_and_touching_rooms_share_walls() {
+        let mut dungeon = dungeon();
+        dungeon.tunnels.clear();
+        for room in &mut dungeon.rooms {
+            room.tiles.clear();
+        }
+        dungeon.rooms[0].tiles = vec![(0, 0), (1, 0), (0, 1), (1, 1)];
+        dungeon.rooms[1].tiles = vec![(1, 0), (1, 1), (2, 0), (2, 1)];
+        dungeon.rooms[2].tiles = vec![(3, 0), (3, 1), (3, 2)];
+        dungeon.rooms[3].tiles = vec![(9, 9)];
+
+        let graph = dungeon.room_graph();
+        assert_eq!(graph.shared_walls, vec![
+            SharedWall { rooms: (0, 1), overlap: 2, contact: 0 },
+            SharedWall { rooms: (1, 2), overlap: 0, contact: 2 },
+        ]);
+        assert_eq!(graph.shared_wall(2, 1), Some(&graph.shared_walls[1]));
+        assert_eq!(graph.neighbors(1), [0, 2]);
+        assert_eq!(graph.tunnel_length(0, 1), None);
+        assert!(!graph.are_adjacent(0, 2));
+        assert_eq!(graph.hops(0)[..4], [Some(0), Some(1), Some(2), None]);
+    }
+}
@@ -0,0 +1,209 @@
+//! # Geometric Transforms
+//!
+//! Moves, turns, flips, and enlarges a parsed dungeon, for composing dungeons into larger
+//! worlds. Each transform returns a new `Dungeon` with every room, tunnel, excavated tile,
+//! grid cell, elevation, monster, decoration object, and stairs position transformed, on
+//! every floor.
+//!
+//! - **`translate(dx, dy)`**: Shifts the dungeon by `dx` and `dy` tiles.
+//! - **`rotate90()`**: Turns the dungeon a quarter turn counterclockwise about the
+//!   origin, so `(x, y)` becomes `(-y, x)`.
+//! - **`mirror_x()`**: Flips the x coordinates, so `(x, y)` becomes `(-x, y)`.
+//! - **`scale(factor)`**: Enlarges every tile into a `factor` x `factor` block whose
+//!   lowest corner lies at `(x * factor, y * factor)`. Room centers, monsters, decoration
+//!   objects, and stairs stay single tiles at the corner of their block, the rest of the
+//!   block becoming floor; tunnel paths are stretched without gaps and the corridor width
+//!   grows by the factor.
+//!
+//! Bounds are recomputed around the transformed tiles (see `Dungeon::recompute_bounds`);
+//! the type, level, area, and other attributes decoded from the NFT ID are kept.
+
+use super::decorate::Decoration;
+use super::dungeon::{Bounds, Dungeon, Room};
+use super::grid::{Grid, Tile};
+use super::spawn::MonsterSpawn;
+
+impl Dungeon {
+    /// The dungeon shifted by `dx` and `dy` tiles.
+    pub fn translate(&self, dx: i32, dy: i32) -> Dungeon {
+        Transform { factor: 1, anchor: |(x, y): (i32, i32)| (x + dx, y + dy) }.apply(self)
+    }
+
+    /// The dungeon turned a quarter turn counterclockwise about the origin.
+    pub fn rotate90(&self) -> Dungeon {
+        Transform { factor: 1, anchor: |(x, y): (i32, i32)| (-y, x) }.apply(self)
+    }
+
+    /// The dungeon with its x coordinates flipped.
+    pub fn mirror_x(&self) -> Dungeon {
+        Transform { factor: 1, anchor: |(x, y): (i32, i32)| (-x, y) }.apply(self)
+    }
+
+    /// The dungeon with every tile enlarged into a `factor` x `factor` block; factors
+    /// below 2 leave it unchanged.
+    pub fn scale(&self, factor: u32) -> Dungeon {
+        let factor = factor.max(1) as i32;
+        Transform { factor, anchor: |(x, y): (i32, i32)| (x * factor, y * factor) }.apply(self)
+    }
+}
+
+// Maps each tile to the `factor` x `factor` block starting at its anchor
+struct Transform<F> {
+    factor: i32,
+    anchor: F,
+}
+
+impl<F: Fn((i32, i32)) -> (i32, i32)> Transform<F> {
+    fn apply(&self, dungeon: &Dungeon) -> Dungeon {
+        let mut transformed = Dungeon {
+            rooms: self.rooms(&dungeon.rooms),
+            tunnels: dungeon.tunnels.iter().map(|tunnel| self.tunnel(tunnel)).collect(),
+            corridor_width: dungeon.corridor_width * self.factor as u32,
+            excavated: self.tiles(&dungeon.excavated),
+            grid: self.grid(&dungeon.grid, Tile::Wall, unmark),
+            elevation: self.grid(&dungeon.elevation, 0, |&height| height),
+            monsters: self.monsters(&dungeon.monsters),
+            decorations: self.decorations(&dungeon.decorations),
+            stairs_down: dungeon.stairs_down.map(&self.anchor),
+            ..dungeon.clone()
+        };
+        for floor in &mut transformed.floors {
+            floor.rooms = self.rooms(&floor.rooms);
+            floor.tunnels = floor.tunnels.iter().map(|tunnel| self.tunnel(tunnel)).collect();
+            floor.corridor_width *= self.factor as u32;
+            floor.excavated = self.tiles(&floor.excavated);
+            floor.grid = self.grid(&floor.grid, Tile::Wall, unmark);
+            floor.elevation = self.grid(&floor.elevation, 0, |&height| height);
+            floor.monsters = self.monsters(&floor.monsters);
+            floor.decorations = self.decorations(&floor.decorations);
+            floor.stairs_up = (self.anchor)(floor.stairs_up);
+            floor.stairs_down = floor.stairs_down.map(&self.anchor);
+            let centers = floor.rooms.iter().map(|room| room.center);
+            floor.bounds = Bounds::covering(floor.excavated.iter().copied().chain(centers)).unwrap_or(floor.bounds);
+        }
+        transformed.recompute_bounds();
+        transformed
+    }
+
+    // Tiles of the block of `pos`, its anchor first
+    fn block(&self, pos: (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+        let ((x, y), factor) = ((self.anchor)(pos), self.factor);
+        (0..factor).flat_map(move |dy| (0..factor).map(move |dx| (x + dx, y + dy)))
+    }
+
+    fn tiles(&self, tiles: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        tiles.iter().flat_map(|&pos| self.block(pos)).collect()
+    }
+
+    fn rooms(&self, rooms: &[Room]) -> Vec<Room> {
+        rooms.iter().map(|room| Room { center: (self.anchor)(room.center), tiles: self.tiles(&room.tiles), ..room.clone() }).collect()
+    }
+
+    fn monsters(&self, monsters: &[MonsterSpawn]) -> Vec<MonsterSpawn> {
+        monsters.iter().map(|monster| MonsterSpawn { position: (self.anchor)(monster.position), ..monster.clone() }).collect()
+    }
+
+    fn decorations(&self, decorations: &[Decoration]) -> Vec<Decoration> {
+        decorations.iter().map(|decoration| Decoration { position: (self.anchor)(decoration.position), ..*decoration }).collect()
+    }
+
+    // Anchors of the path, joined by unit steps (horizontal first) where scaling pulled
+    // them apart
+    fn tunnel(&self, tunnel: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        if self.factor == 1 {
+            return tunnel.iter().map(|&pos| (self.anchor)(pos)).collect();
+        }
+        let mut path: Vec<(i32, i32)> = Vec::new();
+        for (x, y) in tunnel.iter().map(|&pos| (self.anchor)(pos)) {
+            match path.last().copied() {
+                Some((mut px, mut py)) => {
+                    while px != x {
+                        px += (x - px).signum();
+                        path.push((px, py));
+                    }
+                    while py != y {
+                        py += (y - py).signum();
+                        path.push((px, py));
+                    }
+                }
+                None => path.push((x, y)),
+            }
+        }
+        path
+    }
+
+    // Grid with every cell copied to the anchor of its block and `rest` of it to the
+    // other tiles of the block; empty grids stay empty
+    fn grid<T: Clone>(&self, grid: &Grid<T>, fill: T, rest: impl Fn(&T) -> T) -> Grid<T> {
+        if grid.width() == 0 || grid.height() == 0 {
+            return grid.clone();
+        }
+        let (x_range, y_range) = (grid.x_range(), grid.y_range());
+        let corners: Vec<(i32, i32)> = self.block((x_range.0, y_range.0)).chain(self.block((x_range.1, y_range.1))).collect();
+        let span = |coord: fn(&(i32, i32)) -> i32| {
+            (corners.iter().map(coord).min().unwrap_or(0), corners.iter().map(coord).max().unwrap_or(0))
+        };
+        let mut transformed = Grid::new(span(|pos| pos.0), span(|pos| pos.1), fill);
+        for (pos, value) in grid.iter() {
+            for (i, tile) in self.block(pos).enumerate() {
+                transformed.set(tile, if i == 0 { value.clone() } else { rest(value) });
+            }
+        }
+        transformed
+    }
+}
+
+// Tile filling the rest of the block of `tile`: markers of a single position become floor
+fn unmark(tile: &Tile) -> Tile {
+    match tile {
+        Tile::RoomCenter | Tile::StairsUp | Tile::StairsDown => Tile::Floor,
+        &other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn rigid_transforms_undo_themselves() {
+        let dungeon = dungeon();
+        assert_eq!(dungeon.translate(7, -3).translate(-7, 3), dungeon);
+        assert_eq!(dungeon.mirror_x().mirror_x(), dungeon);
+        assert_eq!(dungeon.rotate90().rotate90().rotate90().rotate90(), dungeon);
+
+        let turned = dungeon.rotate90();
+        let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+        assert_eq!(turned.bounds, Bounds { x_range: (-y_range.1, -y_range.0), y_range: x_range });
+        assert_eq!(turned.grid.width(), dungeon.grid.height());
+        let (x, y) = dungeon.rooms[0].center;
+        assert_eq!(turned.rooms[0].center, (-y, x));
+        assert_eq!(turned.grid.get((-y, x)), Some(&Tile::RoomCenter));
+        assert_eq!(dungeon.mirror_x().monsters[0].position, (-dungeon.monsters[0].position.0, dungeon.monsters[0].position.1));
+        assert!(turned.is_fully_connected() && turned.is_solvable());
+    }
+
+    #[test]
+    fn scaled_dungeons_keep_their_layout() {
+        let dungeon = dungeon();
+        let scaled = dungeon.scale(3);
+        assert_eq!((scaled.grid.width(), scaled.grid.height()), (3 * dungeon.grid.width(), 3 * dungeon.grid.height()));
+        assert_eq!(scaled.excavated.len(), 9 * dungeon.excavated.len());
+        assert_eq!(scaled.corridor_width, 3 * dungeon.corridor_width);
+        for (room, original) in scaled.rooms.iter().zip(&dungeon.rooms) {
+            assert_eq!(room.center, (3 * original.center.0, 3 * original.center.1));
+            assert_eq!(room.tiles.len(), 9 * original.tiles.len());
+        }
+        // One room center marker per room, and unbroken tunnels
+        assert_eq!(scaled.grid.iter().filter(|&(_, &tile)| tile == Tile::RoomCenter).count(), dungeon.rooms.len());
+        for tunnel in &scaled.tunnels {
+            assert!(tunnel.windows(2).all(|step| (step[0].0 - step[1].0).abs() + (step[0].1 - step[1].1).abs() == 1));
+        }
+        let (x_range, y_range) = (scaled.bounds.x_range, scaled.bounds.y_range);
+        assert!(scaled.excavated.iter().all(|&(x, y)| x > x_range.0 && x < x_range.1 && y > y_range.0 && y < y_range.1));
+        assert!(scaled.monsters.iter().all(|monster| scaled.grid.is_walkable(monster.position)));
+        assert!(scaled.is_fully_connected() && scaled.is_solvable());
+        assert_eq!(dungeon.scale(0), dungeon);
+    }
+}
@@ -0,0 +1,157 @@
+//! # Traps
+//!
+//! Derives trap locations from the NFT ID.
+//!
+//! - **Triples**: The characters after "nft1" are read in consecutive triples; each
+//!   triple yields one trap, so a 62-character ID arms up to 19. The dungeon's `Balance`
+//!   can arm only a share of the triples, spread evenly over the ID.
+//! - **Placement**: Traps lie on tunnel tiles and room thresholds (door tiles). The triple's
+//!   base62 value picks the tile; triples landing on an already trapped tile are skipped.
+//! - **Flavor**: Each dungeon type has its own tunnel trap and threshold trap (e.g., spike
+//!   pits and poison darts by default, brimstone pits and hellfire vents in Hell).
+//! - **Damage**: The dungeon level plus 0-3, picked from the triple's last character.
+
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use super::dungeon::Dungeon;
+use super::excavator::{base_dungeon_type, char_to_num};
+use super::grid::Tile;
+
+/// A trap placed in the dungeon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trap {
+    /// Trap name from the dungeon type's flavors.
+    pub kind: String,
+    /// Tile the trap is hidden on.
+    pub position: (i32, i32),
+    /// Whether the trap guards a room threshold rather than a tunnel.
+    pub threshold: bool,
+    /// Damage dealt when triggered.
+    pub damage: u64,
+}
+
+/// Tunnel and threshold traps of a dungeon type
+/// Variant types share the traps of their base type.
+pub fn trap_flavors(dungeon_type: &str) -> (&'static str, &'static str) {
+    match base_dungeon_type(dungeon_type) {
+        "Ancient Ruins" => ("Collapsing Floor", "Rolling Boulder"),
+        "Barrens" => ("Sinkhole", "Bone Snare"),
+        "Cave" => ("Falling Stalactite", "Spike Pit"),
+        "Desert" => ("Quicksand Pit", "Scarab Swarm"),
+        "Enchanted Forest" => ("Fairy Ring", "Thorn Snare"),
+        "Forest" => ("Bear Trap", "Swinging Log"),
+        "Grassland" => ("Hidden Pit", "Tripwire Arrows"),
+        "Hell" => ("Brimstone Pit", "Hellfire Vent"),
+        "Ice Cavern" => ("Thin Ice", "Icicle Drop"),
+        "Jungle" => ("Punji Pit", "Poison Darts"),
+        "Kingdom Ruins" => ("Trapdoor", "Crossbow Trap"),
+        "Lava Pits" => ("Magma Vent", "Fire Jet"),
+        "Mountain" => ("Rockslide", "Pendulum Blade"),
+        "Necropolis" => ("Grasping Hands", "Cursed Glyph"),
+        "Ocean Depths" => ("Riptide", "Urchin Bed"),
+        "Poison Swamp" => ("Toxic Bog", "Poison Darts"),
+        "Quagmire" => ("Sucking Mud", "Leech Nest"),
+        "Rainforest" => ("Vine Snare", "Poison Darts"),
+        "Swamp" => ("Sinking Mire", "Gas Bubble"),
+        "Temple" => ("Pressure Plate", "Blade Wall"),
+        "Underground Tunnels" => ("Cave-in", "Spike Pit"),
+        "Volcanic Crater" => ("Steam Vent", "Ash Geyser"),
+        "Water" => ("Whirlpool", "Net Trap"),
+        "Xeno Hive" => ("Acid Pool", "Spore Pod"),
+        "Yellow Wasteland" => ("Landmine", "Rusted Snare"),
+        "Zephyr Highlands" => ("Gust Chasm", "Lightning Rod"),
+        _ => ("Spike Pit", "Poison Darts"),
+    }
+}
+
+impl Dungeon {
+    /// Traps on the tunnels and room thresholds of this dungeon (see `traps`).
+    pub fn traps(&self) -> Vec<Trap> {
+        place_traps(self)
+    }
+}
+
+/// Place one trap per character triple of the NFT ID
+pub fn place_traps(dungeon: &Dungeon) -> Vec<Trap> {
+    let candidates: Vec<((i32, i32), Tile)> = dungeon
+        .grid
+        .iter()
+        .filter(|&(_, &tile)| matches!(tile, Tile::Tunnel | Tile::Door))
+        .map(|(pos, &tile)| (pos, tile))
+        .collect();
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let (tunnel_trap, threshold_trap) = trap_flavors(&dungeon.dungeon_type);
+    let chars: Vec<char> = dungeon.seed_id().chars().skip(4).collect();
+    let mut trapped = HashSet::new();
+    let frequency = dungeon.balance.trap_frequency(dungeon.level);
+    let mut traps = Vec::new();
+    for (index, triple) in chars.chunks_exact(3).enumerate() {
+        // Arm a triple whenever the running share of armed triples reaches another whole trap
+        if ((index + 1) as f64 * frequency).floor() == (index as f64 * frequency).floor() {
+            continue;
+        }
+        let values: Vec<usize> = triple.iter().map(|&c| char_to_num(c).unsigned_abs() as usize).collect();
+        let value = values[0] * 62 * 62 + values[1] * 62 + values[2];
+        let (position, tile) = candidates[value % candidates.len()];
+        if !trapped.insert(position) {
+            continue;
+        }
+        let threshold = tile == Tile::Door;
+        traps.push(Trap {
+            kind: if threshold { threshold_trap } else { tunnel_trap }.to_string(),
+            position,
+            threshold,
+            damage: dungeon.level + (values[2] % 4) as u64,
+        });
+    }
+
+    traps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn traps_hide_on_distinct_tunnels_and_thresholds() {
+        let dungeon = dungeon();
+        let traps = dungeon.traps();
+        assert!(!traps.is_empty());
+        let mut positions = HashSet::new();
+        for trap in &traps {
+            let tile = dungeon.grid.get(trap.position).copied();
+            assert_eq!(tile, Some(if trap.threshold { Tile::Door } else { Tile::Tunnel }));
+            assert!(positions.insert(trap.position));
+            assert!((dungeon.level..=dungeon.level + 3).contains(&trap.damage));
+        }
+        assert_eq!(traps, place_traps(&dungeon));
+    }
+
+    #[test]
+    fn flavors_follow_the_dungeon_type() {
+        let traps = dungeon().traps();
+        assert!(traps.iter().all(|trap| trap.kind == if trap.threshold { "Blade Wall" } else { "Pressure Plate" }));
+        assert_eq!(trap_flavors("Hell"), ("Brimstone Pit", "Hellfire Vent"));
+        assert_eq!(trap_flavors("Twilight Temple"), trap_flavors("Temple"));
+        assert_eq!(trap_flavors("Unknown"), ("Spike Pit", "Poison Darts"));
+    }
+
+    #[test]
+    fn dungeons_without_tunnels_have_no_traps() {
+        let mut dungeon = dungeon();
+        let corridors: Vec<(i32, i32)> = dungeon
+            .grid
+            .iter()
+            .filter(|&(_, &tile)| matches!(tile, Tile::Tunnel | Tile::Door))
+            .map(|(pos, _)| pos)
+            .collect();
+        for pos in corridors {
+            dungeon.grid.set(pos, Tile::Floor);
+        }
+        assert!(dungeon.traps().is_empty());
+    }
+}
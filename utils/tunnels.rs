@@ -0,0 +1,203 @@
+//! # Tunnel Styles
+//!
+//! Algorithms digging the tunnel between two room centers. Every style yields a
+//! 4-connected path from the first center to the second, so corridors stay walkable.
+//!
+//! - **Manhattan**: Along x, then along y; the L-shaped paths of the original generator.
+//! - **Bresenham**: Straight diagonal lines, each step taken along the axis that keeps
+//!   the path closest to the line between the centers.
+//! - **Drunkard's walk**: A random walk that staggers towards its target, for caverns.
+//!   A walk that strays too long finishes with a Manhattan path.
+//! - **Winding**: A quadratic Bezier curve bowed to one side of the line by a random
+//!   control point, rasterized like Bresenham lines.
+//!
+//! Random styles draw from the "tunnels" RNG stream of the `nft_id`, so the same NFT
+//! always digs the same tunnels.
+//!
+//! `GenerationConfig::tunnel_style` picks the style. The default (`TunnelStyle::Manhattan`)
+//! keeps existing dungeons unchanged; `TunnelStyle::ByType` picks one by dungeon type
+//! (see `style_for_type`).
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use super::excavator::{base_dungeon_type, create_tunnel};
+use super::smoothing::is_natural_type;
+
+/// Steps a drunkard's walk may take per tile of Manhattan distance before it gives up
+const WALK_PATIENCE: i32 = 4;
+
+/// How tunnels between room centers are dug.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum TunnelStyle {
+    /// L-shaped paths along x, then y.
+    #[default]
+    Manhattan,
+    /// Straight diagonal lines.
+    Bresenham,
+    /// Random walks biased towards the target.
+    DrunkardsWalk,
+    /// Curves bowed to one side of the line.
+    Winding,
+    /// Picked by the dungeon type (see `style_for_type`).
+    ByType,
+}
+
+impl TunnelStyle {
+    /// The style used for a dungeon of the given type
+    pub fn resolve(self, dungeon_type: &str) -> TunnelStyle {
+        match self {
+            TunnelStyle::ByType => style_for_type(dungeon_type),
+            style => style,
+        }
+    }
+
+    /// Digs a tunnel from `start` to `end`, both included
+    /// `TunnelStyle::ByType` digs Manhattan paths; resolve it for a dungeon type first.
+    pub fn dig(self, start: (i32, i32), end: (i32, i32), rng: &mut impl Rng) -> Vec<(i32, i32)> {
+        match self {
+            TunnelStyle::Manhattan | TunnelStyle::ByType => create_tunnel(start, end),
+            TunnelStyle::Bresenham => line(start, end),
+            TunnelStyle::DrunkardsWalk => drunkards_walk(start, end, rng),
+            TunnelStyle::Winding => winding(start, end, rng),
+        }
+    }
+}
+
+/// Tunnel style fitting a dungeon type: Manhattan halls in built ruins and temples,
+/// straight shafts in mines and hives, staggering walks in natural caverns, and winding
+/// paths across open ground
+pub fn style_for_type(dungeon_type: &str) -> TunnelStyle {
+    match base_dungeon_type(dungeon_type) {
+        "Ancient Ruins" | "Kingdom Ruins" | "Necropolis" | "Temple" => TunnelStyle::Manhattan,
+        "Mountain" | "Xeno Hive" => TunnelStyle::Bresenham,
+        natural if is_natural_type(natural) => TunnelStyle::DrunkardsWalk,
+        "Unknown" => TunnelStyle::Manhattan,
+        _ => TunnelStyle::Winding,
+    }
+}
+
+// 4-connected line from `start` to `end`, stepping along whichever axis stays closer to it
+pub(crate) fn line(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
+    let (dx, dy) = ((end.0 - start.0) as i64, (end.1 - start.1) as i64);
+    // Twice the area between the line and the vector from `start` to `pos`
+    let deviation = |(x, y): (i32, i32)| ((x - start.0) as i64 * dy - (y - start.1) as i64 * dx).abs();
+    let mut pos = start;
+    let mut tunnel = vec![pos];
+    while pos != end {
+        let along_x = (pos.0 + (end.0 - pos.0).signum(), pos.1);
+        let along_y = (pos.0, pos.1 + (end.1 - pos.1).signum());
+        pos = if pos.1 == end.1 || (pos.0 != end.0 && deviation(along_x) <= deviation(along_y)) { along_x } else { along_y };
+        tunnel.push(pos);
+    }
+    tunnel
+}
+
+// Random walk from `start` to `end`: towards the target two times in three, else any way
+fn drunkards_walk(start: (i32, i32), end: (i32, i32), rng: &mut impl Rng) -> Vec<(i32, i32)> {
+    let distance = (end.0 - start.0).abs() + (end.1 - start.1).abs();
+    let mut pos = start;
+    let mut tunnel = vec![pos];
+    for _ in 0..distance * WALK_PATIENCE {
+        if pos == end {
+            return tunnel;
+        }
+        let (dx, dy) = if rng.gen_bool(2.0 / 3.0) {
+            // Close whichever gap is left, the wider one more often
+            let (gap_x, gap_y) = (end.0 - pos.0, end.1 - pos.1);
+            if rng.gen_range(0..gap_x.abs() + gap_y.abs()) < gap_x.abs() {
+                (gap_x.signum(), 0)
+            } else {
+                (0, gap_y.signum())
+            }
+        } else {
+            [(1, 0), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0..4)]
+        };
+        pos = (pos.0 + dx, pos.1 + dy);
+        tunnel.push(pos);
+    }
+    // Stagger home along a Manhattan path
+    tunnel.extend(create_tunnel(pos, end).into_iter().skip(1));
+    tunnel
+}
+
+// Quadratic Bezier curve from `start` to `end`, bowed by a control point beside the midpoint
+fn winding(start: (i32, i32), end: (i32, i32), rng: &mut impl Rng) -> Vec<(i32, i32)> {
+    let (dx, dy) = ((end.0 - start.0) as f64, (end.1 - start.1) as f64);
+    // Offset the control point perpendicular to the line, by up to half its length
+    let bow = rng.gen_range(-0.5..=0.5);
+    let control = ((start.0 + end.0) as f64 / 2.0 - dy * bow, (start.1 + end.1) as f64 / 2.0 + dx * bow);
+    let samples = ((dx.abs() + dy.abs()) / 3.0).ceil().max(1.0) as u32;
+    let point = |t: f64| {
+        let at = |a: i32, c: f64, b: i32| (1.0 - t).powi(2) * a as f64 + 2.0 * (1.0 - t) * t * c + t * t * b as f64;
+        (at(start.0, control.0, end.0).round() as i32, at(start.1, control.1, end.1).round() as i32)
+    };
+
+    let mut tunnel = vec![start];
+    let mut from = start;
+    for sample in 1..=samples {
+        let to = if sample == samples { end } else { point(sample as f64 / samples as f64) };
+        tunnel.extend(line(from, to).into_iter().skip(1));
+        from = to;
+    }
+    tunnel
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::super::config::GenerationConfig;
+    use super::super::excavator::parse_nft_id_with_config;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    // Whether every step of `tunnel` moves to a 4-neighbour
+    fn is_connected(tunnel: &[(i32, i32)]) -> bool {
+        tunnel.windows(2).all(|step| (step[0].0 - step[1].0).abs() + (step[0].1 - step[1].1).abs() == 1)
+    }
+
+    #[test]
+    fn lines_step_diagonally() {
+        assert_eq!(line((0, 0), (3, 3)), vec![(0, 0), (1, 0), (1, 1), (2, 1), (2, 2), (3, 2), (3, 3)]);
+        assert_eq!(line((0, 0), (-4, 1)), vec![(0, 0), (-1, 0), (-2, 0), (-2, 1), (-3, 1), (-4, 1)]);
+        assert_eq!(line((2, 2), (2, 2)), vec![(2, 2)]);
+    }
+
+    #[test]
+    fn every_style_connects_both_centers() {
+        let styles = [TunnelStyle::Manhattan, TunnelStyle::Bresenham, TunnelStyle::DrunkardsWalk, TunnelStyle::Winding];
+        for (start, end) in [((0, 0), (20, -7)), ((5, 5), (5, 5)), ((-3, 12), (-30, 40))] {
+            for style in styles {
+                for seed in 0..8 {
+                    let tunnel = style.dig(start, end, &mut StdRng::seed_from_u64(seed));
+                    assert_eq!((tunnel.first(), tunnel.last()), (Some(&start), Some(&end)), "{:?}", style);
+                    assert!(is_connected(&tunnel), "{:?}", style);
+                    assert_eq!(tunnel, style.dig(start, end, &mut StdRng::seed_from_u64(seed)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dungeon_types_pick_their_style() {
+        assert_eq!(TunnelStyle::ByType.resolve("Twilight Temple"), TunnelStyle::Manhattan);
+        assert_eq!(TunnelStyle::ByType.resolve("Xeno Hive"), TunnelStyle::Bresenham);
+        assert_eq!(TunnelStyle::ByType.resolve("Cave"), TunnelStyle::DrunkardsWalk);
+        assert_eq!(TunnelStyle::ByType.resolve("Forest"), TunnelStyle::Winding);
+        assert_eq!(TunnelStyle::Winding.resolve("Temple"), TunnelStyle::Winding);
+    }
+
+    #[test]
+    fn configured_styles_keep_dungeons_connected() {
+        let dungeon = dungeon();
+        for style in [TunnelStyle::Bresenham, TunnelStyle::DrunkardsWalk, TunnelStyle::Winding, TunnelStyle::ByType] {
+            let config = GenerationConfig { tunnel_style: style, ..GenerationConfig::default() };
+            let dug = parse_nft_id_with_config(NFT_ID, &config).unwrap();
+            assert!(dug.is_fully_connected(), "{:?}", style);
+            assert_eq!(dug, parse_nft_id_with_config(NFT_ID, &config).unwrap());
+            assert_eq!(dug.rooms.len(), dungeon.rooms.len());
+        }
+        let winding = GenerationConfig { tunnel_style: TunnelStyle::Winding, ..GenerationConfig::default() };
+        assert_ne!(parse_nft_id_with_config(NFT_ID, &winding).unwrap().tunnels, dungeon.tunnels);
+    }
+}
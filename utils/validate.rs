@@ -0,0 +1,132 @@
+//! # Dungeon Validation
+//!
+//! `Dungeon::validate` screens a decoded dungeon for structural problems, so collection
+//! creators can reject NFT IDs before minting (see the `lint` CLI command).
+//!
+//! Each problem is reported as a `LintWarning` naming the rooms involved; room numbers
+//! in the messages count from 1, as in the `parse` output. An empty list means the
+//! dungeon is sound.
+//!
+//! Reachability comes from the connected regions of `Dungeon::components`: rooms outside
+//! the region of the first room are disconnected, and so are areas holding no room at all.
+//! Areas of up to `CLUSTER_SIZE` tiles are the rubble of `decorate`, which is never
+//! reported.
+
+use std::collections::HashMap;
+use std::fmt;
+use super::decorate::CLUSTER_SIZE;
+use super::dungeon::Dungeon;
+
+/// A structural problem found by `Dungeon::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintWarning {
+    /// The room's center cannot be reached from the first room over walkable tiles.
+    DisconnectedRoom { room: usize, center: (i32, i32) },
+    /// The room's center lies outside the dungeon bounds, or some of its tiles outside
+    /// the tile grid.
+    RoomOutOfBounds { room: usize, center: (i32, i32) },
+    /// The size formula left the room with no size or no tiles.
+    ZeroSizeRoom { room: usize },
+    /// Two rooms share a center, so one is drawn over the other.
+    DuplicateCenter { first: usize, second: usize, center: (i32, i32) },
+    /// Excavated tiles holding no room center, more than rubble, are cut off from the first
+    /// room; `tile` is the first tile of the area.
+    DisconnectedArea { size: usize, tile: (i32, i32) },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintWarning::DisconnectedRoom { room, center } => {
+                write!(f, "Room {} at {:?} is not reachable from the first room.", room + 1, center)
+            }
+            LintWarning::RoomOutOfBounds { room, center } => {
+                write!(f, "Room {} at {:?} lies outside the dungeon bounds.", room + 1, center)
+            }
+            LintWarning::ZeroSizeRoom { room } => write!(f, "Room {} has zero size.", room + 1),
+            LintWarning::DuplicateCenter { first, second, center } => {
+                write!(f, "Rooms {} and {} share the center {:?}.", first + 1, second + 1, center)
+            }
+            LintWarning::DisconnectedArea { size, tile } => {
+                write!(f, "Area of {} tiles at {:?} is not reachable from the first room.", size, tile)
+            }
+        }
+    }
+}
+
+impl Dungeon {
+    /// Lists the structural problems of the dungeon, room by room in decode order, then the
+    /// disconnected areas in label order.
+    pub fn validate(&self) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+        let regions = self.components();
+        let reachable = regions.iter().find(|region| region.rooms.first() == Some(&0)).map_or(&[][..], |region| &region.rooms[..]);
+        let (x_range, y_range) = (self.bounds.x_range, self.bounds.y_range);
+        let mut centers: HashMap<(i32, i32), usize> = HashMap::new();
+
+        for (index, room) in self.rooms.iter().enumerate() {
+            let center = room.center;
+            let in_bounds = (x_range.0..=x_range.1).contains(&center.0) && (y_range.0..=y_range.1).contains(&center.1);
+            if !in_bounds || room.tiles.iter().any(|&pos| !self.grid.contains(pos)) {
+                warnings.push(LintWarning::RoomOutOfBounds { room: index, center });
+            }
+            if room.size == 0 || room.tiles.is_empty() {
+                warnings.push(LintWarning::ZeroSizeRoom { room: index });
+            }
+            if !reachable.contains(&index) {
+                warnings.push(LintWarning::DisconnectedRoom { room: index, center });
+            }
+            if let Some(&first) = centers.get(&center) {
+                warnings.push(LintWarning::DuplicateCenter { first, second: index, center });
+            } else {
+                centers.insert(center, index);
+            }
+        }
+        for region in regions.iter().filter(|region| region.rooms.is_empty() && region.size() > CLUSTER_SIZE) {
+            warnings.push(LintWarning::DisconnectedArea { size: region.size(), tile: region.tiles[0] });
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::super::grid::Tile;
+    use super::*;
+
+    #[test]
+    fn generated_dungeons_are_sound() {
+        assert_eq!(dungeon().validate(), Vec::new());
+    }
+
+    #[test]
+    fn broken_dungeons_report_every_problem() {
+        let mut dungeon = dungeon();
+        let last = dungeon.rooms.len() - 1;
+        // Copy the first room onto the second, shrink it to nothing, and wall off the last
+        dungeon.rooms[1].center = dungeon.rooms[0].center;
+        dungeon.rooms[1].size = 0;
+        let exit = dungeon.rooms[last].center;
+        for &pos in &dungeon.rooms[last].tiles.clone() {
+            dungeon.grid.set(pos, Tile::Wall);
+        }
+        dungeon.rooms[2].center = (dungeon.bounds.x_range.1 + 5, 0);
+        // Dig a 3x3 cave into solid rock, out of reach of everything else
+        let square = |(x, y): (i32, i32), radius: i32| (-radius..=radius).flat_map(move |dx| (-radius..=radius).map(move |dy| (x + dx, y + dy)));
+        let rock = |pos: (i32, i32)| dungeon.grid.get(pos) == Some(&Tile::Wall);
+        let cave = dungeon.grid.iter().map(|(pos, _)| pos).find(|&pos| square(pos, 2).all(rock)).unwrap();
+        for pos in square(cave, 1) {
+            dungeon.grid.set(pos, Tile::Floor);
+        }
+
+        let warnings = dungeon.validate();
+        let center = dungeon.rooms[0].center;
+        assert!(warnings.contains(&LintWarning::DuplicateCenter { first: 0, second: 1, center }));
+        assert!(warnings.contains(&LintWarning::ZeroSizeRoom { room: 1 }));
+        assert!(warnings.contains(&LintWarning::DisconnectedRoom { room: last, center: exit }));
+        assert!(warnings.iter().any(|warning| matches!(warning, LintWarning::RoomOutOfBounds { room: 2, .. })));
+        assert!(warnings.contains(&LintWarning::DisconnectedArea { size: 9, tile: (cave.0 - 1, cave.1 - 1) }));
+        assert_eq!(LintWarning::ZeroSizeRoom { room: 1 }.to_string(), "Room 2 has zero size.");
+    }
+}
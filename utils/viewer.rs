@@ -0,0 +1,316 @@
+//! # Map Viewer
+//!
+//! A terminal map viewer for inspecting a whole dungeon, built on `ratatui`.
+//!
+//! - **Map**: The dungeon in the glyphs of `ascii`, centered on a cursor shown in reverse
+//!   video. Zoomed out, each character stands for a square block of tiles and shows its
+//!   most telling glyph: markers first, then doors, terrain and decorations, and
+//!   excavated ground.
+//! - **Sidebar**: The dungeon's type, biome, level, rooms, area, and rarity, followed by
+//!   the room under the cursor: its name, kind, size, shape, and monsters.
+//! - **Keys**: Arrows or `W`/`A`/`S`/`D` move the cursor one character, panning the map;
+//!   `+` and `-` zoom in and out through `ZOOM_LEVELS`; `Tab` and `Shift+Tab` jump to
+//!   the next and previous room; `Q` or `Esc` quits.
+//!
+//! `MapViewer` holds the view state and renders it as text, independent of the
+//! terminal; `run_viewer` wires it to a ratatui terminal UI.
+
+use std::collections::HashMap;
+use std::io;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use super::ascii::{render_rows, BONES_GLYPH, COBWEBS_GLYPH, CRYSTALS_GLYPH, DOOR_GLYPH, EMBERS_GLYPH, EMPTY_GLYPH, EXCAVATED_GLYPH, LAVA_GLYPH, MUSHROOMS_GLYPH, SAND_GLYPH, SHALLOW_WATER_GLYPH, WATER_GLYPH};
+use super::dungeon::Dungeon;
+use super::explore::Direction;
+use super::rarity::RarityWeights;
+
+/// Tiles per character side at each zoom level, from the closest
+pub const ZOOM_LEVELS: [i32; 4] = [1, 2, 4, 8];
+/// Width of the sidebar, in characters
+pub const SIDEBAR_WIDTH: u16 = 36;
+
+/// View state of the map viewer: the cursor, the zoom level, and the rendered glyphs.
+pub struct MapViewer<'a> {
+    dungeon: &'a Dungeon,
+    // Glyph rows of the whole dungeon, from the highest y
+    rows: Vec<Vec<char>>,
+    // First room covering each room tile
+    room_tiles: HashMap<(i32, i32), usize>,
+    cursor: (i32, i32),
+    zoom: usize,
+}
+
+impl<'a> MapViewer<'a> {
+    /// Starts at the center of the first room, fully zoomed in.
+    pub fn new(dungeon: &'a Dungeon) -> MapViewer<'a> {
+        let rows = render_rows(dungeon).iter().map(|row| row.chars().collect()).collect();
+        let mut room_tiles = HashMap::new();
+        for (index, room) in dungeon.rooms.iter().enumerate() {
+            for &pos in &room.tiles {
+                room_tiles.entry(pos).or_insert(index);
+            }
+        }
+        let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+        let cursor = dungeon.rooms.first().map_or(((x_range.0 + x_range.1) / 2, (y_range.0 + y_range.1) / 2), |room| room.center);
+        MapViewer { dungeon, rows, room_tiles, cursor, zoom: 0 }
+    }
+
+    /// Tile under the cursor.
+    pub fn cursor(&self) -> (i32, i32) {
+        self.cursor
+    }
+
+    /// Tiles per character side at the current zoom level.
+    pub fn zoom(&self) -> i32 {
+        ZOOM_LEVELS[self.zoom]
+    }
+
+    /// Moves the cursor one character, staying within the dungeon bounds.
+    pub fn move_cursor(&mut self, direction: Direction) {
+        let (dx, dy) = direction.delta();
+        let (x_range, y_range) = (self.dungeon.bounds.x_range, self.dungeon.bounds.y_range);
+        self.cursor = (
+            (self.cursor.0 + dx * self.zoom()).clamp(x_range.0, x_range.1),
+            (self.cursor.1 + dy * self.zoom()).clamp(y_range.0, y_range.1),
+        );
+    }
+
+    /// Shows fewer tiles per character, if not fully zoomed in.
+    pub fn zoom_in(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1);
+    }
+
+    /// Shows more tiles per character, if not fully zoomed out.
+    pub fn zoom_out(&mut self) {
+        self.zoom = (self.zoom + 1).min(ZOOM_LEVELS.len() - 1);
+    }
+
+    /// Moves the cursor to the center of the room after (or, `forward` unset, before) the
+    /// hovered one, wrapping around.
+    pub fn jump_to_room(&mut self, forward: bool) {
+        let count = self.dungeon.rooms.len();
+        if count == 0 {
+            return;
+        }
+        let next = match (self.hovered_room(), forward) {
+            (Some(index), true) => (index + 1) % count,
+            (Some(index), false) => (index + count - 1) % count,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+        self.cursor = self.dungeon.rooms[next].center;
+    }
+
+    /// Index of the room under the cursor, if any: the first room covering a tile of the
+    /// cursor's block.
+    pub fn hovered_room(&self) -> Option<usize> {
+        self.block(self.cursor).filter_map(|pos| self.room_tiles.get(&pos).copied()).min()
+    }
+
+    /// Renders a `width` x `height` window centered on the cursor, one string per row.
+    pub fn render(&self, width: u16, height: u16) -> Vec<String> {
+        let zoom = self.zoom();
+        let left = self.cursor.0 - (width / 2) as i32 * zoom;
+        let top = self.cursor.1 + (height / 2) as i32 * zoom;
+        (0..height as i32)
+            .map(|row| (0..width as i32).map(|col| self.block_glyph((left + col * zoom, top - row * zoom))).collect())
+            .collect()
+    }
+
+    /// Attributes of the dungeon, as label and value.
+    pub fn attributes(&self) -> Vec<(&'static str, String)> {
+        let dungeon = self.dungeon;
+        let rarity = dungeon.rarity(&RarityWeights::default());
+        vec![
+            ("Type", dungeon.dungeon_type.clone()),
+            ("Biome", dungeon.biome.to_string()),
+            ("Level", dungeon.level.to_string()),
+            ("Rooms", dungeon.rooms.len().to_string()),
+            ("Area", dungeon.area_size.to_string()),
+            ("Rarity", format!("{:.1} ({})", rarity.score, rarity.tier)),
+            ("Monsters", dungeon.monsters.len().to_string()),
+            ("Cursor", format!("({}, {})", self.cursor.0, self.cursor.1)),
+            ("Zoom", format!("1:{}", self.zoom())),
+        ]
+    }
+
+    /// Description of the room under the cursor, one line per fact.
+    pub fn room_details(&self) -> Vec<String> {
+        let Some(index) = self.hovered_room() else {
+            return vec![String::from("No room under the cursor")];
+        };
+        let room = &self.dungeon.rooms[index];
+        let monsters = self.dungeon.monsters.iter().filter(|monster| self.room_tiles.get(&monster.position) == Some(&index)).count();
+        vec![
+            format!("{}. {}", index + 1, room.name),
+            format!("Kind: {:?}", room.kind),
+            format!("Center: ({}, {})", room.center.0, room.center.1),
+            format!("Size {}, shape '{}', {} tiles", room.size, room.shape, room.tiles.len()),
+            format!("Monsters: {}", monsters),
+        ]
+    }
+
+    // Tiles shown by the character whose block starts at the top-left `corner`
+    fn block(&self, (x, y): (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+        let zoom = self.zoom();
+        (0..zoom).flat_map(move |dy| (0..zoom).map(move |dx| (x + dx, y - dy)))
+    }
+
+    // Most telling glyph of the block at `corner`, the first one on ties
+    fn block_glyph(&self, corner: (i32, i32)) -> char {
+        let (x_range, y_range) = (self.dungeon.bounds.x_range, self.dungeon.bounds.y_range);
+        let glyph = |(x, y): (i32, i32)| {
+            if x < x_range.0 || x > x_range.1 || y < y_range.0 || y > y_range.1 {
+                return ' ';
+            }
+            self.rows[(y_range.1 - y) as usize][(x - x_range.0) as usize]
+        };
+        self.block(corner).map(glyph).fold(' ', |best, glyph| if rank(glyph) > rank(best) { glyph } else { best })
+    }
+}
+
+// How much a glyph tells about its block: markers first, then doors, terrain and
+// decorations, and ground
+fn rank(glyph: char) -> u8 {
+    match glyph {
+        ' ' => 0,
+        EMPTY_GLYPH => 1,
+        EXCAVATED_GLYPH => 2,
+        WATER_GLYPH | LAVA_GLYPH | SAND_GLYPH | SHALLOW_WATER_GLYPH => 3,
+        BONES_GLYPH | MUSHROOMS_GLYPH | CRYSTALS_GLYPH | COBWEBS_GLYPH | EMBERS_GLYPH => 3,
+        DOOR_GLYPH => 4,
+        _ => 5,
+    }
+}
+
+/// Run the map viewer in the current terminal until the user quits
+pub fn run_viewer(dungeon: &Dungeon) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = view_loop(&mut terminal, &mut MapViewer::new(dungeon));
+    // Always restore the terminal, even if the loop failed
+    ratatui::restore();
+    result
+}
+
+fn view_loop(terminal: &mut DefaultTerminal, viewer: &mut MapViewer) -> io::Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, viewer))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('W') => viewer.move_cursor(Direction::Up),
+            KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('A') => viewer.move_cursor(Direction::Left),
+            KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('S') => viewer.move_cursor(Direction::Down),
+            KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('D') => viewer.move_cursor(Direction::Right),
+            KeyCode::Char('+') | KeyCode::Char('=') => viewer.zoom_in(),
+            KeyCode::Char('-') => viewer.zoom_out(),
+            KeyCode::Tab => viewer.jump_to_room(true),
+            KeyCode::BackTab => viewer.jump_to_room(false),
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Ok(()),
+            _ => {}
+        }
+    }
+}
+
+// Draw the map with the cursor highlighted, and the sidebar beside it
+fn draw(frame: &mut Frame, viewer: &MapViewer) {
+    let [map_area, sidebar_area] = Layout::horizontal([Constraint::Min(1), Constraint::Length(SIDEBAR_WIDTH)]).areas(frame.area());
+
+    let map_block = Block::bordered().title(format!(" {} ", viewer.dungeon.nft_id));
+    let inner = map_block.inner(map_area);
+    let cursor_row = (inner.height / 2) as usize;
+    let cursor_col = (inner.width / 2) as usize;
+    let lines: Vec<Line> = viewer
+        .render(inner.width, inner.height)
+        .into_iter()
+        .enumerate()
+        .map(|(row, text)| {
+            if row != cursor_row {
+                return Line::raw(text);
+            }
+            let chars: Vec<char> = text.chars().collect();
+            let split = cursor_col.min(chars.len());
+            let (before, rest) = chars.split_at(split);
+            let (cursor, after) = rest.split_at(rest.len().min(1));
+            Line::from(vec![
+                Span::raw(before.iter().collect::<String>()),
+                Span::styled(cursor.iter().collect::<String>(), Style::new().add_modifier(Modifier::REVERSED)),
+                Span::raw(after.iter().collect::<String>()),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(map_block), map_area);
+
+    let mut sidebar: Vec<Line> = viewer.attributes().into_iter().map(|(label, value)| Line::raw(format!("{:<9}{}", label, value))).collect();
+    sidebar.push(Line::raw(""));
+    sidebar.push(Line::styled("Room", Style::new().add_modifier(Modifier::BOLD)));
+    sidebar.extend(viewer.room_details().into_iter().map(Line::raw));
+    sidebar.push(Line::raw(""));
+    sidebar.push(Line::raw("Arrows/WASD move, +/- zoom, Tab next room, Q quit"));
+    frame.render_widget(Paragraph::new(sidebar).wrap(Wrap { trim: true }).block(Block::bordered().title(" Dungeon ")), sidebar_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ascii::room_glyph;
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn the_viewer_starts_on_the_first_room() {
+        let dungeon = dungeon();
+        let mut viewer = MapViewer::new(&dungeon);
+        assert_eq!(viewer.cursor(), dungeon.rooms[0].center);
+        assert_eq!(viewer.hovered_room(), Some(0));
+        assert!(viewer.room_details()[0].ends_with(&dungeon.rooms[0].name));
+
+        // The cursor character shows the room's center glyph
+        let rows = viewer.render(21, 11);
+        assert_eq!(rows.len(), 11);
+        assert_eq!(rows[5].chars().nth(10), Some(room_glyph(dungeon.rooms[0].kind)));
+
+        viewer.jump_to_room(true);
+        assert_eq!((viewer.cursor(), viewer.hovered_room()), (dungeon.rooms[1].center, Some(1)));
+        viewer.jump_to_room(false);
+        viewer.jump_to_room(false);
+        assert_eq!(viewer.hovered_room(), Some(dungeon.rooms.len() - 1));
+        assert!(viewer.attributes().contains(&("Level", dungeon.level.to_string())));
+    }
+
+    #[test]
+    fn zooming_out_pans_further_and_keeps_markers() {
+        let dungeon = dungeon();
+        let mut viewer = MapViewer::new(&dungeon);
+        viewer.zoom_in();
+        assert_eq!(viewer.zoom(), 1);
+        for _ in 0..ZOOM_LEVELS.len() {
+            viewer.zoom_out();
+        }
+        assert_eq!(viewer.zoom(), 8);
+
+        // Every room center still shows in the zoomed-out map
+        let (x_range, y_range) = (dungeon.bounds.x_range, dungeon.bounds.y_range);
+        let (width, height) = ((x_range.1 - x_range.0) / 8 + 3, (y_range.1 - y_range.0) / 8 + 3);
+        let start = viewer.cursor();
+        viewer.move_cursor(Direction::Right);
+        assert_eq!(viewer.cursor(), ((start.0 + 8).min(x_range.1), start.1));
+        viewer.cursor = ((x_range.0 + x_range.1) / 2, (y_range.0 + y_range.1) / 2);
+        let map: String = viewer.render(2 * width as u16, 2 * height as u16).concat();
+        assert!(map.chars().filter(|&glyph| "EXTBSR".contains(glyph)).count() >= dungeon.rooms.len().min(2));
+
+        // The cursor never leaves the bounds
+        for _ in 0..100 {
+            viewer.move_cursor(Direction::Down);
+        }
+        assert_eq!(viewer.cursor().1, y_range.0);
+    }
+}
@@ -0,0 +1,94 @@
+//! # WebAssembly Bindings
+//!
+//! A JavaScript-friendly API for rendering dungeons in the browser, exported with
+//! `wasm-bindgen` behind the `wasm` feature. Build it with
+//! `wasm-pack build --target web --features wasm`.
+//!
+//! Dungeons cross the boundary as JSON (the same document as `Dungeon::to_json`), and
+//! maps as SVG markup that can be inserted into the page or drawn onto a canvas with
+//! `drawImage`, or as raw RGBA pixels for an `ImageData`. Errors surface as JavaScript `Error`s carrying the `DungeonError` message.
+//!
+//! ```js
+//! import init, { parseNftId, renderSvg } from "./pkg/chia_dungeon.js";
+//!
+//! await init();
+//! const dungeon = JSON.parse(parseNftId(nftId));
+//! document.getElementById("map").innerHTML = renderSvg(nftId, 0);
+//! ```
+
+use wasm_bindgen::prelude::*;
+use super::ascii;
+use super::excavator;
+use super::generator;
+use super::dungeon::Dungeon;
+use super::plot::{render_rgba_image, render_svg_string};
+use super::theme::Theme;
+
+/// Generate a random synthetic NFT ID
+#[wasm_bindgen(js_name = generateNftId)]
+pub fn generate_nft_id() -> String {
+    generator::generate_nft_id()
+}
+
+/// Generate the synthetic NFT ID for `seed` (a BigInt in JavaScript)
+#[wasm_bindgen(js_name = generateNftIdFromSeed)]
+pub fn generate_nft_id_from_seed(seed: u64) -> String {
+    generator::generate_nft_id_from_seed(seed)
+}
+
+/// Decode the NFT ID into the dungeon's JSON representation
+#[wasm_bindgen(js_name = parseNftId)]
+pub fn parse_nft_id(nft_id: &str) -> Result<String, JsError> {
+    let dungeon = excavator::parse_nft_id(nft_id)?;
+    Ok(dungeon.to_json()?)
+}
+
+/// Render one floor of the dungeon (0 is the top floor) as an SVG document
+#[wasm_bindgen(js_name = renderSvg)]
+pub fn render_svg(nft_id: &str, floor: usize) -> Result<String, JsError> {
+    let floor = floor_view(nft_id, floor)?;
+    render_svg_string(&floor).map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Render one floor of the dungeon as 640x480 RGBA pixels, row by row
+#[wasm_bindgen(js_name = renderRgba)]
+pub fn render_rgba(nft_id: &str, floor: usize) -> Result<Vec<u8>, JsError> {
+    let floor = floor_view(nft_id, floor)?;
+    let image = render_rgba_image(&floor, &Theme::for_dungeon(&floor), None).map_err(|err| JsError::new(&err.to_string()))?;
+    Ok(image.into_raw())
+}
+
+/// Render the top floor of the dungeon as ASCII text
+#[wasm_bindgen(js_name = renderAscii)]
+pub fn render_ascii(nft_id: &str) -> Result<String, JsError> {
+    Ok(ascii::render_ascii(&excavator::parse_nft_id(nft_id)?))
+}
+
+// Decode the NFT ID and view its floor at `depth`
+fn floor_view(nft_id: &str, depth: usize) -> Result<Dungeon, JsError> {
+    let dungeon = excavator::parse_nft_id(nft_id)?;
+    dungeon
+        .floor_view(depth)
+        .ok_or_else(|| JsError::new(&format!("Dungeon has no floor {}", depth)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::dungeon::Dungeon;
+    use super::super::fixtures::{dungeon, NFT_ID};
+    use super::*;
+
+    #[test]
+    fn dungeons_cross_as_json() {
+        let json = parse_nft_id(NFT_ID).unwrap();
+        assert_eq!(Dungeon::from_json(&json).unwrap(), dungeon());
+        assert_eq!(generate_nft_id_from_seed(7), generator::generate_nft_id_from_seed(7));
+    }
+
+    #[test]
+    fn maps_render_as_svg_pixels_and_text() {
+        assert!(render_svg(NFT_ID, 0).unwrap().contains("<svg"));
+        assert_eq!(render_rgba(NFT_ID, 0).unwrap().len(), 640 * 480 * 4);
+        assert_eq!(render_ascii(NFT_ID).unwrap(), ascii::render_ascii(&dungeon()));
+    }
+}
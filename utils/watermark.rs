@@ -0,0 +1,250 @@
+//! # Watermarks
+//!
+//! Branded overlays drawn over rendered maps, so marketplaces can generate previews that
+//! carry the NFT they show. A `RenderOptions` picks the overlays, one per corner:
+//!
+//! - **Badges**: The level and dungeon type on dark boxes in the top-left corner.
+//! - **Logo**: A collection logo image scaled to fit `CORNER_FRACTION` of the image
+//!   height, in the top-right corner, blended by its alpha channel. It covers the
+//!   minimap of viewport maps (see `plot::Viewport`).
+//! - **NFT ID**: The full NFT ID on a dark box in the bottom-left corner.
+//! - **QR code**: A QR code of a link to the NFT in the bottom-right corner, its
+//!   marketplace page (see `nft_url`) unless another link is given.
+//!
+//! A `RenderOptions` may also ask for a danger heatmap, which is drawn with the map
+//! rather than over it (see `plot::render_options_map`).
+//!
+//! Watermarks are drawn on raster images after plotting: `apply_overlays` draws onto an
+//! image in memory, `watermark_file` onto a saved PNG or isometric map, and
+//! `render_branded_png` renders and brands a map in one call. Their sizes follow the
+//! image height, so a 2048 pixel high preview is branded like an enlarged 480 pixel one.
+
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, Rgb, RgbImage, RgbaImage};
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use qrcode::{Color as Module, QrCode};
+use super::dungeon::Dungeon;
+use super::plot::render_rgb_image;
+use super::theme::Theme;
+
+/// Link to the marketplace page of an NFT, followed by its ID
+pub const NFT_URL_BASE: &str = "https://mintgarden.io/nfts/";
+/// Fraction of the image height taken by the logo and the QR code
+pub const CORNER_FRACTION: f64 = 0.2;
+/// Image height at which overlays are drawn at their base size
+const BASE_HEIGHT: f64 = 480.0;
+/// Pixels between an overlay and the edge of the image, and padding around text, at `BASE_HEIGHT`
+const EDGE: f64 = 8.0;
+const PADDING: f64 = 4.0;
+/// Light modules around a QR code, so scanners find its edges
+const QUIET_ZONE: u32 = 2;
+/// Background of badges and the NFT ID
+const BOX_COLOR: RGBColor = RGBColor(20, 20, 20);
+
+/// Overlays drawn over a rendered map (see `watermark`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RenderOptions {
+    /// Whether to write the NFT ID in the bottom-left corner.
+    pub nft_id: bool,
+    /// Collection logo image drawn in the top-right corner.
+    pub logo: Option<PathBuf>,
+    /// Whether to show level and dungeon type badges in the top-left corner.
+    pub badges: bool,
+    /// Link encoded in a QR code in the bottom-right corner.
+    pub qr_url: Option<String>,
+    /// Whether to tint walkable tiles by their danger (see `danger`).
+    pub heatmap: bool,
+}
+
+impl RenderOptions {
+    /// The NFT ID, the badges, and a QR code linking to the marketplace page of `dungeon`
+    pub fn branded(dungeon: &Dungeon) -> RenderOptions {
+        RenderOptions { nft_id: true, logo: None, badges: true, qr_url: Some(nft_url(&dungeon.nft_id)), heatmap: false }
+    }
+
+    /// Whether no overlay is drawn.
+    pub fn is_empty(&self) -> bool {
+        *self == RenderOptions::default()
+    }
+
+    /// Whether any overlay is drawn over the plotted map, needing a raster image.
+    pub fn has_watermarks(&self) -> bool {
+        self.nft_id || self.logo.is_some() || self.badges || self.qr_url.is_some()
+    }
+}
+
+/// Link to the marketplace page of the NFT `nft_id`
+pub fn nft_url(nft_id: &str) -> String {
+    format!("{}{}", NFT_URL_BASE, nft_id)
+}
+
+/// Draw the watermarks of `options` for `dungeon` onto `image`
+pub fn apply_overlays(image: &mut RgbImage, dungeon: &Dungeon, options: &RenderOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let (width, height) = image.dimensions();
+    let scale = height as f64 / BASE_HEIGHT;
+    let (edge, padding) = ((EDGE * scale).round() as i32, (PADDING * scale).round() as i32);
+    let corner = (height as f64 * CORNER_FRACTION).round() as u32;
+    {
+        let root = BitMapBackend::with_buffer(image.as_mut(), (width, height)).into_drawing_area();
+        let font = ("sans-serif", 14.0 * scale).into_font().color(&WHITE);
+        if options.badges {
+            let mut x = edge;
+            for badge in [format!("Level {}", dungeon.level), dungeon.dungeon_type.clone()] {
+                x += draw_label(&root, &badge, (x, edge), &font, padding)?.0 + edge;
+            }
+        }
+        if options.nft_id {
+            let (_, text_height) = root.estimate_text_size(&dungeon.nft_id, &font)?;
+            let top = height as i32 - edge - text_height as i32 - 2 * padding;
+            draw_label(&root, &dungeon.nft_id, (edge, top), &font, padding)?;
+        }
+        root.present()?;
+    }
+    if let Some(url) = &options.qr_url {
+        draw_qr_code(image, url, corner, edge as u32)?;
+    }
+    if let Some(logo) = &options.logo {
+        draw_logo(image, &image::open(logo)?.into_rgba8(), corner, edge as u32);
+    }
+    Ok(())
+}
+
+/// Draw the watermarks of `options` for `dungeon` onto the raster map saved at `path`
+pub fn watermark_file(path: &Path, dungeon: &Dungeon, options: &RenderOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let mut image = image::open(path)?.into_rgb8();
+    apply_overlays(&mut image, dungeon, options)?;
+    image.save(path)?;
+    Ok(())
+}
+
+/// Render the dungeon map in the palette of `theme` with the overlays of `options`, as
+/// an encoded PNG in memory
+pub fn render_branded_png(dungeon: &Dungeon, theme: &Theme, options: &RenderOptions) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let danger = options.heatmap.then(|| dungeon.danger_map());
+    let mut image = render_rgb_image(dungeon, theme, danger.as_ref())?;
+    apply_overlays(&mut image, dungeon, options)?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+// Write `text` on a dark box whose top-left corner is at `pos`, returning the width and
+// height of the box
+fn draw_label<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    text: &str,
+    pos: (i32, i32),
+    font: &TextStyle,
+    padding: i32,
+) -> Result<(i32, i32), Box<dyn std::error::Error>>
+where
+    DB::ErrorType: 'static,
+{
+    let (text_width, text_height) = root.estimate_text_size(text, font)?;
+    let size = (text_width as i32 + 2 * padding, text_height as i32 + 2 * padding);
+    root.draw(&Rectangle::new([pos, (pos.0 + size.0, pos.1 + size.1)], BOX_COLOR.filled()))?;
+    root.draw(&Text::new(text.to_string(), (pos.0 + padding, pos.1 + padding), font.clone()))?;
+    Ok(size)
+}
+
+// Draw a QR code of `url` at most `side` pixels wide in the bottom-right corner of
+// `image`, `edge` pixels from its edges, with whole pixels per module
+fn draw_qr_code(image: &mut RgbImage, url: &str, side: u32, edge: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let code = QrCode::new(url.as_bytes())?;
+    let width = code.width() as u32;
+    let module = (side / (width + 2 * QUIET_ZONE)).max(1);
+    let full = module * (width + 2 * QUIET_ZONE);
+    let origin = (image.width().saturating_sub(edge + full), image.height().saturating_sub(edge + full));
+    let modules = code.to_colors();
+    for y in 0..full {
+        for x in 0..full {
+            let (mx, my) = ((x / module).checked_sub(QUIET_ZONE), (y / module).checked_sub(QUIET_ZONE));
+            let dark = match (mx, my) {
+                (Some(mx), Some(my)) if mx < width && my < width => modules[(my * width + mx) as usize] == Module::Dark,
+                _ => false,
+            };
+            if let Some(pixel) = image.get_pixel_mut_checked(origin.0 + x, origin.1 + y) {
+                *pixel = if dark { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) };
+            }
+        }
+    }
+    Ok(())
+}
+
+// Blend `logo`, scaled to fit a `side` pixel square, into the top-right corner of
+// `image`, `edge` pixels from its edges
+fn draw_logo(image: &mut RgbImage, logo: &RgbaImage, side: u32, edge: u32) {
+    let fit = (side as f64 / logo.width().max(logo.height()).max(1) as f64).min(1.0);
+    let size = ((logo.width() as f64 * fit).round().max(1.0) as u32, (logo.height() as f64 * fit).round().max(1.0) as u32);
+    let logo = imageops::resize(logo, size.0, size.1, FilterType::Triangle);
+    let left = image.width().saturating_sub(edge + size.0);
+    for (x, y, pixel) in logo.enumerate_pixels() {
+        if let Some(below) = image.get_pixel_mut_checked(left + x, edge + y) {
+            let alpha = pixel[3] as u32;
+            for channel in 0..3 {
+                below[channel] = ((pixel[channel] as u32 * alpha + below[channel] as u32 * (255 - alpha)) / 255) as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fixtures::dungeon;
+    use super::*;
+
+    #[test]
+    fn qr_codes_and_labels_mark_their_corners() {
+        let dungeon = dungeon();
+        let theme = Theme::for_dungeon(&dungeon);
+        let plain = render_rgb_image(&dungeon, &theme, None).unwrap();
+        let options = RenderOptions::branded(&dungeon);
+        assert!(!options.is_empty() && RenderOptions::default().is_empty());
+        assert!(options.has_watermarks() && !RenderOptions { heatmap: true, ..RenderOptions::default() }.has_watermarks());
+        let png = render_branded_png(&dungeon, &theme, &options).unwrap();
+        let branded = image::load_from_memory(&png).unwrap().into_rgb8();
+        assert_eq!(branded.dimensions(), plain.dimensions());
+
+        // Every module of the code, quiet zone included, is a square of whole pixels
+        let code = QrCode::new(nft_url(&dungeon.nft_id).as_bytes()).unwrap();
+        let width = code.width() as u32;
+        let module = (96 / (width + 2 * QUIET_ZONE)).max(1);
+        let full = module * (width + 2 * QUIET_ZONE);
+        let origin = (branded.width() - 8 - full, branded.height() - 8 - full);
+        for (i, &color) in code.to_colors().iter().enumerate() {
+            let (mx, my) = (i as u32 % width + QUIET_ZONE, i as u32 / width + QUIET_ZONE);
+            for (dx, dy) in [(0, 0), (module - 1, module - 1)] {
+                let pixel = branded.get_pixel(origin.0 + mx * module + dx, origin.1 + my * module + dy);
+                assert_eq!(*pixel, if color == Module::Dark { Rgb([0, 0, 0]) } else { Rgb([255, 255, 255]) });
+            }
+        }
+        // The badges and NFT ID are drawn on dark boxes
+        let boxed = |x: u32, y: u32| *branded.get_pixel(x, y) == Rgb([BOX_COLOR.0, BOX_COLOR.1, BOX_COLOR.2]);
+        assert!(boxed(10, 10));
+        assert!(boxed(10, branded.height() - 10));
+        assert_ne!(branded.get_pixel(10, 10), plain.get_pixel(10, 10));
+    }
+
+    #[test]
+    fn logos_blend_into_the_top_right_corner() {
+        let dungeon = dungeon();
+        let dir = std::env::temp_dir().join(format!("chia_dungeon_watermark_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let logo_path = dir.join("logo.png");
+        // Opaque red on the left half, transparent on the right
+        RgbaImage::from_fn(200, 100, |x, _| if x < 100 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 0]) }).save(&logo_path).unwrap();
+        let mut image = RgbImage::from_pixel(640, 480, Rgb([10, 200, 10]));
+        apply_overlays(&mut image, &dungeon, &RenderOptions { logo: Some(logo_path), ..RenderOptions::default() }).unwrap();
+
+        // The logo is scaled down to 96 pixels wide, 8 pixels from the edges
+        assert_eq!(*image.get_pixel(640 - 8 - 90, 20), Rgb([255, 0, 0]));
+        assert_eq!(*image.get_pixel(640 - 8 - 5, 20), Rgb([10, 200, 10]));
+        assert_eq!(*image.get_pixel(640 - 8 - 90, 60), Rgb([10, 200, 10]));
+        assert_eq!(*image.get_pixel(300, 240), Rgb([10, 200, 10]));
+        assert!(apply_overlays(&mut image, &dungeon, &RenderOptions { logo: Some(dir.join("missing.png")), ..RenderOptions::default() }).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}